@@ -0,0 +1,20 @@
+;; Tree-sitter queries for the call graph of a JavaScript or TypeScript file
+;; Each @definition is a function whose name is captured as @definition.name, and each @call
+;; is a call whose callee's name is captured as @call.name. Method calls capture the receiver
+;; as @call.receiver.
+
+([(function_declaration name: (identifier) @definition.name)
+  (generator_function_declaration name: (identifier) @definition.name)
+  (method_definition name: (property_identifier) @definition.name)
+  (variable_declarator
+   name: (identifier) @definition.name
+   value: [(arrow_function) (function_expression)])]) @definition
+
+(call_expression
+ function: [(identifier) @call.name
+            (member_expression
+             object: (_) @call.receiver
+             property: (property_identifier) @call.name)]) @call
+
+(new_expression
+ constructor: (identifier) @call.name) @call
@@ -0,0 +1,11 @@
+;; Tree-sitter queries for the call graph of a Python file
+;; Each @definition is a function whose name is captured as @definition.name, and each @call
+;; is a call whose callee's name is captured as @call.name. Method calls capture the receiver
+;; as @call.receiver.
+
+(function_definition
+ name: (identifier) @definition.name) @definition
+
+(call
+ function: [(identifier) @call.name
+            (attribute object: (_) @call.receiver attribute: (identifier) @call.name)]) @call
@@ -0,0 +1,18 @@
+;; Tree-sitter queries for the call graph of a Rust file
+;; Each @definition is a function whose name is captured as @definition.name, and each @call
+;; is a call whose callee's name is captured as @call.name. Path-qualified calls capture the
+;; path as @call.scope, and method calls capture the receiver as @call.receiver.
+
+(function_item
+ name: (identifier) @definition.name) @definition
+
+(call_expression
+ function: [(identifier) @call.name
+            (scoped_identifier path: (_) @call.scope name: (identifier) @call.name)
+            (field_expression value: (_) @call.receiver field: (field_identifier) @call.name)
+            (generic_function
+             function: [(identifier) @call.name
+                        (scoped_identifier path: (_) @call.scope name: (identifier) @call.name)
+                        (field_expression
+                         value: (_) @call.receiver
+                         field: (field_identifier) @call.name)])]) @call
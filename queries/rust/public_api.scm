@@ -0,0 +1,33 @@
+;; Tree-sitter queries for the public API of a Rust file
+;; Items declared with a plain `pub` are captured as @item; pub(crate) and friends are not
+;; public API. Whether an item is reachable (not nested in a private module, a function body,
+;; or a private enum or trait) is decided by the caller.
+
+([(function_item (visibility_modifier) @vis)
+  (function_signature_item (visibility_modifier) @vis)
+  (struct_item (visibility_modifier) @vis)
+  (enum_item (visibility_modifier) @vis)
+  (union_item (visibility_modifier) @vis)
+  (trait_item (visibility_modifier) @vis)
+  (type_item (visibility_modifier) @vis)
+  (const_item (visibility_modifier) @vis)
+  (static_item (visibility_modifier) @vis)
+  (mod_item (visibility_modifier) @vis)
+  (use_declaration (visibility_modifier) @vis)
+  ;; Fields of public structs
+  (field_declaration (visibility_modifier) @vis)] @item
+ (#eq? @vis "pub"))
+
+;; Variants of public enums and items of public traits, which need no `pub` of their own
+(enum_variant) @item
+
+(trait_item
+ body: (declaration_list
+        [(function_signature_item) (function_item) (associated_type) (const_item)] @item))
+
+;; Exported macros
+(_
+ (attribute_item
+  (attribute (identifier) @attribute (#eq? @attribute "macro_export")))
+ .
+ (macro_definition) @item)
@@ -1,5 +1,6 @@
 ;; Tree-sitter validation queries for Rust semantic editing
 ;; Focus on realistic problematic patterns we've actually encountered
+;; Rules with (#operation? insert replace delete) only apply to code written by those edits
 
 ;; CRITICAL: Type definitions cannot be inside function bodies
 (function_item 
@@ -82,14 +83,59 @@
 
 ;; CRITICAL: Break/continue outside of loops
 
-;; CRITICAL: Visibility modifiers on items inside functions
-(function_item
- body: (block
-        [(struct_item (visibility_modifier))
-         (enum_item (visibility_modifier))
-         (function_item (visibility_modifier))
-         (const_item (visibility_modifier))
-         (static_item (visibility_modifier))] @invalid.visibility.in.function.body))
+;; Items can be inserted into a match arm's block, but replacing the arm's code with one
+;; drops what the arm did
+(match_arm
+ value: (block
+         [(function_item) (struct_item) (enum_item) (impl_item) (trait_item)]
+         @invalid.item.replacing.match.arm.body)
+ (#operation? replace))
+
+;; CRITICAL: Visibility modifiers on items inside functions, at any depth of block
+(block
+ [(struct_item (visibility_modifier))
+  (enum_item (visibility_modifier))
+  (union_item (visibility_modifier))
+  (function_item (visibility_modifier))
+  (const_item (visibility_modifier))
+  (static_item (visibility_modifier))
+  (type_item (visibility_modifier))
+  (trait_item (visibility_modifier))
+  (mod_item (visibility_modifier))
+  (use_declaration (visibility_modifier))] @invalid.visibility.in.function.body)
+
+;; CRITICAL: Statements belong in function bodies, not at module level. Macro invocations like
+;; `lazy_static!` can expand to items, so they're allowed
+(source_file
+ [(let_declaration)
+  (expression_statement
+   [(call_expression)
+    (assignment_expression)
+    (compound_assignment_expr)
+    (if_expression)
+    (match_expression)
+    (for_expression)
+    (while_expression)
+    (loop_expression)
+    (return_expression)])] @invalid.statement.at.module.level)
+
+;; CRITICAL: Two methods with the same name in one impl block. Only checked when an edit writes
+;; one of them, since methods can share a name behind different #[cfg]s
+(impl_item
+ body: (declaration_list
+        (function_item name: (identifier) @first.name)
+        (function_item name: (identifier) @duplicate.name) @invalid.duplicate.function.in.impl
+        (#eq? @first.name @duplicate.name))
+ (#operation? insert replace))
+
+;; CRITICAL: Attributes must be followed by the item they apply to
+(source_file
+ (attribute_item) @invalid.attribute.without.item
+ .)
+
+(declaration_list
+ (attribute_item) @invalid.attribute.without.item
+ .)
 
 
 ;; Type aliases with generics inside function bodies might be questionable
@@ -0,0 +1,24 @@
+use std::time::{Duration, Instant};
+
+/// A time budget for a single tool call. Tools that can take a long time check it between
+/// units of work and, once it is exhausted, return partial results with a continuation token
+/// instead of leaving a client with its own deadline waiting.
+#[derive(Debug, Clone, Copy)]
+pub struct Budget {
+    started: Instant,
+    timeout: Option<Duration>,
+}
+
+impl Budget {
+    pub fn from_timeout_ms(timeout_ms: Option<u64>) -> Self {
+        Self {
+            started: Instant::now(),
+            timeout: timeout_ms.map(Duration::from_millis),
+        }
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.timeout
+            .is_some_and(|timeout| self.started.elapsed() >= timeout)
+    }
+}
@@ -0,0 +1,281 @@
+use crate::symbols::function_definitions;
+use std::collections::{BTreeMap, HashMap};
+use tree_sitter::{Node, Query, QueryCursor, StreamingIterator, Tree};
+
+/// Shown as the caller of calls that are not inside any function
+pub const TOP_LEVEL: &str = "(top level)";
+
+/// A function defined in the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    /// The function's name, qualified by its impl block or class if it has one
+    pub name: String,
+    /// 1-indexed line the definition starts on
+    pub line: usize,
+}
+
+/// Every call from one function to another, with how many times it occurs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallGraph {
+    /// Functions defined in the file, in document order
+    pub functions: Vec<Function>,
+    /// Calls between functions defined in the file, keyed by (caller, callee)
+    pub edges: BTreeMap<(String, String), usize>,
+    /// Calls to functions that are not defined in the file, keyed by (caller, callee)
+    pub external: BTreeMap<(String, String), usize>,
+}
+
+impl CallGraph {
+    /// Everything in the file that can reach `function` through some chain of calls, nearest
+    /// callers first
+    pub fn transitive_callers<'a>(&'a self, function: &'a str) -> Vec<&'a str> {
+        let mut reached = vec![function];
+        let mut index = 0;
+        while let Some(current) = reached.get(index).copied() {
+            for (caller, callee) in self.edges.keys() {
+                if callee == current && !reached.contains(&caller.as_str()) {
+                    reached.push(caller);
+                }
+            }
+            index += 1;
+        }
+        reached.split_off(1)
+    }
+}
+
+/// A call found by the query
+struct Call<'tree> {
+    node: Node<'tree>,
+    name: &'tree str,
+    /// The path a call like `Type::function()` is qualified with
+    scope: Option<&'tree str>,
+    /// The expression a method like `value.method()` is called on
+    receiver: Option<&'tree str>,
+}
+
+/// Build the call graph of a file from a query that captures function `@definition`s and
+/// `@call`s along with their `@definition.name` and `@call.name`, and optionally the
+/// `@call.scope` of path-qualified calls and the `@call.receiver` of method calls.
+///
+/// Calls are resolved by name: bare calls to free functions, `Type::name()` calls to methods
+/// of that type, and method calls to methods of any type defined in the file (preferring the
+/// caller's own type for `self` and `this`), so a method call can be attributed to several
+/// methods that share its name.
+pub fn call_graph(query: &Query, tree: &Tree, source: &str) -> CallGraph {
+    let capture = |name| query.capture_index_for_name(name);
+    let (Some(call), Some(call_name)) = (capture("call"), capture("call.name")) else {
+        return CallGraph::default();
+    };
+    let (call_scope, call_receiver) = (capture("call.scope"), capture("call.receiver"));
+
+    let definitions = function_definitions(query, tree, source);
+    let mut calls = vec![];
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+    while let Some(query_match) = matches.next() {
+        let node = |index| {
+            query_match
+                .captures
+                .iter()
+                .find(|capture| Some(capture.index) == index)
+                .map(|capture| capture.node)
+        };
+        let text = |index| node(index).map(|node| &source[node.byte_range()]);
+
+        if let Some((node, name)) = node(Some(call)).zip(text(Some(call_name))) {
+            calls.push(Call {
+                node,
+                name,
+                scope: text(call_scope),
+                receiver: text(call_receiver),
+            });
+        }
+    }
+
+    let by_node = definitions
+        .iter()
+        .map(|definition| (definition.node.id(), definition))
+        .collect::<HashMap<_, _>>();
+
+    let mut graph = CallGraph {
+        functions: definitions
+            .iter()
+            .map(|definition| Function {
+                name: definition.qualified.clone(),
+                line: definition.node.start_position().row + 1,
+            })
+            .collect(),
+        ..CallGraph::default()
+    };
+
+    for call in calls {
+        let caller = std::iter::successors(call.node.parent(), Node::parent)
+            .find_map(|ancestor| by_node.get(&ancestor.id()).copied());
+        let caller_name = caller.map_or(TOP_LEVEL, |caller| caller.qualified.as_str());
+        let caller_owner = caller.and_then(|caller| caller.owner);
+
+        let named = || {
+            definitions
+                .iter()
+                .filter(|definition| definition.name == call.name)
+        };
+        let callees = match (call.scope, call.receiver) {
+            (Some(scope), _) => {
+                let owner = if scope == "Self" {
+                    caller_owner
+                } else {
+                    Some(scope)
+                };
+                named()
+                    .filter(|definition| owner.is_some() && definition.owner == owner)
+                    .collect::<Vec<_>>()
+            }
+            (None, Some(receiver)) => {
+                let methods = named()
+                    .filter(|definition| definition.owner.is_some())
+                    .collect::<Vec<_>>();
+                let own_methods = methods
+                    .iter()
+                    .filter(|definition| definition.owner == caller_owner)
+                    .copied()
+                    .collect::<Vec<_>>();
+                if matches!(receiver, "self" | "this") && !own_methods.is_empty() {
+                    own_methods
+                } else {
+                    methods
+                }
+            }
+            (None, None) => named()
+                .filter(|definition| definition.owner.is_none())
+                .collect(),
+        };
+
+        if callees.is_empty() {
+            *graph
+                .external
+                .entry((caller_name.to_string(), call.name.to_string()))
+                .or_default() += 1;
+        }
+        for callee in callees {
+            *graph
+                .edges
+                .entry((caller_name.to_string(), callee.qualified.clone()))
+                .or_default() += 1;
+        }
+    }
+
+    graph
+}
+
+#[cfg(all(
+    test,
+    any(feature = "rust", feature = "python", feature = "javascript")
+))]
+mod tests {
+    use super::*;
+    use crate::languages::{LanguageName, LanguageRegistry};
+
+    fn graph(language: LanguageName, source: &str) -> CallGraph {
+        let registry = LanguageRegistry::new().unwrap();
+        let language = registry.get_language(language);
+        let tree = language
+            .tree_sitter_parser()
+            .unwrap()
+            .parse(source, None)
+            .unwrap();
+        call_graph(language.calls_query().unwrap(), &tree, source)
+    }
+
+    fn edges(graph: &CallGraph) -> Vec<(&str, &str, usize)> {
+        graph
+            .edges
+            .iter()
+            .map(|((caller, callee), count)| (caller.as_str(), callee.as_str(), *count))
+            .collect()
+    }
+
+    #[test]
+    #[cfg(feature = "rust")]
+    fn rust_calls_between_functions_and_methods() {
+        let graph = graph(
+            LanguageName::Rust,
+            r#"
+struct Config;
+
+impl Config {
+    fn new() -> Self {
+        validate();
+        Config
+    }
+
+    fn load(&self) {
+        validate();
+        validate();
+        println!("loaded");
+        std::fs::read("x");
+    }
+}
+
+fn validate() {}
+
+fn main() {
+    Config::new().load();
+}
+"#,
+        );
+
+        assert_eq!(
+            edges(&graph),
+            vec![
+                ("Config::load", "validate", 2),
+                ("Config::new", "validate", 1),
+                ("main", "Config::load", 1),
+                ("main", "Config::new", 1),
+            ]
+        );
+        assert!(
+            graph
+                .external
+                .contains_key(&("Config::load".to_string(), "read".to_string()))
+        );
+        assert_eq!(
+            graph.transitive_callers("validate"),
+            ["Config::load", "Config::new", "main"]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "python")]
+    fn python_calls() {
+        let python = graph(
+            LanguageName::Python,
+            "class Greeter:\n    def greet(self):\n        return format_name('x')\n\n\
+             def format_name(name):\n    return name.strip()\n\nGreeter().greet()\n",
+        );
+        assert_eq!(
+            edges(&python),
+            vec![
+                ("(top level)", "Greeter.greet", 1),
+                ("Greeter.greet", "format_name", 1)
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "javascript")]
+    fn javascript_calls() {
+        let javascript = graph(
+            LanguageName::Javascript,
+            "const helper = () => 1;\nfunction main() { return helper() + new Widget().render(); }\n\
+             class Widget { render() { return helper(); } }\n",
+        );
+        assert_eq!(
+            edges(&javascript),
+            vec![
+                ("Widget.render", "helper", 1),
+                ("main", "Widget.render", 1),
+                ("main", "helper", 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use crate::{languages::LanguageName, lsp::Diagnostic};
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use std::{
+    env,
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    str::FromStr,
+};
+
+/// A type checker or linter that is run on a file after an edit is persisted, and whose output
+/// is parsed into diagnostics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checker {
+    /// `cargo clippy --message-format=json`, run in the crate containing the file
+    Clippy,
+    /// `tsc --noEmit`, run in the project containing the file
+    Tsc,
+    /// `mypy` on the file alone
+    Mypy,
+}
+
+/// Diagnostics from running a checker after an edit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckReport {
+    /// Problems in the edited file
+    pub diagnostics: Vec<Diagnostic>,
+    /// Problems the checker found in other files
+    pub elsewhere: usize,
+}
+
+impl FromStr for Checker {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "clippy" => Ok(Self::Clippy),
+            "tsc" => Ok(Self::Tsc),
+            "mypy" => Ok(Self::Mypy),
+            other => Err(anyhow!(
+                "unknown checker `{other}`, expected clippy, tsc, mypy, or off"
+            )),
+        }
+    }
+}
+
+impl Display for Checker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Clippy => "clippy",
+            Self::Tsc => "tsc",
+            Self::Mypy => "mypy",
+        })
+    }
+}
+
+impl Checker {
+    /// The checker used for a language when `SEMANTIC_EDIT_CHECKS=on`
+    pub fn default_for(language: LanguageName) -> Option<Self> {
+        match language {
+            LanguageName::Rust => Some(Self::Clippy),
+            LanguageName::Typescript | LanguageName::Tsx => Some(Self::Tsc),
+            LanguageName::Python => Some(Self::Mypy),
+            _ => None,
+        }
+    }
+
+    /// The checker to run after persisting a file in this language. Checks are off unless
+    /// `SEMANTIC_EDIT_CHECKS=on` enables the default for every language, or
+    /// `SEMANTIC_EDIT_CHECK_<LANGUAGE>` names a checker (or `off`) for one language.
+    pub fn configured(language: LanguageName) -> Result<Option<Self>> {
+        let variable = format!(
+            "SEMANTIC_EDIT_CHECK_{}",
+            language.to_string().to_uppercase()
+        );
+        match env::var(&variable).as_deref() {
+            Ok("off") => Ok(None),
+            Ok(checker) => checker
+                .parse()
+                .map(Some)
+                .map_err(|error| anyhow!("{variable}: {error}")),
+            Err(_) if env::var("SEMANTIC_EDIT_CHECKS").as_deref() == Ok("on") => {
+                Ok(Self::default_for(language))
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Run the checker on an edited file
+    pub fn check(self, file: &Path) -> Result<CheckReport> {
+        let (output, root) = self.run(file)?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        let diagnostics = match self {
+            Self::Clippy => parse_clippy(&stdout),
+            Self::Tsc => parse_tsc(&stdout),
+            Self::Mypy => parse_mypy(&stdout),
+        };
+        if diagnostics.is_empty() && !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!(
+                "{self} failed: {}",
+                stderr.trim().lines().last().unwrap_or("no output")
+            ));
+        }
+
+        let (diagnostics, elsewhere): (Vec<_>, Vec<_>) = diagnostics
+            .into_iter()
+            .partition(|(path, _)| is_same_file(file, &root, path));
+        Ok(CheckReport {
+            diagnostics: diagnostics
+                .into_iter()
+                .map(|(_, diagnostic)| diagnostic)
+                .collect(),
+            elsewhere: elsewhere.len(),
+        })
+    }
+
+    /// Run the checker on a file, returning its output and the directory it was run in, which
+    /// the paths it reports may be relative to
+    pub fn run(self, file: &Path) -> Result<(Output, PathBuf)> {
+        let directory = file.parent().unwrap_or(file);
+        let (mut command, root) = match self {
+            Self::Clippy => {
+                let mut command = Command::new("cargo");
+                command.args(["clippy", "--quiet", "--message-format=json"]);
+                (command, nearest(directory, "Cargo.toml"))
+            }
+            Self::Tsc => {
+                let root = nearest(directory, "tsconfig.json")
+                    .or_else(|| nearest(directory, "package.json"));
+                // prefer the project's own compiler over a global one
+                let local = root
+                    .as_ref()
+                    .map(|root| root.join("node_modules/.bin/tsc"))
+                    .filter(|tsc| tsc.exists());
+                let mut command = Command::new(local.as_deref().unwrap_or(Path::new("tsc")));
+                command.args(["--noEmit", "--pretty", "false"]);
+                (command, root)
+            }
+            Self::Mypy => {
+                let mut command = Command::new("mypy");
+                command
+                    .args([
+                        "--show-column-numbers",
+                        "--show-error-codes",
+                        "--no-error-summary",
+                        "--no-color-output",
+                    ])
+                    .arg(file);
+                (command, Some(directory.to_path_buf()))
+            }
+        };
+
+        let root = root.ok_or_else(|| {
+            anyhow!(
+                "{self} needs a project to run in, and none was found above {}",
+                directory.display()
+            )
+        })?;
+        let output = command
+            .current_dir(&root)
+            .output()
+            .map_err(|error| anyhow!("{self} could not be started: {error}"))?;
+        Ok((output, root))
+    }
+}
+
+/// The nearest directory at or above `directory` that contains `marker`
+pub fn nearest(directory: &Path, marker: &str) -> Option<PathBuf> {
+    directory
+        .ancestors()
+        .find(|ancestor| ancestor.join(marker).exists())
+        .map(Path::to_path_buf)
+}
+
+/// Whether a path reported by a checker, which may be relative to where it was run or to a
+/// workspace root above it, refers to the edited file
+pub fn is_same_file(file: &Path, root: &Path, reported: &str) -> bool {
+    let reported = Path::new(reported);
+    if reported.is_absolute() {
+        reported == file
+    } else {
+        root.join(reported) == file || file.ends_with(reported)
+    }
+}
+
+/// Errors and warnings with their file, from the JSON lines cargo prints for each compiler message
+fn parse_clippy(output: &str) -> Vec<(String, Diagnostic)> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|line| line["reason"] == "compiler-message")
+        .filter_map(|line| {
+            let message = &line["message"];
+            let severity = match message["level"].as_str()? {
+                "error" => "error",
+                "warning" => "warning",
+                _ => return None,
+            };
+            let span = message["spans"]
+                .as_array()?
+                .iter()
+                .find(|span| span["is_primary"] == true)?;
+            Some((
+                span["file_name"].as_str()?.to_string(),
+                Diagnostic {
+                    line: span["line_start"].as_u64()? as usize,
+                    column: span["column_start"].as_u64()? as usize,
+                    severity,
+                    code: message["code"]["code"].as_str().map(String::from),
+                    message: message["message"].as_str()?.to_string(),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Errors from lines like `src/app.ts(12,5): error TS2322: Type 'string' is not assignable`
+fn parse_tsc(output: &str) -> Vec<(String, Diagnostic)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (location, rest) = line.split_once("): ")?;
+            let (path, position) = location.rsplit_once('(')?;
+            let (line_number, column) = position.split_once(',')?;
+            let (severity, rest) = rest.split_once(' ')?;
+            let severity = match severity {
+                "error" => "error",
+                "warning" => "warning",
+                _ => return None,
+            };
+            let (code, message) = rest.split_once(": ")?;
+            Some((
+                path.to_string(),
+                Diagnostic {
+                    line: line_number.parse().ok()?,
+                    column: column.parse().ok()?,
+                    severity,
+                    code: Some(code.to_string()),
+                    message: message.to_string(),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Errors from lines like `app/models.py:12:5: error: Incompatible types  [assignment]`
+fn parse_mypy(output: &str) -> Vec<(String, Diagnostic)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, ':');
+            let path = parts.next()?;
+            let line_number = parts.next()?.parse().ok()?;
+            let column = parts.next()?.trim().parse().ok()?;
+            let (severity, message) = parts.next()?.trim().split_once(": ")?;
+            let severity = match severity {
+                "error" => "error",
+                "warning" => "warning",
+                _ => return None,
+            };
+            let (message, code) = match message.rsplit_once("  [") {
+                Some((message, code)) => (message, code.strip_suffix(']').map(String::from)),
+                None => (message, None),
+            };
+            Some((
+                path.to_string(),
+                Diagnostic {
+                    line: line_number,
+                    column,
+                    severity,
+                    code,
+                    message: message.to_string(),
+                },
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(diagnostics: Vec<(String, Diagnostic)>) -> Vec<String> {
+        diagnostics
+            .into_iter()
+            .map(|(path, diagnostic)| format!("{path} {diagnostic}"))
+            .collect()
+    }
+
+    #[test]
+    fn clippy_messages() {
+        let output = r#"{"reason":"compiler-artifact","target":{"name":"app"}}
+{"reason":"compiler-message","message":{"level":"warning","message":"unneeded `return` statement","code":{"code":"clippy::needless_return","explanation":null},"spans":[{"file_name":"src/lib.rs","is_primary":false,"line_start":1,"column_start":1},{"file_name":"src/lib.rs","is_primary":true,"line_start":4,"column_start":5}]}}
+{"reason":"compiler-message","message":{"level":"error","message":"mismatched types","code":{"code":"E0308","explanation":"..."},"spans":[{"file_name":"src/main.rs","is_primary":true,"line_start":10,"column_start":18}]}}
+{"reason":"compiler-message","message":{"level":"warning","message":"1 warning emitted","code":null,"spans":[]}}
+{"reason":"build-finished","success":false}"#;
+        assert_eq!(
+            rendered(parse_clippy(output)),
+            [
+                "src/lib.rs 4:5 warning[clippy::needless_return]: unneeded `return` statement",
+                "src/main.rs 10:18 error[E0308]: mismatched types",
+            ]
+        );
+    }
+
+    #[test]
+    fn tsc_messages() {
+        let output = "src/app.ts(12,5): error TS2322: Type 'string' is not assignable to type 'number'.\n\
+                      src/lib/util (copy).ts(3,1): error TS2304: Cannot find name 'foo'.\n\
+                      Found 2 errors.\n";
+        assert_eq!(
+            rendered(parse_tsc(output)),
+            [
+                "src/app.ts 12:5 error[TS2322]: Type 'string' is not assignable to type 'number'.",
+                "src/lib/util (copy).ts 3:1 error[TS2304]: Cannot find name 'foo'.",
+            ]
+        );
+    }
+
+    #[test]
+    fn mypy_messages() {
+        let output = "app/models.py:12:5: error: Incompatible types in assignment (expression has type \"str\", variable has type \"int\")  [assignment]\n\
+                      app/models.py:12:5: note: See https://mypy.readthedocs.io\n\
+                      app/views.py:3:1: error: Name \"foo\" is not defined  [name-defined]\n";
+        assert_eq!(
+            rendered(parse_mypy(output)),
+            [
+                "app/models.py 12:5 error[assignment]: Incompatible types in assignment (expression has type \"str\", variable has type \"int\")",
+                "app/views.py 3:1 error[name-defined]: Name \"foo\" is not defined",
+            ]
+        );
+    }
+
+    #[test]
+    fn reported_paths_match_the_edited_file() {
+        let file = Path::new("/work/crate/src/lib.rs");
+        assert!(is_same_file(file, Path::new("/work/crate"), "src/lib.rs"));
+        assert!(is_same_file(
+            file,
+            Path::new("/work/crate/src"),
+            "crate/src/lib.rs"
+        ));
+        assert!(!is_same_file(file, Path::new("/work/crate"), "src/main.rs"));
+        assert!(!is_same_file(file, Path::new("/work/crate"), "b/lib.rs"));
+    }
+}
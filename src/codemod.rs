@@ -0,0 +1,275 @@
+use crate::{
+    languages::{LanguageCommon, LanguageName, toml},
+    placeholders,
+};
+use anyhow::{Result, anyhow, bail};
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tree_sitter::{Query, QueryCursor, StreamingIterator};
+
+/// Where codemods are defined, relative to the working directory
+pub const CODEMODS: &str = ".semantic-edit/codemods";
+
+/// A reusable transformation, defined in `.semantic-edit/codemods/<name>.toml` or `.json`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Codemod {
+    #[serde(skip)]
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The language of the files the codemod applies to
+    pub language: LanguageName,
+    /// Transformations applied in order, each to the result of the one before
+    pub steps: Vec<Step>,
+}
+
+/// A tree-sitter query and what to replace one of its captures with in every match
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    pub query: String,
+    /// The capture to replace. Optional when the query has a single capture.
+    #[serde(default)]
+    pub capture: Option<String>,
+    /// The replacement text, where `{{capture}}` stands for the text of any capture in the match.
+    /// Snippet placeholders like `${file_stem}` are expanded for each file.
+    pub replace: String,
+}
+
+/// A step whose query has been compiled for its language
+#[derive(Debug)]
+pub struct CompiledStep {
+    query: Query,
+    capture: u32,
+    replace: String,
+}
+
+impl Codemod {
+    /// The names of the codemods defined in a working directory, with their descriptions
+    pub fn available(working_directory: &Path) -> Vec<(String, Option<String>)> {
+        let mut available = fs::read_dir(working_directory.join(CODEMODS))
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let name = path.file_stem()?.to_str()?.to_string();
+                let description = Self::read(&path, name.clone())
+                    .ok()
+                    .and_then(|codemod| codemod.description);
+                Some((name, description))
+            })
+            .collect::<Vec<_>>();
+        available.sort();
+        available
+    }
+
+    /// Load the codemod with this name from a working directory
+    pub fn load(working_directory: &Path, name: &str) -> Result<Self> {
+        let directory = working_directory.join(CODEMODS);
+        let path = ["toml", "json"]
+            .iter()
+            .map(|extension| directory.join(format!("{name}.{extension}")))
+            .find(|path| path.exists())
+            .ok_or_else(|| {
+                let available = Self::available(working_directory)
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>();
+                anyhow!(
+                    "No codemod named {name} is defined in {CODEMODS}/. {}",
+                    if available.is_empty() {
+                        "Define one in a TOML or JSON file there.".to_string()
+                    } else {
+                        format!("Available codemods: {}", available.join(", "))
+                    }
+                )
+            })?;
+        Self::read(&path, name.to_string())
+    }
+
+    fn read(path: &Path, name: String) -> Result<Self> {
+        let display = || PathBuf::from(CODEMODS).join(path.file_name().unwrap_or_default());
+        let source = fs::read_to_string(path)?;
+        let value = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            toml::to_json(&source)
+                .map_err(|error| anyhow!("{} is not valid TOML: {error}", display().display()))?
+        } else {
+            serde_json::from_str(&source)?
+        };
+
+        let codemod: Self = serde_json::from_value(value)
+            .map_err(|error| anyhow!("{} is not a valid codemod: {error}", display().display()))?;
+        if codemod.language == LanguageName::Other {
+            bail!(
+                "{} must set `language` to a supported language",
+                display().display()
+            );
+        }
+        Ok(Self { name, ..codemod })
+    }
+
+    /// Compile every step's query for the codemod's language
+    pub fn compile(&self, language: &LanguageCommon) -> Result<Vec<CompiledStep>> {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let step_name = || format!("Step {} of codemod {}", index + 1, self.name);
+                let query = Query::new(language.tree_sitter_language(), &step.query)
+                    .map_err(|error| anyhow!("{} has an invalid query: {error}", step_name()))?;
+                let names = query.capture_names();
+                let capture = match (&step.capture, names) {
+                    (Some(capture), _) => names.iter().position(|name| name == capture),
+                    (None, [_]) => Some(0),
+                    (None, _) => None,
+                }
+                .ok_or_else(|| {
+                    anyhow!(
+                        "{} must set `capture` to one of the query's captures: {}",
+                        step_name(),
+                        names.join(", ")
+                    )
+                })?;
+
+                Ok(CompiledStep {
+                    query,
+                    capture: capture as u32,
+                    replace: step.replace.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Apply compiled steps to a file's source in order, returning the transformed source and how
+/// many replacements were made
+pub fn apply(
+    language: &LanguageCommon,
+    steps: &[CompiledStep],
+    file: &Path,
+    source: &str,
+) -> Result<(String, usize)> {
+    let mut parser = language.tree_sitter_parser()?;
+    let mut source = source.to_string();
+    let mut total = 0;
+
+    for step in steps {
+        let tree = parser
+            .parse(&source, None)
+            .ok_or_else(|| anyhow!("Unable to parse as {language}"))?;
+        let names = step.query.capture_names();
+        let template = placeholders::expand(&step.replace, file);
+
+        let mut replacements = vec![];
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&step.query, tree.root_node(), source.as_bytes());
+        while let Some(query_match) = matches.next() {
+            let Some(target) = query_match
+                .captures
+                .iter()
+                .find(|capture| capture.index == step.capture)
+            else {
+                continue;
+            };
+
+            let mut replacement = template.clone();
+            for capture in query_match.captures {
+                let placeholder = format!("{{{{{}}}}}", names[capture.index as usize]);
+                replacement = replacement.replace(&placeholder, &source[capture.node.byte_range()]);
+            }
+            replacements.push((target.node.byte_range(), replacement));
+        }
+
+        // when matches overlap, the first (and so outermost) one wins
+        replacements.sort_by_key(|(range, _)| (range.start, std::cmp::Reverse(range.end)));
+        let mut kept: Vec<(std::ops::Range<usize>, String)> = vec![];
+        for (range, replacement) in replacements {
+            if kept.last().is_none_or(|(last, _)| last.end <= range.start) {
+                kept.push((range, replacement));
+            }
+        }
+
+        total += kept.len();
+        for (range, replacement) in kept.into_iter().rev() {
+            source.replace_range(range, &replacement);
+        }
+    }
+
+    Ok((source, total))
+}
+
+#[cfg(all(test, any(feature = "rust", feature = "python")))]
+mod tests {
+    use super::*;
+    use crate::languages::LanguageRegistry;
+
+    #[test]
+    #[cfg(feature = "rust")]
+    fn steps_apply_in_order() {
+        let codemod: Codemod = serde_json::from_value(serde_json::json!({
+            "language": "rust",
+            "steps": [
+                {
+                    "query": r#"(call_expression
+                        function: (field_expression field: (field_identifier) @method)
+                        (#eq? @method "unwrap")) @call"#,
+                    "capture": "method",
+                    "replace": "expect",
+                },
+                {
+                    "query": r#"((call_expression
+                        function: (field_expression field: (field_identifier) @method)
+                        arguments: (arguments) @arguments)
+                        (#eq? @method "expect")
+                        (#eq? @arguments "()"))"#,
+                    "capture": "arguments",
+                    "replace": "(\"TODO\")",
+                },
+            ],
+        }))
+        .unwrap();
+
+        let registry = LanguageRegistry::new().unwrap();
+        let rust = registry.get_language(LanguageName::Rust);
+        let steps = codemod.compile(rust).unwrap();
+        let (output, count) = apply(
+            rust,
+            &steps,
+            Path::new("src/main.rs"),
+            "fn main() {\n    let a = b.unwrap();\n    c.unwrap().d.unwrap();\n}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "fn main() {\n    let a = b.expect(\"TODO\");\n    c.expect(\"TODO\").d.expect(\"TODO\");\n}\n"
+        );
+        assert_eq!(count, 6);
+    }
+
+    #[test]
+    #[cfg(feature = "python")]
+    fn captures_must_be_named_when_ambiguous() {
+        let codemod: Codemod = serde_json::from_value(serde_json::json!({
+            "language": "python",
+            "steps": [{ "query": "(call function: (identifier) @f arguments: (_) @a)", "replace": "x" }],
+        }))
+        .unwrap();
+        let registry = LanguageRegistry::new().unwrap();
+        let error = codemod
+            .compile(registry.get_language(LanguageName::Python))
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("one of the query's captures: f, a")
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Staged edits whose file changed after they were staged, how persist_edit resolves them, and
+//! the conflicts a three-way merge can leave
+
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How to persist an edit whose file changed after it was staged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum OnConflict {
+    /// Find the edit's anchor again in the file as it is now, and apply the edit there
+    Reresolve,
+    /// Three-way merge the staged change with the changes made to the file since, if they
+    /// don't overlap
+    Merge,
+    /// Write the file as the edit left it when it was staged, discarding the changes made since
+    Overwrite,
+}
+
+/// The conflicting sections of a failed merge, with the lines around them left out
+pub fn sections(conflicted: &str) -> String {
+    let mut sections = String::new();
+    let mut in_conflict = false;
+    for line in conflicted.lines() {
+        if line.starts_with("<<<<<<<") {
+            in_conflict = true;
+        }
+        if in_conflict {
+            sections.push_str(line);
+            sections.push('\n');
+        }
+        if line.starts_with(">>>>>>>") {
+            in_conflict = false;
+        }
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_leave_out_merged_lines() {
+        let conflicted = diffy::merge("a\nb\nc\n", "a\nB\nc\n", "a\nβ\nc\n").unwrap_err();
+        assert_eq!(
+            sections(&conflicted),
+            "<<<<<<< ours\nB\n||||||| original\nb\n=======\nβ\n>>>>>>> theirs\n"
+        );
+    }
+}
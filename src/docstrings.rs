@@ -0,0 +1,99 @@
+use tree_sitter::Node;
+
+/// The definition a function, class, or decorated definition node defines
+pub fn definition(node: Node<'_>) -> Node<'_> {
+    match node.kind() {
+        "decorated_definition" => node.child_by_field_name("definition").unwrap_or(node),
+        _ => node,
+    }
+}
+
+/// The string a function or class definition starts its body with as its docstring
+pub fn docstring(node: Node<'_>) -> Option<Node<'_>> {
+    let node = definition(node);
+    if !matches!(node.kind(), "function_definition" | "class_definition") {
+        return None;
+    }
+    let body = node.child_by_field_name("body")?;
+    let statement = body
+        .named_children(&mut body.walk())
+        .find(|child| child.kind() != "comment")?;
+    let string = statement.named_child(0)?;
+    (statement.kind() == "expression_statement"
+        && statement.named_child_count() == 1
+        && string.kind() == "string")
+        .then_some(string)
+}
+
+/// The first line of a definition's body, where a docstring goes, whether it's a statement or a
+/// comment
+pub fn body_start(node: Node<'_>) -> Option<Node<'_>> {
+    // a comment right after the header belongs to the definition rather than its body
+    let node = definition(node);
+    let first = node
+        .children(&mut node.walk())
+        .skip_while(|child| child.kind() != ":")
+        .find(|child| child.is_named())?;
+    match first.kind() {
+        "block" => first.named_child(0),
+        _ => Some(first),
+    }
+}
+
+/// `text` as a docstring quoted like `existing`, the docstring it replaces or another from the
+/// same file, or with `"""` when there is none. Docstrings of more than one line end with their
+/// closing quotes on a line of their own, and `text` that is already quoted is used as it is.
+pub fn render_docstring(text: &str, existing: Option<&str>) -> String {
+    let text = text.trim();
+    let unprefixed = text.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    if unprefixed.starts_with(['"', '\'']) && text.len() - unprefixed.len() <= 2 {
+        return text.to_string();
+    }
+
+    let existing = existing.unwrap_or(r#"""""""#);
+    let quotes = existing.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    let prefix = &existing[..existing.len() - quotes.len()];
+    let quote = if quotes.starts_with('\'') { '\'' } else { '"' };
+    let mut delimiter = if quotes.starts_with(&quote.to_string().repeat(3)) || text.contains('\n') {
+        quote.to_string().repeat(3)
+    } else {
+        quote.to_string()
+    };
+    if text.contains(&delimiter) || text.ends_with(quote) {
+        let other = if quote == '"' { '\'' } else { '"' };
+        delimiter = other.to_string().repeat(3);
+    }
+
+    if text.contains('\n') {
+        format!("{prefix}{delimiter}{text}\n{delimiter}")
+    } else {
+        format!("{prefix}{delimiter}{text}{delimiter}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn docstrings_are_quoted_like_the_existing_ones() {
+        assert_eq!(render_docstring("Parse it.", None), r#""""Parse it.""""#);
+        assert_eq!(
+            render_docstring("Parse it.", Some("'''Old.'''")),
+            "'''Parse it.'''"
+        );
+        assert_eq!(
+            render_docstring("Parse it.", Some("r\"Old.\"")),
+            "r\"Parse it.\""
+        );
+        assert_eq!(
+            render_docstring("Parse it.\n\nCarefully.", Some("\"Old.\"")),
+            "\"\"\"Parse it.\n\nCarefully.\n\"\"\""
+        );
+        assert_eq!(
+            render_docstring(r#"Returns "x""#, None),
+            r#"'''Returns "x"'''"#
+        );
+        assert_eq!(render_docstring("'''Kept.'''", None), "'''Kept.'''");
+    }
+}
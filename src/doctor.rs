@@ -0,0 +1,454 @@
+use crate::{
+    formatting,
+    languages::{LanguageName, LanguageRegistry, dynamic},
+    lsp::LanguageServer,
+    state::SemanticEditTools,
+};
+use std::{
+    collections::BTreeSet,
+    env,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tree_sitter::{LANGUAGE_VERSION, MIN_COMPATIBLE_LANGUAGE_VERSION};
+
+/// The result of checking one thing the tools depend on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Ready, with details like a version
+    Ok(String),
+    /// Not needed for everything, or only used when configured
+    Skipped(String),
+    /// Broken, with what goes wrong because of it and how to fix it
+    Problem { impact: String, fix: String },
+}
+
+impl Check {
+    fn new(name: impl Into<String>, outcome: Outcome) -> Self {
+        Self {
+            name: name.into(),
+            outcome,
+        }
+    }
+
+    pub fn is_problem(&self) -> bool {
+        matches!(self.outcome, Outcome::Problem { .. })
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.outcome {
+            Outcome::Ok(details) => write!(f, "✅ {}: {details}", self.name),
+            Outcome::Skipped(reason) => write!(f, "➖ {}: {reason}", self.name),
+            Outcome::Problem { impact, fix } => {
+                write!(f, "❌ {}: {impact}\n   Fix: {fix}", self.name)
+            }
+        }
+    }
+}
+
+/// A formatter that edits to files in some languages are run through
+struct ExternalFormatter {
+    program: &'static str,
+    languages: &'static str,
+    /// The languages it formats, which `SEMANTIC_EDIT_FORMATTER_<LANGUAGE>` can give another
+    /// formatter
+    names: &'static [LanguageName],
+    install: &'static str,
+    enabled: bool,
+}
+
+const FORMATTERS: [ExternalFormatter; 5] = [
+    ExternalFormatter {
+        program: "rustfmt",
+        languages: "Rust",
+        names: &[LanguageName::Rust],
+        install: "rustup component add rustfmt",
+        enabled: cfg!(feature = "rust"),
+    },
+    ExternalFormatter {
+        program: "ruff",
+        languages: "Python",
+        names: &[LanguageName::Python],
+        install: "pip install ruff, or uv tool install ruff",
+        enabled: cfg!(feature = "python"),
+    },
+    ExternalFormatter {
+        program: "biome",
+        languages: "JavaScript, TypeScript, and JSON",
+        names: &[
+            LanguageName::Javascript,
+            LanguageName::Jsx,
+            LanguageName::Typescript,
+            LanguageName::Tsx,
+            LanguageName::Json,
+        ],
+        install: "npm install --global @biomejs/biome",
+        enabled: cfg!(any(
+            feature = "javascript",
+            feature = "typescript",
+            feature = "json"
+        )),
+    },
+    ExternalFormatter {
+        program: "google-java-format",
+        languages: "Java",
+        names: &[LanguageName::Java],
+        install: "download it from https://github.com/google/google-java-format/releases, or \
+                  brew install google-java-format",
+        enabled: cfg!(feature = "java"),
+    },
+    ExternalFormatter {
+        program: "ktlint",
+        languages: "Kotlin",
+        names: &[LanguageName::Kotlin],
+        install: "download it from https://github.com/pinterest/ktlint/releases, or brew \
+                  install ktlint",
+        enabled: cfg!(feature = "kotlin"),
+    },
+];
+
+/// Check the formatters edits are run through, session storage, grammars, the working
+/// directory, and configured language servers
+pub fn run(state: &SemanticEditTools) -> Vec<Check> {
+    let registry = state.language_registry();
+    let mut checks = FORMATTERS
+        .iter()
+        .filter(|formatter| formatter.enabled)
+        .map(|external| {
+            let replaced = external.names.iter().all(|name| {
+                formatting::Formatter::configured(registry.get_language(*name))
+                    != formatting::Formatter::BuiltIn
+            });
+            if replaced {
+                Check::new(
+                    format!("{} formatter", external.program),
+                    Outcome::Skipped(format!(
+                        "{} files are formatted as SEMANTIC_EDIT_FORMATTER_* configures",
+                        external.languages
+                    )),
+                )
+            } else {
+                formatter(external)
+            }
+        })
+        .collect::<Vec<_>>();
+    checks.extend(configured_formatters(registry));
+    checks.push(session_storage(state));
+    checks.extend(grammars(state.language_registry()));
+    checks.push(working_directory(state));
+    checks.extend(language_servers());
+    checks
+}
+
+fn formatter(formatter: &ExternalFormatter) -> Check {
+    let name = format!("{} formatter", formatter.program);
+    let outcome = match Command::new(formatter.program).arg("--version").output() {
+        Ok(output) if output.status.success() => Outcome::Ok(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        ),
+        Ok(output) => Outcome::Problem {
+            impact: format!(
+                "`{} --version` failed, so edits to {} files will be rejected: {}",
+                formatter.program,
+                formatter.languages,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            fix: format!("reinstall it: {}", formatter.install),
+        },
+        Err(error) => Outcome::Problem {
+            impact: format!(
+                "{} can't be run ({error}), so edits to {} files will be rejected",
+                formatter.program, formatter.languages
+            ),
+            fix: format!(
+                "install it and make sure it's on the PATH the server is started with: {}",
+                formatter.install
+            ),
+        },
+    };
+    Check::new(name, outcome)
+}
+
+/// Formatter commands configured in place of a language's built-in formatter that can't be found
+fn configured_formatters(registry: &LanguageRegistry) -> Vec<Check> {
+    let mut seen = BTreeSet::new();
+    registry
+        .languages()
+        .filter(|language| seen.insert(language.to_string()))
+        .filter_map(|language| {
+            let formatting::Formatter::Command(command) =
+                formatting::Formatter::configured(language)
+            else {
+                return None;
+            };
+            let program = command.split_whitespace().next()?;
+            let outcome = match find_program(program) {
+                Some(path) => Outcome::Ok(format!("`{command}`, with {}", path.display())),
+                None => Outcome::Problem {
+                    impact: format!(
+                        "{program} isn't on the PATH, so edits to {language} files will be \
+                         rejected"
+                    ),
+                    fix: format!(
+                        "install it, or change {}",
+                        formatting::Formatter::variable(language)
+                    ),
+                },
+            };
+            Some(Check::new(format!("{language} formatter"), outcome))
+        })
+        .collect()
+}
+
+fn session_storage(state: &SemanticEditTools) -> Check {
+    let name = "Session storage";
+    if let Err(error) = state
+        .get_staged_operation(None)
+        .and_then(|_| state.get_context(None))
+    {
+        return Check::new(
+            name,
+            Outcome::Problem {
+                impact: format!("sessions can't be read: {error}"),
+                fix: "delete or repair the session files, or set \
+                      SEMANTIC_EDIT_SESSION_STORAGE=memory to keep sessions in memory"
+                    .into(),
+            },
+        );
+    }
+
+    let paths = state.session_storage_paths();
+    if paths.is_empty() {
+        return Check::new(
+            name,
+            Outcome::Ok("kept in memory, so staged edits are lost when the server stops".into()),
+        );
+    }
+    let mut writable = vec![];
+    for path in paths {
+        let directory = path.parent().unwrap_or(Path::new("."));
+        if let Err(error) = probe_writable(directory) {
+            return Check::new(
+                name,
+                Outcome::Problem {
+                    impact: format!(
+                        "{} is not writable ({error}), so staged edits can't be saved",
+                        directory.display()
+                    ),
+                    fix: "fix the directory's permissions, point MCP_SESSION_STORAGE_PATH \
+                          somewhere writable, or set SEMANTIC_EDIT_SESSION_STORAGE=memory"
+                        .into(),
+                },
+            );
+        }
+        writable.push(path.display().to_string());
+    }
+    Check::new(
+        name,
+        Outcome::Ok(format!("{} writable", writable.join(" and "))),
+    )
+}
+
+fn grammars(registry: &LanguageRegistry) -> Vec<Check> {
+    let supported = MIN_COMPATIBLE_LANGUAGE_VERSION..=LANGUAGE_VERSION;
+    // dialects like jsx share a grammar with another language
+    let mut seen = BTreeSet::new();
+    let mut checks = registry
+        .languages()
+        .filter(|language| language.name() != LanguageName::Other)
+        .filter(|language| seen.insert(language.to_string()))
+        .map(|language| {
+            let version = language.tree_sitter_language().abi_version();
+            let outcome = if supported.contains(&version) {
+                Outcome::Ok(format!("ABI version {version}"))
+            } else {
+                Outcome::Problem {
+                    impact: format!(
+                        "ABI version {version} is outside the {}-{} this build of tree-sitter \
+                         supports",
+                        supported.start(),
+                        supported.end()
+                    ),
+                    fix: "rebuild semantic-edit-mcp with a grammar crate that matches its \
+                          tree-sitter version"
+                        .into(),
+                }
+            };
+            Check::new(format!("{language} grammar"), outcome)
+        })
+        .collect::<Vec<_>>();
+
+    let Some(config) = dynamic::configured() else {
+        return checks;
+    };
+    match dynamic::read_config(&config) {
+        Ok(grammars) => checks.extend(grammars.iter().map(|grammar| {
+            let outcome = match dynamic::load(grammar) {
+                Ok((_, language)) => Outcome::Ok(format!(
+                    "ABI version {}, loaded from {}",
+                    language.abi_version(),
+                    grammar.library
+                )),
+                Err(error) => Outcome::Problem {
+                    impact: format!("{error}, so its files are edited as plain text"),
+                    fix: format!(
+                        "check the `library` and `symbol` configured for it in {}",
+                        config.display()
+                    ),
+                },
+            };
+            Check::new(format!("{} grammar", grammar.name), outcome)
+        })),
+        Err(error) => checks.push(Check::new(
+            "Grammar configuration",
+            Outcome::Problem {
+                impact: format!(
+                    "{} can't be read ({error}), so no grammars are loaded from it",
+                    config.display()
+                ),
+                fix: "fix the file, or unset SEMANTIC_EDIT_GRAMMARS".into(),
+            },
+        )),
+    }
+    checks
+}
+
+fn working_directory(state: &SemanticEditTools) -> Check {
+    let name = "Working directory";
+    let directory = match state.get_context(None) {
+        Ok(Some(directory)) => directory,
+        Ok(None) => {
+            return Check::new(
+                name,
+                Outcome::Skipped(
+                    "not set, so file paths must be absolute until set_working_directory is used"
+                        .into(),
+                ),
+            );
+        }
+        Err(_) => return Check::new(name, Outcome::Skipped("session storage is broken".into())),
+    };
+
+    let outcome = if let Err(error) = fs::read_dir(&directory) {
+        Outcome::Problem {
+            impact: format!("{} can't be read: {error}", directory.display()),
+            fix: "check that the directory exists and is readable, or set a different one with \
+                  set_working_directory"
+                .into(),
+        }
+    } else if let Err(error) = probe_writable(&directory) {
+        Outcome::Problem {
+            impact: format!(
+                "{} is not writable ({error}), so edits can't be persisted there",
+                directory.display()
+            ),
+            fix: "fix the directory's permissions".into(),
+        }
+    } else {
+        Outcome::Ok(format!("{} is readable and writable", directory.display()))
+    };
+    Check::new(name, outcome)
+}
+
+/// Configured language servers that can't be found
+fn language_servers() -> Vec<Check> {
+    [
+        LanguageName::Rust,
+        LanguageName::Json,
+        LanguageName::Toml,
+        LanguageName::Javascript,
+        LanguageName::Typescript,
+        LanguageName::Tsx,
+        LanguageName::Python,
+        LanguageName::Jsx,
+        LanguageName::Yaml,
+        LanguageName::Java,
+        LanguageName::Kotlin,
+    ]
+    .into_iter()
+    .filter_map(|language| {
+        let server = LanguageServer::configured(language)?;
+        let outcome = match find_program(server.program()) {
+            Some(path) => Outcome::Ok(format!("{}", path.display())),
+            None => Outcome::Problem {
+                impact: format!(
+                    "{} isn't on the PATH, so {language} previews can't list its diagnostics",
+                    server.program()
+                ),
+                fix: format!(
+                    "install it, or change SEMANTIC_EDIT_LSP_{}",
+                    language.to_string().to_uppercase()
+                ),
+            },
+        };
+        Some(Check::new(format!("{language} language server"), outcome))
+    })
+    .collect()
+}
+
+/// Where `program` would be run from, if it can be found
+fn find_program(program: &str) -> Option<PathBuf> {
+    if program.contains(std::path::MAIN_SEPARATOR) {
+        return Path::new(program).is_file().then(|| program.into());
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|directory| directory.join(program))
+        .find(|path| path.is_file())
+}
+
+/// Check that files can be created in `directory` by creating and removing one
+fn probe_writable(directory: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(directory)?;
+    let probe = directory.join(format!(".semantic-edit-doctor-{}", std::process::id()));
+    fs::write(&probe, "")?;
+    fs::remove_file(probe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn working_directory_that_was_removed_is_a_problem() {
+        let dir = env::temp_dir().join(format!("semantic-edit-doctor-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let state = SemanticEditTools::new(None)
+            .unwrap()
+            .with_working_directory(dir.clone(), None)
+            .unwrap();
+
+        let check = working_directory(&state);
+        assert!(!check.is_problem(), "{check}");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::remove_dir(&dir).unwrap();
+        let check = working_directory(&state);
+        assert!(check.is_problem(), "{check}");
+        assert!(
+            check
+                .to_string()
+                .contains("Fix: check that the directory exists")
+        );
+
+        let check = session_storage(&state);
+        assert_eq!(
+            check.outcome,
+            Outcome::Ok("kept in memory, so staged edits are lost when the server stops".into())
+        );
+    }
+}
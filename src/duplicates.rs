@@ -0,0 +1,214 @@
+use crate::{
+    languages::LanguageName,
+    symbols::{FunctionDefinition, function_definitions},
+};
+use std::{
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::PathBuf,
+};
+use tree_sitter::{Node, Query, Tree};
+
+/// Functions with fewer syntax nodes than this in their body are too small to be worth
+/// reporting as duplicates
+const MIN_NODES: usize = 40;
+
+/// Length of the runs of node kinds that are compared between functions
+const SHINGLE_LENGTH: usize = 4;
+
+/// The normalized shape of a function body
+#[derive(Debug, Clone)]
+pub struct Fingerprint {
+    pub file: PathBuf,
+    pub name: String,
+    pub language: LanguageName,
+    /// 1-indexed line the function starts on
+    pub line: usize,
+    pub line_count: usize,
+    node_count: usize,
+    shingles: HashSet<u64>,
+}
+
+/// Two functions whose bodies have a similar shape
+#[derive(Debug)]
+pub struct Duplicate<'a> {
+    pub first: &'a Fingerprint,
+    pub second: &'a Fingerprint,
+    /// Between 0 and 1, where 1 means the bodies have the same shape
+    pub similarity: f64,
+}
+
+/// Fingerprint every function that a query captures as a `@definition` with a
+/// `@definition.name`.
+///
+/// A body's shape is the sequence of syntax node kinds in it, so names, literals, comments,
+/// and formatting do not affect it.
+pub fn fingerprints(
+    language: LanguageName,
+    query: &Query,
+    tree: &Tree,
+    source: &str,
+    file: PathBuf,
+) -> Vec<Fingerprint> {
+    let mut fingerprints = vec![];
+    for FunctionDefinition {
+        node, qualified, ..
+    } in function_definitions(query, tree, source)
+    {
+        let body = node
+            .child_by_field_name("body")
+            .or_else(|| {
+                node.child_by_field_name("value")
+                    .and_then(|value| value.child_by_field_name("body"))
+            })
+            .unwrap_or(node);
+        let mut kinds = vec![];
+        collect_kinds(body, &mut kinds);
+        if kinds.len() < MIN_NODES {
+            continue;
+        }
+
+        fingerprints.push(Fingerprint {
+            file: file.clone(),
+            name: qualified,
+            language,
+            line: node.start_position().row + 1,
+            line_count: node.end_position().row - node.start_position().row + 1,
+            node_count: kinds.len(),
+            shingles: kinds
+                .windows(SHINGLE_LENGTH)
+                .map(|window| {
+                    let mut hasher = DefaultHasher::new();
+                    window.hash(&mut hasher);
+                    hasher.finish()
+                })
+                .collect(),
+        });
+    }
+    fingerprints
+}
+
+fn collect_kinds(node: Node<'_>, kinds: &mut Vec<u16>) {
+    if node.kind().contains("comment") {
+        return;
+    }
+    kinds.push(node.kind_id());
+    for child in node.children(&mut node.walk()) {
+        collect_kinds(child, kinds);
+    }
+}
+
+/// Jaccard similarity of two functions' shingle sets
+fn similarity(first: &Fingerprint, second: &Fingerprint) -> f64 {
+    let shared = first.shingles.intersection(&second.shingles).count();
+    let total = first.shingles.len() + second.shingles.len() - shared;
+    if total == 0 {
+        1.0
+    } else {
+        shared as f64 / total as f64
+    }
+}
+
+/// Find pairs of functions in the same language whose similarity is at least that language's
+/// threshold, most similar and then largest first
+pub fn near_duplicates(
+    fingerprints: &[Fingerprint],
+    threshold: impl Fn(LanguageName) -> f64,
+) -> Vec<Duplicate<'_>> {
+    let mut duplicates = vec![];
+    for (index, first) in fingerprints.iter().enumerate() {
+        let threshold = threshold(first.language);
+        for second in &fingerprints[index + 1..] {
+            if second.language != first.language {
+                continue;
+            }
+
+            // the similarity can be no more than the ratio of the two sizes
+            let (smaller, larger) = if first.node_count < second.node_count {
+                (first.node_count, second.node_count)
+            } else {
+                (second.node_count, first.node_count)
+            };
+            if (smaller as f64) < threshold * larger as f64 {
+                continue;
+            }
+
+            let similarity = similarity(first, second);
+            if similarity >= threshold {
+                duplicates.push(Duplicate {
+                    first,
+                    second,
+                    similarity,
+                });
+            }
+        }
+    }
+
+    duplicates.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then(b.first.node_count.cmp(&a.first.node_count))
+    });
+    duplicates
+}
+
+#[cfg(all(test, feature = "rust"))]
+mod tests {
+    use super::*;
+    use crate::languages::LanguageRegistry;
+
+    fn fingerprint(source: &str) -> Vec<Fingerprint> {
+        let registry = LanguageRegistry::new().unwrap();
+        let rust = registry.get_language(LanguageName::Rust);
+        let tree = rust
+            .tree_sitter_parser()
+            .unwrap()
+            .parse(source, None)
+            .unwrap();
+        fingerprints(
+            LanguageName::Rust,
+            rust.calls_query().unwrap(),
+            &tree,
+            source,
+            "lib.rs".into(),
+        )
+    }
+
+    const TOTAL: &str = "fn total(items: &[Item]) -> u32 {
+        let mut sum = 0;
+        for item in items {
+            if item.enabled && item.count > 0 {
+                sum += item.count * item.price;
+            }
+        }
+        println!(\"{sum}\");
+        sum
+    }";
+
+    #[test]
+    fn renamed_copies_are_identical() {
+        let renamed = TOTAL
+            .replace("total", "subtotal")
+            .replace("sum", "acc")
+            .replace("item", "entry");
+        let fingerprints = fingerprint(&format!("{TOTAL}\n// a copy\n{renamed}"));
+        let duplicates = near_duplicates(&fingerprints, |_| 0.9);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].first.name, "total");
+        assert_eq!(duplicates[0].second.name, "subtotal");
+        assert_eq!(duplicates[0].similarity, 1.0);
+    }
+
+    #[test]
+    fn different_functions_are_not_duplicates() {
+        let other = "fn describe(config: &Config) -> String {
+            match config.mode {
+                Mode::Fast => format!(\"fast: {}\", config.name),
+                Mode::Slow { delay } => format!(\"slow ({delay}ms): {}\", config.name),
+            }
+        }";
+        let fingerprints = fingerprint(&format!("{TOTAL}\n{other}"));
+        assert_eq!(fingerprints.len(), 2);
+        assert!(near_duplicates(&fingerprints, |_| 0.5).is_empty());
+    }
+}
@@ -1,19 +1,32 @@
+mod clusters;
 mod edit;
 mod edit_iterator;
 mod edit_position;
 
 use crate::{
-    languages::{LanguageCommon, LanguageRegistry},
-    selector::Selector,
-    state::StagedOperation,
-    validation::ContextValidator,
+    encoding::Encoding,
+    formatting::{FormatScope, Formatter},
+    languages::{LanguageCommon, LanguageName, LanguageRegistry},
+    literals,
+    lsp::LanguageServer,
+    response_format::ResponseFormat,
+    searcher::{find_positions, find_relaxed},
+    selector::{Operation, Selector},
+    state::{self, Review, StagedOperation},
+    symbols,
+    validation::{
+        self, ContextValidator, EditContext, OperationType, ValidationRules, scope, workflow,
+    },
+    versions,
 };
 use anyhow::{Result, anyhow};
-use diffy::{DiffOptions, Patch, PatchFormatter};
+use diffy::{DiffOptions, Hunk, Line, Patch, PatchFormatter};
+use regex::Regex;
 use ropey::Rope;
-use std::{collections::BTreeSet, iter, path::PathBuf};
+use std::{collections::BTreeSet, iter, ops::Range, path::PathBuf};
 use tree_sitter::Tree;
 
+pub(crate) use clusters::{Cluster, clusters};
 pub(crate) use edit::Edit;
 pub(crate) use edit_iterator::EditIterator;
 pub(crate) use edit_position::EditPosition;
@@ -29,6 +42,21 @@ pub struct Editor<'language> {
     tree: Tree,
     rope: Rope,
     staged_edit: Option<EditPosition>,
+    #[field(with)]
+    report_impact: bool,
+    encoding: Encoding,
+    /// Whether to edit a file that already has syntax errors, checking only for errors the
+    /// edit introduces
+    allow_dirty: bool,
+    /// Where the errors the file already had start, when `allow_dirty` is set
+    pre_existing_errors: BTreeSet<usize>,
+    /// Whether to validate all of a large file rather than only the code around the edit, as is
+    /// done before it is persisted
+    full_validation: bool,
+    /// How strictly the project wants each validation rule enforced
+    rules: ValidationRules,
+    /// Violations of validation rules that the project only warns about, from the last edit
+    warnings: Option<String>,
 }
 
 impl<'language> Editor<'language> {
@@ -39,7 +67,28 @@ impl<'language> Editor<'language> {
         file_path: PathBuf,
         staged_edit: Option<EditPosition>,
     ) -> Result<Self> {
-        let source_code = std::fs::read_to_string(&file_path)?;
+        let (source_code, encoding) = Encoding::read(&file_path)?;
+        Self::from_source(
+            content,
+            selector,
+            language,
+            file_path,
+            staged_edit,
+            source_code,
+            encoding,
+        )
+    }
+
+    /// An editor for `source_code` in place of the file's contents on disk
+    pub fn from_source(
+        content: String,
+        selector: Selector,
+        language: &'language LanguageCommon,
+        file_path: PathBuf,
+        staged_edit: Option<EditPosition>,
+        source_code: String,
+        encoding: Encoding,
+    ) -> Result<Self> {
         let mut parser = language.tree_sitter_parser()?;
         let tree = parser.parse(&source_code, None).ok_or_else(|| {
             anyhow!(
@@ -49,6 +98,7 @@ impl<'language> Editor<'language> {
             )
         })?;
         let rope = Rope::from_str(&source_code);
+        let rules = ValidationRules::for_file(&file_path, &language.to_string())?;
 
         Ok(Self {
             content,
@@ -59,9 +109,33 @@ impl<'language> Editor<'language> {
             source_code,
             rope,
             staged_edit,
+            report_impact: false,
+            encoding,
+            allow_dirty: false,
+            pre_existing_errors: BTreeSet::new(),
+            full_validation: false,
+            rules,
+            warnings: None,
         })
     }
 
+    /// Edit even if the file already has syntax errors, so that the edit fixing them can be
+    /// made. Only errors that the edit introduces are then reported.
+    pub fn with_allow_dirty(mut self, allow_dirty: bool) -> Self {
+        self.allow_dirty = allow_dirty;
+        self.pre_existing_errors = if allow_dirty {
+            self.language
+                .editor()
+                .collect_errors(&self.tree, &self.source_code)
+                .into_iter()
+                .map(|error| error.range.start)
+                .collect()
+        } else {
+            BTreeSet::new()
+        };
+        self
+    }
+
     pub fn from_staged_operation(
         staged_operation: StagedOperation,
         language_registry: &'language LanguageRegistry,
@@ -72,62 +146,225 @@ impl<'language> Editor<'language> {
             file_path,
             language_name,
             edit_position,
+            allow_dirty,
+            review: _,
+            base_version: _,
         } = staged_operation;
-        let language = language_registry.get_language(language_name);
-        Self::new(content, selector, language, file_path, edit_position)
+        let file_path = state::canonicalize(&file_path)?;
+        // grammars loaded at runtime have no name of their own and are found again by extension
+        let language = match language_name {
+            LanguageName::Other => language_registry
+                .dynamic_language(&file_path)
+                .unwrap_or_else(|| language_registry.get_language(language_name)),
+            language_name => language_registry.get_language(language_name),
+        };
+        Ok(
+            Self::new(content, selector, language, file_path, edit_position)?
+                .with_allow_dirty(allow_dirty),
+        )
     }
 
     fn prevalidate(&self) -> Option<String> {
+        if self.allow_dirty {
+            return None;
+        }
         self.validate_tree(&self.tree, &self.source_code)
             .map(|errors| {
                 format!(
                     "Syntax error found prior to edit, not attempting.
-Suggestion: Pause and show your human collaborator this context:\n\n{errors}"
+Suggestion: Pause and show your human collaborator this context. If this edit is meant to fix \
+these errors, retry it with allow_dirty.\n\n{errors}"
                 )
             })
     }
 
     fn validate_tree(&self, tree: &Tree, content: &str) -> Option<String> {
-        Self::validate(self.language, tree, content)
+        Self::validate_where(
+            self.language,
+            tree,
+            content,
+            &self.rules,
+            None,
+            None,
+            |_| true,
+        )
+        .err()
+    }
+
+    /// Validate the result of an edit that replaced the source between `region.start` and the
+    /// edit's old end with `region`, changing the length of the file by `delta`. With
+    /// `allow_dirty`, only errors in the edited region, or elsewhere but not in the original
+    /// file, count. In large files, only the code around the edit is validated until the edit is
+    /// persisted.
+    ///
+    /// Returns the errors that reject the edit, or else warnings about it.
+    fn validate_edit(
+        &self,
+        tree: &Tree,
+        content: &str,
+        region: Range<usize>,
+        delta: isize,
+    ) -> Result<Option<String>, String> {
+        let scope = if self.full_validation {
+            None
+        } else {
+            scope::partial(tree, region.clone())
+        };
+        let edit = EditContext {
+            operation: OperationType::new(self.selector.operation, &self.content),
+            region: region.clone(),
+        };
+
+        let rules = &self.rules;
+        let warnings = Self::validate_where(
+            self.language,
+            tree,
+            content,
+            rules,
+            scope,
+            Some(&edit),
+            |range| {
+                if !self.allow_dirty {
+                    return true;
+                }
+                let start = range.start;
+                if (region.start..=region.end).contains(&start) {
+                    return true;
+                }
+                let original = if start < region.start {
+                    start
+                } else {
+                    start.saturating_add_signed(-delta)
+                };
+                !self.pre_existing_errors.contains(&original)
+            },
+        )?;
+
+        if workflow::is_workflow(&self.file_path) {
+            let problems = validation::introduced(
+                workflow::problems(&self.source_code),
+                workflow::problems(content),
+            );
+            if !problems.is_empty() {
+                return Err(workflow::describe(&problems));
+            }
+        }
+        let mut notes = warnings.into_iter().collect::<Vec<_>>();
+        for (kind, applies, check) in validation::FILE_WARNINGS {
+            if applies(&self.file_path) {
+                let introduced = validation::introduced(check(&self.source_code), check(content));
+                if !introduced.is_empty() {
+                    notes.push(validation::describe_warnings(kind, &introduced));
+                }
+            }
+        }
+        #[cfg(feature = "spelling")]
+        {
+            let misspellings =
+                |source| validation::misspellings(self.language, &self.file_path, source);
+            let introduced =
+                validation::introduced(misspellings(&self.source_code), misspellings(content));
+            if !introduced.is_empty() {
+                notes.push(validation::describe_warnings("Spelling", &introduced));
+            }
+        }
+        Ok((!notes.is_empty()).then(|| {
+            notes
+                .iter()
+                .map(|note| note.trim_end())
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        }))
+    }
+
+    /// A note about the long lines and trailing whitespace the edit introduces, once `formatted`
+    pub fn style_warnings(&self, formatted: &str) -> Option<String> {
+        let style = |source| validation::style(self.language.name(), &self.file_path, source);
+        let introduced = validation::introduced(style(&self.source_code), style(formatted));
+        (!introduced.is_empty()).then(|| validation::describe_warnings("Style", &introduced))
     }
 
     pub fn validate(language: &LanguageCommon, tree: &Tree, content: &str) -> Option<String> {
-        let errors = language.editor().collect_errors(tree, content);
+        let rules = ValidationRules::default();
+        Self::validate_where(language, tree, content, &rules, None, None, |_| true).err()
+    }
+
+    /// Validate `content`, or only the part of it within `scope`, reporting only the errors whose
+    /// byte range is `relevant`, as errors or warnings according to the project's `rules`. Rules
+    /// for some kinds of edit are checked against `edit`.
+    fn validate_where(
+        language: &LanguageCommon,
+        tree: &Tree,
+        content: &str,
+        rules: &ValidationRules,
+        scope: Option<Range<usize>>,
+        edit: Option<&EditContext>,
+        relevant: impl Fn(&Range<usize>) -> bool,
+    ) -> Result<Option<String>, String> {
+        let in_scope = |range: &Range<usize>| {
+            scope
+                .as_ref()
+                .is_none_or(|scope| range.start <= scope.end && scope.start <= range.end)
+        };
+        let mut errors = language.editor().collect_errors(tree, content);
+        errors.retain(|error| in_scope(&error.range) && relevant(&error.range));
+        errors.sort_by_key(|error| error.range.start);
+        errors.dedup_by_key(|error| error.range.start);
         if errors.is_empty() {
             if let Some(query) = language.validation_query() {
-                let validation_result = ContextValidator::validate_tree(tree, query, content);
-
-                if !validation_result.is_valid {
-                    return Some(validation_result.format_errors());
-                }
+                let mut validation_result =
+                    ContextValidator::validate_tree(tree, query, content, scope.clone(), edit);
+                validation_result
+                    .violations
+                    .retain(|violation| relevant(&violation.node.byte_range()));
+                return validation_result.check(rules);
             }
 
-            return None;
+            return Ok(None);
         }
 
         let context_lines = 3;
-        let lines_with_errors = errors.into_iter().collect::<BTreeSet<_>>();
+        let lines_with_errors = errors
+            .iter()
+            .map(|error| error.line)
+            .collect::<BTreeSet<_>>();
         let context_lines = lines_with_errors
             .iter()
             .copied()
             .flat_map(|line| line.saturating_sub(context_lines)..line + context_lines)
             .collect::<BTreeSet<_>>();
-        Some(
-            iter::once(String::from("===SYNTAX ERRORS===\n"))
-                .chain(
-                    content
-                        .lines()
-                        .enumerate()
-                        .filter(|(index, _)| context_lines.contains(index))
-                        .map(|(index, line)| {
-                            let display_index = index + 1;
-                            if lines_with_errors.contains(&index) {
-                                format!("{display_index:>4} ->⎸{line}\n")
-                            } else {
-                                format!("{display_index:>4}   ⎸{line}\n")
-                            }
-                        }),
-                )
+        let listing = content
+            .lines()
+            .enumerate()
+            .filter(|(index, _)| context_lines.contains(index))
+            .map(|(index, line)| {
+                let display_index = index + 1;
+                if lines_with_errors.contains(&index) {
+                    let mut listing = format!("{display_index:>4} ->⎸{line}\n");
+                    for error in errors.iter().filter(|error| error.line == index) {
+                        listing.push_str(&format!(
+                            "       ⎸{}{}\n",
+                            error.underline(content),
+                            error
+                                .hint
+                                .as_deref()
+                                .map(|hint| format!(" {hint}"))
+                                .unwrap_or_default()
+                        ));
+                    }
+                    listing
+                } else {
+                    format!("{display_index:>4}   ⎸{line}\n")
+                }
+            })
+            .collect::<String>();
+        Err(
+            iter::once(ResponseFormat::configured().section("Syntax errors", &listing, ""))
+                .chain(iter::once(String::from("\nErrors at:\n")))
+                .chain(errors.iter().map(|error| match &error.hint {
+                    Some(hint) => format!("- {error}: {hint}\n"),
+                    None => format!("- {error}\n"),
+                }))
                 .collect(),
         )
     }
@@ -154,54 +391,403 @@ Suggestion: Pause and show your human collaborator this context:\n\n{errors}"
         //     log::trace!("deduped from {count} to {count_after}");
         // }
 
+        let mut applied = None;
         for edit in &mut edits {
             if edit.apply() {
                 log::trace!("using {edit:#?}");
                 if let Some(annotation) = edit.annotation() {
                     log::info!("used {annotation}");
                 }
-                return Ok((edit.take_message().unwrap_or_default(), edit.take_output()));
+                applied = Some((
+                    edit.take_message().unwrap_or_default(),
+                    edit.take_output(),
+                    edit.take_warnings(),
+                ));
+                break;
             }
         }
 
-        log::trace!("{edits:#?}");
+        let Some((message, output, warnings)) = applied else {
+            log::trace!("{edits:#?}");
 
-        Ok((
-            edits
-                .first_mut()
-                .unwrap()
-                .take_message()
-                .unwrap_or_default(),
-            None,
-        ))
+            // an edit that parsed was rejected for a reason that holds wherever it's placed
+            let rejected = edits
+                .iter()
+                .position(|edit| edit.rejected_after_parsing())
+                .unwrap_or_default();
+            return Ok((edits[rejected].take_message().unwrap_or_default(), None));
+        };
+
+        self.warnings = warnings;
+        Ok((message, output))
+    }
+
+    /// When an edit is rejected by validation, try language-aware repairs of the content and
+    /// keep the first one that produces a valid file
+    fn repair(&mut self) -> Result<Option<(&'static str, String)>> {
+        if self.prevalidate().is_some() || self.build_edits().is_err() {
+            return Ok(None);
+        }
+
+        let original = std::mem::take(&mut self.content);
+        for (description, content) in self.language.editor().repairs(&original) {
+            self.content = content;
+            if let (_, Some(output)) = self.edit()? {
+                log::info!("repaired rejected edit: {description}");
+                return Ok(Some((description, output)));
+            }
+        }
+        self.content = original;
+        Ok(None)
+    }
+
+    /// With relaxed matching enabled, replace an anchor that isn't found verbatim with the source
+    /// text matched by the first relaxation that succeeds, describing what was relaxed
+    fn relax_anchor(&mut self) -> Option<String> {
+        let anchor = self.selector.anchor.trim();
+        if !self.selector.relaxed || find_positions(&self.source_code, anchor).is_ok() {
+            return None;
+        }
+
+        let (relaxation, range) = find_relaxed(&self.source_code, anchor)?;
+        let matched = self.source_code[range].to_string();
+        let message = format!(
+            "Anchor \"{anchor}\" was not found verbatim; matched \"{matched}\" after {}.\n\n",
+            relaxation.description()
+        );
+        self.selector.anchor = matched;
+        Some(message)
+    }
+
+    /// When matches of the anchor lie inside string literals or comments and `inside_literals`
+    /// wasn't given, say where, since an edit there changes a mention rather than the code
+    fn literal_note(&self) -> String {
+        if self.selector.inside_literals.is_some() {
+            return String::new();
+        }
+        let ranges: Vec<_> = match self.staged_edit {
+            Some(EditPosition {
+                start_byte,
+                end_byte: Some(end_byte),
+            }) => iter::once(start_byte..end_byte).collect(),
+            _ => find_positions(&self.source_code, self.selector.anchor.trim())
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(start, end)| start..end)
+                .collect(),
+        };
+        let count = ranges.len();
+        let places = ranges
+            .into_iter()
+            .filter_map(|range| {
+                let literal = literals::enclosing(self.language, &self.tree, range.clone())?;
+                Some(format!(
+                    "{literal} on line {}",
+                    self.rope.byte_to_line(range.start) + 1
+                ))
+            })
+            .collect::<Vec<_>>();
+        let inside = places.len();
+        let places = match places.as_slice() {
+            [] => return String::new(),
+            [place] => place.clone(),
+            [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+        };
+        if inside == count {
+            format!(
+                "⚠️ The match is inside {places}, where an edit changes text rather than code. \
+                 Set `inside_literals` to true if that's intended, or to false to skip matches \
+                 in strings and comments.\n\n"
+            )
+        } else {
+            format!(
+                "⚠️ The anchor also matches inside {places}. Check that the edit below changes \
+                 the code you meant, or set `inside_literals` to false to skip matches in \
+                 strings and comments.\n\n"
+            )
+        }
+    }
+
+    /// With a regex selector, find the one match of the anchor's pattern, fill its capture
+    /// groups into the content, and target the text it matched: the AST node there for inserts,
+    /// and exactly the match for replace. Returns a description of the match, or why the pattern
+    /// can't be used.
+    fn match_regex(&mut self) -> Result<String, String> {
+        if !self.selector.regex {
+            return Ok(String::new());
+        }
+        let pattern = self.selector.anchor.trim().to_string();
+        let regex = Regex::new(&pattern).map_err(|e| {
+            format!("The anchor `{pattern}` is not a valid regular expression: {e}")
+        })?;
+        let line = |offset: usize| self.source_code[..offset].matches('\n').count() + 1;
+        let mut matches = regex.find_iter(&self.source_code).collect::<Vec<_>>();
+        if let Some(inside) = self.selector.inside_literals {
+            matches.retain(|matched| {
+                literals::enclosing(self.language, &self.tree, matched.range()).is_some() == inside
+            });
+            if matches.is_empty() && regex.is_match(&self.source_code) {
+                return Err(literals::none_left(
+                    &format!("The pattern `{pattern}`"),
+                    inside,
+                ));
+            }
+        }
+        let matched = match matches.as_slice() {
+            [] => return Err(format!("The pattern `{pattern}` doesn't match anything")),
+            [matched] => *matched,
+            matches => {
+                return Err(format!(
+                    "The pattern `{pattern}` matches {} times, on lines {}. Make it specific \
+                     enough to match once.",
+                    matches.len(),
+                    matches
+                        .iter()
+                        .map(|matched| line(matched.start()).to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+        };
+        if matched.is_empty() {
+            return Err(format!(
+                "The pattern `{pattern}` matches an empty string, so there is no text to target"
+            ));
+        }
+
+        let mut content = String::new();
+        if let Some(captures) = regex.captures_at(&self.source_code, matched.start()) {
+            captures.expand(&self.content, &mut content);
+        }
+        let message = format!(
+            "Pattern `{pattern}` matched \"{}\" on line {}.\n\n",
+            matched.as_str(),
+            line(matched.start())
+        );
+        if self.selector.operation == Operation::Replace {
+            self.staged_edit = Some(EditPosition {
+                start_byte: matched.start(),
+                end_byte: Some(matched.end()),
+            });
+        }
+        self.selector.anchor = matched.as_str().to_string();
+        self.selector.regex = false;
+        self.content = content;
+        Ok(message)
+    }
+
+    /// With a symbol selector, find the declaration it names and target it: exactly its range
+    /// for replace, and its whole text as the anchor for inserts. Returns a description of what was
+    /// found, or why the symbol can't be used.
+    fn match_symbol(&mut self) -> Result<String, String> {
+        let Some(query) = self.selector.symbol.take() else {
+            return Ok(String::new());
+        };
+        let outline = symbols::outline(self.language, &self.tree, &self.source_code);
+        let node = symbols::find(&outline, &query)?;
+        let message = format!(
+            "Symbol `{}` found on line {}.\n\n",
+            query.trim(),
+            node.start_position().row + 1
+        );
+        let text = &self.source_code[node.byte_range()];
+        self.selector.anchor = if self.selector.operation == Operation::Replace {
+            self.staged_edit = Some(EditPosition {
+                start_byte: node.start_byte(),
+                end_byte: Some(node.end_byte()),
+            });
+            text.lines().next().unwrap_or_default().trim().to_string()
+        } else {
+            text.to_string()
+        };
+        Ok(message)
+    }
+
+    /// With a line selector, target the lines it names, or the smallest node enclosing them when
+    /// snapping: exactly that range for replace, and new lines before or after it for inserts.
+    /// Returns a description of what was selected, or why the lines can't be used.
+    fn match_lines(&mut self) -> Result<String, String> {
+        let Some(start_line) = self.selector.start_line.take() else {
+            return Ok(String::new());
+        };
+        let end_line = self.selector.end_line.take().unwrap_or(start_line);
+        let line_count = self.source_code.lines().count();
+        if end_line > line_count {
+            return Err(format!(
+                "Line {end_line} is past the end of the file, which has {line_count} lines"
+            ));
+        }
+        let source = &self.source_code;
+        let start = self.rope.line_to_byte(start_line - 1);
+        let end = start
+            + source[start..self.rope.line_to_byte(end_line)]
+                .trim_end()
+                .len();
+        let lines = if start_line == end_line {
+            format!("line {start_line}")
+        } else {
+            format!("lines {start_line}-{end_line}")
+        };
+        let trimmed_start =
+            start + (source[start..end].len() - source[start..end].trim_start().len());
+
+        let (range, message) = if std::mem::take(&mut self.selector.snap_to_node) {
+            let mut node = self
+                .tree
+                .root_node()
+                .descendant_for_byte_range(trimmed_start, end)
+                .ok_or_else(|| format!("There is no syntax node around {lines}"))?;
+            while !node.is_named() || node.start_byte() > trimmed_start || node.end_byte() < end {
+                node = node
+                    .parent()
+                    .ok_or_else(|| format!("There is no syntax node around {lines}"))?;
+            }
+            let message = format!(
+                "Snapped {lines} to the enclosing {}, lines {}-{}.\n\n",
+                node.kind(),
+                node.start_position().row + 1,
+                node.end_position().row + 1
+            );
+            (node.byte_range(), message)
+        } else {
+            (start..end, format!("Selected {lines}.\n\n"))
+        };
+        let line_start = source[..range.start]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let line_end = source[range.end..]
+            .find('\n')
+            .map_or(source.len(), |index| range.end + index);
+        self.staged_edit = Some(match self.selector.operation {
+            Operation::Replace => EditPosition {
+                start_byte: range.start,
+                end_byte: Some(range.end),
+            },
+            Operation::InsertBefore => {
+                self.content.push('\n');
+                EditPosition {
+                    start_byte: line_start,
+                    end_byte: None,
+                }
+            }
+            Operation::InsertAfter => {
+                self.content.insert(0, '\n');
+                EditPosition {
+                    start_byte: line_end,
+                    end_byte: None,
+                }
+            }
+        });
+        self.selector.anchor = source[line_start..line_end]
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        Ok(message)
+    }
+
+    /// Resolve a regex, symbol, or line selector to the text it targets
+    fn match_selector(&mut self) -> Result<String, String> {
+        if self.staged_edit.is_none() {
+            self.selector.validate()?;
+        }
+        Ok(self.match_regex()? + &self.match_symbol()? + &self.match_lines()?)
     }
 
     pub fn preview(mut self) -> Result<(String, Option<StagedOperation>)> {
-        let (message, output) = self.edit()?;
-        if let Some(output) = &output {
-            let mut preview = String::new();
+        // edits placed by position rather than matched text can't be mentions
+        let by_text = self.staged_edit.is_none()
+            && self.selector.symbol.is_none()
+            && self.selector.start_line.is_none();
+        let mut preview = match self.match_selector() {
+            Ok(matched) => matched,
+            Err(message) => return Ok((message, None)),
+        };
+        preview.push_str(&self.relax_anchor().unwrap_or_default());
+        if by_text {
+            preview.push_str(&self.literal_note());
+        }
+        if !self.pre_existing_errors.is_empty() {
+            let count = self.pre_existing_errors.len();
+            preview.push_str(&format!(
+                "⚠️ The file already had {count} syntax error{} before this edit. Only errors \
+                 the edit introduces were checked, and the file can't be formatted until all of \
+                 them are fixed.\n\n",
+                if count == 1 { "" } else { "s" }
+            ));
+        }
+        let (message, mut output) = self.edit()?;
 
+        if output.is_none() {
+            if let Some((description, repaired)) = self.repair()? {
+                preview.push_str(&format!(
+                    "{message}\n\n💡 SUGGESTED FIX: {description}. The repaired edit below has been \
+                     staged in place of the original; review it and use persist_edit to apply the \
+                     suggested fix, or stage a different edit.\n\n"
+                ));
+                output = Some(repaired);
+            }
+        }
+
+        if let Some(output) = &output {
+            if scope::is_partial(self.source_code.len()) {
+                preview.push_str(
+                    "ℹ️ This file is large, so only the code around the edit was validated. The \
+                     whole file is validated when the edit is persisted.\n\n",
+                );
+            }
+            let formatted = match FormatScope::configured() {
+                Ok(FormatScope::Edit) => "the lines your edit touches",
+                _ => "the entire file, including your edit",
+            };
             preview.push_str(&format!(
-                "Previewing: {}\nNote: the editor applies a consistent formatting style to the entire file, including your edit\n\n",
+                "Previewing: {}\nNote: the editor applies a consistent formatting style to {formatted}\n\n",
                 self.selector.operation_name()
             ));
+            if let Some(summary) = self.symbol_summary(output, self.report_impact) {
+                preview.push_str(&summary);
+            }
             preview.push_str(&self.diff(output));
+            if let Some(warnings) = &self.warnings {
+                preview.push_str(&format!("\n\n{}", warnings.trim_end()));
+            }
+            if let Some(report) = self.language_server_report(output) {
+                preview.push_str(&report);
+            }
+
+            if let Some(reason) = self.encoding.read_only_reason() {
+                preview.insert_str(0, &format!("⚠️ {reason}\n\n"));
+                return Ok((preview, None));
+            }
 
             Ok((preview, Some(self.into())))
         } else {
-            Ok((message, None))
+            preview.push_str(&message);
+            preview.push_str(&self.pitfall_hints());
+            Ok((preview, None))
+        }
+    }
+
+    /// Hints for language-specific mistakes the content seems to make, when it was rejected by
+    /// validation rather than because it couldn't be placed
+    fn pitfall_hints(&self) -> String {
+        if self.prevalidate().is_some() || self.build_edits().is_err() {
+            return String::new();
         }
+        self.language
+            .editor()
+            .pitfalls(&self.content)
+            .into_iter()
+            .map(|hint| format!("\n\n💡 Tip: {hint}"))
+            .collect()
     }
 
     fn diff(&self, output: &str) -> String {
         let source_code: &str = &self.source_code;
         let content_patch = &self.content;
         let diff_patch = DiffOptions::new().create_patch(source_code, output);
-        let formatter = PatchFormatter::new().missing_newline_message(false);
 
-        // Get the diff string and clean it up for AI consumption
-        let diff_output = formatter.fmt_patch(&diff_patch).to_string();
-        let lines: Vec<&str> = diff_output.lines().collect();
         let mut cleaned_diff = String::new();
 
         let content_line_count = content_patch.lines().count();
@@ -216,28 +802,93 @@ Suggestion: Pause and show your human collaborator this context:\n\n{errors}"
             cleaned_diff.push('\n');
         }
 
-        cleaned_diff.push_str("===DIFF===\n");
-        for line in lines {
-            // Skip ALL diff headers: file headers, hunk headers (line numbers), and any metadata
-            if line.starts_with("---") || line.starts_with("+++") || line.starts_with("@@") {
-                // Skip "\ No newline at end of file" messages
-                continue;
-            }
-            cleaned_diff.push_str(line);
-            cleaned_diff.push('\n');
+        cleaned_diff.push_str(&ResponseFormat::configured().section(
+            "Diff",
+            &format_numbered_patch(
+                &diff_patch,
+                &clusters(self.language, &diff_patch, output),
+                source_code,
+            ),
+            "diff",
+        ));
+        cleaned_diff
+    }
+
+    /// Describe which symbols the edit adds, removes, or modifies, for humans reviewing the change,
+    /// optionally listing the places in the file that still refer to them
+    fn symbol_summary(&self, output: &str, include_impact: bool) -> Option<String> {
+        let new_tree = self.parse(output, None)?;
+        let changes = symbols::diff_symbols(
+            self.language,
+            &self.tree,
+            &self.source_code,
+            &new_tree,
+            output,
+        );
+
+        let mut summary = String::new();
+        if let Some(changed) = symbols::summarize(&changes) {
+            summary.push_str(&format!("Changed symbols: {changed}\n\n"));
         }
 
-        // Remove trailing newline to avoid extra spacing
-        if cleaned_diff.ends_with('\n') {
-            cleaned_diff.pop();
+        if include_impact && !changes.is_empty() {
+            summary.push_str("Possibly affected references:\n");
+            summary.push_str(&symbols::impact_report(
+                self.language,
+                &changes,
+                &new_tree,
+                output,
+            ));
+            summary.push('\n');
         }
-        cleaned_diff
+
+        (!summary.is_empty()).then_some(summary)
     }
 
-    pub fn format_code(&self, source: &str) -> Result<String, String> {
-        self.language
-            .editor()
-            .format_code(source, &self.file_path)
+    /// Diagnostics for the staged content from the language server configured for this
+    /// language, if there is one
+    fn language_server_report(&self, output: &str) -> Option<String> {
+        let server = LanguageServer::configured(self.language.name())?;
+        let name = server.name();
+        Some(
+            match server.diagnostics(self.language.name(), &self.file_path, output) {
+                Ok(diagnostics) if diagnostics.is_empty() => {
+                    format!("\n\n✅ {name} reports no errors or warnings in the staged content")
+                }
+                Ok(diagnostics) => {
+                    let mut report = format!("\n\n⚠️ {name} diagnostics for the staged content:");
+                    for diagnostic in diagnostics {
+                        report.push_str(&format!("\n  {diagnostic}"));
+                    }
+                    report
+                }
+                Err(error) => {
+                    log::warn!("{name} diagnostics failed: {error}");
+                    format!("\n\n⚠️ Unable to get diagnostics from {name}: {error}")
+                }
+            },
+        )
+    }
+
+    /// Apply the edit the way persist_edit would, returning a description of the result and the
+    /// edited source, or why the edit was rejected
+    pub fn simulate(mut self) -> Result<(String, Option<String>)> {
+        let matched = match self.match_selector() {
+            Ok(matched) => matched,
+            Err(message) => return Ok((message, None)),
+        };
+        let relaxed = self.relax_anchor().unwrap_or_default();
+        let (message, output) = self.edit()?;
+        Ok((format!("{matched}{relaxed}{message}"), output))
+    }
+
+    /// Format the edited `source`, within the configured [`FormatScope`] of `edited`, the byte
+    /// range of `source` the edit wrote
+    pub fn format_code(&self, source: &str, edited: Range<usize>) -> Result<String, String> {
+        let scope = FormatScope::configured().map_err(|error| error.to_string())?;
+        Formatter::configured(self.language)
+            .format(self.language, source, &self.file_path)
+            .map(|formatted| scope.apply(source, formatted, edited))
             .map_err(|e| {
                 let diff = self.diff(source);
                 format!(
@@ -251,16 +902,28 @@ Suggestion: Pause and show your human collaborator this context:\n\n{errors}"
     }
 
     pub fn commit(mut self) -> Result<(String, Option<String>, PathBuf)> {
+        if let Some(reason) = self.encoding.read_only_reason() {
+            return Ok((reason, None, self.file_path));
+        }
+
+        self.full_validation = true;
         let (mut message, output) = self.edit()?;
         if let Some(output) = &output {
+            let summary = self.symbol_summary(output, false).unwrap_or_default();
             let diff = self.diff(output);
+            let warnings = self
+                .warnings
+                .as_deref()
+                .map(|warnings| format!("\n\n{}", warnings.trim_end()))
+                .unwrap_or_default();
 
             message = format!(
-                "{} operation result:\n{}\n\n{diff}",
+                "{} operation result:\n{}\n\n{summary}{diff}{warnings}",
                 self.selector.operation_name(),
                 message,
             );
         }
+        let output = output.map(|output| self.encoding.encode(output));
         Ok((message, output, self.file_path))
     }
 
@@ -278,6 +941,8 @@ impl From<Editor<'_>> for StagedOperation {
             file_path,
             language,
             staged_edit,
+            allow_dirty,
+            source_code,
             ..
         } = value;
         Self {
@@ -286,8 +951,131 @@ impl From<Editor<'_>> for StagedOperation {
             file_path,
             language_name: language.name(),
             edit_position: staged_edit,
+            allow_dirty,
+            review: Review::default(),
+            base_version: Some(versions::of(&source_code)),
+        }
+    }
+}
+
+/// Format a patch for AI consumption: its changed and context lines, without file headers, hunk
+/// headers, or missing-newline messages
+pub(crate) fn format_patch(patch: &Patch<'_, str>) -> String {
+    let formatter = PatchFormatter::new().missing_newline_message(false);
+    let diff_output = formatter.fmt_patch(patch).to_string();
+    let mut cleaned_diff = String::new();
+    for line in diff_output.lines() {
+        // Skip ALL diff headers: file headers, hunk headers (line numbers), and any metadata
+        if line.starts_with("---") || line.starts_with("+++") || line.starts_with("@@") {
+            continue;
+        }
+        cleaned_diff.push_str(line);
+        cleaned_diff.push('\n');
+    }
+
+    // Remove trailing newline to avoid extra spacing
+    if cleaned_diff.ends_with('\n') {
+        cleaned_diff.pop();
+    }
+    cleaned_diff
+}
+
+/// Format a patch like [`format_patch`], but with a numbered `@@ hunk N @@` line starting each
+/// of its `clusters` when there is more than one, so that persist_edit can be asked for some of
+/// them. The hunks of a cluster are shown as one, joined by the lines of `source` between them.
+pub(crate) fn format_numbered_patch(
+    patch: &Patch<'_, str>,
+    clusters: &[Cluster],
+    source: &str,
+) -> String {
+    let lines = source.split_inclusive('\n').collect::<Vec<_>>();
+    let hunks = patch.hunks();
+    let mut formatted = String::new();
+    for (number, cluster) in clusters.iter().enumerate() {
+        let edits = cluster.hunks.len();
+        match (&cluster.symbol, clusters.len() > 1) {
+            (Some(symbol), true) => formatted.push_str(&format!(
+                "@@ hunk {}: {edits} edits to {symbol} @@\n",
+                number + 1
+            )),
+            (Some(symbol), false) => {
+                formatted.push_str(&format!("@@ {edits} edits to {symbol} @@\n"))
+            }
+            (None, true) => formatted.push_str(&format!("@@ hunk {} @@\n", number + 1)),
+            (None, false) => {}
+        }
+        for index in cluster.hunks.clone() {
+            if index > cluster.hunks.start {
+                let gap = &lines[old_lines(&hunks[index - 1]).end..old_lines(&hunks[index]).start];
+                if gap.len() > JOINED_LINES {
+                    formatted.push_str("⋮\n");
+                } else {
+                    for line in gap {
+                        push_line(&mut formatted, ' ', line);
+                    }
+                }
+            }
+            for line in hunks[index].lines() {
+                match line {
+                    Line::Context(text) => push_line(&mut formatted, ' ', text),
+                    Line::Delete(text) => push_line(&mut formatted, '-', text),
+                    Line::Insert(text) => push_line(&mut formatted, '+', text),
+                }
+            }
+        }
+    }
+    // Remove trailing newline to avoid extra spacing
+    formatted.pop();
+    formatted
+}
+
+/// Add a line of a diff, leaving blank unchanged lines blank as diffy does
+fn push_line(formatted: &mut String, prefix: char, text: &str) {
+    let text = text.trim_end_matches(['\n', '\r']);
+    if prefix != ' ' || !text.is_empty() {
+        formatted.push(prefix);
+    }
+    formatted.push_str(text);
+    formatted.push('\n');
+}
+
+/// The most unchanged lines shown between the hunks of a cluster, beyond which they're elided
+const JOINED_LINES: usize = 12;
+
+/// The zero-based lines of the original text that a hunk replaces
+fn old_lines(hunk: &Hunk<'_, str>) -> Range<usize> {
+    let range = hunk.old_range();
+    // unified diff ranges count from 1, except that an empty range names the line before it
+    let start = if range.is_empty() {
+        range.start()
+    } else {
+        range.start() - 1
+    };
+    start..start + range.len()
+}
+
+/// The original text of `patch`, `source`, with only the hunks at the zero-based indices in
+/// `selected` applied
+pub(crate) fn apply_hunks(source: &str, patch: &Patch<'_, str>, selected: &[usize]) -> String {
+    let lines = source.split_inclusive('\n').collect::<Vec<_>>();
+    let mut applied = String::with_capacity(source.len());
+    let mut next = 0;
+    for (index, hunk) in patch.hunks().iter().enumerate() {
+        let range = old_lines(hunk);
+        applied.extend(lines[next..range.start].iter().copied());
+        let apply = selected.contains(&index);
+        for line in hunk.lines() {
+            match line {
+                Line::Context(text) => applied.push_str(text),
+                Line::Delete(text) if !apply => applied.push_str(text),
+                Line::Insert(text) if apply => applied.push_str(text),
+                Line::Delete(_) | Line::Insert(_) => {}
+            }
         }
+        next = range.end;
     }
+    applied.extend(lines[next..].iter().copied());
+    applied
 }
 
 pub fn changed_lines(patch: &Patch<'_, str>, content_line_count: usize) -> usize {
@@ -0,0 +1,146 @@
+use crate::{languages::LanguageCommon, symbols};
+use diffy::{Line, Patch};
+use std::ops::Range;
+
+/// Consecutive hunks of a diff that change the same symbol, like several edits from a batch
+/// that land in one function, which are numbered, reviewed, and persisted as one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Cluster {
+    /// The indices of its hunks in the patch
+    pub hunks: Range<usize>,
+    /// The symbol its hunks change, when there is more than one of them
+    pub symbol: Option<String>,
+}
+
+/// Group the hunks of `patch`, whose edited text is `output`, into clusters of consecutive
+/// hunks that change the same innermost symbol. Hunks outside any symbol are clusters of their
+/// own.
+pub(crate) fn clusters(
+    language: &LanguageCommon,
+    patch: &Patch<'_, str>,
+    output: &str,
+) -> Vec<Cluster> {
+    let tree = language
+        .tree_sitter_parser()
+        .ok()
+        .and_then(|mut parser| parser.parse(output, None));
+    let outline = tree
+        .as_ref()
+        .map(|tree| symbols::outline(language, tree, output))
+        .unwrap_or_default();
+    let lines = output.split_inclusive('\n').collect::<Vec<_>>();
+    let line_starts = lines
+        .iter()
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some(start)
+        })
+        .collect::<Vec<_>>();
+    // the bytes of a line of `output` without its indentation or line break
+    let content = |line: usize| -> Range<usize> {
+        let Some(text) = lines.get(line) else {
+            return output.len()..output.len();
+        };
+        let trimmed = text.trim_end_matches(['\n', '\r']);
+        let start = line_starts[line] + trimmed.len() - trimmed.trim_start().len();
+        start..line_starts[line] + trimmed.len()
+    };
+
+    let mut clusters: Vec<(Cluster, Option<usize>)> = vec![];
+    for (index, hunk) in patch.hunks().iter().enumerate() {
+        let symbol = changed_lines(hunk.lines(), hunk.new_range()).and_then(|changed| {
+            symbols::innermost(
+                &outline,
+                content(changed.start).start..content(changed.end).end,
+            )
+        });
+        match clusters.last_mut() {
+            Some((cluster, previous)) if symbol.is_some() && *previous == symbol => {
+                cluster.hunks.end = index + 1;
+            }
+            _ => clusters.push((
+                Cluster {
+                    hunks: index..index + 1,
+                    symbol: None,
+                },
+                symbol,
+            )),
+        }
+    }
+
+    clusters
+        .into_iter()
+        .map(|(mut cluster, symbol)| {
+            if cluster.hunks.len() > 1 {
+                cluster.symbol = symbol.map(|symbol| symbols::describe(&outline, symbol));
+            }
+            cluster
+        })
+        .collect()
+}
+
+/// The first and last zero-based lines of the edited text that a hunk inserts, or that it
+/// deletes lines before
+fn changed_lines(lines: &[Line<'_, str>], new_range: diffy::HunkRange) -> Option<Range<usize>> {
+    // unified diff ranges count from 1, except that an empty range names the line before it
+    let mut line = if new_range.is_empty() {
+        new_range.start()
+    } else {
+        new_range.start() - 1
+    };
+    let mut changed: Option<Range<usize>> = None;
+    for hunk_line in lines {
+        let at = line;
+        match hunk_line {
+            Line::Context(_) => {
+                line += 1;
+                continue;
+            }
+            Line::Insert(_) => line += 1,
+            Line::Delete(_) => {}
+        }
+        changed = Some(match changed {
+            Some(range) => range.start..at,
+            None => at..at,
+        });
+    }
+    changed
+}
+
+#[cfg(all(test, feature = "rust"))]
+mod tests {
+    use super::*;
+    use crate::languages::{LanguageName, LanguageRegistry};
+    use diffy::DiffOptions;
+
+    #[test]
+    fn hunks_in_one_function_are_clustered() {
+        let body = (1..=12)
+            .map(|n| format!("    let x{n} = {n};\n"))
+            .collect::<String>();
+        let source = format!("fn first() {{\n{body}}}\n\nfn second() {{\n{body}}}\n");
+        let output = source
+            .replacen("x1 = 1;", "x1 = 10;", 1)
+            .replacen("x12 = 12", "x12 = 120", 1)
+            .replace("x6 = 6", "x6 = 60")
+            .replacen("x6 = 60", "x6 = 6", 1);
+        let registry = LanguageRegistry::new().unwrap();
+        let language = registry.get_language(LanguageName::Rust);
+        let patch = DiffOptions::new().create_patch(&source, &output);
+        assert_eq!(patch.hunks().len(), 3);
+        assert_eq!(
+            clusters(language, &patch, &output),
+            [
+                Cluster {
+                    hunks: 0..2,
+                    symbol: Some("fn `first`".into()),
+                },
+                Cluster {
+                    hunks: 2..3,
+                    symbol: None,
+                },
+            ]
+        );
+    }
+}
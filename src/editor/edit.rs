@@ -5,6 +5,7 @@ use ropey::Rope;
 use std::{
     borrow::Cow,
     fmt::{self, Debug, Formatter},
+    ops::Range,
 };
 use tree_sitter::{InputEdit, Node, Point, Tree};
 
@@ -20,10 +21,17 @@ pub struct Edit<'editor, 'language> {
     position: EditPosition,
     #[field(get = is_valid)]
     valid: Option<bool>,
+    /// Whether the edited file parsed without errors but was rejected by validation, like for
+    /// breaking a workflow, which makes that rejection the reason worth reporting
+    #[field(get)]
+    rejected_after_parsing: bool,
     #[field(get, take)]
     message: Option<String>,
     #[field(get, take)]
     output: Option<String>,
+    /// Violations of validation rules that the project only warns about
+    #[field(take)]
+    warnings: Option<String>,
     #[field(get, set, with, take)]
     nodes: Option<Vec<Node<'editor>>>,
     #[field(with, get, set)]
@@ -109,8 +117,10 @@ impl<'editor, 'language> Edit<'editor, 'language> {
             position,
             content: Cow::Borrowed(&editor.content),
             valid: None,
+            rejected_after_parsing: false,
             message: None,
             output: None,
+            warnings: None,
             nodes: None,
             annotation: None,
         }
@@ -223,7 +233,9 @@ impl<'editor, 'language> Edit<'editor, 'language> {
             return false;
         }
 
-        let valid = if let Some(message) = self.validate(&output) {
+        let delta = new_end_byte as isize - old_end_byte as isize;
+        let valid = if let Err(message) = self.validate(&output, start_byte..new_end_byte, delta) {
+            self.rejected_after_parsing = !self.tree.root_node().has_error();
             self.message = Some(message);
             false
         } else {
@@ -232,11 +244,22 @@ impl<'editor, 'language> Edit<'editor, 'language> {
                 self.editor.selector.operation_name()
             ));
 
-            match self.editor.format_code(&output) {
+            match self.editor.format_code(&output, start_byte..new_end_byte) {
                 Ok(formatted) => {
+                    if let Some(note) = self.editor.style_warnings(&formatted) {
+                        self.warnings = Some(match self.warnings.take() {
+                            Some(warnings) => format!("{warnings}\n\n{note}"),
+                            None => note,
+                        });
+                    }
                     self.output = Some(formatted);
                     true
                 }
+                // a file with syntax errors of its own can't be formatted
+                Err(_) if self.editor.allow_dirty => {
+                    self.output = Some(output);
+                    true
+                }
                 Err(err) => {
                     self.message = Some(err);
                     false
@@ -248,10 +271,17 @@ impl<'editor, 'language> Edit<'editor, 'language> {
         valid
     }
 
-    fn validate(&mut self, output: &str) -> Option<String> {
-        let errors = self.editor.validate_tree(&self.tree, output)?;
+    /// Validate the edited `output`, keeping any warnings, or describe why the edit is rejected
+    fn validate(&mut self, output: &str, region: Range<usize>, delta: isize) -> Result<(), String> {
+        let errors = match self.editor.validate_edit(&self.tree, output, region, delta) {
+            Ok(warnings) => {
+                self.warnings = warnings;
+                return Ok(());
+            }
+            Err(errors) => errors,
+        };
         let diff = self.editor.diff(output);
-        Some(format!(
+        Err(format!(
             "This edit would result in invalid syntax, but the file is still in a valid state. \
 No change was performed.
 Suggestion: Try a different change.\n
@@ -263,15 +293,18 @@ Suggestion: Try a different change.\n
         self.editor.source_code()
     }
 
+    #[cfg(any(feature = "python", feature = "yaml"))]
     pub(crate) fn start_byte(&self) -> usize {
         self.position.start_byte
     }
 
+    #[cfg(any(feature = "python", feature = "yaml"))]
     pub(crate) fn set_start_byte(&mut self, start_byte: usize) -> &mut Self {
         self.position.start_byte = start_byte;
         self
     }
 
+    #[cfg(feature = "json")]
     pub(crate) fn modify(mut fun: impl FnMut(&mut Self)) -> impl FnMut(Self) -> Self {
         move |mut edit| {
             fun(&mut edit);
@@ -279,6 +312,7 @@ Suggestion: Try a different change.\n
         }
     }
 
+    #[cfg(feature = "python")]
     pub(crate) fn with_start_byte(mut self, start_byte: usize) -> Self {
         self.position.start_byte = start_byte;
         self
@@ -4,6 +4,7 @@ use tree_sitter::{Node, Tree};
 
 use crate::{
     editor::EditPosition,
+    literals,
     searcher::find_positions,
     selector::{Operation, Selector},
 };
@@ -50,10 +51,18 @@ impl<'editor, 'language> EditIterator<'editor, 'language> {
     }
 
     pub(crate) fn find_edits(&self) -> Result<Vec<Edit<'editor, 'language>>, String> {
+        // an operation staged at an exact position, like a language server's code action,
+        // applies there rather than wherever the anchor is found
+        if let Some(edit_position) = self.staged_edit {
+            return Ok(vec![Edit::new(self.editor, *edit_position)]);
+        }
+
         let source_code: &str = self.source_code;
         let tree: &Tree = self.tree;
         self.selector.validate()?;
-        let Selector { operation, anchor } = &*self.selector;
+        let Selector {
+            operation, anchor, ..
+        } = &*self.selector;
 
         match operation {
             Operation::InsertAfter => {
@@ -132,8 +141,19 @@ impl<'editor, 'language> EditIterator<'editor, 'language> {
         tree: &'editor Tree,
     ) -> Result<Vec<Edit<'editor, 'language>>, String> {
         let anchor = anchor.trim();
+        let mut positions = find_positions(source_code, anchor)?
+            .into_iter()
+            .map(|(start, end)| start..end)
+            .collect::<Vec<_>>();
+        if let Some(inside) = self.selector.inside_literals {
+            positions = literals::filter(self.editor.language(), tree, positions, inside);
+            if positions.is_empty() {
+                return Err(literals::none_left(&format!("Anchor \"{anchor}\""), inside));
+            }
+        }
+
         let mut candidates = vec![];
-        for (start, end) in find_positions(source_code, anchor)? {
+        for std::ops::Range { start, end } in positions {
             if let Some(parent) = tree.root_node().descendant_for_byte_range(start, end) {
                 let nodes = siblings_in_range(parent, start, end);
                 if !nodes.is_empty() {
@@ -168,11 +188,6 @@ impl<'editor, 'language> Iterator for EditIterator<'editor, 'language> {
     type Item = Result<Edit<'editor, 'language>, String>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // If we have a staged edit, return it first and only once
-        if let Some(edit_position) = self.staged_edit.take() {
-            return Some(Ok(Edit::new(self.editor, *edit_position)));
-        }
-
         // Ensure text ranges are loaded
         if let Err(e) = self.ensure_text_ranges_loaded() {
             return Some(Err(e));
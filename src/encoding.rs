@@ -0,0 +1,270 @@
+use anyhow::{Result, anyhow, bail};
+use std::{env, fs, path::Path};
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16_BOMS: [&[u8]; 2] = [b"\xFE\xFF", b"\xFF\xFE"];
+
+/// Files larger than this are only edited with `allow_large`, unless `SEMANTIC_EDIT_MAX_FILE_SIZE`
+/// sets another size in bytes
+const DEFAULT_MAX_FILE_SIZE: usize = 4 * 1024 * 1024;
+
+/// How much of a file is checked for NUL bytes, which text files don't contain
+const BINARY_SNIFF_LENGTH: usize = 8 * 1024;
+
+/// The leading bytes of common binary formats, and what to call them
+const MAGIC_NUMBERS: [(&[u8], &str); 10] = [
+    (b"\x89PNG\r\n\x1a\n", "PNG image"),
+    (b"\xFF\xD8\xFF", "JPEG image"),
+    (b"GIF8", "GIF image"),
+    (b"%PDF-", "PDF document"),
+    (b"PK\x03\x04", "zip archive"),
+    (b"\x1f\x8b", "gzip archive"),
+    (b"\x7fELF", "ELF executable"),
+    (b"\xCF\xFA\xED\xFE", "Mach-O executable"),
+    (b"\0asm", "WebAssembly module"),
+    (b"SQLite format 3\0", "SQLite database"),
+];
+
+/// How a source file was encoded on disk, so that edits can be written back faithfully
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Encoding {
+    /// Whether the file began with a UTF-8 byte-order mark, which is restored on write
+    pub bom: bool,
+    /// Byte offset of the first invalid UTF-8 sequence, if the file had to be decoded lossily.
+    /// Lossily decoded files can be previewed but never written back.
+    pub invalid_utf8_at: Option<usize>,
+}
+
+impl Encoding {
+    /// Read a file as text, without any byte-order mark so that byte offsets refer to the text
+    pub fn read(path: &Path) -> Result<(String, Self)> {
+        let bytes = std::fs::read(path)?;
+        Self::decode(path, bytes)
+    }
+
+    fn decode(path: &Path, bytes: Vec<u8>) -> Result<(String, Self)> {
+        if let Some(kind) = binary_kind(&bytes) {
+            bail!(
+                "{} is a binary file ({kind}, {}), not text, so it can't be previewed or edited. \
+                 Edit the source it's generated from, or use a tool made for its format.",
+                path.display(),
+                size(bytes.len())
+            );
+        }
+
+        if UTF16_BOMS.iter().any(|bom| bytes.starts_with(bom)) {
+            bail!(
+                "{} is UTF-16 encoded, which is not supported. Convert it to UTF-8 to edit it.",
+                path.display()
+            );
+        }
+
+        let (bom, body) = match bytes.strip_prefix(UTF8_BOM) {
+            Some(body) => (true, body.to_vec()),
+            None => (false, bytes),
+        };
+
+        match String::from_utf8(body) {
+            Ok(text) => Ok((
+                text,
+                Self {
+                    bom,
+                    invalid_utf8_at: None,
+                },
+            )),
+            Err(error) => {
+                let offset =
+                    error.utf8_error().valid_up_to() + if bom { UTF8_BOM.len() } else { 0 };
+                Ok((
+                    String::from_utf8_lossy(error.as_bytes()).into_owned(),
+                    Self {
+                        bom,
+                        invalid_utf8_at: Some(offset),
+                    },
+                ))
+            }
+        }
+    }
+
+    /// Explain why a lossily decoded file cannot be written, if it was
+    pub fn read_only_reason(&self) -> Option<String> {
+        self.invalid_utf8_at.map(|offset| {
+            format!(
+                "This file is not valid UTF-8 (first invalid byte at offset {offset}), possibly because \
+                 it uses a legacy encoding like Latin-1. It was read lossily, with invalid bytes \
+                 shown as �, and is read-only: edits can be previewed but not persisted. \
+                 Convert the file to UTF-8 to edit it."
+            )
+        })
+    }
+
+    /// Prepare edited text for writing, restoring the byte-order mark if the file had one
+    pub fn encode(&self, text: String) -> String {
+        if self.bom {
+            format!("\u{feff}{text}")
+        } else {
+            text
+        }
+    }
+}
+
+/// Refuse to edit a file larger than the configured ceiling, since files that large are usually
+/// generated bundles or datasets rather than source. Files that can't be read are left for the
+/// read to report. `allow_large` lets a tool call edit it anyway.
+pub fn check_size(path: &Path, allow_large: bool) -> Result<()> {
+    if allow_large {
+        return Ok(());
+    }
+    check_size_within(path, max_file_size()?)
+}
+
+/// The size limit for editing in bytes, from `SEMANTIC_EDIT_MAX_FILE_SIZE` or 4 MiB by default
+pub fn max_file_size() -> Result<usize> {
+    match env::var("SEMANTIC_EDIT_MAX_FILE_SIZE") {
+        Ok(max) => max.trim().parse().map_err(|_| {
+            anyhow!(
+                "SEMANTIC_EDIT_MAX_FILE_SIZE is `{max}`, which is not a number of bytes. Set it \
+                 to a whole number of bytes, or unset it for the default of {}.",
+                size(DEFAULT_MAX_FILE_SIZE)
+            )
+        }),
+        Err(_) => Ok(DEFAULT_MAX_FILE_SIZE),
+    }
+}
+
+/// Refuse to edit a file larger than `max` bytes
+pub fn check_size_within(path: &Path, max: usize) -> Result<()> {
+    let Ok(metadata) = fs::metadata(path) else {
+        return Ok(());
+    };
+    let len = usize::try_from(metadata.len()).unwrap_or(usize::MAX);
+    if len > max {
+        // sizes that round to the same figure are given exactly, so the file is visibly larger
+        let (len, max) = if max < 1024 || size(len) == size(max) {
+            (format!("{len} bytes"), format!("{max}-byte"))
+        } else {
+            (size(len), size(max))
+        };
+        bail!(
+            "{} is {len}, larger than the {max} limit for editing, so it is probably generated \
+             or data rather than source. Pass allow_large to edit it anyway.",
+            path.display(),
+        );
+    }
+    Ok(())
+}
+
+/// What kind of binary file `bytes` is, if it isn't text. UTF-16 has NUL bytes but is text, and
+/// is reported separately.
+fn binary_kind(bytes: &[u8]) -> Option<&'static str> {
+    if let Some((_, kind)) = MAGIC_NUMBERS
+        .iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+    {
+        return Some(kind);
+    }
+    let sniffed = &bytes[..bytes.len().min(BINARY_SNIFF_LENGTH)];
+    (!UTF16_BOMS.iter().any(|bom| bytes.starts_with(bom)) && sniffed.contains(&0))
+        .then_some("unknown format")
+}
+
+/// A file size in the largest unit that keeps it at least 1, like `12.5 KiB`
+fn size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} byte{}", if bytes == 1 { "" } else { "s" });
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(bytes: &[u8]) -> Result<(String, Encoding)> {
+        Encoding::decode(Path::new("file.txt"), bytes.to_vec())
+    }
+
+    #[test]
+    fn strips_and_restores_utf8_bom() {
+        let (text, encoding) = decode(b"\xEF\xBB\xBFkey = 1\n").unwrap();
+        assert!(encoding.bom);
+        assert_eq!(text, "key = 1\n");
+        assert_eq!(
+            encoding.encode("key = 2\n".into()).as_bytes(),
+            b"\xEF\xBB\xBFkey = 2\n"
+        );
+    }
+
+    #[test]
+    fn latin1_is_read_lossily() {
+        let (text, encoding) = decode(b"name = \"Caf\xE9\"\n").unwrap();
+        assert_eq!(text, "name = \"Caf\u{fffd}\"\n");
+        assert_eq!(encoding.invalid_utf8_at, Some(11));
+        assert!(encoding.read_only_reason().unwrap().contains("offset 11"));
+    }
+
+    #[test]
+    fn binary_files_are_described_not_decoded() {
+        let error = decode(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "file.txt is a binary file (PNG image, 16 bytes), not text, so it can't be previewed \
+             or edited. Edit the source it's generated from, or use a tool made for its format."
+        );
+
+        let mut unknown = b"\x01\x02\0\x03".repeat(1000);
+        unknown.extend(b"trailing text");
+        let error = decode(&unknown).unwrap_err().to_string();
+        assert!(error.contains("(unknown format, 3.9 KiB)"), "{error}");
+
+        assert!(decode(b"Makefile text\n").is_ok());
+    }
+
+    #[test]
+    fn files_over_the_size_limit_are_refused() {
+        let path = env::temp_dir().join(format!("semantic-edit-large-{}.js", std::process::id()));
+        fs::write(&path, "x".repeat(3 * 1024)).unwrap();
+        assert!(check_size_within(&path, 3 * 1024).is_ok());
+
+        let error = check_size_within(&path, 2 * 1024).unwrap_err().to_string();
+        assert!(
+            error.ends_with(
+                "is 3.0 KiB, larger than the 2.0 KiB limit for editing, so it is probably \
+                 generated or data rather than source. Pass allow_large to edit it anyway."
+            ),
+            "{error}"
+        );
+        assert!(check_size(&path, true).is_ok());
+
+        let error = check_size_within(&path, 3 * 1024 - 1)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("is 3072 bytes, larger than the 3071-byte limit"),
+            "{error}"
+        );
+
+        fs::remove_file(&path).unwrap();
+        assert!(check_size_within(&path, 0).is_ok());
+    }
+
+    #[test]
+    fn sizes_use_the_largest_whole_unit() {
+        assert_eq!(size(1), "1 byte");
+        assert_eq!(size(1023), "1023 bytes");
+        assert_eq!(size(DEFAULT_MAX_FILE_SIZE), "4.0 MiB");
+        assert_eq!(size(5 * 1024 * 1024 * 1024 / 2), "2.5 GiB");
+    }
+
+    #[test]
+    fn utf16_is_rejected() {
+        assert!(decode(b"\xFF\xFEk\0").is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! Fixes that compilers suggest along with their diagnostics, read from cargo's JSON messages or
+//! from the code fixes tsserver returns, which stage_fixes applies to a file
+
+use serde_json::Value;
+use std::ops::Range;
+
+/// A change a compiler suggests to fix a diagnostic, made of one or more replacements that must
+/// be applied together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixIt {
+    /// The file, as the compiler reported it
+    pub path: String,
+    /// The diagnostic and the suggestion, like "unused import: `std::fmt` (remove the whole
+    /// `use` item)"
+    pub message: String,
+    pub replacements: Vec<Replacement>,
+    /// Whether the compiler is sure the fix is what was meant, as with rustc's
+    /// `MachineApplicable`
+    pub certain: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    pub start: Position,
+    pub end: Position,
+    pub text: String,
+}
+
+/// Where a replacement starts or ends, in the terms its compiler uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    /// A byte offset, along with the 1-based line it was on, which notices files that changed
+    /// since they were checked
+    Byte { offset: usize, line: usize },
+    /// A 1-based line and a 1-based column counted in UTF-16 code units
+    Utf16 { line: usize, column: usize },
+}
+
+impl Position {
+    pub fn line(self) -> usize {
+        match self {
+            Position::Byte { line, .. } | Position::Utf16 { line, .. } => line,
+        }
+    }
+
+    /// The byte offset of this position in `source`, or None if it's no longer there
+    pub fn offset(self, source: &str) -> Option<usize> {
+        let line_start = |line: usize| match line {
+            0 => None,
+            1 => Some(0),
+            line => source
+                .match_indices('\n')
+                .nth(line - 2)
+                .map(|(index, _)| index + 1),
+        };
+        match self {
+            Position::Byte { offset, line } => {
+                let start = line_start(line)?;
+                let end = source[start..]
+                    .find('\n')
+                    .map_or(source.len(), |end| start + end);
+                ((start..=end).contains(&offset) && source.is_char_boundary(offset))
+                    .then_some(offset)
+            }
+            Position::Utf16 { line, column } => {
+                let start = line_start(line)?;
+                let mut units = 1;
+                for (index, c) in source[start..].char_indices() {
+                    if units >= column || c == '\n' {
+                        return (units == column).then_some(start + index);
+                    }
+                    units += c.len_utf16();
+                }
+                (units == column).then_some(source.len())
+            }
+        }
+    }
+}
+
+/// The byte ranges a fix replaces in a file, with their replacements
+pub type Resolved<'a> = Vec<(Range<usize>, &'a str)>;
+
+impl FixIt {
+    /// The byte ranges this fix replaces in `source`, with their replacements, or None if any of
+    /// them is no longer there
+    pub fn resolve<'a>(&'a self, source: &str) -> Option<Resolved<'a>> {
+        self.replacements
+            .iter()
+            .map(|replacement| {
+                let start = replacement.start.offset(source)?;
+                let end = replacement.end.offset(source)?;
+                (start <= end).then_some((start..end, replacement.text.as_str()))
+            })
+            .collect()
+    }
+}
+
+/// Read fixes from either format: the JSON lines `cargo check --message-format=json` prints, or
+/// tsserver's code fixes, as a response to `getCodeFixes` or the array in its body
+pub fn parse(diagnostics: &str) -> Vec<FixIt> {
+    let mut fixes = match serde_json::from_str::<Value>(diagnostics.trim()) {
+        Ok(value) if value.is_array() || value.get("body").is_some() => parse_tsserver(&value),
+        _ => parse_cargo(diagnostics),
+    };
+    let mut seen = vec![];
+    fixes.retain(|fix| {
+        let key = (fix.path.clone(), fix.replacements.clone());
+        let new = !seen.contains(&key);
+        seen.push(key);
+        new
+    });
+    fixes
+}
+
+/// The first suggestion of each compiler message that has one. Alternatives after the first,
+/// like other paths to import a name from, would conflict with it.
+fn parse_cargo(output: &str) -> Vec<FixIt> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|line| line["reason"] == "compiler-message")
+        .filter_map(|line| {
+            let message = &line["message"];
+            let text = message["message"].as_str()?;
+            std::iter::once(message)
+                .chain(message["children"].as_array().into_iter().flatten())
+                .find_map(|suggestion| cargo_fix(text, suggestion))
+        })
+        .collect()
+}
+
+fn cargo_fix(diagnostic: &str, suggestion: &Value) -> Option<FixIt> {
+    let spans = suggestion["spans"]
+        .as_array()?
+        .iter()
+        .filter(|span| span["suggested_replacement"].is_string())
+        .collect::<Vec<_>>();
+    let first = spans.first()?;
+    let certain = match first["suggestion_applicability"].as_str() {
+        Some("MachineApplicable") => true,
+        Some("HasPlaceholders") => return None,
+        _ => false,
+    };
+    let replacements = spans
+        .iter()
+        .map(|span| {
+            Some(Replacement {
+                start: Position::Byte {
+                    offset: span["byte_start"].as_u64()? as usize,
+                    line: span["line_start"].as_u64()? as usize,
+                },
+                end: Position::Byte {
+                    offset: span["byte_end"].as_u64()? as usize,
+                    line: span["line_end"].as_u64()? as usize,
+                },
+                text: span["suggested_replacement"].as_str()?.to_string(),
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let message = match (suggestion["message"].as_str(), replacements.as_slice()) {
+        (Some(help), _) if help == diagnostic => diagnostic.to_string(),
+        // helps like "replace it with" are followed by the replacement
+        (Some(help), [only]) if !only.text.is_empty() && !only.text.contains('\n') => {
+            format!("{diagnostic} ({help} `{}`)", only.text)
+        }
+        (Some(help), _) => format!("{diagnostic} ({help})"),
+        (None, _) => diagnostic.to_string(),
+    };
+    Some(FixIt {
+        path: first["file_name"].as_str()?.to_string(),
+        message,
+        replacements,
+        certain,
+    })
+}
+
+/// The changes each tsserver code fix makes, one fix for each file it changes
+fn parse_tsserver(value: &Value) -> Vec<FixIt> {
+    let actions = value.get("body").unwrap_or(value);
+    actions
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|action| {
+            let description = action["description"].as_str().unwrap_or("code fix");
+            action["changes"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(move |change| {
+                    let position = |position: &Value| {
+                        Some(Position::Utf16 {
+                            line: position["line"].as_u64()? as usize,
+                            column: position["offset"].as_u64()? as usize,
+                        })
+                    };
+                    let replacements = change["textChanges"]
+                        .as_array()?
+                        .iter()
+                        .map(|text_change| {
+                            Some(Replacement {
+                                start: position(&text_change["start"])?,
+                                end: position(&text_change["end"])?,
+                                text: text_change["newText"].as_str()?.to_string(),
+                            })
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    Some(FixIt {
+                        path: change["fileName"].as_str()?.to_string(),
+                        message: description.to_string(),
+                        replacements,
+                        certain: true,
+                    })
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "use std::fmt;\n\nfn main() {\n    return;\n}\n";
+
+    #[test]
+    fn cargo_suggestions() {
+        let output = r#"{"reason":"compiler-message","message":{"level":"warning","message":"unused import: `std::fmt`","spans":[{"file_name":"src/main.rs","byte_start":4,"byte_end":12,"line_start":1,"line_end":1,"is_primary":true,"suggested_replacement":null}],"children":[{"message":"`#[warn(unused_imports)]` on by default","spans":[]},{"message":"remove the whole `use` item","spans":[{"file_name":"src/main.rs","byte_start":0,"byte_end":15,"line_start":1,"line_end":3,"suggested_replacement":"","suggestion_applicability":"MachineApplicable"}]}]}}
+{"reason":"compiler-message","message":{"level":"warning","message":"unneeded `return` statement","spans":[],"children":[{"message":"remove `return`","spans":[{"file_name":"src/main.rs","byte_start":31,"byte_end":38,"line_start":4,"line_end":4,"suggested_replacement":"","suggestion_applicability":"MaybeIncorrect"}]}]}}
+{"reason":"compiler-message","message":{"level":"error","message":"cannot find value `x`","spans":[],"children":[{"message":"a local variable with a similar name exists","spans":[{"file_name":"src/main.rs","byte_start":31,"byte_end":32,"line_start":4,"line_end":4,"suggested_replacement":"y /* value */","suggestion_applicability":"HasPlaceholders"}]}]}}"#;
+        let fixes = parse(output);
+        assert_eq!(
+            fixes
+                .iter()
+                .map(|fix| (fix.message.as_str(), fix.certain))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "unused import: `std::fmt` (remove the whole `use` item)",
+                    true
+                ),
+                ("unneeded `return` statement (remove `return`)", false),
+            ]
+        );
+        assert_eq!(fixes[0].resolve(SOURCE), Some(vec![(0..15, "")]));
+        assert_eq!(fixes[1].resolve(SOURCE), Some(vec![(31..38, "")]));
+        assert_eq!(fixes[1].resolve(&SOURCE[15..]), None);
+    }
+
+    #[test]
+    fn tsserver_code_fixes() {
+        let response = r#"{"command":"getCodeFixes","body":[{"fixName":"unusedIdentifier","description":"Remove unused declaration for: 'a'","changes":[{"fileName":"/work/src/app.ts","textChanges":[{"start":{"line":2,"offset":3},"end":{"line":2,"offset":13},"newText":""}]}]}]}"#;
+        let fixes = parse(response);
+        assert_eq!(fixes.len(), 1);
+        assert_eq!(fixes[0].path, "/work/src/app.ts");
+        assert_eq!(
+            fixes[0].resolve("{\n  let a = 1;\n}\n"),
+            Some(vec![(4..14, "")])
+        );
+        assert_eq!(fixes[0].resolve("{}\n"), None);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{editor::apply_hunks, hooks::shell_quote, languages::LanguageCommon};
+use anyhow::{Result, anyhow};
+use diffy::DiffOptions;
+use std::{
+    env,
+    io::{Read, Write},
+    ops::Range,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// The formatter edits to a language's files are run through, configured with
+/// `SEMANTIC_EDIT_FORMATTER_<LANGUAGE>`. `off` leaves edits as they were written, and anything
+/// else is a shell command that reads the file on stdin and prints it formatted, in which
+/// `{file}` stands for the file. Unset, the language's built-in formatter is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Formatter {
+    BuiltIn,
+    Off,
+    Command(String),
+}
+
+impl Formatter {
+    pub fn configured(language: &LanguageCommon) -> Self {
+        Self::parse(env::var(Self::variable(language)).ok().as_deref())
+    }
+
+    /// The variable that configures the formatter for `language`, named for a grammar loaded at
+    /// runtime by its configured name
+    pub fn variable(language: &LanguageCommon) -> String {
+        format!(
+            "SEMANTIC_EDIT_FORMATTER_{}",
+            language.to_string().to_uppercase().replace('-', "_")
+        )
+    }
+
+    fn parse(configuration: Option<&str>) -> Self {
+        match configuration.map(str::trim) {
+            None | Some("") => Self::BuiltIn,
+            Some("off") => Self::Off,
+            Some(command) => Self::Command(command.to_string()),
+        }
+    }
+
+    /// The name of the formatter for reports, or None if edits aren't formatted
+    pub fn name(&self, language: &LanguageCommon) -> Option<String> {
+        match self {
+            Self::BuiltIn => language.editor().formatter().map(String::from),
+            Self::Off => None,
+            Self::Command(command) => command.split_whitespace().next().map(String::from),
+        }
+    }
+
+    pub fn format(
+        &self,
+        language: &LanguageCommon,
+        source: &str,
+        file_path: &Path,
+    ) -> Result<String> {
+        let command = match self {
+            Self::BuiltIn => return language.editor().format_code(source, file_path),
+            Self::Off => return Ok(source.to_string()),
+            Self::Command(command) => command,
+        };
+
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command.replace("{file}", &shell_quote(&file_path.to_string_lossy())))
+            .current_dir(file_path.parent().unwrap_or(Path::new(".")))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|error| anyhow!("`{command}` could not be started: {error}"))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(source.as_bytes())?;
+            drop(stdin);
+        }
+
+        let mut stdout = String::new();
+        if let Some(mut out) = child.stdout.take() {
+            out.read_to_string(&mut stdout)?;
+        }
+
+        let mut stderr = String::new();
+        if let Some(mut err) = child.stderr.take() {
+            err.read_to_string(&mut stderr)?;
+        }
+
+        if child.wait()?.success() {
+            Ok(stdout)
+        } else {
+            Err(anyhow!(stderr))
+        }
+    }
+}
+
+/// How much of a file formatting an edit changes, configured with `SEMANTIC_EDIT_FORMAT_SCOPE`.
+/// `edit` keeps only what the formatter changes on the lines the edit touches, so that edits to a
+/// file that was never formatted don't reformat all of it. Unset, or `file`, the whole file is
+/// formatted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatScope {
+    File,
+    Edit,
+}
+
+impl FormatScope {
+    pub fn configured() -> Result<Self> {
+        Self::parse(env::var("SEMANTIC_EDIT_FORMAT_SCOPE").ok().as_deref())
+    }
+
+    fn parse(configuration: Option<&str>) -> Result<Self> {
+        match configuration.map(str::trim) {
+            None | Some("") | Some("file") => Ok(Self::File),
+            Some("edit") => Ok(Self::Edit),
+            Some(other) => Err(anyhow!(
+                "SEMANTIC_EDIT_FORMAT_SCOPE is `{other}`, but can only be `file` or `edit`"
+            )),
+        }
+    }
+
+    /// `formatted`, the formatted `source`, with the formatter's changes outside the scope
+    /// reverted. `edited` is the byte range of `source` the edit wrote.
+    pub fn apply(self, source: &str, formatted: String, edited: Range<usize>) -> String {
+        if self == Self::File {
+            return formatted;
+        }
+        let line = |byte: usize| source[..byte.min(source.len())].matches('\n').count();
+        let edited_lines = line(edited.start)..line(edited.end) + 1;
+        let patch = DiffOptions::new()
+            .set_context_len(0)
+            .create_patch(source, &formatted);
+        let selected = patch
+            .hunks()
+            .iter()
+            .enumerate()
+            .filter(|(_, hunk)| {
+                let range = hunk.old_range();
+                if range.is_empty() {
+                    // lines added after the line `start`, counting from 1, or before the first
+                    (edited_lines.start..=edited_lines.end).contains(&range.start())
+                } else {
+                    let start = range.start() - 1;
+                    start < edited_lines.end && edited_lines.start < start + range.len()
+                }
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        apply_hunks(source, &patch, &selected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configuration() {
+        assert_eq!(Formatter::parse(None), Formatter::BuiltIn);
+        assert_eq!(Formatter::parse(Some(" off ")), Formatter::Off);
+        assert_eq!(
+            Formatter::parse(Some("prettier --stdin-filepath {file}")),
+            Formatter::Command("prettier --stdin-filepath {file}".into())
+        );
+        assert_eq!(FormatScope::parse(None).unwrap(), FormatScope::File);
+        assert_eq!(FormatScope::parse(Some("edit")).unwrap(), FormatScope::Edit);
+        assert!(FormatScope::parse(Some("region")).is_err());
+    }
+
+    #[test]
+    fn edit_scope_keeps_formatting_of_edited_lines() {
+        let source = "fn a(){}\n\nfn b(){\nlet x=1;\n}\n\nfn c(){}\n";
+        let formatted = "fn a() {}\n\nfn b() {\n    let x = 1;\n}\n\nfn c() {}\n";
+        // changes to lines next to the edit are one change with it
+        let edited = source.find("let").unwrap()..source.find("1;").unwrap();
+        assert_eq!(
+            FormatScope::Edit.apply(source, formatted.into(), edited.clone()),
+            "fn a(){}\n\nfn b() {\n    let x = 1;\n}\n\nfn c(){}\n"
+        );
+        assert_eq!(
+            FormatScope::File.apply(source, formatted.into(), edited),
+            formatted
+        );
+
+        // a blank line the formatter adds right after an edit belongs to it
+        let source = "a\nb\nc\n";
+        assert_eq!(
+            FormatScope::Edit.apply(source, "a\nb\n\nc\n".into(), 2..3),
+            "a\nb\n\nc\n"
+        );
+        assert_eq!(
+            FormatScope::Edit.apply(source, "a\nb\nc\n\n".into(), 2..3),
+            source
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    #[cfg(feature = "toml")]
+    fn commands_format_stdin() {
+        use crate::languages::{LanguageName, LanguageRegistry};
+
+        let registry = LanguageRegistry::new().unwrap();
+        let language = registry.get_language(LanguageName::Toml);
+        assert_eq!(
+            Formatter::variable(language),
+            "SEMANTIC_EDIT_FORMATTER_TOML"
+        );
+
+        let file = env::temp_dir().join("it's.toml");
+        let formatter = Formatter::Command("tr a-z A-Z; echo {file}".into());
+        assert_eq!(
+            formatter.format(language, "a = 1\n", &file).unwrap(),
+            format!("A = 1\n{}\n", file.display())
+        );
+        assert_eq!(formatter.name(language).as_deref(), Some("tr"));
+        assert_eq!(
+            Formatter::Off.format(language, "a=1", &file).unwrap(),
+            "a=1"
+        );
+        assert!(
+            Formatter::Command("exit 2".into())
+                .format(language, "", &file)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Front matter at the top of Markdown documents, a YAML block between `---` lines or a TOML
+//! block between `+++` lines, whose keys set_key edits with the grammar of its format
+
+use crate::languages::LanguageName;
+use std::ops::Range;
+
+/// The format of a front matter block, told apart by its delimiters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    pub fn language(self) -> LanguageName {
+        match self {
+            Format::Yaml => LanguageName::Yaml,
+            Format::Toml => LanguageName::Toml,
+        }
+    }
+
+    fn delimiter(self) -> &'static str {
+        match self {
+            Format::Yaml => "---",
+            Format::Toml => "+++",
+        }
+    }
+}
+
+/// A front matter block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontMatter {
+    pub format: Format,
+    /// The lines between the delimiters
+    pub range: Range<usize>,
+}
+
+/// The front matter `source` starts with, if it has any
+pub fn find(source: &str) -> Option<FrontMatter> {
+    let first = source.split_inclusive('\n').next()?;
+    let format = [Format::Yaml, Format::Toml]
+        .into_iter()
+        .find(|format| first.trim_end() == format.delimiter())?;
+    let start = first.len();
+    let mut offset = start;
+    for line in source[start..].split_inclusive('\n') {
+        let line_end = line.trim_end();
+        if line_end == format.delimiter() || (format == Format::Yaml && line_end == "...") {
+            return Some(FrontMatter {
+                format,
+                range: start..offset,
+            });
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// A YAML front matter block holding `content`, for a document that has none yet
+pub fn new_block(content: &str) -> String {
+    format!("---\n{content}\n---\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_front_matter() {
+        let source = "---\ntitle: Hello\ntags: [rust]\n---\n\n# Hello\n";
+        let front_matter = find(source).unwrap();
+        assert_eq!(front_matter.format, Format::Yaml);
+        assert_eq!(&source[front_matter.range], "title: Hello\ntags: [rust]\n");
+
+        let source = "+++\ntitle = \"Hello\"\n+++\nBody\n";
+        let front_matter = find(source).unwrap();
+        assert_eq!(front_matter.format, Format::Toml);
+        assert_eq!(&source[front_matter.range], "title = \"Hello\"\n");
+
+        assert_eq!(find("# Hello\n\n---\n"), None);
+        assert_eq!(find("---\ntitle: unterminated\n"), None);
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::VecDeque,
+    env,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// How far back persists are counted for `SEMANTIC_EDIT_MAX_PERSISTS_PER_MINUTE`
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits on how much may be written without explicit confirmation, so that an agent stuck in
+/// a loop can't rewrite a repository unchecked. Each is unlimited unless configured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// `SEMANTIC_EDIT_MAX_PERSISTS_PER_MINUTE`: writes in any sixty seconds
+    pub persists_per_minute: Option<usize>,
+    /// `SEMANTIC_EDIT_MAX_EDIT_BYTES`: bytes changed by one write, across all of its files
+    pub bytes_per_edit: Option<usize>,
+    /// `SEMANTIC_EDIT_MAX_BATCH_FILES`: files changed by one write
+    pub files_per_batch: Option<usize>,
+}
+
+impl Limits {
+    /// The limits set in the environment. Values that aren't numbers are ignored.
+    pub fn configured() -> Self {
+        let limit = |variable| env::var(variable).ok().and_then(|limit| limit.parse().ok());
+        Self {
+            persists_per_minute: limit("SEMANTIC_EDIT_MAX_PERSISTS_PER_MINUTE"),
+            bytes_per_edit: limit("SEMANTIC_EDIT_MAX_EDIT_BYTES"),
+            files_per_batch: limit("SEMANTIC_EDIT_MAX_BATCH_FILES"),
+        }
+    }
+}
+
+/// Enforces [`Limits`] on everything persisted by this process, whichever session it's for
+#[derive(Debug, Default)]
+pub struct Guardrails {
+    limits: Limits,
+    /// When recent writes happened, oldest first
+    persists: Mutex<VecDeque<Instant>>,
+}
+
+impl Guardrails {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            persists: Mutex::default(),
+        }
+    }
+
+    /// The limits that writing `files` files, changing `bytes` bytes in total, would exceed
+    /// now, described for the agent that asked for the write
+    pub fn exceeded(&self, files: usize, bytes: usize) -> Vec<String> {
+        let mut exceeded = vec![];
+        if let Some(max) = self.limits.persists_per_minute {
+            let recent = self.recent_persists(Instant::now());
+            if recent >= max {
+                exceeded.push(format!(
+                    "{recent} edit{} already persisted in the last minute, and \
+                     SEMANTIC_EDIT_MAX_PERSISTS_PER_MINUTE allows {max}",
+                    if recent == 1 { " was" } else { "s were" }
+                ));
+            }
+        }
+        if let Some(max) = self.limits.bytes_per_edit
+            && bytes > max
+        {
+            exceeded.push(format!(
+                "it changes {bytes} bytes, and SEMANTIC_EDIT_MAX_EDIT_BYTES allows {max}"
+            ));
+        }
+        if let Some(max) = self.limits.files_per_batch
+            && files > max
+        {
+            exceeded.push(format!(
+                "it changes {files} files, and SEMANTIC_EDIT_MAX_BATCH_FILES allows {max}"
+            ));
+        }
+        exceeded
+    }
+
+    /// Count a write against the rate limit
+    pub fn record_persist(&self) {
+        self.persists
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push_back(Instant::now());
+    }
+
+    fn recent_persists(&self, now: Instant) -> usize {
+        let mut persists = self.persists.lock().unwrap_or_else(PoisonError::into_inner);
+        while persists
+            .front()
+            .is_some_and(|persisted| now.duration_since(*persisted) >= RATE_WINDOW)
+        {
+            persists.pop_front();
+        }
+        persists.len()
+    }
+}
+
+/// Why a write is held back until it's confirmed, naming how to confirm it
+pub fn confirmation_required(exceeded: &[String], confirm: &str) -> String {
+    format!(
+        "Nothing was written because {}. Review the change, then {confirm} to write it anyway.",
+        exceeded.join("; ")
+    )
+}
+
+/// How many bytes differ between two versions of a file: the longer of the spans between their
+/// common prefix and common suffix
+pub fn changed_bytes(original: &[u8], edited: &[u8]) -> usize {
+    let prefix = original
+        .iter()
+        .zip(edited)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = original[prefix..]
+        .iter()
+        .rev()
+        .zip(edited[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (original.len() - prefix - suffix).max(edited.len() - prefix - suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_are_only_enforced_when_set() {
+        assert!(Guardrails::default().exceeded(1000, 1_000_000).is_empty());
+
+        let guardrails = Guardrails::new(Limits {
+            persists_per_minute: Some(2),
+            bytes_per_edit: Some(100),
+            files_per_batch: Some(3),
+        });
+        assert!(guardrails.exceeded(3, 100).is_empty());
+        assert_eq!(guardrails.exceeded(4, 101).len(), 2);
+
+        guardrails.record_persist();
+        assert!(guardrails.exceeded(1, 1).is_empty());
+        guardrails.record_persist();
+        assert_eq!(
+            guardrails.exceeded(1, 1),
+            ["2 edits were already persisted in the last minute, and \
+              SEMANTIC_EDIT_MAX_PERSISTS_PER_MINUTE allows 2"]
+        );
+        assert_eq!(guardrails.recent_persists(Instant::now() + RATE_WINDOW), 0);
+    }
+
+    #[test]
+    fn changed_bytes_spans_the_difference() {
+        assert_eq!(changed_bytes(b"fn a() {}", b"fn a() {}"), 0);
+        assert_eq!(changed_bytes(b"fn a() {}", b"fn abc() {}"), 2);
+        assert_eq!(changed_bytes(b"abcdef", b"aXcdeY"), 5);
+        assert_eq!(changed_bytes(b"", b"hello"), 5);
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::{Result, anyhow};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Lines of output shown for each failing command
+const OUTPUT_LINES: usize = 20;
+
+/// Commands run on a file after an edit is persisted, configured with `SEMANTIC_EDIT_PRE_COMMIT`.
+/// `on` runs the repository's [pre-commit](https://pre-commit.com) hooks on the file; anything
+/// else is a `;`-separated list of shell commands in which `{file}` stands for the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hooks {
+    commands: Vec<String>,
+}
+
+/// A command that exited unsuccessfully
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookFailure {
+    pub command: String,
+    pub status: Option<i32>,
+    /// The end of the command's combined output
+    pub output: String,
+}
+
+impl Hooks {
+    pub fn configured() -> Option<Self> {
+        Self::parse(&env::var("SEMANTIC_EDIT_PRE_COMMIT").ok()?)
+    }
+
+    fn parse(configuration: &str) -> Option<Self> {
+        let commands = match configuration.trim() {
+            "" | "off" => return None,
+            "on" => vec!["pre-commit run --files {file}".to_string()],
+            commands => commands
+                .split(';')
+                .map(str::trim)
+                .filter(|command| !command.is_empty())
+                .map(String::from)
+                .collect(),
+        };
+        Some(Self { commands })
+    }
+
+    /// Run every command on a file from the root of its repository, returning those that fail
+    pub fn run(&self, file: &Path) -> Result<Vec<HookFailure>> {
+        let root = repository_root(file);
+        let relative = file.strip_prefix(&root).unwrap_or(file);
+        let quoted = shell_quote(&relative.to_string_lossy());
+
+        let mut failures = vec![];
+        for command in &self.commands {
+            let output = Command::new("sh")
+                .arg("-c")
+                .arg(command.replace("{file}", &quoted))
+                .current_dir(&root)
+                .output()
+                .map_err(|error| anyhow!("`{command}` could not be started: {error}"))?;
+            if output.status.success() {
+                continue;
+            }
+
+            let combined = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            let lines = combined.trim().lines().collect::<Vec<_>>();
+            failures.push(HookFailure {
+                command: command.clone(),
+                status: output.status.code(),
+                output: lines[lines.len().saturating_sub(OUTPUT_LINES)..].join("\n"),
+            });
+        }
+        Ok(failures)
+    }
+}
+
+/// The root of the git repository containing a file, where hooks expect to run, or the file's
+/// directory outside of a repository
+fn repository_root(file: &Path) -> PathBuf {
+    let directory = file.parent().unwrap_or(file);
+    directory
+        .ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .unwrap_or(directory)
+        .to_path_buf()
+}
+
+pub fn shell_quote(argument: &str) -> String {
+    format!("'{}'", argument.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn configuration() {
+        assert_eq!(Hooks::parse("off"), None);
+        assert_eq!(
+            Hooks::parse("on").unwrap().commands,
+            ["pre-commit run --files {file}"]
+        );
+        assert_eq!(
+            Hooks::parse("cargo fmt --check; ruff check {file};")
+                .unwrap()
+                .commands,
+            ["cargo fmt --check", "ruff check {file}"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_commands_are_reported() {
+        let dir = env::temp_dir().join(format!("semantic-edit-hooks-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        let file = dir.join("src/it's.rs");
+        fs::write(&file, "fn main() {}\n").unwrap();
+
+        let hooks =
+            Hooks::parse("test -f {file}; echo checking {file} && echo bad style >&2 && exit 3")
+                .unwrap();
+        assert_eq!(
+            hooks.run(&file).unwrap(),
+            [HookFailure {
+                command: "echo checking {file} && echo bad style >&2 && exit 3".into(),
+                status: Some(3),
+                output: "checking src/it's.rs\nbad style".into(),
+            }]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
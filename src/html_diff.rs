@@ -0,0 +1,228 @@
+use crate::languages::LanguageCommon;
+use diffy::{DiffOptions, Line};
+use std::fmt::Write;
+use tree_sitter::Node;
+
+/// Render the change from `original` to `edited` as a standalone HTML page, with the two versions
+/// side by side and highlighted using `language`'s grammar, for people reviewing an edit in a
+/// browser
+pub fn render(title: &str, language: &LanguageCommon, original: &str, edited: &str) -> String {
+    let old = highlighted_lines(language, original);
+    let new = highlighted_lines(language, edited);
+    let patch = DiffOptions::new().create_patch(original, edited);
+
+    let mut rows = String::new();
+    for hunk in patch.hunks() {
+        let (mut old_line, mut new_line) = (start(hunk.old_range()), start(hunk.new_range()));
+        let _ = writeln!(
+            rows,
+            r#"<tr class="hunk"><td colspan="4">⋯ lines {} to {}</td></tr>"#,
+            old_line + 1,
+            old_line + hunk.old_range().len()
+        );
+        let (mut deleted, mut inserted) = (vec![], vec![]);
+        for line in hunk.lines() {
+            match line {
+                Line::Delete(_) => {
+                    deleted.push(old_line);
+                    old_line += 1;
+                }
+                Line::Insert(_) => {
+                    inserted.push(new_line);
+                    new_line += 1;
+                }
+                Line::Context(_) => {
+                    push_change(&mut rows, &old, &new, &mut deleted, &mut inserted);
+                    let _ = writeln!(
+                        rows,
+                        "<tr>{}{}</tr>",
+                        cells(&old, Some(old_line), ""),
+                        cells(&new, Some(new_line), "")
+                    );
+                    old_line += 1;
+                    new_line += 1;
+                }
+            }
+        }
+        push_change(&mut rows, &old, &new, &mut deleted, &mut inserted);
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; margin: 1.5em; color: #1f2328; }}
+table {{ border-collapse: collapse; width: 100%; table-layout: fixed; }}
+td {{ font-family: ui-monospace, monospace; font-size: 13px; white-space: pre-wrap; vertical-align: top; padding: 0 0.5em; }}
+td.number {{ width: 3.5em; text-align: right; color: #6e7781; user-select: none; }}
+tr.hunk td {{ background: #ddf4ff; color: #57606a; padding: 0.25em 0.5em; }}
+.deleted {{ background: #ffebe9; }}
+.inserted {{ background: #e6ffec; }}
+.comment {{ color: #6e7781; font-style: italic; }}
+.string {{ color: #0a3069; }}
+.number-literal {{ color: #0550ae; }}
+.keyword {{ color: #cf222e; }}
+.type {{ color: #953800; }}
+.function {{ color: #8250df; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<table>
+{rows}</table>
+</body>
+</html>
+"#,
+        title = escape(title)
+    )
+}
+
+/// The zero-based line a hunk range starts at. Unified diff ranges count from 1, except that an
+/// empty range names the line before it.
+fn start(range: diffy::HunkRange) -> usize {
+    if range.is_empty() {
+        range.start()
+    } else {
+        range.start() - 1
+    }
+}
+
+/// Pair the lines deleted since the last unchanged one with the lines inserted in their place,
+/// one row each
+fn push_change(
+    rows: &mut String,
+    old: &[String],
+    new: &[String],
+    deleted: &mut Vec<usize>,
+    inserted: &mut Vec<usize>,
+) {
+    for row in 0..deleted.len().max(inserted.len()) {
+        let _ = writeln!(
+            rows,
+            "<tr>{}{}</tr>",
+            cells(old, deleted.get(row).copied(), "deleted"),
+            cells(new, inserted.get(row).copied(), "inserted")
+        );
+    }
+    deleted.clear();
+    inserted.clear();
+}
+
+fn cells(lines: &[String], line: Option<usize>, class: &str) -> String {
+    match line {
+        Some(line) => format!(
+            r#"<td class="number {class}">{}</td><td class="{class}">{}</td>"#,
+            line + 1,
+            lines.get(line).map_or("", String::as_str)
+        ),
+        None => r#"<td class="number"></td><td></td>"#.to_string(),
+    }
+}
+
+/// The lines of `text` as escaped HTML, with tokens wrapped in spans by what kind of syntax they
+/// are, as far as can be told from node kinds in any grammar
+fn highlighted_lines(language: &LanguageCommon, text: &str) -> Vec<String> {
+    let mut classes = vec![None; text.len()];
+    if let Ok(mut parser) = language.tree_sitter_parser()
+        && let Some(tree) = parser.parse(text, None)
+    {
+        classify(tree.root_node(), &mut classes);
+    }
+
+    let mut lines = vec![];
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let mut html = String::new();
+        let mut current = None;
+        for (index, character) in content.char_indices() {
+            let class = classes[offset + index];
+            if class != current {
+                if current.is_some() {
+                    html.push_str("</span>");
+                }
+                if let Some(class) = class {
+                    let _ = write!(html, r#"<span class="{class}">"#);
+                }
+                current = class;
+            }
+            html.push_str(&escape(character.encode_utf8(&mut [0; 4])));
+        }
+        if current.is_some() {
+            html.push_str("</span>");
+        }
+        lines.push(html);
+        offset += line.len();
+    }
+    lines
+}
+
+fn classify(node: Node, classes: &mut [Option<&'static str>]) {
+    let kind = node.kind();
+    let class = if kind.contains("comment") {
+        Some("comment")
+    } else if kind.contains("string") || kind.contains("char_literal") {
+        Some("string")
+    } else if kind.contains("integer") || kind.contains("float") || kind == "number" {
+        Some("number-literal")
+    } else if kind.contains("type_identifier") || kind == "primitive_type" {
+        Some("type")
+    } else if !node.is_named() && kind.chars().all(|c| c.is_ascii_alphabetic() || c == '_') {
+        Some("keyword")
+    } else if kind == "identifier"
+        && node.parent().is_some_and(|parent| {
+            parent.kind().contains("function") && parent.child_by_field_name("name") == Some(node)
+        })
+    {
+        Some("function")
+    } else {
+        None
+    };
+
+    match class {
+        Some(class) => classes[node.byte_range()].fill(Some(class)),
+        None => {
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                classify(child, classes);
+            }
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(all(test, feature = "rust"))]
+mod tests {
+    use super::*;
+    use crate::languages::{LanguageName, LanguageRegistry};
+
+    #[test]
+    fn changes_are_shown_side_by_side_and_highlighted() {
+        let registry = LanguageRegistry::new().unwrap();
+        let rust = registry.get_language(LanguageName::Rust);
+        let html = render(
+            "src/lib.rs",
+            rust,
+            "// sum\nfn add(a: u32) -> u32 {\n    a + 1\n}\n",
+            "// sum\nfn add(a: u32) -> u32 {\n    a + 2 // <more>\n}\n",
+        );
+
+        assert!(html.contains("<title>src/lib.rs</title>"));
+        assert!(html.contains(
+            r#"<td class="number deleted">3</td><td class="deleted">    a + <span class="number-literal">1</span></td><td class="number inserted">3</td>"#
+        ));
+        assert!(html.contains(r#"<span class="comment">// &lt;more&gt;</span>"#));
+        assert!(
+            html.contains(r#"<span class="keyword">fn</span> <span class="function">add</span>"#)
+        );
+    }
+}
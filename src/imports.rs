@@ -0,0 +1,710 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+use tree_sitter::{Node, Tree};
+
+/// Lines longer than this are wrapped in parentheses with one name per line, as black and ruff
+/// would format them
+const LINE_LENGTH: usize = 88;
+
+/// Top-level modules of the Python standard library, sorted, from `sys.stdlib_module_names`
+const STANDARD_LIBRARY: &[&str] = &[
+    "__future__",
+    "_thread",
+    "abc",
+    "aifc",
+    "argparse",
+    "array",
+    "ast",
+    "asynchat",
+    "asyncio",
+    "asyncore",
+    "atexit",
+    "audioop",
+    "base64",
+    "bdb",
+    "binascii",
+    "bisect",
+    "builtins",
+    "bz2",
+    "cProfile",
+    "calendar",
+    "cgi",
+    "cgitb",
+    "chunk",
+    "cmath",
+    "cmd",
+    "code",
+    "codecs",
+    "codeop",
+    "collections",
+    "colorsys",
+    "compileall",
+    "concurrent",
+    "configparser",
+    "contextlib",
+    "contextvars",
+    "copy",
+    "copyreg",
+    "crypt",
+    "csv",
+    "ctypes",
+    "curses",
+    "dataclasses",
+    "datetime",
+    "dbm",
+    "decimal",
+    "difflib",
+    "dis",
+    "doctest",
+    "email",
+    "encodings",
+    "ensurepip",
+    "enum",
+    "errno",
+    "faulthandler",
+    "fcntl",
+    "filecmp",
+    "fileinput",
+    "fnmatch",
+    "fractions",
+    "ftplib",
+    "functools",
+    "gc",
+    "getopt",
+    "getpass",
+    "gettext",
+    "glob",
+    "graphlib",
+    "grp",
+    "gzip",
+    "hashlib",
+    "heapq",
+    "hmac",
+    "html",
+    "http",
+    "idlelib",
+    "imaplib",
+    "imghdr",
+    "importlib",
+    "inspect",
+    "io",
+    "ipaddress",
+    "itertools",
+    "json",
+    "keyword",
+    "lib2to3",
+    "linecache",
+    "locale",
+    "logging",
+    "lzma",
+    "mailbox",
+    "mailcap",
+    "marshal",
+    "math",
+    "mimetypes",
+    "mmap",
+    "modulefinder",
+    "msilib",
+    "msvcrt",
+    "multiprocessing",
+    "netrc",
+    "nis",
+    "nntplib",
+    "ntpath",
+    "numbers",
+    "opcode",
+    "operator",
+    "optparse",
+    "os",
+    "ossaudiodev",
+    "pathlib",
+    "pdb",
+    "pickle",
+    "pickletools",
+    "pipes",
+    "pkgutil",
+    "platform",
+    "plistlib",
+    "poplib",
+    "posix",
+    "posixpath",
+    "pprint",
+    "profile",
+    "pstats",
+    "pty",
+    "pwd",
+    "py_compile",
+    "pyclbr",
+    "pydoc",
+    "queue",
+    "quopri",
+    "random",
+    "re",
+    "readline",
+    "reprlib",
+    "resource",
+    "rlcompleter",
+    "runpy",
+    "sched",
+    "secrets",
+    "select",
+    "selectors",
+    "shelve",
+    "shlex",
+    "shutil",
+    "signal",
+    "site",
+    "smtplib",
+    "sndhdr",
+    "socket",
+    "socketserver",
+    "spwd",
+    "sqlite3",
+    "sre_compile",
+    "sre_constants",
+    "sre_parse",
+    "ssl",
+    "stat",
+    "statistics",
+    "string",
+    "stringprep",
+    "struct",
+    "subprocess",
+    "sunau",
+    "symtable",
+    "sys",
+    "sysconfig",
+    "syslog",
+    "tabnanny",
+    "tarfile",
+    "telnetlib",
+    "tempfile",
+    "termios",
+    "textwrap",
+    "threading",
+    "time",
+    "timeit",
+    "tkinter",
+    "token",
+    "tokenize",
+    "tomllib",
+    "trace",
+    "traceback",
+    "tracemalloc",
+    "tty",
+    "turtle",
+    "types",
+    "typing",
+    "unicodedata",
+    "unittest",
+    "urllib",
+    "uu",
+    "uuid",
+    "venv",
+    "warnings",
+    "wave",
+    "weakref",
+    "webbrowser",
+    "winreg",
+    "winsound",
+    "wsgiref",
+    "xdrlib",
+    "xml",
+    "xmlrpc",
+    "zipapp",
+    "zipfile",
+    "zipimport",
+    "zlib",
+    "zoneinfo",
+];
+
+/// isort's sections, in the order they are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Section {
+    Future,
+    Standard,
+    ThirdParty,
+    FirstParty,
+    Local,
+}
+
+/// One import statement, or one module of an `import a, b` statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Import {
+    module: String,
+    /// The names imported from the module with their aliases, or None for `import module`
+    names: Option<BTreeSet<(String, Option<String>)>>,
+    /// The alias of `import module as alias`
+    alias: Option<String>,
+    /// Comment lines directly above the statement
+    comments: Vec<String>,
+    /// A comment at the end of the statement's line
+    trailing: Option<String>,
+}
+
+/// A Python file's imports, sorted and grouped the way isort does
+#[derive(Debug)]
+pub struct Organized {
+    /// The part of the file holding its leading import statements, or the empty range where
+    /// they belong if it has none
+    pub range: Range<usize>,
+    /// What to replace the range with
+    pub content: String,
+}
+
+/// Organize the import statements at the top of `source`, adding those in `added`, an import
+/// statement's parse tree, first. Imports below the first code that isn't an import are left
+/// where they are.
+pub fn organize(
+    tree: &Tree,
+    source: &str,
+    added: Option<(&Tree, &str)>,
+    file_path: &Path,
+) -> Result<Organized, String> {
+    let root = tree.root_node();
+    let children = root.named_children(&mut root.walk()).collect::<Vec<_>>();
+    let first = children.iter().position(|node| is_import(*node));
+    let last = first.map(|first| {
+        first
+            + children[first..]
+                .iter()
+                .take_while(|node| is_import(**node) || node.kind() == "comment")
+                .enumerate()
+                .filter(|(_, node)| is_import(**node))
+                .map(|(index, _)| index)
+                .last()
+                .unwrap_or_default()
+    });
+
+    let mut imports: Vec<Import> = vec![];
+    let mut loose_comments = vec![];
+    let range = match first.zip(last) {
+        Some((first, mut last)) => {
+            // comments above the first import stay at the top of the file, but one after the
+            // last import on its line belongs to it
+            if children.get(last + 1).is_some_and(|node| {
+                node.kind() == "comment"
+                    && node.start_position().row == children[last].end_position().row
+            }) {
+                last += 1;
+            }
+
+            let mut pending: Vec<Node> = vec![];
+            let mut import_row = None;
+            for node in &children[first..=last] {
+                if node.kind() != "comment" {
+                    // comments separated from the import by a blank line aren't about it
+                    let attached = (0..pending.len())
+                        .rev()
+                        .find(|index| {
+                            let next = pending.get(index + 1).unwrap_or(node);
+                            pending[*index].end_position().row + 1 < next.start_position().row
+                        })
+                        .map_or(0, |index| index + 1);
+                    loose_comments.extend(
+                        pending
+                            .drain(..attached)
+                            .map(|comment| text(source, comment).to_string()),
+                    );
+                    let comments = pending
+                        .drain(..)
+                        .map(|comment| text(source, comment).to_string())
+                        .collect();
+                    imports.extend(parse(*node, source, comments)?);
+                    import_row = Some(node.end_position().row);
+                } else if import_row == Some(node.start_position().row)
+                    && let Some(import) = imports.last_mut()
+                {
+                    import.trailing = Some(text(source, *node).to_string());
+                } else {
+                    pending.push(*node);
+                }
+            }
+            loose_comments.extend(
+                pending
+                    .drain(..)
+                    .map(|comment| text(source, comment).to_string()),
+            );
+            children[first].start_byte()..children[last].end_byte()
+        }
+        None => {
+            let start = insertion_point(&children, source);
+            start..start
+        }
+    };
+
+    if let Some((tree, added_source)) = added {
+        let root = tree.root_node();
+        for node in root.named_children(&mut root.walk()) {
+            if node.kind() == "comment" {
+                continue;
+            }
+            if !is_import(node) {
+                return Err(format!(
+                    "`{}` isn't an import statement",
+                    text(added_source, node).trim()
+                ));
+            }
+            imports.extend(parse(node, added_source, vec![])?);
+        }
+    }
+
+    let first_party = first_party(file_path);
+    let content = render(merge(imports), &loose_comments, &first_party);
+    Ok(Organized {
+        content: if range.is_empty() {
+            insertion(source, range.start, &content)
+        } else {
+            content
+        },
+        range,
+    })
+}
+
+fn is_import(node: Node<'_>) -> bool {
+    matches!(
+        node.kind(),
+        "import_statement" | "import_from_statement" | "future_import_statement"
+    )
+}
+
+fn text<'a>(source: &'a str, node: Node<'_>) -> &'a str {
+    &source[node.byte_range()]
+}
+
+/// The imports an import statement makes, one per module
+fn parse(node: Node<'_>, source: &str, comments: Vec<String>) -> Result<Vec<Import>, String> {
+    let name_and_alias = |node: Node<'_>| match node.kind() {
+        "aliased_import" => (
+            node.child_by_field_name("name")
+                .map_or("", |name| text(source, name))
+                .to_string(),
+            node.child_by_field_name("alias")
+                .map(|alias| text(source, alias).to_string()),
+        ),
+        _ => (text(source, node).to_string(), None),
+    };
+    let names = |node: Node<'_>, skip: Option<Node<'_>>| {
+        node.children_by_field_name("name", &mut node.walk())
+            .filter(|name| Some(*name) != skip)
+            .map(name_and_alias)
+            .collect::<BTreeSet<_>>()
+    };
+
+    match node.kind() {
+        "import_statement" => Ok(names(node, None)
+            .into_iter()
+            .enumerate()
+            .map(|(index, (module, alias))| Import {
+                module,
+                names: None,
+                alias,
+                comments: if index == 0 { comments.clone() } else { vec![] },
+                trailing: None,
+            })
+            .collect()),
+        "future_import_statement" => Ok(vec![Import {
+            module: "__future__".into(),
+            names: Some(names(node, None)),
+            alias: None,
+            comments,
+            trailing: None,
+        }]),
+        _ => {
+            let module = node
+                .child_by_field_name("module_name")
+                .ok_or_else(|| format!("Unable to read `{}`", text(source, node)))?;
+            let mut names = names(node, Some(module));
+            if node
+                .named_children(&mut node.walk())
+                .any(|child| child.kind() == "wildcard_import")
+            {
+                names.insert(("*".into(), None));
+            }
+            Ok(vec![Import {
+                module: text(source, module).to_string(),
+                names: Some(names),
+                alias: None,
+                comments,
+                trailing: None,
+            }])
+        }
+    }
+}
+
+/// Combine imports of the same module and drop duplicates
+fn merge(imports: Vec<Import>) -> Vec<Import> {
+    let mut merged: Vec<Import> = vec![];
+    for import in imports {
+        let existing = merged.iter_mut().find(|existing| {
+            existing.module == import.module
+                && existing.names.is_some() == import.names.is_some()
+                && (import.names.is_some() || existing.alias == import.alias)
+        });
+        match existing {
+            Some(existing) => {
+                existing.comments.extend(import.comments);
+                if existing.trailing.is_none() {
+                    existing.trailing = import.trailing;
+                }
+                if let (Some(names), Some(more)) = (&mut existing.names, import.names) {
+                    names.extend(more);
+                }
+            }
+            None => merged.push(import),
+        }
+    }
+    merged
+}
+
+fn section(module: &str, first_party: &BTreeSet<String>) -> Section {
+    let top = module.split('.').next().unwrap_or_default();
+    if module == "__future__" {
+        Section::Future
+    } else if module.starts_with('.') {
+        Section::Local
+    } else if STANDARD_LIBRARY.binary_search(&top).is_ok() {
+        Section::Standard
+    } else if first_party.contains(top) {
+        Section::FirstParty
+    } else {
+        Section::ThirdParty
+    }
+}
+
+/// Names sort like isort's `order_by_type`: constants, then classes, then everything else
+fn name_order(name: &str) -> (u8, String) {
+    let rank = if name.len() > 1 && name.chars().all(|c| !c.is_lowercase()) {
+        0
+    } else if name.starts_with(char::is_uppercase) {
+        1
+    } else {
+        2
+    };
+    (rank, name.to_lowercase())
+}
+
+fn render(
+    mut imports: Vec<Import>,
+    loose_comments: &[String],
+    first_party: &BTreeSet<String>,
+) -> String {
+    // within a section, `import x` comes before `from x import y`
+    imports.sort_by_cached_key(|import| {
+        (
+            section(&import.module, first_party),
+            import.names.is_some(),
+            import.module.to_lowercase(),
+            import.alias.clone(),
+        )
+    });
+
+    let mut lines = loose_comments.to_vec();
+    let mut previous = None;
+    for import in imports {
+        let section = section(&import.module, first_party);
+        if previous.is_some_and(|previous| previous != section) {
+            lines.push(String::new());
+        }
+        previous = Some(section);
+        lines.extend(import.comments);
+
+        let trailing = import
+            .trailing
+            .map(|comment| format!("  {comment}"))
+            .unwrap_or_default();
+        let Some(names) = import.names else {
+            lines.push(match import.alias {
+                Some(alias) => format!("import {} as {alias}{trailing}", import.module),
+                None => format!("import {}{trailing}", import.module),
+            });
+            continue;
+        };
+
+        let mut names = names
+            .into_iter()
+            .map(|(name, alias)| match alias {
+                Some(alias) => (name_order(&name), format!("{name} as {alias}")),
+                None => (name_order(&name), name),
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        let names = names.into_iter().map(|(_, name)| name).collect::<Vec<_>>();
+        let line = format!("from {} import {}", import.module, names.join(", "));
+        if line.len() + trailing.len() <= LINE_LENGTH {
+            lines.push(format!("{line}{trailing}"));
+        } else {
+            lines.push(format!("from {} import ({trailing}", import.module));
+            lines.extend(names.iter().map(|name| format!("    {name},")));
+            lines.push(")".into());
+        }
+    }
+    lines.join("\n")
+}
+
+/// Where imports go in a file without any: after a shebang, leading comments, and the module's
+/// docstring
+fn insertion_point(children: &[Node<'_>], source: &str) -> usize {
+    let mut offset = 0;
+    let mut docstring = false;
+    for node in children {
+        if node.kind() != "comment" {
+            let is_docstring = node.kind() == "expression_statement"
+                && node
+                    .named_child(0)
+                    .is_some_and(|child| child.kind() == "string");
+            if docstring || !is_docstring {
+                break;
+            }
+            docstring = true;
+        }
+        offset = source[node.end_byte()..]
+            .find('\n')
+            .map_or(source.len(), |index| node.end_byte() + index + 1);
+    }
+    offset
+}
+
+/// `content` with the blank lines that set it apart from what comes before and after it at
+/// `offset`
+fn insertion(source: &str, offset: usize, content: &str) -> String {
+    let before = if offset == 0 || source[..offset].ends_with("\n\n") {
+        ""
+    } else if source[..offset].ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    let after = if source[offset..].trim().is_empty() {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    format!("{before}{content}{after}")
+}
+
+/// The top-level modules and packages of the project `file_path` is in: those next to it, in
+/// the directories above it up to the project's root, and in the root's `src` directory
+fn first_party(file_path: &Path) -> BTreeSet<String> {
+    let mut directories: Vec<PathBuf> = vec![];
+    for directory in file_path.ancestors().skip(1) {
+        directories.push(directory.to_path_buf());
+        if ["pyproject.toml", "setup.py", "setup.cfg", ".git"]
+            .iter()
+            .any(|marker| directory.join(marker).exists())
+        {
+            directories.push(directory.join("src"));
+            break;
+        }
+    }
+
+    directories
+        .iter()
+        .filter_map(|directory| fs::read_dir(directory).ok())
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = if path.is_dir() {
+                path.file_name()?.to_str()?
+            } else if path.extension().is_some_and(|extension| extension == "py") {
+                path.file_stem()?.to_str()?
+            } else {
+                return None;
+            };
+            (!name.starts_with('.')).then(|| name.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_standard_library_list_is_sorted() {
+        assert!(STANDARD_LIBRARY.is_sorted());
+    }
+
+    #[cfg(feature = "python")]
+    fn organized(source: &str, added: Option<&str>) -> String {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_python::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let added = added.map(|added| (parser.parse(added, None).unwrap(), added));
+        let organized = organize(
+            &tree,
+            source,
+            added.as_ref().map(|(tree, added)| (tree, *added)),
+            Path::new("/nonexistent/project/app/views.py"),
+        )
+        .unwrap();
+        format!(
+            "{}{}{}",
+            &source[..organized.range.start],
+            organized.content,
+            &source[organized.range.end..]
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "python")]
+    fn imports_are_grouped_like_isort() {
+        let source = r#""""Views."""
+
+import requests
+from typing import Optional
+import os, sys
+from .models import User  # the user model
+from __future__ import annotations
+
+# for typing
+from typing import Any, TYPE_CHECKING, Optional
+import requests
+
+app = None
+"#;
+        assert_eq!(
+            organized(source, None),
+            r#""""Views."""
+
+from __future__ import annotations
+
+import os
+import sys
+# for typing
+from typing import TYPE_CHECKING, Any, Optional
+
+import requests
+
+from .models import User  # the user model
+
+app = None
+"#
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "python")]
+    fn imports_are_added_where_they_belong() {
+        let source = "#!/usr/bin/env python3\n\"\"\"A script.\"\"\"\nprint(1)\n";
+        assert_eq!(
+            organized(source, Some("import json")),
+            "#!/usr/bin/env python3\n\"\"\"A script.\"\"\"\n\nimport json\n\nprint(1)\n"
+        );
+
+        let long = "from collections import OrderedDict, defaultdict, deque, namedtuple, Counter, \
+                    ChainMap, UserDict";
+        assert_eq!(
+            organized("import os\n", Some(long)),
+            "import os\nfrom collections import (\n    ChainMap,\n    Counter,\n    OrderedDict,\n    \
+             UserDict,\n    defaultdict,\n    deque,\n    namedtuple,\n)\n"
+        );
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(any(feature = "python", feature = "yaml"))]
+use crate::editor::Edit;
 use std::{
     borrow::Cow,
     collections::BTreeMap,
@@ -70,6 +72,7 @@ impl Indentation {
             .map(|(spaces, _)| spaces)
     }
 
+    #[cfg(any(feature = "python", feature = "yaml"))]
     pub fn minimum(&self, source: &str) -> usize {
         source
             .lines()
@@ -135,7 +138,11 @@ impl Indentation {
                 count: new_units,
             };
             let line = line.trim_start();
-            writeln!(&mut string, "{new_indentation}{line}").unwrap();
+            if line.is_empty() {
+                string.push('\n');
+            } else {
+                writeln!(&mut string, "{new_indentation}{line}").unwrap();
+            }
         }
 
         if !content.ends_with('\n') {
@@ -180,3 +187,54 @@ impl Indentation {
     //     }
     // }
 }
+
+/// Reindent an edit's content to the indentation of the nodes it's placed by, starting it at
+/// the beginning of its line when only whitespace comes before it, for languages where
+/// indentation is structure
+#[cfg(any(feature = "python", feature = "yaml"))]
+pub(crate) fn adjust(edit: &mut Edit<'_, '_>, default: Indentation) {
+    let source_code = edit.source_code();
+    let mut start_byte = edit.start_byte();
+
+    let line_start = find_line_start(source_code, start_byte);
+
+    let line_end = source_code[start_byte..]
+        .find('\n')
+        .map(|newline| start_byte + newline)
+        .unwrap_or(source_code.len());
+
+    // Detect the file's indentation style
+    let file_indentation = Indentation::determine(source_code).unwrap_or(default);
+
+    let reference_region = if let Some(nodes) = edit.nodes()
+        && let Some(first) = nodes.first()
+        && let Some(last) = nodes.last()
+    {
+        let line_start = find_line_start(source_code, first.start_byte());
+
+        &source_code[line_start..last.end_byte()]
+    } else {
+        &source_code[line_start..line_end]
+    };
+
+    let target_indentation_count = file_indentation.minimum(reference_region);
+
+    if source_code[line_start..start_byte].trim().is_empty() {
+        start_byte = line_start;
+    }
+    file_indentation.reindent(
+        target_indentation_count,
+        edit.content_mut(),
+        start_byte == line_start,
+    );
+
+    edit.set_start_byte(start_byte);
+}
+
+#[cfg(any(feature = "python", feature = "yaml"))]
+pub(crate) fn find_line_start(source_code: &str, start_byte: usize) -> usize {
+    source_code[..start_byte]
+        .rfind('\n')
+        .map(|pos| pos + 1) // +1 to get position after the newline
+        .unwrap_or(0) // If no newline found, start of file
+}
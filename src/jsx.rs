@@ -0,0 +1,272 @@
+use std::ops::Range;
+use tree_sitter::{Node, Tree};
+
+/// A change to the props of a JSX element: the range to replace and the text to put there
+#[derive(Debug, PartialEq, Eq)]
+pub struct PropEdit {
+    pub range: Range<usize>,
+    pub content: String,
+    /// The first line of the element's opening tag
+    pub anchor: String,
+}
+
+/// Set `prop` on an element named `component`, or remove it when `value` is None
+///
+/// `occurrence` counts from 1 in the order elements appear in the file, and is needed when more
+/// than one element has that name. `value` is used as it is if it's a `{expression}` or a quoted
+/// string, `true` leaves just the prop's name, and anything else is quoted like the file's other
+/// string props.
+pub fn set_prop(
+    tree: &Tree,
+    source: &str,
+    component: &str,
+    occurrence: Option<usize>,
+    prop: &str,
+    value: Option<&str>,
+) -> Result<PropEdit, String> {
+    let mut tags = vec![];
+    let mut quotes = (0, 0);
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        match node.kind() {
+            "jsx_opening_element" | "jsx_self_closing_element"
+                if node
+                    .child_by_field_name("name")
+                    .is_some_and(|name| &source[name.byte_range()] == component) =>
+            {
+                tags.push(node);
+            }
+            "jsx_attribute" => match node.named_child(1).map(|value| &source[value.byte_range()]) {
+                Some(value) if value.starts_with('\'') => quotes.0 += 1,
+                Some(value) if value.starts_with('"') => quotes.1 += 1,
+                _ => {}
+            },
+            _ => {}
+        }
+        stack.extend(node.named_children(&mut node.walk()));
+    }
+    tags.sort_by_key(Node::start_byte);
+
+    let line = |node: Node<'_>| node.start_position().row + 1;
+    let tag = match (occurrence, tags.as_slice()) {
+        (_, []) => return Err(format!("There is no <{component}> element")),
+        (None, [tag]) => *tag,
+        (None, tags) => {
+            return Err(format!(
+                "There are {} <{component}> elements, on lines {}. Pass occurrence to choose \
+                 one, counting from 1.",
+                tags.len(),
+                tags.iter()
+                    .map(|tag| line(*tag).to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        (Some(occurrence), tags) => *occurrence
+            .checked_sub(1)
+            .and_then(|index| tags.get(index))
+            .ok_or_else(|| {
+                format!(
+                    "There {} only {} <{component}> element{}, so there is no occurrence \
+                     {occurrence}",
+                    if tags.len() == 1 { "is" } else { "are" },
+                    tags.len(),
+                    if tags.len() == 1 { "" } else { "s" }
+                )
+            })?,
+    };
+    let anchor = source[tag.byte_range()]
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    let existing = tag.named_children(&mut tag.walk()).find(|attribute| {
+        attribute.kind() == "jsx_attribute"
+            && attribute
+                .named_child(0)
+                .is_some_and(|name| &source[name.byte_range()] == prop)
+    });
+    let Some(value) = value else {
+        let existing = existing.ok_or_else(|| {
+            format!(
+                "The <{component}> element on line {} has no {prop} prop",
+                line(tag)
+            )
+        })?;
+        return Ok(PropEdit {
+            range: removal(source, existing.byte_range()),
+            content: String::new(),
+            anchor,
+        });
+    };
+
+    let quote = if quotes.0 > quotes.1 { '\'' } else { '"' };
+    let content = attribute(prop, value, quote);
+    if let Some(existing) = existing {
+        return Ok(PropEdit {
+            range: existing.byte_range(),
+            content,
+            anchor,
+        });
+    }
+
+    // after the name, type arguments, and props, and on a line of its own if the props are
+    let last = tag
+        .named_children(&mut tag.walk())
+        .filter(|child| child.kind() != "comment")
+        .last()
+        .ok_or_else(|| {
+            format!(
+                "The <{component}> element on line {} has no name",
+                line(tag)
+            )
+        })?;
+    let offset = last.end_byte();
+    let content = if last.kind() == "jsx_attribute"
+        && last.start_position().row != tag.start_position().row
+    {
+        let start = source[..last.start_byte()]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        format!("\n{}{content}", &source[start..last.start_byte()])
+    } else {
+        format!(" {content}")
+    };
+    Ok(PropEdit {
+        range: offset..offset,
+        content,
+        anchor,
+    })
+}
+
+/// `prop` with `value`, quoted with `quote` unless it is an expression or already quoted
+fn attribute(prop: &str, value: &str, quote: char) -> String {
+    let value = value.trim();
+    let quoted =
+        |quote: char| value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote);
+    if value == "true" {
+        prop.to_string()
+    } else if value.starts_with('{') || quoted('"') || quoted('\'') {
+        format!("{prop}={value}")
+    } else {
+        let other = if quote == '"' { '\'' } else { '"' };
+        match (value.contains(quote), value.contains(other)) {
+            (false, _) => format!("{prop}={quote}{value}{quote}"),
+            (true, false) => format!("{prop}={other}{value}{other}"),
+            // JSX strings can't escape quotes, but a string expression can
+            (true, true) => format!(
+                "{prop}={{{}}}",
+                serde_json::to_string(value).unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// The text to remove along with a prop: the whole line if the prop is alone on it, and the
+/// space before it otherwise
+fn removal(source: &str, range: Range<usize>) -> Range<usize> {
+    let before = source[..range.start].trim_end_matches([' ', '\t']);
+    let after = source[range.end..].trim_start_matches([' ', '\t']);
+    let line_start = before.is_empty() || before.ends_with('\n');
+    if line_start && (after.is_empty() || after.starts_with('\n')) {
+        let end = source.len() - after.len() + usize::from(!after.is_empty());
+        before.len()..end
+    } else {
+        before.len()..range.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_quoted_unless_they_are_expressions() {
+        assert_eq!(attribute("variant", "primary", '"'), r#"variant="primary""#);
+        assert_eq!(attribute("variant", "primary", '\''), "variant='primary'");
+        assert_eq!(
+            attribute("title", r#"say "hi""#, '"'),
+            r#"title='say "hi"'"#
+        );
+        assert_eq!(
+            attribute("title", r#"it's "hi""#, '"'),
+            r#"title={"it's \"hi\""}"#
+        );
+        assert_eq!(attribute("count", "{n + 1}", '"'), "count={n + 1}");
+        assert_eq!(attribute("label", "'x'", '"'), "label='x'");
+        assert_eq!(attribute("disabled", "true", '"'), "disabled");
+    }
+
+    #[cfg(feature = "typescript")]
+    fn edited(
+        source: &str,
+        component: &str,
+        occurrence: Option<usize>,
+        prop: &str,
+        value: Option<&str>,
+    ) -> String {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_typescript::LANGUAGE_TSX.into())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let edit = set_prop(&tree, source, component, occurrence, prop, value).unwrap();
+        format!(
+            "{}{}{}",
+            &source[..edit.range.start],
+            edit.content,
+            &source[edit.range.end..]
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "typescript")]
+    fn props_are_added_updated_and_removed() {
+        let source = "const a = <Button onClick={go} />;\nconst b = <Button>Go</Button>;\n";
+        assert_eq!(
+            edited(source, "Button", Some(1), "variant", Some("primary")),
+            "const a = <Button onClick={go} variant=\"primary\" />;\nconst b = <Button>Go</Button>;\n"
+        );
+        assert_eq!(
+            edited(source, "Button", Some(2), "disabled", Some("true")),
+            "const a = <Button onClick={go} />;\nconst b = <Button disabled>Go</Button>;\n"
+        );
+        assert_eq!(
+            edited(source, "Button", Some(1), "onClick", Some("{stop}")),
+            "const a = <Button onClick={stop} />;\nconst b = <Button>Go</Button>;\n"
+        );
+        assert_eq!(
+            edited(source, "Button", Some(1), "onClick", None),
+            "const a = <Button />;\nconst b = <Button>Go</Button>;\n"
+        );
+
+        let source = "<Modal\n  open={open}\n  title='Hi'\n>\n  body\n</Modal>\n";
+        assert_eq!(
+            edited(source, "Modal", None, "size", Some("large")),
+            "<Modal\n  open={open}\n  title='Hi'\n  size='large'\n>\n  body\n</Modal>\n"
+        );
+        assert_eq!(
+            edited(source, "Modal", None, "open", None),
+            "<Modal\n  title='Hi'\n>\n  body\n</Modal>\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "typescript")]
+    fn elements_must_be_chosen_unambiguously() {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_typescript::LANGUAGE_TSX.into())
+            .unwrap();
+        let source = "<>\n  <Item />\n  <Item />\n</>;\n";
+        let tree = parser.parse(source, None).unwrap();
+        let error = |occurrence, prop, value| {
+            set_prop(&tree, source, "Item", occurrence, prop, value).unwrap_err()
+        };
+        assert!(error(None, "key", Some("a")).contains("on lines 2, 3"));
+        assert!(error(Some(3), "key", Some("a")).contains("only 2 <Item> elements"));
+        assert!(error(Some(1), "key", None).contains("has no key prop"));
+    }
+}
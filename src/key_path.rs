@@ -0,0 +1,621 @@
+use crate::languages::traits::LanguageEditor;
+use std::ops::Range;
+use tree_sitter::{Node, Tree};
+
+/// One step of a key path: a key of a map, a position in a sequence counting from 0, or `[]`
+/// for the end of a sequence, which can only be last
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+    Append,
+}
+
+/// The keyed or positional children of a map or sequence node, which set_key walks
+#[cfg_attr(
+    not(any(feature = "json", feature = "toml", feature = "yaml")),
+    expect(
+        dead_code,
+        reason = "only the JSON, TOML, and YAML editors list entries"
+    )
+)]
+pub enum Entries<'tree> {
+    Map(Vec<Entry<'tree>>),
+    Sequence(Vec<Node<'tree>>),
+}
+
+/// An entry of a map
+pub struct Entry<'tree> {
+    /// The entry's key, split into parts when it's dotted like a TOML `[dependencies.serde]`
+    pub key: Vec<String>,
+    /// The node holding the entry's value
+    pub value: Node<'tree>,
+    /// Whether the entry is a section like a TOML table, whose value is the lines below its
+    /// header rather than an expression that can be replaced
+    pub section: bool,
+}
+
+/// A change made by set_key: the range to replace and the text to put there
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change {
+    pub range: Range<usize>,
+    pub content: String,
+    /// The first line of the replaced value, or of the map or sequence an entry is added to
+    pub anchor: String,
+}
+
+/// Parse a path like `jobs.build.steps[2].run` or `dependencies."serde.json"`
+pub fn parse(path: &str) -> Result<Vec<Segment>, String> {
+    let invalid = |reason: &str| format!("The key path `{path}` {reason}");
+    let mut segments = vec![];
+    let mut rest = path.trim();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let (index, after) = after
+                .split_once(']')
+                .ok_or_else(|| invalid("has a `[` without a `]`"))?;
+            if index.trim().is_empty() {
+                if !after.is_empty() {
+                    return Err(invalid("has `[]` before its end, but it can only be last"));
+                }
+                segments.push(Segment::Append);
+            } else {
+                let index = index
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid(&format!("has `[{index}]`, which isn't a position")))?;
+                segments.push(Segment::Index(index));
+            }
+            rest = after;
+        } else {
+            let key = if let Some(after) = rest.strip_prefix('"') {
+                let end = after
+                    .find('"')
+                    .ok_or_else(|| invalid("has a quoted key without a closing quote"))?;
+                rest = &after[end + 1..];
+                after[..end].to_string()
+            } else {
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                let key = rest[..end].trim();
+                if key.is_empty() {
+                    return Err(invalid("has an empty key"));
+                }
+                rest = &rest[end..];
+                key.to_string()
+            };
+            segments.push(Segment::Key(key));
+        }
+        rest = match rest.strip_prefix('.') {
+            Some("") => return Err(invalid("ends with a `.`")),
+            Some(after) if after.starts_with('[') => return Err(invalid("has `.` before `[`")),
+            Some(after) => after,
+            None if rest.is_empty() || rest.starts_with('[') => rest,
+            None => return Err(invalid("has text after a quoted key or `]` without a `.`")),
+        };
+    }
+    if segments.is_empty() {
+        return Err(invalid("is empty"));
+    }
+    Ok(segments)
+}
+
+/// Replace the value at `path` with `value`, written as it would be in the file. A missing key
+/// is added to its map, holding maps for the keys after it, and a position one past the end of
+/// a sequence or `[]` appends to it. A missing key followed by `[]` is added with a sequence of
+/// `value`.
+pub fn set(
+    editor: &dyn LanguageEditor,
+    tree: &Tree,
+    source: &str,
+    path: &[Segment],
+    value: &str,
+) -> Result<Change, String> {
+    let mut node = tree.root_node();
+    while editor.key_path_entries(node, source).is_none() {
+        node = node
+            .named_children(&mut node.walk())
+            .find(|child| child.kind() != "comment")
+            .ok_or("This file has no map or sequence for a key path to address")?;
+    }
+
+    let mut walked = String::new();
+    let mut remaining = path;
+    let mut section = false;
+    while let Some(segment) = remaining.first() {
+        let here = if walked.is_empty() {
+            "The top level".to_string()
+        } else {
+            format!("`{walked}`")
+        };
+        let entries = editor
+            .key_path_entries(node, source)
+            .ok_or_else(|| format!("{here} isn't a map or sequence"))?;
+        match (entries, segment) {
+            (Entries::Map(entries), Segment::Key(_)) => {
+                let keys = remaining
+                    .iter()
+                    .map_while(|segment| match segment {
+                        Segment::Key(key) => Some(key.as_str()),
+                        Segment::Index(_) | Segment::Append => None,
+                    })
+                    .collect::<Vec<_>>();
+                let matches = |entry: &Entry<'_>, length: usize| {
+                    entry.key.len() == length
+                        && entry
+                            .key
+                            .iter()
+                            .map(String::as_str)
+                            .eq(keys[..length].iter().copied())
+                };
+                // the longest key that starts the path, so that a TOML `[dependencies.serde]`
+                // is found for `dependencies.serde.version` even if `[dependencies]` is there too
+                let Some(length) = entries
+                    .iter()
+                    .map(|entry| entry.key.len())
+                    .filter(|length| *length <= keys.len())
+                    .filter(|length| entries.iter().any(|entry| matches(entry, *length)))
+                    .max()
+                else {
+                    // the rest of the path is keys, maybe followed by `[]`, so the missing key
+                    // is added with maps for the keys after it
+                    let appends = remaining.last() == Some(&Segment::Append);
+                    if keys.len() + usize::from(appends) == remaining.len() {
+                        let mut value = if appends {
+                            format!("[{value}]")
+                        } else {
+                            value.to_string()
+                        };
+                        for key in keys[1..].iter().rev() {
+                            value = editor
+                                .key_path_map(key, &value)
+                                .ok_or_else(|| format!("A map can't be added to {here}"))?;
+                        }
+                        let (offset, content) = editor
+                            .key_path_insertion(node, source, keys[0], &value)
+                            .ok_or_else(|| format!("A key can't be added to {here}"))?;
+                        return Ok(Change {
+                            range: offset..offset,
+                            content,
+                            anchor: first_line(node, source),
+                        });
+                    }
+                    return Err(format!(
+                        "{here} has no `{}`. Its keys are: {}",
+                        keys[0],
+                        entries
+                            .iter()
+                            .map(|entry| entry.key.join("."))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                };
+                let matching = entries
+                    .iter()
+                    .filter(|entry| matches(entry, length))
+                    .collect::<Vec<_>>();
+                push_keys(&mut walked, &keys[..length]);
+                remaining = &remaining[length..];
+                // `[n]` picks among entries with the same key, like a TOML array of tables,
+                // unless there's only one and its value is a sequence that `[n]` indexes into
+                let picks = matching.len() > 1
+                    || !matches!(
+                        editor.key_path_entries(matching[0].value, source),
+                        Some(Entries::Sequence(_))
+                    );
+                let entry = match remaining.first() {
+                    Some(Segment::Index(index)) if picks => {
+                        let entry = matching.get(*index).ok_or_else(|| {
+                            format!(
+                                "`{walked}` has {} entries, so [{index}] is past the end",
+                                matching.len()
+                            )
+                        })?;
+                        walked.push_str(&format!("[{index}]"));
+                        remaining = &remaining[1..];
+                        entry
+                    }
+                    _ if matching.len() == 1 => matching[0],
+                    _ => {
+                        return Err(format!(
+                            "`{walked}` has {} entries. Pick one with `{walked}[0]` through \
+                             `{walked}[{}]`.",
+                            matching.len(),
+                            matching.len() - 1
+                        ));
+                    }
+                };
+                node = entry.value;
+                section = entry.section;
+            }
+            (Entries::Sequence(items), Segment::Index(index)) => {
+                remaining = &remaining[1..];
+                match items.get(*index) {
+                    Some(item) => node = *item,
+                    None if *index == items.len() && remaining.is_empty() => {
+                        return Ok(append(editor, node, &items, source, value));
+                    }
+                    None => {
+                        return Err(format!(
+                            "{here} has {} items, so [{index}] is past its end. Use [{}] to \
+                             append one.",
+                            items.len(),
+                            items.len()
+                        ));
+                    }
+                }
+                walked.push_str(&format!("[{index}]"));
+                section = false;
+            }
+            (Entries::Sequence(items), Segment::Append) => {
+                return Ok(append(editor, node, &items, source, value));
+            }
+            (Entries::Map(_), Segment::Index(index)) => {
+                return Err(format!(
+                    "{here} is a map, not a sequence, so it has no [{index}]"
+                ));
+            }
+            (Entries::Map(_), Segment::Append) => {
+                return Err(format!(
+                    "{here} is a map, not a sequence, so nothing can be appended to it"
+                ));
+            }
+            (Entries::Sequence(_), Segment::Key(key)) => {
+                return Err(format!(
+                    "{here} is a sequence, so it has positions like [0] rather than `{key}`"
+                ));
+            }
+        }
+    }
+
+    if section {
+        return Err(format!(
+            "`{walked}` is a section of the file. Set the keys in it one at a time."
+        ));
+    }
+    Ok(Change {
+        range: node.byte_range(),
+        content: value.to_string(),
+        anchor: first_line(node, source),
+    })
+}
+
+fn push_keys(walked: &mut String, keys: &[&str]) {
+    for key in keys {
+        if !walked.is_empty() {
+            walked.push('.');
+        }
+        walked.push_str(key);
+    }
+}
+
+/// Add `value` after the last item of `sequence`, on a line of its own if the items are
+fn append(
+    editor: &dyn LanguageEditor,
+    sequence: Node<'_>,
+    items: &[Node<'_>],
+    source: &str,
+    value: &str,
+) -> Change {
+    let appended = editor.key_path_append(sequence, source, value);
+    let (offset, content) = match (appended, items.last()) {
+        (Some(appended), _) => appended,
+        (None, Some(last)) if last.start_position().row != sequence.start_position().row => (
+            last.end_byte(),
+            format!(",\n{}{value}", indent_of(source, last.start_byte())),
+        ),
+        (None, Some(last)) => (last.end_byte(), format!(", {value}")),
+        (None, None) => (sequence.start_byte() + 1, value.to_string()),
+    };
+    Change {
+        range: offset..offset,
+        content,
+        anchor: first_line(sequence, source),
+    }
+}
+
+/// The indentation of the line `offset` is on
+pub fn indent_of(source: &str, offset: usize) -> &str {
+    let start = source[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line = &source[start..];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+fn first_line(node: Node<'_>, source: &str) -> String {
+    source[node.byte_range()]
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_paths() {
+        use Segment::{Index, Key};
+        assert_eq!(
+            parse("jobs.build.steps[2].run").unwrap(),
+            vec![
+                Key("jobs".into()),
+                Key("build".into()),
+                Key("steps".into()),
+                Index(2),
+                Key("run".into())
+            ]
+        );
+        assert_eq!(
+            parse(r#"dependencies."serde.json"[0][1]"#).unwrap(),
+            vec![
+                Key("dependencies".into()),
+                Key("serde.json".into()),
+                Index(0),
+                Index(1)
+            ]
+        );
+        assert_eq!(
+            parse("tags[]").unwrap(),
+            vec![Key("tags".into()), Segment::Append]
+        );
+        assert!(parse("tags[].name").is_err());
+        assert!(parse("jobs..build").is_err());
+        assert!(parse("steps[two]").is_err());
+        assert!(parse("steps.").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+    fn changed(
+        editor: &dyn LanguageEditor,
+        language: tree_sitter::Language,
+        source: &str,
+        path: &str,
+        value: &str,
+    ) -> Result<String, String> {
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let change = set(editor, &tree, source, &parse(path)?, value)?;
+        Ok(format!(
+            "{}{}{}",
+            &source[..change.range.start],
+            change.content,
+            &source[change.range.end..]
+        ))
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_paths() {
+        let editor = crate::languages::json::JsonEditor;
+        let set = |source: &str, path: &str, value: &str| {
+            changed(
+                &editor,
+                tree_sitter_json::LANGUAGE.into(),
+                source,
+                path,
+                value,
+            )
+        };
+        let source = r#"{
+  "jobs": {
+    "build": {
+      "steps": [
+        { "uses": "actions/checkout@v4" },
+        { "run": "cargo build" },
+        { "run": "cargo test" }
+      ]
+    }
+  }
+}
+"#;
+        assert_eq!(
+            set(source, "jobs.build.steps[2].run", r#""cargo test --all""#).unwrap(),
+            source.replace(r#""cargo test""#, r#""cargo test --all""#)
+        );
+        assert_eq!(
+            set(source, "jobs.build.steps[1].name", r#""Build""#).unwrap(),
+            source.replace(r#""cargo build" }"#, r#""cargo build", "name": "Build" }"#)
+        );
+        assert_eq!(
+            set(source, "jobs.build.steps[3]", r#"{ "run": "cargo doc" }"#).unwrap(),
+            source.replace(
+                r#"{ "run": "cargo test" }"#,
+                r#"{ "run": "cargo test" },
+        { "run": "cargo doc" }"#
+            )
+        );
+        assert_eq!(
+            set(source, "jobs.build.timeout", "10").unwrap(),
+            source.replace(
+                r#"      ]
+"#,
+                r#"      ],
+      "timeout": 10
+"#
+            )
+        );
+        assert_eq!(
+            set(source, "jobs.build.env.CI", "true").unwrap(),
+            source.replace(
+                r#"      ]
+"#,
+                r#"      ],
+      "env": { "CI": true }
+"#
+            )
+        );
+        assert!(
+            set(source, "jobs.test.steps[0]", "{}")
+                .unwrap_err()
+                .contains("`jobs` has no `test`")
+        );
+        assert!(
+            set(source, "jobs.build.steps[5]", "{}")
+                .unwrap_err()
+                .contains("has 3 items")
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_paths() {
+        let editor = crate::languages::toml::TomlEditor;
+        let set = |source: &str, path: &str, value: &str| {
+            changed(
+                &editor,
+                tree_sitter_toml_ng::LANGUAGE.into(),
+                source,
+                path,
+                value,
+            )
+        };
+        let source = r#"[package]
+name = "app"
+
+[dependencies]
+anyhow = "1"
+serde = { version = "1", features = ["derive"] }
+
+[dependencies.tokio]
+version = "1"
+
+[[bin]]
+name = "app"
+
+[[bin]]
+name = "helper"
+"#;
+        assert_eq!(
+            set(source, "dependencies.serde.version", r#""1.0.200""#).unwrap(),
+            source.replace(r#"version = "1", "#, r#"version = "1.0.200", "#)
+        );
+        assert_eq!(
+            set(source, "dependencies.tokio.version", r#""1.40""#).unwrap(),
+            source.replace(
+                r#"[dependencies.tokio]
+version = "1""#,
+                r#"[dependencies.tokio]
+version = "1.40""#
+            )
+        );
+        assert_eq!(
+            set(source, "dependencies.serde.features[1]", r#""rc""#).unwrap(),
+            source.replace(r#"["derive"]"#, r#"["derive", "rc"]"#)
+        );
+        assert_eq!(
+            set(source, "bin[1].path", r#""src/helper.rs""#).unwrap(),
+            source.replace(
+                r#"name = "helper""#,
+                r#"name = "helper"
+path = "src/helper.rs""#
+            )
+        );
+        assert_eq!(
+            set(source, "package.edition", r#""2024""#).unwrap(),
+            source.replace(
+                r#"name = "app"
+
+[dependencies]"#,
+                r#"name = "app"
+edition = "2024"
+
+[dependencies]"#
+            )
+        );
+        assert_eq!(
+            set(source, "dependencies.clap.features[]", r#""derive""#).unwrap(),
+            source.replace(
+                r#"features = ["derive"] }
+"#,
+                r#"features = ["derive"] }
+clap = { features = ["derive"] }
+"#
+            )
+        );
+        assert!(
+            set(source, "bin.name", r#""x""#)
+                .unwrap_err()
+                .contains("Pick one with `bin[0]` through `bin[1]`")
+        );
+        assert!(
+            set(source, "dependencies", "{}")
+                .unwrap_err()
+                .contains("is a section")
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_paths() {
+        let editor = crate::languages::yaml::YamlEditor;
+        let set = |source: &str, path: &str, value: &str| {
+            changed(
+                &editor,
+                tree_sitter_yaml::LANGUAGE.into(),
+                source,
+                path,
+                value,
+            )
+        };
+        let source = r#"# CI
+on: [push]
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - name: Build
+        run: |
+          cargo build
+    env: { RUST_LOG: debug }
+"#;
+        assert_eq!(
+            set(source, "jobs.build.runs-on", "macos-latest").unwrap(),
+            source.replace("ubuntu-latest", "macos-latest")
+        );
+        assert_eq!(
+            set(source, "jobs.build.steps[0].with", "{ fetch-depth: 0 }").unwrap(),
+            source.replace(
+                "checkout@v4\n",
+                "checkout@v4\n        with: { fetch-depth: 0 }\n"
+            )
+        );
+        assert_eq!(
+            set(source, "jobs.build.steps[2]", "run: cargo test").unwrap(),
+            source.replace(
+                "          cargo build\n",
+                "          cargo build\n      - run: cargo test\n"
+            )
+        );
+        assert_eq!(
+            set(source, "jobs.build.timeout-minutes", "10").unwrap(),
+            source.replace("debug }\n", "debug }\n    timeout-minutes: 10\n")
+        );
+        assert_eq!(
+            set(source, "jobs.build.env.CI", "true").unwrap(),
+            source.replace("debug }", "debug, CI: true }")
+        );
+        assert_eq!(
+            set(source, "on[1]", "pull_request").unwrap(),
+            source.replace("[push]", "[push, pull_request]")
+        );
+        assert_eq!(
+            set(source, "on[]", "pull_request").unwrap(),
+            source.replace("[push]", "[push, pull_request]")
+        );
+        assert_eq!(
+            set(source, "jobs.build.services[]", "redis").unwrap(),
+            source.replace("debug }\n", "debug }\n    services: [redis]\n")
+        );
+        assert_eq!(
+            set(source, "jobs.build.strategy.matrix.os", "[ubuntu-latest]").unwrap(),
+            source.replace(
+                "debug }\n",
+                "debug }\n    strategy: { matrix: { os: [ubuntu-latest] } }\n"
+            )
+        );
+    }
+}
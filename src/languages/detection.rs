@@ -0,0 +1,358 @@
+use super::LanguageName;
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// How much of the start and end of a file is read when looking for a shebang or modeline
+const SNIFF_BYTES: u64 = 4096;
+
+/// How many lines at the start and end of a file may hold a modeline, as in vim
+const MODELINE_LINES: usize = 5;
+
+/// How much of a file is read to guess its language from its content
+const CLASSIFY_BYTES: usize = 1024;
+
+/// The least keyword score and confidence for a content-based guess to be used
+const MIN_SCORE: usize = 3;
+const MIN_CONFIDENCE: u8 = 60;
+
+/// Substrings characteristic of each language, with their weights
+const RUST_KEYWORDS: &[(&str, usize)] = &[
+    ("fn ", 2),
+    ("let mut ", 3),
+    ("impl ", 3),
+    ("pub fn ", 3),
+    ("use std::", 3),
+    ("::", 1),
+    ("&self", 3),
+    ("&mut ", 2),
+    ("#[derive(", 3),
+    ("println!(", 3),
+    ("match ", 1),
+    ("-> ", 1),
+];
+const PYTHON_KEYWORDS: &[(&str, usize)] = &[
+    ("def ", 3),
+    ("import ", 1),
+    ("from ", 1),
+    ("self.", 2),
+    ("elif ", 3),
+    ("None", 1),
+    ("True", 1),
+    ("False", 1),
+    ("print(", 1),
+    ("__name__", 3),
+    ("__init__", 3),
+    ("lambda ", 2),
+];
+const JAVASCRIPT_KEYWORDS: &[(&str, usize)] = &[
+    ("function ", 2),
+    ("const ", 2),
+    ("=> ", 2),
+    ("require(", 3),
+    ("module.exports", 3),
+    ("console.log(", 3),
+    ("===", 3),
+    ("export ", 1),
+    ("var ", 1),
+    ("null", 1),
+    ("undefined", 2),
+];
+/// Substrings that distinguish TypeScript from JavaScript, counted on top of JavaScript's
+const TYPESCRIPT_KEYWORDS: &[(&str, usize)] = &[
+    ("interface ", 3),
+    (": string", 3),
+    (": number", 3),
+    (": boolean", 3),
+    ("export type ", 3),
+    ("as const", 3),
+    ("implements ", 2),
+    ("readonly ", 2),
+];
+
+/// A language proposed by [`classify`] for content with no other clue to its language
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Guess {
+    pub language: LanguageName,
+    /// How much more the content resembles this language than any other, as a percentage
+    pub confidence: u8,
+}
+
+/// Detect the language of a file without a recognized extension from its shebang
+/// (`#!/usr/bin/env python3`) or an editor modeline (`# vim: set ft=python:`,
+/// `-*- mode: python -*-`)
+pub(super) fn sniff(path: &Path) -> Option<LanguageName> {
+    let (head, tail) = read_ends(path)?;
+    let head_lines = head.lines().take(MODELINE_LINES);
+    let tail_lines = if tail.is_empty() { &head } else { &tail }
+        .lines()
+        .rev()
+        .take(MODELINE_LINES)
+        .collect::<Vec<_>>();
+
+    head.lines()
+        .next()
+        .and_then(|line| line.strip_prefix("#!"))
+        .and_then(interpreter)
+        .or_else(|| head_lines.chain(tail_lines).find_map(modeline))
+}
+
+/// Guess a file's language from the keywords in its first kilobyte, for files that have no
+/// extension, shebang, or modeline. Files with an unrecognized extension, like `.md`, are left
+/// to the plain editor. Returns None when no language stands out.
+pub(super) fn guess(path: &Path) -> Option<Guess> {
+    if path.extension().is_some() {
+        return None;
+    }
+    let mut head = vec![];
+    File::open(path)
+        .ok()?
+        .take(CLASSIFY_BYTES as u64)
+        .read_to_end(&mut head)
+        .ok()?;
+    if head.contains(&0) {
+        return None;
+    }
+    classify(&String::from_utf8_lossy(&head))
+}
+
+fn classify(text: &str) -> Option<Guess> {
+    let text = text.trim();
+    let structured = if text.starts_with(['{', '['])
+        && serde_json::from_str::<serde_json::Value>(text).is_ok()
+    {
+        Some(LanguageName::Json)
+    } else if text.contains('=') && taplo::parser::parse(text).errors.is_empty() {
+        Some(LanguageName::Toml)
+    } else {
+        None
+    };
+    if let Some(language) = structured {
+        return Some(Guess {
+            language,
+            confidence: 100,
+        });
+    }
+
+    let score = |keywords: &[(&str, usize)]| {
+        keywords
+            .iter()
+            .map(|(keyword, weight)| text.matches(keyword).count() * weight)
+            .sum::<usize>()
+    };
+    let (javascript, typescript) = (score(JAVASCRIPT_KEYWORDS), score(TYPESCRIPT_KEYWORDS));
+    let ecma = if typescript > 0 {
+        (LanguageName::Typescript, javascript + typescript)
+    } else {
+        (LanguageName::Javascript, javascript)
+    };
+    let scores = [
+        (LanguageName::Rust, score(RUST_KEYWORDS)),
+        (LanguageName::Python, score(PYTHON_KEYWORDS)),
+        ecma,
+    ];
+
+    let total = scores.iter().map(|(_, score)| score).sum::<usize>();
+    let (language, best) = scores.into_iter().max_by_key(|(_, score)| *score)?;
+    let confidence = (best * 100 / total.max(1)) as u8;
+    (best >= MIN_SCORE && confidence >= MIN_CONFIDENCE).then_some(Guess {
+        language,
+        confidence,
+    })
+}
+
+/// The first and last few kilobytes of a file, or None for files that can't be read or aren't
+/// text
+fn read_ends(path: &Path) -> Option<(String, String)> {
+    let mut file = File::open(path).ok()?;
+    let length = file.metadata().ok()?.len();
+
+    let mut head = vec![];
+    (&mut file).take(SNIFF_BYTES).read_to_end(&mut head).ok()?;
+    let mut tail = vec![];
+    if length > SNIFF_BYTES {
+        file.seek(SeekFrom::Start(
+            length.saturating_sub(SNIFF_BYTES).max(SNIFF_BYTES),
+        ))
+        .ok()?;
+        file.read_to_end(&mut tail).ok()?;
+    }
+    if head.contains(&0) {
+        return None;
+    }
+
+    Some((
+        String::from_utf8_lossy(&head).into_owned(),
+        String::from_utf8_lossy(&tail).into_owned(),
+    ))
+}
+
+/// The language run by a shebang's interpreter, like `/usr/bin/python3.12` or
+/// `/usr/bin/env -S node --experimental-vm-modules`
+fn interpreter(shebang: &str) -> Option<LanguageName> {
+    let mut words = shebang.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    match program {
+        "python" | "pypy" => Some(LanguageName::Python),
+        "node" | "nodejs" | "bun" => Some(LanguageName::Javascript),
+        "ts-node" | "deno" => Some(LanguageName::Typescript),
+        "rust-script" | "cargo" => Some(LanguageName::Rust),
+        _ => None,
+    }
+}
+
+/// The language set by a vim (`vim: set ft=python:`, `vi: filetype=rust`) or emacs
+/// (`-*- mode: python -*-`, `-*- python -*-`) modeline on a line
+fn modeline(line: &str) -> Option<LanguageName> {
+    if let Some((_, emacs)) = line.split_once("-*-") {
+        let (variables, _) = emacs.split_once("-*-")?;
+        let mode = if variables.contains(':') {
+            variables.split(';').find_map(|variable| {
+                let (name, value) = variable.split_once(':')?;
+                name.trim()
+                    .eq_ignore_ascii_case("mode")
+                    .then(|| value.trim())
+            })?
+        } else {
+            variables.trim()
+        };
+        return named(mode.trim_end_matches("-mode").trim_end_matches("-ts"));
+    }
+
+    let (_, vim) = [" vim:", " vi:", " ex:", "\tvim:", "\tvi:"]
+        .iter()
+        .find_map(|marker| line.split_once(marker))?;
+    vim.split([' ', ':', '\t'])
+        .filter_map(|option| option.split_once('='))
+        .find(|(name, _)| matches!(*name, "ft" | "filetype" | "syntax" | "syn"))
+        .and_then(|(_, value)| named(value))
+}
+
+/// A language from the name an editor gives it
+fn named(name: &str) -> Option<LanguageName> {
+    match name.to_ascii_lowercase().as_str() {
+        "javascriptreact" | "rjsx" => Some(LanguageName::Jsx),
+        "typescriptreact" => Some(LanguageName::Tsx),
+        "js2" | "js" | "javascript" => Some(LanguageName::Javascript),
+        "conf-toml" => Some(LanguageName::Toml),
+        "rustic" => Some(LanguageName::Rust),
+        other => match other.parse() {
+            Ok(LanguageName::Other) | Err(_) => None,
+            Ok(language) => Some(language),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shebangs() {
+        for (shebang, language) in [
+            ("/usr/bin/env python3", Some(LanguageName::Python)),
+            ("/usr/bin/python3.12 -u", Some(LanguageName::Python)),
+            (
+                "/usr/bin/env -S node --experimental-vm-modules",
+                Some(LanguageName::Javascript),
+            ),
+            (
+                "/usr/bin/env -S deno run --allow-net",
+                Some(LanguageName::Typescript),
+            ),
+            (
+                "/usr/bin/env PYTHONUNBUFFERED=1 python",
+                Some(LanguageName::Python),
+            ),
+            ("/bin/bash", None),
+            ("/bin/sh -e", None),
+        ] {
+            assert_eq!(interpreter(shebang), language, "{shebang}");
+        }
+    }
+
+    #[test]
+    fn modelines() {
+        for (line, language) in [
+            ("# vim: set ft=python :", Some(LanguageName::Python)),
+            ("// vim: filetype=typescriptreact", Some(LanguageName::Tsx)),
+            (
+                "/* vi:set ts=4 sw=4 syntax=javascript: */",
+                Some(LanguageName::Javascript),
+            ),
+            (
+                "# -*- mode: python; coding: utf-8 -*-",
+                Some(LanguageName::Python),
+            ),
+            ("// -*- rust -*-", Some(LanguageName::Rust)),
+            ("# -*- coding: utf-8 -*-", None),
+            ("# vim: set ft=sh :", None),
+            ("let invim = 1; // not a modeline", None),
+        ] {
+            assert_eq!(modeline(line), language, "{line}");
+        }
+    }
+
+    #[test]
+    fn content() {
+        for (text, language) in [
+            (
+                "use std::fmt;\n\npub fn render(&self) -> String {\n    let mut out = String::new();\n",
+                Some(LanguageName::Rust),
+            ),
+            (
+                "import os\n\ndef main():\n    if os.environ.get('X') is None:\n        print('none')\n",
+                Some(LanguageName::Python),
+            ),
+            (
+                "const fs = require('fs');\nmodule.exports = function read(path) {\n  return fs.readFileSync(path);\n};\n",
+                Some(LanguageName::Javascript),
+            ),
+            (
+                "export interface User {\n  name: string;\n  age: number;\n}\nexport const admin = (user: User) => user.name === 'root';\n",
+                Some(LanguageName::Typescript),
+            ),
+            (
+                "{\"name\": \"app\", \"private\": true}",
+                Some(LanguageName::Json),
+            ),
+            (
+                "[server]\nport = 8080\nhost = \"localhost\"\n",
+                Some(LanguageName::Toml),
+            ),
+            ("Remember to water the plants.\nAnd call mom.\n", None),
+        ] {
+            assert_eq!(
+                classify(text).map(|guess| guess.language),
+                language,
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn files_without_extensions() {
+        let dir = std::env::temp_dir().join(format!("semantic-edit-sniff-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("deploy");
+        std::fs::write(&script, "#!/usr/bin/env python3\nprint('hi')\n").unwrap();
+        let config = dir.join("settings");
+        std::fs::write(&config, "[server]\nport = 80\n\n# vim: ft=toml\n").unwrap();
+        let shell = dir.join("build");
+        std::fs::write(&shell, "#!/bin/bash\necho hi\n").unwrap();
+
+        assert_eq!(sniff(&script), Some(LanguageName::Python));
+        assert_eq!(sniff(&config), Some(LanguageName::Toml));
+        assert_eq!(sniff(&shell), None);
+        assert_eq!(sniff(&dir.join("missing")), None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
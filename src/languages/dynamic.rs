@@ -0,0 +1,241 @@
+use super::{LanguageCommon, LanguageName, toml, traits::LanguageEditor};
+use crate::state::expand;
+use anyhow::{Result, anyhow, bail};
+use libloading::{Library, Symbol};
+use serde::Deserialize;
+use std::{
+    env,
+    fmt::{self, Debug, Formatter},
+    fs,
+    path::PathBuf,
+};
+use tree_sitter::{Language, Parser};
+use tree_sitter_language::LanguageFn;
+
+/// Environment variable naming the TOML file that declares grammars to load at runtime
+const CONFIG: &str = "SEMANTIC_EDIT_GRAMMARS";
+
+/// What a grammar was loaded from, which must be kept as long as the grammar is in use
+pub enum Source {
+    Library(Library),
+    #[cfg(feature = "wasm")]
+    Wasm(tree_sitter::WasmStore),
+}
+
+impl Debug for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Library(library) => library.fmt(f),
+            #[cfg(feature = "wasm")]
+            Self::Wasm(_) => f.write_str("WasmStore"),
+        }
+    }
+}
+
+/// A compiled tree-sitter grammar declared in the configuration file:
+///
+/// ```toml
+/// [[grammar]]
+/// name = "go"
+/// extensions = ["go"]
+/// library = "~/.local/lib/tree-sitter/libtree-sitter-go.so"
+/// ```
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Grammar {
+    pub name: String,
+    pub extensions: Vec<String>,
+    /// Path to the shared library, or to a WebAssembly grammar ending in `.wasm`. `~` and
+    /// environment variables are expanded.
+    pub library: String,
+    /// The function in the library that returns the grammar. Defaults to `tree_sitter_{name}`.
+    #[serde(default)]
+    pub symbol: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default)]
+    grammar: Vec<Grammar>,
+}
+
+/// The grammar configuration file, if one is configured
+pub fn configured() -> Option<PathBuf> {
+    env::var_os(CONFIG).map(PathBuf::from)
+}
+
+pub fn read_config(path: &std::path::Path) -> Result<Vec<Grammar>> {
+    let source = fs::read_to_string(path)?;
+    let config: Config = serde_json::from_value(toml::to_json(&source)?)?;
+    Ok(config.grammar)
+}
+
+/// Load a grammar from its shared library or WebAssembly module, checking that this version of
+/// tree-sitter can use it
+pub fn load(grammar: &Grammar) -> Result<(Source, Language)> {
+    let path = expand(&grammar.library)?;
+    let symbol = grammar
+        .symbol
+        .clone()
+        .unwrap_or_else(|| format!("tree_sitter_{}", grammar.name.replace('-', "_")));
+    if path.ends_with(".wasm") {
+        return load_wasm(&path, &symbol);
+    }
+
+    // SAFETY: loading a library runs its initializers. The configuration names the library as
+    // a tree-sitter grammar, which has none beyond the C runtime's.
+    let library = unsafe { Library::new(&path) }
+        .map_err(|error| anyhow!("{path} could not be loaded: {error}"))?;
+
+    // SAFETY: tree-sitter grammars export their language function with this signature
+    let language_fn = unsafe {
+        let function: Symbol<unsafe extern "C" fn() -> *const ()> = library
+            .get(symbol.as_bytes())
+            .map_err(|error| anyhow!("{path} has no {symbol} function: {error}"))?;
+        LanguageFn::from_raw(*function)
+    };
+    let language = Language::new(language_fn);
+
+    if let Err(error) = Parser::new().set_language(&language) {
+        bail!("{path} was built for a different version of tree-sitter: {error}");
+    }
+    Ok((Source::Library(library), language))
+}
+
+/// The engine that runs every WebAssembly grammar
+#[cfg(feature = "wasm")]
+fn engine() -> &'static tree_sitter::wasmtime::Engine {
+    static ENGINE: std::sync::OnceLock<tree_sitter::wasmtime::Engine> = std::sync::OnceLock::new();
+    ENGINE.get_or_init(Default::default)
+}
+
+/// A store for a parser to run WebAssembly grammars in. Each parser needs its own.
+#[cfg(feature = "wasm")]
+pub fn wasm_store() -> Result<tree_sitter::WasmStore> {
+    Ok(tree_sitter::WasmStore::new(engine())?)
+}
+
+/// Load a grammar compiled to WebAssembly, which runs sandboxed in wasmtime
+#[cfg(feature = "wasm")]
+fn load_wasm(path: &str, symbol: &str) -> Result<(Source, Language)> {
+    let bytes = fs::read(path).map_err(|error| anyhow!("{path} could not be read: {error}"))?;
+    let mut store = wasm_store()?;
+    // the store looks for a `tree_sitter_{name}` export
+    let name = symbol.strip_prefix("tree_sitter_").unwrap_or(symbol);
+    let language = store
+        .load_language(name, &bytes)
+        .map_err(|error| anyhow!("{path} could not be loaded: {error}"))?;
+    Ok((Source::Wasm(store), language))
+}
+
+#[cfg(not(feature = "wasm"))]
+fn load_wasm(path: &str, _symbol: &str) -> Result<(Source, Language)> {
+    bail!(
+        "{path} is a WebAssembly grammar, which needs semantic-edit-mcp built with the `wasm` \
+         feature"
+    )
+}
+
+/// A language for a grammar loaded at runtime, edited generically: edits are validated against
+/// the grammar's error nodes, and files are not formatted
+pub fn language(name: &str, language: Language) -> LanguageCommon {
+    LanguageCommon {
+        name: LanguageName::Other,
+        file_extensions: &[],
+        language,
+        editor: Box::new(DynamicEditor),
+        validation_query: None,
+        validation_source: None,
+        public_api_query: None,
+        calls_query: None,
+        dynamic_name: Some(name.to_string()),
+        node_types: None,
+        symbol_kinds: &[],
+    }
+}
+
+struct DynamicEditor;
+
+impl LanguageEditor for DynamicEditor {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configuration() {
+        let dir = env::temp_dir().join(format!("semantic-edit-grammars-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("grammars.toml");
+        fs::write(
+            &config,
+            r#"
+[[grammar]]
+name = "go"
+extensions = ["go"]
+library = "/nonexistent/libtree-sitter-go.so"
+
+[[grammar]]
+name = "kotlin"
+extensions = [".kt", "kts"]
+library = "/nonexistent/kotlin.so"
+symbol = "tree_sitter_kotlin_ng"
+"#,
+        )
+        .unwrap();
+
+        let grammars = read_config(&config).unwrap();
+        assert_eq!(grammars.len(), 2);
+        assert_eq!(grammars[1].symbol.as_deref(), Some("tree_sitter_kotlin_ng"));
+
+        let error = load(&grammars[0]).unwrap_err().to_string();
+        assert!(error.starts_with("/nonexistent/libtree-sitter-go.so could not be loaded"));
+
+        let not_wasm = dir.join("tree-sitter-go.wasm");
+        fs::write(&not_wasm, "not a wasm module").unwrap();
+        let error = load(&Grammar {
+            library: not_wasm.to_string_lossy().into_owned(),
+            ..grammars[0].clone()
+        })
+        .unwrap_err()
+        .to_string();
+        assert!(error.starts_with(&format!("{} ", not_wasm.display())));
+        assert!(if cfg!(feature = "wasm") {
+            error.contains("could not be loaded")
+        } else {
+            error.contains("`wasm` feature")
+        });
+
+        fs::write(&config, "[[grammar]]\nname = \"go\"\n").unwrap();
+        assert!(read_config(&config).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn registered_grammars_are_detected_by_extension() {
+        use crate::languages::LanguageRegistry;
+        use std::path::Path;
+
+        let mut registry = LanguageRegistry::new().unwrap();
+        registry.register(
+            "jsonc",
+            &[".jsonc".into(), "json".into()],
+            tree_sitter_json::LANGUAGE.into(),
+        );
+
+        let language = registry
+            .get_language_with_hint(Path::new("settings.jsonc"), None)
+            .unwrap();
+        assert_eq!(language.to_string(), "jsonc");
+        assert_eq!(language.name(), LanguageName::Other);
+
+        // built-in extensions aren't taken over
+        let json = registry
+            .get_language_with_hint(Path::new("package.json"), None)
+            .unwrap();
+        assert_eq!(json.to_string(), "json");
+    }
+}
@@ -8,6 +8,10 @@ use std::{
 
 pub(super) struct EcmaEditor;
 impl LanguageEditor for EcmaEditor {
+    fn formatter(&self) -> Option<&'static str> {
+        Some("biome")
+    }
+
     fn format_code(&self, source: &str, file_path: &Path) -> Result<String> {
         let mut command = Command::new("biome");
         command
@@ -0,0 +1,88 @@
+use crate::{
+    languages::{LanguageCommon, LanguageName, traits::LanguageEditor},
+    repair::{Repair, generic_repairs},
+};
+use anyhow::{Result, anyhow};
+use std::{
+    io::{Read, Write},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+pub fn language() -> LanguageCommon {
+    LanguageCommon {
+        name: LanguageName::Java,
+        file_extensions: &["java"],
+        language: tree_sitter_java::LANGUAGE.into(),
+        editor: Box::new(JavaEditor),
+        validation_query: None,
+        validation_source: None,
+        public_api_query: None,
+        calls_query: None,
+        dynamic_name: None,
+        node_types: Some(tree_sitter_java::NODE_TYPES),
+        symbol_kinds: &[
+            ("class_declaration", "class"),
+            ("interface_declaration", "interface"),
+            ("enum_declaration", "enum"),
+            ("enum_constant", "constant"),
+            ("record_declaration", "record"),
+            ("annotation_type_declaration", "annotation"),
+            ("method_declaration", "method"),
+            ("constructor_declaration", "constructor"),
+            ("field_declaration", "field"),
+            ("constant_declaration", "field"),
+        ],
+    }
+}
+
+struct JavaEditor;
+
+impl LanguageEditor for JavaEditor {
+    fn repairs(&self, content: &str) -> Vec<Repair> {
+        let mut repairs = generic_repairs(content);
+        let trimmed = content.trim_end();
+        if !trimmed.contains('\n')
+            && !trimmed.ends_with([';', '{', '}', ','])
+            && !trimmed.starts_with(['@', '/'])
+            && !trimmed.is_empty()
+        {
+            repairs.push(("added a missing semicolon", format!("{trimmed};")));
+        }
+        repairs
+    }
+
+    fn formatter(&self) -> Option<&'static str> {
+        Some("google-java-format")
+    }
+
+    fn format_code(&self, source: &str, _file_path: &Path) -> Result<String> {
+        let mut child = Command::new("google-java-format")
+            .arg("-")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(source.as_bytes())?;
+            drop(stdin);
+        }
+
+        let mut stdout = String::new();
+        if let Some(mut out) = child.stdout.take() {
+            out.read_to_string(&mut stdout)?;
+        }
+
+        let mut stderr = String::new();
+        if let Some(mut err) = child.stderr.take() {
+            err.read_to_string(&mut stderr)?;
+        }
+
+        if child.wait()?.success() {
+            Ok(stdout)
+        } else {
+            Err(anyhow!(stderr))
+        }
+    }
+}
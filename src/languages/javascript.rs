@@ -1,11 +1,20 @@
 use super::{LanguageCommon, LanguageName, ecma_editor::EcmaEditor};
+use tree_sitter::Query;
 
 pub fn language() -> LanguageCommon {
+    let language = tree_sitter_javascript::LANGUAGE.into();
+    let calls_query = Query::new(&language, super::ECMA_CALLS_QUERY).unwrap();
     LanguageCommon {
         name: LanguageName::Javascript,
         file_extensions: &["js"],
-        language: tree_sitter_javascript::LANGUAGE.into(),
+        language,
         editor: Box::new(EcmaEditor),
         validation_query: None,
+        validation_source: None,
+        public_api_query: None,
+        calls_query: Some(calls_query),
+        dynamic_name: None,
+        node_types: Some(tree_sitter_javascript::NODE_TYPES),
+        symbol_kinds: super::ECMA_SYMBOL_KINDS,
     }
 }
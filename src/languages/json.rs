@@ -1,9 +1,14 @@
 use crate::{
     editor::{Edit, EditIterator, Editor},
+    key_path::{Entries, Entry, indent_of},
     languages::{LanguageCommon, LanguageEditor, LanguageName, ecma_editor::EcmaEditor},
+    recipes::{Parameter, Placement, Recipe},
+    repair::{Repair, generic_repairs},
+    sort_keys::{Group, key_text},
 };
 use anyhow::Result;
 use std::path::Path;
+use tree_sitter::Node;
 
 pub fn language() -> LanguageCommon {
     LanguageCommon {
@@ -11,6 +16,12 @@ pub fn language() -> LanguageCommon {
         file_extensions: &["json"],
         language: tree_sitter_json::LANGUAGE.into(),
         validation_query: None,
+        validation_source: None,
+        public_api_query: None,
+        calls_query: None,
+        dynamic_name: None,
+        node_types: Some(tree_sitter_json::NODE_TYPES),
+        symbol_kinds: &[("pair", "key")],
         editor: Box::new(JsonEditor::new()),
     }
 }
@@ -29,11 +40,153 @@ impl JsonEditor {
     }
 }
 
+/// The parameters of a package.json dependency
+const DEPENDENCY: &[Parameter] = &[
+    Parameter {
+        name: "name",
+        description: "The package, like `react` or `@types/node`",
+        default: None,
+    },
+    Parameter {
+        name: "version",
+        description: "The version range, like `^18.2.0`",
+        default: Some("*"),
+    },
+];
+
+const RECIPES: &[Recipe] = &[
+    Recipe {
+        name: "dependency",
+        description: "A dependency in a package.json, or its version if it's already there",
+        parameters: DEPENDENCY,
+        placement: Placement::Entry {
+            path: "dependencies.\"{{name}}\"",
+        },
+        template: "\"{{version}}\"",
+    },
+    Recipe {
+        name: "dev_dependency",
+        description: "A development dependency in a package.json",
+        parameters: DEPENDENCY,
+        placement: Placement::Entry {
+            path: "devDependencies.\"{{name}}\"",
+        },
+        template: "\"{{version}}\"",
+    },
+];
+
 impl LanguageEditor for JsonEditor {
+    fn recipes(&self) -> &'static [Recipe] {
+        RECIPES
+    }
+
+    fn formatter(&self) -> Option<&'static str> {
+        Some("biome")
+    }
+
     fn format_code(&self, source: &str, file_path: &Path) -> Result<String> {
         EcmaEditor.format_code(source, file_path)
     }
 
+    fn repairs(&self, content: &str) -> Vec<Repair> {
+        let mut repairs = generic_repairs(content);
+        if let Some(repaired) = remove_trailing_commas(content) {
+            repairs.push(("removed trailing commas before closing brackets", repaired));
+        }
+        repairs
+    }
+
+    fn pitfalls(&self, content: &str) -> Vec<&'static str> {
+        let mut pitfalls = vec![];
+        let outside_strings = outside_strings(content);
+        if remove_trailing_commas(content).is_some() {
+            pitfalls.push("JSON doesn't allow a comma after the last item of an object or array.");
+        }
+        if outside_strings.contains("//") || outside_strings.contains("/*") {
+            pitfalls.push("JSON has no comments. Put notes in a field, or leave them out.");
+        }
+        if outside_strings.contains('\'') {
+            pitfalls.push("Strings and keys in JSON are in double quotes, never single quotes.");
+        }
+        if outside_strings.lines().any(|line| {
+            let line = line.trim_start().trim_start_matches(['{', ',', ' ']);
+            line.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && line.contains(':')
+        }) {
+            pitfalls.push("Keys in JSON objects are quoted strings, like `\"name\": 1`.");
+        }
+        pitfalls
+    }
+
+    fn sortable_entries<'tree>(
+        &self,
+        node: Node<'tree>,
+        source: &str,
+    ) -> Option<Vec<Group<'tree>>> {
+        (node.kind() == "object").then(|| {
+            vec![
+                node.named_children(&mut node.walk())
+                    .filter(|child| child.kind() == "pair")
+                    .filter_map(|pair| {
+                        let key = pair.child_by_field_name("key")?;
+                        Some((pair, key_text(key, source)))
+                    })
+                    .collect(),
+            ]
+        })
+    }
+
+    fn key_path_entries<'tree>(&self, node: Node<'tree>, source: &str) -> Option<Entries<'tree>> {
+        let children = node
+            .named_children(&mut node.walk())
+            .filter(|child| child.kind() != "comment")
+            .collect::<Vec<_>>();
+        match node.kind() {
+            "object" => Some(Entries::Map(
+                children
+                    .into_iter()
+                    .filter_map(|pair| {
+                        Some(Entry {
+                            key: vec![key_text(pair.child_by_field_name("key")?, source)],
+                            value: pair.child_by_field_name("value")?,
+                            section: false,
+                        })
+                    })
+                    .collect(),
+            )),
+            "array" => Some(Entries::Sequence(children)),
+            _ => None,
+        }
+    }
+
+    fn key_path_insertion(
+        &self,
+        node: Node<'_>,
+        source: &str,
+        key: &str,
+        value: &str,
+    ) -> Option<(usize, String)> {
+        if node.kind() != "object" {
+            return None;
+        }
+        let pair = pair(key, value)?;
+        let last = node
+            .named_children(&mut node.walk())
+            .filter(|child| child.kind() == "pair")
+            .last();
+        Some(match last {
+            Some(last) if last.start_position().row != node.start_position().row => (
+                last.end_byte(),
+                format!(",\n{}{pair}", indent_of(source, last.start_byte())),
+            ),
+            Some(last) => (last.end_byte(), format!(", {pair}")),
+            None => (node.start_byte() + 1, pair),
+        })
+    }
+
+    fn key_path_map(&self, key: &str, value: &str) -> Option<String> {
+        Some(format!("{{ {} }}", pair(key, value)?))
+    }
+
     fn build_edits<'language, 'editor>(
         &self,
         editor: &'editor Editor<'language>,
@@ -56,3 +209,63 @@ impl LanguageEditor for JsonEditor {
         Ok(edits)
     }
 }
+
+/// `key` and `value` as an object member
+fn pair(key: &str, value: &str) -> Option<String> {
+    Some(format!("{}: {value}", serde_json::to_string(key).ok()?))
+}
+
+/// `content` with the insides of its double-quoted strings removed, so that what's left is
+/// only punctuation, keys' quotes, and literals
+fn outside_strings(content: &str) -> String {
+    let mut outside = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in content.chars() {
+        if in_string {
+            match (escaped, c) {
+                (true, _) => escaped = false,
+                (false, '\\') => escaped = true,
+                (false, '"') => {
+                    in_string = false;
+                    outside.push(c);
+                }
+                _ => {}
+            }
+        } else {
+            in_string = c == '"';
+            outside.push(c);
+        }
+    }
+    outside
+}
+
+/// Remove commas that directly precede a closing bracket, which JSON does not permit
+fn remove_trailing_commas(content: &str) -> Option<String> {
+    let mut repaired = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut changed = false;
+
+    for c in content.chars() {
+        if in_string {
+            match (escaped, c) {
+                (true, _) => escaped = false,
+                (false, '\\') => escaped = true,
+                (false, '"') => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == '}' || c == ']' {
+            let before_whitespace = repaired.trim_end().len();
+            if repaired[..before_whitespace].ends_with(',') {
+                repaired.remove(before_whitespace - 1);
+                changed = true;
+            }
+        }
+        repaired.push(c);
+    }
+
+    changed.then_some(repaired)
+}
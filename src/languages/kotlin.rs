@@ -0,0 +1,70 @@
+use crate::languages::{LanguageCommon, LanguageName, traits::LanguageEditor};
+use anyhow::{Result, anyhow};
+use std::{
+    io::{Read, Write},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+pub fn language() -> LanguageCommon {
+    LanguageCommon {
+        name: LanguageName::Kotlin,
+        file_extensions: &["kt", "kts"],
+        language: tree_sitter_kotlin_ng::LANGUAGE.into(),
+        editor: Box::new(KotlinEditor),
+        validation_query: None,
+        validation_source: None,
+        public_api_query: None,
+        calls_query: None,
+        dynamic_name: None,
+        node_types: Some(tree_sitter_kotlin_ng::NODE_TYPES),
+        symbol_kinds: &[
+            ("class_declaration", "class"),
+            ("object_declaration", "object"),
+            ("companion_object", "object"),
+            ("enum_entry", "constant"),
+            ("function_declaration", "fun"),
+            ("property_declaration", "property"),
+        ],
+    }
+}
+
+struct KotlinEditor;
+
+impl LanguageEditor for KotlinEditor {
+    fn formatter(&self) -> Option<&'static str> {
+        Some("ktlint")
+    }
+
+    fn format_code(&self, source: &str, _file_path: &Path) -> Result<String> {
+        let mut child = Command::new("ktlint")
+            .args(["--format", "--stdin", "--log-level=error"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(source.as_bytes())?;
+            drop(stdin);
+        }
+
+        let mut stdout = String::new();
+        if let Some(mut out) = child.stdout.take() {
+            out.read_to_string(&mut stdout)?;
+        }
+
+        let mut stderr = String::new();
+        if let Some(mut err) = child.stderr.take() {
+            err.read_to_string(&mut stderr)?;
+        }
+
+        // ktlint also fails for lint violations it can't fix, like wildcard imports, after
+        // printing the formatted code, which isn't a reason to reject an edit
+        if child.wait()?.success() || !stdout.is_empty() {
+            Ok(stdout)
+        } else {
+            Err(anyhow!(stderr))
+        }
+    }
+}
@@ -1,14 +1,31 @@
+mod detection;
+pub mod dynamic;
+pub use detection::Guess;
+#[cfg(any(feature = "javascript", feature = "typescript", feature = "json"))]
 mod ecma_editor;
+#[cfg(feature = "java")]
+pub mod java;
+#[cfg(feature = "javascript")]
 pub mod javascript;
+#[cfg(feature = "json")]
 pub mod json;
+#[cfg(feature = "javascript")]
 pub mod jsx;
+#[cfg(feature = "kotlin")]
+pub mod kotlin;
 pub mod plain;
+#[cfg(feature = "python")]
 pub mod python;
+#[cfg(feature = "rust")]
 pub mod rust;
 pub mod toml;
 pub mod traits;
+#[cfg(feature = "typescript")]
 pub mod tsx;
+#[cfg(feature = "typescript")]
 pub mod typescript;
+#[cfg(feature = "yaml")]
+pub mod yaml;
 
 use anyhow::Result;
 use clap::ValueEnum;
@@ -16,7 +33,7 @@ use enum_map::{Enum, EnumMap, enum_map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     convert::Infallible,
     fmt::{self, Debug, Display, Formatter},
     path::Path,
@@ -28,11 +45,79 @@ use tree_sitter::{Language, Parser, Query};
 
 use crate::languages::traits::LanguageEditor;
 
+/// Call graph queries shared by JavaScript, TypeScript, and their JSX variants
+#[cfg(any(feature = "javascript", feature = "typescript"))]
+const ECMA_CALLS_QUERY: &str = include_str!("../../queries/ecma/calls.scm");
+
+#[cfg(feature = "javascript")]
+const ECMA_SYMBOL_KINDS: &[(&str, &str)] = &[
+    ("function_declaration", "function"),
+    ("generator_function_declaration", "function"),
+    ("class_declaration", "class"),
+    ("method_definition", "method"),
+    ("field_definition", "field"),
+];
+
+#[cfg(feature = "typescript")]
+const TYPESCRIPT_SYMBOL_KINDS: &[(&str, &str)] = &[
+    ("function_declaration", "function"),
+    ("generator_function_declaration", "function"),
+    ("function_signature", "function"),
+    ("class_declaration", "class"),
+    ("abstract_class_declaration", "class"),
+    ("method_definition", "method"),
+    ("method_signature", "method"),
+    ("public_field_definition", "field"),
+    ("interface_declaration", "interface"),
+    ("property_signature", "property"),
+    ("type_alias_declaration", "type"),
+    ("enum_declaration", "enum"),
+    ("internal_module", "namespace"),
+];
+
+/// Per-session choices of grammar for file extensions, like parsing `.js` files as JSX,
+/// consulted before a file's language is detected
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Dialects(BTreeMap<String, LanguageName>);
+
+impl Dialects {
+    /// The language chosen for files with `file_path`'s extension, if any
+    pub fn get(&self, file_path: &Path) -> Option<LanguageName> {
+        let extension = file_path.extension()?.to_str()?;
+        self.0.get(extension).copied()
+    }
+
+    /// Choose the language for an extension, or with None, return it to the default
+    pub fn set(&mut self, extension: &str, language: Option<LanguageName>) {
+        let extension = extension.trim_start_matches('.').to_string();
+        match language {
+            Some(language) => self.0.insert(extension, language),
+            None => self.0.remove(&extension),
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, LanguageName)> {
+        self.0
+            .iter()
+            .map(|(extension, language)| (extension.as_str(), *language))
+    }
+}
+
 /// Registry to manage all supported languages
 #[derive(Debug)]
 pub struct LanguageRegistry {
     languages: EnumMap<LanguageName, LanguageCommon>,
     extensions: HashMap<&'static str, LanguageName>,
+    /// Grammars loaded at runtime, see [`dynamic`]
+    dynamic: Vec<LanguageCommon>,
+    dynamic_extensions: HashMap<String, usize>,
+    /// What dynamic grammars were loaded from, which must outlive them
+    sources: Vec<dynamic::Source>,
 }
 
 #[derive(fieldwork::Fieldwork)]
@@ -45,6 +130,19 @@ pub struct LanguageCommon {
     language: Language,
     editor: Box<dyn LanguageEditor>,
     validation_query: Option<Query>,
+    /// The source of `validation_query`, for explaining its rules
+    validation_source: Option<&'static str>,
+    /// Captures each `@item` that belongs to a file's public API, for languages that have one
+    public_api_query: Option<Query>,
+    /// Captures function `@definition`s and `@call`s, with their names, for call graphs
+    calls_query: Option<Query>,
+    /// Node kinds that appear in a file outline, paired with a short human-readable label
+    symbol_kinds: &'static [(&'static str, &'static str)],
+    /// The grammar's node-types.json, which describes each kind of node, for node_kinds
+    node_types: Option<&'static str>,
+    /// The configured name of a grammar loaded at runtime, which has no [`LanguageName`] of its
+    /// own
+    dynamic_name: Option<String>,
 }
 
 impl Debug for LanguageCommon {
@@ -54,18 +152,26 @@ impl Debug for LanguageCommon {
             .field("file_extensions", &self.file_extensions)
             .field("language", &self.language)
             .field("validation_query", &self.validation_query)
+            .field("public_api_query", &self.public_api_query)
+            .field("calls_query", &self.calls_query)
+            .field("symbol_kinds", &self.symbol_kinds)
+            .field("dynamic_name", &self.dynamic_name)
             .finish()
     }
 }
 impl Display for LanguageCommon {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str(self.name.as_str())
+        f.write_str(self.dynamic_name.as_deref().unwrap_or(self.name.as_str()))
     }
 }
 
 impl LanguageCommon {
     pub fn tree_sitter_parser(&self) -> Result<Parser> {
         let mut parser = Parser::new();
+        #[cfg(feature = "wasm")]
+        if self.language.is_wasm() {
+            parser.set_wasm_store(dynamic::wasm_store()?)?;
+        }
         parser.set_language(self.tree_sitter_language())?;
         Ok(parser)
     }
@@ -99,6 +205,9 @@ pub enum LanguageName {
     Tsx,
     Python,
     Jsx,
+    Yaml,
+    Java,
+    Kotlin,
     #[serde(other)]
     Other,
 }
@@ -116,6 +225,9 @@ impl FromStr for LanguageName {
             "ts" | "typescript" => LanguageName::Typescript,
             "tsx" => LanguageName::Tsx,
             "py" | "python" => LanguageName::Python,
+            "yaml" | "yml" => LanguageName::Yaml,
+            "java" => LanguageName::Java,
+            "kotlin" | "kt" | "kts" => LanguageName::Kotlin,
             _ => LanguageName::Other,
         })
     }
@@ -133,17 +245,32 @@ impl Display for LanguageName {
     }
 }
 
+/// A built-in language if its cargo feature is enabled, or else the plaintext editor, which
+/// claims no file extensions
+macro_rules! built_in {
+    ($feature:literal, $language:expr) => {{
+        #[cfg(feature = $feature)]
+        let language = $language;
+        #[cfg(not(feature = $feature))]
+        let language = plain::language();
+        language
+    }};
+}
+
 impl LanguageRegistry {
     pub fn new() -> Result<Self> {
         let languages = enum_map! {
-            LanguageName::Rust => rust::language(),
-            LanguageName::Json => json::language(),
-            LanguageName::Toml => toml::language(),
-            LanguageName::Javascript => javascript::language(),
-            LanguageName::Typescript => typescript::language(),
-            LanguageName::Tsx => tsx::language(),
-            LanguageName::Python => python::language(),
-            LanguageName::Jsx => jsx::language(),
+            LanguageName::Rust => built_in!("rust", rust::language()),
+            LanguageName::Json => built_in!("json", json::language()),
+            LanguageName::Toml => built_in!("toml", toml::language()),
+            LanguageName::Javascript => built_in!("javascript", javascript::language()),
+            LanguageName::Typescript => built_in!("typescript", typescript::language()),
+            LanguageName::Tsx => built_in!("typescript", tsx::language()),
+            LanguageName::Python => built_in!("python", python::language()),
+            LanguageName::Jsx => built_in!("javascript", jsx::language()),
+            LanguageName::Yaml => built_in!("yaml", yaml::language()),
+            LanguageName::Java => built_in!("java", java::language()),
+            LanguageName::Kotlin => built_in!("kotlin", kotlin::language()),
             LanguageName::Other => plain::language(),
         };
 
@@ -152,29 +279,116 @@ impl LanguageRegistry {
             .flat_map(|(name, lang)| lang.file_extensions.iter().map(move |ext| (*ext, name)))
             .collect();
 
-        Ok(Self {
+        let mut registry = Self {
             languages,
             extensions,
-        })
+            dynamic: vec![],
+            dynamic_extensions: HashMap::new(),
+            sources: vec![],
+        };
+        if let Some(config) = dynamic::configured() {
+            registry.load_grammars(&config);
+        }
+        Ok(registry)
+    }
+
+    /// Load the grammars declared in a configuration file, logging any that can't be loaded so
+    /// that one broken grammar doesn't keep the server from starting
+    fn load_grammars(&mut self, config: &Path) {
+        let grammars = match dynamic::read_config(config) {
+            Ok(grammars) => grammars,
+            Err(error) => {
+                log::error!("not loading grammars from {}: {error}", config.display());
+                return;
+            }
+        };
+
+        for grammar in grammars {
+            match dynamic::load(&grammar) {
+                Ok((source, language)) => {
+                    log::info!(
+                        "loaded the {} grammar from {}",
+                        grammar.name,
+                        grammar.library
+                    );
+                    self.sources.push(source);
+                    self.register(&grammar.name, &grammar.extensions, language);
+                }
+                Err(error) => log::error!("not loading the {} grammar: {error}", grammar.name),
+            }
+        }
+    }
+
+    /// Add a grammar for files with the given extensions, which can't be ones a built-in
+    /// language already handles
+    fn register(&mut self, name: &str, extensions: &[String], language: Language) {
+        let index = self.dynamic.len();
+        self.dynamic.push(dynamic::language(name, language));
+        for extension in extensions {
+            let extension = extension.trim_start_matches('.');
+            if self.extensions.contains_key(extension) {
+                log::warn!(
+                    "the {name} grammar can't handle .{extension} files, which are built in"
+                );
+            } else {
+                self.dynamic_extensions.insert(extension.to_string(), index);
+            }
+        }
+    }
+
+    /// The grammar loaded at runtime for files with `file_path`'s extension, if any
+    pub fn dynamic_language(&self, file_path: &Path) -> Option<&LanguageCommon> {
+        let extension = file_path.extension()?.to_str()?;
+        self.dynamic_extensions
+            .get(extension)
+            .map(|index| &self.dynamic[*index])
     }
 
     pub fn get_language(&self, name: LanguageName) -> &LanguageCommon {
         &self.languages[name]
     }
 
+    /// Every built-in language
+    pub fn languages(&self) -> impl Iterator<Item = &LanguageCommon> {
+        self.languages.values()
+    }
+
     pub fn get_language_with_hint(
         &self,
         file_path: &Path,
         language_hint: Option<LanguageName>,
     ) -> Result<&LanguageCommon> {
+        if language_hint.is_none()
+            && let Some(language) = self.dynamic_language(file_path)
+        {
+            return Ok(language);
+        }
+
         let language_name = language_hint
             .or_else(|| self.detect_language_from_path(file_path))
+            .or_else(|| {
+                self.guess_language_from_content(file_path)
+                    .map(|guess| guess.language)
+            })
             .unwrap_or(LanguageName::Other);
         Ok(self.get_language(language_name))
     }
 
+    /// Detect a file's language from its extension, or for files without a recognized
+    /// extension, from a shebang or editor modeline in its content
     pub fn detect_language_from_path(&self, file_path: &Path) -> Option<LanguageName> {
-        let extension = file_path.extension()?.to_str()?;
-        self.extensions.get(extension).copied()
+        file_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| self.extensions.get(extension).copied())
+            .or_else(|| detection::sniff(file_path))
+    }
+
+    /// Guess the language of a file that [`detect_language_from_path`] can't place from the
+    /// keywords in its content
+    ///
+    /// [`detect_language_from_path`]: Self::detect_language_from_path
+    pub fn guess_language_from_content(&self, file_path: &Path) -> Option<Guess> {
+        detection::guess(file_path)
     }
 }
@@ -1,6 +1,7 @@
 use crate::{
     editor::{Edit, EditIterator, Editor},
     languages::{LanguageCommon, LanguageName, traits::LanguageEditor},
+    validation::SyntaxError,
 };
 use anyhow::Result;
 
@@ -11,13 +12,19 @@ pub fn language() -> LanguageCommon {
         language: tree_sitter_plain::LANGUAGE.into(),
         editor: Box::new(PlainEditor),
         validation_query: None,
+        validation_source: None,
+        public_api_query: None,
+        calls_query: None,
+        dynamic_name: None,
+        node_types: None,
+        symbol_kinds: &[],
     }
 }
 
 struct PlainEditor;
 
 impl LanguageEditor for PlainEditor {
-    fn collect_errors(&self, _tree: &tree_sitter::Tree, _content: &str) -> Vec<usize> {
+    fn collect_errors(&self, _tree: &tree_sitter::Tree, _content: &str) -> Vec<SyntaxError> {
         vec![]
     }
 
@@ -1,7 +1,10 @@
 use crate::{
+    docstrings::{body_start, definition, docstring},
     editor::{Edit, EditIterator, Editor},
-    indentation::Indentation,
+    indentation::{self, Indentation, find_line_start},
     languages::{LanguageCommon, LanguageName, traits::LanguageEditor},
+    recipes::{Parameter, Placement, Recipe},
+    repair::{Repair, generic_repairs},
 };
 use anyhow::{Result, anyhow};
 use std::{
@@ -9,14 +12,13 @@ use std::{
     path::Path,
     process::{Command, Stdio},
 };
-use tree_sitter::Query;
+use tree_sitter::{Node, Parser, Query};
 pub fn language() -> LanguageCommon {
     let language = tree_sitter_python::LANGUAGE.into();
-    let query = Query::new(
-        &language,
-        include_str!("../../queries/python/validation.scm"),
-    )
-    .unwrap();
+    let validation_source = include_str!("../../queries/python/validation.scm");
+    let query = Query::new(&language, validation_source).unwrap();
+    let calls_query =
+        Query::new(&language, include_str!("../../queries/python/calls.scm")).unwrap();
 
     LanguageCommon {
         name: LanguageName::Python,
@@ -24,12 +26,124 @@ pub fn language() -> LanguageCommon {
         language,
         editor: Box::new(PythonEditor),
         validation_query: Some(query),
+        validation_source: Some(validation_source),
+        public_api_query: None,
+        calls_query: Some(calls_query),
+        dynamic_name: None,
+        node_types: Some(tree_sitter_python::NODE_TYPES),
+        symbol_kinds: &[
+            ("function_definition", "function"),
+            ("class_definition", "class"),
+        ],
     }
 }
 
 struct PythonEditor;
 
+const RECIPES: &[Recipe] = &[Recipe {
+    name: "method",
+    description: "A method at the end of a class",
+    parameters: &[
+        Parameter {
+            name: "class",
+            description: "The name of the class",
+            default: None,
+        },
+        Parameter {
+            name: "signature",
+            description: "The method's name and parameters, like `get(self, key: str) -> int`",
+            default: None,
+        },
+        Parameter {
+            name: "body",
+            description: "The statements of the method",
+            default: Some("raise NotImplementedError"),
+        },
+    ],
+    placement: Placement::Body {
+        kind: "class_definition",
+        symbol: "class {{class}}",
+    },
+    template: "def {{signature}}:\n    {{body}}",
+}];
+
 impl LanguageEditor for PythonEditor {
+    fn recipes(&self) -> &'static [Recipe] {
+        RECIPES
+    }
+
+    fn repairs(&self, content: &str) -> Vec<Repair> {
+        let mut repairs = generic_repairs(content);
+        let mut added_colon = false;
+        let repaired = content
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_end();
+                if is_block_header(trimmed) && !trimmed.ends_with(':') {
+                    added_colon = true;
+                    format!("{trimmed}:")
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if added_colon {
+            let trailing_newline = if content.ends_with('\n') { "\n" } else { "" };
+            repairs.push((
+                "added missing colons to block statements",
+                format!("{repaired}{trailing_newline}"),
+            ));
+        }
+        repairs
+    }
+
+    fn pitfalls(&self, content: &str) -> Vec<&'static str> {
+        let mut pitfalls = vec![];
+        let lines = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>();
+        let indent = |line: &str| line.len() - line.trim_start().len();
+
+        if lines
+            .iter()
+            .any(|line| is_block_header(line.trim_end()) && !line.trim_end().ends_with(':'))
+        {
+            pitfalls.push(
+                "The header line of a `def`, `class`, `if`, `for`, `while`, `with`, or `try` \
+                 ends with `:`.",
+            );
+        }
+        if lines
+            .windows(2)
+            .any(|pair| pair[0].trim_end().ends_with(':') && indent(pair[1]) <= indent(pair[0]))
+        {
+            pitfalls.push(
+                "A block's body is indented further than its header line, and every line of the \
+                 body by the same amount. A block with nothing in it needs `pass`.",
+            );
+        }
+        let indentation = lines
+            .iter()
+            .map(|line| &line[..indent(line)])
+            .collect::<String>();
+        if indentation.contains(' ') && indentation.contains('\t') {
+            pitfalls.push("Indent with spaces or tabs, not both: Python rejects a mix.");
+        }
+        if let Some((first, rest)) = lines.split_first()
+            && rest.iter().any(|line| indent(line) < indent(first))
+        {
+            pitfalls.push(
+                "Write `content` indented relative to its own first line, which shouldn't be \
+                 indented more than the lines after it. The editor reindents the whole of it to \
+                 fit where it's placed.",
+            );
+        }
+        pitfalls
+    }
+
     fn build_edits<'language, 'editor>(
         &self,
         editor: &'editor Editor<'language>,
@@ -38,6 +152,15 @@ impl LanguageEditor for PythonEditor {
 
         let mut edits = edit_iterator.find_edits()?;
 
+        for edit in &mut edits {
+            if edit.position().end_byte.is_some()
+                && let Some([node]) = edit.nodes()
+                && let Some(content) = keep_docstring(*node, edit.source_code(), edit.content())
+            {
+                edit.set_content(content);
+            }
+        }
+
         let additional_edits = edits
             .iter()
             .filter_map(|edit| {
@@ -67,12 +190,16 @@ impl LanguageEditor for PythonEditor {
         edits.extend(additional_edits);
 
         for edit in &mut edits {
-            Self::adjust_indentation(edit);
+            indentation::adjust(edit, Indentation::Spaces(4));
         }
 
         Ok(edits)
     }
 
+    fn formatter(&self) -> Option<&'static str> {
+        Some("ruff")
+    }
+
     fn format_code(&self, source: &str, _file_path: &Path) -> Result<String> {
         let mut child = Command::new("ruff")
             .args(["format", "-"])
@@ -104,51 +231,105 @@ impl LanguageEditor for PythonEditor {
     }
 }
 
-impl PythonEditor {
-    fn adjust_indentation<'language, 'editor>(edit: &mut Edit<'editor, 'language>) {
-        let source_code = edit.source_code();
-        let mut start_byte = edit.start_byte();
-
-        let line_start = find_line_start(source_code, start_byte);
+/// Whether a line starts a compound statement, which ends with `:` and is followed by a block
+fn is_block_header(line: &str) -> bool {
+    matches!(
+        line.trim_start().split([' ', '(', ':']).next(),
+        Some(
+            "def"
+                | "class"
+                | "if"
+                | "elif"
+                | "else"
+                | "for"
+                | "while"
+                | "try"
+                | "except"
+                | "finally"
+                | "with"
+        )
+    )
+}
 
-        let line_end = source_code[start_byte..]
-            .find(|x: char| !x.is_whitespace() || x == '\n')
-            .map(|newline| start_byte + newline)
-            .unwrap_or(source_code.len());
+/// `content`, which replaces `node`, with the docstring `node` had added to it when it is a
+/// function or class with none of its own, so that rewriting a function's code doesn't drop its
+/// documentation
+fn keep_docstring(node: Node<'_>, source: &str, content: &str) -> Option<String> {
+    let old = docstring(node)?;
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_python::LANGUAGE.into())
+        .ok()?;
+    let tree = parser.parse(content, None)?;
+    let root = tree.root_node();
+    let replacement = match root.named_child_count() {
+        1 => root.named_child(0)?,
+        _ => return None,
+    };
+    if !matches!(
+        definition(replacement).kind(),
+        "function_definition" | "class_definition"
+    ) || docstring(replacement).is_some()
+    {
+        return None;
+    }
 
-        // Detect the file's indentation style
-        let file_indentation =
-            Indentation::determine(source_code).unwrap_or(Indentation::Spaces(4));
+    let first = body_start(replacement)?;
+    let line_start = content[..first.start_byte()].rfind('\n')? + 1;
+    let indentation = &content[line_start..first.start_byte()];
+    if !indentation.trim().is_empty() {
+        return None;
+    }
+    let old_line_start = find_line_start(source, old.start_byte());
+    let old_indentation = &source[old_line_start..old.start_byte()];
+    let kept = source[old.byte_range()]
+        .split('\n')
+        .enumerate()
+        .map(|(index, line)| match line.strip_prefix(old_indentation) {
+            Some(line) if index > 0 => format!("{indentation}{line}"),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(format!(
+        "{}{indentation}{kept}\n{}",
+        &content[..line_start],
+        &content[line_start..]
+    ))
+}
 
-        let reference_region = if let Some(nodes) = edit.nodes()
-            && let Some(first) = nodes.first()
-            && let Some(last) = nodes.last()
-        {
-            let line_start = find_line_start(source_code, first.start_byte());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            &source_code[line_start..last.end_byte()]
-        } else {
-            &source_code[line_start..line_end]
-        };
+    fn parse(source: &str) -> tree_sitter::Tree {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_python::LANGUAGE.into())
+            .unwrap();
+        parser.parse(source, None).unwrap()
+    }
 
-        let target_indentation_count = file_indentation.minimum(reference_region);
+    #[test]
+    fn replacing_a_function_keeps_its_docstring() {
+        let source = "class Parser:\n    def count(self, lines):\n        '''Count the records.\n\n        Blank lines are skipped.\n        '''\n        return len(lines)\n";
+        let tree = parse(source);
+        let class = tree.root_node().named_child(0).unwrap();
+        let method = body_start(class).unwrap();
+        assert_eq!(
+            &source[docstring(method).unwrap().byte_range()],
+            "'''Count the records.\n\n        Blank lines are skipped.\n        '''"
+        );
 
-        if source_code[line_start..start_byte].trim().is_empty() {
-            start_byte = line_start;
-        }
-        file_indentation.reindent(
-            target_indentation_count,
-            edit.content_mut(),
-            start_byte == line_start,
+        let content =
+            "def count(self, lines):\n    # non-blank\n    return sum(1 for line in lines if line)";
+        assert_eq!(
+            keep_docstring(method, source, content).unwrap(),
+            "def count(self, lines):\n    '''Count the records.\n\n    Blank lines are skipped.\n    '''\n    # non-blank\n    return sum(1 for line in lines if line)"
         );
 
-        edit.set_start_byte(start_byte);
+        // a replacement with a docstring of its own keeps that one
+        let documented = "def count(self, lines):\n    \"\"\"How many.\"\"\"\n    return 0";
+        assert_eq!(keep_docstring(method, source, documented), None);
     }
 }
-
-fn find_line_start(source_code: &str, start_byte: usize) -> usize {
-    source_code[..start_byte]
-        .rfind('\n')
-        .map(|pos| pos + 1) // +1 to get position after the newline
-        .unwrap_or(0) // If no newline found, start of file
-}
@@ -1,8 +1,13 @@
-use crate::editor::{Edit, EditIterator, Editor};
+use crate::{
+    editor::{Edit, EditIterator, Editor},
+    recipes::{Parameter, Placement, Recipe},
+    repair::{Repair, generic_repairs},
+};
 
 use super::{LanguageCommon, LanguageName, traits::LanguageEditor};
 use anyhow::{Result, anyhow};
 use std::{
+    env,
     io::{Read, Write},
     path::Path,
     process::{Command, Stdio},
@@ -11,10 +16,36 @@ use tree_sitter::{Node, Query};
 
 pub fn language() -> LanguageCommon {
     let language = tree_sitter_rust::LANGUAGE.into();
-    let query = Query::new(&language, include_str!("../../queries/rust/validation.scm")).unwrap();
+    let validation_source = include_str!("../../queries/rust/validation.scm");
+    let query = Query::new(&language, validation_source).unwrap();
+    let public_api_query =
+        Query::new(&language, include_str!("../../queries/rust/public_api.scm")).unwrap();
+    let calls_query = Query::new(&language, include_str!("../../queries/rust/calls.scm")).unwrap();
     LanguageCommon {
         language,
         validation_query: Some(query),
+        validation_source: Some(validation_source),
+        public_api_query: Some(public_api_query),
+        calls_query: Some(calls_query),
+        dynamic_name: None,
+        node_types: Some(tree_sitter_rust::NODE_TYPES),
+        symbol_kinds: &[
+            ("function_item", "fn"),
+            ("function_signature_item", "fn"),
+            ("struct_item", "struct"),
+            ("field_declaration", "field"),
+            ("enum_item", "enum"),
+            ("enum_variant", "variant"),
+            ("union_item", "union"),
+            ("trait_item", "trait"),
+            ("impl_item", "impl"),
+            ("mod_item", "mod"),
+            ("const_item", "const"),
+            ("static_item", "static"),
+            ("type_item", "type"),
+            ("associated_type", "type"),
+            ("macro_definition", "macro"),
+        ],
         editor: Box::new(RustEditor),
         name: LanguageName::Rust,
         file_extensions: &["rs"],
@@ -23,13 +54,117 @@ pub fn language() -> LanguageCommon {
 
 struct RustEditor;
 
+const RECIPES: &[Recipe] = &[
+    Recipe {
+        name: "test",
+        description: "A test function in the tests module",
+        parameters: &[
+            Parameter {
+                name: "name",
+                description: "The name of the test function",
+                default: None,
+            },
+            Parameter {
+                name: "body",
+                description: "The statements of the test",
+                default: Some("todo!()"),
+            },
+            Parameter {
+                name: "module",
+                description: "The module the test goes in",
+                default: Some("tests"),
+            },
+        ],
+        placement: Placement::Body {
+            kind: "mod_item",
+            symbol: "mod {{module}}",
+        },
+        template: "#[test]\nfn {{name}}() {\n    {{body}}\n}",
+    },
+    Recipe {
+        name: "method",
+        description: "A method at the end of an impl block",
+        parameters: &[
+            Parameter {
+                name: "impl",
+                description: "The type the impl block is for, like `Parser` or `Display for \
+                              Token`",
+                default: None,
+            },
+            Parameter {
+                name: "signature",
+                description: "Everything before the body, like `pub fn len(&self) -> usize`",
+                default: None,
+            },
+            Parameter {
+                name: "body",
+                description: "The statements of the method",
+                default: Some("todo!()"),
+            },
+        ],
+        placement: Placement::Body {
+            kind: "impl_item",
+            symbol: "impl {{impl}}",
+        },
+        template: "{{signature}} {\n    {{body}}\n}",
+    },
+];
+
 impl LanguageEditor for RustEditor {
+    fn recipes(&self) -> &'static [Recipe] {
+        RECIPES
+    }
+
+    fn repairs(&self, content: &str) -> Vec<Repair> {
+        let mut repairs = generic_repairs(content);
+        let trimmed = content.trim_end();
+        if !trimmed.contains('\n') && !trimmed.ends_with([';', '}', ',']) && !trimmed.is_empty() {
+            repairs.push(("added a missing semicolon", format!("{trimmed};")));
+        }
+        repairs
+    }
+
+    fn pitfalls(&self, content: &str) -> Vec<&'static str> {
+        let mut pitfalls = vec![];
+        let lines = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with("//"))
+            .collect::<Vec<_>>();
+        let continues = |line: &str| {
+            line.starts_with(['.', '?', '+', '-', '*', '/', '|', '&', '=', ')', ']'])
+                || line.starts_with("as ")
+        };
+        if lines.iter().enumerate().any(|(index, line)| {
+            line.starts_with("let ")
+                && !line.ends_with([';', '{', '(', '[', '=', ',', '.', '+', '-', '*', '|', '&'])
+                && lines.get(index + 1).is_some_and(|next| !continues(next))
+        }) {
+            pitfalls.push(
+                "Statements end with `;`, `let` bindings included. Only the last expression of a \
+                 block, the value it evaluates to, goes without one.",
+            );
+        }
+        if lines.windows(2).any(|pair| {
+            pair[0].contains("=>") && pair[1].contains("=>") && !pair[0].ends_with([',', '{', '}'])
+        }) {
+            pitfalls.push("Match arms whose body isn't a block are separated by `,`.");
+        }
+        pitfalls
+    }
+
+    fn formatter(&self) -> Option<&'static str> {
+        Some("rustfmt")
+    }
+
     fn format_code(&self, source: &str, _file_path: &Path) -> Result<String> {
         // let source = syn::parse_file(source)?;
         // Ok(prettyplease::unparse(&source))
 
+        // SEMANTIC_EDIT_RUST_EDITION picks the edition for crates that aren't on the latest one
+        let edition = env::var("SEMANTIC_EDIT_RUST_EDITION").unwrap_or_else(|_| "2024".into());
         let mut child = Command::new("rustfmt")
-            .args(["--emit", "stdout", "--edition", "2024"])
+            .args(["--emit", "stdout", "--edition", edition.trim()])
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -1,9 +1,21 @@
-use crate::languages::{LanguageCommon, LanguageName, traits::LanguageEditor};
-use anyhow::Result;
-use std::{ops::Range, path::Path};
-use taplo::rowan::{TextRange, TextSize};
-use tree_sitter::Tree;
+//! TOML editing, which needs the `toml` feature, and parsing of the TOML configuration files
+//! that are read regardless
 
+use anyhow::{Result, bail};
+#[cfg(feature = "toml")]
+use {
+    crate::{
+        key_path::{Entries, Entry, indent_of},
+        languages::{LanguageCommon, LanguageName, traits::LanguageEditor},
+        recipes::{Parameter, Placement, Recipe},
+        sort_keys::{Group, key_text},
+        validation::SyntaxError,
+    },
+    std::path::Path,
+    tree_sitter::{Node, Tree},
+};
+
+#[cfg(feature = "toml")]
 pub fn language() -> LanguageCommon {
     LanguageCommon {
         name: LanguageName::Toml,
@@ -11,24 +23,92 @@ pub fn language() -> LanguageCommon {
         language: tree_sitter_toml_ng::LANGUAGE.into(),
         editor: Box::new(TomlEditor::new()),
         validation_query: None,
+        validation_source: None,
+        public_api_query: None,
+        calls_query: None,
+        dynamic_name: None,
+        node_types: Some(tree_sitter_toml_ng::NODE_TYPES),
+        symbol_kinds: &[
+            ("table", "table"),
+            ("table_array_element", "table"),
+            ("pair", "key"),
+        ],
+    }
+}
+
+/// Parse a TOML document as JSON, for deserializing configuration written in TOML
+pub fn to_json(source: &str) -> Result<serde_json::Value> {
+    let parse = taplo::parser::parse(source);
+    if let Some(error) = parse.errors.first() {
+        bail!("{error}");
     }
+    Ok(serde_json::to_value(parse.into_dom())?)
 }
 
+#[cfg(feature = "toml")]
 pub struct TomlEditor;
 
+#[cfg(feature = "toml")]
 impl Default for TomlEditor {
     fn default() -> Self {
         Self::new()
     }
 }
 
+#[cfg(feature = "toml")]
 impl TomlEditor {
     pub fn new() -> Self {
         Self
     }
 }
 
+/// The parameters of a Cargo.toml dependency
+#[cfg(feature = "toml")]
+const DEPENDENCY: &[Parameter] = &[
+    Parameter {
+        name: "name",
+        description: "The crate, like `serde`",
+        default: None,
+    },
+    Parameter {
+        name: "version",
+        description: "The version requirement, like `1.0`",
+        default: Some("*"),
+    },
+];
+
+#[cfg(feature = "toml")]
+const RECIPES: &[Recipe] = &[
+    Recipe {
+        name: "dependency",
+        description: "A dependency in a Cargo.toml, or its version if it's already there",
+        parameters: DEPENDENCY,
+        placement: Placement::Entry {
+            path: "dependencies.\"{{name}}\"",
+        },
+        template: "\"{{version}}\"",
+    },
+    Recipe {
+        name: "dev_dependency",
+        description: "A dependency of a Cargo.toml's tests, examples, and benchmarks",
+        parameters: DEPENDENCY,
+        placement: Placement::Entry {
+            path: "dev-dependencies.\"{{name}}\"",
+        },
+        template: "\"{{version}}\"",
+    },
+];
+
+#[cfg(feature = "toml")]
 impl LanguageEditor for TomlEditor {
+    fn recipes(&self) -> &'static [Recipe] {
+        RECIPES
+    }
+
+    fn formatter(&self) -> Option<&'static str> {
+        Some("taplo")
+    }
+
     fn format_code(&self, source: &str, _file_path: &Path) -> Result<String> {
         Ok(taplo::formatter::format(
             source,
@@ -36,43 +116,134 @@ impl LanguageEditor for TomlEditor {
         ))
     }
 
-    fn collect_errors(&self, _tree: &Tree, content: &str) -> Vec<usize> {
-        let converter = LineConverter::new(content);
-
+    fn collect_errors(&self, _tree: &Tree, content: &str) -> Vec<SyntaxError> {
         taplo::parser::parse(content)
             .errors
             .into_iter()
-            .flat_map(|error| converter.range_to_lines(error.range))
+            .map(|error| {
+                let range = usize::from(error.range.start())..usize::from(error.range.end());
+                SyntaxError::new(content, range)
+            })
             .collect()
     }
-}
 
-struct LineConverter {
-    newline_positions: Vec<usize>,
-}
+    fn sortable_entries<'tree>(
+        &self,
+        node: Node<'tree>,
+        source: &str,
+    ) -> Option<Vec<Group<'tree>>> {
+        let children = node.named_children(&mut node.walk()).collect::<Vec<_>>();
+        let keyed = |kind: &str| -> Group<'tree> {
+            children
+                .iter()
+                .filter(|child| child.kind() == kind)
+                .filter_map(|child| Some((*child, key_text(child.named_child(0)?, source))))
+                .collect()
+        };
+        match node.kind() {
+            "table" | "table_array_element" | "inline_table" => Some(vec![keyed("pair")]),
+            // the subtables of an element of an array of tables follow it, so tables are only
+            // reordered where there are no arrays of tables
+            "document"
+                if children
+                    .iter()
+                    .any(|child| child.kind() == "table_array_element") =>
+            {
+                Some(vec![keyed("pair")])
+            }
+            "document" => Some(vec![keyed("pair"), keyed("table")]),
+            _ => None,
+        }
+    }
 
-impl LineConverter {
-    fn new(text: &str) -> Self {
-        let newline_positions = std::iter::once(0)
-            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
-            .chain(std::iter::once(text.len())) // End of file
-            .collect();
+    fn key_path_entries<'tree>(&self, node: Node<'tree>, source: &str) -> Option<Entries<'tree>> {
+        let children = node
+            .named_children(&mut node.walk())
+            .filter(|child| child.kind() != "comment")
+            .collect::<Vec<_>>();
+        let entry = |child: Node<'tree>| -> Option<Entry<'tree>> {
+            let key = key_parts(child.named_child(0)?, source);
+            match child.kind() {
+                "pair" => Some(Entry {
+                    key,
+                    value: child.named_child(1)?,
+                    section: false,
+                }),
+                "table" | "table_array_element" => Some(Entry {
+                    key,
+                    value: child,
+                    section: true,
+                }),
+                _ => None,
+            }
+        };
+        match node.kind() {
+            "document" | "table" | "table_array_element" | "inline_table" => Some(Entries::Map(
+                children.into_iter().filter_map(entry).collect(),
+            )),
+            "array" => Some(Entries::Sequence(children)),
+            _ => None,
+        }
+    }
 
-        Self { newline_positions }
+    fn key_path_insertion(
+        &self,
+        node: Node<'_>,
+        source: &str,
+        key: &str,
+        value: &str,
+    ) -> Option<(usize, String)> {
+        let pair = pair(key, value)?;
+        // a document's own pairs come before its first table
+        let last = node
+            .named_children(&mut node.walk())
+            .take_while(|child| !child.kind().starts_with("table"))
+            .filter(|child| child.kind() == "pair")
+            .last();
+        Some(match (node.kind(), last) {
+            ("inline_table", Some(last)) => (last.end_byte(), format!(", {pair}")),
+            ("inline_table", None) => (node.start_byte() + 1, format!(" {pair} ")),
+            (_, Some(last)) => (
+                last.end_byte(),
+                format!("\n{}{pair}", indent_of(source, last.start_byte())),
+            ),
+            ("document", None) => (node.start_byte(), format!("{pair}\n")),
+            (_, None) => {
+                let header_end = source[node.start_byte()..]
+                    .find('\n')
+                    .map_or(node.end_byte(), |index| node.start_byte() + index);
+                (header_end, format!("\n{pair}"))
+            }
+        })
     }
 
-    fn textsize_to_line(&self, offset: TextSize) -> usize {
-        let byte_offset = usize::from(offset); // Safe conversion
-        match self.newline_positions.binary_search(&byte_offset) {
-            Ok(line) => line + 1,
-            Err(line) => line,
-        }
+    fn key_path_map(&self, key: &str, value: &str) -> Option<String> {
+        Some(format!("{{ {} }}", pair(key, value)?))
     }
+}
 
-    fn range_to_lines(&self, range: TextRange) -> Range<usize> {
-        Range {
-            start: self.textsize_to_line(range.start()),
-            end: self.textsize_to_line(range.end()),
-        }
+/// `key` and `value` as a key/value pair, with the key quoted unless it's bare
+#[cfg(feature = "toml")]
+fn pair(key: &str, value: &str) -> Option<String> {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    Some(if bare {
+        format!("{key} = {value}")
+    } else {
+        format!("{} = {value}", serde_json::to_string(key).ok()?)
+    })
+}
+
+/// The parts of a key, which a dotted key like `dependencies.serde` has more than one of
+#[cfg(feature = "toml")]
+fn key_parts(key: Node<'_>, source: &str) -> Vec<String> {
+    if key.kind() == "dotted_key" {
+        key.named_children(&mut key.walk())
+            .flat_map(|part| key_parts(part, source))
+            .collect()
+    } else {
+        vec![key_text(key, source)]
     }
 }
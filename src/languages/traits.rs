@@ -4,16 +4,20 @@ use anyhow::Result;
 
 use tree_sitter::{Node, Tree};
 
-use crate::editor::{Edit, EditIterator, Editor};
+use crate::{
+    editor::{Edit, EditIterator, Editor},
+    key_path::Entries,
+    recipes::Recipe,
+    repair::{self, Repair},
+    sort_keys::Group,
+    validation::SyntaxError,
+};
 
 /// Trait for language-specific operations like validation and formatting
 pub trait LanguageEditor: Send + Sync {
-    /// Collect syntax error line numbers from a tree-sitter parse tree
-    fn collect_errors(&self, tree: &Tree, _content: &str) -> Vec<usize> {
-        collect_errors(tree)
-            .into_iter()
-            .map(|node| node.start_position().row)
-            .collect()
+    /// Collect syntax errors from a tree-sitter parse tree of `content`
+    fn collect_errors(&self, tree: &Tree, content: &str) -> Vec<SyntaxError> {
+        syntax_errors(tree, content)
     }
 
     /// Format code according to language conventions
@@ -22,6 +26,79 @@ pub trait LanguageEditor: Send + Sync {
         Ok(source.to_string())
     }
 
+    /// The name of the formatter `format_code` runs, if it runs one
+    fn formatter(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Propose repaired versions of edit content that failed validation, for common mistakes
+    /// like a missing closing brace or an unterminated string
+    fn repairs(&self, content: &str) -> Vec<Repair> {
+        repair::generic_repairs(content)
+    }
+
+    /// Hints about mistakes that commonly make edits in this language fail, for those that edit
+    /// content which failed validation appears to make
+    fn pitfalls(&self, content: &str) -> Vec<&'static str> {
+        let _ = content;
+        vec![]
+    }
+
+    /// The entries sort_keys reorders if `node` is an object or table, in groups that are each
+    /// sorted separately, or None for nodes that don't hold keys
+    fn sortable_entries<'tree>(
+        &self,
+        node: Node<'tree>,
+        source: &str,
+    ) -> Option<Vec<Group<'tree>>> {
+        let _ = (node, source);
+        None
+    }
+
+    /// The entries set_key walks through if `node` is a map or sequence, or None for other
+    /// nodes
+    fn key_path_entries<'tree>(&self, node: Node<'tree>, source: &str) -> Option<Entries<'tree>> {
+        let _ = (node, source);
+        None
+    }
+
+    /// Where and what to insert to add `key` with `value` to the map `node`, for set_key
+    fn key_path_insertion(
+        &self,
+        node: Node<'_>,
+        source: &str,
+        key: &str,
+        value: &str,
+    ) -> Option<(usize, String)> {
+        let _ = (node, source, key, value);
+        None
+    }
+
+    /// A map holding only `key` with `value`, written inline, which set_key adds for a missing
+    /// key that the key path goes through
+    fn key_path_map(&self, key: &str, value: &str) -> Option<String> {
+        let _ = (key, value);
+        None
+    }
+
+    /// Where and what to insert to add `value` to the end of the sequence `node`, for set_key,
+    /// or None to add it after the last item with a comma as in a JSON array
+    fn key_path_append(
+        &self,
+        node: Node<'_>,
+        source: &str,
+        value: &str,
+    ) -> Option<(usize, String)> {
+        let _ = (node, source, value);
+        None
+    }
+
+    /// Templates for common insertions in this language, which list_recipes describes and
+    /// apply_recipe fills in
+    fn recipes(&self) -> &'static [Recipe] {
+        &[]
+    }
+
     fn build_edits<'language, 'editor>(
         &self,
         editor: &'editor Editor<'language>,
@@ -30,6 +107,14 @@ pub trait LanguageEditor: Send + Sync {
     }
 }
 
+/// The error and missing nodes in `tree`, each with a hint of what the grammar expected there
+pub fn syntax_errors(tree: &Tree, content: &str) -> Vec<SyntaxError> {
+    collect_errors(tree)
+        .into_iter()
+        .map(|node| SyntaxError::new(content, node.byte_range()).with_hint(expected(node)))
+        .collect()
+}
+
 fn collect_errors<'tree>(tree: &'tree Tree) -> Vec<Node<'tree>> {
     let mut errors = vec![];
     collect_errors_recursive(tree.root_node(), &mut errors);
@@ -42,8 +127,61 @@ fn collect_errors_recursive<'tree>(node: Node<'tree>, errors: &mut Vec<Node<'tre
         errors.push(node);
     }
 
-    // Recursively check all children
-    for child in node.children(&mut node.walk()) {
+    // Recursively check the children that have errors somewhere within them
+    for child in node
+        .children(&mut node.walk())
+        .filter(|child| child.has_error())
+    {
         collect_errors_recursive(child, errors);
     }
 }
+
+/// Most alternatives worth listing in a hint; past this, the grammar accepts nearly anything here
+/// and the list would not help
+const MAX_EXPECTED: usize = 6;
+
+/// What the grammar would have accepted where an error node starts, from the parse state
+/// tree-sitter was in just before it
+fn expected(node: Node<'_>) -> Option<String> {
+    if node.is_missing() {
+        return Some(if node.is_named() {
+            format!("missing {}", node.kind().replace('_', " "))
+        } else {
+            format!("missing `{}`", node.kind())
+        });
+    }
+
+    let state = match node.prev_sibling() {
+        Some(previous) => previous.next_parse_state(),
+        None => node.parent()?.parse_state(),
+    };
+    let language = node.language();
+    let mut expected = language
+        .lookahead_iterator(state)?
+        .filter(|symbol| {
+            language.node_kind_is_visible(*symbol) && !language.node_kind_is_supertype(*symbol)
+        })
+        .filter_map(|symbol| {
+            let kind = language.node_kind_for_id(symbol)?;
+            if kind.is_empty() || kind == "ERROR" || kind == "end" {
+                None
+            } else if language.node_kind_is_named(symbol) {
+                Some(kind.replace('_', " "))
+            } else {
+                Some(format!("`{kind}`"))
+            }
+        })
+        .collect::<Vec<_>>();
+    expected.sort();
+    expected.dedup();
+
+    match expected.len() {
+        0 => None,
+        1 => Some(format!("expected {}", expected[0])),
+        len if len <= MAX_EXPECTED => {
+            let last = expected.pop()?;
+            Some(format!("expected {} or {last}", expected.join(", ")))
+        }
+        _ => None,
+    }
+}
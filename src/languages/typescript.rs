@@ -1,12 +1,21 @@
 use super::{LanguageCommon, LanguageName};
 use crate::languages::ecma_editor::EcmaEditor;
+use tree_sitter::Query;
 
 pub fn language() -> LanguageCommon {
+    let language = tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into();
+    let calls_query = Query::new(&language, super::ECMA_CALLS_QUERY).unwrap();
     LanguageCommon {
         name: LanguageName::Typescript,
         file_extensions: &["ts"],
-        language: tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+        language,
         editor: Box::new(EcmaEditor),
         validation_query: None,
+        validation_source: None,
+        public_api_query: None,
+        calls_query: Some(calls_query),
+        dynamic_name: None,
+        node_types: Some(tree_sitter_typescript::TYPESCRIPT_NODE_TYPES),
+        symbol_kinds: super::TYPESCRIPT_SYMBOL_KINDS,
     }
 }
@@ -0,0 +1,290 @@
+use crate::{
+    editor::{Edit, EditIterator, Editor},
+    indentation::{self, Indentation},
+    key_path::{Entries, Entry},
+    languages::{
+        LanguageCommon, LanguageName,
+        traits::{LanguageEditor, syntax_errors},
+    },
+    sort_keys::key_text,
+    validation::SyntaxError,
+};
+use std::collections::BTreeMap;
+use tree_sitter::{Node, Tree};
+
+pub fn language() -> LanguageCommon {
+    LanguageCommon {
+        name: LanguageName::Yaml,
+        file_extensions: &["yaml", "yml"],
+        language: tree_sitter_yaml::LANGUAGE.into(),
+        editor: Box::new(YamlEditor),
+        validation_query: None,
+        validation_source: None,
+        public_api_query: None,
+        calls_query: None,
+        dynamic_name: None,
+        node_types: Some(tree_sitter_yaml::NODE_TYPES),
+        symbol_kinds: &[("block_mapping_pair", "key")],
+    }
+}
+
+pub struct YamlEditor;
+
+impl LanguageEditor for YamlEditor {
+    /// Syntax errors, along with indentation that uses tabs and keys repeated in a mapping,
+    /// which parse but which YAML doesn't allow
+    fn collect_errors(&self, tree: &Tree, content: &str) -> Vec<SyntaxError> {
+        let mut errors = syntax_errors(tree, content);
+        errors.extend(tab_indentation(tree, content));
+        duplicate_keys(tree.root_node(), content, &mut errors);
+        errors.sort_by_key(|error| error.range.start);
+        errors
+    }
+
+    fn key_path_entries<'tree>(&self, node: Node<'tree>, source: &str) -> Option<Entries<'tree>> {
+        let node = collection(node)?;
+        let children = node
+            .named_children(&mut node.walk())
+            .filter(|child| child.kind() != "comment")
+            .collect::<Vec<_>>();
+        match node.kind() {
+            "block_mapping" | "flow_mapping" => Some(Entries::Map(
+                children
+                    .into_iter()
+                    .filter_map(|pair| {
+                        Some(Entry {
+                            key: vec![key_text(pair.child_by_field_name("key")?, source)],
+                            value: pair.child_by_field_name("value")?,
+                            section: false,
+                        })
+                    })
+                    .collect(),
+            )),
+            "block_sequence" => Some(Entries::Sequence(
+                children
+                    .into_iter()
+                    .filter_map(|item| item.named_child(0))
+                    .collect(),
+            )),
+            "flow_sequence" => Some(Entries::Sequence(children)),
+            _ => None,
+        }
+    }
+
+    fn key_path_insertion(
+        &self,
+        node: Node<'_>,
+        source: &str,
+        key: &str,
+        value: &str,
+    ) -> Option<(usize, String)> {
+        let node = collection(node)?;
+        let pair = pair(key, value)?;
+        let last = node
+            .named_children(&mut node.walk())
+            .filter(|child| child.kind() != "comment")
+            .last();
+        match (node.kind(), last) {
+            ("block_mapping", Some(last)) => {
+                Some((end_of(last, source), format!("\n{}{pair}", column_of(last))))
+            }
+            ("flow_mapping", Some(last)) => Some((last.end_byte(), format!(", {pair}"))),
+            ("flow_mapping", None) => Some((node.start_byte() + 1, pair)),
+            _ => None,
+        }
+    }
+
+    fn key_path_map(&self, key: &str, value: &str) -> Option<String> {
+        Some(format!("{{ {} }}", pair(key, value)?))
+    }
+
+    fn key_path_append(
+        &self,
+        node: Node<'_>,
+        source: &str,
+        value: &str,
+    ) -> Option<(usize, String)> {
+        let node = collection(node)?;
+        if node.kind() != "block_sequence" {
+            return None;
+        }
+        let last = node
+            .named_children(&mut node.walk())
+            .filter(|child| child.kind() == "block_sequence_item")
+            .last()?;
+        Some((
+            end_of(last, source),
+            format!("\n{}- {value}", column_of(last)),
+        ))
+    }
+
+    /// Edits reindented to the nodes they're placed by, since a line's indentation decides what
+    /// mapping or sequence it belongs to. An edit at an exact position, like one set_key
+    /// stages, is left as it is.
+    fn build_edits<'language, 'editor>(
+        &self,
+        editor: &'editor Editor<'language>,
+    ) -> Result<Vec<Edit<'editor, 'language>>, String> {
+        let mut edits = EditIterator::new(editor).find_edits()?;
+        if editor.staged_edit().is_none() {
+            for edit in &mut edits {
+                indentation::adjust(edit, Indentation::Spaces(2));
+                // an insert at the start of a line, like after a node that ends with its line
+                // break, needs one of its own to keep the next line apart
+                let start = edit.start_byte();
+                if edit.position().end_byte.is_none()
+                    && (start == 0 || edit.source_code()[..start].ends_with('\n'))
+                    && !edit.content().ends_with('\n')
+                {
+                    edit.content_mut().to_mut().push('\n');
+                }
+            }
+        }
+        Ok(edits)
+    }
+}
+
+/// `key` and `value` as a mapping entry, with the key quoted unless it's a plain scalar
+fn pair(key: &str, value: &str) -> Option<String> {
+    let plain = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'));
+    Some(if plain {
+        format!("{key}: {value}")
+    } else {
+        format!("{}: {value}", serde_json::to_string(key).ok()?)
+    })
+}
+
+/// The mapping or sequence `node` is, or that a block or flow node holds beside its anchor or tag
+fn collection(node: Node<'_>) -> Option<Node<'_>> {
+    match node.kind() {
+        "block_mapping" | "flow_mapping" | "block_sequence" | "flow_sequence" => Some(node),
+        "block_node" | "flow_node" => node
+            .named_children(&mut node.walk())
+            .find(|child| !matches!(child.kind(), "anchor" | "tag" | "comment"))
+            .and_then(collection),
+        _ => None,
+    }
+}
+
+/// Where `node` ends, before the line breaks a block scalar in it keeps
+fn end_of(node: Node<'_>, source: &str) -> usize {
+    node.start_byte() + source[node.byte_range()].trim_end().len()
+}
+
+/// Spaces up to the column `node` starts at, which is its indentation within a sequence item
+/// like `- name: build` too
+fn column_of(node: Node<'_>) -> String {
+    " ".repeat(node.start_position().column)
+}
+
+/// Lines indented with tabs, which YAML only allows inside block scalars and quoted strings
+fn tab_indentation(tree: &Tree, content: &str) -> Vec<SyntaxError> {
+    let mut errors = vec![];
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let indentation = line.len() - line.trim_start_matches([' ', '\t']).len();
+        let start = offset;
+        offset += line.len();
+        if !line[..indentation].contains('\t') || line.trim().is_empty() {
+            continue;
+        }
+        let text = start + indentation;
+        let in_scalar = tree
+            .root_node()
+            .descendant_for_byte_range(text, text)
+            .is_some_and(|node| {
+                matches!(
+                    node.kind(),
+                    "block_scalar" | "double_quote_scalar" | "single_quote_scalar"
+                ) && node.start_byte() < text
+            });
+        if !in_scalar {
+            errors.push(SyntaxError::new(content, start..text).with_hint(Some(
+                "tabs can't indent YAML, indent with spaces".to_string(),
+            )));
+        }
+    }
+    errors
+}
+
+/// Keys that appear more than once in the same mapping, which YAML doesn't allow and which most
+/// parsers resolve by silently keeping one of the values
+fn duplicate_keys(node: Node<'_>, content: &str, errors: &mut Vec<SyntaxError>) {
+    if matches!(node.kind(), "block_mapping" | "flow_mapping") {
+        let mut seen = BTreeMap::new();
+        for key in node
+            .named_children(&mut node.walk())
+            .filter_map(|pair| pair.child_by_field_name("key"))
+        {
+            let text = key_text(key, content);
+            if text == "<<" {
+                continue;
+            }
+            match seen.get(&text) {
+                Some(line) => {
+                    errors.push(SyntaxError::new(content, key.byte_range()).with_hint(Some(
+                        format!("`{text}` is already a key of this mapping, on line {line}"),
+                    )))
+                }
+                None => {
+                    seen.insert(text, key.start_position().row + 1);
+                }
+            }
+        }
+    }
+    for child in node.named_children(&mut node.walk()) {
+        duplicate_keys(child, content, errors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(source: &str) -> Vec<String> {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_yaml::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        YamlEditor
+            .collect_errors(&tree, source)
+            .into_iter()
+            .map(|error| format!("{}: {}", error.line + 1, error.hint.unwrap_or_default()))
+            .collect()
+    }
+
+    #[test]
+    fn valid_document() {
+        let source = "defaults: &defaults\n  adapter: postgres\ndevelopment:\n  <<: *defaults\n  \
+                      database: dev\nscript: |\n  \techo \"tabs are fine in here\"\n";
+        assert_eq!(errors(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let source = "name: app\nversion: 1\nname: other\nnested: { a: 1, a: 2 }\n";
+        assert_eq!(
+            errors(source),
+            vec![
+                "3: `name` is already a key of this mapping, on line 1",
+                "4: `a` is already a key of this mapping, on line 4",
+            ]
+        );
+    }
+
+    #[test]
+    fn tab_indentation() {
+        let source = "jobs:\n\tbuild: true\n";
+        assert!(
+            errors(source)
+                .iter()
+                .any(|error| error == "2: tabs can't indent YAML, indent with spaces"),
+            "{:?}",
+            errors(source)
+        );
+    }
+}
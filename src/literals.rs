@@ -0,0 +1,153 @@
+//! Whether a match lies inside a string literal or comment, where text that looks like code is
+//! more often a mention of it, like a log message naming a function
+
+use crate::languages::{LanguageCommon, LanguageName};
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Range,
+};
+use tree_sitter::{Node, Tree};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Literal {
+    String,
+    Comment,
+}
+
+impl Display for Literal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Literal::String => "a string literal",
+            Literal::Comment => "a comment",
+        })
+    }
+}
+
+/// The kind of literal `range` lies inside, if any. A match that starts a literal, like an anchor
+/// of `// TODO` or `"SELECT`, targets the literal itself and so isn't inside it, and neither is
+/// code interpolated into a string. Strings are data in JSON, TOML, and YAML, so only their
+/// comments count.
+pub fn enclosing(language: &LanguageCommon, tree: &Tree, range: Range<usize>) -> Option<Literal> {
+    let strings_are_data = matches!(
+        language.name(),
+        LanguageName::Json | LanguageName::Toml | LanguageName::Yaml
+    );
+    let mut node = tree
+        .root_node()
+        .descendant_for_byte_range(range.start, range.end)?;
+    loop {
+        let kind = node.kind();
+        if kind.contains("interpolat") || kind.contains("substitution") {
+            return None;
+        }
+        let literal = if kind.contains("comment") {
+            Some(Literal::Comment)
+        } else if is_string(node) && !strings_are_data {
+            Some(Literal::String)
+        } else {
+            None
+        };
+        // the contents of a string can be a node of their own, starting after the quote
+        if let Some(literal) = literal
+            && node.start_byte() < range.start
+        {
+            return Some(literal);
+        }
+        node = node.parent()?;
+    }
+}
+
+fn is_string(node: Node<'_>) -> bool {
+    let kind = node.kind();
+    kind.contains("string")
+        || kind.ends_with("char_literal")
+        || kind == "character_literal"
+        || kind == "text_block"
+}
+
+/// Keep the `ranges` inside literals when `inside` is true, or those outside them when it's
+/// false
+pub fn filter(
+    language: &LanguageCommon,
+    tree: &Tree,
+    ranges: impl IntoIterator<Item = Range<usize>>,
+    inside: bool,
+) -> Vec<Range<usize>> {
+    ranges
+        .into_iter()
+        .filter(|range| enclosing(language, tree, range.clone()).is_some() == inside)
+        .collect()
+}
+
+/// Why nothing is left to target once `inside_literals` has filtered the matches of `what`, like
+/// an anchor or pattern
+pub fn none_left(what: &str, inside: bool) -> String {
+    if inside {
+        format!(
+            "{what} is only found outside string literals and comments, and `inside_literals` \
+             is true"
+        )
+    } else {
+        format!(
+            "{what} is only found inside string literals or comments, and `inside_literals` is \
+             false"
+        )
+    }
+}
+
+#[cfg(all(test, any(feature = "rust", feature = "python", feature = "json")))]
+mod tests {
+    use super::*;
+    use crate::languages::LanguageRegistry;
+
+    fn enclosing_match(language: LanguageName, source: &str, text: &str) -> Option<Literal> {
+        let registry = LanguageRegistry::new().unwrap();
+        let language = registry.get_language(language);
+        let tree = language
+            .tree_sitter_parser()
+            .unwrap()
+            .parse(source, None)
+            .unwrap();
+        let start = source.find(text).unwrap();
+        enclosing(language, &tree, start..start + text.len())
+    }
+
+    #[test]
+    #[cfg(feature = "rust")]
+    fn rust_strings_and_comments() {
+        let rust = "fn save() {\n    // save the user\n    log(\"save failed\");\n    \
+                    save_user();\n}\n";
+        assert_eq!(
+            enclosing_match(LanguageName::Rust, rust, "save the"),
+            Some(Literal::Comment)
+        );
+        assert_eq!(
+            enclosing_match(LanguageName::Rust, rust, "save failed"),
+            Some(Literal::String)
+        );
+        assert_eq!(enclosing_match(LanguageName::Rust, rust, "save_user"), None);
+        assert_eq!(enclosing_match(LanguageName::Rust, rust, "// save"), None);
+        assert_eq!(enclosing_match(LanguageName::Rust, rust, "\"save"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "python")]
+    fn python_interpolations_are_code() {
+        let python = "name = f\"hello {user.name}\"\n";
+        assert_eq!(
+            enclosing_match(LanguageName::Python, python, "hello"),
+            Some(Literal::String)
+        );
+        assert_eq!(
+            enclosing_match(LanguageName::Python, python, "user.name"),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json_strings_are_data() {
+        let json = "{\n  \"name\": \"save\"\n}\n";
+        assert_eq!(enclosing_match(LanguageName::Json, json, "save"), None);
+    }
+}
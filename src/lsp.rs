@@ -0,0 +1,943 @@
+use crate::languages::LanguageName;
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Value, json};
+use std::{
+    env,
+    fmt::{self, Display, Formatter},
+    io::{BufRead, BufReader, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    process::{ChildStdin, Command, Stdio},
+    sync::mpsc::{Receiver, RecvTimeoutError, channel},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long to wait for a language server to report diagnostics when
+/// `SEMANTIC_EDIT_LSP_TIMEOUT_MS` is not set
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to keep listening after diagnostics arrive, since servers often publish a quick
+/// first pass and then a more complete one
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// Files whose presence marks the root of a project, in the order they are looked for
+const PROJECT_MARKERS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "tsconfig.json",
+    "pyproject.toml",
+    "setup.py",
+    ".git",
+];
+
+/// A language server command configured for a language with `SEMANTIC_EDIT_LSP_<LANGUAGE>`,
+/// like `SEMANTIC_EDIT_LSP_RUST=rust-analyzer` or
+/// `SEMANTIC_EDIT_LSP_TYPESCRIPT="typescript-language-server --stdio"`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageServer {
+    program: String,
+    args: Vec<String>,
+}
+
+/// An error or warning reported by a language server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// 1-indexed line
+    pub line: usize,
+    /// 1-indexed column, in UTF-16 code units as the language server protocol counts them
+    pub column: usize,
+    pub severity: &'static str,
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{} {}", self.line, self.column, self.severity)?;
+        if let Some(code) = &self.code {
+            write!(f, "[{code}]")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl LanguageServer {
+    /// The language server configured for a language, if any
+    pub fn configured(language: LanguageName) -> Option<Self> {
+        let variable = format!("SEMANTIC_EDIT_LSP_{}", language.to_string().to_uppercase());
+        Self::parse(&env::var(variable).ok()?)
+    }
+
+    fn parse(command: &str) -> Option<Self> {
+        let mut words = command.split_whitespace().map(String::from);
+        Some(Self {
+            program: words.next()?,
+            args: words.collect(),
+        })
+    }
+
+    /// The name of the server's executable, for messages
+    pub fn name(&self) -> &str {
+        Path::new(&self.program)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&self.program)
+    }
+
+    /// The command that starts the server, as configured
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    /// Start the server, open `text` as the contents of `file` without writing it to disk, and
+    /// return the errors and warnings the server reports for it
+    pub fn diagnostics(
+        &self,
+        language: LanguageName,
+        file: &Path,
+        text: &str,
+    ) -> Result<Vec<Diagnostic>> {
+        self.with_document(language, file, text, |session, uri| {
+            let diagnostics = session.published_diagnostics(uri)?.ok_or_else(|| {
+                anyhow!(
+                    "No diagnostics were reported within {}ms",
+                    session.timeout.as_millis()
+                )
+            })?;
+            Ok(parse_diagnostics(&diagnostics))
+        })
+    }
+
+    /// Start the server, open `text` as the contents of `file`, and list the code actions it
+    /// offers for the bytes in `range`. With a `choice` naming one of them by title or
+    /// 1-indexed number, apply that action's changes to `text` instead.
+    pub fn code_actions(
+        &self,
+        language: LanguageName,
+        file: &Path,
+        text: &str,
+        range: Range<usize>,
+        choice: Option<&str>,
+    ) -> Result<CodeActions> {
+        self.with_document(language, file, text, |session, uri| {
+            let range = json!({
+                "start": position(text, range.start),
+                "end": position(text, range.end),
+            });
+            // quick fixes are offered for the diagnostics they fix, so the server needs to be
+            // told which diagnostics apply to the range
+            let diagnostics = session
+                .published_diagnostics(uri)?
+                .and_then(|diagnostics| diagnostics.as_array().cloned())
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|diagnostic| {
+                    diagnostic["range"]["start"]["line"].as_u64() <= range["end"]["line"].as_u64()
+                        && diagnostic["range"]["end"]["line"].as_u64()
+                            >= range["start"]["line"].as_u64()
+                })
+                .collect::<Vec<_>>();
+
+            let actions = session.call(
+                "textDocument/codeAction",
+                json!({
+                    "textDocument": { "uri": uri },
+                    "range": range,
+                    "context": { "diagnostics": diagnostics },
+                }),
+            )?;
+            let actions = actions.as_array().cloned().unwrap_or_default();
+            let listed = actions
+                .iter()
+                .map(|action| CodeAction {
+                    title: action["title"].as_str().unwrap_or_default().to_string(),
+                    kind: action["kind"].as_str().map(String::from),
+                })
+                .collect::<Vec<_>>();
+
+            let Some(choice) = choice else {
+                return Ok(CodeActions::Available(listed));
+            };
+            let index = choose(&listed, choice)?;
+            let mut action = actions[index].clone();
+            if action.get("edit").is_none() && action.get("data").is_some() {
+                action = session.call("codeAction/resolve", action)?;
+            }
+            let title = listed[index].title.clone();
+            let edit = action.get("edit").ok_or_else(|| {
+                anyhow!(
+                    "`{title}` runs a command on the language server instead of returning \
+                     changes, so it can't be previewed"
+                )
+            })?;
+
+            Ok(CodeActions::Applied {
+                text: apply_text_edits(text, &file_edits(edit, uri, &title)?)?,
+                title,
+            })
+        })
+    }
+
+    /// Start the server in the file's project, open `text` as the contents of `file`, and hand
+    /// the session and the document's URI to `f` before shutting the server down
+    fn with_document<T>(
+        &self,
+        language: LanguageName,
+        file: &Path,
+        text: &str,
+        f: impl FnOnce(&mut Session<'_, ChildStdin>, &str) -> Result<T>,
+    ) -> Result<T> {
+        let root = project_root(file);
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .current_dir(&root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|error| anyhow!("The server could not be started: {error}"))?;
+
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let (sender, incoming) = channel();
+        thread::spawn(move || {
+            let mut stdout = BufReader::new(stdout);
+            while let Ok(Some(message)) = read_message(&mut stdout) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let document = Document {
+            root,
+            file: file.to_path_buf(),
+            language_id: language_id(language),
+            text: text.strip_prefix('\u{feff}').unwrap_or(text),
+        };
+        let mut session = Session::new(stdin, &incoming, timeout());
+        let result = session
+            .open(&document)
+            .and_then(|uri| f(&mut session, &uri));
+        session.shutdown();
+
+        let _ = child.kill();
+        let _ = child.wait();
+        result
+    }
+}
+
+/// A code action offered by a language server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeAction {
+    pub title: String,
+    /// Like `quickfix` or `refactor.rewrite`
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodeActions {
+    /// The actions offered, when none was chosen
+    Available(Vec<CodeAction>),
+    /// The document's text after applying the chosen action
+    Applied { title: String, text: String },
+}
+
+/// Find an action by its 1-indexed number or its title
+fn choose(actions: &[CodeAction], choice: &str) -> Result<usize> {
+    let choice = choice.trim();
+    choice
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_sub(1))
+        .filter(|index| *index < actions.len())
+        .or_else(|| actions.iter().position(|action| action.title == choice))
+        .or_else(|| {
+            let choice = choice.to_lowercase();
+            let mut matching = actions
+                .iter()
+                .enumerate()
+                .filter(|(_, action)| action.title.to_lowercase().contains(&choice));
+            match (matching.next(), matching.next()) {
+                (Some((index, _)), None) => Some(index),
+                _ => None,
+            }
+        })
+        .ok_or_else(|| {
+            anyhow!(
+                "No single code action matches `{choice}`. Available actions: {}",
+                actions
+                    .iter()
+                    .enumerate()
+                    .map(|(index, action)| format!("{}. {}", index + 1, action.title))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+fn timeout() -> Duration {
+    env::var("SEMANTIC_EDIT_LSP_TIMEOUT_MS")
+        .ok()
+        .and_then(|ms| ms.parse().ok())
+        .map_or(DEFAULT_TIMEOUT, Duration::from_millis)
+}
+
+/// The nearest ancestor of a file that looks like the root of a project, so the server sees the
+/// file in the context of the rest of its project
+fn project_root(file: &Path) -> PathBuf {
+    let parent = file.parent().unwrap_or(file);
+    parent
+        .ancestors()
+        .find(|directory| {
+            PROJECT_MARKERS
+                .iter()
+                .any(|marker| directory.join(marker).exists())
+        })
+        .unwrap_or(parent)
+        .to_path_buf()
+}
+
+fn language_id(language: LanguageName) -> &'static str {
+    match language {
+        LanguageName::Rust => "rust",
+        LanguageName::Json => "json",
+        LanguageName::Toml => "toml",
+        LanguageName::Javascript => "javascript",
+        LanguageName::Jsx => "javascriptreact",
+        LanguageName::Typescript => "typescript",
+        LanguageName::Tsx => "typescriptreact",
+        LanguageName::Python => "python",
+        LanguageName::Yaml => "yaml",
+        LanguageName::Java => "java",
+        LanguageName::Kotlin => "kotlin",
+        LanguageName::Other => "plaintext",
+    }
+}
+
+/// A `file://` URI for an absolute path
+fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
+}
+
+/// Undo percent-encoding, so that URIs that escape different characters can be compared
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%'
+            && let Some(byte) = uri
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read one framed message, or None at the end of the stream
+fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            content_length = Some(value.trim().parse::<usize>()?);
+        }
+    }
+
+    let content_length =
+        content_length.ok_or_else(|| anyhow!("Message without a Content-Length header"))?;
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+struct Document<'a> {
+    root: PathBuf,
+    file: PathBuf,
+    language_id: &'static str,
+    text: &'a str,
+}
+
+/// One conversation with a language server, from initialization through shutdown
+struct Session<'a, W> {
+    writer: W,
+    incoming: &'a Receiver<Value>,
+    /// How long to wait for each response
+    timeout: Duration,
+    next_id: u64,
+}
+
+impl<'a, W: Write> Session<'a, W> {
+    fn new(writer: W, incoming: &'a Receiver<Value>, timeout: Duration) -> Self {
+        Self {
+            writer,
+            incoming,
+            timeout,
+            next_id: 1,
+        }
+    }
+
+    /// Initialize the server for the document's project and open the document, returning its
+    /// URI
+    fn open(&mut self, document: &Document<'_>) -> Result<String> {
+        let root_uri = file_uri(&document.root);
+        self.call(
+            "initialize",
+            json!({
+                "processId": std::process::id(),
+                "rootUri": root_uri,
+                "workspaceFolders": [{ "uri": root_uri, "name": "workspace" }],
+                "capabilities": {
+                    "textDocument": {
+                        "publishDiagnostics": {},
+                        "codeAction": {
+                            "codeActionLiteralSupport": {
+                                "codeActionKind": {
+                                    "valueSet": [
+                                        "",
+                                        "quickfix",
+                                        "refactor",
+                                        "refactor.extract",
+                                        "refactor.inline",
+                                        "refactor.rewrite",
+                                        "source",
+                                        "source.organizeImports",
+                                    ],
+                                },
+                            },
+                            "dataSupport": true,
+                            "resolveSupport": { "properties": ["edit"] },
+                        },
+                    },
+                    "workspace": {
+                        "configuration": true,
+                        "workspaceFolders": true,
+                        "workspaceEdit": { "documentChanges": true },
+                    },
+                },
+            }),
+        )?;
+        self.notify("initialized", json!({}))?;
+
+        let uri = file_uri(&document.file);
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": uri,
+                    "languageId": document.language_id,
+                    "version": 1,
+                    "text": document.text,
+                },
+            }),
+        )?;
+        Ok(uri)
+    }
+
+    /// The latest diagnostics the server publishes for a document, or None if it publishes
+    /// none before the timeout
+    fn published_diagnostics(&mut self, uri: &str) -> Result<Option<Value>> {
+        let deadline = Instant::now() + self.timeout;
+        let mut latest = None;
+        let mut until = deadline;
+        while let Some(message) = self.next(until)? {
+            if message["method"] == "textDocument/publishDiagnostics"
+                && message["params"]["uri"]
+                    .as_str()
+                    .is_some_and(|published| percent_decode(published) == percent_decode(uri))
+            {
+                latest = Some(message["params"]["diagnostics"].clone());
+                until = deadline.min(Instant::now() + SETTLE);
+            }
+        }
+        Ok(latest)
+    }
+
+    /// Make a request and wait for its result
+    fn call(&mut self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        write_message(
+            &mut self.writer,
+            &json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }),
+        )?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let message = self.next(deadline)?.ok_or_else(|| {
+                anyhow!(
+                    "The language server did not respond to {method} within {}ms",
+                    self.timeout.as_millis()
+                )
+            })?;
+            if message["id"] == id {
+                if let Some(error) = message.get("error") {
+                    bail!(
+                        "The language server returned an error for {method}: {}",
+                        error["message"]
+                    );
+                }
+                return Ok(message["result"].clone());
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        write_message(
+            &mut self.writer,
+            &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+        )
+    }
+
+    /// Ask the server to exit, without waiting for it to, since the process is killed
+    /// afterwards anyway
+    fn shutdown(&mut self) {
+        let id = self.next_id;
+        let _ = write_message(
+            &mut self.writer,
+            &json!({ "jsonrpc": "2.0", "id": id, "method": "shutdown", "params": null }),
+        );
+        let _ = self.notify("exit", Value::Null);
+    }
+
+    /// The next response or notification from the server, answering any requests the server
+    /// makes along the way. Returns None once `until` passes.
+    fn next(&mut self, until: Instant) -> Result<Option<Value>> {
+        loop {
+            let remaining = until.saturating_duration_since(Instant::now());
+            let message = match self.incoming.recv_timeout(remaining) {
+                Ok(message) => message,
+                Err(RecvTimeoutError::Timeout) => return Ok(None),
+                Err(RecvTimeoutError::Disconnected) => bail!("The language server exited"),
+            };
+
+            let Some(method) = message["method"].as_str() else {
+                return Ok(Some(message));
+            };
+            let Some(id) = message.get("id") else {
+                return Ok(Some(message));
+            };
+
+            let result = match method {
+                // we don't have settings of our own, so every configuration section is left to
+                // the server's defaults
+                "workspace/configuration" => Value::Array(
+                    message["params"]["items"]
+                        .as_array()
+                        .map_or(vec![], |items| vec![Value::Null; items.len()]),
+                ),
+                // changes go through the staged preview instead of being applied directly
+                "workspace/applyEdit" => json!({
+                    "applied": false,
+                    "failureReason": "edits must be previewed before they are applied",
+                }),
+                _ => Value::Null,
+            };
+            write_message(
+                &mut self.writer,
+                &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            )?;
+        }
+    }
+}
+
+/// Errors and warnings from a `publishDiagnostics` notification, in file order
+fn parse_diagnostics(diagnostics: &Value) -> Vec<Diagnostic> {
+    let mut diagnostics = diagnostics
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|diagnostic| {
+            // a missing severity is left to the client, and we treat it as an error
+            let severity = match diagnostic["severity"].as_u64() {
+                None | Some(1) => "error",
+                Some(2) => "warning",
+                _ => return None,
+            };
+            let start = &diagnostic["range"]["start"];
+            let code = match &diagnostic["code"] {
+                Value::String(code) => Some(code.clone()),
+                Value::Number(code) => Some(code.to_string()),
+                _ => None,
+            };
+            Some(Diagnostic {
+                line: start["line"].as_u64()? as usize + 1,
+                column: start["character"].as_u64()? as usize + 1,
+                severity,
+                code,
+                message: diagnostic["message"].as_str()?.lines().next()?.to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    diagnostics
+}
+
+/// A `WorkspaceEdit` that changes each file from its original text to its edited text, with
+/// one `TextEdit` per file covering everything between the first and last changed character
+pub fn workspace_edit<'a>(files: impl IntoIterator<Item = (&'a Path, &'a str, &'a str)>) -> Value {
+    let changes = files
+        .into_iter()
+        .map(|(file, original, edited)| (file_uri(file), json!([text_edit(original, edited)])))
+        .collect::<serde_json::Map<_, _>>();
+    json!({ "changes": changes })
+}
+
+/// The lengths in bytes of the longest common prefix and, after it, the longest common suffix
+/// of two texts, so that everything between them is what changed
+pub fn unchanged_ends(original: &str, edited: &str) -> (usize, usize) {
+    let prefix = original
+        .char_indices()
+        .zip(edited.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(original.len().min(edited.len()), |((index, _), _)| index);
+    let suffix = original[prefix..]
+        .chars()
+        .rev()
+        .zip(edited[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum::<usize>();
+    (prefix, suffix)
+}
+
+fn text_edit(original: &str, edited: &str) -> Value {
+    let (prefix, suffix) = unchanged_ends(original, edited);
+    json!({
+        "range": {
+            "start": position(original, prefix),
+            "end": position(original, original.len() - suffix),
+        },
+        "newText": edited[prefix..edited.len() - suffix],
+    })
+}
+
+/// The protocol's position of a byte offset: a 0-indexed line, and a character counted in
+/// UTF-16 code units
+fn position(text: &str, offset: usize) -> Value {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[line_start..].encode_utf16().count(),
+    })
+}
+
+/// The byte offset of a protocol position, which counts characters in UTF-16 code units
+fn offset(text: &str, position: &Value) -> Result<usize> {
+    let (Some(line), Some(character)) = (position["line"].as_u64(), position["character"].as_u64())
+    else {
+        bail!("Invalid position {position}");
+    };
+    let line_start = if line == 0 {
+        0
+    } else {
+        text.match_indices('\n')
+            .nth(line as usize - 1)
+            .map(|(index, _)| index + 1)
+            .ok_or_else(|| anyhow!("Position {position} is past the end of the file"))?
+    };
+
+    let mut units = 0;
+    for (index, c) in text[line_start..].char_indices() {
+        if units >= character as usize || c == '\n' {
+            return Ok(line_start + index);
+        }
+        units += c.len_utf16();
+    }
+    Ok(text.len())
+}
+
+/// The text edits a `WorkspaceEdit` makes to one document, refusing edits that change any other
+/// file, since only one file can be staged at a time
+fn file_edits(edit: &Value, uri: &str, title: &str) -> Result<Vec<Value>> {
+    let mut edits = vec![];
+    let mut add = |document: &str, text_edits: &Value| {
+        if percent_decode(document) != percent_decode(uri) {
+            bail!(
+                "`{title}` also changes {}, and only changes to a single file can be staged",
+                percent_decode(document).trim_start_matches("file://")
+            );
+        }
+        edits.extend(text_edits.as_array().into_iter().flatten().cloned());
+        Ok(())
+    };
+
+    if let Some(changes) = edit["changes"].as_object() {
+        for (document, text_edits) in changes {
+            add(document, text_edits)?;
+        }
+    }
+    for change in edit["documentChanges"].as_array().into_iter().flatten() {
+        match change["textDocument"]["uri"].as_str() {
+            Some(document) => add(document, &change["edits"])?,
+            None => bail!("`{title}` creates, renames, or deletes files, which can't be staged"),
+        }
+    }
+    Ok(edits)
+}
+
+/// Apply text edits, whose ranges all refer to the original text, from the end of the text
+/// backwards so that earlier offsets stay valid
+fn apply_text_edits(text: &str, edits: &[Value]) -> Result<String> {
+    let mut ranges = edits
+        .iter()
+        .map(|edit| {
+            Ok((
+                offset(text, &edit["range"]["start"])?,
+                offset(text, &edit["range"]["end"])?,
+                edit["newText"].as_str().unwrap_or_default(),
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    ranges.sort_by_key(|(start, end, _)| (*start, *end));
+
+    let mut edited = text.to_string();
+    for (start, end, new_text) in ranges.into_iter().rev() {
+        edited.replace_range(start..end, new_text);
+    }
+    Ok(edited)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::pipe;
+
+    #[test]
+    fn messages_round_trip() {
+        let message = json!({ "jsonrpc": "2.0", "method": "exit", "params": "ünïcode" });
+        let mut framed = vec![];
+        write_message(&mut framed, &message).unwrap();
+        framed.extend_from_slice(b"content-length: 2\r\nContent-Type: x\r\n\r\n{}");
+
+        let mut reader = &framed[..];
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(read_message(&mut reader).unwrap(), Some(json!({})));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn workspace_edits_cover_only_the_change() {
+        let edit = workspace_edit([(
+            Path::new("/project/lib.rs"),
+            "fn a() {}\nlet s = \"é\"; old();\nfn b() {}\n",
+            "fn a() {}\nlet s = \"é\"; new(1);\nfn b() {}\n",
+        )]);
+        assert_eq!(
+            edit,
+            json!({
+                "changes": {
+                    "file:///project/lib.rs": [{
+                        "range": {
+                            "start": { "line": 1, "character": 13 },
+                            "end": { "line": 1, "character": 17 },
+                        },
+                        "newText": "new(1",
+                    }],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn code_action_edits_apply_to_the_document() {
+        let text = "use a;\nlet é = b();\n";
+        let uri = "file:///project/src/lib.rs";
+        let edit = json!({
+            "documentChanges": [{
+                "textDocument": { "uri": uri, "version": 1 },
+                "edits": [
+                    {
+                        "range": {
+                            "start": { "line": 1, "character": 8 },
+                            "end": { "line": 1, "character": 9 },
+                        },
+                        "newText": "c",
+                    },
+                    {
+                        "range": {
+                            "start": { "line": 0, "character": 6 },
+                            "end": { "line": 0, "character": 6 },
+                        },
+                        "newText": "\nuse std::collections::HashMap;",
+                    },
+                ],
+            }],
+        });
+        let edits = file_edits(&edit, uri, "Import").unwrap();
+        assert_eq!(
+            apply_text_edits(text, &edits).unwrap(),
+            "use a;\nuse std::collections::HashMap;\nlet é = c();\n"
+        );
+
+        let rename = json!({
+            "changes": { uri: [], "file:///project/src/main.rs": [] },
+        });
+        assert!(
+            file_edits(&rename, uri, "Rename")
+                .unwrap_err()
+                .to_string()
+                .contains("/project/src/main.rs")
+        );
+    }
+
+    #[test]
+    fn actions_are_chosen_by_number_or_title() {
+        let actions =
+            ["Fill match arms", "Import `HashMap`", "Import `HashSet`"].map(|title| CodeAction {
+                title: title.into(),
+                kind: None,
+            });
+        assert_eq!(choose(&actions, "2").unwrap(), 1);
+        assert_eq!(choose(&actions, "Import `HashSet`").unwrap(), 2);
+        assert_eq!(choose(&actions, "fill match").unwrap(), 0);
+        assert!(choose(&actions, "import").is_err());
+        assert!(choose(&actions, "4").is_err());
+    }
+
+    #[test]
+    fn uris_are_compared_decoded() {
+        let uri = file_uri(Path::new("/tmp/my project/lib.rs"));
+        assert_eq!(uri, "file:///tmp/my%20project/lib.rs");
+        assert_eq!(
+            percent_decode(&uri),
+            percent_decode("file:///tmp/my%20project/lib%2Ers")
+        );
+    }
+
+    #[test]
+    fn commands_are_split_into_arguments() {
+        let server = LanguageServer::parse(" /usr/bin/pyright-langserver  --stdio ").unwrap();
+        assert_eq!(server.name(), "pyright-langserver");
+        assert_eq!(server.args, ["--stdio"]);
+        assert_eq!(LanguageServer::parse("  "), None);
+    }
+
+    /// Play the part of a language server that asks for configuration, publishes a first pass
+    /// of diagnostics, and then a complete one
+    #[test]
+    fn session_collects_the_latest_diagnostics() {
+        let (reader, writer) = pipe().unwrap();
+        let (sender, incoming) = channel();
+        let server = thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            let mut received = vec![];
+            let mut next = || {
+                let message = read_message(&mut reader).unwrap().unwrap();
+                received.push(message["method"].as_str().unwrap_or("response").to_string());
+                message
+            };
+
+            let initialize = next();
+            assert_eq!(initialize["params"]["rootUri"], "file:///project");
+            sender
+                .send(json!({ "jsonrpc": "2.0", "id": 1, "result": { "capabilities": {} } }))
+                .unwrap();
+            next();
+            let open = next();
+            assert_eq!(open["params"]["textDocument"]["text"], "fn main() { x }");
+            let uri = open["params"]["textDocument"]["uri"].clone();
+
+            sender
+                .send(json!({
+                    "jsonrpc": "2.0",
+                    "id": "config",
+                    "method": "workspace/configuration",
+                    "params": { "items": [{ "section": "a" }, { "section": "b" }] },
+                }))
+                .unwrap();
+            assert_eq!(next()["result"], json!([null, null]));
+
+            let publish = |diagnostics: Value| {
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": { "uri": uri, "diagnostics": diagnostics },
+                })
+            };
+            sender.send(publish(json!([]))).unwrap();
+            sender
+                .send(publish(json!([
+                    {
+                        "range": { "start": { "line": 0, "character": 12 } },
+                        "severity": 1,
+                        "code": "E0425",
+                        "message": "cannot find value `x` in this scope\nhelp: ...",
+                    },
+                    {
+                        "range": { "start": { "line": 0, "character": 3 } },
+                        "severity": 3,
+                        "message": "information is not reported",
+                    },
+                ])))
+                .unwrap();
+
+            next();
+            next();
+            received
+        });
+
+        let document = Document {
+            root: "/project".into(),
+            file: "/project/src/main.rs".into(),
+            language_id: "rust",
+            text: "fn main() { x }",
+        };
+        let mut session = Session::new(writer, &incoming, Duration::from_secs(5));
+        let uri = session.open(&document).unwrap();
+        let diagnostics = parse_diagnostics(&session.published_diagnostics(&uri).unwrap().unwrap());
+        session.shutdown();
+
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["1:13 error[E0425]: cannot find value `x` in this scope"]
+        );
+        assert_eq!(
+            server.join().unwrap(),
+            [
+                "initialize",
+                "initialized",
+                "textDocument/didOpen",
+                "response",
+                "shutdown",
+                "exit"
+            ]
+        );
+    }
+}
@@ -1,14 +1,54 @@
 #![allow(clippy::collapsible_if)]
 #![deny(dead_code)]
 
+mod budget;
+mod call_graph;
+mod checks;
+mod codemod;
+mod conflicts;
+mod docstrings;
+mod doctor;
+mod duplicates;
 mod editor;
+mod encoding;
+mod fixits;
+mod formatting;
+mod front_matter;
+mod guardrails;
+mod hooks;
+mod html_diff;
+mod imports;
 mod indentation;
+mod jsx;
+mod key_path;
 mod languages;
+mod literals;
+mod lsp;
+mod members;
+mod metrics;
+mod module_map;
+mod node_kinds;
+mod package_json;
+mod placeholders;
+mod protected;
+mod public_api;
+mod recipes;
+mod repair;
+mod response_format;
+mod scaffold;
 mod searcher;
+mod secrets;
+mod sections;
 mod selector;
+mod server;
+mod sort_keys;
 mod state;
+mod symbols;
+mod toc;
 mod tools;
 mod validation;
+mod versions;
+mod workspace;
 
 #[cfg(test)]
 mod tests;
@@ -23,13 +63,22 @@ The purpose of the preview/retarget/persist pattern is so you can review a diff
 "#;
 
 fn main() {
-    let mut state = SemanticEditTools::new(
-        env::var("MCP_SESSION_STORAGE_PATH")
-            .ok()
-            .as_deref()
-            .or(Some("~/.ai-tools/sessions/semantic-edit.json")),
-    )
-    .unwrap();
+    // replays start from a fresh session rather than one stored by a previous run
+    let replaying = env::args().nth(1).as_deref() == Some("replay");
+    let storage_path = match env::var("SEMANTIC_EDIT_SESSION_STORAGE").as_deref() {
+        _ if replaying => None,
+        Ok("memory") => None,
+        Ok("json") | Err(_) => Some(
+            env::var("MCP_SESSION_STORAGE_PATH")
+                .unwrap_or_else(|_| "~/.ai-tools/sessions/semantic-edit.json".into()),
+        ),
+        Ok(other) => {
+            panic!("unknown SEMANTIC_EDIT_SESSION_STORAGE `{other}`, expected json or memory")
+        }
+    };
 
-    mcplease::run::<Tools, _>(&mut state, server_info!(), Some(INSTRUCTIONS)).unwrap()
+    let mut state = SemanticEditTools::new(storage_path.as_deref()).unwrap();
+
+    server::run::<Tools, _>(&mut state, server_info!(), Some(INSTRUCTIONS)).unwrap();
+    state.shutdown().unwrap();
 }
@@ -0,0 +1,309 @@
+use crate::indentation::Indentation;
+use std::{borrow::Cow, ops::Range};
+use tree_sitter::{Node, Tree};
+
+/// Words that can come before a member's name in TypeScript
+const MODIFIERS: &[&str] = &[
+    "abstract",
+    "async",
+    "declare",
+    "get",
+    "override",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "set",
+    "static",
+];
+
+/// Where to add a member to a TypeScript interface, object type, or class, and the text to put
+/// there
+#[derive(Debug, PartialEq, Eq)]
+pub struct Insertion {
+    /// The range to replace, which is empty unless the body has nothing in it but whitespace
+    pub range: Range<usize>,
+    pub content: String,
+    /// The first line of the member the new one follows, or of the container if it has none
+    pub anchor: String,
+}
+
+/// `member` with `modifiers` before it, and with a `?` after its name if it is `optional`
+pub fn with_modifiers(member: &str, modifiers: &[String], optional: bool) -> String {
+    let member = member.trim();
+    let mut words = member.split_inclusive(' ').peekable();
+    let mut prefix = 0;
+    while let Some(word) = words.next_if(|word| MODIFIERS.contains(&word.trim())) {
+        prefix += word.len();
+    }
+    let (existing, declaration) = member.split_at(prefix);
+
+    let declaration = match declaration
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$' || c == '#'))
+    {
+        Some(end) if optional && end > 0 && !declaration[end..].starts_with('?') => {
+            format!("{}?{}", &declaration[..end], &declaration[end..])
+        }
+        _ => declaration.to_string(),
+    };
+    let modifiers = modifiers
+        .iter()
+        .map(|modifier| modifier.trim())
+        .filter(|modifier| !existing.split_whitespace().any(|word| word == *modifier))
+        .map(|modifier| format!("{modifier} "))
+        .collect::<String>();
+    format!("{modifiers}{existing}{declaration}")
+}
+
+/// Where `member` goes at the end of the interface, object type alias, or class named
+/// `container`, punctuated like the members already there
+pub fn insertion(
+    tree: &Tree,
+    source: &str,
+    container: &str,
+    member: &str,
+) -> Result<Insertion, String> {
+    let (declaration, body) = find(tree.root_node(), source, container)?;
+    let class = body.kind() == "class_body";
+    let members = body
+        .named_children(&mut body.walk())
+        .filter(|child| child.kind() != "comment")
+        .collect::<Vec<_>>();
+    let separators = body
+        .children(&mut body.walk())
+        .filter(|child| !child.is_named())
+        .map(|child| child.kind())
+        .collect::<Vec<_>>();
+
+    let separator = if class {
+        // fields and signatures end with a semicolon, and methods with a body need none
+        if member.trim_end().ends_with(['}', ';']) {
+            ""
+        } else {
+            ";"
+        }
+    } else if separators.contains(&",") {
+        ","
+    } else if separators.contains(&";") || members.is_empty() {
+        ";"
+    } else {
+        ""
+    };
+    let member = match class {
+        true => format!("{}{separator}", member.trim()),
+        false => format!("{}{separator}", member.trim().trim_end_matches([';', ','])),
+    };
+
+    let indentation = Indentation::determine(source).unwrap_or(Indentation::Spaces(2));
+    let line_start = |offset: usize| source[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let indent_of = |offset: usize| {
+        let start = line_start(offset);
+        let end = start + source[start..].len() - source[start..].trim_start().len();
+        &source[start..end]
+    };
+    let indented = |indent: &str| {
+        let mut content = Cow::Borrowed(member.as_str());
+        indentation.reindent(indentation.unit_count(indent), &mut content, true);
+        content.into_owned()
+    };
+    let first_line = |node: Node<'_>| {
+        source[node.byte_range()]
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+
+    let Some(last) = members.last() else {
+        let indent = format!("{}{indentation}", indent_of(declaration.start_byte()));
+        return Ok(Insertion {
+            range: body.start_byte() + 1..body.end_byte() - 1,
+            content: format!(
+                "\n{}\n{}",
+                indented(&indent),
+                indent_of(declaration.start_byte())
+            ),
+            anchor: first_line(declaration),
+        });
+    };
+
+    // a member without the separator the others have gets one before the new member
+    let next = last.next_sibling();
+    let terminated = next.is_some_and(|next| !next.is_named() && matches!(next.kind(), ";" | ","));
+    let missing = if class || terminated { "" } else { separator };
+    let offset = match next {
+        Some(next) if terminated => next.end_byte(),
+        _ => last.end_byte(),
+    };
+
+    if body.start_position().row == body.end_position().row {
+        return Ok(Insertion {
+            range: offset..offset,
+            content: format!("{missing} {member}"),
+            anchor: first_line(*last),
+        });
+    }
+
+    // a comment after the last member stays on its line, unless the body closes on that line
+    let line_end = source[offset..]
+        .find('\n')
+        .map_or(source.len(), |index| offset + index);
+    let end = if body.end_byte() > line_end {
+        line_end
+    } else {
+        offset
+    };
+    Ok(Insertion {
+        range: offset..end,
+        content: format!(
+            "{missing}{}\n{}",
+            &source[offset..end],
+            indented(indent_of(last.start_byte()))
+        ),
+        anchor: first_line(*last),
+    })
+}
+
+/// The declaration named `name` and its body
+fn find<'tree>(
+    root: Node<'tree>,
+    source: &str,
+    name: &str,
+) -> Result<(Node<'tree>, Node<'tree>), String> {
+    let mut found = vec![];
+    let mut cursor = root.walk();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        let body = match node.kind() {
+            "interface_declaration"
+            | "class_declaration"
+            | "abstract_class_declaration"
+            | "class" => node.child_by_field_name("body"),
+            "type_alias_declaration" => node.child_by_field_name("value").and_then(object_type),
+            _ => None,
+        };
+        if let Some(body) = body
+            && node
+                .child_by_field_name("name")
+                .is_some_and(|declared| &source[declared.byte_range()] == name)
+        {
+            found.push((node, body));
+        }
+        stack.extend(node.named_children(&mut cursor));
+    }
+
+    match found.as_slice() {
+        [found] => Ok(*found),
+        [] => Err(format!(
+            "There is no interface, object type, or class named {name}"
+        )),
+        _ => Err(format!(
+            "{name} is declared {} times. Rename one, or add the member with preview_edit.",
+            found.len()
+        )),
+    }
+}
+
+/// The object type a type alias is, or the last one in an intersection like `Base & { .. }`
+fn object_type(value: Node<'_>) -> Option<Node<'_>> {
+    match value.kind() {
+        "object_type" => Some(value),
+        "intersection_type" => value
+            .named_children(&mut value.walk())
+            .filter_map(object_type)
+            .last(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifiers_and_optional_marks_go_around_the_name() {
+        assert_eq!(
+            with_modifiers("timeout: number", &["readonly".into()], true),
+            "readonly timeout?: number"
+        );
+        assert_eq!(
+            with_modifiers(
+                "static  count = 0",
+                &["private".into(), "static".into()],
+                false
+            ),
+            "private static  count = 0"
+        );
+        assert_eq!(with_modifiers("name?: string", &[], true), "name?: string");
+        assert_eq!(with_modifiers("close(): void", &[], true), "close?(): void");
+    }
+
+    #[cfg(feature = "typescript")]
+    fn added(source: &str, container: &str, member: &str) -> String {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let insertion = insertion(&tree, source, container, member).unwrap();
+        format!(
+            "{}{}{}",
+            &source[..insertion.range.start],
+            insertion.content,
+            &source[insertion.range.end..]
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "typescript")]
+    fn members_are_separated_like_their_neighbors() {
+        let source =
+            "interface Options {\n  name: string; // shown in the title\n  debug?: boolean\n}\n";
+        assert_eq!(
+            added(source, "Options", "timeout: number"),
+            "interface Options {\n  name: string; // shown in the title\n  debug?: boolean;\n  timeout: number;\n}\n"
+        );
+
+        let source = "type Point = Base & {\n    x: number,\n    y: number,\n};\n";
+        assert_eq!(
+            added(source, "Point", "z: number;"),
+            "type Point = Base & {\n    x: number,\n    y: number,\n    z: number,\n};\n"
+        );
+
+        assert_eq!(
+            added(
+                "type Pair = { a: string; b: string };\n",
+                "Pair",
+                "c: string"
+            ),
+            "type Pair = { a: string; b: string; c: string; };\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "typescript")]
+    fn classes_get_fields_and_methods() {
+        let source = "export class Client {\n  private socket: Socket;\n\n  open() {\n    this.socket.open();\n  }\n}\n";
+        assert_eq!(
+            added(source, "Client", "close() {\n  this.socket.end();\n}"),
+            "export class Client {\n  private socket: Socket;\n\n  open() {\n    this.socket.open();\n  }\n  close() {\n    this.socket.end();\n  }\n}\n"
+        );
+        assert_eq!(
+            added("class Empty {}\n", "Empty", "count = 0"),
+            "class Empty {\n  count = 0;\n}\n"
+        );
+        assert!(insertion_error("class A {}\nclass A {}\n", "A").contains("declared 2 times"));
+        assert!(insertion_error("const a = 1;\n", "A").contains("no interface"));
+    }
+
+    #[cfg(feature = "typescript")]
+    fn insertion_error(source: &str, container: &str) -> String {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        insertion(&tree, source, container, "a: string").unwrap_err()
+    }
+}
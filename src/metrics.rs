@@ -0,0 +1,292 @@
+use crate::symbols::{FunctionDefinition, function_definitions};
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tree_sitter::{Node, Query, Tree};
+
+/// Node kinds that branch or loop, across the supported languages
+const BRANCHES: &[&str] = &[
+    "if_expression",
+    "if_statement",
+    "elif_clause",
+    "ternary_expression",
+    "conditional_expression",
+    "for_expression",
+    "for_statement",
+    "for_in_statement",
+    "while_expression",
+    "while_statement",
+    "do_statement",
+    "loop_expression",
+    "match_arm",
+    "case_clause",
+    "switch_case",
+    "catch_clause",
+    "except_clause",
+];
+
+/// Node kinds that nest a block of code inside a function
+const NESTING: &[&str] = &[
+    "if_expression",
+    "if_statement",
+    "for_expression",
+    "for_statement",
+    "for_in_statement",
+    "while_expression",
+    "while_statement",
+    "do_statement",
+    "loop_expression",
+    "match_expression",
+    "match_statement",
+    "switch_statement",
+    "try_statement",
+    "with_statement",
+    "closure_expression",
+    "lambda",
+    "arrow_function",
+    "function_expression",
+];
+
+/// Short-circuiting operators, which add a path through a function just like a branch
+const BOOLEAN_OPERATORS: &[&str] = &["&&", "||", "and", "or", "??"];
+
+/// Size and complexity measurements for one function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionMetrics {
+    pub name: String,
+    /// 1-indexed line the function starts on
+    pub line: usize,
+    pub lines: usize,
+    /// Parameters, not counting `self`
+    pub parameters: usize,
+    /// Deepest nesting of control flow and closures inside the body
+    pub nesting: usize,
+    /// Cyclomatic complexity: one more than the number of branches and short-circuiting
+    /// operators
+    pub complexity: usize,
+}
+
+/// Which measurement to order functions by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    Lines,
+    Parameters,
+    Nesting,
+    Complexity,
+}
+
+impl Metric {
+    pub fn of(self, metrics: &FunctionMetrics) -> usize {
+        match self {
+            Metric::Lines => metrics.lines,
+            Metric::Parameters => metrics.parameters,
+            Metric::Nesting => metrics.nesting,
+            Metric::Complexity => metrics.complexity,
+        }
+    }
+}
+
+/// Measure every function that a query captures as a `@definition` with a `@definition.name`
+pub fn function_metrics(query: &Query, tree: &Tree, source: &str) -> Vec<FunctionMetrics> {
+    function_definitions(query, tree, source)
+        .into_iter()
+        .map(
+            |FunctionDefinition {
+                 node, qualified, ..
+             }| {
+                // arrow functions assigned to variables are defined by their declarator
+                let function = node
+                    .child_by_field_name("value")
+                    .filter(|value| value.child_by_field_name("body").is_some())
+                    .unwrap_or(node);
+                let body = function.child_by_field_name("body").unwrap_or(function);
+
+                FunctionMetrics {
+                    name: qualified,
+                    line: node.start_position().row + 1,
+                    lines: node.end_position().row - node.start_position().row + 1,
+                    parameters: parameters(function, source),
+                    nesting: nesting(body, body),
+                    complexity: 1 + branches(body, body),
+                }
+            },
+        )
+        .collect()
+}
+
+fn parameters(function: Node<'_>, source: &str) -> usize {
+    let Some(parameters) = function
+        .child_by_field_name("parameters")
+        .or_else(|| function.child_by_field_name("parameter"))
+    else {
+        return 0;
+    };
+    if parameters.kind() == "identifier" {
+        // a single arrow function parameter without parentheses
+        return 1;
+    }
+
+    parameters
+        .named_children(&mut parameters.walk())
+        .filter(|parameter| {
+            !parameter.kind().contains("comment")
+                && parameter.kind() != "self_parameter"
+                && !matches!(&source[parameter.byte_range()], "self" | "cls")
+        })
+        .count()
+}
+
+/// Whether a node starts a separately measured function, which does not count toward the
+/// function that contains it
+fn is_nested_function(node: Node<'_>, root: Node<'_>) -> bool {
+    node != root
+        && matches!(
+            node.kind(),
+            "function_item" | "function_definition" | "function_declaration" | "method_definition"
+        )
+}
+
+fn nesting(node: Node<'_>, root: Node<'_>) -> usize {
+    if is_nested_function(node, root) {
+        return 0;
+    }
+    // `else if` continues a chain rather than nesting inside it
+    let is_else_if = node
+        .parent()
+        .is_some_and(|parent| parent.kind() == "else_clause");
+    let own = usize::from(NESTING.contains(&node.kind()) && !is_else_if);
+    own + node
+        .named_children(&mut node.walk())
+        .map(|child| nesting(child, root))
+        .max()
+        .unwrap_or(0)
+}
+
+fn branches(node: Node<'_>, root: Node<'_>) -> usize {
+    if is_nested_function(node, root) {
+        return 0;
+    }
+    let is_boolean_operator =
+        node.kind() == "binary_expression" || node.kind() == "boolean_operator";
+    // each match arm after the first adds a path
+    let is_first_arm = node.kind() == "match_arm"
+        && node
+            .prev_named_sibling()
+            .is_none_or(|previous| previous.kind() != "match_arm");
+    let own = usize::from(
+        (BRANCHES.contains(&node.kind()) && !is_first_arm)
+            || (is_boolean_operator
+                && node
+                    .child_by_field_name("operator")
+                    .is_some_and(|operator| BOOLEAN_OPERATORS.contains(&operator.kind()))),
+    );
+    own + node
+        .children(&mut node.walk())
+        .map(|child| branches(child, root))
+        .sum::<usize>()
+}
+
+#[cfg(all(
+    test,
+    any(feature = "rust", feature = "python", feature = "javascript")
+))]
+mod tests {
+    use super::*;
+    use crate::languages::{LanguageName, LanguageRegistry};
+
+    fn metrics(language: LanguageName, source: &str) -> Vec<FunctionMetrics> {
+        let registry = LanguageRegistry::new().unwrap();
+        let language = registry.get_language(language);
+        let tree = language
+            .tree_sitter_parser()
+            .unwrap()
+            .parse(source, None)
+            .unwrap();
+        function_metrics(language.calls_query().unwrap(), &tree, source)
+    }
+
+    #[test]
+    #[cfg(feature = "rust")]
+    fn rust_metrics() {
+        let metrics = metrics(
+            LanguageName::Rust,
+            r#"
+impl Parser {
+    fn parse(&mut self, input: &str, strict: bool) -> Result<()> {
+        for line in input.lines() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            } else if strict {
+                match line.split_once('=') {
+                    Some((key, value)) => self.insert(key, value),
+                    None => return Err(anyhow!("bad line")),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn noop() {}
+"#,
+        );
+
+        assert_eq!(
+            metrics,
+            vec![
+                FunctionMetrics {
+                    name: "Parser::parse".into(),
+                    line: 3,
+                    lines: 13,
+                    parameters: 2,
+                    nesting: 3,
+                    complexity: 6,
+                },
+                FunctionMetrics {
+                    name: "noop".into(),
+                    line: 18,
+                    lines: 1,
+                    parameters: 0,
+                    nesting: 0,
+                    complexity: 1,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "python")]
+    fn python_metrics() {
+        let python = metrics(
+            LanguageName::Python,
+            "class Greeter:\n    def greet(self, name, loud=False):\n        if loud and name:\n            return name.upper()\n        return name\n",
+        );
+        assert_eq!(
+            (
+                python[0].parameters,
+                python[0].nesting,
+                python[0].complexity
+            ),
+            (2, 1, 3)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "javascript")]
+    fn javascript_metrics() {
+        let javascript = metrics(
+            LanguageName::Javascript,
+            "const total = items => items.reduce((sum, item) => sum + (item.price ?? 0), 0);\n",
+        );
+        assert_eq!(javascript[0].name, "total");
+        assert_eq!(
+            (
+                javascript[0].parameters,
+                javascript[0].nesting,
+                javascript[0].complexity
+            ),
+            (1, 1, 2)
+        );
+    }
+}
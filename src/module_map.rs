@@ -0,0 +1,158 @@
+use crate::{encoding::Encoding, languages::LanguageCommon};
+use anyhow::Result;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use tree_sitter::{Node, Parser};
+
+/// A module in a Rust crate's module tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Module {
+    /// Full module path, such as `crate::state::migration`
+    pub path: String,
+    pub location: Location,
+}
+
+/// Where a module's contents are defined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    /// The module has its own file
+    File(PathBuf),
+    /// The module is declared with a body inside another module's file
+    Inline(PathBuf),
+    /// The module is declared with `mod name;` but none of the files it could live in exist
+    Missing(Vec<PathBuf>),
+}
+
+/// Build the module tree of the crate rooted at `root_file` (usually `src/lib.rs` or
+/// `src/main.rs`) by following `mod` declarations the way rustc does, including `#[path]`
+/// attributes. Modules are listed depth-first in declaration order, starting with `crate`.
+pub fn module_map(language: &LanguageCommon, root_file: &Path) -> Result<Vec<Module>> {
+    let mut mapper = Mapper {
+        parser: language.tree_sitter_parser()?,
+        visited: HashSet::new(),
+        modules: vec![Module {
+            path: "crate".into(),
+            location: Location::File(root_file.to_path_buf()),
+        }],
+    };
+    let directory = root_file.parent().unwrap_or(Path::new("")).to_path_buf();
+    mapper.visit_file(root_file, "crate", &directory)?;
+    Ok(mapper.modules)
+}
+
+struct Mapper {
+    parser: Parser,
+    visited: HashSet<PathBuf>,
+    modules: Vec<Module>,
+}
+
+impl Mapper {
+    /// Collect the modules declared in `file`, whose child module files live in `directory`
+    fn visit_file(&mut self, file: &Path, module_path: &str, directory: &Path) -> Result<()> {
+        // #[path] attributes can point back at a file that is already part of the tree
+        if !self.visited.insert(file.to_path_buf()) {
+            return Ok(());
+        }
+
+        let (source, _) = Encoding::read(file)?;
+        let Some(tree) = self.parser.parse(&source, None) else {
+            return Ok(());
+        };
+
+        self.visit_items(tree.root_node(), &source, file, module_path, directory)
+    }
+
+    fn visit_items(
+        &mut self,
+        items: Node<'_>,
+        source: &str,
+        file: &Path,
+        module_path: &str,
+        directory: &Path,
+    ) -> Result<()> {
+        for item in items.named_children(&mut items.walk()) {
+            if item.kind() != "mod_item" {
+                continue;
+            }
+            let Some(name) = item.child_by_field_name("name") else {
+                continue;
+            };
+            let name = &source[name.byte_range()];
+            let path = format!("{module_path}::{name}");
+
+            if let Some(body) = item.child_by_field_name("body") {
+                self.modules.push(Module {
+                    path: path.clone(),
+                    location: Location::Inline(file.to_path_buf()),
+                });
+                self.visit_items(body, source, file, &path, &directory.join(name))?;
+                continue;
+            }
+
+            // At the top of a file, #[path] is relative to the file's own directory. Inside an
+            // inline module it is relative to the directory that module's children live in.
+            let path_attribute = path_attribute(item, source);
+            let candidates = match &path_attribute {
+                Some(attribute) if items.kind() == "source_file" => {
+                    vec![file.parent().unwrap_or(Path::new("")).join(attribute)]
+                }
+                Some(attribute) => vec![directory.join(attribute)],
+                None => vec![
+                    directory.join(format!("{name}.rs")),
+                    directory.join(name).join("mod.rs"),
+                ],
+            };
+
+            match candidates.iter().find(|candidate| candidate.is_file()) {
+                Some(module_file) => {
+                    let module_file = module_file.clone();
+                    self.modules.push(Module {
+                        path: path.clone(),
+                        location: Location::File(module_file.clone()),
+                    });
+                    // Files named by #[path] are treated like mod.rs files
+                    let child_directory = if path_attribute.is_some() {
+                        module_file.parent().unwrap_or(Path::new("")).to_path_buf()
+                    } else {
+                        child_directory(&module_file)
+                    };
+                    self.visit_file(&module_file, &path, &child_directory)?;
+                }
+                None => self.modules.push(Module {
+                    path,
+                    location: Location::Missing(candidates),
+                }),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Child modules of both `foo/mod.rs` and `foo.rs` live in `foo/`
+fn child_directory(module_file: &Path) -> PathBuf {
+    let parent = module_file.parent().unwrap_or(Path::new(""));
+    match module_file.file_stem().and_then(|stem| stem.to_str()) {
+        Some("mod") | None => parent.to_path_buf(),
+        Some(stem) => parent.join(stem),
+    }
+}
+
+/// The value of a `#[path = "..."]` attribute on a `mod` item, if it has one
+fn path_attribute(item: Node<'_>, source: &str) -> Option<String> {
+    let mut sibling = item.prev_named_sibling();
+    while let Some(attribute) = sibling.filter(|node| node.kind() == "attribute_item") {
+        let text = source[attribute.byte_range()]
+            .trim_start_matches("#[")
+            .trim_end_matches(']');
+        if let Some((key, value)) = text.split_once('=')
+            && key.trim() == "path"
+        {
+            return Some(value.trim().trim_matches('"').to_string());
+        }
+        sibling = attribute.prev_named_sibling();
+    }
+    None
+}
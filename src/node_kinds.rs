@@ -0,0 +1,245 @@
+//! A glossary of the named node kinds of a language's grammar, read from its node-types.json,
+//! for writing queries and choosing what to anchor edits on
+
+use crate::languages::LanguageCommon;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+};
+
+/// Subtypes or child kinds listed in a description before the rest are counted instead
+const LISTED_KINDS: usize = 8;
+
+/// A named node kind, and whether edits can target it on its own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeKind {
+    pub name: String,
+    pub description: String,
+    pub target: Target,
+}
+
+/// How well a kind of node works as the target of an edit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// Found by name with a symbol selector, labeled like this in outlines
+    Symbol(&'static str),
+    /// One of a list of statements, items, or entries, which can be replaced, or have another
+    /// inserted before or after it, on its own
+    Statement,
+    /// Part of a larger node, which is the better target
+    Part,
+}
+
+impl Target {
+    pub fn is_good(self) -> bool {
+        self != Target::Part
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Symbol(label) => write!(f, "good edit target, by name as a `{label}` symbol"),
+            Target::Statement => f.write_str("good edit target, as a statement or entry"),
+            Target::Part => f.write_str("part of a larger node"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NodeType {
+    #[serde(rename = "type")]
+    kind: String,
+    named: bool,
+    #[serde(default)]
+    root: bool,
+    #[serde(default)]
+    subtypes: Vec<KindRef>,
+    #[serde(default)]
+    fields: BTreeMap<String, Children>,
+    children: Option<Children>,
+}
+
+#[derive(Deserialize)]
+struct Children {
+    multiple: bool,
+    types: Vec<KindRef>,
+}
+
+#[derive(Deserialize)]
+struct KindRef {
+    #[serde(rename = "type")]
+    kind: String,
+    named: bool,
+}
+
+/// The named node kinds of `language`, sorted by name, or None if its grammar's node-types.json
+/// isn't built in
+pub fn glossary(language: &LanguageCommon) -> Option<Vec<NodeKind>> {
+    let node_types: Vec<NodeType> = serde_json::from_str(language.node_types()?).ok()?;
+    let by_kind = node_types
+        .iter()
+        .filter(|node_type| node_type.named)
+        .map(|node_type| (node_type.kind.as_str(), node_type))
+        .collect::<BTreeMap<_, _>>();
+
+    // statements are what the root and the bodies of other nodes hold lists of
+    let bodies = node_types
+        .iter()
+        .filter_map(|node_type| node_type.fields.get("body"))
+        .flat_map(|body| &body.types)
+        .map(|body| body.kind.as_str())
+        .collect::<BTreeSet<_>>();
+    let mut statements = BTreeSet::new();
+    for node_type in &node_types {
+        if !(node_type.root || bodies.contains(node_type.kind.as_str())) {
+            continue;
+        }
+        if let Some(children) = node_type.children.as_ref().filter(|list| list.multiple) {
+            for child in children.types.iter().filter(|child| child.named) {
+                expand(&by_kind, &child.kind, &mut statements);
+            }
+        }
+    }
+
+    Some(
+        by_kind
+            .values()
+            .map(|node_type| {
+                let target = match language
+                    .symbol_kinds()
+                    .iter()
+                    .find(|(kind, _)| *kind == node_type.kind)
+                {
+                    Some((_, label)) => Target::Symbol(label),
+                    None if statements.contains(node_type.kind.as_str()) => Target::Statement,
+                    None => Target::Part,
+                };
+                NodeKind {
+                    name: node_type.kind.clone(),
+                    description: describe(node_type),
+                    target,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Add `kind` to `kinds`, along with the kinds it stands for if it's a supertype. Expressions are left
+/// out, since an expression is rarely a statement of its own.
+fn expand<'a>(
+    by_kind: &BTreeMap<&str, &'a NodeType>,
+    kind: &'a str,
+    kinds: &mut BTreeSet<&'a str>,
+) {
+    match by_kind.get(kind) {
+        Some(node_type) if !node_type.subtypes.is_empty() => {
+            if kind.contains("expression") || !kinds.insert(kind) {
+                return;
+            }
+            for subtype in node_type.subtypes.iter().filter(|subtype| subtype.named) {
+                expand(by_kind, &subtype.kind, kinds);
+            }
+        }
+        _ => {
+            kinds.insert(kind);
+        }
+    }
+}
+
+fn describe(node_type: &NodeType) -> String {
+    let list = |kinds: &[&str]| {
+        let mut listed = kinds
+            .iter()
+            .take(LISTED_KINDS)
+            .map(|kind| format!("`{kind}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if kinds.len() > LISTED_KINDS {
+            listed.push_str(&format!(", and {} more", kinds.len() - LISTED_KINDS));
+        }
+        listed
+    };
+
+    if !node_type.subtypes.is_empty() {
+        return format!("any of: {}", list(&named(&node_type.subtypes)));
+    }
+    let mut parts = vec![];
+    if !node_type.fields.is_empty() {
+        parts.push(format!(
+            "fields: {}",
+            node_type
+                .fields
+                .keys()
+                .map(|field| format!("`{field}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if let Some(children) = &node_type.children {
+        let kinds = named(&children.types);
+        if !kinds.is_empty() {
+            parts.push(format!(
+                "{}: {}",
+                if children.multiple {
+                    "children"
+                } else {
+                    "a child"
+                },
+                list(&kinds)
+            ));
+        }
+    }
+    if parts.is_empty() {
+        "text with no named nodes inside".into()
+    } else {
+        parts.join("; ")
+    }
+}
+
+/// The named kinds among `kinds`
+fn named(kinds: &[KindRef]) -> Vec<&str> {
+    kinds
+        .iter()
+        .filter(|kind| kind.named)
+        .map(|kind| kind.kind.as_str())
+        .collect()
+}
+
+#[cfg(all(test, feature = "rust"))]
+mod tests {
+    use super::*;
+    use crate::languages::{LanguageName, LanguageRegistry};
+
+    #[test]
+    fn rust_kinds() {
+        let registry = LanguageRegistry::new().unwrap();
+        let glossary = glossary(registry.get_language(LanguageName::Rust)).unwrap();
+        let kind = |name: &str| glossary.iter().find(|kind| kind.name == name).unwrap();
+
+        assert_eq!(kind("function_item").target, Target::Symbol("fn"));
+        assert!(
+            kind("function_item")
+                .description
+                .starts_with("fields: `body`, `name`, `parameters`"),
+            "{}",
+            kind("function_item").description
+        );
+        assert_eq!(kind("let_declaration").target, Target::Statement);
+        assert_eq!(kind("use_declaration").target, Target::Statement);
+        assert_eq!(kind("_declaration_statement").target, Target::Statement);
+        assert_eq!(kind("identifier").target, Target::Part);
+        assert_eq!(kind("binary_expression").target, Target::Part);
+        assert_eq!(
+            kind("identifier").description,
+            "text with no named nodes inside"
+        );
+        assert!(
+            kind("_declaration_statement")
+                .description
+                .starts_with("any of: ")
+        );
+        assert!(glossary.iter().all(|kind| kind.name != "{"));
+    }
+}
@@ -0,0 +1,304 @@
+use crate::indentation::Indentation;
+use std::ops::Range;
+use tree_sitter::{Node, Tree};
+
+/// The fields of package.json that map names to strings, which set_package_entry edits
+pub const FIELDS: &[&str] = &[
+    "bin",
+    "dependencies",
+    "devDependencies",
+    "engines",
+    "optionalDependencies",
+    "peerDependencies",
+    "resolutions",
+    "scripts",
+];
+
+/// A change to package.json: the range to replace and the text to put there
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change {
+    pub range: Range<usize>,
+    pub content: String,
+    /// The first line of the field's pair, or of the last pair in the file if the field is new
+    pub anchor: String,
+}
+
+/// Set `key` to `value` in the map under `field`, or remove it when `value` is None
+///
+/// An existing entry stays where it is. A new script goes at the end, and any other new entry
+/// where it belongs alphabetically if the map's keys are in order, as npm keeps dependencies, and
+/// at the end otherwise. The field is
+/// added at the end of the file's object if it isn't there.
+pub fn set_entry(
+    tree: &Tree,
+    source: &str,
+    field: &str,
+    key: &str,
+    value: Option<&str>,
+) -> Result<Change, String> {
+    let root = tree
+        .root_node()
+        .named_children(&mut tree.root_node().walk())
+        .find(|node| node.kind() == "object")
+        .ok_or("package.json doesn't hold an object")?;
+    let indentation = Indentation::determine(source).unwrap_or(Indentation::Spaces(2));
+    let first_line = |node: Node<'_>| {
+        source[node.byte_range()]
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+    let quoted = |text: &str| serde_json::to_string(text).unwrap_or_default();
+
+    let Some(pair) = pairs(root)
+        .into_iter()
+        .find(|pair| key_of(*pair, source) == field)
+    else {
+        let value = value.ok_or_else(|| format!("package.json has no {field}"))?;
+        let colon = pairs(root)
+            .first()
+            .map_or(": ", |pair| colon(*pair, source));
+        let indent = format!("{}{indentation}", indent_of(source, root.start_byte()));
+        let map = format!(
+            "{}{colon}{{\n{indent}{indentation}{}{colon}{}\n{indent}}}",
+            quoted(field),
+            quoted(key),
+            quoted(value)
+        );
+        let mut change = insert(source, root, None, &map, &indent);
+        change.anchor = pairs(root)
+            .last()
+            .map_or_else(|| first_line(root), |last| first_line(*last));
+        return Ok(change);
+    };
+
+    let anchor = first_line(pair);
+    let map = pair
+        .child_by_field_name("value")
+        .filter(|map| map.kind() == "object")
+        .ok_or_else(|| format!("{field} in package.json isn't an object"))?;
+    let entries = pairs(map);
+    let existing = entries
+        .iter()
+        .position(|entry| key_of(*entry, source) == key);
+
+    let mut change = match (existing, value) {
+        (Some(index), Some(value)) => {
+            let old = entries[index]
+                .child_by_field_name("value")
+                .ok_or_else(|| format!("{key} in {field} has no value"))?;
+            Change {
+                range: old.byte_range(),
+                content: quoted(value),
+                anchor: String::new(),
+            }
+        }
+        (Some(index), None) => Change {
+            range: match (index.checked_sub(1), entries.get(index + 1)) {
+                (_, Some(next)) => entries[index].start_byte()..next.start_byte(),
+                (Some(previous), None) => entries[previous].end_byte()..entries[index].end_byte(),
+                (None, None) => map.start_byte() + 1..map.end_byte() - 1,
+            },
+            content: String::new(),
+            anchor: String::new(),
+        },
+        (None, None) => return Err(format!("{field} in package.json has no {key}")),
+        (None, Some(value)) => {
+            let keys = entries
+                .iter()
+                .map(|entry| key_of(*entry, source))
+                .collect::<Vec<_>>();
+            // scripts are in the order they were written, which is often the order they run in
+            let before = (field != "scripts" && keys.is_sorted())
+                .then(|| keys.iter().position(|existing| existing.as_str() > key))
+                .flatten()
+                .map(|index| entries[index]);
+            let colon = entries
+                .first()
+                .or(Some(&pair))
+                .map_or(": ", |entry| colon(*entry, source));
+            let indent = entries.first().map_or_else(
+                || format!("{}{indentation}", indent_of(source, pair.start_byte())),
+                |first| indent_of(source, first.start_byte()).to_string(),
+            );
+            let entry = format!("{}{colon}{}", quoted(key), quoted(value));
+            insert(source, map, before, &entry, &indent)
+        }
+    };
+    change.anchor = anchor;
+    Ok(change)
+}
+
+/// Insert `entry` into `object` before the pair `before`, or at the end, on a line of its own
+/// indented with `indent` unless the object is all on one line
+fn insert(
+    source: &str,
+    object: Node<'_>,
+    before: Option<Node<'_>>,
+    entry: &str,
+    indent: &str,
+) -> Change {
+    let inline = object.start_position().row == object.end_position().row;
+    let pairs = pairs(object);
+    let (range, content) = match (before, pairs.last()) {
+        (Some(before), _) if inline => (
+            before.start_byte()..before.start_byte(),
+            format!("{entry}, "),
+        ),
+        (Some(before), _) => (
+            before.start_byte()..before.start_byte(),
+            format!("{entry},\n{}", indent_of(source, before.start_byte())),
+        ),
+        (None, Some(last)) if inline => (last.end_byte()..last.end_byte(), format!(", {entry}")),
+        (None, Some(last)) => (
+            last.end_byte()..last.end_byte(),
+            format!(",\n{indent}{entry}"),
+        ),
+        (None, None) => (
+            object.start_byte() + 1..object.end_byte() - 1,
+            format!(
+                "\n{indent}{entry}\n{}",
+                indent_of(source, object.start_byte())
+            ),
+        ),
+    };
+    Change {
+        range,
+        content,
+        anchor: String::new(),
+    }
+}
+
+fn pairs(object: Node<'_>) -> Vec<Node<'_>> {
+    object
+        .named_children(&mut object.walk())
+        .filter(|child| child.kind() == "pair")
+        .collect()
+}
+
+/// The key of a pair, without quotes
+fn key_of(pair: Node<'_>, source: &str) -> String {
+    pair.child_by_field_name("key")
+        .map(|key| source[key.byte_range()].trim_matches('"').to_string())
+        .unwrap_or_default()
+}
+
+/// What separates a pair's key from its value, like `": "`
+fn colon<'source>(pair: Node<'_>, source: &'source str) -> &'source str {
+    match (
+        pair.child_by_field_name("key"),
+        pair.child_by_field_name("value"),
+    ) {
+        (Some(key), Some(value)) => &source[key.end_byte()..value.start_byte()],
+        _ => ": ",
+    }
+}
+
+/// The indentation of the line `offset` is on
+fn indent_of(source: &str, offset: usize) -> &str {
+    let start = source[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line = &source[start..];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+
+    fn changed(source: &str, field: &str, key: &str, value: Option<&str>) -> String {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_json::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let change = set_entry(&tree, source, field, key, value).unwrap();
+        format!(
+            "{}{}{}",
+            &source[..change.range.start],
+            change.content,
+            &source[change.range.end..]
+        )
+    }
+
+    const PACKAGE: &str = r#"{
+  "name": "app",
+  "scripts": {
+    "test": "vitest",
+    "build": "vite build"
+  },
+  "dependencies": {
+    "react": "^18.2.0",
+    "zod": "^3.22.0"
+  }
+}
+"#;
+
+    #[test]
+    fn scripts_keep_their_order() {
+        assert_eq!(
+            changed(PACKAGE, "scripts", "lint", Some("eslint .")),
+            PACKAGE.replace(
+                r#""build": "vite build"
+"#,
+                r#""build": "vite build",
+    "lint": "eslint ."
+"#
+            )
+        );
+        assert_eq!(
+            changed(PACKAGE, "scripts", "test", Some("vitest run")),
+            PACKAGE.replace(r#""vitest""#, r#""vitest run""#)
+        );
+        assert_eq!(
+            changed(PACKAGE, "scripts", "test", None),
+            PACKAGE.replace(
+                r#""test": "vitest",
+    "#,
+                ""
+            )
+        );
+        assert_eq!(
+            changed(PACKAGE, "scripts", "build", None),
+            PACKAGE.replace(
+                r#",
+    "build": "vite build""#,
+                ""
+            )
+        );
+    }
+
+    #[test]
+    fn sorted_maps_stay_sorted() {
+        assert_eq!(
+            changed(PACKAGE, "dependencies", "vite", Some("^5.0.0")),
+            PACKAGE.replace(
+                r#""zod""#,
+                r#""vite": "^5.0.0",
+    "zod""#
+            )
+        );
+        assert_eq!(
+            changed(PACKAGE, "devDependencies", "vitest", Some("^1.0.0")),
+            PACKAGE.replace(
+                r#"    "zod": "^3.22.0"
+  }"#,
+                r#"    "zod": "^3.22.0"
+  },
+  "devDependencies": {
+    "vitest": "^1.0.0"
+  }"#
+            )
+        );
+        assert_eq!(
+            changed(
+                r#"{"scripts": {}}"#,
+                "scripts",
+                "dev",
+                Some(r#"vite --host "0.0.0.0""#)
+            ),
+            "{\"scripts\": {\n  \"dev\": \"vite --host \\\"0.0.0.0\\\"\"\n}}"
+        );
+    }
+}
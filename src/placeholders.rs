@@ -0,0 +1,143 @@
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Expand snippet placeholders in edit content for the file it is going into:
+///
+/// * `${1:name}` becomes its default text `name`, and `${1}` is removed, so that snippets
+///   written for editors can be used as-is
+/// * `${file_stem}` and `${file_name}` become the file's name without and with its extension
+/// * `${date}` becomes today's date (UTC) as `YYYY-MM-DD`
+///
+/// Anything else that looks like a placeholder, like a JavaScript template literal's `${value}`,
+/// is left alone, as is a placeholder preceded by a backslash, which loses the backslash.
+pub fn expand(content: &str, file: &Path) -> String {
+    if !content.contains("${") {
+        return content.to_string();
+    }
+
+    let mut expanded = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find("${") {
+        let escaped = rest[..start].ends_with('\\');
+        let Some((placeholder, length)) = placeholder(&rest[start..], file) else {
+            expanded.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            continue;
+        };
+
+        if escaped {
+            expanded.push_str(&rest[..start - 1]);
+            expanded.push_str(&rest[start..start + length]);
+        } else {
+            expanded.push_str(&rest[..start]);
+            expanded.push_str(&placeholder);
+        }
+        rest = &rest[start + length..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// The expansion of the placeholder at the start of `text`, and the placeholder's length
+fn placeholder(text: &str, file: &Path) -> Option<(String, usize)> {
+    // find the closing brace, allowing braces to nest within a default
+    let mut depth = 0;
+    let end = text.char_indices().skip(1).find_map(|(index, c)| {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 1 => return Some(index),
+            '}' => depth -= 1,
+            _ => {}
+        }
+        None
+    })?;
+    let inner = &text[2..end];
+
+    let expansion = match inner {
+        "file_stem" => file.file_stem()?.to_string_lossy().into_owned(),
+        "file_name" => file.file_name()?.to_string_lossy().into_owned(),
+        "date" => today(),
+        _ => {
+            let (tabstop, default) = inner.split_once(':').unwrap_or((inner, ""));
+            if tabstop.is_empty() || !tabstop.bytes().all(|byte| byte.is_ascii_digit()) {
+                return None;
+            }
+            expand(default, file)
+        }
+    };
+    Some((expansion, end + 1))
+}
+
+/// Today's date in UTC as `YYYY-MM-DD`
+fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// The proleptic Gregorian date `days` after 1970-01-01, from Howard Hinnant's
+/// [date algorithms](https://howardhinnant.github.io/date_algorithms.html#civil_from_days)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_expanded() {
+        let file = Path::new("src/user_card.rs");
+        assert_eq!(
+            expand(
+                "pub struct ${1:${file_stem}_${2:props}} {\n    ${3}\n}\n// ${file_name}",
+                file
+            ),
+            "pub struct user_card_props {\n    \n}\n// user_card.rs"
+        );
+        assert_eq!(expand("// ${date}", file), format!("// {}", today()));
+    }
+
+    #[test]
+    fn other_dollar_braces_are_left_alone() {
+        let file = Path::new("app.ts");
+        for content in [
+            "const s = `${name}: ${count + 1}`;",
+            r#"let re = /^\$\{a\}$/;"#,
+            "const t = `${",
+            "${:x} ${a1:y}",
+        ] {
+            assert_eq!(expand(content, file), content);
+        }
+        assert_eq!(
+            expand(r"echo \${file_stem} ${file_stem}", file),
+            "echo ${file_stem} app"
+        );
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20_742), (2026, 10, 16));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+}
@@ -0,0 +1,128 @@
+use crate::languages::toml;
+use anyhow::{Result, anyhow};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where a project lists paths that are only written with explicit confirmation, relative to a
+/// directory containing the edited file
+pub const PROTECTED: &str = ".semantic-edit/protected.toml";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    paths: Vec<String>,
+}
+
+/// The glob patterns of a project's high-risk files, like
+///
+/// ```toml
+/// # .semantic-edit/protected.toml
+/// paths = ["**/migrations/**", "Cargo.lock", ".github/**"]
+/// ```
+///
+/// Patterns match paths relative to the directory containing `.semantic-edit`.
+#[derive(Debug)]
+pub struct ProtectedPaths {
+    root: PathBuf,
+    patterns: Vec<String>,
+    globs: GlobSet,
+}
+
+impl ProtectedPaths {
+    /// The patterns in the nearest protected path configuration above `file`, if there is one
+    pub fn for_file(file: &Path) -> Result<Option<Self>> {
+        let Some(root) = file
+            .ancestors()
+            .skip(1)
+            .find(|directory| directory.join(PROTECTED).is_file())
+        else {
+            return Ok(None);
+        };
+
+        let path = root.join(PROTECTED);
+        let invalid = |error: &dyn std::fmt::Display| {
+            anyhow!(
+                "{} is not a valid protected path configuration: {error}",
+                path.display()
+            )
+        };
+        let config: Config = serde_json::from_value(toml::to_json(&fs::read_to_string(&path)?)?)
+            .map_err(|error| invalid(&error))?;
+        let mut globs = GlobSetBuilder::new();
+        for pattern in &config.paths {
+            globs.add(Glob::new(pattern).map_err(|error| invalid(&error))?);
+        }
+        Ok(Some(Self {
+            root: root.to_path_buf(),
+            patterns: config.paths,
+            globs: globs.build().map_err(|error| invalid(&error))?,
+        }))
+    }
+
+    /// The first pattern that matches `file`
+    pub fn matching(&self, file: &Path) -> Option<&str> {
+        let relative = file.strip_prefix(&self.root).ok()?;
+        let index = *self.globs.matches(relative).first()?;
+        Some(&self.patterns[index])
+    }
+}
+
+/// Why writing `file` needs confirmation, if it is protected
+pub fn reason(file: &Path) -> Result<Option<String>> {
+    let Some(protected) = ProtectedPaths::for_file(file)? else {
+        return Ok(None);
+    };
+    Ok(protected.matching(file).map(|pattern| {
+        format!(
+            "{} matches the protected path `{pattern}` in {PROTECTED}",
+            file.strip_prefix(&protected.root).unwrap_or(file).display()
+        )
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_match_relative_to_the_configuration() {
+        let dir =
+            std::env::temp_dir().join(format!("semantic-edit-protected-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".semantic-edit")).unwrap();
+        fs::write(
+            dir.join(PROTECTED),
+            "paths = [\"**/migrations/**\", \"Cargo.lock\", \".github/**\"]\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            reason(&dir.join("db/migrations/0001_init.sql")).unwrap(),
+            Some(format!(
+                "db/migrations/0001_init.sql matches the protected path `**/migrations/**` in \
+                 {PROTECTED}"
+            ))
+        );
+        assert!(reason(&dir.join("Cargo.lock")).unwrap().is_some());
+        assert!(
+            reason(&dir.join(".github/workflows/ci.yml"))
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(reason(&dir.join("src/lib.rs")).unwrap(), None);
+        assert_eq!(reason(&dir.join("src/Cargo.lock")).unwrap(), None);
+
+        fs::write(dir.join(PROTECTED), "paths = [\"[\"]\n").unwrap();
+        let error = reason(&dir.join("src/lib.rs")).unwrap_err().to_string();
+        assert!(
+            error.contains("is not a valid protected path configuration"),
+            "{error}"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
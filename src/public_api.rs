@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+use tree_sitter::{Node, Query, QueryCursor, StreamingIterator, Tree};
+
+/// An item in a file's public API
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiItem {
+    /// 1-indexed line the item starts on
+    pub line: usize,
+    /// How many enclosing items (modules, types, traits, impl blocks) it is listed under
+    pub depth: usize,
+    /// The item's declaration without its body or value, on one line
+    pub signature: String,
+}
+
+/// List the public API of a file, using a query that captures every potentially public
+/// `@item`.
+///
+/// Captured items only count if every item that encloses them is public too, so `pub` items in
+/// private modules or function bodies are left out. Public methods are listed under the
+/// inherent `impl` block that defines them.
+pub fn public_api(query: &Query, tree: &Tree, source: &str) -> Vec<ApiItem> {
+    let Some(item_capture) = query.capture_index_for_name("item") else {
+        return vec![];
+    };
+
+    let mut captured = vec![];
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+    while let Some(query_match) = matches.next() {
+        captured.extend(
+            query_match
+                .captures
+                .iter()
+                .filter(|capture| capture.index == item_capture)
+                .map(|capture| capture.node),
+        );
+    }
+
+    let public = captured.iter().map(Node::id).collect::<HashSet<_>>();
+    let mut listed = HashSet::new();
+    let mut items = vec![];
+    for node in captured {
+        let Some(impl_blocks) = enclosing_impl_blocks(node, &public) else {
+            continue;
+        };
+        for listed_node in impl_blocks.into_iter().chain([node]) {
+            if listed.insert(listed_node.id()) {
+                items.push(listed_node);
+            }
+        }
+    }
+    items.sort_by_key(Node::start_byte);
+
+    let listed_ids = items.iter().map(Node::id).collect::<HashSet<_>>();
+    items
+        .into_iter()
+        .map(|node| ApiItem {
+            line: node.start_position().row + 1,
+            depth: ancestors(node)
+                .filter(|ancestor| listed_ids.contains(&ancestor.id()))
+                .count(),
+            signature: signature(node, source),
+        })
+        .collect()
+}
+
+fn ancestors(node: Node<'_>) -> impl Iterator<Item = Node<'_>> {
+    std::iter::successors(node.parent(), Node::parent)
+}
+
+/// Check that everything enclosing `node` is public, returning the inherent impl blocks it is
+/// nested in, or None if it is not reachable from outside the file
+fn enclosing_impl_blocks<'tree>(
+    node: Node<'tree>,
+    public: &HashSet<usize>,
+) -> Option<Vec<Node<'tree>>> {
+    let mut impl_blocks = vec![];
+    for ancestor in ancestors(node) {
+        match ancestor.kind() {
+            "source_file" => return Some(impl_blocks),
+            "declaration_list" | "field_declaration_list" | "enum_variant_list" => {}
+            "impl_item" if ancestor.child_by_field_name("trait").is_none() => {
+                impl_blocks.push(ancestor);
+            }
+            "mod_item" | "struct_item" | "enum_item" | "union_item" | "trait_item"
+                if public.contains(&ancestor.id()) => {}
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// An item's declaration up to its body or value, with whitespace collapsed
+fn signature(node: Node<'_>, source: &str) -> String {
+    let end = node
+        .child_by_field_name("body")
+        .or_else(|| node.child_by_field_name("value"))
+        .filter(|_| node.kind() != "enum_variant")
+        .map_or(node.end_byte(), |child| child.start_byte());
+    let text = source[node.start_byte()..end]
+        .trim_end()
+        .trim_end_matches(['=', ';', '{'])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    match node.kind() {
+        "macro_definition" => text.split('{').next().unwrap_or_default().trim().into(),
+        _ => text,
+    }
+}
+
+#[cfg(all(test, feature = "rust"))]
+mod tests {
+    use super::*;
+    use crate::languages::{LanguageName, LanguageRegistry};
+
+    fn api(source: &str) -> Vec<(usize, String)> {
+        let registry = LanguageRegistry::new().unwrap();
+        let rust = registry.get_language(LanguageName::Rust);
+        let tree = rust
+            .tree_sitter_parser()
+            .unwrap()
+            .parse(source, None)
+            .unwrap();
+        public_api(rust.public_api_query().unwrap(), &tree, source)
+            .into_iter()
+            .map(|item| (item.depth, item.signature))
+            .collect()
+    }
+
+    #[test]
+    fn lists_reachable_public_items() {
+        let source = r#"
+pub struct Config {
+    pub name: String,
+    secret: String,
+}
+
+impl Config {
+    pub fn new(name: impl Into<String>) -> Self {
+        todo!()
+    }
+
+    fn private(&self) {}
+}
+
+pub(crate) fn internal() {}
+
+mod private {
+    pub fn hidden() {}
+}
+
+pub mod public {
+    pub const LIMIT: usize = 10;
+}
+
+fn body() {
+    pub struct Local;
+}
+"#;
+        assert_eq!(
+            api(source),
+            vec![
+                (0, "pub struct Config".to_string()),
+                (1, "pub name: String".to_string()),
+                (0, "impl Config".to_string()),
+                (1, "pub fn new(name: impl Into<String>) -> Self".to_string()),
+                (0, "pub mod public".to_string()),
+                (1, "pub const LIMIT: usize".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_enum_variants_and_trait_items() {
+        let source = r#"
+pub enum Shape {
+    Circle { radius: f64 },
+    Square(f64),
+}
+
+enum Hidden {
+    Variant,
+}
+
+pub trait Area {
+    type Unit;
+    fn area(&self) -> f64;
+    fn describe(&self) -> String {
+        String::new()
+    }
+}
+
+#[macro_export]
+macro_rules! shape {
+    ($x:expr) => {};
+}
+"#;
+        assert_eq!(
+            api(source),
+            vec![
+                (0, "pub enum Shape".to_string()),
+                (1, "Circle { radius: f64 }".to_string()),
+                (1, "Square(f64)".to_string()),
+                (0, "pub trait Area".to_string()),
+                (1, "type Unit".to_string()),
+                (1, "fn area(&self) -> f64".to_string()),
+                (1, "fn describe(&self) -> String".to_string()),
+                (0, "macro_rules! shape".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! Recipes for insertions common enough to be worth a template, like a test in a Rust `tests`
+//! module or a dependency in a package.json, which language editors register and apply_recipe
+//! fills in with parameters
+
+use crate::{
+    indentation::Indentation,
+    key_path::{self, Change},
+    languages::LanguageCommon,
+    symbols,
+};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+use tree_sitter::{Node, Tree};
+
+/// A template for a common insertion, bound to the kind of node it goes in
+#[derive(Debug)]
+pub struct Recipe {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub parameters: &'static [Parameter],
+    pub placement: Placement,
+    /// The text to insert, in which `{{parameter}}` stands for a parameter's value. A
+    /// placeholder on a line of its own is replaced by every line of the value, indented like
+    /// the placeholder.
+    pub template: &'static str,
+}
+
+#[derive(Debug)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// The value used when the parameter is left out, which makes it optional
+    pub default: Option<&'static str>,
+}
+
+/// Where a recipe's text goes, with placeholders filled in like the template's
+#[derive(Debug)]
+pub enum Placement {
+    /// At the end of the body of the symbol `symbol` names, as find would look it up, which
+    /// must be a node of kind `kind`
+    #[cfg_attr(
+        not(any(feature = "rust", feature = "python")),
+        expect(
+            dead_code,
+            reason = "only the Rust and Python editors have body recipes"
+        )
+    )]
+    Body {
+        kind: &'static str,
+        symbol: &'static str,
+    },
+    /// As the value at the key path `path`, added or replaced as set_key would
+    #[cfg_attr(
+        not(any(feature = "json", feature = "toml")),
+        expect(
+            dead_code,
+            reason = "only the JSON and TOML editors have entry recipes"
+        )
+    )]
+    Entry { path: &'static str },
+}
+
+impl Display for Placement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Placement::Body { kind, symbol } => {
+                write!(f, "at the end of `{symbol}` (node kind `{kind}`)")
+            }
+            Placement::Entry { path } => write!(f, "as `{path}`"),
+        }
+    }
+}
+
+impl Recipe {
+    /// The value of each parameter: the one given, or else its default
+    fn values(
+        &self,
+        given: &BTreeMap<String, String>,
+    ) -> Result<BTreeMap<&'static str, String>, String> {
+        let names = || {
+            self.parameters
+                .iter()
+                .map(|parameter| parameter.name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        if let Some(unknown) = given.keys().find(|name| {
+            !self
+                .parameters
+                .iter()
+                .any(|parameter| parameter.name == *name)
+        }) {
+            return Err(format!(
+                "The {} recipe has no parameter `{unknown}`. Its parameters are {}.",
+                self.name,
+                names()
+            ));
+        }
+        self.parameters
+            .iter()
+            .map(|parameter| {
+                let value = given
+                    .get(parameter.name)
+                    .map(String::as_str)
+                    .or(parameter.default)
+                    .ok_or_else(|| {
+                        format!(
+                            "The {} recipe needs `{}`: {}",
+                            self.name, parameter.name, parameter.description
+                        )
+                    })?;
+                Ok((parameter.name, value.to_string()))
+            })
+            .collect()
+    }
+
+    /// Where this recipe goes in `source` and the text to put there, for the `given`
+    /// parameters
+    pub fn change(
+        &self,
+        language: &LanguageCommon,
+        tree: &Tree,
+        source: &str,
+        given: &BTreeMap<String, String>,
+    ) -> Result<Change, String> {
+        let values = self.values(given)?;
+        let snippet = fill(self.template, &values);
+        match self.placement {
+            Placement::Body { kind, symbol } => {
+                let symbol = fill(symbol, &values);
+                let outline = symbols::outline(language, tree, source);
+                let node = symbols::find(&outline, &symbol)?;
+                if node.kind() != kind {
+                    return Err(format!(
+                        "`{symbol}` is a `{}` node, and the {} recipe only adds to `{kind}` nodes",
+                        node.kind(),
+                        self.name,
+                    ));
+                }
+                let body = node.child_by_field_name("body").ok_or_else(|| {
+                    format!(
+                        "`{symbol}` has no body in this file to add the {} to",
+                        self.name
+                    )
+                })?;
+                Ok(body_insertion(node, body, source, snippet.trim_end()))
+            }
+            Placement::Entry { path } => {
+                let path = key_path::parse(&fill(path, &values))?;
+                key_path::set(language.editor(), tree, source, &path, snippet.trim())
+            }
+        }
+    }
+}
+
+/// `template` with each `{{parameter}}` replaced by its value
+fn fill(template: &str, values: &BTreeMap<&str, String>) -> String {
+    let mut filled = String::with_capacity(template.len());
+    for line in template.split_inclusive('\n') {
+        let alone = line
+            .trim()
+            .strip_prefix("{{")
+            .and_then(|rest| rest.strip_suffix("}}"))
+            .and_then(|name| values.get(name));
+        if let Some(value) = alone {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            for value_line in value.lines() {
+                if !value_line.trim().is_empty() {
+                    filled.push_str(indent);
+                    filled.push_str(value_line);
+                }
+                filled.push('\n');
+            }
+            if !line.ends_with('\n') {
+                filled.pop();
+            }
+            continue;
+        }
+
+        let mut line = Cow::Borrowed(line);
+        for (name, value) in values {
+            let placeholder = format!("{{{{{name}}}}}");
+            if line.contains(&placeholder) {
+                line = Cow::Owned(line.replace(&placeholder, value));
+            }
+        }
+        filled.push_str(&line);
+    }
+    filled
+}
+
+/// `snippet` after the last item of `body`, the body of `node`, separated from it by a blank
+/// line and indented like it, or as the only item of an empty body
+fn body_insertion(node: Node<'_>, body: Node<'_>, source: &str, snippet: &str) -> Change {
+    let indentation = Indentation::determine(source).unwrap_or(Indentation::Spaces(4));
+    let indented = |indent: &str| {
+        let mut content = Cow::Borrowed(snippet);
+        indentation.reindent(indentation.unit_count(indent), &mut content, true);
+        content.into_owned()
+    };
+    let first_line = |node: Node<'_>| {
+        source[node.byte_range()]
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+
+    if let Some(last) = body.named_children(&mut body.walk()).last() {
+        return Change {
+            range: last.end_byte()..last.end_byte(),
+            content: format!(
+                "\n\n{}",
+                indented(key_path::indent_of(source, last.start_byte()))
+            ),
+            anchor: first_line(last),
+        };
+    }
+
+    // an empty body like `{}`, whose braces are its first and last children
+    let outer = key_path::indent_of(source, node.start_byte());
+    let open = body
+        .child(0)
+        .filter(|child| !child.is_named())
+        .map_or(body.start_byte(), |child| child.end_byte());
+    let close = body
+        .child(body.child_count().saturating_sub(1))
+        .filter(|child| !child.is_named() && child.end_byte() > open)
+        .map_or(body.end_byte(), |child| child.start_byte());
+    Change {
+        range: open..close,
+        content: format!("\n{}\n{outer}", indented(&format!("{outer}{indentation}"))),
+        anchor: first_line(node),
+    }
+}
+
+#[cfg(all(test, any(feature = "rust", feature = "python", feature = "json")))]
+mod tests {
+    use crate::languages::{LanguageName, LanguageRegistry};
+
+    fn apply(language: LanguageName, source: &str, recipe: &str, given: &[(&str, &str)]) -> String {
+        let registry = LanguageRegistry::new().unwrap();
+        let language = registry.get_language(language);
+        let recipe = language
+            .editor()
+            .recipes()
+            .iter()
+            .find(|candidate| candidate.name == recipe)
+            .unwrap();
+        let tree = language
+            .tree_sitter_parser()
+            .unwrap()
+            .parse(source, None)
+            .unwrap();
+        let given = given
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        match recipe.change(language, &tree, source, &given) {
+            Ok(change) => format!(
+                "{}{}{}",
+                &source[..change.range.start],
+                change.content,
+                &source[change.range.end..]
+            ),
+            Err(message) => message,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "rust")]
+    fn rust_tests_are_added_to_the_tests_module() {
+        let source = "fn add() {}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn adds() {}\n}\n";
+        assert_eq!(
+            apply(
+                LanguageName::Rust,
+                source,
+                "test",
+                &[
+                    ("name", "subtracts"),
+                    ("body", "let x = 1;\nassert_eq!(x, 1);")
+                ]
+            ),
+            "fn add() {}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn adds() {}\n\n    \
+             #[test]\n    fn subtracts() {\n        let x = 1;\n        assert_eq!(x, 1);\n    \
+             }\n}\n"
+        );
+        assert_eq!(
+            apply(
+                LanguageName::Rust,
+                "mod tests {}\n",
+                "test",
+                &[("name", "works")]
+            ),
+            "mod tests {\n    #[test]\n    fn works() {\n        todo!()\n    }\n}\n"
+        );
+        assert_eq!(
+            apply(LanguageName::Rust, source, "test", &[("nme", "x")]),
+            "The test recipe has no parameter `nme`. Its parameters are name, body, module."
+        );
+        assert_eq!(
+            apply(LanguageName::Rust, source, "test", &[]),
+            "The test recipe needs `name`: The name of the test function"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "python")]
+    fn python_methods() {
+        assert_eq!(
+            apply(
+                LanguageName::Python,
+                "class Cache:\n    def get(self, key):\n        return None\n",
+                "method",
+                &[("class", "Cache"), ("signature", "clear(self)")]
+            ),
+            "class Cache:\n    def get(self, key):\n        return None\n\n    \
+             def clear(self):\n        raise NotImplementedError\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json_dependencies() {
+        assert_eq!(
+            apply(
+                LanguageName::Json,
+                "{\n  \"dependencies\": {\n    \"react\": \"^18.0.0\"\n  }\n}\n",
+                "dependency",
+                &[("name", "@types/node"), ("version", "^20.0.0")]
+            ),
+            "{\n  \"dependencies\": {\n    \"react\": \"^18.0.0\",\n    \"@types/node\": \
+             \"^20.0.0\"\n  }\n}\n"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+/// A repaired version of edit content along with a short description of what was changed
+pub(crate) type Repair = (&'static str, String);
+
+/// Repairs that apply to any curly-brace or bracket-delimited language: closing an
+/// unterminated string, appending missing closing delimiters, and dropping unmatched trailing
+/// closing delimiters.
+pub(crate) fn generic_repairs(content: &str) -> Vec<Repair> {
+    let mut repairs = vec![];
+    let scan = scan_delimiters(content);
+
+    if scan.unterminated_string || !scan.unclosed.is_empty() {
+        let mut repaired = content.trim_end().to_string();
+        if scan.unterminated_string {
+            repaired.push('"');
+        }
+        if !scan.unclosed.is_empty() {
+            let separator = if repaired.contains('\n') { "\n" } else { " " };
+            for opener in scan.unclosed.iter().rev() {
+                repaired.push_str(separator);
+                repaired.push(closer_for(*opener));
+            }
+        }
+        let description = match (scan.unterminated_string, scan.unclosed.is_empty()) {
+            (true, true) => "closed an unterminated string",
+            (true, false) => "closed an unterminated string and added missing closing delimiters",
+            _ => "added missing closing delimiters",
+        };
+        repairs.push((description, repaired));
+    }
+
+    if scan.unmatched_trailing_closers > 0 {
+        let mut repaired = content.trim_end().to_string();
+        for _ in 0..scan.unmatched_trailing_closers {
+            repaired.pop();
+            repaired.truncate(repaired.trim_end().len());
+        }
+        repairs.push(("removed unmatched closing delimiters", repaired));
+    }
+
+    repairs
+}
+
+struct DelimiterScan {
+    unclosed: Vec<char>,
+    unterminated_string: bool,
+    unmatched_trailing_closers: usize,
+}
+
+fn closer_for(opener: char) -> char {
+    match opener {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+fn scan_delimiters(content: &str) -> DelimiterScan {
+    let mut unclosed = vec![];
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut unmatched_trailing_closers = 0;
+
+    for c in content.chars() {
+        if in_string {
+            match (escaped, c) {
+                (true, _) => escaped = false,
+                (false, '\\') => escaped = true,
+                (false, '"') => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => unclosed.push(c),
+            ')' | ']' | '}' => {
+                if unclosed
+                    .last()
+                    .is_some_and(|opener| closer_for(*opener) == c)
+                {
+                    unclosed.pop();
+                } else {
+                    unmatched_trailing_closers += 1;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+
+        if !c.is_whitespace() {
+            unmatched_trailing_closers = 0;
+        }
+    }
+
+    DelimiterScan {
+        unclosed,
+        unterminated_string: in_string,
+        unmatched_trailing_closers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closes_unterminated_strings_and_delimiters() {
+        assert_eq!(
+            generic_repairs("fn main() {\n    println!(\"hi);"),
+            vec![(
+                "closed an unterminated string and added missing closing delimiters",
+                "fn main() {\n    println!(\"hi);\"\n)\n}".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn removes_unmatched_trailing_closers() {
+        assert_eq!(
+            generic_repairs("fn main() {\n}\n}\n"),
+            vec![(
+                "removed unmatched closing delimiters",
+                "fn main() {\n}".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn balanced_content_needs_no_repair() {
+        assert!(generic_repairs("let s = \"{[(\";").is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+use std::env;
+
+/// How tool responses lay out diffs, listings, and matches
+///
+/// Responses are plain text by default. `SEMANTIC_EDIT_RESPONSE_FORMAT=markdown` puts diffs and
+/// listings in fenced code blocks under headings and search matches in tables, for clients that
+/// render responses as Markdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResponseFormat {
+    #[default]
+    Text,
+    Markdown,
+}
+
+impl ResponseFormat {
+    pub fn configured() -> Self {
+        match env::var("SEMANTIC_EDIT_RESPONSE_FORMAT").as_deref() {
+            Ok(format) if format.eq_ignore_ascii_case("markdown") => Self::Markdown,
+            _ => Self::Text,
+        }
+    }
+
+    /// A titled section holding `body`, such as a diff or a listing of syntax errors. In
+    /// Markdown the body is fenced as `syntax`.
+    pub fn section(self, title: &str, body: &str, syntax: &str) -> String {
+        match self {
+            Self::Text => format!("==={}===\n{body}", title.to_uppercase()),
+            Self::Markdown => format!("### {title}\n\n{}", fence(body, syntax)),
+        }
+    }
+
+    /// The changes to one of several files, set apart by the file's name
+    pub fn file_section(self, file: &str, body: &str, syntax: &str) -> String {
+        match self {
+            Self::Text => format!("\n=== {file} ===\n{body}\n"),
+            Self::Markdown => format!("\n#### {file}\n\n{}", fence(body, syntax)),
+        }
+    }
+
+    /// Lines where text was found in a file, as `file:line: text` or as a table
+    pub fn matches<'a>(
+        self,
+        matches: impl IntoIterator<Item = (&'a str, usize, &'a str)>,
+    ) -> String {
+        let mut listing = match self {
+            Self::Text => String::new(),
+            Self::Markdown => String::from("\n| File | Line | Text |\n| --- | --- | --- |\n"),
+        };
+        for (file, line, text) in matches {
+            listing.push_str(&match self {
+                Self::Text => format!("{file}:{line}: {text}\n"),
+                Self::Markdown => format!(
+                    "| {} | {line} | `{}` |\n",
+                    file.replace('|', "\\|"),
+                    text.replace('|', "\\|").replace('`', "'")
+                ),
+            });
+        }
+        listing
+    }
+}
+
+/// What became of one file in an operation across several files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Changed,
+    Unchanged,
+    Skipped,
+}
+
+/// One file's entry in the report of an operation across several files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileReport {
+    pub file: String,
+    pub status: FileStatus,
+    /// A few words on what happened, like `2 replacements` or why the file was skipped
+    pub summary: String,
+    /// The diff of a changed file, or the details of why a file was skipped
+    pub body: Option<String>,
+}
+
+impl ResponseFormat {
+    /// A report on an operation that examined `total` files: how many succeeded, then an entry
+    /// for each of `files` with its status, summary, and diff, or `no change`. Files examined
+    /// but left out of `files` count as unchanged.
+    pub fn file_report(self, headline: &str, files: &[FileReport], total: usize) -> String {
+        let count = |status| files.iter().filter(|file| file.status == status).count();
+        let (changed, skipped) = (count(FileStatus::Changed), count(FileStatus::Skipped));
+        let unchanged = total.saturating_sub(changed + skipped);
+        let mut tally = vec![format!("{changed} with changes")];
+        if unchanged > 0 {
+            tally.push(format!("{unchanged} with no change"));
+        }
+        let mut report = format!(
+            "{headline}\n{} of {total} file{} succeeded: {}.",
+            changed + unchanged,
+            if total == 1 { "" } else { "s" },
+            tally.join(", ")
+        );
+        if skipped > 0 {
+            report.push_str(&format!(
+                " {skipped} {} skipped.",
+                if skipped == 1 { "was" } else { "were" }
+            ));
+        }
+        report.push('\n');
+
+        let status = |file: &FileReport| match file.status {
+            FileStatus::Changed => format!("✅ {}", file.summary),
+            FileStatus::Unchanged if file.summary.is_empty() => "✅ No change".to_string(),
+            FileStatus::Unchanged => format!("✅ No change: {}", file.summary),
+            FileStatus::Skipped => format!("❌ Skipped: {}", file.summary),
+        };
+        let syntax = |file: &FileReport| match file.status {
+            FileStatus::Changed => "diff",
+            _ => "",
+        };
+        match self {
+            Self::Text => {
+                for file in files {
+                    report.push_str(&format!("\n=== {} ===\n{}\n", file.file, status(file)));
+                    if let Some(body) = &file.body {
+                        report.push_str(body.trim_end_matches('\n'));
+                        report.push('\n');
+                    }
+                }
+            }
+            Self::Markdown => {
+                report.push_str("\n| File | Status |\n| --- | --- |\n");
+                for file in files {
+                    report.push_str(&format!(
+                        "| {} | {} |\n",
+                        file.file.replace('|', "\\|"),
+                        status(file).replace('|', "\\|").replace('\n', " ")
+                    ));
+                }
+                for file in files {
+                    if let Some(body) = &file.body {
+                        report.push_str(&format!(
+                            "\n#### {}\n\n{}",
+                            file.file,
+                            fence(body, syntax(file))
+                        ));
+                    }
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Fence `body` as a code block, long enough that no run of backticks in it closes the block
+fn fence(body: &str, syntax: &str) -> String {
+    let longest = body
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!(
+        "{fence}{syntax}\n{}\n{fence}\n",
+        body.trim_end_matches('\n')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_sections_are_unchanged_and_markdown_is_fenced() {
+        let patch = "@@ -1 +1 @@\n-a\n+b\n";
+        assert_eq!(
+            ResponseFormat::Text.section("Diff", patch, "diff"),
+            format!("===DIFF===\n{patch}")
+        );
+        assert_eq!(
+            ResponseFormat::Markdown.section("Diff", patch, "diff"),
+            format!("### Diff\n\n```diff\n{patch}```\n")
+        );
+        assert_eq!(
+            ResponseFormat::Markdown.file_section("a.md", "```rust\n```", ""),
+            "\n#### a.md\n\n````\n```rust\n```\n````\n"
+        );
+    }
+
+    #[test]
+    fn file_reports_count_successes_and_show_each_file() {
+        let files = [
+            FileReport {
+                file: "src/a.rs".into(),
+                status: FileStatus::Changed,
+                summary: "2 replacements".into(),
+                body: Some("@@ -1 +1 @@\n-a\n+b\n".into()),
+            },
+            FileReport {
+                file: "src/b.rs".into(),
+                status: FileStatus::Unchanged,
+                summary: String::new(),
+                body: None,
+            },
+            FileReport {
+                file: "src/c.rs".into(),
+                status: FileStatus::Skipped,
+                summary: "not valid UTF-8".into(),
+                body: None,
+            },
+        ];
+        assert_eq!(
+            ResponseFormat::Text.file_report("Done:", &files, 5),
+            "Done:\n4 of 5 files succeeded: 1 with changes, 3 with no change. 1 was skipped.\n\n\
+             === src/a.rs ===\n✅ 2 replacements\n@@ -1 +1 @@\n-a\n+b\n\n\
+             === src/b.rs ===\n✅ No change\n\n\
+             === src/c.rs ===\n❌ Skipped: not valid UTF-8\n"
+        );
+        assert_eq!(
+            ResponseFormat::Markdown.file_report("Done:", &files[..1], 1),
+            "Done:\n1 of 1 file succeeded: 1 with changes.\n\n\
+             | File | Status |\n| --- | --- |\n| src/a.rs | ✅ 2 replacements |\n\n\
+             #### src/a.rs\n\n```diff\n@@ -1 +1 @@\n-a\n+b\n```\n"
+        );
+    }
+
+    #[test]
+    fn markdown_matches_are_a_table() {
+        let matches = [("src/lib.rs", 3, "a | b")];
+        assert_eq!(
+            ResponseFormat::Text.matches(matches),
+            "src/lib.rs:3: a | b\n"
+        );
+        assert_eq!(
+            ResponseFormat::Markdown.matches(matches),
+            "\n| File | Line | Text |\n| --- | --- | --- |\n| src/lib.rs | 3 | `a \\| b` |\n"
+        );
+    }
+}
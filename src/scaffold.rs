@@ -0,0 +1,178 @@
+use crate::placeholders;
+use anyhow::{Result, anyhow};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where project-local templates live, relative to the working directory
+pub const LOCAL_TEMPLATES: &str = ".semantic-edit/templates";
+
+/// Templates that ship with the server, as (name, extension, content)
+const BUILT_IN: &[(&str, &str, &str)] = &[
+    (
+        "rust_module",
+        "rs",
+        include_str!("../templates/rust_module.rs"),
+    ),
+    (
+        "react_component",
+        "tsx",
+        include_str!("../templates/react_component.tsx"),
+    ),
+    (
+        "python_module",
+        "py",
+        include_str!("../templates/python_module.py"),
+    ),
+];
+
+/// A file template. `{{name}}` in the content is replaced by the new file's name and
+/// `{{Name}}` by the same name in PascalCase, and snippet placeholders like `${date}` are
+/// expanded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pub name: String,
+    /// The extension of files this template is meant for
+    pub extension: String,
+    pub content: String,
+    /// Where a project-local template was read from
+    pub source: Option<PathBuf>,
+}
+
+impl Template {
+    /// Every template available, with project-local templates from `working_directory`
+    /// replacing built-in templates of the same name, sorted by name
+    pub fn all(working_directory: Option<&Path>) -> Result<Vec<Self>> {
+        let mut templates = BUILT_IN
+            .iter()
+            .map(|(name, extension, content)| Self {
+                name: name.to_string(),
+                extension: extension.to_string(),
+                content: content.to_string(),
+                source: None,
+            })
+            .collect::<Vec<_>>();
+
+        let local = working_directory.map(|directory| directory.join(LOCAL_TEMPLATES));
+        if let Some(local) = local.filter(|local| local.is_dir()) {
+            for entry in fs::read_dir(&local)? {
+                let path = entry?.path();
+                let (Some(name), Some(extension)) = (
+                    path.file_stem().and_then(|stem| stem.to_str()),
+                    path.extension().and_then(|extension| extension.to_str()),
+                ) else {
+                    continue;
+                };
+                let template = Self {
+                    name: name.to_string(),
+                    extension: extension.to_string(),
+                    content: fs::read_to_string(&path)?,
+                    source: Some(path.clone()),
+                };
+                templates.retain(|existing| existing.name != template.name);
+                templates.push(template);
+            }
+        }
+
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    /// The template named `name`, or without a name, the built-in template for the new file's
+    /// extension
+    pub fn find(templates: &[Self], name: Option<&str>, file: &Path) -> Result<Self> {
+        let extension = file.extension().and_then(|extension| extension.to_str());
+        let found = match name {
+            Some(name) => templates.iter().find(|template| template.name == name),
+            None => templates.iter().find(|template| {
+                template.source.is_none() && Some(template.extension.as_str()) == extension
+            }),
+        };
+
+        found.cloned().ok_or_else(|| {
+            anyhow!(
+                "{}. Available templates: {}",
+                match name {
+                    Some(name) => format!("No template is named {name}"),
+                    None => format!(
+                        "No built-in template is meant for files like {}; choose one by name",
+                        file.display()
+                    ),
+                },
+                templates
+                    .iter()
+                    .map(|template| format!("{} (.{})", template.name, template.extension))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+    }
+
+    /// The template's content for a new file
+    pub fn render(&self, file: &Path) -> String {
+        let name = file_name(file);
+        let content = self
+            .content
+            .replace("{{name}}", &name)
+            .replace("{{Name}}", &pascal_case(&name));
+        placeholders::expand(&content, file)
+    }
+}
+
+/// The name a file gives the code inside it: its stem, or its directory's name for files like
+/// `mod.rs` and `__init__.py` that stand for their directory
+fn file_name(file: &Path) -> String {
+    let stem = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match stem.as_str() {
+        "mod" | "__init__" | "index" => file
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .map_or(stem.clone(), |parent| parent.to_string_lossy().into_owned()),
+        _ => stem,
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split(['_', '-', ' ', '.'])
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |first| {
+                first.to_uppercase().chain(chars).collect()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_filled_in() {
+        let templates = Template::all(None).unwrap();
+        let component = Template::find(&templates, None, Path::new("src/user-card.tsx")).unwrap();
+        assert_eq!(component.name, "react_component");
+        let rendered = component.render(Path::new("src/user-card.tsx"));
+        assert!(rendered.contains("export function UserCard({ children }: UserCardProps)"));
+        assert!(rendered.contains(r#"className="user-card""#));
+
+        let module = Template::find(&templates, None, Path::new("src/parser/mod.rs")).unwrap();
+        assert!(
+            module
+                .render(Path::new("src/parser/mod.rs"))
+                .contains("pub fn parser() {}")
+        );
+    }
+
+    #[test]
+    fn unknown_templates_list_the_alternatives() {
+        let templates = Template::all(None).unwrap();
+        let error = Template::find(&templates, None, Path::new("config.toml")).unwrap_err();
+        assert!(error.to_string().contains("python_module (.py)"));
+        assert!(Template::find(&templates, Some("python_module"), Path::new("a.toml")).is_ok());
+    }
+}
@@ -1,3 +1,79 @@
+use std::ops::Range;
+
+/// A progressively looser way to match an anchor that was not found verbatim
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Relaxation {
+    Trimmed,
+    IgnoredWhitespace,
+    IgnoredTrailingPunctuation,
+}
+
+impl Relaxation {
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            Relaxation::Trimmed => "trimming surrounding whitespace and backticks",
+            Relaxation::IgnoredWhitespace => "ignoring whitespace",
+            Relaxation::IgnoredTrailingPunctuation => {
+                "ignoring whitespace and trailing punctuation"
+            }
+        }
+    }
+}
+
+/// Retry a failed anchor search with each [`Relaxation`] in turn, returning the first relaxation
+/// that matched along with the source range of its first match
+pub(crate) fn find_relaxed(source_text: &str, snippet: &str) -> Option<(Relaxation, Range<usize>)> {
+    let trimmed = snippet.trim().trim_matches('`').trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    if trimmed != snippet.trim() {
+        if let Ok(positions) = find_positions(source_text, trimmed) {
+            let (start, end) = positions[0];
+            return Some((Relaxation::Trimmed, start..end));
+        }
+    }
+
+    if let Some(range) = find_ignoring_whitespace(source_text, trimmed) {
+        return Some((Relaxation::IgnoredWhitespace, range));
+    }
+
+    let without_punctuation =
+        trimmed.trim_end_matches(|c: char| c.is_ascii_punctuation() || c.is_whitespace());
+    if !without_punctuation.is_empty() && without_punctuation != trimmed {
+        if let Some(range) = find_ignoring_whitespace(source_text, without_punctuation) {
+            return Some((Relaxation::IgnoredTrailingPunctuation, range));
+        }
+    }
+
+    None
+}
+
+fn find_ignoring_whitespace(source_text: &str, snippet: &str) -> Option<Range<usize>> {
+    let needle = snippet
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+    if needle.is_empty() {
+        return None;
+    }
+
+    let haystack = source_text
+        .char_indices()
+        .filter(|(_, c)| !c.is_whitespace())
+        .collect::<Vec<_>>();
+
+    haystack
+        .windows(needle.len())
+        .find(|window| window.iter().map(|(_, c)| c).eq(needle.iter()))
+        .map(|window| {
+            let (start, _) = window[0];
+            let (last, c) = window[window.len() - 1];
+            start..last + c.len_utf8()
+        })
+}
+
 pub(crate) fn find_positions(
     source_text: &str,
     snippet: &str,
@@ -192,4 +268,33 @@ mod tests {
         let result = position_strs(source, snippet);
         assert!(result.is_empty());
     }
+
+    #[test]
+    fn test_relaxed_trims_backticks() {
+        let source = "fn main() {\n    run();\n}";
+        let (relaxation, range) = find_relaxed(source, "`fn main`").unwrap();
+        assert_eq!(relaxation, Relaxation::Trimmed);
+        assert_eq!(&source[range], "fn main");
+    }
+
+    #[test]
+    fn test_relaxed_ignores_whitespace_within_first_token() {
+        let source = "let total = add(a, b);";
+        let (relaxation, range) = find_relaxed(source, "add(a,b)").unwrap();
+        assert_eq!(relaxation, Relaxation::IgnoredWhitespace);
+        assert_eq!(&source[range], "add(a, b)");
+    }
+
+    #[test]
+    fn test_relaxed_ignores_trailing_punctuation() {
+        let source = "fn greet(name: &str) -> String\nwhere\n{}";
+        let (relaxation, range) = find_relaxed(source, "fn greet(name:&str) -> String {").unwrap();
+        assert_eq!(relaxation, Relaxation::IgnoredTrailingPunctuation);
+        assert_eq!(&source[range], "fn greet(name: &str) -> String");
+    }
+
+    #[test]
+    fn test_relaxed_gives_up() {
+        assert_eq!(find_relaxed("fn main() {}", "fn other"), None);
+    }
 }
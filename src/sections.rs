@@ -0,0 +1,196 @@
+//! Sections of Markdown documents: a heading and everything up to the next heading of the same
+//! or a higher level
+
+use crate::validation::markdown::{Heading, headings};
+use std::ops::Range;
+
+/// A change made by shifting the headings of a section
+#[derive(Debug, PartialEq, Eq)]
+pub struct Shift {
+    /// The lines of the section, from its heading to its last line
+    pub range: Range<usize>,
+    pub content: String,
+    /// The section's heading line, or the document's first heading line
+    pub anchor: String,
+    /// How many headings were shifted
+    pub count: usize,
+}
+
+/// Promote (negative `by`) or demote (positive `by`) every heading in the section whose heading
+/// is `section`, named by its text or by its `#anchor`, or with None, every heading in the
+/// document. Setext headings are rewritten with `#`s, since they can only be levels 1 and 2.
+pub fn shift(source: &str, section: Option<&str>, by: isize) -> Result<Shift, String> {
+    let headings = headings(source);
+    let (first, last) = match section {
+        Some(section) => {
+            let index = find(&headings, section)?;
+            let level = headings[index].level;
+            let end = headings[index + 1..]
+                .iter()
+                .position(|heading| heading.level <= level)
+                .map_or(headings.len(), |position| index + 1 + position);
+            (index, end)
+        }
+        None if headings.is_empty() => return Err("This document has no headings".into()),
+        None => (0, headings.len()),
+    };
+    let shifted = &headings[first..last];
+
+    let out_of_range = shifted
+        .iter()
+        .filter(|heading| !(1..=6).contains(&(heading.level as isize + by)))
+        .map(|heading| {
+            format!(
+                "`{}` (line {}, level {})",
+                heading.text, heading.line, heading.level
+            )
+        })
+        .collect::<Vec<_>>();
+    if !out_of_range.is_empty() {
+        return Err(format!(
+            "Shifting by {by} would take these headings past the levels Markdown has, 1 through \
+             6: {}",
+            out_of_range.join(", ")
+        ));
+    }
+
+    let lines = line_ranges(source);
+    let start = lines[shifted[0].line - 1].start;
+    let end = match headings.get(last) {
+        Some(next) => lines[next.line - 1].start,
+        None => source.len(),
+    };
+    let mut content = String::new();
+    let mut offset = start;
+    for heading in shifted {
+        let line = &lines[heading.line - 1];
+        content.push_str(&source[offset..line.start]);
+        let text = source[line.clone()].trim_end_matches(['\n', '\r']);
+        let level = (heading.level as isize + by) as usize;
+        let rewritten = if heading.underlined {
+            format!("{} {}", "#".repeat(level), text.trim())
+        } else {
+            let indent = &text[..text.len() - text.trim_start().len()];
+            let rest = text.trim_start().trim_start_matches('#');
+            format!("{indent}{}{rest}", "#".repeat(level))
+        };
+        content.push_str(&rewritten);
+        content.push_str(&source[line.start + text.len()..line.end]);
+        offset = line.end;
+        if heading.underlined {
+            offset = lines[heading.line].end;
+        }
+    }
+    content.push_str(&source[offset..end]);
+
+    Ok(Shift {
+        range: start..end,
+        content,
+        anchor: source[lines[shifted[0].line - 1].clone()]
+            .trim()
+            .to_string(),
+        count: shifted.len(),
+    })
+}
+
+/// The heading named by its text or `#anchor`
+fn find(headings: &[Heading], section: &str) -> Result<usize, String> {
+    if let Some(anchor) = section.strip_prefix('#') {
+        return headings
+            .iter()
+            .position(|heading| heading.anchor == anchor)
+            .ok_or_else(|| format!("No heading has the anchor `#{anchor}`"));
+    }
+    let matching = headings
+        .iter()
+        .enumerate()
+        .filter(|(_, heading)| heading.text.eq_ignore_ascii_case(section.trim()))
+        .collect::<Vec<_>>();
+    match matching.as_slice() {
+        [(index, _)] => Ok(*index),
+        [] => Err(format!(
+            "No heading is `{section}`. The headings are: {}",
+            headings
+                .iter()
+                .map(|heading| heading.text.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        matching => Err(format!(
+            "{} headings are `{section}`. Name the one to shift by its anchor: {}",
+            matching.len(),
+            matching
+                .iter()
+                .map(|(_, heading)| format!("#{}", heading.anchor))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// The byte range of each line of `source`, including its line break
+fn line_ranges(source: &str) -> Vec<Range<usize>> {
+    let mut offset = 0;
+    source
+        .split_inclusive('\n')
+        .map(|line| {
+            let range = offset..offset + line.len();
+            offset = range.end;
+            range
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUIDE: &str = "# Guide\n\n## Setup\n\nText.\n\n### Linux\n\n```sh\n# apt install\n```\n\n\
+                         Windows\n-------\n\n## Usage\n\n### Linux\n";
+
+    fn shifted(section: Option<&str>, by: isize) -> Result<String, String> {
+        let Shift { range, content, .. } = shift(GUIDE, section, by)?;
+        Ok(format!(
+            "{}{content}{}",
+            &GUIDE[..range.start],
+            &GUIDE[range.end..]
+        ))
+    }
+
+    #[test]
+    fn demotes_a_section() {
+        assert_eq!(
+            shifted(Some("Setup"), 1).unwrap(),
+            "# Guide\n\n### Setup\n\nText.\n\n#### Linux\n\n```sh\n# apt install\n```\n\n\
+             Windows\n-------\n\n## Usage\n\n### Linux\n"
+        );
+        assert_eq!(
+            shifted(Some("Windows"), 1).unwrap(),
+            GUIDE.replace("Windows\n-------\n", "### Windows\n")
+        );
+    }
+
+    #[test]
+    fn promotes_a_section_named_by_anchor() {
+        assert_eq!(
+            shifted(Some("#linux-1"), -1).unwrap(),
+            GUIDE.replace("## Usage\n\n### Linux", "## Usage\n\n## Linux")
+        );
+        assert!(
+            shifted(Some("Linux"), -1)
+                .unwrap_err()
+                .contains("#linux, #linux-1")
+        );
+    }
+
+    #[test]
+    fn levels_stay_in_range() {
+        assert!(
+            shifted(None, -1)
+                .unwrap_err()
+                .contains("`Guide` (line 1, level 1)")
+        );
+        assert!(shifted(Some("Usage"), 3).is_ok());
+        assert!(shifted(Some("Usage"), 4).is_err());
+    }
+}
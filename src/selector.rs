@@ -44,6 +44,9 @@ impl Display for Operation {
     }
 }
 
+// The ways of targeting an edit are flat fields rather than an enum because the selector is
+// flattened into the parameters of the edit tools, where they read as optional arguments of
+// one tool call. `validate` rejects the combinations an enum would have ruled out.
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, Eq, PartialEq, clap::Args)]
 pub struct Selector {
     /// The type of edit operation to perform.
@@ -72,21 +75,131 @@ pub struct Selector {
     /// - `"struct User"` - Targets a struct definition  
     /// - `"// TODO: implement"` - Targets a specific comment
     /// - `"import React"` - Targets an import statement
+    #[serde(default)]
+    #[arg(default_value = "")]
     pub anchor: String,
+
+    /// When the anchor isn't found verbatim, retry with progressively relaxed matching:
+    /// trimming surrounding whitespace and backticks, then ignoring all whitespace, then also
+    /// ignoring trailing punctuation. The preview reports which relaxation matched.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub relaxed: bool,
+
+    /// Treat the anchor as a regular expression, like `fn \w+_test\(`, which must match exactly
+    /// once. `insert_after` and `insert_before` insert relative to the AST node at the match,
+    /// and `replace` replaces only the matched text. `$1` or `${name}` in the content is
+    /// replaced with the match's capture groups instead of being a snippet placeholder, and `$$`
+    /// writes a literal `$`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub regex: bool,
+
+    /// Target a named symbol instead of anchor text, like `fn display_name`, `struct User`, or
+    /// `User::display_name`. The kind is optional, and a name that isn't qualified by the
+    /// symbols it's nested in must be unambiguous. Leave `anchor` out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub symbol: Option<String>,
+
+    /// Target lines by number instead of anchor text, counting from 1, like the locations
+    /// compilers and linters report. `replace` replaces the lines from `start_line` through
+    /// `end_line`, and the inserts go on lines of their own before or after them. Leave `anchor`
+    /// out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub start_line: Option<usize>,
+
+    /// The last line targeted, the same as `start_line` if omitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub end_line: Option<usize>,
+
+    /// Widen the lines to the smallest named syntax node that covers them: the statement or
+    /// expression a reported line is in, or a whole function when the lines span its signature
+    /// or several of its statements
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub snap_to_node: bool,
+
+    /// Whether anchor and regex matches inside string literals and comments count. By default
+    /// they do, and the preview says when the match is inside one. `false` skips them, so that
+    /// `save_user` finds the call rather than a log message that mentions it, and `true` only
+    /// uses them, for editing the text of a string or comment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub inside_literals: Option<bool>,
 }
 
 impl Selector {
+    /// Target the AST node at `anchor` text
+    pub fn anchor(operation: Operation, anchor: impl Into<String>) -> Self {
+        Self {
+            operation,
+            anchor: anchor.into(),
+            relaxed: false,
+            regex: false,
+            symbol: None,
+            start_line: None,
+            end_line: None,
+            snap_to_node: false,
+            inside_literals: None,
+        }
+    }
+
     pub fn operation_name(&self) -> &str {
         self.operation.as_str()
     }
 
     /// Validate that the selector is properly formed
     pub fn validate(&self) -> Result<(), String> {
-        let Self { anchor, .. } = self;
+        let Self {
+            anchor,
+            relaxed,
+            regex,
+            symbol,
+            start_line,
+            end_line,
+            snap_to_node,
+            inside_literals,
+            ..
+        } = self;
 
         let mut errors = vec![];
-        if anchor.trim().is_empty() {
-            errors.push("- `anchor` cannot be empty");
+        if anchor.trim().is_empty() && symbol.is_none() && start_line.is_none() {
+            errors.push(
+                "- `anchor` cannot be empty unless `anchor_ref`, `symbol`, or `start_line` is given",
+            );
+        }
+        if [*regex, symbol.is_some(), start_line.is_some()]
+            .into_iter()
+            .filter(|set| *set)
+            .count()
+            > 1
+        {
+            errors.push("- only one of `regex`, `symbol`, and `start_line` can be used");
+        }
+        if !anchor.trim().is_empty() && (symbol.is_some() || start_line.is_some()) {
+            errors.push(
+                "- `anchor` can't be used with `symbol` or `start_line`, which are what get targeted",
+            );
+        }
+        if *relaxed && (symbol.is_some() || start_line.is_some()) {
+            errors.push("- `relaxed` only applies to `anchor` matches");
+        }
+        if start_line.is_none() && (end_line.is_some() || *snap_to_node) {
+            errors.push("- `end_line` and `snap_to_node` need a `start_line`");
+        }
+        if inside_literals.is_some() && (symbol.is_some() || start_line.is_some()) {
+            errors.push("- `inside_literals` only applies to `anchor` and `regex` matches");
+        }
+        if start_line == &Some(0) {
+            errors.push("- lines are counted from 1, so `start_line` can't be 0");
+        }
+        if let (Some(start), Some(end)) = (start_line, end_line)
+            && end < start
+        {
+            errors.push("- `end_line` can't be before `start_line`");
         }
 
         // if anchor.contains('\n') {
@@ -100,3 +213,45 @@ impl Selector {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchors_are_not_ignored_in_favor_of_a_symbol_or_lines() {
+        let selector = Selector {
+            symbol: Some("fn main".into()),
+            ..Selector::anchor(Operation::Replace, "fn helper")
+        };
+        assert_eq!(
+            selector.validate(),
+            Err(
+                "- `anchor` can't be used with `symbol` or `start_line`, which are what get targeted"
+                    .into()
+            )
+        );
+        assert!(
+            Selector {
+                symbol: Some("fn main".into()),
+                ..Selector::anchor(Operation::Replace, "")
+            }
+            .validate()
+            .is_ok()
+        );
+
+        let lines = Selector {
+            start_line: Some(3),
+            relaxed: true,
+            ..Selector::anchor(Operation::Replace, "fn helper")
+        };
+        assert_eq!(
+            lines.validate(),
+            Err(
+                "- `anchor` can't be used with `symbol` or `start_line`, which are what get \
+                 targeted\n- `relaxed` only applies to `anchor` matches"
+                    .into()
+            )
+        );
+    }
+}
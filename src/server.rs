@@ -0,0 +1,505 @@
+mod record;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use env_logger::{Builder, Target};
+use mcplease::{
+    traits::{AsToolsList, Tool},
+    types::{Info, McpError, McpMessage, McpResponse},
+};
+pub use record::{Recorder, Replay};
+use serde_json::Value;
+use std::{
+    fmt::Debug,
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Read, Write},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{Mutex, MutexGuard, PoisonError, mpsc},
+    thread,
+};
+
+/// Messages larger than this are rejected without being parsed
+const MAX_MESSAGE_BYTES: u64 = 16 * 1024 * 1024;
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const INTERNAL_ERROR: i32 = -32603;
+
+/// Protocol revisions this server can speak, oldest first
+const SUPPORTED_PROTOCOL_VERSIONS: [&str; 3] = ["2024-11-05", "2025-03-26", "2025-06-18"];
+
+#[derive(clap::Parser)]
+struct Cli<T: Subcommand> {
+    #[command(subcommand)]
+    tool: T,
+}
+
+/// Run a single tool from the command line, serve MCP over stdin/stdout with `serve`, or replay
+/// a recorded session with `replay`
+pub fn run<Tools: Debug + Subcommand + AsToolsList + Tool<State>, State: Send>(
+    state: &mut State,
+    server_info: Info,
+    instructions: Option<&'static str>,
+) -> Result<()> {
+    if let Ok(log_location) = std::env::var("MCP_LOG_LOCATION") {
+        let path = PathBuf::from(&*shellexpand::tilde(&log_location));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Builder::from_default_env()
+            .target(Target::Pipe(Box::new(
+                OpenOptions::new().create(true).append(true).open(path)?,
+            )))
+            .init();
+    }
+
+    match Cli::<Tools>::try_parse() {
+        Ok(Cli { tool }) => {
+            let result = tool.execute(state)?;
+            println!("{result}");
+        }
+        Err(e) => {
+            if std::env::args().nth(1).as_deref() == Some("serve") {
+                let input = Input::read(BufReader::new(std::io::stdin()));
+                let stop = input.stopper();
+                if let Err(e) = ctrlc::set_handler(move || {
+                    log::info!("received termination signal, shutting down");
+                    stop();
+                }) {
+                    log::warn!("unable to handle termination signals: {e}");
+                }
+
+                serve::<Tools, State>(state, &server_info, instructions, input, std::io::stdout())?;
+            } else if std::env::args().nth(1).as_deref() == Some("replay") {
+                let replay = Replay::parse_from(std::env::args().skip(1));
+                println!(
+                    "{}",
+                    self::replay::<Tools, State>(&replay, state, &server_info, instructions)?
+                );
+            } else {
+                eprintln!("{e}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Serve newline-delimited JSON-RPC until the input closes or is stopped, recording every
+/// exchange in the file named by `SEMANTIC_EDIT_RECORD` if it is set. Tool calls that have
+/// already arrived are finished before returning, so that the caller can shut down cleanly.
+///
+/// Malformed, oversized, and invalid messages are answered with JSON-RPC error responses
+/// instead of ending the session, batched requests are answered with a batch of responses,
+/// and a tool that panics produces an internal error response for that request only.
+///
+/// Tool calls run one at a time, in order, on a worker thread, so that a slow edit, formatter,
+/// or workspace walk doesn't hold up reading the input. Other messages are handled as they
+/// arrive, and their responses may overtake a tool call's. Every request is handled with the
+/// same `state`, locked for the duration of the request, so a request that arrives during a
+/// tool call waits for it rather than seeing the state partway through an edit, while
+/// notifications and malformed messages are answered right away.
+pub fn serve<Tools: Debug + AsToolsList + Tool<State>, State: Send>(
+    state: &mut State,
+    server_info: &Info,
+    instructions: Option<&'static str>,
+    input: Input,
+    writer: impl Write + Send,
+) -> Result<()> {
+    log::trace!("started!");
+    let connection = Connection {
+        server_info,
+        instructions,
+        client: Mutex::new(Client::default()),
+        recorder: Recorder::configured()?,
+    };
+    let writer = Mutex::new(writer);
+    let state = Mutex::new(state);
+
+    thread::scope(|scope| {
+        let (tool_calls, queued) = mpsc::channel::<Vec<u8>>();
+        let worker = scope.spawn(|| -> Result<()> {
+            for line in queued {
+                let response = connection.respond::<Tools, State>(&state, &line)?;
+                write_response(&writer, response)?;
+            }
+            Ok(())
+        });
+
+        for event in &input.events {
+            let response = match event {
+                Event::Closed => break,
+                Event::Stop => {
+                    log::info!("stopping once the tool calls that have already arrived finish");
+                    break;
+                }
+                Event::Oversized => {
+                    log::error!("rejected message over {MAX_MESSAGE_BYTES} bytes");
+                    Some(serde_json::to_string(&error_response(
+                        Value::Null,
+                        INVALID_REQUEST,
+                        format!("Message exceeds the maximum size of {MAX_MESSAGE_BYTES} bytes"),
+                    ))?)
+                }
+                Event::Line(line) if calls_tool(&line) => {
+                    if tool_calls.send(line).is_err() {
+                        // the worker has stopped, and joining it below reports why
+                        break;
+                    }
+                    continue;
+                }
+                Event::Line(line) => connection.respond::<Tools, State>(&state, &line)?,
+            };
+
+            write_response(&writer, response)?;
+        }
+
+        // finish the tool calls that have already arrived before returning
+        drop(tool_calls);
+        worker
+            .join()
+            .unwrap_or_else(|panic| panic::resume_unwind(panic))
+    })
+}
+
+/// The lines a client sends, read on a thread of their own so that a termination signal can end
+/// serving while a read is waiting for the next message
+pub struct Input {
+    events: mpsc::Receiver<Event>,
+    sender: mpsc::Sender<Event>,
+}
+
+/// What the serving loop handles next
+enum Event {
+    Line(Vec<u8>),
+    /// A line over [`MAX_MESSAGE_BYTES`], which was skipped without being kept
+    Oversized,
+    /// The input closed, or could no longer be read
+    Closed,
+    /// A termination signal asked the server to stop
+    Stop,
+}
+
+impl Input {
+    pub fn read(mut reader: impl BufRead + Send + 'static) -> Self {
+        let (sender, events) = mpsc::channel();
+        let lines = sender.clone();
+        thread::spawn(move || {
+            loop {
+                let mut line = Vec::new();
+                let event = match reader
+                    .by_ref()
+                    .take(MAX_MESSAGE_BYTES)
+                    .read_until(b'\n', &mut line)
+                {
+                    Ok(0) => {
+                        log::info!("input closed, shutting down");
+                        Event::Closed
+                    }
+                    Ok(read) if read as u64 == MAX_MESSAGE_BYTES && !line.ends_with(b"\n") => {
+                        match skip_line(&mut reader) {
+                            Ok(()) => Event::Oversized,
+                            Err(e) => {
+                                log::error!("Error reading line: {e}");
+                                Event::Closed
+                            }
+                        }
+                    }
+                    Ok(_) => Event::Line(line),
+                    Err(e) => {
+                        log::error!("Error reading line: {e}");
+                        Event::Closed
+                    }
+                };
+                let closed = matches!(event, Event::Closed);
+                if lines.send(event).is_err() || closed {
+                    break;
+                }
+            }
+        });
+        Self { events, sender }
+    }
+
+    /// A function that stops serving this input after the message being handled, for a
+    /// termination signal handler
+    pub fn stopper(&self) -> impl Fn() + Send + 'static {
+        let sender = self.sender.clone();
+        move || {
+            let _ = sender.send(Event::Stop);
+        }
+    }
+}
+
+/// Re-run a session recorded by [`serve`] against a scratch copy of its workspace, reporting
+/// which responses differ from the recorded ones
+pub fn replay<Tools: Debug + AsToolsList + Tool<State>, State>(
+    replay: &Replay,
+    state: &mut State,
+    server_info: &Info,
+    instructions: Option<&'static str>,
+) -> Result<String> {
+    let connection = Connection {
+        server_info,
+        instructions,
+        client: Mutex::new(Client::default()),
+        recorder: None,
+    };
+    replay.run::<Tools, State>(&connection, &Mutex::new(state))
+}
+
+/// Write one serialized response, if there is one, as a single line
+fn write_response(writer: &Mutex<impl Write>, response: Option<String>) -> Result<()> {
+    if let Some(response) = response {
+        log::trace!("-> {response}");
+        let mut writer = writer.lock().unwrap_or_else(PoisonError::into_inner);
+        writer.write_all(response.as_bytes())?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Whether a line is a tool call, or a batch with one in it, which is handled on the worker
+/// thread. Anything that doesn't parse is answered right away with an error.
+fn calls_tool(line: &[u8]) -> bool {
+    #[derive(serde::Deserialize)]
+    struct Method {
+        method: Option<String>,
+    }
+
+    let is_tool_call = |message: &Method| message.method.as_deref() == Some("tools/call");
+    if let Ok(batch) = serde_json::from_slice::<Vec<Method>>(line) {
+        batch.iter().any(is_tool_call)
+    } else if let Ok(message) = serde_json::from_slice::<Method>(line) {
+        is_tool_call(&message)
+    } else {
+        false
+    }
+}
+
+/// Discard the remainder of an oversized line
+fn skip_line(reader: &mut impl BufRead) -> Result<()> {
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        match buffer.iter().position(|byte| *byte == b'\n') {
+            Some(newline) => {
+                reader.consume(newline + 1);
+                return Ok(());
+            }
+            None => {
+                let len = buffer.len();
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+/// Everything known about the connection being served
+struct Connection<'a> {
+    server_info: &'a Info,
+    instructions: Option<&'static str>,
+    client: Mutex<Client>,
+    recorder: Option<Recorder>,
+}
+
+impl Connection<'_> {
+    fn client(&self) -> MutexGuard<'_, Client> {
+        self.client.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Produce the serialized response to one line of input, if it calls for one
+    fn respond<Tools: Debug + AsToolsList + Tool<State>, State>(
+        &self,
+        state: &Mutex<&mut State>,
+        line: &[u8],
+    ) -> Result<Option<String>> {
+        let Ok(line) = std::str::from_utf8(line) else {
+            return Ok(Some(serde_json::to_string(&error_response(
+                Value::Null,
+                PARSE_ERROR,
+                "Parse error: message is not valid UTF-8".into(),
+            ))?));
+        };
+
+        if line.trim().is_empty() {
+            return Ok(None);
+        }
+
+        log::trace!("<- {line}");
+
+        let response = match serde_json::from_str::<Value>(line) {
+            Ok(Value::Array(batch)) if batch.is_empty() => Some(serde_json::to_string(
+                &error_response(Value::Null, INVALID_REQUEST, "Empty batch".into()),
+            )?),
+            Ok(Value::Array(batch)) => {
+                let responses = batch
+                    .into_iter()
+                    .filter_map(|message| self.handle::<Tools, State>(state, message))
+                    .collect::<Vec<_>>();
+                if responses.is_empty() {
+                    None
+                } else {
+                    Some(serde_json::to_string(&responses)?)
+                }
+            }
+            Ok(message) => self
+                .handle::<Tools, State>(state, message)
+                .map(|response| serde_json::to_string(&response))
+                .transpose()?,
+            Err(e) => {
+                log::error!("{e:?}");
+                Some(serde_json::to_string(&error_response(
+                    Value::Null,
+                    PARSE_ERROR,
+                    format!("Parse error: {e}"),
+                ))?)
+            }
+        };
+
+        if let Some(recorder) = &self.recorder
+            && let Err(e) = recorder.record(line, response.as_deref())
+        {
+            log::warn!("unable to record the session: {e}");
+        }
+
+        Ok(response)
+    }
+
+    /// Respond to a single message, or return None for notifications. Only requests lock the
+    /// state.
+    fn handle<Tools: Debug + AsToolsList + Tool<State>, State>(
+        &self,
+        state: &Mutex<&mut State>,
+        message: Value,
+    ) -> Option<McpResponse> {
+        let id = message.get("id").cloned().unwrap_or(Value::Null);
+        match serde_json::from_value(message) {
+            Ok(McpMessage::Request(request)) => {
+                let method = request.method.clone();
+                if method == "initialize" {
+                    *self.client() = Client::from_initialize(request.params.as_ref());
+                }
+
+                let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+                let mut response = panic::catch_unwind(AssertUnwindSafe(|| {
+                    request.execute::<State, Tools>(*state, self.instructions, self.server_info)
+                }))
+                .unwrap_or_else(|_| {
+                    log::error!("request {id} panicked");
+                    error_response(
+                        id,
+                        INTERNAL_ERROR,
+                        "Internal error while handling request".into(),
+                    )
+                });
+
+                self.client().adapt(&method, &mut response);
+                Some(response)
+            }
+            Ok(McpMessage::Notification(n)) => {
+                log::trace!("received {n:?}, ignoring");
+                None
+            }
+            Err(e) => {
+                log::error!("{e:?}");
+                Some(error_response(
+                    id,
+                    INVALID_REQUEST,
+                    format!("Invalid request: {e}"),
+                ))
+            }
+        }
+    }
+}
+
+/// What was agreed with the client in `initialize`.
+///
+/// Responses are adapted by protocol revision alone. The capabilities a client declares (roots,
+/// sampling, elicitation) are for requests this server never makes, and content annotations
+/// have no capability of their own, only the revision that introduced them, so capabilities are
+/// logged for diagnosing clients but not otherwise used.
+#[derive(Debug)]
+struct Client {
+    /// The protocol revision agreed with the client
+    protocol_version: &'static str,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self {
+            protocol_version: SUPPORTED_PROTOCOL_VERSIONS[0],
+        }
+    }
+}
+
+impl Client {
+    /// Agree on the client's requested protocol revision if it is one we support, and
+    /// otherwise offer the latest one we do, as the MCP lifecycle specifies
+    fn from_initialize(params: Option<&Value>) -> Self {
+        let requested = params
+            .and_then(|params| params.get("protocolVersion"))
+            .and_then(Value::as_str);
+        let protocol_version = SUPPORTED_PROTOCOL_VERSIONS
+            .into_iter()
+            .find(|version| Some(*version) == requested)
+            .unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[SUPPORTED_PROTOCOL_VERSIONS.len() - 1]);
+
+        let name = params
+            .and_then(|params| params.pointer("/clientInfo/name"))
+            .and_then(Value::as_str)
+            .unwrap_or("unknown client");
+        let capabilities = params
+            .and_then(|params| params.get("capabilities"))
+            .unwrap_or(&Value::Null);
+        log::info!(
+            "{name} requested protocol {}, using {protocol_version}; client capabilities: {capabilities}",
+            requested.unwrap_or("(none)")
+        );
+
+        Self { protocol_version }
+    }
+
+    /// Content annotations were introduced in the 2025-03-26 revision
+    fn accepts_annotations(&self) -> bool {
+        self.protocol_version >= "2025-03-26"
+    }
+
+    /// Shape a response for the protocol revision agreed with this client
+    fn adapt(&self, method: &str, response: &mut McpResponse) {
+        let Some(result) = response.result.as_mut() else {
+            return;
+        };
+
+        match method {
+            "initialize" => {
+                result["protocolVersion"] = self.protocol_version.into();
+            }
+            "tools/call" if self.accepts_annotations() => {
+                if let Some(content) = result.get_mut("content").and_then(Value::as_array_mut) {
+                    for item in content {
+                        item["annotations"] = serde_json::json!({ "audience": ["assistant"] });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn error_response(id: Value, code: i32, message: String) -> McpResponse {
+    McpResponse {
+        jsonrpc: "2.0",
+        id,
+        result: None,
+        error: Some(McpError {
+            code,
+            message,
+            data: None,
+        }),
+    }
+}
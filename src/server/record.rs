@@ -0,0 +1,213 @@
+use super::Connection;
+use crate::state::expand;
+use anyhow::{Context, Result, anyhow};
+use diffy::{DiffOptions, PatchFormatter};
+use mcplease::traits::{AsToolsList, Tool};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    env,
+    fmt::{Debug, Write as _},
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+use walkdir::WalkDir;
+
+/// Environment variable naming the file to record a served session in
+const RECORD: &str = "SEMANTIC_EDIT_RECORD";
+
+/// Directories that aren't copied into the scratch workspace for a replay
+const SKIPPED_DIRECTORIES: [&str; 3] = [".git", "target", "node_modules"];
+
+/// One line of input and the response to it, as recorded
+#[derive(Debug, Serialize, Deserialize)]
+struct Exchange {
+    /// The message, or the line as it was received if it wasn't valid JSON
+    request: Value,
+    response: Option<Value>,
+}
+
+/// Appends every request served, and the response to it, to a file as JSON lines
+#[derive(Debug)]
+pub struct Recorder(Mutex<File>);
+
+impl Recorder {
+    /// The recorder configured with `SEMANTIC_EDIT_RECORD`, if recording is turned on. `~` and
+    /// environment variables in the path are expanded.
+    pub fn configured() -> Result<Option<Self>> {
+        env::var(RECORD)
+            .ok()
+            .map(|path| Self::create(Path::new(&expand(&path)?)))
+            .transpose()
+    }
+
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Unable to record the session in {}", path.display()))?;
+        Ok(Self(Mutex::new(file)))
+    }
+
+    pub fn record(&self, line: &str, response: Option<&str>) -> Result<()> {
+        let exchange = Exchange {
+            request: serde_json::from_str(line).unwrap_or_else(|_| Value::String(line.into())),
+            response: response.map(serde_json::from_str).transpose()?,
+        };
+        let mut file = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        serde_json::to_writer(&mut *file, &exchange)?;
+        file.write_all(b"\n")?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// Arguments to the `replay` command
+#[derive(clap::Parser, Debug)]
+#[command(name = "replay")]
+pub struct Replay {
+    /// A session recorded with SEMANTIC_EDIT_RECORD
+    pub recording: PathBuf,
+
+    /// The workspace to copy for the replay. Defaults to the current directory.
+    #[arg(long)]
+    pub workspace: Option<PathBuf>,
+
+    /// Where the workspace was when the session was recorded, if not at `--workspace`. Paths
+    /// under it in the recording are replayed against the copy.
+    #[arg(long)]
+    pub recorded_workspace: Option<String>,
+}
+
+impl Replay {
+    /// Re-run every recorded request against a scratch copy of the workspace, in the order they
+    /// were recorded, and report whether each response matches the recorded one
+    pub(super) fn run<Tools: Debug + AsToolsList + Tool<State>, State>(
+        &self,
+        connection: &Connection<'_>,
+        state: &Mutex<&mut State>,
+    ) -> Result<String> {
+        let exchanges = BufReader::new(File::open(&self.recording).with_context(|| {
+            format!("Unable to read the recording {}", self.recording.display())
+        })?)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+        .map(|(index, line)| {
+            serde_json::from_str::<Exchange>(&line?)
+                .map_err(|error| anyhow!("Line {} of the recording is invalid: {error}", index + 1))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+        let workspace = fs::canonicalize(match &self.workspace {
+            Some(workspace) => workspace.clone(),
+            None => env::current_dir()?,
+        })?;
+        let recorded_workspace = self
+            .recorded_workspace
+            .clone()
+            .unwrap_or_else(|| workspace.to_string_lossy().into_owned());
+        let scratch = env::temp_dir().join(format!("semantic-edit-replay-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&scratch);
+        copy_workspace(&workspace, &scratch)?;
+        // paths in responses are canonical
+        let scratch = fs::canonicalize(scratch)?;
+        let scratch_path = scratch.to_string_lossy().into_owned();
+
+        let mut report = format!(
+            "Replaying {} requests from {} in {}\n\n",
+            exchanges.len(),
+            self.recording.display(),
+            scratch.display()
+        );
+        let mut matched = 0;
+        for (index, exchange) in exchanges.iter().enumerate() {
+            let request = match &exchange.request {
+                Value::String(line) => line.clone(),
+                request => request.to_string(),
+            }
+            .replace(&recorded_workspace, &scratch_path);
+            let response = connection
+                .respond::<Tools, State>(state, request.as_bytes())?
+                .map(|response| response.replace(&scratch_path, &recorded_workspace))
+                .map(|response| serde_json::from_str::<Value>(&response))
+                .transpose()?;
+
+            let step = index + 1;
+            let description = describe(&exchange.request);
+            if response == exchange.response {
+                matched += 1;
+                writeln!(report, "✅ {step}. {description}")?;
+            } else {
+                let pretty = |response: &Option<Value>| {
+                    serde_json::to_string_pretty(response).unwrap_or_default() + "\n"
+                };
+                let recorded = pretty(&exchange.response);
+                let replayed = pretty(&response);
+                let patch = DiffOptions::new().create_patch(&recorded, &replayed);
+                writeln!(
+                    report,
+                    "❌ {step}. {description}: the response differs from the recording\n{}",
+                    PatchFormatter::new().fmt_patch(&patch)
+                )?;
+            }
+        }
+
+        write!(
+            report,
+            "\n{matched} of {} responses matched the recording. The scratch workspace is left at \
+             {} for inspection.",
+            exchanges.len(),
+            scratch.display()
+        )?;
+        Ok(report)
+    }
+}
+
+/// A short description of a recorded request, like `tools/call preview_edit`
+fn describe(request: &Value) -> String {
+    let describe_one = |message: &Value| {
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or("(invalid message)");
+        match message.pointer("/params/name").and_then(Value::as_str) {
+            Some(tool) if method == "tools/call" => format!("{method} {tool}"),
+            _ => method.to_string(),
+        }
+    };
+    match request {
+        Value::Array(batch) => format!(
+            "batch of {}",
+            batch
+                .iter()
+                .map(describe_one)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        request => describe_one(request),
+    }
+}
+
+/// Copy everything in `workspace` to `scratch`, other than version control data, build output,
+/// and dependencies
+fn copy_workspace(workspace: &Path, scratch: &Path) -> Result<()> {
+    let entries = WalkDir::new(workspace).into_iter().filter_entry(|entry| {
+        entry.depth() == 0
+            || !(entry.file_type().is_dir()
+                && SKIPPED_DIRECTORIES.contains(&&*entry.file_name().to_string_lossy()))
+    });
+    for entry in entries {
+        let entry = entry?;
+        let target = scratch.join(entry.path().strip_prefix(workspace)?);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,257 @@
+use crate::languages::traits::LanguageEditor;
+use std::ops::Range;
+use tree_sitter::{Node, Tree};
+
+/// Entries of a node that are sorted together, like the pairs of an object, each with the key
+/// it's sorted by
+pub type Group<'tree> = Vec<(Node<'tree>, String)>;
+
+/// The key of a pair or table header, without quotes
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+pub fn key_text(node: Node<'_>, source: &str) -> String {
+    source[node.byte_range()]
+        .trim()
+        .trim_matches(['"', '\''])
+        .to_string()
+}
+
+/// The object or table to sort: the innermost one around `offset`, or the outermost one in the
+/// document
+pub fn container<'tree>(
+    editor: &dyn LanguageEditor,
+    tree: &'tree Tree,
+    source: &str,
+    offset: Option<usize>,
+) -> Option<Node<'tree>> {
+    let is_container = |node: &Node<'_>| editor.sortable_entries(*node, source).is_some();
+    match offset {
+        Some(offset) => {
+            let mut node = tree.root_node().descendant_for_byte_range(offset, offset)?;
+            loop {
+                if is_container(&node) {
+                    return Some(node);
+                }
+                node = node.parent()?;
+            }
+        }
+        None => {
+            let mut node = tree.root_node();
+            while !is_container(&node) {
+                node = node.named_child(0)?;
+            }
+            Some(node)
+        }
+    }
+}
+
+/// A sorted entry and the span of the source it replaces
+struct Slot {
+    /// The entry with the comment lines directly above it, without trailing whitespace
+    range: Range<usize>,
+    /// The range, along with any trailing whitespace of the entry's node
+    covers: Range<usize>,
+    key: String,
+    group: usize,
+    text: String,
+}
+
+/// The text of `node` with its entries in key order, and those of every container nested in it
+/// too unless `shallow`. Comment lines directly above an entry move with it, and the whitespace
+/// and separators between entries stay where they are, so the result keeps the document's
+/// formatting. Entries with the same key keep their order.
+pub fn sorted(editor: &dyn LanguageEditor, node: Node<'_>, source: &str, shallow: bool) -> String {
+    let rewrite = |child: Node<'_>| {
+        if shallow {
+            source[child.byte_range()].to_string()
+        } else {
+            sorted(editor, child, source, false)
+        }
+    };
+
+    let mut slots = vec![];
+    for (group, entries) in editor
+        .sortable_entries(node, source)
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+    {
+        for (entry, key) in entries {
+            let mut previous = entry.prev_sibling();
+            while let Some(sibling) = previous.filter(|sibling| sibling.kind() == "comment") {
+                previous = sibling.prev_sibling();
+            }
+            let floor = previous.map_or(node.start_byte(), content_end);
+            let start = comments_start(source, floor, entry.start_byte());
+            let end = content_end(entry);
+            // what follows the entry's content is left in place, so it is cut from the end of
+            // the rewritten entry
+            let text = rewrite(entry);
+            let text = &text[..text.len() - (entry.end_byte() - end)];
+            let end = entry.start_byte() + source[entry.start_byte()..end].trim_end().len();
+            slots.push(Slot {
+                range: start..end,
+                covers: start..entry.end_byte(),
+                key,
+                group,
+                text: format!("{}{}", &source[start..entry.start_byte()], text.trim_end()),
+            });
+        }
+    }
+    slots.sort_by_key(|slot| slot.range.start);
+
+    // each group's entries fill that group's slots in key order
+    let mut sorted_texts = vec![String::new(); slots.len()];
+    let groups = slots
+        .iter()
+        .map(|slot| slot.group)
+        .max()
+        .map_or(0, |max| max + 1);
+    for group in 0..groups {
+        let positions = (0..slots.len())
+            .filter(|&index| slots[index].group == group)
+            .collect::<Vec<_>>();
+        let mut by_key = positions.clone();
+        by_key.sort_by(|&a, &b| slots[a].key.cmp(&slots[b].key));
+        for (position, entry) in positions.into_iter().zip(by_key) {
+            sorted_texts[position] = slots[entry].text.clone();
+        }
+    }
+
+    let mut pieces = slots
+        .iter()
+        .zip(sorted_texts)
+        .map(|(slot, text)| (slot.range.clone(), text))
+        .collect::<Vec<_>>();
+    for child in node.children(&mut node.walk()) {
+        let range = child.byte_range();
+        if !slots
+            .iter()
+            .any(|slot| slot.covers.start <= range.start && range.end <= slot.covers.end)
+        {
+            pieces.push((range, rewrite(child)));
+        }
+    }
+    pieces.sort_by_key(|(range, _)| range.start);
+
+    let mut text = String::new();
+    let mut position = node.start_byte();
+    for (range, piece) in pieces {
+        text.push_str(&source[position..range.start]);
+        text.push_str(&piece);
+        position = range.end;
+    }
+    text.push_str(&source[position..node.end_byte()]);
+    text
+}
+
+/// The end of a node without the comments on their own lines at the end of it, which in TOML
+/// describe whatever follows the table they're parsed as part of
+fn content_end(node: Node<'_>) -> usize {
+    let mut end = node.end_byte();
+    let mut child = node.child(node.child_count().saturating_sub(1));
+    while let Some(comment) = child.filter(|child| child.kind() == "comment") {
+        let previous = comment.prev_sibling();
+        if previous
+            .is_none_or(|previous| previous.end_position().row == comment.start_position().row)
+        {
+            break;
+        }
+        end = comment.start_byte();
+        child = previous;
+    }
+    end
+}
+
+/// Where the first of the comment lines directly above `start` and after `floor` begins, or
+/// `start` if there are none or it isn't the first thing on its line
+fn comments_start(source: &str, floor: usize, start: usize) -> usize {
+    let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+    if !source[line_start..start].trim().is_empty() {
+        return start;
+    }
+    let mut comments_start = line_start;
+    while comments_start > floor {
+        let previous = source[..comments_start - 1]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let line = source[previous..comments_start - 1].trim();
+        if previous < floor || !(line.starts_with('#') || line.starts_with("//")) {
+            break;
+        }
+        comments_start = previous;
+    }
+    if comments_start == line_start {
+        start
+    } else {
+        // the indentation before the first comment belongs to the whitespace between entries
+        let line = &source[comments_start..];
+        comments_start + line.len() - line.trim_start().len()
+    }
+}
+
+#[cfg(all(test, any(feature = "json", feature = "toml")))]
+mod tests {
+    use super::*;
+    use crate::languages::LanguageName;
+    use crate::languages::LanguageRegistry;
+
+    fn sort(language: LanguageName, source: &str, anchor: Option<&str>, shallow: bool) -> String {
+        let registry = LanguageRegistry::new().unwrap();
+        let language = registry.get_language(language);
+        let tree = language
+            .tree_sitter_parser()
+            .unwrap()
+            .parse(source, None)
+            .unwrap();
+        let offset = anchor.map(|anchor| source.find(anchor).unwrap());
+        let node = container(language.editor(), &tree, source, offset).unwrap();
+        format!(
+            "{}{}{}",
+            &source[..node.start_byte()],
+            sorted(language.editor(), node, source, shallow),
+            &source[node.end_byte()..]
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json_objects_are_sorted_inside_out_keeping_formatting() {
+        let source = "{\n  \"b\": {\"z\": 1, \"y\": [{\"k\": 1, \"j\": 2}, 0]},\n  \"a\": 2\n}\n";
+        assert_eq!(
+            sort(LanguageName::Json, source, None, false),
+            "{\n  \"a\": 2,\n  \"b\": {\"y\": [{\"j\": 2, \"k\": 1}, 0], \"z\": 1}\n}\n"
+        );
+        assert_eq!(
+            sort(LanguageName::Json, source, None, true),
+            "{\n  \"a\": 2,\n  \"b\": {\"z\": 1, \"y\": [{\"k\": 1, \"j\": 2}, 0]}\n}\n"
+        );
+        assert_eq!(
+            sort(LanguageName::Json, source, Some("\"k\""), false),
+            "{\n  \"b\": {\"z\": 1, \"y\": [{\"j\": 2, \"k\": 1}, 0]},\n  \"a\": 2\n}\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn toml_tables_move_with_the_comments_above_them() {
+        let source = "# demo\n[package]\nversion = \"0.1.0\"\nname = \"demo\" # crate name\n\n\
+                      # runtime dependencies\n[dependencies]\nclap = { version = \"4\", features = [] }\n\
+                      anyhow = \"1\"\n";
+        assert_eq!(
+            sort(LanguageName::Toml, source, None, false),
+            "# runtime dependencies\n[dependencies]\nanyhow = \"1\"\n\
+             clap = { features = [], version = \"4\" }\n\n\
+             # demo\n[package]\nname = \"demo\" # crate name\nversion = \"0.1.0\"\n"
+        );
+        assert_eq!(
+            sort(LanguageName::Toml, source, Some("clap"), true),
+            "# demo\n[package]\nversion = \"0.1.0\"\nname = \"demo\" # crate name\n\n\
+             # runtime dependencies\n[dependencies]\nanyhow = \"1\"\n\
+             clap = { version = \"4\", features = [] }\n"
+        );
+
+        // the subtables of an element of an array of tables must stay after it
+        let source = "[[bin]]\nname = \"b\"\n[bin.extra]\nz = 1\n[[bin]]\nname = \"a\"\n";
+        assert_eq!(sort(LanguageName::Toml, source, None, false), source);
+    }
+}
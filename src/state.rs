@@ -1,16 +1,36 @@
+mod anchors;
+mod bookmarks;
+mod history;
+mod migration;
+mod offset_map;
+mod session_store;
+mod stats;
+mod storage;
+
 use crate::{
-    editor::EditPosition,
-    languages::{LanguageName, LanguageRegistry},
+    editor::{EditPosition, Editor},
+    encoding::Encoding,
+    guardrails::{Guardrails, Limits},
+    languages::{Dialects, LanguageCommon, LanguageName, LanguageRegistry},
     selector::Selector,
+    versions::{self, VersionCache},
 };
+use anchors::AnchorHistory;
 use anyhow::{Result, anyhow};
+pub use bookmarks::Bookmark;
+use bookmarks::Bookmarks;
 use fieldwork::Fieldwork;
-use mcplease::session::SessionStore;
+pub use history::Write;
+use history::{UndoTexts, WriteHistory};
+pub use migration::DroppedSession;
+use offset_map::OffsetMap;
 use serde::{Deserialize, Serialize};
+use session_store::SessionStore;
+pub use stats::SessionStats;
 use std::{
     fmt::{self, Debug, Formatter},
-    path::PathBuf,
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
 };
 
 /// Shared context data that can be used across multiple MCP servers
@@ -21,10 +41,47 @@ pub struct SharedContextData {
 }
 
 /// Session data specific to semantic editing operations
-#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SemanticEditSessionData {
+    /// Format version of this session, see [`migration`]. Sessions written before versioning
+    /// was introduced have no version field and are treated as version 0.
+    #[serde(default)]
+    version: u32,
     /// Currently staged operation
     staged_operation: Option<StagedOperation>,
+    /// Working directories saved by push_working_directory, most recent last
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    directory_stack: Vec<PathBuf>,
+    /// Grammars chosen by set_dialect for file extensions
+    #[serde(default, skip_serializing_if = "Dialects::is_empty")]
+    dialects: Dialects,
+    /// What has been written and rejected, for end_session_summary
+    #[serde(default, skip_serializing_if = "SessionStats::is_empty")]
+    stats: SessionStats,
+    /// Anchors that recently resolved in each file, for `anchor_ref`
+    #[serde(default, skip_serializing_if = "AnchorHistory::is_empty")]
+    anchors: AnchorHistory,
+    /// Ranges marked with set_bookmark, by name
+    #[serde(default, skip_serializing_if = "Bookmarks::is_empty")]
+    bookmarks: Bookmarks,
+    /// Recent writes, for undo_edit
+    #[serde(default, skip_serializing_if = "WriteHistory::is_empty")]
+    history: WriteHistory,
+}
+
+impl Default for SemanticEditSessionData {
+    fn default() -> Self {
+        Self {
+            version: migration::CURRENT_VERSION,
+            staged_operation: None,
+            directory_stack: vec![],
+            dialects: Dialects::default(),
+            stats: SessionStats::default(),
+            anchors: AnchorHistory::default(),
+            bookmarks: Bookmarks::default(),
+            history: WriteHistory::default(),
+        }
+    }
 }
 
 /// Represents a staged operation that can be previewed and committed
@@ -36,27 +93,135 @@ pub struct StagedOperation {
     pub file_path: PathBuf,
     pub language_name: LanguageName,
     pub edit_position: Option<EditPosition>,
+    /// Whether the file may have syntax errors of its own, see
+    /// [`Editor::with_allow_dirty`](crate::editor::Editor::with_allow_dirty)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_dirty: bool,
+    /// What reviewers have said about this operation with annotate_edit
+    #[serde(default, skip_serializing_if = "Review::is_empty")]
+    pub review: Review,
+    /// The version of the file the operation was staged against, so that persist_edit can tell
+    /// whether the file changed after it was staged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_version: Option<String>,
+}
+
+/// Notes and an approval decision attached to a staged operation with annotate_edit, for review
+/// that happens outside of the agent's conversation
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Review {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    /// Whether a reviewer approved the operation, or None until one decides
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approved: Option<bool>,
+}
+
+impl Review {
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty() && self.approved.is_none()
+    }
+
+    /// The decision and each note, one per line
+    pub fn describe(&self) -> String {
+        let mut description = format!(
+            "Review: {}",
+            match self.approved {
+                Some(true) => "approved",
+                Some(false) => "not approved",
+                None => "no decision yet",
+            }
+        );
+        for note in &self.notes {
+            description.push_str(&format!("\n- {note}"));
+        }
+        description
+    }
 }
 
 impl StagedOperation {
+    /// Aim the operation somewhere else. Reviewers' notes are kept, but an approval decision is
+    /// withdrawn, since it was made about the edit as it was.
     pub fn retarget(&mut self, selector: Selector) {
         self.selector = selector;
+        self.review.approved = None;
+    }
+
+    /// A one-line summary of what this operation will do when persisted
+    pub fn describe(&self) -> String {
+        let lines = self.content.lines().count();
+        let notes = match self.review.notes.len() {
+            1 => "1 note".to_string(),
+            notes => format!("{notes} notes"),
+        };
+        let review = match (self.review.approved, self.review.notes.is_empty()) {
+            (None, true) => String::new(),
+            (None, false) => format!(" [{notes}]"),
+            (Some(true), _) => format!(" [approved, {notes}]"),
+            (Some(false), _) => format!(" [not approved, {notes}]"),
+        };
+        format!(
+            "{} `{}` in {} ({lines} line{} of content){review}",
+            self.selector.operation_name(),
+            self.selector.anchor,
+            self.file_path.display(),
+            if lines == 1 { "" } else { "s" }
+        )
+    }
+
+    /// Move an operation staged at an exact position to where its text is after its file was
+    /// edited. Returns false if the text it targets was removed, leaving nowhere for it to apply.
+    fn follow(&mut self, offsets: &OffsetMap) -> bool {
+        let Some(position) = &mut self.edit_position else {
+            return true;
+        };
+        let start = position.start_byte;
+        if let Some(end) = position.end_byte
+            && offsets.removed(&(start..end))
+        {
+            return false;
+        }
+        position.start_byte = offsets.map(start);
+        position.end_byte = position
+            .end_byte
+            .map(|end| offsets.map(end).max(position.start_byte));
+        true
     }
 }
 
+/// A hook that receives edited files instead of them being written to disk
+pub type CommitFn = Arc<dyn Fn(PathBuf, String) + Send + Sync>;
+
 /// Semantic editing tools with session support
-#[derive(Fieldwork)]
-#[fieldwork(get, get_mut)]
+///
+/// Sessions are kept behind locks and the language registry is shared, so a clone can be handed
+/// to each connection of a server that serves several clients at once. Each clone reads and
+/// writes its own default session, and sessions never see each other's staged operations,
+/// directory stacks, or dialects.
+#[derive(Clone, Fieldwork)]
+#[fieldwork(get)]
 pub struct SemanticEditTools {
     /// Private session store for edit-specific state (staged operations, etc.)
-    session_store: SessionStore<SemanticEditSessionData>,
+    #[field(get = false)]
+    session_store: Arc<Mutex<SessionStore<SemanticEditSessionData>>>,
     /// Shared context store for cross-server communication
-    shared_context_store: SessionStore<SharedContextData>,
+    #[field(get = false)]
+    shared_context_store: Arc<Mutex<SessionStore<SharedContextData>>>,
     language_registry: Arc<LanguageRegistry>,
-    #[field(set, get_mut(option_borrow_inner = false))]
-    commit_fn: Option<Box<dyn Fn(PathBuf, String) + 'static>>,
-    #[field(set, with)]
-    default_session_id: &'static str,
+    /// Limits on writes, shared by every session
+    guardrails: Arc<Guardrails>,
+    /// Versions of files from before and after writes, shared by every session
+    versions: Arc<VersionCache>,
+    /// The text of files before the writes undo_edit can undo
+    #[field(get = false)]
+    undo_texts: Arc<UndoTexts>,
+    #[field(get = false, set, get_mut(option_borrow_inner = false))]
+    commit_fn: Option<CommitFn>,
+    #[field(get = false)]
+    default_session_id: Arc<str>,
+    /// Sessions that couldn't be read when the store was migrated
+    #[field(get = false)]
+    dropped_sessions: Arc<[DroppedSession]>,
 }
 
 impl Debug for SemanticEditTools {
@@ -65,134 +230,518 @@ impl Debug for SemanticEditTools {
             .field("session_store", &self.session_store)
             .field("shared_context_store", &self.shared_context_store)
             .field("language_registry", &self.language_registry)
+            .field("guardrails", &self.guardrails)
+            .field("versions", &self.versions)
+            .field("undo_texts", &self.undo_texts)
             .field("default_session_id", &self.default_session_id)
+            .field("dropped_sessions", &self.dropped_sessions)
             .finish()
     }
 }
 
+/// Lock a store, carrying on after a panic in another session: every update is a single
+/// read-modify-write of the backend, so a panic can't leave a store half-updated
+fn lock<T>(store: &Mutex<T>) -> MutexGuard<'_, T> {
+    store.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 impl SemanticEditTools {
     /// Create a new SemanticEditTools instance
+    ///
+    /// Sessions are stored as JSON at `storage_path` and the working directory is shared with
+    /// other servers through `~/.ai-tools/sessions/shared-context.json`. Without a
+    /// `storage_path`, both are kept only in memory.
     pub fn new(storage_path: Option<&str>) -> Result<Self> {
         // Private session store for edit-specific state
-        let private_path = storage_path.map(|s| PathBuf::from(&*shellexpand::tilde(s)));
+        let private_path = storage_path.map(expand).transpose()?.map(PathBuf::from);
+        let dropped_sessions = match &private_path {
+            Some(private_path) => migration::migrate(private_path)?,
+            None => vec![],
+        };
         let session_store = SessionStore::new(private_path)?;
+        let undo_texts = UndoTexts::new(session_store.is_persistent());
 
         // Shared context store for cross-server communication
-        let mut shared_path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        shared_path.push(".ai-tools");
-        shared_path.push("sessions");
-        shared_path.push("shared-context.json");
-        let shared_context_store = SessionStore::new(Some(shared_path))?;
+        let shared_path = storage_path.is_some().then(|| {
+            let mut shared_path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+            shared_path.push(".ai-tools");
+            shared_path.push("sessions");
+            shared_path.push("shared-context.json");
+            shared_path
+        });
+        let shared_context_store = SessionStore::new(shared_path)?;
 
         let language_registry = Arc::new(LanguageRegistry::new()?);
 
         Ok(Self {
-            session_store,
-            shared_context_store,
+            session_store: Arc::new(Mutex::new(session_store)),
+            shared_context_store: Arc::new(Mutex::new(shared_context_store)),
             language_registry,
+            guardrails: Arc::new(Guardrails::new(Limits::configured())),
+            versions: Arc::default(),
+            undo_texts: Arc::new(undo_texts),
             commit_fn: None,
-            default_session_id: "default",
+            default_session_id: "default".into(),
+            dropped_sessions: dropped_sessions.into(),
         })
     }
 
+    /// A handle to the same stores and languages that uses `session_id` when a tool doesn't name
+    /// a session, for serving another client alongside this one
+    #[cfg(test)]
+    pub fn session(&self, session_id: impl Into<Arc<str>>) -> Self {
+        Self {
+            default_session_id: session_id.into(),
+            commit_fn: None,
+            ..self.clone()
+        }
+    }
+
+    /// Use `session_id` when a tool doesn't name a session
+    #[cfg(test)]
+    pub fn with_default_session_id(mut self, session_id: impl Into<Arc<str>>) -> Self {
+        self.default_session_id = session_id.into();
+        self
+    }
+
+    pub fn default_session_id(&self) -> &str {
+        &self.default_session_id
+    }
+
+    /// Log what remains staged as the server stops. Staged operations are written to session
+    /// storage as soon as they change, so there is nothing left to flush.
+    pub fn shutdown(&self) -> Result<()> {
+        let persistent = lock(&self.session_store).is_persistent();
+        match self.get_staged_operation(None)? {
+            Some(staged) if persistent => log::info!(
+                "shutting down with a staged edit preserved in session storage, \
+                 list_staged_edits will show it after restarting: {}",
+                staged.describe()
+            ),
+            Some(staged) => log::warn!(
+                "shutting down with a staged edit that is discarded because sessions are \
+                 kept in memory: {}",
+                staged.describe()
+            ),
+            None => log::info!("shutting down with no staged edits"),
+        }
+        Ok(())
+    }
+
+    /// The files sessions and the shared working directory are stored in, when they aren't kept
+    /// in memory
+    pub fn session_storage_paths(&self) -> Vec<PathBuf> {
+        [
+            lock(&self.session_store).path().map(Path::to_path_buf),
+            lock(&self.shared_context_store)
+                .path()
+                .map(Path::to_path_buf),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Where files made for a session, like exported diffs, are written: a directory named after
+    /// the session beside the session storage file, or in the temporary directory when sessions
+    /// are kept in memory
+    pub fn session_directory(&self, session_id: Option<&str>) -> PathBuf {
+        let session_id = session_id.unwrap_or_else(|| self.default_session_id());
+        session_directory(lock(&self.session_store).path(), session_id)
+    }
+
     /// Get context for a session
-    pub fn get_context(&mut self, session_id: Option<&str>) -> Result<Option<PathBuf>> {
+    pub fn get_context(&self, session_id: Option<&str>) -> Result<Option<PathBuf>> {
         let session_id = session_id.unwrap_or_else(|| self.default_session_id());
-        let shared_data = self.shared_context_store.get_or_create(session_id)?;
-        Ok(shared_data.context_path.clone())
+        let mut store = lock(&self.shared_context_store);
+        Ok(store.get_or_create(session_id)?.context_path.clone())
     }
 
-    /// Stage a new operation, replacing any existing staged operation
+    /// Read from a session's edit-specific data
+    fn read_session<R>(
+        &self,
+        session_id: Option<&str>,
+        fun: impl FnOnce(&SemanticEditSessionData) -> R,
+    ) -> Result<R> {
+        let session_id = session_id.unwrap_or_else(|| self.default_session_id());
+        let mut store = lock(&self.session_store);
+        Ok(fun(store.get_or_create(session_id)?))
+    }
+
+    /// Change a session's edit-specific data
+    fn update_session<R>(
+        &self,
+        session_id: Option<&str>,
+        fun: impl FnOnce(&mut SemanticEditSessionData) -> R,
+    ) -> Result<R> {
+        let session_id = session_id.unwrap_or_else(|| self.default_session_id());
+        let mut result = None;
+        lock(&self.session_store).update(session_id, |data| result = Some(fun(data)))?;
+        result.ok_or_else(|| anyhow!("session `{session_id}` was not updated"))
+    }
+
+    /// Stage a new operation, replacing any existing staged operation. The text the operation
+    /// was staged against is cached, so that persist_edit can merge it with changes made to the
+    /// file after it was staged.
     pub fn preview_edit(
-        &mut self,
+        &self,
         session_id: Option<&str>,
         staged_operation: Option<StagedOperation>,
     ) -> Result<()> {
-        let session_id = session_id.unwrap_or_else(|| self.default_session_id());
-        self.session_store.update(session_id, |data| {
+        if let Some(staged) = &staged_operation
+            && let Some(base_version) = &staged.base_version
+            && let Ok((text, _)) = Encoding::read(&staged.file_path)
+            && versions::of(&text) == *base_version
+        {
+            self.versions.insert(&text);
+        }
+        self.update_session(session_id, |data| {
             data.staged_operation = staged_operation;
         })
     }
 
+    /// Preview `content` at a position that a tool has already found in `file_path`, staging it
+    /// if it's valid and counting a rejection if it isn't, and return the preview
+    pub fn stage_edit_at(
+        &self,
+        content: String,
+        selector: Selector,
+        language: &LanguageCommon,
+        file_path: PathBuf,
+        position: EditPosition,
+    ) -> Result<String> {
+        let editor = Editor::new(content, selector, language, file_path, Some(position))?;
+        let (message, staged_operation) = editor.preview()?;
+        if staged_operation.is_none() {
+            self.record_rejection(None)?;
+        }
+        self.preview_edit(None, staged_operation)?;
+        Ok(message)
+    }
+
     /// Get the currently staged operation, if any
     pub fn get_staged_operation(
-        &mut self,
+        &self,
         session_id: Option<&str>,
-    ) -> Result<Option<&StagedOperation>> {
-        let session_id = session_id.unwrap_or_else(|| self.default_session_id());
-        let session_data = self.session_store.get_or_create(session_id)?;
-        Ok(session_data.staged_operation.as_ref())
+    ) -> Result<Option<StagedOperation>> {
+        self.read_session(session_id, |data| data.staged_operation.clone())
     }
 
     /// Take the staged operation, removing it from storage
     pub fn take_staged_operation(
-        &mut self,
+        &self,
         session_id: Option<&str>,
     ) -> Result<Option<StagedOperation>> {
-        let mut staged_op = None;
-        let session_id = session_id.unwrap_or_else(|| self.default_session_id());
-        self.session_store.update(session_id, |data| {
-            staged_op = data.staged_operation.take();
-        })?;
-        Ok(staged_op)
+        self.update_session(session_id, |data| data.staged_operation.take())
     }
 
-    /// Modify the staged operation in place
+    /// Modify the staged operation in place, returning the result
     pub fn modify_staged_operation<F>(
-        &mut self,
+        &self,
         session_id: Option<&str>,
         fun: F,
-    ) -> Result<Option<&StagedOperation>>
+    ) -> Result<Option<StagedOperation>>
     where
         F: FnOnce(&mut StagedOperation),
     {
-        let session_id = session_id.unwrap_or_else(|| self.default_session_id());
-        self.session_store.update(session_id, |data| {
-            if let Some(ref mut op) = data.staged_operation {
+        self.update_session(session_id, |data| {
+            data.staged_operation.as_mut().map(|op| {
                 fun(op);
-            }
-        })?;
-        self.get_staged_operation(Some(session_id))
+                op.clone()
+            })
+        })
     }
 
     /// Set context path for a session
-    pub fn set_working_directory(&mut self, path: PathBuf, session_id: Option<&str>) -> Result<()> {
+    pub fn set_working_directory(&self, path: PathBuf, session_id: Option<&str>) -> Result<()> {
         let session_id = session_id.unwrap_or_else(|| self.default_session_id());
+        let path = canonicalize(&path)?;
 
-        self.shared_context_store_mut().update(session_id, |data| {
+        lock(&self.shared_context_store).update(session_id, |data| {
             data.context_path = Some(path);
         })
     }
 
-    #[allow(dead_code, reason = "used in tests")]
-    pub fn with_working_directory(
-        mut self,
-        path: PathBuf,
+    /// Save the current working directory on the session's stack and switch to `path`
+    pub fn push_working_directory(&self, path: PathBuf, session_id: Option<&str>) -> Result<()> {
+        let session_id = session_id.unwrap_or_else(|| self.default_session_id());
+        let current = self
+            .get_context(Some(session_id))?
+            .ok_or_else(|| anyhow!("No working directory is set, so there is nothing to push. Use set_working_directory instead."))?;
+        self.set_working_directory(path, Some(session_id))?;
+        self.update_session(Some(session_id), |data| {
+            data.directory_stack.push(current);
+        })
+    }
+
+    /// Return to the most recently pushed working directory, if any
+    pub fn pop_working_directory(&self, session_id: Option<&str>) -> Result<Option<PathBuf>> {
+        let session_id = session_id.unwrap_or_else(|| self.default_session_id());
+        let popped = self.update_session(Some(session_id), |data| data.directory_stack.pop())?;
+        if let Some(path) = &popped {
+            self.set_working_directory(path.clone(), Some(session_id))?;
+        }
+        Ok(popped)
+    }
+
+    /// Working directories saved by push_working_directory, most recent last
+    pub fn directory_stack(&self, session_id: Option<&str>) -> Result<Vec<PathBuf>> {
+        self.read_session(session_id, |data| data.directory_stack.clone())
+    }
+
+    /// Grammars chosen for file extensions in this session
+    pub fn dialects(&self, session_id: Option<&str>) -> Result<Dialects> {
+        self.read_session(session_id, |data| data.dialects.clone())
+    }
+
+    /// Choose the grammar for files with an extension, or with None, return to the default
+    pub fn set_dialect(
+        &self,
+        extension: &str,
+        language: Option<LanguageName>,
+        session_id: Option<&str>,
+    ) -> Result<()> {
+        self.update_session(session_id, |data| {
+            data.dialects.set(extension, language);
+        })
+    }
+
+    /// Enforce `limits` instead of those set in the environment
+    #[cfg(test)]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.guardrails = Arc::new(Guardrails::new(limits));
+        self
+    }
+
+    /// Count a write of `edited` over `original` to `file` in the session's statistics, and
+    /// move the session's bookmarks and any operation staged at an exact position in `file` to
+    /// follow their text. A staged operation whose text was removed is discarded. Both versions
+    /// of the file are cached for comparing with later, and the write can be undone with
+    /// undo_edit. `original` is None when the write created the file. This is called once the
+    /// file has been written, so that a write that fails is never recorded.
+    pub fn record_write(
+        &self,
+        file: &Path,
+        original: Option<&str>,
+        edited: &str,
+        formatter: Option<&str>,
+        session_id: Option<&str>,
+    ) -> Result<()> {
+        self.follow_write(file, original, edited, formatter, true, session_id)
+    }
+
+    /// Record a write like [`record_write`](Self::record_write) that restores `file` to
+    /// `restored`, without remembering it as a write to undo
+    pub fn record_undo(
+        &self,
+        file: &Path,
+        current: &str,
+        restored: &str,
+        session_id: Option<&str>,
+    ) -> Result<()> {
+        self.follow_write(file, Some(current), restored, None, false, session_id)
+    }
+
+    fn follow_write(
+        &self,
+        file: &Path,
+        before: Option<&str>,
+        edited: &str,
+        formatter: Option<&str>,
+        undoable: bool,
+        session_id: Option<&str>,
+    ) -> Result<()> {
+        let original = before.unwrap_or_default();
+        self.versions.insert(original);
+        self.versions.insert(edited);
+        let unmarked_edited = edited.trim_start_matches('\u{feff}');
+        let offsets = OffsetMap::new(original.trim_start_matches('\u{feff}'), unmarked_edited);
+        let (original_version, edited_version) = (versions::of(original), versions::of(edited));
+        let directory = self.session_directory(session_id);
+        let mut kept = Ok(());
+        self.update_session(session_id, |data| {
+            data.stats.record_write(file, original, edited, formatter);
+            if undoable {
+                data.history.record(file, before, edited);
+                // inside the update, so that no other instance prunes the texts in between
+                kept = self.undo_texts.keep(&directory, before, &data.history);
+            }
+            data.bookmarks.update(file, &offsets, unmarked_edited);
+            if let Some(staged) = &mut data.staged_operation
+                && staged.file_path == file
+                && !staged.follow(&offsets)
+            {
+                log::info!(
+                    "discarding the staged edit because the text it targets was removed: {}",
+                    staged.describe()
+                );
+                data.staged_operation = None;
+            }
+            if let Some(staged) = &mut data.staged_operation
+                && staged.file_path == file
+                && staged.base_version.as_ref() == Some(&original_version)
+            {
+                staged.base_version = Some(edited_version);
+            }
+        })?;
+        kept
+    }
+
+    /// Take the session's most recent write, or its most recent write to `file`, to undo it
+    pub fn take_write(
+        &self,
+        file: Option<&Path>,
+        session_id: Option<&str>,
+    ) -> Result<Option<Write>> {
+        self.update_session(session_id, |data| data.history.take(file))
+    }
+
+    /// The text of the file before `write`, one of the session's writes, or None if the write
+    /// created the file
+    pub fn undo_text(&self, write: &Write, session_id: Option<&str>) -> Result<Option<String>> {
+        self.undo_texts
+            .before(&self.session_directory(session_id), write)
+    }
+
+    /// Put back a write taken with [`take_write`](Self::take_write) that couldn't be undone
+    pub fn restore_write(&self, write: Write, session_id: Option<&str>) -> Result<()> {
+        self.update_session(session_id, |data| data.history.restore(write))
+    }
+
+    /// Count a preview that validation rejected in the session's statistics
+    pub fn record_rejection(&self, session_id: Option<&str>) -> Result<()> {
+        self.update_session(session_id, |data| data.stats.record_rejection())
+    }
+
+    /// Why the session's stored data was dropped when the server started, if it was
+    pub fn dropped_session(&self, session_id: Option<&str>) -> Option<&DroppedSession> {
+        let session_id = session_id.unwrap_or_else(|| self.default_session_id());
+        self.dropped_sessions
+            .iter()
+            .find(|dropped| dropped.session_id == session_id)
+    }
+
+    /// The session's statistics, which start over from here
+    pub fn take_stats(&self, session_id: Option<&str>) -> Result<SessionStats> {
+        self.update_session(session_id, |data| std::mem::take(&mut data.stats))
+    }
+
+    /// Remember that `anchor` resolved in `file`, so `anchor_ref` can refer to it
+    pub fn record_anchor(&self, file: &Path, anchor: &str, session_id: Option<&str>) -> Result<()> {
+        self.update_session(session_id, |data| data.anchors.record(file, anchor))
+    }
+
+    /// The anchor in `file` that an `anchor_ref` like `last` refers to
+    pub fn resolve_anchor_ref(
+        &self,
+        file: &Path,
+        reference: &str,
         session_id: Option<&str>,
-    ) -> Result<Self> {
+    ) -> Result<String> {
+        self.read_session(session_id, |data| data.anchors.resolve(file, reference))?
+    }
+
+    /// Mark a range with `name`, replacing any bookmark with that name
+    pub fn set_bookmark(
+        &self,
+        name: &str,
+        bookmark: Bookmark,
+        session_id: Option<&str>,
+    ) -> Result<()> {
+        self.update_session(session_id, |data| data.bookmarks.set(name, bookmark))
+    }
+
+    pub fn bookmark(&self, name: &str, session_id: Option<&str>) -> Result<Bookmark> {
+        self.read_session(session_id, |data| data.bookmarks.get(name).cloned())?
+    }
+
+    #[cfg(test)]
+    pub fn with_working_directory(self, path: PathBuf, session_id: Option<&str>) -> Result<Self> {
         self.set_working_directory(path, session_id)?;
         Ok(self)
     }
 
-    /// Resolve a path relative to session context if needed
-    pub(crate) fn resolve_path(
-        &mut self,
+    /// Resolve a path relative to session context if needed, expanding `~` and environment
+    /// variables
+    pub(crate) fn resolve_path(&self, path_str: &str, session_id: Option<&str>) -> Result<PathBuf> {
+        let path = PathBuf::from(&*expand(path_str)?);
+
+        if path.is_absolute() {
+            return canonicalize(&path);
+        }
+
+        let session_id = session_id.unwrap_or_else(|| self.default_session_id());
+
+        match self.get_context(Some(session_id))? {
+            Some(context) => std::fs::canonicalize(context.join(&path)).map_err(|e| {
+                anyhow!(
+                    "Unable to resolve {} relative to the working directory {}: {e}",
+                    path.display(),
+                    context.display()
+                )
+            }),
+            None => Err(anyhow!(
+                "No working directory is set for `{session_id}`, so {} cannot be resolved. Use set_working_directory first or provide an absolute path.",
+                path.display()
+            )),
+        }
+    }
+
+    /// Resolve a path like [`resolve_path`](Self::resolve_path), for a file that doesn't exist
+    /// yet and so can't be canonicalized
+    pub(crate) fn resolve_new_path(
+        &self,
         path_str: &str,
         session_id: Option<&str>,
     ) -> Result<PathBuf> {
-        let path = PathBuf::from(&*shellexpand::tilde(path_str));
+        let path = PathBuf::from(&*expand(path_str)?);
 
         if path.is_absolute() {
-            return Ok(std::fs::canonicalize(path)?);
+            return Ok(path);
         }
 
         let session_id = session_id.unwrap_or_else(|| self.default_session_id());
 
         match self.get_context(Some(session_id))? {
-            Some(context) => Ok(std::fs::canonicalize(context.join(path_str))?),
+            Some(context) => Ok(context.join(path)),
             None => Err(anyhow!(
-                "No context found for `{session_id}`. Use set_context first or provide an absolute path.",
+                "No working directory is set for `{session_id}`, so {} cannot be resolved. Use set_working_directory first or provide an absolute path.",
+                path.display()
             )),
         }
     }
 }
+
+/// The directory named after `session_id` beside the session storage file at `storage`, or in
+/// the temporary directory when sessions are kept in memory
+fn session_directory(storage: Option<&Path>, session_id: &str) -> PathBuf {
+    let base = storage.map_or_else(
+        || std::env::temp_dir().join("semantic-edit"),
+        |path| path.with_extension(""),
+    );
+    base.join(
+        session_id
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>(),
+    )
+}
+
+/// Expand `~` and `$VAR`/`${VAR}` references in a user-provided path
+pub(crate) fn expand(path: &str) -> Result<String> {
+    shellexpand::full(path)
+        .map(|expanded| expanded.into_owned())
+        .map_err(|e| anyhow!("Unable to expand {path}: {e}"))
+}
+
+/// Resolve symlinks and relative components so that every path the tools store or compare
+/// refers to a file the same way, however it was reached
+pub(crate) fn canonicalize(path: &Path) -> Result<PathBuf> {
+    std::fs::canonicalize(path).map_err(|e| anyhow!("Unable to resolve {}: {e}", path.display()))
+}
@@ -0,0 +1,95 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// How many anchors are remembered for each file
+pub const ANCHOR_HISTORY: usize = 5;
+
+/// The anchors that recently resolved in each file, so that later edits can refer to them with
+/// an `anchor_ref` like `last` instead of sending them again
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AnchorHistory(BTreeMap<PathBuf, Vec<String>>);
+
+impl AnchorHistory {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Remember that `anchor` resolved in `file`, as the most recent anchor there
+    pub fn record(&mut self, file: &Path, anchor: &str) {
+        let anchors = self.0.entry(file.to_path_buf()).or_default();
+        anchors.retain(|recorded| recorded != anchor);
+        anchors.insert(0, anchor.to_string());
+        anchors.truncate(ANCHOR_HISTORY);
+    }
+
+    /// The anchor that `reference` names in `file`: `last` for the most recent, and `last-1`
+    /// through `last-4` for those before it
+    pub fn resolve(&self, file: &Path, reference: &str) -> Result<String> {
+        let anchors = self.0.get(file).map(Vec::as_slice).unwrap_or_default();
+        if anchors.is_empty() {
+            return Err(anyhow!(
+                "No anchors have been used in this file yet, so anchor_ref `{reference}` can't \
+                 be resolved. Pass anchor instead."
+            ));
+        }
+
+        let index = match reference.trim() {
+            "last" => Some(0),
+            reference => reference
+                .strip_prefix("last-")
+                .and_then(|back| back.parse::<usize>().ok()),
+        };
+        index
+            .and_then(|index| anchors.get(index))
+            .cloned()
+            .ok_or_else(|| {
+                let remembered = anchors
+                    .iter()
+                    .enumerate()
+                    .map(|(index, anchor)| format!("- {}: `{anchor}`", name(index)))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                anyhow!(
+                    "anchor_ref `{reference}` doesn't name a remembered anchor. The anchors used \
+                     in this file are, most recent first:\n{remembered}"
+                )
+            })
+    }
+}
+
+fn name(index: usize) -> String {
+    if index == 0 {
+        "last".into()
+    } else {
+        format!("last-{index}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references_count_back_from_the_most_recent_anchor() {
+        let file = Path::new("src/lib.rs");
+        let mut history = AnchorHistory::default();
+        assert!(history.resolve(file, "last").is_err());
+
+        for anchor in ["fn a", "fn b", "fn a", "fn c", "fn d", "fn e", "fn f"] {
+            history.record(file, anchor);
+        }
+        assert_eq!(history.resolve(file, "last").unwrap(), "fn f");
+        assert_eq!(history.resolve(file, "last-2").unwrap(), "fn d");
+        // `fn a` moved to the front when it was used again, so `fn b` fell off the end instead
+        assert_eq!(history.resolve(file, "last-4").unwrap(), "fn a");
+
+        let error = history.resolve(file, "last-5").unwrap_err().to_string();
+        assert!(error.ends_with("- last-4: `fn a`"), "{error}");
+        assert!(history.resolve(file, "first").is_err());
+        assert!(history.resolve(Path::new("src/main.rs"), "last").is_err());
+    }
+}
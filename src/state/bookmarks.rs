@@ -0,0 +1,131 @@
+use super::OffsetMap;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// A named range of a file, kept pointing at the same text as edits are written around it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Bookmark {
+    pub file: PathBuf,
+    pub range: Range<usize>,
+    /// A hash of the file's content as of the last edit the range was updated for, to notice
+    /// changes made some other way
+    content_hash: u64,
+}
+
+impl Bookmark {
+    pub fn new(file: PathBuf, range: Range<usize>, source: &str) -> Self {
+        Self {
+            file,
+            range,
+            content_hash: hash(without_bom(source)),
+        }
+    }
+
+    /// The range in `source`, the file's current content, unless the file has changed in ways
+    /// the bookmark wasn't updated for
+    pub fn range_in(&self, name: &str, source: &str) -> Result<Range<usize>> {
+        if hash(without_bom(source)) != self.content_hash {
+            return Err(anyhow!(
+                "{} has changed outside of these tools since bookmark `{name}` was set, so it \
+                 can't be found reliably. Set it again with set_bookmark.",
+                self.file.display()
+            ));
+        }
+        Ok(self.range.clone())
+    }
+}
+
+/// A session's bookmarks by name
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Bookmarks(BTreeMap<String, Bookmark>);
+
+impl Bookmarks {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn set(&mut self, name: &str, bookmark: Bookmark) {
+        self.0.insert(name.to_string(), bookmark);
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Bookmark> {
+        self.0.get(name).ok_or_else(|| {
+            if self.0.is_empty() {
+                anyhow!("There is no bookmark `{name}`. Set one with set_bookmark first.")
+            } else {
+                anyhow!(
+                    "There is no bookmark `{name}`. The bookmarks in this session are: {}",
+                    self.0
+                        .keys()
+                        .map(|name| format!("`{name}`"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+        })
+    }
+
+    /// Move the bookmarks in `file` to where their text is after an edit. Bookmarks whose text
+    /// was removed entirely are dropped.
+    pub fn update(&mut self, file: &Path, offsets: &OffsetMap, edited: &str) {
+        self.0.retain(|_, bookmark| {
+            if bookmark.file != file {
+                return true;
+            }
+            if offsets.removed(&bookmark.range) {
+                return false;
+            }
+            let start = offsets.map(bookmark.range.start);
+            let end = offsets.map(bookmark.range.end);
+            bookmark.range = start..end.max(start);
+            bookmark.content_hash = hash(edited);
+            true
+        });
+    }
+}
+
+fn without_bom(source: &str) -> &str {
+    source.strip_prefix('\u{feff}').unwrap_or(source)
+}
+
+fn hash(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookmarks_follow_their_text_through_edits() {
+        let original = "fn a() {}\n\nfn b() {\n    1\n}\n";
+        let edited = "use std::fs;\n\nfn a() {}\n\nfn b() {\n    2\n}\n";
+        let b = original.find("fn b").unwrap();
+
+        let path = Path::new("src/lib.rs");
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.set(
+            "b",
+            Bookmark::new(path.into(), b..original.len() - 1, original),
+        );
+        bookmarks.set("a", Bookmark::new(path.into(), 0..9, original));
+        bookmarks.update(path, &OffsetMap::new(original, edited), edited);
+        let bookmark = bookmarks.get("b").unwrap();
+        let range = bookmark.range_in("b", edited).unwrap();
+        assert_eq!(&edited[range], "fn b() {\n    2\n}");
+        assert!(bookmark.range_in("b", original).is_err());
+
+        let removed = "use std::fs;\n\nfn b() {\n    2\n}\n";
+        bookmarks.update(path, &OffsetMap::new(edited, removed), removed);
+        let error = bookmarks.get("a").unwrap_err().to_string();
+        assert!(error.ends_with("are: `b`"), "{error}");
+    }
+}
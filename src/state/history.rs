@@ -0,0 +1,230 @@
+use crate::versions;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+/// How many writes each session can undo
+pub const UNDO_HISTORY: usize = 10;
+
+/// A write that undo_edit can reverse
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Write {
+    pub file: PathBuf,
+    /// The version of the file's text before the write, which is kept in [`UndoTexts`], or None
+    /// if the write created the file
+    pub before: Option<String>,
+    /// The version the write left the file at, to notice changes made since
+    pub after: String,
+}
+
+/// The session's most recent writes, oldest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WriteHistory(Vec<Write>);
+
+impl WriteHistory {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Remember a write of `edited` over `before`, forgetting the oldest once there are too many
+    pub fn record(&mut self, file: &Path, before: Option<&str>, edited: &str) {
+        self.0.push(Write {
+            file: file.to_path_buf(),
+            before: before.map(versions::of),
+            after: versions::of(edited),
+        });
+        if self.0.len() > UNDO_HISTORY {
+            self.0.remove(0);
+        }
+    }
+
+    /// Forget and return the most recent write, or the most recent one to `file`
+    pub fn take(&mut self, file: Option<&Path>) -> Option<Write> {
+        let index = self
+            .0
+            .iter()
+            .rposition(|write| file.is_none_or(|file| write.file == file))?;
+        Some(self.0.remove(index))
+    }
+
+    /// Put back a write that turned out not to be undoable, as the most recent
+    pub fn restore(&mut self, write: Write) {
+        self.0.push(write);
+    }
+
+    /// Whether a write in the history started from the text at `version`
+    fn refers_to(&self, version: &str) -> bool {
+        self.0
+            .iter()
+            .any(|write| write.before.as_deref() == Some(version))
+    }
+}
+
+/// The text of files before the writes in each session's history, kept out of the session file
+/// so that it doesn't grow with the size of the files written. Each text is named by its version
+/// in an `undo` directory inside the session's directory, on disk when sessions are persistent
+/// and only in memory when they aren't.
+#[derive(Debug)]
+pub enum UndoTexts {
+    Files,
+    Memory(Mutex<HashMap<PathBuf, String>>),
+}
+
+impl UndoTexts {
+    pub fn new(persistent: bool) -> Self {
+        if persistent {
+            Self::Files
+        } else {
+            Self::Memory(Mutex::default())
+        }
+    }
+
+    /// Store `before` for the session whose directory is `session_directory`, and remove the
+    /// texts that its `history` no longer refers to
+    pub fn keep(
+        &self,
+        session_directory: &Path,
+        before: Option<&str>,
+        history: &WriteHistory,
+    ) -> Result<()> {
+        if let Some(before) = before {
+            self.store(session_directory, before)?;
+        }
+        let directory = session_directory.join("undo");
+        match self {
+            Self::Files => {
+                let entries = match fs::read_dir(&directory) {
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+                    entries => entries?,
+                };
+                for entry in entries {
+                    let path = entry?.path();
+                    if !history.refers_to(&path.file_name().unwrap_or_default().to_string_lossy()) {
+                        fs::remove_file(path)?;
+                    }
+                }
+            }
+            Self::Memory(texts) => {
+                texts
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .retain(|path, _| {
+                        path.parent() != Some(&*directory)
+                            || path
+                                .file_name()
+                                .is_some_and(|name| history.refers_to(&name.to_string_lossy()))
+                    })
+            }
+        }
+        Ok(())
+    }
+
+    /// Store `text` by its version for the session whose directory is `session_directory`
+    pub fn store(&self, session_directory: &Path, text: &str) -> Result<()> {
+        let text = text.trim_start_matches('\u{feff}');
+        let directory = session_directory.join("undo");
+        let path = directory.join(versions::of(text));
+        match self {
+            Self::Files => {
+                fs::create_dir_all(&directory)?;
+                fs::write(path, text)?;
+            }
+            Self::Memory(texts) => {
+                texts
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .insert(path, text.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// The text of the file before `write` in the session whose directory is
+    /// `session_directory`, or None if the write created the file
+    pub fn before(&self, session_directory: &Path, write: &Write) -> Result<Option<String>> {
+        let Some(version) = &write.before else {
+            return Ok(None);
+        };
+        let path = session_directory.join("undo").join(version);
+        let text = match self {
+            Self::Files => match fs::read_to_string(&path) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                text => Some(text?),
+            },
+            Self::Memory(texts) => texts
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&path)
+                .cloned(),
+        };
+        text.map(Some).ok_or_else(|| {
+            anyhow!(
+                "The text {} had before the write to undo (version `{version}`) is no longer \
+                 stored, so nothing was written",
+                write.file.display()
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_most_recent_writes_are_undone_first() {
+        let mut history = WriteHistory::default();
+        for index in 0..=UNDO_HISTORY {
+            history.record(Path::new("a.rs"), Some(&index.to_string()), "after");
+        }
+        history.record(Path::new("b.rs"), None, "created");
+
+        let write = history.take(Some(Path::new("a.rs"))).unwrap();
+        assert_eq!(write.before, Some(versions::of("10")));
+        assert_eq!(write.after, versions::of("after"));
+        assert_eq!(history.take(None).unwrap().file, Path::new("b.rs"));
+
+        let mut undone = vec![];
+        while let Some(write) = history.take(None) {
+            undone.push(write.before.unwrap());
+        }
+        assert_eq!(
+            undone,
+            (2..10)
+                .rev()
+                .map(|index| versions::of(&index.to_string()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn texts_are_kept_while_the_history_refers_to_them() {
+        let directory =
+            std::env::temp_dir().join(format!("semantic-edit-undo-texts-{}", std::process::id()));
+        for texts in [UndoTexts::new(true), UndoTexts::new(false)] {
+            let mut history = WriteHistory::default();
+            for index in 0..=UNDO_HISTORY {
+                let before = index.to_string();
+                history.record(Path::new("a.rs"), Some(&before), "after");
+                texts.keep(&directory, Some(&before), &history).unwrap();
+            }
+
+            let write = history.take(None).unwrap();
+            assert_eq!(
+                texts.before(&directory, &write).unwrap().as_deref(),
+                Some("10")
+            );
+            let forgotten = Write {
+                before: Some(versions::of("0")),
+                ..write
+            };
+            assert!(texts.before(&directory, &forgotten).is_err());
+        }
+        let _ = fs::remove_dir_all(directory);
+    }
+}
@@ -0,0 +1,314 @@
+use super::{SemanticEditSessionData, history::UndoTexts};
+use crate::versions;
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The session data format written by this version of the crate
+pub const CURRENT_VERSION: u32 = 2;
+
+/// An upgrade of a session's data, given the session's directory for anything it moves there
+type Migration = fn(&mut Map<String, Value>, &Path) -> Result<()>;
+
+/// Upgrades from each version to the next, indexed by the version they upgrade from
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [v0_to_v1, v1_to_v2];
+
+/// Sessions written before versioning carry no version field but are otherwise unchanged
+fn v0_to_v1(_data: &mut Map<String, Value>, _directory: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Undo history refers to the text from before each write by its version, and the text moves
+/// from the session into the session's directory
+fn v1_to_v2(data: &mut Map<String, Value>, directory: &Path) -> Result<()> {
+    let writes = data
+        .get_mut("history")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten();
+    for before in writes.filter_map(|write| write.get_mut("before")) {
+        if let Some(text) = before.as_str() {
+            UndoTexts::Files.store(directory, text)?;
+            *before = versions::of(text).into();
+        }
+    }
+    Ok(())
+}
+
+/// A session that couldn't be read, which is left out of the migrated file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroppedSession {
+    pub session_id: String,
+    pub reason: String,
+    /// The backup of the file that still holds the session
+    pub backup: PathBuf,
+}
+
+impl Display for DroppedSession {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Session `{}` couldn't be read when the server started ({}), so it started over. \
+             Its previous contents are in {}.",
+            self.session_id,
+            self.reason,
+            self.backup.display()
+        )
+    }
+}
+
+/// Bring the session file at `path` up to [`CURRENT_VERSION`] before it is loaded.
+///
+/// The file is backed up to `<path>.v<version>.bak`, named for the oldest format in it, before
+/// it is rewritten. Sessions that cannot be read even after migrating are dropped individually
+/// rather than causing every session in the file to be discarded, and are returned so they can
+/// be reported. A file that cannot be parsed at all is backed up before it gets overwritten.
+pub fn migrate(path: &Path) -> Result<Vec<DroppedSession>> {
+    let _lock = super::storage::lock(path)?;
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Ok(vec![]);
+    };
+
+    if contents.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut sessions: Map<String, Value> = match serde_json::from_str(&contents) {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            let backup = backup(path, "unreadable")?;
+            log::warn!(
+                "unable to parse {} ({e}), backed up to {} before it is replaced",
+                path.display(),
+                backup.display()
+            );
+            return Ok(vec![]);
+        }
+    };
+
+    let oldest = sessions
+        .values()
+        .filter_map(|entry| entry.get("data")?.as_object())
+        .map(|data| data.get("version").and_then(Value::as_u64).unwrap_or(0))
+        .min()
+        .unwrap_or(CURRENT_VERSION.into());
+
+    let mut changed = false;
+    let mut dropped = vec![];
+    sessions.retain(|session_id, entry| {
+        let Some(data) = entry.get_mut("data").and_then(Value::as_object_mut) else {
+            log::warn!("dropping malformed session {session_id}");
+            dropped.push((session_id.clone(), "it has no data".to_string()));
+            changed = true;
+            return false;
+        };
+
+        let version = data.get("version").and_then(Value::as_u64).unwrap_or(0) as usize;
+        if version > CURRENT_VERSION as usize {
+            log::warn!(
+                "session {session_id} was written by a newer version (format {version}), reading it as format {CURRENT_VERSION}"
+            );
+            return true;
+        }
+
+        if version < CURRENT_VERSION as usize {
+            let directory = super::session_directory(Some(path), session_id);
+            if let Err(e) = MIGRATIONS[version..]
+                .iter()
+                .try_for_each(|migration| migration(data, &directory))
+            {
+                log::warn!("dropping session {session_id}, which couldn't be migrated: {e}");
+                dropped.push((session_id.clone(), e.to_string()));
+                changed = true;
+                return false;
+            }
+            data.insert("version".into(), CURRENT_VERSION.into());
+            changed = true;
+        }
+
+        match serde_json::from_value::<SemanticEditSessionData>(Value::Object(data.clone())) {
+            Ok(_) => true,
+            Err(e) => {
+                log::warn!("dropping unreadable session {session_id}: {e}");
+                dropped.push((session_id.clone(), e.to_string()));
+                changed = true;
+                false
+            }
+        }
+    });
+
+    if !changed {
+        return Ok(vec![]);
+    }
+
+    let backup = backup(path, &format!("v{oldest}"))?;
+    log::info!(
+        "migrated {} to session format {CURRENT_VERSION}, previous contents saved to {}",
+        path.display(),
+        backup.display()
+    );
+    fs::write(path, serde_json::to_string_pretty(&sessions)?)?;
+
+    Ok(dropped
+        .into_iter()
+        .map(|(session_id, reason)| DroppedSession {
+            session_id,
+            reason,
+            backup: backup.clone(),
+        })
+        .collect())
+}
+
+/// Copy the file at `path` to `<path>.<label>.bak`, numbering the copy if a backup by that name
+/// is already there, so that no earlier backup is overwritten
+pub(super) fn backup(path: &Path, label: &str) -> Result<PathBuf> {
+    let backup = (0u32..)
+        .map(|copy| {
+            let mut backup = path.as_os_str().to_owned();
+            backup.push(format!(".{label}"));
+            if copy > 0 {
+                backup.push(format!(".{copy}"));
+            }
+            backup.push(".bak");
+            PathBuf::from(backup)
+        })
+        .find(|backup| !backup.exists())
+        .expect("a backup name is free");
+    fs::copy(path, &backup)?;
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "semantic-edit-migration-{name}-{}.json",
+            std::process::id()
+        ))
+    }
+
+    fn read(path: &Path) -> Option<Value> {
+        fs::read_to_string(path)
+            .ok()
+            .map(|contents| serde_json::from_str(&contents).unwrap())
+    }
+
+    fn backup_path(path: &Path, suffix: &str) -> PathBuf {
+        PathBuf::from(format!("{}{suffix}", path.display()))
+    }
+
+    fn clean_up(path: &Path) {
+        for suffix in ["", ".lock", ".v0.bak", ".v0.1.bak"] {
+            let _ = fs::remove_file(backup_path(path, suffix));
+        }
+    }
+
+    /// The migrated sessions, the `.v0.bak` backup if there is one, and the dropped sessions
+    fn migrate_json(name: &str, sessions: Value) -> (Value, Option<Value>, Vec<DroppedSession>) {
+        let path = path(name);
+        clean_up(&path);
+        fs::write(&path, sessions.to_string()).unwrap();
+        let dropped = migrate(&path).unwrap();
+        let result = (
+            read(&path).unwrap(),
+            read(&backup_path(&path, ".v0.bak")),
+            dropped,
+        );
+        clean_up(&path);
+        result
+    }
+
+    #[test]
+    fn unversioned_sessions_are_upgraded_and_backed_up() {
+        let original = json!({
+            "default": { "data": { "staged_operation": null }, "metadata": {} }
+        });
+        let (migrated, backup, dropped) = migrate_json("unversioned", original.clone());
+        assert_eq!(migrated["default"]["data"]["version"], CURRENT_VERSION);
+        assert_eq!(backup, Some(original));
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn current_sessions_are_left_alone() {
+        let original = json!({
+            "default": { "data": { "version": CURRENT_VERSION }, "metadata": {} }
+        });
+        let (migrated, backup, _) = migrate_json("current", original.clone());
+        assert_eq!(migrated, original);
+        assert_eq!(backup, None);
+    }
+
+    #[test]
+    fn unreadable_sessions_do_not_take_others_with_them() {
+        let (migrated, backup, dropped) = migrate_json(
+            "unreadable",
+            json!({
+                "good": { "data": {}, "metadata": {} },
+                "bad": { "data": { "staged_operation": 42 }, "metadata": {} }
+            }),
+        );
+        assert!(migrated.get("good").is_some());
+        assert!(migrated.get("bad").is_none());
+        assert!(backup.unwrap().get("bad").is_some());
+        assert_eq!(
+            dropped
+                .iter()
+                .map(|dropped| dropped.session_id.as_str())
+                .collect::<Vec<_>>(),
+            ["bad"]
+        );
+        assert!(dropped[0].backup.to_string_lossy().ends_with(".v0.bak"));
+    }
+
+    #[test]
+    fn undo_history_text_moves_into_the_session_directory() {
+        let path = path("history");
+        let directory = super::super::session_directory(Some(&path), "default");
+        let (migrated, _, dropped) = migrate_json(
+            "history",
+            json!({
+                "default": {
+                    "data": {
+                        "version": 1,
+                        "history": [{ "file": "a.rs", "before": "fn a() {}\n", "after": "1" }]
+                    },
+                    "metadata": {}
+                }
+            }),
+        );
+        let _ = fs::remove_file(backup_path(&path, ".v1.bak"));
+
+        let version = versions::of("fn a() {}\n");
+        assert!(dropped.is_empty());
+        assert_eq!(migrated["default"]["data"]["history"][0]["before"], version);
+        assert_eq!(
+            fs::read_to_string(directory.join("undo").join(version)).unwrap(),
+            "fn a() {}\n"
+        );
+        let _ = fs::remove_dir_all(directory);
+    }
+
+    #[test]
+    fn earlier_backups_are_kept() {
+        let path = path("repeated");
+        clean_up(&path);
+        let first = json!({ "first": { "data": {}, "metadata": {} } });
+        let second = json!({ "second": { "data": {}, "metadata": {} } });
+        fs::write(&path, first.to_string()).unwrap();
+        migrate(&path).unwrap();
+        fs::write(&path, second.to_string()).unwrap();
+        migrate(&path).unwrap();
+
+        assert_eq!(read(&backup_path(&path, ".v0.bak")), Some(first));
+        assert_eq!(read(&backup_path(&path, ".v0.1.bak")), Some(second));
+        clean_up(&path);
+    }
+}
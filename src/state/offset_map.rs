@@ -0,0 +1,225 @@
+use diffy::{DiffOptions, Line};
+use std::ops::Range;
+
+/// What became of a line of the original text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineChange {
+    /// The line is unchanged, at this line of the edited text
+    Kept(usize),
+    /// The line was rewritten as this line of the edited text
+    Changed(usize),
+    /// The line was deleted, and this line of the edited text follows where it was
+    Removed(usize),
+}
+
+/// Where offsets in a file's text moved to when the file was edited, worked out from a line diff
+/// so that positions recorded before a write still point at the same text after it
+#[derive(Debug)]
+pub struct OffsetMap<'a> {
+    original: &'a str,
+    edited: &'a str,
+    lines: Vec<LineChange>,
+    original_starts: Vec<usize>,
+    edited_starts: Vec<usize>,
+}
+
+impl<'a> OffsetMap<'a> {
+    pub fn new(original: &'a str, edited: &'a str) -> Self {
+        // one hunk with every line as context, so lines can be counted from the start
+        let context = original.lines().count() + edited.lines().count();
+        let patch = DiffOptions::new()
+            .set_context_len(context)
+            .create_patch(original, edited);
+
+        let mut lines = vec![];
+        let (mut new, mut deleted, mut inserted) = (0, vec![], 0);
+        for line in patch.hunks().iter().flat_map(|hunk| hunk.lines()) {
+            match line {
+                Line::Context(_) => {
+                    end_change(&mut lines, &mut deleted, &mut inserted, new);
+                    lines.push(LineChange::Kept(new));
+                    new += 1;
+                }
+                Line::Delete(_) => {
+                    deleted.push(lines.len());
+                    lines.push(LineChange::Removed(new));
+                }
+                Line::Insert(_) => {
+                    new += 1;
+                    inserted += 1;
+                }
+            }
+        }
+        end_change(&mut lines, &mut deleted, &mut inserted, new);
+        if patch.hunks().is_empty() {
+            lines = (0..original.split_inclusive('\n').count())
+                .map(LineChange::Kept)
+                .collect();
+        }
+
+        Self {
+            original,
+            edited,
+            lines,
+            original_starts: line_starts(original),
+            edited_starts: line_starts(edited),
+        }
+    }
+
+    /// Where `offset` in the original text is in the edited text. Offsets in changed lines keep
+    /// their place relative to whatever text the line starts or ends with that didn't change.
+    pub fn map(&self, offset: usize) -> usize {
+        let Some((line, column)) = self.position(offset) else {
+            return self.edited.len();
+        };
+        let mapped = match self.lines[line] {
+            LineChange::Kept(new) => self.edited_start(new) + column,
+            LineChange::Removed(new) => self.edited_start(new),
+            LineChange::Changed(new) => {
+                let old = self.original_line(line);
+                let text = self.edited_line(new);
+                let prefix = old
+                    .char_indices()
+                    .zip(text.chars())
+                    .find(|((_, old), new)| old != new)
+                    .map_or(old.len().min(text.len()), |((index, _), _)| index);
+                let suffix = old
+                    .chars()
+                    .rev()
+                    .zip(text.chars().rev())
+                    .take_while(|(old, new)| old == new)
+                    .map(|(old, _)| old.len_utf8())
+                    .sum::<usize>()
+                    .min(old.len() - prefix)
+                    .min(text.len() - prefix);
+                self.edited_start(new)
+                    + if column <= prefix {
+                        column
+                    } else if column >= old.len() - suffix {
+                        text.len() - (old.len() - column)
+                    } else {
+                        prefix
+                    }
+            }
+        };
+        let mut mapped = mapped.min(self.edited.len());
+        while !self.edited.is_char_boundary(mapped) {
+            mapped -= 1;
+        }
+        mapped
+    }
+
+    /// Whether every line of `range` in the original text was deleted, leaving nothing for a
+    /// position in it to follow
+    pub fn removed(&self, range: &Range<usize>) -> bool {
+        let Some((first, _)) = self.position(range.start) else {
+            return false;
+        };
+        let last = self
+            .position(range.end.saturating_sub(1).max(range.start))
+            .map_or(self.lines.len() - 1, |(line, _)| line);
+        self.lines[first..=last]
+            .iter()
+            .all(|change| matches!(change, LineChange::Removed(_)))
+    }
+
+    /// The line `offset` is on and its column in bytes, or None at the end of the original text
+    fn position(&self, offset: usize) -> Option<(usize, usize)> {
+        if offset >= self.original.len() {
+            return None;
+        }
+        let line = self
+            .original_starts
+            .partition_point(|start| *start <= offset)
+            - 1;
+        Some((line, offset - self.original_starts[line]))
+    }
+
+    fn edited_start(&self, line: usize) -> usize {
+        self.edited_starts
+            .get(line)
+            .copied()
+            .unwrap_or(self.edited.len())
+    }
+
+    fn original_line(&self, line: usize) -> &'a str {
+        let end = self
+            .original_starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.original.len());
+        &self.original[self.original_starts[line]..end]
+    }
+
+    fn edited_line(&self, line: usize) -> &'a str {
+        let start = self.edited_start(line);
+        let end = self.edited_start(line + 1);
+        &self.edited[start..end]
+    }
+}
+
+/// Settle the lines deleted since the last unchanged one, pairing them in order with the lines
+/// inserted in their place, which end at line `new` of the edited text
+fn end_change(
+    lines: &mut [LineChange],
+    deleted: &mut Vec<usize>,
+    inserted: &mut usize,
+    new: usize,
+) {
+    for (index, old) in deleted.drain(..).enumerate() {
+        lines[old] = if index < *inserted {
+            LineChange::Changed(new - *inserted + index)
+        } else {
+            LineChange::Removed(new)
+        };
+    }
+    *inserted = 0;
+}
+
+fn line_starts(text: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    starts.extend(text.match_indices('\n').map(|(index, _)| index + 1));
+    if starts.last() == Some(&text.len()) && !text.is_empty() {
+        starts.pop();
+    }
+    starts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_follow_their_text() {
+        let original = "fn a() {}\n\nfn b() {\n    one(1)\n}\n\nfn c() {}\n";
+        let edited = "use std::fs;\n\nfn a() {}\n\nfn b() {\n    two(1)\n}\n";
+        let map = OffsetMap::new(original, edited);
+
+        let b = original.find("fn b").unwrap();
+        assert_eq!(map.map(b), edited.find("fn b").unwrap());
+        assert_eq!(map.map(original.len()), edited.len());
+
+        // text that didn't change at either end of a changed line keeps its place
+        assert_eq!(
+            map.map(original.find("(1)").unwrap()),
+            edited.find("(1)").unwrap()
+        );
+        assert_eq!(
+            map.map(original.find("one").unwrap()),
+            edited.find("two").unwrap()
+        );
+
+        let c = original.find("fn c").unwrap();
+        assert!(map.removed(&(c..original.len() - 1)));
+        assert!(!map.removed(&(b..c)));
+        assert_eq!(map.map(c), edited.len());
+    }
+
+    #[test]
+    fn unchanged_text_maps_to_itself() {
+        let text = "a\nb";
+        let map = OffsetMap::new(text, text);
+        assert_eq!(map.map(2), 2);
+        assert!(!map.removed(&(0..3)));
+    }
+}
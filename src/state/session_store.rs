@@ -0,0 +1,177 @@
+use super::storage::{JsonFileBackend, MemoryBackend, SessionBackend};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Metadata tracked for each session, in the same format as mcplease's session store so that
+/// existing session files remain readable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionMetadata {
+    created_at: SystemTime,
+    last_used: SystemTime,
+}
+
+impl Default for SessionMetadata {
+    fn default() -> Self {
+        let now = SystemTime::now();
+        Self {
+            created_at: now,
+            last_used: now,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SessionEntry<T> {
+    data: T,
+    metadata: SessionMetadata,
+}
+
+/// Every stored session, keyed by session id
+pub type Sessions<T> = HashMap<String, SessionEntry<T>>;
+
+/// Session storage on top of a [`SessionBackend`].
+///
+/// Every read goes to the backend and every update is a read-modify-write, so instances sharing
+/// a backend see each other's changes and never overwrite sessions they did not modify.
+#[derive(Debug)]
+pub struct SessionStore<T> {
+    sessions: Sessions<T>,
+    backend: Box<dyn SessionBackend<T>>,
+}
+
+impl<T> SessionStore<T>
+where
+    T: Serialize + for<'de> Deserialize<'de> + Clone + Debug + Default + PartialEq + Send + 'static,
+{
+    /// Store sessions in a JSON file at `storage_path`, or only in memory if there is none
+    pub fn new(storage_path: Option<PathBuf>) -> Result<Self> {
+        Ok(match storage_path {
+            Some(storage_path) => Self::with_backend(JsonFileBackend::new(storage_path)?),
+            None => Self::with_backend(MemoryBackend::default()),
+        })
+    }
+
+    pub fn with_backend(backend: impl SessionBackend<T> + 'static) -> Self {
+        Self {
+            sessions: HashMap::new(),
+            backend: Box::new(backend),
+        }
+    }
+
+    /// Whether sessions outlive this process
+    pub fn is_persistent(&self) -> bool {
+        self.backend.is_persistent()
+    }
+
+    /// The file sessions are stored in, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.backend.path()
+    }
+
+    /// Get session data, creating a new session if it doesn't exist
+    pub fn get_or_create(&mut self, session_id: &str) -> Result<&T> {
+        self.sessions = self.backend.read()?;
+
+        if !self.sessions.contains_key(session_id) {
+            self.update(session_id, |_| {})?;
+        }
+
+        Ok(&self.sessions[session_id].data)
+    }
+
+    /// Update session data using a closure, creating the session with default values first if
+    /// it doesn't exist. Other sessions are reloaded from the backend rather than written from
+    /// memory, so changes made by other processes are preserved.
+    pub fn update(&mut self, session_id: &str, fun: impl FnOnce(&mut T)) -> Result<()> {
+        let mut fun = Some(fun);
+        let cache = &mut self.sessions;
+        self.backend.read_modify_write(&mut |sessions| {
+            let mut changed = !sessions.contains_key(session_id);
+            let entry = sessions.entry(session_id.to_string()).or_default();
+            let before = entry.data.clone();
+            if let Some(fun) = fun.take() {
+                fun(&mut entry.data);
+            }
+            changed |= before != entry.data;
+            if changed {
+                entry.metadata.last_used = SystemTime::now();
+            }
+            cache.clone_from(sessions);
+            changed
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, thread};
+
+    #[test]
+    fn concurrent_instances_preserve_each_others_sessions() {
+        let path = std::env::temp_dir().join(format!(
+            "semantic-edit-session-store-{}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let threads = (0..8)
+            .map(|n| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    let mut store = SessionStore::<Vec<u32>>::new(Some(path)).unwrap();
+                    for i in 0..10 {
+                        store
+                            .update(&format!("session-{n}"), |data| data.push(i))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let mut store = SessionStore::<Vec<u32>>::new(Some(path.clone())).unwrap();
+        for n in 0..8 {
+            assert_eq!(
+                store.get_or_create(&format!("session-{n}")).unwrap(),
+                &(0..10).collect::<Vec<_>>()
+            );
+        }
+
+        let _ = fs::remove_file(format!("{}.lock", path.display()));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn updates_from_another_instance_are_visible() {
+        let path = std::env::temp_dir().join(format!(
+            "semantic-edit-session-store-visibility-{}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let mut first = SessionStore::<Option<String>>::new(Some(path.clone())).unwrap();
+        let mut second = SessionStore::<Option<String>>::new(Some(path.clone())).unwrap();
+        assert_eq!(first.get_or_create("default").unwrap(), &None);
+
+        second
+            .update("default", |data| *data = Some("staged".into()))
+            .unwrap();
+        assert_eq!(
+            first.get_or_create("default").unwrap().as_deref(),
+            Some("staged")
+        );
+
+        let _ = fs::remove_file(format!("{}.lock", path.display()));
+        let _ = fs::remove_file(path);
+    }
+}
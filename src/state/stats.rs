@@ -0,0 +1,144 @@
+use diffy::{DiffOptions, Line};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+/// What a session has written and had rejected, for end_session_summary
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SessionStats {
+    /// Files written, with how each was changed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    files: BTreeMap<PathBuf, FileStats>,
+    /// Previews that validation rejected
+    #[serde(default, skip_serializing_if = "is_zero")]
+    rejections: usize,
+    /// How many writes each formatter formatted
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    formatters: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+struct FileStats {
+    writes: usize,
+    added: usize,
+    removed: usize,
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+impl SessionStats {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Count a write of `edited` over `original` to `file`, formatted by `formatter`
+    pub fn record_write(
+        &mut self,
+        file: &Path,
+        original: &str,
+        edited: &str,
+        formatter: Option<&str>,
+    ) {
+        let file = self.files.entry(file.to_path_buf()).or_default();
+        file.writes += 1;
+        for hunk in DiffOptions::new().create_patch(original, edited).hunks() {
+            for line in hunk.lines() {
+                match line {
+                    Line::Insert(_) => file.added += 1,
+                    Line::Delete(_) => file.removed += 1,
+                    Line::Context(_) => {}
+                }
+            }
+        }
+        if let Some(formatter) = formatter {
+            *self.formatters.entry(formatter.to_string()).or_default() += 1;
+        }
+    }
+
+    pub fn record_rejection(&mut self) {
+        self.rejections += 1;
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    format!("{count} {noun}{}", if count == 1 { "" } else { "s" })
+}
+
+impl Display for SessionStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let added = self.files.values().map(|file| file.added).sum::<usize>();
+        let removed = self.files.values().map(|file| file.removed).sum::<usize>();
+        writeln!(
+            f,
+            "{} touched, +{added} -{removed} lines",
+            plural(self.files.len(), "file")
+        )?;
+        for (path, file) in &self.files {
+            writeln!(
+                f,
+                "  {}: {}, +{} -{}",
+                path.display(),
+                plural(file.writes, "write"),
+                file.added,
+                file.removed
+            )?;
+        }
+
+        writeln!(
+            f,
+            "{} rejected by validation",
+            plural(self.rejections, "preview")
+        )?;
+
+        if self.formatters.is_empty() {
+            write!(f, "No formatters were run")
+        } else {
+            let formatters = self
+                .formatters
+                .iter()
+                .map(|(formatter, count)| format!("{formatter} ({})", plural(*count, "write")))
+                .collect::<Vec<_>>();
+            write!(f, "Formatters run: {}", formatters.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_writes_rejections_and_formatters() {
+        let mut stats = SessionStats::default();
+        assert!(stats.is_empty());
+
+        stats.record_write(
+            Path::new("src/lib.rs"),
+            "fn a() {}\n",
+            "fn a() {}\n\nfn b() {}\n",
+            Some("rustfmt"),
+        );
+        stats.record_write(
+            Path::new("src/lib.rs"),
+            "fn a() {}\n\nfn b() {}\n",
+            "fn b() {}\n",
+            Some("rustfmt"),
+        );
+        stats.record_write(Path::new("notes.txt"), "a\n", "b\n", None);
+        stats.record_rejection();
+
+        assert_eq!(
+            stats.to_string(),
+            "2 files touched, +3 -3 lines\n  \
+             notes.txt: 1 write, +1 -1\n  \
+             src/lib.rs: 2 writes, +2 -2\n\
+             1 preview rejected by validation\n\
+             Formatters run: rustfmt (2 writes)"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use super::session_store::Sessions;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    fs::{self, File, OpenOptions, TryLockError},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long to keep retrying when another process holds the session lock
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Where a [`SessionStore`](super::session_store::SessionStore) keeps its sessions
+pub trait SessionBackend<T>: Debug + Send {
+    /// Read every stored session
+    fn read(&self) -> Result<Sessions<T>>;
+
+    /// Whether sessions outlive this process
+    fn is_persistent(&self) -> bool;
+
+    /// The file sessions are stored in, if any
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Read the stored sessions, let `modify` change them, and persist the result if `modify`
+    /// returns true, as a single step with respect to anything else sharing this storage
+    fn read_modify_write(&mut self, modify: &mut dyn FnMut(&mut Sessions<T>) -> bool)
+    -> Result<()>;
+}
+
+/// Sessions kept only in memory, never written anywhere
+#[derive(Debug)]
+pub struct MemoryBackend<T>(Sessions<T>);
+
+impl<T> Default for MemoryBackend<T> {
+    fn default() -> Self {
+        Self(HashMap::new())
+    }
+}
+
+impl<T: Clone + Debug + Send> SessionBackend<T> for MemoryBackend<T> {
+    fn read(&self) -> Result<Sessions<T>> {
+        Ok(self.0.clone())
+    }
+
+    fn is_persistent(&self) -> bool {
+        false
+    }
+
+    fn read_modify_write(
+        &mut self,
+        modify: &mut dyn FnMut(&mut Sessions<T>) -> bool,
+    ) -> Result<()> {
+        modify(&mut self.0);
+        Ok(())
+    }
+}
+
+/// Sessions stored in a JSON file that can be shared between server instances.
+///
+/// Reads and read-modify-write cycles hold a lock on a sidecar `.lock` file, so concurrent
+/// instances see each other's changes and never overwrite sessions they did not modify.
+#[derive(Debug)]
+pub struct JsonFileBackend {
+    path: PathBuf,
+}
+
+impl JsonFileBackend {
+    pub fn new(path: PathBuf) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self { path })
+    }
+
+    fn load<T: for<'de> Deserialize<'de>>(&self) -> Result<Sessions<T>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        if contents.trim().is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        serde_json::from_str(&contents).or_else(|e| {
+            let backup = super::migration::backup(&self.path, "unreadable")?;
+            log::warn!(
+                "unable to parse {} ({e}), backed up to {} before it is replaced",
+                self.path.display(),
+                backup.display()
+            );
+            Ok(HashMap::new())
+        })
+    }
+
+    /// Write atomically (temp file + rename) so readers never observe a partial file
+    fn save<T: Serialize>(&self, sessions: &Sessions<T>) -> Result<()> {
+        let temp_path = sibling_path(&self.path, ".tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(sessions)?)?;
+        fs::rename(temp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+impl<T> SessionBackend<T> for JsonFileBackend
+where
+    T: Serialize + for<'de> Deserialize<'de>,
+{
+    fn read(&self) -> Result<Sessions<T>> {
+        let _lock = lock(&self.path)?;
+        self.load()
+    }
+
+    fn is_persistent(&self) -> bool {
+        true
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    fn read_modify_write(
+        &mut self,
+        modify: &mut dyn FnMut(&mut Sessions<T>) -> bool,
+    ) -> Result<()> {
+        let _lock = lock(&self.path)?;
+        let mut sessions = self.load()?;
+        if modify(&mut sessions) {
+            self.save(&sessions)?;
+        }
+        Ok(())
+    }
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut sibling = path.as_os_str().to_owned();
+    sibling.push(suffix);
+    PathBuf::from(sibling)
+}
+
+/// Acquire an exclusive lock for the session file at `path`, retrying while another process
+/// holds it. The lock is released when the returned file is dropped.
+pub(super) fn lock(path: &Path) -> Result<File> {
+    let lock_path = sibling_path(path, ".lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| anyhow!("could not open {}: {e}", lock_path.display()))?;
+
+    let start = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(file),
+            Err(TryLockError::WouldBlock) if start.elapsed() < LOCK_TIMEOUT => {
+                thread::sleep(LOCK_RETRY_INTERVAL);
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(anyhow!(
+                    "timed out waiting for another process to release {}",
+                    lock_path.display()
+                ));
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(anyhow!("could not lock {}: {e}", lock_path.display()));
+            }
+        }
+    }
+}
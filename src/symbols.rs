@@ -0,0 +1,754 @@
+use crate::{languages::LanguageCommon, workspace::DEFAULT_PAGE_SIZE};
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Range,
+};
+use tree_sitter::{Node, Query, QueryCursor, StreamingIterator, Tree};
+
+/// A named item in a file's outline, such as a function, type, field, or key
+#[derive(Debug, Clone)]
+pub struct Symbol<'tree> {
+    pub node: Node<'tree>,
+    pub label: &'static str,
+    pub name: String,
+    pub parent: Option<usize>,
+}
+
+impl Display for Symbol<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} `{}`", self.label, self.name)
+    }
+}
+
+/// Collect every symbol in the tree, in document order, using the language's symbol kinds
+pub fn outline<'tree>(
+    language: &LanguageCommon,
+    tree: &'tree Tree,
+    source: &str,
+) -> Vec<Symbol<'tree>> {
+    let mut symbols = vec![];
+    collect_symbols(language, tree.root_node(), source, None, &mut symbols);
+    symbols
+}
+
+fn collect_symbols<'tree>(
+    language: &LanguageCommon,
+    node: Node<'tree>,
+    source: &str,
+    parent: Option<usize>,
+    symbols: &mut Vec<Symbol<'tree>>,
+) {
+    for child in node.named_children(&mut node.walk()) {
+        let label = language
+            .symbol_kinds()
+            .iter()
+            .find_map(|(kind, label)| (*kind == child.kind()).then_some(*label));
+
+        match label.zip(symbol_name(child, source)) {
+            Some((label, name)) => {
+                symbols.push(Symbol {
+                    node: child,
+                    label,
+                    name,
+                    parent,
+                });
+                let index = symbols.len() - 1;
+                collect_symbols(language, child, source, Some(index), symbols);
+            }
+            None => collect_symbols(language, child, source, parent, symbols),
+        }
+    }
+}
+
+fn symbol_name(node: Node<'_>, source: &str) -> Option<String> {
+    let text = |node: Node<'_>| source[node.byte_range()].trim_matches('"').to_string();
+
+    if let Some(name) = node
+        .child_by_field_name("name")
+        .or_else(|| node.child_by_field_name("key"))
+        .or_else(|| node.child_by_field_name("property"))
+    {
+        return Some(text(name));
+    }
+
+    // Java fields are named by their first declarator, and Kotlin properties by their variable
+    if let Some(name) = node
+        .child_by_field_name("declarator")
+        .or_else(|| named_child(node, "variable_declaration"))
+        .and_then(|declarator| {
+            declarator
+                .child_by_field_name("name")
+                .or_else(|| named_child(declarator, "identifier"))
+        })
+    {
+        return Some(text(name));
+    }
+
+    if let Some(ty) = node.child_by_field_name("type") {
+        return Some(match node.child_by_field_name("trait") {
+            Some(trait_node) => format!("{} for {}", text(trait_node), text(ty)),
+            None => text(ty),
+        });
+    }
+
+    node.named_children(&mut node.walk())
+        .find(|child| child.kind().ends_with("key") || child.kind() == "identifier")
+        .map(text)
+}
+
+fn named_child<'tree>(node: Node<'tree>, kind: &str) -> Option<Node<'tree>> {
+    node.named_children(&mut node.walk())
+        .find(|child| child.kind() == kind)
+}
+
+/// Labels that name the same kind of symbol in different languages
+const FUNCTION_LABELS: &[&str] = &["def", "fn", "fun", "function", "method"];
+
+/// Whether `symbol` is of the kind `kind` labels, counting the labels different languages give
+/// functions as one kind
+pub fn is_kind(symbol: &Symbol<'_>, kind: &str) -> bool {
+    symbol.label == kind
+        || (FUNCTION_LABELS.contains(&kind) && FUNCTION_LABELS.contains(&symbol.label))
+}
+
+/// The symbol in `outline` that `query` names
+///
+/// A name like `display_name` can be qualified by the symbols it is nested in, like
+/// `User::display_name` or `Parser.parse`, and preceded by the kind of symbol, like
+/// `fn display_name` or `struct User`. A name that isn't qualified must be unambiguous.
+pub fn find<'tree>(outline: &[Symbol<'tree>], query: &str) -> Result<Node<'tree>, String> {
+    let query = query.trim();
+    let (kind, name) = match query.split_once(' ') {
+        Some((kind, name))
+            if FUNCTION_LABELS.contains(&kind)
+                || outline.iter().any(|symbol| symbol.label == kind) =>
+        {
+            (Some(kind), name.trim())
+        }
+        _ => (None, query),
+    };
+    let kind_matches = |symbol: &Symbol<'_>| kind.is_none_or(|kind| is_kind(symbol, kind));
+    let qualified = |index: usize, separator: &str| {
+        let mut names = vec![outline[index].name.as_str()];
+        let mut parent = outline[index].parent;
+        while let Some(index) = parent {
+            let name = &outline[index].name;
+            names.push(name.split('<').next().unwrap_or(name).trim());
+            parent = outline[index].parent;
+        }
+        names.reverse();
+        names.join(separator)
+    };
+
+    let candidates = (0..outline.len())
+        .filter(|index| kind_matches(&outline[*index]))
+        .collect::<Vec<_>>();
+    if let Some(index) = candidates
+        .iter()
+        .find(|index| qualified(**index, "::") == name || qualified(**index, ".") == name)
+    {
+        return Ok(outline[*index].node);
+    }
+    let matching = candidates
+        .iter()
+        .filter(|index| outline[**index].name == name)
+        .collect::<Vec<_>>();
+    match matching.as_slice() {
+        [index] => Ok(outline[**index].node),
+        [] => Err(format!(
+            "Nothing named {query} was found. Symbols{}: {}",
+            kind.map(|kind| format!(" of kind {kind}"))
+                .unwrap_or_default(),
+            candidates
+                .iter()
+                .map(|index| qualified(*index, "."))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        _ => Err(format!(
+            "{query} is ambiguous. Use one of: {}",
+            matching
+                .iter()
+                .map(|index| format!("{} {}", outline[**index].label, qualified(**index, ".")))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// A function found by a language's calls query
+#[derive(Debug, Clone)]
+pub struct FunctionDefinition<'tree> {
+    pub node: Node<'tree>,
+    pub name: &'tree str,
+    /// The impl block's type or class that the function is a method of, without generic
+    /// parameters
+    pub owner: Option<&'tree str>,
+    /// The name qualified by its owner, as `Type::method` in Rust and `Class.method` elsewhere
+    pub qualified: String,
+}
+
+/// Every function that a query captures as a `@definition` with a `@definition.name`, in
+/// document order
+pub fn function_definitions<'tree>(
+    query: &Query,
+    tree: &'tree Tree,
+    source: &'tree str,
+) -> Vec<FunctionDefinition<'tree>> {
+    let (Some(definition), Some(definition_name)) = (
+        query.capture_index_for_name("definition"),
+        query.capture_index_for_name("definition.name"),
+    ) else {
+        return vec![];
+    };
+
+    let mut definitions = vec![];
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+    while let Some(query_match) = matches.next() {
+        let node = |index| {
+            query_match
+                .captures
+                .iter()
+                .find(|capture| capture.index == index)
+                .map(|capture| capture.node)
+        };
+        let Some((node, name)) = node(definition).zip(node(definition_name)) else {
+            continue;
+        };
+        let name = &source[name.byte_range()];
+        let owner = owner(node, source);
+        definitions.push(FunctionDefinition {
+            node,
+            name,
+            owner,
+            qualified: match owner {
+                Some(owner) if node.language().name() == Some("rust") => {
+                    format!("{owner}::{name}")
+                }
+                Some(owner) => format!("{owner}.{name}"),
+                None => name.to_string(),
+            },
+        });
+    }
+    definitions.sort_by_key(|definition| definition.node.start_byte());
+    definitions
+}
+
+/// The impl block's type or class that a function is defined in, without generic parameters
+fn owner<'tree>(node: Node<'tree>, source: &'tree str) -> Option<&'tree str> {
+    let ancestor = std::iter::successors(node.parent(), Node::parent).find(|ancestor| {
+        matches!(
+            ancestor.kind(),
+            "impl_item" | "class_definition" | "class_declaration" | "class"
+        )
+    })?;
+    let owner = ancestor
+        .child_by_field_name("type")
+        .or_else(|| ancestor.child_by_field_name("name"))?;
+    let text = &source[owner.byte_range()];
+    Some(text.split('<').next().unwrap_or(text).trim())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeKind {
+    fn preposition(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "to",
+            ChangeKind::Removed => "from",
+            ChangeKind::Modified => "in",
+        }
+    }
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Modified => "modified",
+        })
+    }
+}
+
+/// A symbol that an edit added, removed, or modified
+#[derive(Debug, Clone)]
+pub struct SymbolChange {
+    pub kind: ChangeKind,
+    pub label: &'static str,
+    pub name: String,
+    /// Byte range of the symbol in the new source, if it still exists
+    pub new_range: Option<Range<usize>>,
+    description: String,
+}
+
+impl Display for SymbolChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.description)
+    }
+}
+
+impl SymbolChange {
+    fn new(kind: ChangeKind, symbol: &Symbol<'_>, symbols: &[Symbol<'_>]) -> Self {
+        let description = match symbol.parent.map(|parent| &symbols[parent]) {
+            Some(parent) => format!("{kind} {symbol} {} {parent}", kind.preposition()),
+            None => format!("{kind} {symbol}"),
+        };
+
+        Self {
+            kind,
+            label: symbol.label,
+            name: symbol.name.clone(),
+            new_range: (kind != ChangeKind::Removed).then(|| symbol.node.byte_range()),
+            description,
+        }
+    }
+}
+
+struct KeyedSymbol<'a, 'tree> {
+    key: Vec<(&'static str, &'a str)>,
+    symbol: &'a Symbol<'tree>,
+    own_text: String,
+}
+
+fn keyed<'a, 'tree>(symbols: &'a [Symbol<'tree>], source: &str) -> Vec<KeyedSymbol<'a, 'tree>> {
+    let mut keyed: Vec<KeyedSymbol<'a, 'tree>> = Vec::with_capacity(symbols.len());
+    for (index, symbol) in symbols.iter().enumerate() {
+        let mut key = symbol
+            .parent
+            .map(|parent| keyed[parent].key.clone())
+            .unwrap_or_default();
+        key.push((symbol.label, &symbol.name));
+
+        let children = symbols
+            .iter()
+            .filter(|child| child.parent == Some(index))
+            .map(|child| child.node.byte_range());
+        let mut own_text = String::new();
+        let mut cursor = symbol.node.start_byte();
+        for range in children {
+            own_text.push_str(&source[cursor..range.start]);
+            cursor = range.end;
+        }
+        own_text.push_str(&source[cursor..symbol.node.end_byte()]);
+        own_text.retain(|c| !c.is_whitespace() && c != ',');
+
+        keyed.push(KeyedSymbol {
+            key,
+            symbol,
+            own_text,
+        });
+    }
+    keyed
+}
+
+/// Compute the structural difference between two versions of a file
+pub fn diff_symbols(
+    language: &LanguageCommon,
+    old_tree: &Tree,
+    old_source: &str,
+    new_tree: &Tree,
+    new_source: &str,
+) -> Vec<SymbolChange> {
+    let old_symbols = outline(language, old_tree, old_source);
+    let new_symbols = outline(language, new_tree, new_source);
+    let old_keyed = keyed(&old_symbols, old_source);
+    let new_keyed = keyed(&new_symbols, new_source);
+
+    let mut matched_old = vec![false; old_keyed.len()];
+    let mut counterparts = vec![None; new_keyed.len()];
+
+    // prefer unchanged counterparts so that duplicated names pair up with their originals
+    for unchanged_only in [true, false] {
+        for (index, new) in new_keyed.iter().enumerate() {
+            if counterparts[index].is_some() {
+                continue;
+            }
+            counterparts[index] = old_keyed.iter().enumerate().position(|(old_index, old)| {
+                !matched_old[old_index]
+                    && old.key == new.key
+                    && (!unchanged_only || old.own_text == new.own_text)
+            });
+            if let Some(old_index) = counterparts[index] {
+                matched_old[old_index] = true;
+            }
+        }
+    }
+
+    let mut added = vec![false; new_keyed.len()];
+    let mut changes = vec![];
+
+    for (index, new) in new_keyed.iter().enumerate() {
+        match counterparts[index] {
+            Some(old_index) => {
+                if old_keyed[old_index].own_text != new.own_text {
+                    changes.push(SymbolChange::new(
+                        ChangeKind::Modified,
+                        new.symbol,
+                        &new_symbols,
+                    ));
+                }
+            }
+            None => {
+                added[index] = true;
+                if !new.symbol.parent.is_some_and(|parent| added[parent]) {
+                    changes.push(SymbolChange::new(
+                        ChangeKind::Added,
+                        new.symbol,
+                        &new_symbols,
+                    ));
+                }
+            }
+        }
+    }
+
+    let removed = matched_old
+        .iter()
+        .map(|matched| !matched)
+        .collect::<Vec<_>>();
+    for (index, old) in old_keyed.iter().enumerate() {
+        if removed[index] && !old.symbol.parent.is_some_and(|parent| removed[parent]) {
+            changes.push(SymbolChange::new(
+                ChangeKind::Removed,
+                old.symbol,
+                &old_symbols,
+            ));
+        }
+    }
+
+    changes
+}
+
+/// Summarize symbol changes as a single line, such as
+/// "modified fn `display_name`; removed field `phone_number` from struct `User`"
+pub fn summarize(changes: &[SymbolChange]) -> Option<String> {
+    if changes.is_empty() {
+        None
+    } else {
+        Some(
+            changes
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+}
+
+/// Find the places in `source` that still refer to modified or removed symbols, grouped by
+/// changed symbol. Each reference is described by its enclosing symbol and line. Like a page of
+/// workspace results, at most [`DEFAULT_PAGE_SIZE`] references are listed, followed by a count
+/// of the rest.
+pub fn impact_report(
+    language: &LanguageCommon,
+    changes: &[SymbolChange],
+    tree: &Tree,
+    source: &str,
+) -> String {
+    let symbols = outline(language, tree, source);
+    let mut identifiers = vec![];
+    collect_identifiers(tree.root_node(), &mut identifiers);
+
+    let mut report = String::new();
+    let mut remaining = DEFAULT_PAGE_SIZE;
+    let mut omitted = 0;
+    for change in changes.iter().filter(|c| c.kind != ChangeKind::Added) {
+        let references = identifiers
+            .iter()
+            .filter(|node| source[node.byte_range()] == change.name)
+            .filter(|node| {
+                change
+                    .new_range
+                    .as_ref()
+                    .is_none_or(|range| !range.contains(&node.start_byte()))
+            })
+            .map(|node| {
+                let line = node.start_position().row + 1;
+                match enclosing_symbol(&symbols, node.start_byte()) {
+                    Some(symbol) => format!("{symbol} (line {line})"),
+                    None => format!("top level (line {line})"),
+                }
+            })
+            .collect::<Vec<_>>();
+        let listed = references.len().min(remaining);
+        remaining -= listed;
+        omitted += references.len() - listed;
+
+        if listed > 0 {
+            report.push_str(&format!(
+                "• {} `{}` ({}) is referenced by {}\n",
+                change.label,
+                change.name,
+                change.kind,
+                references[..listed].join(", ")
+            ));
+        }
+    }
+    if omitted > 0 {
+        report.push_str(&format!(
+            "…and {omitted} more reference{} to the changed symbols, not listed\n",
+            if omitted == 1 { "" } else { "s" }
+        ));
+    }
+
+    if report.is_empty() {
+        "No other references to the changed symbols were found in this file.\n".into()
+    } else {
+        report
+    }
+}
+
+fn collect_identifiers<'tree>(node: Node<'tree>, identifiers: &mut Vec<Node<'tree>>) {
+    if node.child_count() == 0 {
+        if node.is_named() && node.kind().ends_with("identifier") {
+            identifiers.push(node);
+        }
+        return;
+    }
+
+    for child in node.children(&mut node.walk()) {
+        collect_identifiers(child, identifiers);
+    }
+}
+
+/// The index of the innermost symbol containing all of `range`
+pub fn innermost(symbols: &[Symbol<'_>], range: Range<usize>) -> Option<usize> {
+    symbols
+        .iter()
+        .enumerate()
+        .filter(|(_, symbol)| {
+            let node = symbol.node.byte_range();
+            node.start <= range.start && range.end <= node.end
+        })
+        .min_by_key(|(_, symbol)| symbol.node.byte_range().len())
+        .map(|(index, _)| index)
+}
+
+/// A symbol named with the symbol it's in, like fn `greeting` in impl `User`
+pub fn describe(symbols: &[Symbol<'_>], index: usize) -> String {
+    let symbol = &symbols[index];
+    match symbol.parent.map(|parent| &symbols[parent]) {
+        Some(parent) => format!("{symbol} in {parent}"),
+        None => symbol.to_string(),
+    }
+}
+
+fn enclosing_symbol<'a, 'tree>(
+    symbols: &'a [Symbol<'tree>],
+    byte: usize,
+) -> Option<&'a Symbol<'tree>> {
+    symbols
+        .iter()
+        .filter(|symbol| symbol.node.byte_range().contains(&byte))
+        .min_by_key(|symbol| symbol.node.byte_range().len())
+}
+
+#[cfg(all(
+    test,
+    any(
+        feature = "rust",
+        feature = "python",
+        feature = "json",
+        feature = "java",
+        feature = "kotlin"
+    )
+))]
+mod tests {
+    use super::*;
+    use crate::languages::{LanguageName, LanguageRegistry};
+
+    #[cfg(any(feature = "rust", feature = "python", feature = "json"))]
+    fn summary(language: LanguageName, old: &str, new: &str) -> Option<String> {
+        let registry = LanguageRegistry::new().unwrap();
+        let language = registry.get_language(language);
+        let mut parser = language.tree_sitter_parser().unwrap();
+        let old_tree = parser.parse(old, None).unwrap();
+        let new_tree = parser.parse(new, None).unwrap();
+        summarize(&diff_symbols(language, &old_tree, old, &new_tree, new))
+    }
+
+    #[test]
+    #[cfg(feature = "rust")]
+    fn rust_struct_and_impl_changes() {
+        let old = r#"
+struct User {
+    name: String,
+    phone_number: String,
+}
+
+impl User {
+    fn display_name(&self) -> &str {
+        &self.name
+    }
+}
+"#;
+        let new = r#"
+struct User {
+    name: String,
+}
+
+impl User {
+    fn display_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_contact_summary(&self) -> String {
+        self.name.clone()
+    }
+}
+"#;
+        assert_eq!(
+            summary(LanguageName::Rust, old, new).as_deref(),
+            Some(
+                "modified fn `display_name` in impl `User`; \
+                 added fn `get_contact_summary` to impl `User`; \
+                 removed field `phone_number` from struct `User`"
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rust")]
+    fn whitespace_only_changes_are_ignored() {
+        let old = "fn main() { println!(\"hi\"); }";
+        let new = "fn main() {\n    println!(\"hi\");\n}\n";
+        assert_eq!(summary(LanguageName::Rust, old, new), None);
+    }
+
+    #[test]
+    #[cfg(feature = "python")]
+    fn removed_children_are_reported_with_their_parent() {
+        let old = "class A:\n    def a(self):\n        pass\n\ndef b():\n    pass\n";
+        let new = "def b():\n    return 1\n";
+        assert_eq!(
+            summary(LanguageName::Python, old, new).as_deref(),
+            Some("modified function `b`; removed class `A`")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rust")]
+    fn impact_lists_remaining_references() {
+        let source = r#"
+fn display_name(user: &User) -> String {
+    user.name.clone()
+}
+
+fn render(user: &User) {
+    println!("{}", display_name(user));
+}
+"#;
+        let registry = LanguageRegistry::new().unwrap();
+        let language = registry.get_language(LanguageName::Rust);
+        let tree = language
+            .tree_sitter_parser()
+            .unwrap()
+            .parse(source, None)
+            .unwrap();
+        let changes = diff_symbols(language, &tree, source, &tree, source);
+        assert!(changes.is_empty());
+
+        let display_name = outline(language, &tree, source).remove(0);
+        let changes = [SymbolChange::new(ChangeKind::Modified, &display_name, &[])];
+        assert_eq!(
+            impact_report(language, &changes, &tree, source),
+            "• fn `display_name` (modified) is referenced by fn `render` (line 7)\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rust")]
+    fn impact_lists_at_most_a_page_of_references() {
+        let calls = "    display_name(user);\n".repeat(DEFAULT_PAGE_SIZE + 2);
+        let source =
+            format!("fn display_name(user: &User) {{}}\n\nfn render(user: &User) {{\n{calls}}}\n");
+        let registry = LanguageRegistry::new().unwrap();
+        let language = registry.get_language(LanguageName::Rust);
+        let tree = language
+            .tree_sitter_parser()
+            .unwrap()
+            .parse(&source, None)
+            .unwrap();
+
+        let display_name = outline(language, &tree, &source).remove(0);
+        let changes = [SymbolChange::new(ChangeKind::Modified, &display_name, &[])];
+        let report = impact_report(language, &changes, &tree, &source);
+        assert_eq!(report.matches("fn `render`").count(), DEFAULT_PAGE_SIZE);
+        assert!(report.ends_with("…and 2 more references to the changed symbols, not listed\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json_keys() {
+        let old = r#"{"name": "x", "scripts": {"test": "jest"}}"#;
+        let new = r#"{"name": "x", "scripts": {"test": "vitest", "lint": "biome"}}"#;
+        assert_eq!(
+            summary(LanguageName::Json, old, new).as_deref(),
+            Some("modified key `test` in key `scripts`; added key `lint` to key `scripts`")
+        );
+    }
+
+    #[cfg(any(feature = "java", feature = "kotlin"))]
+    fn names(language: LanguageName, source: &str) -> Vec<String> {
+        let registry = LanguageRegistry::new().unwrap();
+        let language = registry.get_language(language);
+        let tree = language
+            .tree_sitter_parser()
+            .unwrap()
+            .parse(source, None)
+            .unwrap();
+        let outline = outline(language, &tree, source);
+        outline
+            .iter()
+            .enumerate()
+            .map(|(index, _)| describe(&outline, index))
+            .collect()
+    }
+
+    #[test]
+    #[cfg(feature = "java")]
+    fn java_outline() {
+        assert_eq!(
+            names(
+                LanguageName::Java,
+                "class User {\n    private String name, email;\n\n    User(String name) {}\n\n    \
+                 String displayName() { return name; }\n\n    enum Role { ADMIN }\n}\n",
+            ),
+            [
+                "class `User`",
+                "field `name` in class `User`",
+                "constructor `User` in class `User`",
+                "method `displayName` in class `User`",
+                "enum `Role` in class `User`",
+                "constant `ADMIN` in enum `Role`",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "kotlin")]
+    fn kotlin_outline() {
+        assert_eq!(
+            names(
+                LanguageName::Kotlin,
+                "class User(val name: String) {\n    var email: String? = null\n\n    \
+                 fun displayName() = name\n\n    companion object {\n        \
+                 fun guest() = User(\"guest\")\n    }\n}\n\nenum class Role { ADMIN }\n",
+            ),
+            [
+                "class `User`",
+                "property `email` in class `User`",
+                "fun `displayName` in class `User`",
+                "fun `guest` in class `User`",
+                "class `Role`",
+                "constant `ADMIN` in class `Role`",
+            ]
+        );
+    }
+}
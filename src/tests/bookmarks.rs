@@ -0,0 +1,199 @@
+use super::workspace;
+use crate::{
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+    tools::{FormatFiles, ListStagedEdits, PersistEdit, PreviewEdit, SetBookmark, UseBookmark},
+};
+use mcplease::traits::Tool;
+use std::fs;
+
+#[test]
+fn bookmarks_follow_their_text_through_persisted_edits() {
+    let dir = workspace("bookmarks");
+    let file = dir.join("lib.rs");
+    fs::write(
+        &file,
+        "pub fn total(items: &[u32]) -> u32 {\n    items.iter().sum()\n}\n",
+    )
+    .unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("bookmarks-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let response = SetBookmark {
+        name: "total".into(),
+        file_path: "lib.rs".into(),
+        anchor: "pub fn total".into(),
+        allow_large: false,
+    }
+    .execute(&mut state)
+    .unwrap();
+    assert_eq!(
+        response,
+        "Bookmarked `total`: function item on lines 1-3 of lib.rs"
+    );
+
+    // an edit above the bookmark moves it down
+    PreviewEdit {
+        file_path: "lib.rs".into(),
+        language: None,
+        selector: Selector::anchor(Operation::InsertBefore, "pub fn total"),
+        anchor_ref: None,
+        content: Some(
+            "pub fn first(items: &[u32]) -> Option<u32> {\n    items.first().copied()\n}\n\n"
+                .into(),
+        ),
+        report_impact: false,
+        allow_dirty: false,
+        allow_large: false,
+        expected_version: None,
+        edits: vec![],
+    }
+    .execute(&mut state)
+    .unwrap();
+    PersistEdit {
+        confirm: false,
+        hunks: vec![],
+        on_conflict: None,
+        output_path: None,
+    }
+    .execute(&mut state)
+    .unwrap();
+
+    let use_bookmark = |operation, content: &str| UseBookmark {
+        name: "total".into(),
+        operation,
+        content: Some(content.into()),
+        allow_large: false,
+    };
+    use_bookmark(Operation::InsertBefore, "/// The sum of the items")
+        .execute(&mut state)
+        .unwrap();
+    PersistEdit {
+        confirm: false,
+        hunks: vec![],
+        on_conflict: None,
+        output_path: None,
+    }
+    .execute(&mut state)
+    .unwrap();
+    use_bookmark(
+        Operation::InsertAfter,
+        "\npub fn count(items: &[u32]) -> usize {\n    items.len()\n}",
+    )
+    .execute(&mut state)
+    .unwrap();
+    PersistEdit {
+        confirm: false,
+        hunks: vec![],
+        on_conflict: None,
+        output_path: None,
+    }
+    .execute(&mut state)
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "pub fn first(items: &[u32]) -> Option<u32> {\n    items.first().copied()\n}\n\n\
+         /// The sum of the items\n\
+         pub fn total(items: &[u32]) -> u32 {\n    items.iter().sum()\n}\n\n\
+         pub fn count(items: &[u32]) -> usize {\n    items.len()\n}\n"
+    );
+
+    // a change made some other way makes the bookmark unusable until it's set again
+    fs::write(&file, "pub fn total() -> u32 {\n    0\n}\n").unwrap();
+    let error = use_bookmark(Operation::Replace, "")
+        .execute(&mut state)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("Set it again with set_bookmark"), "{error}");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn staged_edits_at_a_position_follow_other_writes_to_their_file() {
+    let dir = workspace("remap");
+    let file = dir.join("lib.rs");
+    fs::write(
+        &file,
+        "pub fn add(a:u32,b:u32)->u32{a+b}\n\npub fn one() -> u32 {\n    1\n}\n",
+    )
+    .unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("remap-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    SetBookmark {
+        name: "one".into(),
+        file_path: "lib.rs".into(),
+        anchor: "pub fn one".into(),
+        allow_large: false,
+    }
+    .execute(&mut state)
+    .unwrap();
+    UseBookmark {
+        name: "one".into(),
+        operation: Operation::InsertAfter,
+        content: Some("\npub fn two() -> u32 {\n    2\n}".into()),
+        allow_large: false,
+    }
+    .execute(&mut state)
+    .unwrap();
+
+    // formatting the line above moves the staged insertion's position down with its text
+    FormatFiles {
+        path: None,
+        glob: Some("*.rs".into()),
+        apply: true,
+        confirm: false,
+        timeout_ms: None,
+        continuation: None,
+    }
+    .execute(&mut state)
+    .unwrap();
+    PersistEdit {
+        confirm: false,
+        hunks: vec![],
+        on_conflict: None,
+        output_path: None,
+    }
+    .execute(&mut state)
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "pub fn add(a: u32, b: u32) -> u32 {\n    a + b\n}\n\n\
+         pub fn one() -> u32 {\n    1\n}\n\n\
+         pub fn two() -> u32 {\n    2\n}\n"
+    );
+
+    // a staged replacement of text that a write removes is discarded
+    UseBookmark {
+        name: "one".into(),
+        operation: Operation::Replace,
+        content: Some("pub fn one() -> u64 {\n    1\n}".into()),
+        allow_large: false,
+    }
+    .execute(&mut state)
+    .unwrap();
+    let original = fs::read_to_string(&file).unwrap();
+    let edited = original.replace("pub fn one() -> u32 {\n    1\n}\n\n", "");
+    fs::write(&file, &edited).unwrap();
+    state
+        .record_write(&file, Some(&original), &edited, None, None)
+        .unwrap();
+    assert_eq!(
+        ListStagedEdits {
+            workspace_edit: false,
+            html: false,
+        }
+        .execute(&mut state)
+        .unwrap(),
+        "No edits are staged"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,161 @@
+use super::workspace;
+use crate::{guardrails::Limits, state::SemanticEditTools, tools::RunCodemod};
+use mcplease::traits::Tool;
+use std::fs;
+
+#[test]
+fn codemods_preview_then_apply_across_the_workspace() {
+    let dir = workspace("codemod");
+    fs::create_dir_all(dir.join(".semantic-edit/codemods")).unwrap();
+    fs::create_dir_all(dir.join("app")).unwrap();
+    fs::write(
+        dir.join(".semantic-edit/codemods/print_to_log.toml"),
+        r#"description = "Replace print calls with logging"
+language = "python"
+
+[[steps]]
+query = '((call function: (identifier) @function) (#eq? @function "print"))'
+replace = "log.info"
+"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join(".semantic-edit/codemods/break.json"),
+        r#"{"language": "python", "steps": [{"query": "(return_statement) @r", "replace": "return ("}]}"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("app/main.py"),
+        "def main():\n    print(\"hi\")\n    return print\n",
+    )
+    .unwrap();
+    fs::write(dir.join("app/util.py"), "def helper():\n    print(1)\n").unwrap();
+    fs::write(dir.join("app/notes.rs"), "fn print() {}\n").unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("codemod-test")
+        .with_limits(Limits {
+            files_per_batch: Some(1),
+            ..Limits::default()
+        })
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let mut run = |name: &str, apply: bool, confirm: bool| {
+        RunCodemod {
+            name: name.into(),
+            path: None,
+            apply,
+            confirm,
+            timeout_ms: None,
+            continuation: None,
+        }
+        .execute(&mut state)
+        .map_err(|error| error.to_string())
+    };
+
+    let preview = run("print_to_log", false, false).unwrap();
+    assert!(preview.starts_with(
+        "The print_to_log codemod would make 2 replacements in 2 files.\n\
+         2 of 2 files succeeded: 2 with changes."
+    ));
+    assert!(preview.contains(
+        "=== app/main.py ===\n✅ 1 replacement\n def main():\n-    print(\"hi\")\n+    log.info(\"hi\")"
+    ));
+    assert!(preview.contains("=== app/util.py ==="));
+    assert!(preview.contains("Nothing was written"));
+    assert!(
+        fs::read_to_string(dir.join("app/util.py"))
+            .unwrap()
+            .contains("print(1)")
+    );
+
+    let broken = run("break", true, false).unwrap();
+    assert!(
+        broken.contains("1 of 2 files succeeded: 0 with changes, 1 with no change. 1 was skipped.")
+    );
+    assert!(broken.contains("=== app/main.py ===\n❌ Skipped: the result has syntax errors"));
+    assert!(
+        fs::read_to_string(dir.join("app/main.py"))
+            .unwrap()
+            .contains("return print")
+    );
+
+    let error = run("missing", false, false).unwrap_err();
+    assert!(error.contains("Available codemods: break, print_to_log"));
+
+    let held = run("print_to_log", true, false).unwrap();
+    assert!(held.contains(
+        "Nothing was written because it changes 2 files, and SEMANTIC_EDIT_MAX_BATCH_FILES allows 1"
+    ));
+    assert!(
+        fs::read_to_string(dir.join("app/util.py"))
+            .unwrap()
+            .contains("print(1)")
+    );
+
+    run("print_to_log", true, true).unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.join("app/main.py")).unwrap(),
+        "def main():\n    log.info(\"hi\")\n    return print\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("app/notes.rs")).unwrap(),
+        "fn print() {}\n"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn codemods_resume_after_the_time_limit() {
+    let dir = workspace("codemod-time-limit");
+    fs::create_dir_all(dir.join(".semantic-edit/codemods")).unwrap();
+    fs::write(
+        dir.join(".semantic-edit/codemods/print_to_log.toml"),
+        r#"language = "python"
+
+[[steps]]
+query = '((call function: (identifier) @function) (#eq? @function "print"))'
+replace = "log.info"
+"#,
+    )
+    .unwrap();
+    fs::write(dir.join("a.py"), "print(1)\n").unwrap();
+    fs::write(dir.join("b.py"), "print(2)\n").unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("codemod-time-limit-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let mut run = |continuation: Option<&str>| {
+        RunCodemod {
+            name: "print_to_log".into(),
+            path: None,
+            apply: false,
+            confirm: false,
+            timeout_ms: Some(0),
+            continuation: continuation.map(Into::into),
+        }
+        .execute(&mut state)
+        .unwrap()
+    };
+
+    // a walk always gets through one file before it checks the time limit
+    let first = run(None);
+    assert!(first.contains("=== a.py ==="), "{first}");
+    assert!(!first.contains("=== b.py ==="), "{first}");
+    assert!(
+        first.contains("Run the codemod again with the same name and continuation: \"0:b.py\"")
+    );
+
+    let rest = run(Some("0:b.py"));
+    assert!(rest.contains("=== b.py ==="), "{rest}");
+    assert!(
+        !rest.contains("=== a.py ===") && !rest.contains("time limit"),
+        "{rest}"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
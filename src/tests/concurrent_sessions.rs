@@ -0,0 +1,72 @@
+use super::workspace;
+use crate::{
+    state::SemanticEditTools,
+    tools::{PersistEdit, PreviewEdit},
+};
+use mcplease::traits::Tool;
+use serde_json::json;
+use std::{
+    fs,
+    sync::{Arc, mpsc},
+    thread,
+};
+
+#[test]
+fn sessions_served_concurrently_keep_their_own_staged_edits() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SemanticEditTools>();
+
+    let dir = workspace("concurrent-sessions");
+    fs::write(dir.join("lib.rs"), "fn value() -> u8 {\n    0\n}\n").unwrap();
+
+    let state = SemanticEditTools::new(None).unwrap();
+
+    let clients = (1..=8u8)
+        .map(|n| {
+            let mut state = state
+                .session(format!("client-{n}"))
+                .with_working_directory(dir.clone(), None)
+                .unwrap();
+            thread::spawn(move || {
+                let content = format!("fn value() -> u8 {{\n    {n}\n}}");
+                let preview: PreviewEdit = serde_json::from_value(json!({
+                    "file_path": "lib.rs",
+                    "anchor": "fn value",
+                    "operation": "replace",
+                    "content": content,
+                }))
+                .unwrap();
+                preview.execute(&mut state).unwrap();
+                thread::yield_now();
+
+                let staged = state.get_staged_operation(None).unwrap().unwrap();
+                assert_eq!(staged.content, content);
+
+                let (tx, rx) = mpsc::channel();
+                state.set_commit_fn(Some(Arc::new(move |_, output| {
+                    tx.send(output).unwrap();
+                })));
+                PersistEdit {
+                    confirm: false,
+                    hunks: vec![],
+                    on_conflict: None,
+                    output_path: None,
+                }
+                .execute(&mut state)
+                .unwrap();
+                assert!(rx.recv().unwrap().contains(&format!("    {n}\n")));
+                assert!(state.get_staged_operation(None).unwrap().is_none());
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for client in clients {
+        client.join().unwrap();
+    }
+
+    // the original handle's session was never touched
+    assert!(state.get_staged_operation(None).unwrap().is_none());
+    assert_eq!(state.get_context(None).unwrap(), None);
+
+    fs::remove_dir_all(dir).unwrap();
+}
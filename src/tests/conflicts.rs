@@ -0,0 +1,104 @@
+use super::workspace;
+use crate::{
+    conflicts::OnConflict,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+    tools::{PersistEdit, PreviewEdit},
+};
+use mcplease::traits::Tool;
+use std::fs;
+
+const ORIGINAL: &str = "pub fn one() -> u32 {\n    1\n}\n\npub fn two() -> u32 {\n    2\n}\n\n\
+                        pub fn three() -> u32 {\n    3\n}\n";
+
+#[test]
+fn edits_to_files_changed_since_staging_are_resolved_on_request() {
+    let dir = workspace("conflicts");
+    let file = dir.join("lib.rs");
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("conflicts-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let stage = |state: &mut SemanticEditTools| {
+        fs::write(&file, ORIGINAL).unwrap();
+        PreviewEdit {
+            file_path: "lib.rs".into(),
+            language: None,
+            selector: Selector::anchor(Operation::Replace, "pub fn one() -> u32 {"),
+            anchor_ref: None,
+            content: Some("pub fn one() -> u32 {\n    10\n}".into()),
+            report_impact: false,
+            allow_dirty: false,
+            allow_large: false,
+            expected_version: None,
+            edits: vec![],
+        }
+        .execute(state)
+        .unwrap();
+    };
+    let persist = |state: &mut SemanticEditTools, on_conflict: Option<OnConflict>| {
+        PersistEdit {
+            confirm: false,
+            hunks: vec![],
+            on_conflict,
+            output_path: None,
+        }
+        .execute(state)
+        .map_err(|error| error.to_string())
+    };
+
+    stage(&mut state);
+    fs::write(&file, ORIGINAL.replace("    3\n", "    30\n")).unwrap();
+    let conflict = persist(&mut state, None).unwrap_err();
+    assert!(
+        conflict.contains("lib.rs changed after this edit was staged"),
+        "{conflict}"
+    );
+    assert!(conflict.contains("- `merge`"), "{conflict}");
+    assert!(conflict.contains("-    3\n+    30"), "{conflict}");
+    let merged = persist(&mut state, Some(OnConflict::Merge)).unwrap();
+    assert!(merged.starts_with("Merged the staged edit"), "{merged}");
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        ORIGINAL
+            .replace("    1\n", "    10\n")
+            .replace("    3\n", "    30\n")
+    );
+
+    stage(&mut state);
+    fs::write(&file, ORIGINAL.replace("    1\n", "    100\n")).unwrap();
+    let overlapping = persist(&mut state, Some(OnConflict::Merge)).unwrap_err();
+    assert!(overlapping.contains("can't be merged"), "{overlapping}");
+    assert!(overlapping.contains("<<<<<<<"), "{overlapping}");
+    persist(&mut state, Some(OnConflict::Overwrite)).unwrap();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        ORIGINAL.replace("    1\n", "    10\n")
+    );
+
+    stage(&mut state);
+    fs::write(&file, format!("use std::fmt;\n\n{ORIGINAL}")).unwrap();
+    let reresolved = persist(&mut state, Some(OnConflict::Reresolve)).unwrap();
+    assert!(
+        reresolved.contains("its anchor was found again"),
+        "{reresolved}"
+    );
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        format!(
+            "use std::fmt;\n\n{}",
+            ORIGINAL.replace("    1\n", "    10\n")
+        )
+    );
+
+    stage(&mut state);
+    persist(&mut state, None).unwrap();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        ORIGINAL.replace("    1\n", "    10\n")
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,57 @@
+use super::workspace;
+use crate::{
+    state::SemanticEditTools,
+    tools::{EndSessionSummary, FormatFiles},
+};
+use mcplease::traits::Tool;
+use std::fs;
+
+#[test]
+fn formatting_is_only_written_with_apply() {
+    let dir = workspace("format");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("src/lib.rs"),
+        "pub fn add(a:u32,b:u32)->u32{a+b}\n",
+    )
+    .unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("format-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let format = |apply| FormatFiles {
+        path: None,
+        glob: Some("**/*.rs".into()),
+        apply,
+        confirm: false,
+        timeout_ms: None,
+        continuation: None,
+    };
+
+    let preview = format(false).execute(&mut state).unwrap();
+    assert!(preview.contains("+    a + b\n"), "{preview}");
+    assert_eq!(
+        fs::read_to_string(dir.join("src/lib.rs")).unwrap(),
+        "pub fn add(a:u32,b:u32)->u32{a+b}\n"
+    );
+
+    format(true).execute(&mut state).unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.join("src/lib.rs")).unwrap(),
+        "pub fn add(a: u32, b: u32) -> u32 {\n    a + b\n}\n"
+    );
+    assert_eq!(
+        format(false).execute(&mut state).unwrap(),
+        "The 1 file with a formatter is already formatted"
+    );
+
+    let summary = EndSessionSummary {}.execute(&mut state).unwrap();
+    assert!(
+        summary.ends_with("Formatters run: rustfmt (1 write)"),
+        "{summary}"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
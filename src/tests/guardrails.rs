@@ -0,0 +1,85 @@
+use super::workspace;
+use crate::{
+    guardrails::Limits,
+    state::SemanticEditTools,
+    tools::{PersistEdit, PreviewEdit},
+};
+use mcplease::traits::Tool;
+use serde_json::json;
+use std::fs;
+
+#[test]
+fn persists_beyond_the_rate_limit_need_confirmation() {
+    let dir = workspace("guardrails");
+    fs::write(dir.join("lib.rs"), "fn value() -> u8 {\n    0\n}\n").unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("guardrails-test")
+        .with_limits(Limits {
+            persists_per_minute: Some(1),
+            ..Limits::default()
+        })
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let mut replace = |n: u8, confirm: bool| {
+        let preview: PreviewEdit = serde_json::from_value(json!({
+            "file_path": "lib.rs",
+            "anchor": "fn value",
+            "operation": "replace",
+            "content": format!("fn value() -> u8 {{\n    {n}\n}}"),
+        }))
+        .unwrap();
+        preview.execute(&mut state).unwrap();
+        PersistEdit {
+            confirm,
+            hunks: vec![],
+            on_conflict: None,
+            output_path: None,
+        }
+        .execute(&mut state)
+        .map_err(|error| error.to_string())
+    };
+
+    replace(1, false).unwrap();
+    assert!(
+        fs::read_to_string(dir.join("lib.rs"))
+            .unwrap()
+            .contains("    1\n")
+    );
+
+    let error = replace(2, false).unwrap_err();
+    assert!(
+        error.starts_with(
+            "Nothing was written because 1 edit was already persisted in the last minute"
+        ),
+        "{error}"
+    );
+    assert!(
+        error.ends_with(
+            "call persist_edit with confirm to write it anyway. The edit is still staged."
+        )
+    );
+    assert!(state.get_staged_operation(None).unwrap().is_some());
+    assert!(
+        fs::read_to_string(dir.join("lib.rs"))
+            .unwrap()
+            .contains("    1\n")
+    );
+
+    PersistEdit {
+        confirm: true,
+        hunks: vec![],
+        on_conflict: None,
+        output_path: None,
+    }
+    .execute(&mut state)
+    .unwrap();
+    assert!(
+        fs::read_to_string(dir.join("lib.rs"))
+            .unwrap()
+            .contains("    2\n")
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
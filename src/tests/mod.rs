@@ -1,17 +1,84 @@
+#[cfg(feature = "rust")]
+mod bookmarks;
+#[cfg(all(
+    feature = "rust",
+    feature = "python",
+    feature = "json",
+    feature = "toml"
+))]
+mod codemod;
+mod concurrent_sessions;
+#[cfg(feature = "rust")]
+mod conflicts;
+#[cfg(feature = "rust")]
+mod format_files;
+mod guardrails;
+mod path_resolution;
+#[cfg(feature = "rust")]
+mod persist_hunks;
+mod persist_output_path;
+#[cfg(feature = "rust")]
+mod scaffold;
 mod semantic_validation;
+mod server;
+#[cfg(feature = "rust")]
+mod session_summary;
+#[cfg(all(
+    feature = "rust",
+    feature = "json",
+    feature = "toml",
+    feature = "javascript",
+    feature = "typescript",
+    feature = "python",
+    feature = "yaml",
+    feature = "java",
+    feature = "kotlin",
+    feature = "spelling"
+))]
 mod snapshot_runner;
+mod undo;
+mod versions;
+#[cfg(feature = "yaml")]
+mod workflows;
+#[cfg(feature = "rust")]
+mod workspace_edit;
+#[cfg(feature = "rust")]
+mod workspace_health;
 use crate::tools::Tools;
 use mcplease::traits::AsToolsList;
-use snapshot_runner::SnapshotRunner;
-use std::env;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// An empty directory for a test to work in, named after the test and removed first in case
+/// an earlier run left it behind
+fn workspace(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("semantic-edit-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
 
 #[test]
+#[cfg(all(
+    feature = "rust",
+    feature = "json",
+    feature = "toml",
+    feature = "javascript",
+    feature = "typescript",
+    feature = "python",
+    feature = "yaml",
+    feature = "java",
+    feature = "kotlin",
+    feature = "spelling"
+))]
 fn run_snapshot_tests() {
     let update_mode = env::var("UPDATE_SNAPSHOTS").is_ok() || env::var("UPDATE_SNAPSHOT").is_ok();
     let test_filter = env::var("TEST_FILTER").ok();
 
-    let mut runner =
-        SnapshotRunner::new(update_mode, test_filter).expect("Failed to create snapshot runner");
+    let mut runner = snapshot_runner::SnapshotRunner::new(update_mode, test_filter)
+        .expect("Failed to create snapshot runner");
 
     let results = runner
         .run_all_tests()
@@ -35,3 +102,42 @@ fn run_snapshot_tests() {
 fn tools_doesnt_panic() {
     Tools::tools_list();
 }
+
+/// The schemas every MCP client is sent, checked against tests/tool_schemas.json so that changes
+/// to them are reviewed. Run with UPDATE_SNAPSHOTS=1 to rewrite it.
+#[test]
+fn tool_schemas_match_snapshot() {
+    // properties come out in a different order from run to run, so sort every object's keys
+    fn sorted(value: serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(map) => {
+                let mut entries = map.into_iter().collect::<Vec<_>>();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sorted(value)))
+                    .collect()
+            }
+            serde_json::Value::Array(items) => items.into_iter().map(sorted).collect(),
+            value => value,
+        }
+    }
+    let schemas = serde_json::to_value(Tools::tools_list()).unwrap();
+    let schemas = serde_json::to_string_pretty(&sorted(schemas)).unwrap() + "\n";
+    assert!(
+        !schemas.contains("///"),
+        "a tool schema contains doc comment markers"
+    );
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/tool_schemas.json");
+    if env::var("UPDATE_SNAPSHOTS").is_ok() || env::var("UPDATE_SNAPSHOT").is_ok() {
+        fs::write(&path, &schemas).unwrap();
+    } else {
+        let expected = fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            expected == schemas,
+            "the tool schemas changed; review them and run with UPDATE_SNAPSHOTS=1 to update {}",
+            path.display()
+        );
+    }
+}
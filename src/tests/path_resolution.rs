@@ -0,0 +1,108 @@
+use crate::state::SemanticEditTools;
+use std::{fs, path::PathBuf};
+
+/// A fresh workspace with a crate under `real/`
+fn workspace(name: &str) -> PathBuf {
+    let dir = super::workspace(name);
+    fs::create_dir_all(dir.join("real/src")).unwrap();
+    fs::write(dir.join("real/src/lib.rs"), "pub fn lib() {}\n").unwrap();
+    dir
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_working_directory_resolves_to_real_paths() {
+    let dir = workspace("symlinked-workspace");
+    std::os::unix::fs::symlink(dir.join("real"), dir.join("link")).unwrap();
+    let real_file = fs::canonicalize(dir.join("real/src/lib.rs")).unwrap();
+
+    let state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("path-resolution-test")
+        .with_working_directory(dir.join("link"), None)
+        .unwrap();
+
+    assert_eq!(
+        state.get_context(None).unwrap(),
+        Some(fs::canonicalize(dir.join("real")).unwrap())
+    );
+    assert_eq!(state.resolve_path("src/lib.rs", None).unwrap(), real_file);
+    assert_eq!(
+        state
+            .resolve_path(dir.join("link/src/lib.rs").to_str().unwrap(), None)
+            .unwrap(),
+        real_file
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn missing_files_name_the_path() {
+    let dir = workspace("missing-file");
+    let state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("path-resolution-test")
+        .with_working_directory(dir.join("real"), None)
+        .unwrap();
+
+    let error = state.resolve_path("src/missing.rs", None).unwrap_err();
+    assert!(error.to_string().contains("src/missing.rs"));
+    assert!(
+        error
+            .to_string()
+            .contains("relative to the working directory")
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn environment_variables_are_expanded() {
+    let state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("path-resolution-test");
+
+    assert_eq!(
+        state
+            .resolve_path("$CARGO_MANIFEST_DIR/Cargo.toml", None)
+            .unwrap(),
+        fs::canonicalize(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")).unwrap()
+    );
+    assert!(
+        state
+            .resolve_path("${SEMANTIC_EDIT_UNSET_VARIABLE}/Cargo.toml", None)
+            .is_err()
+    );
+}
+
+#[test]
+fn working_directory_stack() {
+    let dir = workspace("directory-stack");
+    fs::create_dir_all(dir.join("other")).unwrap();
+    let real = fs::canonicalize(dir.join("real")).unwrap();
+    let other = fs::canonicalize(dir.join("other")).unwrap();
+
+    let state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("directory-stack-test")
+        .with_working_directory(real.clone(), None)
+        .unwrap();
+
+    let resolved = state.resolve_path("../other", None).unwrap();
+    state.push_working_directory(resolved, None).unwrap();
+    assert_eq!(state.get_context(None).unwrap(), Some(other));
+    assert_eq!(
+        state.directory_stack(None).unwrap(),
+        std::slice::from_ref(&real)
+    );
+
+    assert_eq!(
+        state.pop_working_directory(None).unwrap(),
+        Some(real.clone())
+    );
+    assert_eq!(state.get_context(None).unwrap(), Some(real));
+    assert_eq!(state.pop_working_directory(None).unwrap(), None);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,115 @@
+use super::workspace;
+use crate::{
+    conflicts::OnConflict,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+    tools::{PersistEdit, PreviewEdit},
+};
+use mcplease::traits::Tool;
+use std::fs;
+
+#[test]
+fn hunks_left_out_of_a_persist_stay_staged() {
+    let dir = workspace("hunks");
+    let file = dir.join("lib.rs");
+    let original = "pub mod numbers {\n    pub fn one() -> u32 {\n        1\n    }\n\n    \
+                    pub fn two() -> u32 {\n        2\n    }\n\n    pub fn three() -> u32 {\n        \
+                    3\n    }\n\n    pub fn four() -> u32 {\n        4\n    }\n}\n";
+    fs::write(&file, original).unwrap();
+    let edited = original
+        .replace("    1\n", "    10\n")
+        .replace("    4\n", "    40\n");
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("hunks-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let preview = PreviewEdit {
+        file_path: "lib.rs".into(),
+        language: None,
+        selector: Selector::anchor(Operation::Replace, "pub mod numbers"),
+        anchor_ref: None,
+        content: Some(edited.trim_end().into()),
+        report_impact: false,
+        allow_dirty: false,
+        allow_large: false,
+        expected_version: None,
+        edits: vec![],
+    }
+    .execute(&mut state)
+    .unwrap();
+    assert!(preview.contains("@@ hunk 2 @@"), "{preview}");
+
+    let persist = |hunks: Vec<usize>| PersistEdit {
+        confirm: false,
+        hunks,
+        on_conflict: None,
+        output_path: None,
+    };
+    persist(vec![2]).execute(&mut state).unwrap();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        original.replace("    4\n", "    40\n")
+    );
+
+    persist(vec![]).execute(&mut state).unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), edited);
+    assert!(persist(vec![]).execute(&mut state).is_err());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn persisting_some_hunks_after_a_conflict_reports_both() {
+    let dir = workspace("hunks-conflict");
+    let file = dir.join("lib.rs");
+    let original = "pub mod numbers {\n    pub fn one() -> u32 {\n        1\n    }\n\n    \
+                    pub fn two() -> u32 {\n        2\n    }\n\n    pub fn three() -> u32 {\n        \
+                    3\n    }\n\n    pub fn four() -> u32 {\n        4\n    }\n}\n";
+    fs::write(&file, original).unwrap();
+    let edited = original
+        .replace("    1\n", "    10\n")
+        .replace("    4\n", "    40\n");
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("hunks-conflict-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    PreviewEdit {
+        file_path: "lib.rs".into(),
+        language: None,
+        selector: Selector::anchor(Operation::Replace, "pub mod numbers"),
+        anchor_ref: None,
+        content: Some(edited.trim_end().into()),
+        report_impact: false,
+        allow_dirty: false,
+        allow_large: false,
+        expected_version: None,
+        edits: vec![],
+    }
+    .execute(&mut state)
+    .unwrap();
+    fs::write(&file, format!("//! Numbers\n\n{original}")).unwrap();
+
+    let response = PersistEdit {
+        confirm: false,
+        hunks: vec![1],
+        on_conflict: Some(OnConflict::Reresolve),
+        output_path: None,
+    }
+    .execute(&mut state)
+    .unwrap();
+    assert!(
+        response.contains("changed after this edit was staged, so its anchor was found again"),
+        "{response}"
+    );
+    assert!(response.contains("Applied hunk 1 of 2"), "{response}");
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        format!("//! Numbers\n\n{}", original.replace("    1\n", "    10\n"))
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
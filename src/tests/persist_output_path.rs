@@ -0,0 +1,115 @@
+use super::workspace;
+use crate::{
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+    tools::{PersistEdit, PreviewEdit, UndoEdit},
+};
+use mcplease::traits::Tool;
+use std::fs;
+
+#[test]
+fn edits_can_be_written_to_another_file() {
+    let dir = workspace("output");
+    let file = dir.join("lib.rs");
+    let original = "pub fn one() -> u32 {\n    1\n}\n";
+    fs::write(&file, original).unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("output-path-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let preview = |state: &mut SemanticEditTools| {
+        PreviewEdit {
+            file_path: "lib.rs".into(),
+            language: None,
+            selector: Selector::anchor(Operation::InsertAfter, "pub fn one"),
+            anchor_ref: None,
+            content: Some("\npub fn two() -> u32 {\n    2\n}".into()),
+            report_impact: false,
+            allow_dirty: false,
+            allow_large: false,
+            expected_version: None,
+            edits: vec![],
+        }
+        .execute(state)
+        .unwrap();
+    };
+    let persist = |state: &mut SemanticEditTools, confirm: bool, hunks: Vec<usize>| {
+        PersistEdit {
+            confirm,
+            hunks,
+            on_conflict: None,
+            output_path: Some("extracted/two.rs".into()),
+        }
+        .execute(state)
+    };
+    let extracted = dir.join("extracted/two.rs");
+
+    preview(&mut state);
+    let error = persist(&mut state, false, vec![1]).unwrap_err().to_string();
+    assert!(error.contains("can't be used together"), "{error}");
+
+    let response = persist(&mut state, false, vec![]).unwrap();
+    assert!(response.starts_with("Wrote the edit to"), "{response}");
+    assert_eq!(fs::read_to_string(&file).unwrap(), original);
+    let written = fs::read_to_string(&extracted).unwrap();
+    assert!(written.contains("pub fn two"), "{written}");
+
+    // an existing file is only replaced with confirm
+    fs::write(&extracted, "// placeholder\n").unwrap();
+    preview(&mut state);
+    let error = persist(&mut state, false, vec![]).unwrap_err().to_string();
+    assert!(error.contains("already exists"), "{error}");
+    assert_eq!(fs::read_to_string(&extracted).unwrap(), "// placeholder\n");
+    persist(&mut state, true, vec![]).unwrap();
+    assert_eq!(fs::read_to_string(&extracted).unwrap(), written);
+    assert_eq!(fs::read_to_string(&file).unwrap(), original);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn failed_writes_are_not_recorded() {
+    let dir = workspace("output-failed");
+    fs::write(dir.join("lib.rs"), "pub fn one() -> u32 {\n    1\n}\n").unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("output-path-failed-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    PreviewEdit {
+        file_path: "lib.rs".into(),
+        language: None,
+        selector: Selector::anchor(Operation::InsertAfter, "pub fn one"),
+        anchor_ref: None,
+        content: Some("\npub fn two() -> u32 {\n    2\n}".into()),
+        report_impact: false,
+        allow_dirty: false,
+        allow_large: false,
+        expected_version: None,
+        edits: vec![],
+    }
+    .execute(&mut state)
+    .unwrap();
+
+    // lib.rs is a file, so there is no directory to write into
+    let error = PersistEdit {
+        confirm: false,
+        hunks: vec![],
+        on_conflict: None,
+        output_path: Some("lib.rs/two.rs".into()),
+    }
+    .execute(&mut state)
+    .unwrap_err()
+    .to_string();
+    assert!(error.ends_with("The edit is still staged."), "{error}");
+    assert!(state.get_staged_operation(None).unwrap().is_some());
+    assert_eq!(
+        UndoEdit { file_path: None }.execute(&mut state).unwrap(),
+        "There are no writes to undo in this session"
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
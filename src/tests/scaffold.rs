@@ -0,0 +1,57 @@
+use super::workspace;
+use crate::{state::SemanticEditTools, tools::Scaffold};
+use mcplease::traits::Tool;
+use std::fs;
+
+#[test]
+fn scaffold_creates_files_from_templates() {
+    let dir = workspace("scaffold");
+    fs::create_dir_all(dir.join(".semantic-edit/templates")).unwrap();
+    fs::write(
+        dir.join(".semantic-edit/templates/handler.rs"),
+        "pub async fn {{name}}(request: Request) -> Response {\n    todo!()\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join(".semantic-edit/templates/broken.rs"),
+        "pub fn {{name}}( {\n",
+    )
+    .unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("scaffold-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let mut scaffold = |file_path: &str, template: Option<&str>| {
+        Scaffold {
+            file_path: file_path.into(),
+            template: template.map(String::from),
+        }
+        .execute(&mut state)
+        .map_err(|error| error.to_string())
+    };
+
+    let response = scaffold("web/components/user-card.tsx", None).unwrap();
+    assert!(response.starts_with("Created web/components/user-card.tsx from the react_component"));
+    assert!(
+        fs::read_to_string(dir.join("web/components/user-card.tsx"))
+            .unwrap()
+            .contains("export function UserCard(")
+    );
+
+    scaffold("src/handlers/users.rs", Some("handler")).unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.join("src/handlers/users.rs")).unwrap(),
+        "pub async fn users(request: Request) -> Response {\n    todo!()\n}\n"
+    );
+
+    let error = scaffold("src/handlers/users.rs", Some("handler")).unwrap_err();
+    assert!(error.contains("already exists"));
+
+    let error = scaffold("src/other.rs", Some("broken")).unwrap_err();
+    assert!(error.contains("does not produce valid rust"));
+    assert!(!dir.join("src/other.rs").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -4,6 +4,7 @@ use crate::{
 };
 
 #[test]
+#[cfg(feature = "rust")]
 fn impl_block_pub_fn() {
     assert!(validate_code(r#"impl User pub fn new () {}"#, LanguageName::Rust).is_some());
 }
@@ -19,6 +20,63 @@ fn docs_automation() {
     );
 }
 
+#[test]
+#[cfg(feature = "rust")]
+fn pub_item_in_nested_block() {
+    let errors = validate_code(
+        "fn main() {\n    if true {\n        pub use std::fmt;\n    }\n}\n",
+        LanguageName::Rust,
+    )
+    .unwrap();
+    assert!(
+        errors.contains("Items inside function bodies cannot be `pub` (line 3, column 9, rule")
+    );
+}
+
+#[test]
+#[cfg(feature = "rust")]
+fn statement_at_module_level() {
+    let errors = validate_code(
+        "fn main() {}\n\nlet limit = 10;\ncheck(limit);\n",
+        LanguageName::Rust,
+    )
+    .unwrap();
+    assert_eq!(
+        errors
+            .matches("Statements cannot be placed at module level")
+            .count(),
+        2,
+        "{errors}"
+    );
+    assert_eq!(
+        validate_code(
+            "macro_rules! noop {\n    () => {};\n}\n\nnoop!();\n\nstatic LIMIT: u8 = 10;\n",
+            LanguageName::Rust
+        ),
+        None
+    );
+}
+
+#[test]
+#[cfg(feature = "rust")]
+fn detached_attribute() {
+    let errors = validate_code("fn main() {}\n\n#[derive(Debug)]\n", LanguageName::Rust).unwrap();
+    assert!(errors.contains("This attribute is not followed by an item"));
+
+    let errors = validate_code(
+        "struct A;\n\nimpl A {\n    fn a(&self) {}\n    #[inline]\n}\n",
+        LanguageName::Rust,
+    )
+    .unwrap();
+    assert!(errors.contains("This attribute is not followed by an item"));
+
+    assert_eq!(
+        validate_code("#[derive(Debug)]\nstruct A;\n", LanguageName::Rust),
+        None
+    );
+}
+
+#[cfg(feature = "python")]
 mod python {
     use super::*;
     #[test]
@@ -76,3 +134,19 @@ fn validate_code(code: &str, language: LanguageName) -> Option<String> {
     println!("{result}");
     Some(result)
 }
+
+#[test]
+#[cfg(feature = "rust")]
+fn error_columns_count_characters() {
+    let errors = validate_code(
+        "fn main() {\n    let café = \"naïve\" + ;\n}\n",
+        LanguageName::Rust,
+    )
+    .unwrap();
+    // counting bytes, as tree-sitter does, would put this at column 27
+    assert!(
+        errors.contains("- line 2, column 25: missing identifier\n"),
+        "{errors}"
+    );
+    assert!(errors.contains("\n       ⎸                        ^ missing identifier\n"));
+}
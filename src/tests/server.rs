@@ -0,0 +1,229 @@
+use super::workspace;
+use crate::{
+    server::{Input, Recorder, Replay, replay, serve},
+    state::SemanticEditTools,
+    tools::Tools,
+};
+use mcplease::server_info;
+use serde_json::{Value, json};
+use std::io::{BufReader, Cursor};
+
+fn exchange(input: &str) -> Vec<Value> {
+    let mut state = SemanticEditTools::new(None).unwrap();
+    let mut output = Vec::new();
+    serve::<Tools, _>(
+        &mut state,
+        &server_info!(),
+        None,
+        Input::read(Cursor::new(input.to_owned())),
+        &mut output,
+    )
+    .unwrap();
+
+    String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn malformed_lines_get_parse_errors_and_the_session_continues() {
+    let responses = exchange(concat!(
+        "{\"jsonrpc\": \"2.0\", \"id\": 1, \"method\"\n",
+        "\n",
+        "{\"jsonrpc\": \"2.0\", \"id\": 2, \"method\": \"tools/list\"}\n"
+    ));
+
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["id"], Value::Null);
+    assert_eq!(responses[0]["error"]["code"], -32700);
+    assert_eq!(responses[1]["id"], 2);
+    assert!(responses[1]["result"]["tools"].is_array());
+}
+
+#[test]
+fn batches_are_answered_with_a_batch() {
+    let batch = json!([
+        {"jsonrpc": "2.0", "id": 1, "method": "tools/list"},
+        {"jsonrpc": "2.0", "method": "notifications/initialized"},
+        {"jsonrpc": "2.0", "id": 2, "method": "unknown"}
+    ]);
+    let responses = exchange(&format!("{batch}\n"));
+
+    assert_eq!(responses.len(), 1);
+    let batch = responses[0].as_array().unwrap();
+    assert_eq!(batch.len(), 2);
+    assert_eq!(batch[0]["id"], 1);
+    assert_eq!(batch[1]["id"], 2);
+    assert!(batch[1]["error"].is_object());
+}
+
+#[test]
+fn invalid_requests_keep_their_id() {
+    let responses = exchange("{\"id\": 7, \"params\": {}}\n[]\n");
+
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["id"], 7);
+    assert_eq!(responses[0]["error"]["code"], -32600);
+    assert_eq!(responses[1]["error"]["code"], -32600);
+}
+
+fn initialize_and_call(protocol_version: &str) -> Vec<Value> {
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": protocol_version,
+            "capabilities": {},
+            "clientInfo": {"name": "test", "version": "1.0"}
+        }
+    });
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": {"name": "list_staged_edits", "arguments": {}}
+    });
+    exchange(&format!("{initialize}\n{call}\n"))
+}
+
+#[test]
+fn supported_protocol_versions_are_echoed() {
+    let responses = initialize_and_call("2025-06-18");
+    assert_eq!(responses[0]["result"]["protocolVersion"], "2025-06-18");
+    assert_eq!(
+        responses[1]["result"]["content"][0]["annotations"]["audience"],
+        json!(["assistant"])
+    );
+}
+
+#[test]
+fn older_clients_get_plain_results() {
+    let responses = initialize_and_call("2024-11-05");
+    assert_eq!(responses[0]["result"]["protocolVersion"], "2024-11-05");
+    assert!(
+        responses[1]["result"]["content"][0]
+            .get("annotations")
+            .is_none()
+    );
+}
+
+#[test]
+fn unknown_protocol_versions_get_the_latest_supported() {
+    let responses = initialize_and_call("1999-01-01");
+    assert_eq!(responses[0]["result"]["protocolVersion"], "2025-06-18");
+}
+
+#[test]
+fn tool_calls_and_other_requests_are_all_answered() {
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {"name": "list_staged_edits", "arguments": {}}
+    });
+    let list = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"});
+    let notification = json!({"jsonrpc": "2.0", "method": "notifications/cancelled"});
+    let responses = exchange(&format!("{call}\n{list}\n{notification}\n{call}\n"));
+
+    // the tool calls are answered in order, though the list may overtake them
+    let ids = responses
+        .iter()
+        .map(|response| response["id"].as_u64().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids.iter().filter(|id| **id == 1).count(), 2);
+    assert_eq!(ids.iter().filter(|id| **id == 2).count(), 1);
+    assert!(
+        responses
+            .iter()
+            .all(|response| response.get("error").is_none())
+    );
+}
+
+#[test]
+fn stopping_ends_the_session_while_the_input_is_still_open() {
+    let (reader, writer) = std::io::pipe().unwrap();
+    let input = Input::read(BufReader::new(reader));
+    input.stopper()();
+
+    let mut state = SemanticEditTools::new(None).unwrap();
+    let mut output = Vec::new();
+    serve::<Tools, _>(&mut state, &server_info!(), None, input, &mut output).unwrap();
+    assert!(output.is_empty());
+    drop(writer);
+}
+
+#[test]
+fn recorded_sessions_replay_against_a_copy_of_the_workspace() {
+    let root = workspace("replay-test");
+    let workspace = root.join("workspace");
+    std::fs::create_dir_all(&workspace).unwrap();
+    std::fs::write(workspace.join("lib.rs"), "fn value() -> u8 {\n    0\n}\n").unwrap();
+    let workspace = std::fs::canonicalize(workspace).unwrap();
+
+    let requests = [
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "set_working_directory", "arguments": {"path": workspace}}
+        }),
+        json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "preview_edit", "arguments": {
+                "file_path": "lib.rs",
+                "anchor": "fn value",
+                "operation": "replace",
+                "content": "fn value() -> u8 {\n    1\n}"
+            }}
+        }),
+    ];
+    let input = requests
+        .iter()
+        .map(|request| format!("{request}\n"))
+        .collect::<String>();
+    let recording = root.join("session.jsonl");
+    let recorder = Recorder::create(&recording).unwrap();
+    for (request, response) in requests.iter().zip(exchange(&input)) {
+        recorder
+            .record(&request.to_string(), Some(&response.to_string()))
+            .unwrap();
+    }
+
+    let run = || {
+        let mut state = SemanticEditTools::new(None).unwrap();
+        let arguments = Replay {
+            recording: recording.clone(),
+            workspace: Some(workspace.clone()),
+            recorded_workspace: None,
+        };
+        replay::<Tools, _>(&arguments, &mut state, &server_info!(), None).unwrap()
+    };
+
+    let report = run();
+    assert!(
+        report.contains("✅ 2. tools/call preview_edit\n"),
+        "{report}"
+    );
+    assert!(report.contains("2 of 2 responses matched"), "{report}");
+    // the replay edits the copy, not the workspace
+    assert!(
+        std::fs::read_to_string(workspace.join("lib.rs"))
+            .unwrap()
+            .contains("    0\n")
+    );
+
+    std::fs::write(workspace.join("lib.rs"), "fn value() -> u8 {\n    5\n}\n").unwrap();
+    let report = run();
+    assert!(
+        report.contains("❌ 2. tools/call preview_edit: the response differs"),
+        "{report}"
+    );
+    assert!(report.contains("1 of 2 responses matched"), "{report}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,59 @@
+use super::workspace;
+use crate::{
+    state::SemanticEditTools,
+    tools::{EndSessionSummary, PersistEdit, PreviewEdit},
+};
+use mcplease::traits::Tool;
+use serde_json::json;
+use std::fs;
+
+#[test]
+fn summary_counts_writes_and_rejections_then_starts_over() {
+    let dir = workspace("summary");
+    fs::write(dir.join("lib.rs"), "fn value() -> u8 {\n    0\n}\n").unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("summary-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let mut preview = |content: &str| {
+        let preview: PreviewEdit = serde_json::from_value(json!({
+            "file_path": "lib.rs",
+            "anchor": "fn value",
+            "operation": "replace",
+            "content": content,
+        }))
+        .unwrap();
+        preview.execute(&mut state).unwrap();
+    };
+
+    preview("fn value() -> u8 {\n    1 +\n}");
+    preview("fn value() -> u8 {\n    1\n}\n\nfn other() -> u8 {\n    2\n}");
+    PersistEdit {
+        confirm: false,
+        hunks: vec![],
+        on_conflict: None,
+        output_path: None,
+    }
+    .execute(&mut state)
+    .unwrap();
+
+    let summary = EndSessionSummary {}.execute(&mut state).unwrap();
+    assert!(
+        summary.starts_with("1 file touched, +5 -1 lines\n"),
+        "{summary}"
+    );
+    assert!(summary.contains("lib.rs: 1 write, +5 -1\n"), "{summary}");
+    assert!(
+        summary.ends_with("1 preview rejected by validation\nFormatters run: rustfmt (1 write)"),
+        "{summary}"
+    );
+
+    assert_eq!(
+        EndSessionSummary {}.execute(&mut state).unwrap(),
+        "Nothing was written or rejected in this session."
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
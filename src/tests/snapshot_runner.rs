@@ -340,9 +340,10 @@ impl SnapshotRunner {
             snapshot_execution_result.response.push_str(tool.name());
             snapshot_execution_result.response.push_str(" ===\n");
             let (tx, rx) = std::sync::mpsc::channel();
-            self.state.set_commit_fn(Some(Box::new(move |_, content| {
-                tx.send(content).unwrap();
-            })));
+            self.state
+                .set_commit_fn(Some(std::sync::Arc::new(move |_, content| {
+                    tx.send(content).unwrap();
+                })));
 
             match tool.execute(&mut self.state) {
                 Ok(response) => snapshot_execution_result.response.push_str(&response),
@@ -0,0 +1,76 @@
+use super::workspace;
+use crate::{
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+    tools::{PersistEdit, PreviewEdit, UndoEdit},
+};
+use mcplease::traits::Tool;
+use std::fs;
+
+#[test]
+fn writes_are_undone_most_recent_first() {
+    let dir = workspace("undo");
+    let file = dir.join("lib.rs");
+    let original = "pub fn one() -> u32 {\n    1\n}\n";
+    fs::write(&file, original).unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("undo-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let mut persist = |anchor: &str, content: &str| {
+        PreviewEdit {
+            file_path: "lib.rs".into(),
+            language: None,
+            selector: Selector::anchor(Operation::InsertAfter, anchor),
+            anchor_ref: None,
+            content: Some(content.into()),
+            report_impact: false,
+            allow_dirty: false,
+            allow_large: false,
+            expected_version: None,
+            edits: vec![],
+        }
+        .execute(&mut state)
+        .unwrap();
+        PersistEdit {
+            confirm: false,
+            hunks: vec![],
+            on_conflict: None,
+            output_path: None,
+        }
+        .execute(&mut state)
+        .unwrap();
+        fs::read_to_string(&file).unwrap()
+    };
+    let first = persist("pub fn one", "\npub fn two() -> u32 {\n    2\n}");
+    let second = persist("pub fn two", "\npub fn three() -> u32 {\n    3\n}");
+    assert!(second.contains("pub fn three"), "{second}");
+
+    // a change made since the write is never discarded
+    fs::write(&file, second.replace("    3\n", "    4\n")).unwrap();
+    let error = UndoEdit { file_path: None }
+        .execute(&mut state)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("has changed since the write"), "{error}");
+    fs::write(&file, &second).unwrap();
+
+    let response = UndoEdit {
+        file_path: Some("lib.rs".into()),
+    }
+    .execute(&mut state)
+    .unwrap();
+    assert!(response.contains("-pub fn three() -> u32 {"), "{response}");
+    assert_eq!(fs::read_to_string(&file).unwrap(), first);
+
+    UndoEdit { file_path: None }.execute(&mut state).unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), original);
+    assert_eq!(
+        UndoEdit { file_path: None }.execute(&mut state).unwrap(),
+        "There are no writes to undo in this session"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,73 @@
+use super::workspace;
+use crate::{
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+    tools::{Diff, PersistEdit, PreviewEdit},
+    versions,
+};
+use mcplease::traits::Tool;
+use std::fs;
+
+#[test]
+fn versions_detect_and_show_changes_made_elsewhere() {
+    let dir = workspace("versions");
+    let file = dir.join("lib.rs");
+    let original = "pub fn one() -> u32 {\n    1\n}\n";
+    fs::write(&file, original).unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("versions-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let preview = |expected_version: String| PreviewEdit {
+        file_path: "lib.rs".into(),
+        language: None,
+        selector: Selector::anchor(Operation::InsertAfter, "pub fn one"),
+        anchor_ref: None,
+        content: Some("\npub fn two() -> u32 {\n    2\n}".into()),
+        report_impact: false,
+        allow_dirty: false,
+        allow_large: false,
+        expected_version: Some(expected_version),
+        edits: vec![],
+    };
+
+    preview(versions::of(original)).execute(&mut state).unwrap();
+    let response = PersistEdit {
+        confirm: false,
+        hunks: vec![],
+        on_conflict: None,
+        output_path: None,
+    }
+    .execute(&mut state)
+    .unwrap();
+    let written = fs::read_to_string(&file).unwrap();
+    let version = versions::of(&written);
+    assert!(
+        response.contains(&format!("now at version `{version}`")),
+        "{response}"
+    );
+
+    // someone else changes the file
+    fs::write(&file, written.replace("    2\n", "    3\n")).unwrap();
+    let error = preview(version.clone())
+        .execute(&mut state)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.starts_with("lib.rs has changed since version"),
+        "{error}"
+    );
+
+    let diff = Diff {
+        file_path: "lib.rs".into(),
+        other_path: None,
+        version: Some(version),
+    }
+    .execute(&mut state)
+    .unwrap();
+    assert!(diff.contains("-    2\n+    3\n"), "{diff}");
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,56 @@
+use super::workspace;
+use crate::{
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+    tools::PreviewEdit,
+};
+use mcplease::traits::Tool;
+use std::fs;
+
+#[test]
+fn workflow_edits_are_checked() {
+    let dir = workspace("workflows");
+    fs::create_dir_all(dir.join(".github/workflows")).unwrap();
+    fs::write(
+        dir.join(".github/workflows/ci.yml"),
+        "on: push\n\njobs:\n  build:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: actions/checkout@v4\n",
+    )
+    .unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("workflows-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let mut preview = |anchor: &str, content: &str| {
+        PreviewEdit {
+            file_path: ".github/workflows/ci.yml".into(),
+            language: None,
+            selector: Selector::anchor(Operation::InsertAfter, anchor),
+            anchor_ref: None,
+            content: Some(content.into()),
+            report_impact: false,
+            allow_dirty: false,
+            allow_large: false,
+            expected_version: None,
+            edits: vec![],
+        }
+        .execute(&mut state)
+        .unwrap()
+    };
+
+    let rejected = preview("build:", "test:\n  needs: lint\n  runs-on: ubuntu-latest");
+    assert!(
+        rejected.contains("line 9: Job `test` needs `lint`, but there's no job with that id"),
+        "{rejected}"
+    );
+    let rejected = preview("- uses: actions/checkout@v4", "- uses: actions/cache");
+    assert!(
+        rejected.contains("`uses: actions/cache` isn't an action"),
+        "{rejected}"
+    );
+    let staged = preview("build:", "test:\n  needs: build\n  runs-on: ubuntu-latest");
+    assert!(staged.contains("+    needs: build"), "{staged}");
+
+    let _ = fs::remove_dir_all(&dir);
+}
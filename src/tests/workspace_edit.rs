@@ -0,0 +1,114 @@
+use super::workspace;
+use crate::{
+    state::SemanticEditTools,
+    tools::{ListStagedEdits, PreviewEdit},
+};
+use mcplease::traits::Tool;
+use serde_json::{Value, json};
+use std::fs;
+
+#[test]
+fn staged_edit_exports_as_workspace_edit() {
+    let dir = workspace("workspace-edit");
+    fs::write(
+        dir.join("lib.rs"),
+        "fn first() {}\n\nfn second() -> u8 {\n    1\n}\n",
+    )
+    .unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("workspace-edit-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+
+    let export = |state: &mut SemanticEditTools| -> Value {
+        let response = ListStagedEdits {
+            workspace_edit: true,
+            html: false,
+        }
+        .execute(state)
+        .unwrap();
+        serde_json::from_str(&response).unwrap()
+    };
+    assert_eq!(export(&mut state), json!({ "changes": {} }));
+
+    let preview: PreviewEdit = serde_json::from_value(json!({
+        "file_path": "lib.rs",
+        "anchor": "fn second",
+        "operation": "replace",
+        "content": "fn second() -> u8 {\n    2\n}",
+    }))
+    .unwrap();
+    preview.execute(&mut state).unwrap();
+
+    let uri = format!(
+        "file://{}",
+        fs::canonicalize(dir.join("lib.rs")).unwrap().display()
+    );
+    assert_eq!(
+        export(&mut state),
+        json!({
+            "changes": {
+                uri: [{
+                    "range": {
+                        "start": { "line": 3, "character": 4 },
+                        "end": { "line": 3, "character": 5 },
+                    },
+                    "newText": "2",
+                }],
+            },
+        })
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("lib.rs")).unwrap(),
+        "fn first() {}\n\nfn second() -> u8 {\n    1\n}\n",
+        "exporting does not write the edit"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn staged_edit_exports_as_html() {
+    let dir = workspace("html");
+    fs::write(dir.join("lib.rs"), "fn second() -> u8 {\n    1\n}\n").unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("html-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let export = ListStagedEdits {
+        workspace_edit: false,
+        html: true,
+    };
+    assert!(export.execute(&mut state).is_err());
+
+    let preview: PreviewEdit = serde_json::from_value(json!({
+        "file_path": "lib.rs",
+        "anchor": "fn second",
+        "operation": "replace",
+        "content": "fn second() -> u8 {\n    2\n}",
+    }))
+    .unwrap();
+    preview.execute(&mut state).unwrap();
+
+    let response = ListStagedEdits {
+        workspace_edit: false,
+        html: true,
+    }
+    .execute(&mut state)
+    .unwrap();
+    let page = response.strip_prefix("Wrote the staged diff to ").unwrap();
+    assert!(page.starts_with(&*state.session_directory(None).to_string_lossy()));
+    let html = fs::read_to_string(page).unwrap();
+    assert!(html.contains("<title>lib.rs</title>"), "{html}");
+    assert!(
+        html.contains(r#"<span class="number-literal">2</span>"#),
+        "{html}"
+    );
+
+    fs::remove_file(page).unwrap();
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,48 @@
+use super::workspace;
+use crate::{state::SemanticEditTools, tools::WorkspaceHealth};
+use mcplease::traits::Tool;
+use std::fs;
+
+#[test]
+fn health_checks_resume_after_the_time_limit() {
+    let dir = workspace("health-time-limit");
+    fs::write(dir.join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(dir.join("b.rs"), "fn b( {}\n").unwrap();
+
+    let mut state = SemanticEditTools::new(None)
+        .unwrap()
+        .with_default_session_id("health-time-limit-test")
+        .with_working_directory(dir.clone(), None)
+        .unwrap();
+    let mut check = |continuation: Option<&str>| {
+        WorkspaceHealth {
+            path: None,
+            timeout_ms: Some(0),
+            continuation: continuation.map(Into::into),
+        }
+        .execute(&mut state)
+        .unwrap()
+    };
+
+    // a walk always gets through one file before it checks the time limit
+    let first = check(None);
+    assert!(
+        first.starts_with("No syntax errors in the 1 file parsed"),
+        "{first}"
+    );
+    assert!(
+        first.contains(
+            "Call workspace_health again with the same path and continuation: \"0:b.rs\""
+        ),
+        "{first}"
+    );
+
+    let rest = check(Some("0:b.rs"));
+    assert!(
+        rest.starts_with("1 of the 1 file parsed has syntax errors:\nb.rs:"),
+        "{rest}"
+    );
+    assert!(!rest.contains("time limit"), "{rest}");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,124 @@
+//! Tables of contents for Markdown documents, kept between a pair of marker comments
+
+use crate::validation::markdown::headings;
+use std::ops::Range;
+
+/// The comments a table of contents goes between unless others are given, as markdown-toc and
+/// similar tools write them
+pub const START_MARKER: &str = "<!-- toc -->";
+pub const END_MARKER: &str = "<!-- tocstop -->";
+
+/// The range between the marker lines of `source` and the table of contents to put there: a
+/// nested list linking to each heading after the end marker, down to `max_level`
+pub fn regenerate(
+    source: &str,
+    start_marker: &str,
+    end_marker: &str,
+    max_level: usize,
+) -> Result<(Range<usize>, String), String> {
+    let start = marker_line(source, start_marker)?;
+    let end = marker_line(source, end_marker)?;
+    if end.start < start.end {
+        return Err(format!(
+            "`{end_marker}` comes before `{start_marker}`. The table of contents goes between \
+             them, with `{start_marker}` first."
+        ));
+    }
+    let end_line = source[..end.start].lines().count() + 1;
+
+    let headings = headings(source)
+        .into_iter()
+        .filter(|heading| heading.line > end_line && heading.level <= max_level)
+        .collect::<Vec<_>>();
+    let top = headings
+        .iter()
+        .map(|heading| heading.level)
+        .min()
+        .unwrap_or(1);
+    let mut list = String::new();
+    for heading in &headings {
+        let indent = "  ".repeat(heading.level - top);
+        list.push_str(&format!(
+            "{indent}- [{}](#{})\n",
+            heading.text, heading.anchor
+        ));
+    }
+    let content = if list.is_empty() {
+        String::new()
+    } else {
+        format!("\n{list}\n")
+    };
+    Ok((start.end..end.start, content))
+}
+
+/// The range of the only line that is `marker`, including its line break
+fn marker_line(source: &str, marker: &str) -> Result<Range<usize>, String> {
+    let mut offset = 0;
+    let mut found = vec![];
+    for line in source.split_inclusive('\n') {
+        if line.trim() == marker.trim() {
+            found.push(offset..offset + line.len());
+        }
+        offset += line.len();
+    }
+    match found.as_slice() {
+        [line] => Ok(line.clone()),
+        [] => Err(format!(
+            "There's no `{marker}` line to put the table of contents by. Add `{START_MARKER}` \
+             and `{END_MARKER}` lines where it goes, or pass the markers the document uses."
+        )),
+        lines => Err(format!(
+            "`{marker}` is on {} lines, so it isn't clear which table of contents to update",
+            lines.len()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn updated(source: &str, max_level: usize) -> Result<String, String> {
+        let (range, content) = regenerate(source, START_MARKER, END_MARKER, max_level)?;
+        Ok(format!(
+            "{}{content}{}",
+            &source[..range.start],
+            &source[range.end..]
+        ))
+    }
+
+    #[test]
+    fn regenerates_between_markers() {
+        let source = "# Widgets\n\n<!-- toc -->\n- [Old](#old)\n<!-- tocstop -->\n\n## Install\n\n\
+                      ### From [crates.io](https://crates.io)\n\n## Usage\n\n#### Deep\n\n\
+                      ```sh\n# not a heading\n```\n\n## Usage\n";
+        assert_eq!(
+            updated(source, 3).unwrap(),
+            "# Widgets\n\n<!-- toc -->\n\n- [Install](#install)\n  \
+             - [From crates.io](#from-cratesio)\n- [Usage](#usage)\n- [Usage](#usage-1)\n\n\
+             <!-- tocstop -->\n\n## Install\n\n### From [crates.io](https://crates.io)\n\n\
+             ## Usage\n\n#### Deep\n\n```sh\n# not a heading\n```\n\n## Usage\n"
+        );
+        let current = updated(source, 3).unwrap();
+        assert_eq!(updated(&current, 3).unwrap(), current);
+    }
+
+    #[test]
+    fn markers_are_required_once_and_in_order() {
+        assert!(
+            updated("# Title\n\n## Section\n", 3)
+                .unwrap_err()
+                .contains("There's no `<!-- toc -->` line")
+        );
+        assert!(
+            updated("<!-- tocstop -->\n<!-- toc -->\n", 3)
+                .unwrap_err()
+                .contains("comes before")
+        );
+        assert!(
+            updated("<!-- toc -->\n<!-- toc -->\n<!-- tocstop -->\n", 3)
+                .unwrap_err()
+                .contains("is on 2 lines")
+        );
+    }
+}
@@ -5,9 +5,70 @@ mcplease::tools!(
     (PreviewEdit, preview_edit, "preview_edit"),
     (RetargetEdit, retarget_edit, "retarget_edit"),
     (PersistEdit, persist_edit, "persist_edit"),
+    (UndoEdit, undo_edit, "undo_edit"),
+    (ListStagedEdits, list_staged_edits, "list_staged_edits"),
+    (AnnotateEdit, annotate_edit, "annotate_edit"),
+    (Diff, diff, "diff"),
+    (Merge, merge, "merge"),
+    (SearchWorkspace, search_workspace, "search_workspace"),
+    (SearchSymbols, search_symbols, "search_symbols"),
+    (ModuleMap, module_map, "module_map"),
+    (PublicApi, public_api, "public_api"),
+    (CallGraph, call_graph, "call_graph"),
+    (FindDuplicates, find_duplicates, "find_duplicates"),
+    (WorkspaceHealth, workspace_health, "workspace_health"),
+    (FileMetrics, file_metrics, "file_metrics"),
+    (CodeActions, code_actions, "code_actions"),
+    (StageFixes, stage_fixes, "stage_fixes"),
+    (Scaffold, scaffold, "scaffold"),
+    (RunCodemod, run_codemod, "run_codemod"),
+    (FormatFiles, format_files, "format_files"),
+    (SortKeys, sort_keys, "sort_keys"),
+    (SetPackageEntry, set_package_entry, "set_package_entry"),
+    (SetKey, set_key, "set_key"),
+    (SetBookmark, set_bookmark, "set_bookmark"),
+    (UseBookmark, use_bookmark, "use_bookmark"),
+    (SetDocstring, set_docstring, "set_docstring"),
+    (UpdateToc, update_toc, "update_toc"),
+    (ShiftHeadings, shift_headings, "shift_headings"),
+    (AddImport, add_import, "add_import"),
+    (OrganizeImports, organize_imports, "organize_imports"),
+    (AddMember, add_member, "add_member"),
+    (ListRecipes, list_recipes, "list_recipes"),
+    (ApplyRecipe, apply_recipe, "apply_recipe"),
+    (SetJsxProp, set_jsx_prop, "set_jsx_prop"),
+    (Simulate, simulate, "simulate"),
+    (
+        ExplainValidationRule,
+        explain_validation_rule,
+        "explain_validation_rule"
+    ),
+    (NodeKinds, node_kinds, "node_kinds"),
     (
         SetWorkingDirectory,
         set_working_directory,
         "set_working_directory"
+    ),
+    (
+        GetWorkingDirectory,
+        get_working_directory,
+        "get_working_directory"
+    ),
+    (
+        PushWorkingDirectory,
+        push_working_directory,
+        "push_working_directory"
+    ),
+    (
+        PopWorkingDirectory,
+        pop_working_directory,
+        "pop_working_directory"
+    ),
+    (SetDialect, set_dialect, "set_dialect"),
+    (Doctor, doctor, "doctor"),
+    (
+        EndSessionSummary,
+        end_session_summary,
+        "end_session_summary"
     )
 );
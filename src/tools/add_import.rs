@@ -0,0 +1,62 @@
+use crate::{state::SemanticEditTools, tools::organize_imports};
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Add an import to a Python file in the group isort would put it in, and stage the result for
+/// preview
+///
+/// The file's other imports are organized at the same time, as organize_imports does, so an
+/// import of a module that is already imported from is combined with the existing one.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "add_import")]
+#[group(skip)]
+pub struct AddImport {
+    /// Path to the Python source file
+    pub file_path: String,
+
+    /// One or more import statements, like `from typing import Any` or `import json`
+    pub import: String,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for AddImport {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Import a name from the standard library",
+                item: Self {
+                    file_path: "app/views.py".into(),
+                    import: "from typing import Any".into(),
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Import one of the project's own modules",
+                item: Self {
+                    file_path: "app/views.py".into(),
+                    import: "from app.models import User".into(),
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for AddImport {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        organize_imports::stage(
+            state,
+            &self.file_path,
+            Some(&self.import),
+            self.allow_large,
+        )
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    languages::LanguageName,
+    members,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Stage a new property or method at the end of a TypeScript interface, object type alias, or
+/// class, found by name, and see a preview of it like preview_edit
+///
+/// The member is separated from the others the way they are separated from each other, with
+/// commas, semicolons, or nothing but a line break, and indented to match them. There's no need
+/// to anchor on the current last member.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "add_member")]
+#[group(skip)]
+pub struct AddMember {
+    /// Path to the TypeScript source file
+    pub file_path: String,
+
+    /// Name of the interface, type alias, or class
+    pub container: String,
+
+    /// The member to add, like `timeout: number` or `close(): void { this.socket.end(); }`.
+    /// A trailing separator is optional.
+    pub member: String,
+
+    /// Mark the member optional by adding `?` after its name
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub optional: bool,
+
+    /// Modifiers to put before the member, like `readonly`, `private`, or `static`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[arg(long)]
+    pub modifiers: Vec<String>,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for AddMember {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Add an optional property to an interface",
+                item: Self {
+                    file_path: "src/config.ts".into(),
+                    container: "Options".into(),
+                    member: "timeout: number".into(),
+                    optional: true,
+                    modifiers: vec![],
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Add a method to a class",
+                item: Self {
+                    file_path: "src/client.ts".into(),
+                    container: "Client".into(),
+                    member: "close(): void {\n  this.socket.end();\n}".into(),
+                    optional: false,
+                    modifiers: vec!["public".into()],
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for AddMember {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        encoding::check_size(&file_path, self.allow_large)?;
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+        if !matches!(language.name(), LanguageName::Typescript | LanguageName::Tsx) {
+            return Err(anyhow!(
+                "add_member adds to TypeScript interfaces, types, and classes, and {} is {}",
+                self.file_path,
+                language.name()
+            ));
+        }
+        let (source, _) = Encoding::read(&file_path)?;
+        let tree = language
+            .tree_sitter_parser()?
+            .parse(&source, None)
+            .ok_or_else(|| anyhow!("Unable to parse {}", self.file_path))?;
+        let member = members::with_modifiers(&self.member, &self.modifiers, self.optional);
+        let insertion = members::insertion(&tree, &source, &self.container, &member)
+            .map_err(|message| anyhow!(message))?;
+
+        let (operation, end_byte) = if insertion.range.is_empty() {
+            (Operation::InsertAfter, None)
+        } else {
+            (Operation::Replace, Some(insertion.range.end))
+        };
+        let selector = Selector::anchor(operation, insertion.anchor);
+        state.stage_edit_at(
+            insertion.content,
+            selector,
+            language,
+            file_path,
+            EditPosition {
+                start_byte: insertion.range.start,
+                end_byte,
+            },
+        )
+    }
+}
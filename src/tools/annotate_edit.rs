@@ -0,0 +1,70 @@
+use crate::state::SemanticEditTools;
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Attach a reviewer's note or approval decision to the staged edit
+///
+/// Notes and the decision are kept with the staged edit in the session, shown by
+/// list_staged_edits, and repeated when it is persisted. An edit marked as not approved is only
+/// written by persist_edit with confirm. Retargeting the edit withdraws its approval decision.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "annotate_edit")]
+#[group(skip)]
+pub struct AnnotateEdit {
+    /// A note to add to the staged edit's review
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub note: Option<String>,
+
+    /// Record that a reviewer approved the staged edit, or with false, that they didn't
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub approved: Option<bool>,
+}
+
+impl WithExamples for AnnotateEdit {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Record a reviewer's approval along with their comment",
+                item: Self {
+                    note: Some("Approved by Sam in the #payments channel".into()),
+                    approved: Some(true),
+                },
+            },
+            Example {
+                description: "Hold an edit back until a concern is addressed",
+                item: Self {
+                    note: Some("Check that the retry limit matches the API's".into()),
+                    approved: Some(false),
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for AnnotateEdit {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        if self.note.is_none() && self.approved.is_none() {
+            return Err(anyhow!("Pass a note, approved, or both"));
+        }
+        let staged = state
+            .modify_staged_operation(None, |staged| {
+                if let Some(note) = self.note {
+                    staged.review.notes.push(note);
+                }
+                if let Some(approved) = self.approved {
+                    staged.review.approved = Some(approved);
+                }
+            })?
+            .ok_or_else(|| anyhow!("No operation is currently staged"))?;
+        Ok(format!(
+            "Annotated the staged edit\n\n{}",
+            staged.review.describe()
+        ))
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Stage one of the recipes list_recipes describes, filled in with parameters, and see a
+/// preview of it like preview_edit
+///
+/// A recipe knows where its text goes, like the end of a Rust `tests` module or a package.json's
+/// `dependencies`, and how to indent and punctuate it there, so only the parts that vary are
+/// written.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "apply_recipe")]
+#[group(skip)]
+pub struct ApplyRecipe {
+    /// The file to add to
+    pub file_path: String,
+
+    /// The name of the recipe, like `test` or `dependency`
+    pub recipe: String,
+
+    /// The value of each of the recipe's parameters. Parameters with defaults can be left out.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[arg(skip)]
+    pub parameters: BTreeMap<String, String>,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for ApplyRecipe {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Add a test to the tests module of a Rust file",
+                item: Self {
+                    file_path: "src/lexer.rs".into(),
+                    recipe: "test".into(),
+                    parameters: [
+                        ("name", "skips_comments"),
+                        ("body", "assert_eq!(lex(\"// hi\\n1\"), [Token::Number(1)]);"),
+                    ]
+                    .into_iter()
+                    .map(|(name, value)| (name.into(), value.into()))
+                    .collect(),
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Add a dependency to a package.json",
+                item: Self {
+                    file_path: "package.json".into(),
+                    recipe: "dependency".into(),
+                    parameters: [("name", "zod"), ("version", "^3.23.0")]
+                        .into_iter()
+                        .map(|(name, value)| (name.into(), value.into()))
+                        .collect(),
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for ApplyRecipe {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        encoding::check_size(&file_path, self.allow_large)?;
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+        let recipes = language.editor().recipes();
+        let recipe = recipes
+            .iter()
+            .find(|recipe| recipe.name == self.recipe)
+            .ok_or_else(|| match recipes {
+                [] => anyhow!("There are no recipes for {language}"),
+                _ => anyhow!(
+                    "There is no {} recipe for {language}. Use one of: {}",
+                    self.recipe,
+                    recipes
+                        .iter()
+                        .map(|recipe| recipe.name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            })?;
+
+        let (source, _) = Encoding::read(&file_path)?;
+        let tree = language
+            .tree_sitter_parser()?
+            .parse(&source, None)
+            .ok_or_else(|| anyhow!("Unable to parse {}", self.file_path))?;
+        let change = recipe
+            .change(language, &tree, &source, &self.parameters)
+            .map_err(|message| anyhow!(message))?;
+        if change.content == source[change.range.clone()] {
+            return Ok(format!(
+                "{} already has that {}",
+                self.file_path, recipe.name
+            ));
+        }
+
+        let (operation, end_byte) = if change.range.is_empty() {
+            (Operation::InsertAfter, None)
+        } else {
+            (Operation::Replace, Some(change.range.end))
+        };
+        let selector = Selector::anchor(operation, change.anchor);
+        state.stage_edit_at(
+            change.content,
+            selector,
+            language,
+            file_path,
+            EditPosition {
+                start_byte: change.range.start,
+                end_byte,
+            },
+        )
+    }
+}
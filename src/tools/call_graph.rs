@@ -0,0 +1,258 @@
+use crate::{
+    call_graph::{self, Function},
+    encoding::Encoding,
+    state::SemanticEditTools,
+    workspace::Page,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, path::Path};
+
+/// List which functions in a file call which, as caller → callee edges
+///
+/// Use this to judge the blast radius of changing a function without reading the whole file.
+/// Calls are matched by name, so calls to a same-named method of another type are included.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "call_graph")]
+#[group(skip)]
+pub struct CallGraph {
+    /// Path to the source file
+    pub file_path: String,
+
+    /// Only show this function's callers and callees, and everything that can reach it. Use the
+    /// qualified name (`Type::method` or `Class.method`) for methods.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub function: Option<String>,
+
+    /// Also list calls to functions that are not defined in this file
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub include_external: bool,
+
+    /// Maximum number of calls to list at once. Defaults to 100. When there are more, the
+    /// response ends with a continuation token for the next page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub page_size: Option<usize>,
+
+    /// Continuation token from a previous page. Pass it with the same file and options for the
+    /// next page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub continuation: Option<String>,
+}
+
+impl WithExamples for CallGraph {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Every call between functions in a file",
+                item: Self {
+                    file_path: "src/parser.rs".into(),
+                    function: None,
+                    include_external: false,
+                    page_size: None,
+                    continuation: None,
+                },
+            },
+            Example {
+                description: "Everything that would be affected by changing one method",
+                item: Self {
+                    file_path: "src/parser.rs".into(),
+                    function: Some("Parser::next_token".into()),
+                    include_external: false,
+                    page_size: None,
+                    continuation: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for CallGraph {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file = state.resolve_path(&self.file_path, None)?;
+        let dialect = state.dialects(None)?.get(&file);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file, dialect)?;
+        let query = language.calls_query().ok_or_else(|| {
+            anyhow!("Call graphs are not supported for {language} files like {}", self.file_path)
+        })?;
+
+        let (source, _) = Encoding::read(&file)?;
+        let tree = language
+            .tree_sitter_parser()?
+            .parse(&source, None)
+            .ok_or_else(|| anyhow!("Unable to parse {}", self.file_path))?;
+        let graph = call_graph::call_graph(query, &tree, &source);
+
+        let target = self
+            .function
+            .as_deref()
+            .map(|function| find(&graph, function))
+            .transpose()?;
+        let mut page = Page::new(self.page_size, self.continuation.as_deref())?;
+        let call = |(caller, callee): &(String, String), count: &usize| {
+            format!("{caller} → {callee}{}", times(*count))
+        };
+        let internal = graph
+            .edges
+            .iter()
+            .filter(|_| target.is_none())
+            .map(|(edge, count)| (false, call(edge, count)));
+        let external = graph
+            .external
+            .iter()
+            .filter(|_| self.include_external)
+            .filter(|((caller, _), _)| target.is_none_or(|target| *caller == target.name))
+            .map(|(edge, count)| (true, call(edge, count)));
+        page.extend(Path::new(&self.file_path), internal.chain(external));
+        let (external, internal): (Vec<_>, Vec<_>) =
+            page.items.iter().partition(|(external, _)| *external);
+
+        let mut response = match target {
+            Some(target) => focus(&graph, target)?,
+            None => overview(&graph, &self.file_path, &internal, page.next().is_none())?,
+        };
+
+        if !external.is_empty() {
+            response.push_str("\nCalls to functions defined elsewhere:\n");
+            for (_, call) in external {
+                writeln!(response, "{call}")?;
+            }
+        }
+
+        if let Some(cursor) = page.next() {
+            write!(
+                response,
+                "\nThere are more calls than fit in one page of {}. Call call_graph again with \
+                 the same file and continuation: \"{cursor}\" for the next page.",
+                page.size()
+            )?;
+        }
+
+        Ok(response)
+    }
+}
+
+/// The totals for the whole file, this `page` of its calls, and on the last page the functions
+/// nothing in the file calls
+fn overview(
+    graph: &call_graph::CallGraph,
+    file_path: &str,
+    page: &[&(bool, String)],
+    last_page: bool,
+) -> Result<String> {
+    if graph.functions.is_empty() {
+        return Ok(format!("No functions are defined in {file_path}\n"));
+    }
+
+    let calls: usize = graph.edges.values().sum();
+    let mut response = format!(
+        "Call graph of {file_path}: {} function{}, {calls} call{} between them\n\n",
+        graph.functions.len(),
+        if graph.functions.len() == 1 { "" } else { "s" },
+        if calls == 1 { "" } else { "s" }
+    );
+    for (_, call) in page {
+        writeln!(response, "{call}")?;
+    }
+
+    let uncalled = graph
+        .functions
+        .iter()
+        .filter(|function| !graph.edges.keys().any(|(_, callee)| *callee == function.name))
+        .map(|function| format!("{} (line {})", function.name, function.line))
+        .collect::<Vec<_>>();
+    if last_page && !uncalled.is_empty() {
+        writeln!(
+            response,
+            "\nNot called from within this file: {}",
+            uncalled.join(", ")
+        )?;
+    }
+
+    Ok(response)
+}
+
+/// Find a function by its qualified name, or by its bare name if that is unambiguous
+fn find<'a>(graph: &'a call_graph::CallGraph, function: &str) -> Result<&'a Function> {
+    let matching = graph
+        .functions
+        .iter()
+        .filter(|candidate| {
+            candidate.name == function
+                || candidate.name.rsplit([':', '.']).next() == Some(function)
+        })
+        .collect::<Vec<_>>();
+    match matching.as_slice() {
+        [] => Err(anyhow!(
+            "No function named {function} is defined in this file. Functions: {}",
+            graph
+                .functions
+                .iter()
+                .map(|function| function.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        [target] => Ok(*target),
+        _ => Err(anyhow!(
+            "{function} is ambiguous. Use one of: {}",
+            matching
+                .iter()
+                .map(|function| function.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+fn focus(graph: &call_graph::CallGraph, target: &Function) -> Result<String> {
+    let list = |edges: Vec<String>| {
+        if edges.is_empty() {
+            "nothing in this file".to_string()
+        } else {
+            edges.join(", ")
+        }
+    };
+    let callers = graph
+        .edges
+        .iter()
+        .filter(|((_, callee), _)| *callee == target.name)
+        .map(|((caller, _), count)| format!("{caller}{}", times(*count)))
+        .collect();
+    let callees = graph
+        .edges
+        .iter()
+        .filter(|((caller, _), _)| *caller == target.name)
+        .map(|((_, callee), count)| format!("{callee}{}", times(*count)))
+        .collect();
+    let reachable_from = graph
+        .transitive_callers(&target.name)
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+    Ok(format!(
+        "{} (line {})\nCalled by: {}\nCalls: {}\nReachable from: {}\n",
+        target.name,
+        target.line,
+        list(callers),
+        list(callees),
+        list(reachable_from)
+    ))
+}
+
+fn times(count: usize) -> String {
+    if count == 1 {
+        String::new()
+    } else {
+        format!(" (×{count})")
+    }
+}
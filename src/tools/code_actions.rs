@@ -0,0 +1,171 @@
+use crate::{
+    editor::EditPosition,
+    encoding::Encoding,
+    lsp::{CodeActions as Outcome, LanguageServer, unchanged_ends},
+    searcher::find_positions,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// List the code actions a language server offers at a location, like adding a missing import,
+/// filling in match arms, or inlining a variable, and stage a chosen one for preview
+///
+/// Requires a language server configured with `SEMANTIC_EDIT_LSP_<LANGUAGE>`. A staged action is
+/// applied with persist_edit like any other edit. Actions that change more than one file can't be
+/// staged.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "code_actions")]
+#[group(skip)]
+pub struct CodeActions {
+    /// Path to the source file
+    pub file_path: String,
+
+    /// Text at the location to get actions for, like an unresolved name or the `match` of a
+    /// non-exhaustive match. Whitespace doesn't matter, but the anchor must be unique in the file.
+    pub anchor: String,
+
+    /// The title or number of an action from a previous listing, to stage it for preview. Omit
+    /// to list the available actions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub action: Option<String>,
+}
+
+impl WithExamples for CodeActions {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "See what the language server can do for a non-exhaustive match",
+                item: Self {
+                    file_path: "src/parser.rs".into(),
+                    anchor: "match token.kind".into(),
+                    action: None,
+                },
+            },
+            Example {
+                description: "Stage the second action from that listing",
+                item: Self {
+                    file_path: "src/parser.rs".into(),
+                    anchor: "match token.kind".into(),
+                    action: Some("2".into()),
+                },
+            },
+            Example {
+                description: "Stage an action by its title",
+                item: Self {
+                    file_path: "src/parser.rs".into(),
+                    anchor: "HashMap::new()".into(),
+                    action: Some("Import `std::collections::HashMap`".into()),
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for CodeActions {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+        let server = LanguageServer::configured(language.name()).ok_or_else(|| {
+            anyhow!(
+                "No language server is configured for {language}. Set \
+                 SEMANTIC_EDIT_LSP_{} to the command that starts one.",
+                language.name().to_string().to_uppercase()
+            )
+        })?;
+
+        let (source, _) = Encoding::read(&file_path)?;
+        let range = match find_positions(&source, self.anchor.trim())
+            .map_err(|message| anyhow!(message))?
+            .as_slice()
+        {
+            [(start, end)] => *start..*end,
+            matches => {
+                return Err(anyhow!(
+                    "Anchor \"{}\" matches {} places in {}. Use a longer anchor that only \
+                     matches one.",
+                    self.anchor,
+                    matches.len(),
+                    self.file_path
+                ));
+            }
+        };
+
+        let outcome = server
+            .code_actions(
+                language.name(),
+                &file_path,
+                &source,
+                range,
+                self.action.as_deref(),
+            )
+            .map_err(|error| anyhow!("{} code actions failed: {error}", server.name()))?;
+
+        match outcome {
+            Outcome::Available(actions) if actions.is_empty() => Ok(format!(
+                "{} offers no code actions at \"{}\"",
+                server.name(),
+                self.anchor
+            )),
+
+            Outcome::Available(actions) => {
+                let mut response = format!(
+                    "Code actions from {} at \"{}\":\n",
+                    server.name(),
+                    self.anchor
+                );
+                for (index, action) in actions.iter().enumerate() {
+                    write!(response, "{:>3}. {}", index + 1, action.title)?;
+                    if let Some(kind) = &action.kind {
+                        write!(response, " ({kind})")?;
+                    }
+                    response.push('\n');
+                }
+                response.push_str(
+                    "\nUse code_actions again with `action` set to a title or number to preview \
+                     that action.",
+                );
+                Ok(response)
+            }
+
+            Outcome::Applied { title, text } => {
+                let (prefix, suffix) = unchanged_ends(&source, &text);
+                if prefix == source.len() && prefix == text.len() {
+                    return Ok(format!("`{title}` makes no changes to {}", self.file_path));
+                }
+
+                // describe the staged edit by the line it starts on
+                let line_start = source[..prefix].rfind('\n').map_or(0, |index| index + 1);
+                let line_end = source[prefix..]
+                    .find('\n')
+                    .map_or(source.len(), |index| prefix + index);
+                let selector = Selector::anchor(
+                    Operation::Replace,
+                    source[line_start..line_end].trim(),
+                );
+
+                let message = state.stage_edit_at(
+                    text[prefix..text.len() - suffix].to_string(),
+                    selector,
+                    language,
+                    file_path,
+                    EditPosition {
+                        start_byte: prefix,
+                        end_byte: Some(source.len() - suffix),
+                    },
+                )?;
+                Ok(format!("Code action: {title}\n\n{message}"))
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{
+    editor::format_patch,
+    encoding::Encoding,
+    response_format::ResponseFormat,
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow};
+use diffy::DiffOptions;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Show the differences between two files, or between a file and an earlier version of it
+///
+/// Versions are the identifiers persist_edit reports. The versions from before and after recent
+/// writes by these tools are kept for comparing with, so `version` can be the one reported by the
+/// write before the changes to review.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "diff")]
+#[group(skip)]
+pub struct Diff {
+    /// The file to compare, shown as the newer side of the diff
+    pub file_path: String,
+
+    /// Another file to compare it with, shown as the older side
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub other_path: Option<String>,
+
+    /// A version to compare it with instead of another file, shown as the older side
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub version: Option<String>,
+}
+
+impl WithExamples for Diff {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Compare a file with the one it was copied from",
+                item: Self {
+                    file_path: "src/handlers/users.rs".into(),
+                    other_path: Some("src/handlers/accounts.rs".into()),
+                    version: None,
+                },
+            },
+            Example {
+                description: "Review everything that changed in a file since an earlier write",
+                item: Self {
+                    file_path: "src/main.rs".into(),
+                    other_path: None,
+                    version: Some("a442db5ec26f5be0".into()),
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for Diff {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let read = |path: &str| -> Result<String> {
+            let path = state.resolve_path(path, None)?;
+            Ok(Encoding::read(&path)?.0)
+        };
+        let current = read(&self.file_path)?;
+        let (older, name) = match (&self.other_path, &self.version) {
+            (Some(other_path), None) => (read(other_path)?, other_path.clone()),
+            (None, Some(version)) => (
+                state.versions().get(version)?.to_string(),
+                format!("version `{}`", version.trim()),
+            ),
+            _ => {
+                return Err(anyhow!(
+                    "Pass either other_path or version to compare {} with",
+                    self.file_path
+                ));
+            }
+        };
+
+        if older == current {
+            return Ok(format!("{} is the same as {name}", self.file_path));
+        }
+        let patch = DiffOptions::new().create_patch(&older, &current);
+        Ok(format!(
+            "Changes from {name} to {}:\n\n{}",
+            self.file_path,
+            ResponseFormat::configured().section("Diff", &format_patch(&patch), "diff")
+        ))
+    }
+}
@@ -0,0 +1,42 @@
+use crate::{doctor, state::SemanticEditTools};
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Check that the formatters edits are run through are installed, session storage is writable,
+/// grammars are compatible, and the working directory can be read and written, with steps to fix
+/// anything that isn't. Run this when edits fail for reasons that aren't about their content.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "doctor")]
+#[group(skip)]
+pub struct Doctor {}
+
+impl WithExamples for Doctor {
+    fn examples() -> Vec<Example<Self>> {
+        vec![Example {
+            description: "finding out why every edit to a Python file is rejected",
+            item: Self {},
+        }]
+    }
+}
+
+impl Tool<SemanticEditTools> for Doctor {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let checks = doctor::run(state);
+        let problems = checks.iter().filter(|check| check.is_problem()).count();
+        let mut response = checks
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        response.push_str(&match problems {
+            0 => "\n\nNo problems found.".to_string(),
+            1 => "\n\n1 problem found.".to_string(),
+            problems => format!("\n\n{problems} problems found."),
+        });
+        Ok(response)
+    }
+}
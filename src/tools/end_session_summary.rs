@@ -0,0 +1,40 @@
+use crate::state::SemanticEditTools;
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Summarize what this session changed: files touched with lines added and removed, previews
+/// rejected by validation, and formatters run. The counts start over afterwards, so call this
+/// once at the end of a task to report on it. A session whose stored data couldn't be read when
+/// the server started says so, and where that data was backed up.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "end_session_summary")]
+#[group(skip)]
+pub struct EndSessionSummary {}
+
+impl WithExamples for EndSessionSummary {
+    fn examples() -> Vec<Example<Self>> {
+        vec![Example {
+            description: "reporting what was changed before finishing a task",
+            item: Self {},
+        }]
+    }
+}
+
+impl Tool<SemanticEditTools> for EndSessionSummary {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let stats = state.take_stats(None)?;
+        let summary = if stats.is_empty() {
+            "Nothing was written or rejected in this session.".into()
+        } else {
+            stats.to_string()
+        };
+        Ok(match state.dropped_session(None) {
+            Some(dropped) => format!("{summary}\n\n{dropped}"),
+            None => summary,
+        })
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{
+    languages::LanguageName,
+    state::SemanticEditTools,
+    validation::{ContextValidator, SyntaxError},
+};
+use anyhow::{Result, anyhow, bail};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Explain a validation rule that rejected an edit: what it checks for, the query pattern that
+/// matched, and an example of code that follows it
+///
+/// Validation errors name the rule they come from, like `rule `self.outside.impl``. Pass the
+/// rejected `content` to see exactly which part of it the rule flags.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "explain_validation_rule")]
+#[group(skip)]
+pub struct ExplainValidationRule {
+    /// The rule, named as in the validation error
+    pub rule: String,
+
+    /// The language whose rule to explain. Defaults to every language with a rule of this name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(short, long, value_enum)]
+    pub language: Option<LanguageName>,
+
+    /// Code to check against the rule, like the content of a rejected edit, to list the code it
+    /// flags
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub content: Option<String>,
+}
+
+impl WithExamples for ExplainValidationRule {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Explain the rule that rejected an edit",
+                item: Self {
+                    rule: "self.outside.impl".into(),
+                    language: None,
+                    content: None,
+                },
+            },
+            Example {
+                description: "See which part of a rejected edit a rule flags",
+                item: Self {
+                    rule: "function.in.function".into(),
+                    language: Some(LanguageName::Rust),
+                    content: Some(
+                        "fn outer() {\n    fn inner() {}\n    inner();\n}".into(),
+                    ),
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for ExplainValidationRule {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let rule = self.rule.trim().trim_matches('`');
+        let rule = rule.strip_prefix("invalid.").unwrap_or(rule);
+        let languages = state
+            .language_registry()
+            .languages()
+            .filter(|language| self.language.is_none_or(|name| language.name() == name))
+            .filter_map(|language| {
+                Some((
+                    language,
+                    language.validation_query()?,
+                    language.validation_source()?,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let with_rule = languages
+            .iter()
+            .filter(|(_, query, _)| ContextValidator::rules(query).contains(&rule))
+            .collect::<Vec<_>>();
+        if with_rule.is_empty() {
+            let mut rules = languages
+                .iter()
+                .flat_map(|(language, query, _)| {
+                    ContextValidator::rules(query)
+                        .into_iter()
+                        .map(move |rule| format!("{rule} ({language})"))
+                })
+                .collect::<Vec<_>>();
+            rules.sort();
+            rules.dedup();
+            bail!(
+                "There is no validation rule named `{rule}`{}. Rules: {}",
+                self.language
+                    .map(|language| format!(" for {language}"))
+                    .unwrap_or_default(),
+                rules.join(", ")
+            );
+        }
+
+        let explanation = ContextValidator::explain(rule);
+        let mut response = format!(
+            "Rule `{rule}`\n\n{}\n💡 {}\n",
+            explanation.message, explanation.suggestion
+        );
+
+        for (language, query, source) in with_rule {
+            write!(response, "\n=== {language} ===\nMatched by:\n")?;
+            for pattern in ContextValidator::patterns(query, source, rule) {
+                writeln!(response, "{pattern}\n")?;
+            }
+
+            if let Some(content) = &self.content {
+                let tree = language
+                    .tree_sitter_parser()?
+                    .parse(content, None)
+                    .ok_or_else(|| anyhow!("Unable to parse the content as {language}"))?;
+                let flagged = ContextValidator::validate_tree(&tree, query, content, None, None)
+                    .violations
+                    .into_iter()
+                    .filter(|violation| violation.rule == rule)
+                    .collect::<Vec<_>>();
+                if flagged.is_empty() {
+                    response.push_str(
+                        "The rule flags nothing in the content. Rules are checked against the \
+                         whole file, so the code around the edit may be what breaks it.\n\n",
+                    );
+                } else {
+                    response.push_str("Flagged in the content:\n");
+                    for violation in flagged {
+                        let location = SyntaxError::new(
+                            content,
+                            violation.node.byte_range(),
+                        );
+                        writeln!(
+                            response,
+                            "- {location}: {}",
+                            &content[violation.node.byte_range()]
+                        )?;
+                    }
+                    response.push('\n');
+                }
+            }
+        }
+
+        if let Some(example) = explanation.example {
+            write!(response, "Compliant example:\n{example}\n")?;
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{
+    encoding::Encoding,
+    metrics::{Metric, function_metrics},
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Report size and complexity for every function in a file: line count, parameter count,
+/// deepest nesting, and cyclomatic complexity
+///
+/// Use this to find the functions most in need of refactoring before reading them.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "file_metrics")]
+#[group(skip)]
+pub struct FileMetrics {
+    /// Path to the source file
+    pub file_path: String,
+
+    /// List functions from the highest value of this metric down, instead of in file order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub sort_by: Option<Metric>,
+}
+
+impl WithExamples for FileMetrics {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Measure every function in a file",
+                item: Self {
+                    file_path: "src/parser.rs".into(),
+                    sort_by: None,
+                },
+            },
+            Example {
+                description: "Find the most complex functions first",
+                item: Self {
+                    file_path: "src/parser.rs".into(),
+                    sort_by: Some(Metric::Complexity),
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for FileMetrics {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file = state.resolve_path(&self.file_path, None)?;
+        let dialect = state.dialects(None)?.get(&file);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file, dialect)?;
+        let query = language.calls_query().ok_or_else(|| {
+            anyhow!(
+                "Function metrics are not supported for {language} files like {}",
+                self.file_path
+            )
+        })?;
+
+        let (source, _) = Encoding::read(&file)?;
+        let tree = language
+            .tree_sitter_parser()?
+            .parse(&source, None)
+            .ok_or_else(|| anyhow!("Unable to parse {}", self.file_path))?;
+        let mut metrics = function_metrics(query, &tree, &source);
+        if metrics.is_empty() {
+            return Ok(format!("No functions are defined in {}", self.file_path));
+        }
+        if let Some(metric) = self.sort_by {
+            metrics.sort_by_key(|function| std::cmp::Reverse(metric.of(function)));
+        }
+
+        let mut response = format!(
+            "Metrics for the {} function{} in {}:\n\n line  lines  params  nesting  complexity  function\n",
+            metrics.len(),
+            if metrics.len() == 1 { "" } else { "s" },
+            self.file_path
+        );
+        for function in metrics {
+            writeln!(
+                response,
+                "{:>5}  {:>5}  {:>6}  {:>7}  {:>10}  {}",
+                function.line,
+                function.lines,
+                function.parameters,
+                function.nesting,
+                function.complexity,
+                function.name
+            )?;
+        }
+
+        Ok(response)
+    }
+}
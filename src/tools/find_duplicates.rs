@@ -0,0 +1,207 @@
+use crate::{
+    budget::Budget,
+    duplicates::{fingerprints, near_duplicates},
+    encoding::Encoding,
+    languages::LanguageName,
+    state::SemanticEditTools,
+    workspace::{self, Cursor},
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Write};
+
+/// Similarity percentage required when neither `similarity` nor a per-language threshold is
+/// given
+const DEFAULT_SIMILARITY: u8 = 90;
+
+/// Pairs reported when no max_results is given
+const DEFAULT_MAX_RESULTS: usize = 20;
+
+/// Find functions across the workspace whose bodies have nearly the same structure, as
+/// candidates for merging during cleanup refactors
+///
+/// Bodies are compared by the shape of their syntax trees, so copies that only differ in names,
+/// literals, comments, or formatting are reported as identical. Very small functions are
+/// ignored.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "find_duplicates")]
+#[group(skip)]
+pub struct FindDuplicates {
+    /// Directory to search. Defaults to the session working directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub path: Option<String>,
+
+    /// Minimum similarity to report, as a percentage. Defaults to 90.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub similarity: Option<u8>,
+
+    /// Per-language minimum similarity percentages overriding `similarity`, like
+    /// `{"python": 80}`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[arg(skip)]
+    pub language_similarity: HashMap<LanguageName, u8>,
+
+    /// Maximum number of pairs to report. Defaults to 20.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub max_results: Option<usize>,
+
+    /// Stop reading files after roughly this many milliseconds and report duplicates among the
+    /// files read so far, along with a continuation token for searching the rest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_ms: Option<u64>,
+
+    /// Continuation token from a previous search that reached its time limit. Pass it with the
+    /// same path to search the files that search didn't reach, which are compared among
+    /// themselves.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub continuation: Option<String>,
+}
+
+impl WithExamples for FindDuplicates {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Find copy-pasted functions in the project",
+                item: Self {
+                    path: None,
+                    similarity: None,
+                    language_similarity: HashMap::new(),
+                    max_results: None,
+                    timeout_ms: None,
+                    continuation: None,
+                },
+            },
+            Example {
+                description: "Look for looser matches in Python, where bodies tend to be shorter",
+                item: Self {
+                    path: Some("app".into()),
+                    similarity: Some(90),
+                    language_similarity: HashMap::from([(LanguageName::Python, 75)]),
+                    max_results: Some(10),
+                    timeout_ms: None,
+                    continuation: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for FindDuplicates {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let budget = Budget::from_timeout_ms(self.timeout_ms);
+        let root = match &self.path {
+            Some(path) => state.resolve_path(path, None)?,
+            None => state.get_context(None)?.ok_or_else(|| {
+                anyhow!("No working directory is set. Use set_working_directory or pass a path.")
+            })?,
+        };
+
+        let default_similarity = self.similarity.unwrap_or(DEFAULT_SIMILARITY);
+        for similarity in self
+            .language_similarity
+            .values()
+            .chain([&default_similarity])
+        {
+            if *similarity > 100 {
+                return Err(anyhow!(
+                    "Similarity is a percentage from 0 to 100, not {similarity}"
+                ));
+            }
+        }
+
+        let dialects = state.dialects(None)?;
+        let registry = state.language_registry();
+        let mut all = vec![];
+        let cursor = self
+            .continuation
+            .as_deref()
+            .map(str::parse::<Cursor>)
+            .transpose()?;
+        let mut files = workspace::walk(&root, budget).resume(cursor.as_ref());
+        for file in &mut files {
+            let path = root.join(&file);
+            let Some(language) = dialects
+                .get(&path)
+                .or_else(|| registry.detect_language_from_path(&path))
+                .map(|name| registry.get_language(name))
+            else {
+                continue;
+            };
+            let Some(query) = language.calls_query() else {
+                continue;
+            };
+            let Ok((source, _)) = Encoding::read(&path) else {
+                continue;
+            };
+            let Some(tree) = language.tree_sitter_parser()?.parse(&source, None) else {
+                continue;
+            };
+            all.extend(fingerprints(language.name(), query, &tree, &source, file));
+        }
+
+        let duplicates = near_duplicates(&all, |language| {
+            self.language_similarity
+                .get(&language)
+                .copied()
+                .unwrap_or(default_similarity) as f64
+                / 100.0
+        });
+        let max_results = self.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+
+        let mut response = if duplicates.is_empty() {
+            format!(
+                "No near-duplicate functions among the {} functions compared\n",
+                all.len()
+            )
+        } else {
+            format!(
+                "{} near-duplicate function pair{} among the {} functions compared{}:\n",
+                duplicates.len(),
+                if duplicates.len() == 1 { "" } else { "s" },
+                all.len(),
+                if duplicates.len() > max_results {
+                    format!(", showing the top {max_results}")
+                } else {
+                    String::new()
+                }
+            )
+        };
+
+        for duplicate in duplicates.iter().take(max_results) {
+            let (first, second) = (duplicate.first, duplicate.second);
+            writeln!(
+                response,
+                "{:>3.0}%  {}:{} `{}` ({} lines) ≈ {}:{} `{}` ({} lines)",
+                duplicate.similarity * 100.0,
+                first.file.display(),
+                first.line,
+                first.name,
+                first.line_count,
+                second.file.display(),
+                second.line,
+                second.name,
+                second.line_count
+            )?;
+        }
+
+        if let Some(cursor) = files.continuation() {
+            write!(
+                response,
+                "\nReached the time limit before reading every file, so duplicates in the rest \
+                 are not reported. Call find_duplicates again with the same path and \
+                 continuation: \"{cursor}\" to search them."
+            )?;
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,269 @@
+use crate::{
+    budget::Budget,
+    editor::format_patch,
+    encoding::{self, Encoding},
+    formatting::Formatter,
+    guardrails::{self, changed_bytes},
+    protected,
+    response_format::{FileReport, FileStatus, ResponseFormat},
+    state::SemanticEditTools,
+    workspace::{self, Cursor},
+};
+use anyhow::{Result, anyhow};
+use diffy::DiffOptions;
+use globset::Glob;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Run each language's formatter over every file under a directory, or those matching a glob,
+/// and report on each file it changes or skips
+///
+/// This uses the same formatters as preview_edit and persist_edit. Nothing is written unless
+/// `apply` is true, so review the diff first. Files whose formatter fails, usually because they
+/// have syntax errors, are listed and left alone.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "format_files")]
+#[group(skip)]
+pub struct FormatFiles {
+    /// Directory to format. Defaults to the session working directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub path: Option<String>,
+
+    /// Only format files whose path relative to `path` matches this glob, like `src/**/*.rs`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub glob: Option<String>,
+
+    /// Write the formatted files after reviewing the diff
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub apply: bool,
+
+    /// Write the changes even though they exceed the configured write limits or change protected
+    /// files
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub confirm: bool,
+
+    /// Stop after roughly this many milliseconds and report on the files reached so far, along
+    /// with a continuation token for formatting the rest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_ms: Option<u64>,
+
+    /// Continuation token from a previous call that reached its time limit. Pass it with the same
+    /// path and glob to format the files that call didn't reach.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub continuation: Option<String>,
+}
+
+impl WithExamples for FormatFiles {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "See what formatting the whole project would change after a refactor",
+                item: Self {
+                    path: None,
+                    glob: None,
+                    apply: false,
+                    confirm: false,
+                    timeout_ms: None,
+                    continuation: None,
+                },
+            },
+            Example {
+                description: "Format the reviewed Rust files in one directory",
+                item: Self {
+                    path: Some("src/parser".into()),
+                    glob: Some("**/*.rs".into()),
+                    apply: true,
+                    confirm: false,
+                    timeout_ms: None,
+                    continuation: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for FormatFiles {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let root = match &self.path {
+            Some(path) => state.resolve_path(path, None)?,
+            None => state.get_context(None)?.ok_or_else(|| {
+                anyhow!("No working directory is set. Use set_working_directory or pass a path.")
+            })?,
+        };
+        let glob = self
+            .glob
+            .as_deref()
+            .map(|glob| {
+                Glob::new(glob)
+                    .map(|glob| glob.compile_matcher())
+                    .map_err(|error| anyhow!("{glob} is not a valid glob: {error}"))
+            })
+            .transpose()?;
+
+        let dialects = state.dialects(None)?;
+        let registry = state.language_registry();
+        let mut changed = vec![];
+        let mut files = vec![];
+        let mut total = 0;
+        let cursor = self
+            .continuation
+            .as_deref()
+            .map(str::parse::<Cursor>)
+            .transpose()?;
+        let max_size = encoding::max_file_size()?;
+        let mut walk = workspace::walk(&root, Budget::from_timeout_ms(self.timeout_ms))
+            .resume(cursor.as_ref());
+        for file in &mut walk {
+            if glob.as_ref().is_some_and(|glob| !glob.is_match(&file)) {
+                continue;
+            }
+            let path = root.join(&file);
+            let Some(language) = dialects
+                .get(&path)
+                .or_else(|| registry.detect_language_from_path(&path))
+                .map(|name| registry.get_language(name))
+            else {
+                continue;
+            };
+            let configured = Formatter::configured(language);
+            let Some(formatter) = configured.name(language) else {
+                continue;
+            };
+            total += 1;
+            let skipped = |summary: String| FileReport {
+                file: file.display().to_string(),
+                status: FileStatus::Skipped,
+                summary,
+                body: None,
+            };
+            if encoding::check_size_within(&path, max_size).is_err() {
+                files.push(skipped("larger than the size limit for editing".into()));
+                continue;
+            }
+            let Ok((source, encoding)) = Encoding::read(&path) else {
+                files.push(skipped("unable to read it".into()));
+                continue;
+            };
+            let output = match configured.format(language, &source, &path) {
+                Ok(output) => output,
+                Err(error) => {
+                    let error = error.to_string();
+                    files.push(skipped(format!(
+                        "{formatter} failed: {}",
+                        error.lines().next().unwrap_or_default().trim()
+                    )));
+                    continue;
+                }
+            };
+            if output == source {
+                continue;
+            }
+            if encoding.read_only_reason().is_some() {
+                files.push(skipped("not valid UTF-8".into()));
+                continue;
+            }
+
+            let patch = DiffOptions::new().create_patch(&source, &output);
+            files.push(FileReport {
+                file: file.display().to_string(),
+                status: FileStatus::Changed,
+                summary: format!("formatted with {formatter}"),
+                body: Some(format_patch(&patch)),
+            });
+            changed.push((path, source, output, encoding, formatter));
+        }
+        let stopped = walk
+            .continuation()
+            .map(|cursor| {
+                format!(
+                    "\nReached the time limit before reaching every file. Call format_files again \
+                     with the same path, glob and continuation: \"{cursor}\" for the rest."
+                )
+            })
+            .unwrap_or_default();
+
+        if files.is_empty() {
+            return Ok(format!(
+                "{} already formatted{stopped}",
+                if total == 1 {
+                    "The 1 file with a formatter is".to_string()
+                } else {
+                    format!("All {total} files with a formatter are")
+                }
+            ));
+        }
+
+        let mut response = ResponseFormat::configured().file_report(
+            &format!(
+                "Formatting {} {} file{}.",
+                if self.apply { "changed" } else { "would change" },
+                changed.len(),
+                if changed.len() == 1 { "" } else { "s" },
+            ),
+            &files,
+            total,
+        );
+
+        let protected = changed
+            .iter()
+            .filter_map(|(path, ..)| protected::reason(path).transpose())
+            .collect::<Result<Vec<_>>>()?;
+        for protected in &protected {
+            writeln!(response, "\n⚠️ PROTECTED FILE: {protected}")?;
+        }
+
+        let exceeded = if self.apply && !self.confirm && !changed.is_empty() {
+            let bytes = changed
+                .iter()
+                .map(|(_, source, output, ..)| changed_bytes(source.as_bytes(), output.as_bytes()))
+                .sum();
+            let mut exceeded = protected;
+            exceeded.extend(state.guardrails().exceeded(changed.len(), bytes));
+            exceeded
+        } else {
+            vec![]
+        };
+
+        if !exceeded.is_empty() {
+            write!(
+                response,
+                "\n{}",
+                guardrails::confirmation_required(
+                    &exceeded,
+                    "call format_files again with apply and confirm"
+                )
+            )?;
+        } else if self.apply {
+            if !changed.is_empty() {
+                state.guardrails().record_persist();
+            }
+            let commit = state.commit_fn_mut().take();
+            for (path, source, output, encoding, formatter) in changed {
+                match &commit {
+                    Some(commit) => commit(path.clone(), output.clone()),
+                    None => std::fs::write(&path, encoding.encode(output.clone()))?,
+                }
+                state.record_write(&path, Some(&source), &output, Some(&formatter), None)?;
+            }
+        } else if !changed.is_empty() {
+            write!(
+                response,
+                "\nNothing was written. Call format_files again with apply to write these \
+                 changes."
+            )?;
+        }
+        response.push_str(&stopped);
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,42 @@
+use crate::state::SemanticEditTools;
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Show the working context path for a session, along with any directories saved by
+/// push_working_directory
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "get_working_directory")]
+#[group(skip)]
+pub struct GetWorkingDirectory {}
+
+impl WithExamples for GetWorkingDirectory {
+    fn examples() -> Vec<Example<Self>> {
+        vec![Example {
+            description: "checking which project relative paths resolve against",
+            item: Self {},
+        }]
+    }
+}
+
+impl Tool<SemanticEditTools> for GetWorkingDirectory {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let mut response = match state.get_context(None)? {
+            Some(path) => format!("Working directory is {}", path.display()),
+            None => "No working directory is set".to_string(),
+        };
+
+        let stack = state.directory_stack(None)?;
+        if !stack.is_empty() {
+            response.push_str("\n\nSaved directories, most recent first:");
+            for path in stack.iter().rev() {
+                response.push_str(&format!("\n- {}", path.display()));
+            }
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{languages::LanguageCommon, state::SemanticEditTools};
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// List the recipes apply_recipe can fill in: templates for common insertions like a test in a
+/// Rust `tests` module or a dependency in a package.json or Cargo.toml, with their parameters
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "list_recipes")]
+#[group(skip)]
+pub struct ListRecipes {
+    /// Only list the recipes for this file's language. Omit to list the recipes of every
+    /// language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub file_path: Option<String>,
+}
+
+impl WithExamples for ListRecipes {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "See the recipes for a Rust file",
+                item: Self {
+                    file_path: Some("src/lexer.rs".into()),
+                },
+            },
+            Example {
+                description: "See every language's recipes",
+                item: Self { file_path: None },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for ListRecipes {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let languages: Vec<&LanguageCommon> = match &self.file_path {
+            Some(file_path) => {
+                let file_path = state.resolve_path(file_path, None)?;
+                let dialect = state.dialects(None)?.get(&file_path);
+                vec![
+                    state
+                        .language_registry()
+                        .get_language_with_hint(&file_path, dialect)?,
+                ]
+            }
+            None => state.language_registry().languages().collect(),
+        };
+
+        let mut response = String::new();
+        for language in languages {
+            let recipes = language.editor().recipes();
+            if recipes.is_empty() {
+                continue;
+            }
+            writeln!(response, "Recipes for {language}:")?;
+            for recipe in recipes {
+                writeln!(
+                    response,
+                    "\n{}: {}, {}",
+                    recipe.name, recipe.description, recipe.placement
+                )?;
+                for parameter in recipe.parameters {
+                    write!(response, "- {}: {}", parameter.name, parameter.description)?;
+                    match parameter.default {
+                        Some(default) => writeln!(response, " (defaults to `{default}`)")?,
+                        None => writeln!(response)?,
+                    }
+                }
+                writeln!(response, "```\n{}\n```", recipe.template)?;
+            }
+            writeln!(response)?;
+        }
+
+        Ok(match (response.is_empty(), &self.file_path) {
+            (true, Some(file_path)) => format!("There are no recipes for {file_path}'s language"),
+            (true, None) => "There are no recipes".into(),
+            (false, _) => response.trim_end().to_string(),
+        })
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    editor::Editor, encoding::Encoding, html_diff, languages::LanguageName, lsp,
+    state::SemanticEditTools, versions,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Show edits that have been staged with preview_edit but not yet persisted, including any left
+/// over from before the server restarted
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "list_staged_edits")]
+#[group(skip)]
+pub struct ListStagedEdits {
+    /// Return the staged edits as an LSP `WorkspaceEdit` JSON object, so that editors and other
+    /// tools can review or apply them
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub workspace_edit: bool,
+
+    /// Write the staged edit's diff as a standalone HTML page, side by side and syntax
+    /// highlighted, to the session's directory, and return the page's path
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub html: bool,
+}
+
+impl WithExamples for ListStagedEdits {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "checking for pending edits after reconnecting",
+                item: Self {
+                    workspace_edit: false,
+                    html: false,
+                },
+            },
+            Example {
+                description: "handing the staged edit to an editor to apply",
+                item: Self {
+                    workspace_edit: true,
+                    html: false,
+                },
+            },
+            Example {
+                description: "giving a person a page to review the staged edit in their browser",
+                item: Self {
+                    workspace_edit: false,
+                    html: true,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for ListStagedEdits {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let staged = state.get_staged_operation(None)?;
+        if self.html {
+            let staged = staged.ok_or_else(|| anyhow!("No edits are staged"))?;
+            // grammars loaded at runtime are found again by extension, as for the edit itself
+            let language = state.language_registry().get_language_with_hint(
+                &staged.file_path,
+                Some(staged.language_name).filter(|name| *name != LanguageName::Other),
+            )?;
+            let editor = Editor::from_staged_operation(staged, state.language_registry())?;
+            let original = editor.source_code().to_string();
+            let (_, output, file_path) = editor.commit()?;
+            let output = output.ok_or_else(|| {
+                anyhow!("The staged edit no longer applies. Use preview_edit to stage it again.")
+            })?;
+            let edited = output.trim_start_matches('\u{feff}');
+
+            let directory = state.session_directory(None).join("diffs");
+            std::fs::create_dir_all(&directory)?;
+            let name = file_path
+                .file_name()
+                .map_or_else(|| "edit".into(), |name| name.to_string_lossy());
+            let page = directory.join(format!("{name}-{}.html", versions::of(edited)));
+            let title = state
+                .get_context(None)?
+                .and_then(|root| file_path.strip_prefix(root).ok())
+                .unwrap_or(&file_path)
+                .display()
+                .to_string();
+            std::fs::write(
+                &page,
+                html_diff::render(&title, language, &original, edited),
+            )?;
+            return Ok(format!("Wrote the staged diff to {}", page.display()));
+        }
+        if self.workspace_edit {
+            let mut files = vec![];
+            if let Some(staged) = staged.clone() {
+                let (_, output, file_path) =
+                    Editor::from_staged_operation(staged, state.language_registry())?.commit()?;
+                let output = output.ok_or_else(|| {
+                    anyhow!("The staged edit no longer applies. Use preview_edit to stage it again.")
+                })?;
+                let (original, _) = Encoding::read(&file_path)?;
+                let edited = output.strip_prefix('\u{feff}').unwrap_or(&output).to_string();
+                files.push((file_path, original, edited));
+            }
+
+            return Ok(serde_json::to_string_pretty(&lsp::workspace_edit(
+                files
+                    .iter()
+                    .map(|(file, original, edited)| (file.as_path(), &**original, &**edited)),
+            ))?);
+        }
+
+        Ok(match staged {
+            Some(staged) => format!(
+                "Staged edits:\n- {}\n\nUse persist_edit to apply it, retarget_edit to adjust \
+                 where it applies, or preview_edit to replace it.",
+                staged.describe()
+            ),
+            None => "No edits are staged".to_string(),
+        })
+    }
+}
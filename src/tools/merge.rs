@@ -0,0 +1,145 @@
+use crate::{
+    conflicts,
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    response_format::ResponseFormat,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+};
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Three-way merge two changed copies of a file's text with the text they both started from, and
+/// stage the result for preview
+///
+/// This reconciles an agent's changes with someone else's, like a user's edits made while the
+/// agent was working. Each of `base`, `ours`, and `theirs` is either text or a version reported
+/// by these tools, which keep the versions from before and after recent writes. When the changes
+/// overlap, nothing is staged and the merged text is shown with conflict markers to be resolved
+/// and staged with preview_edit.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "merge")]
+#[group(skip)]
+pub struct Merge {
+    /// The file to stage the merged text for
+    pub file_path: String,
+
+    /// The text both sides started from, or its version
+    pub base: String,
+
+    /// The other side's text, or its version
+    pub theirs: String,
+
+    /// This side's text, or its version. Defaults to the file's current content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub ours: Option<String>,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for Merge {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Merge the user's edits, saved at a version, into the file as the \
+                              agent left it",
+                item: Self {
+                    file_path: "src/main.rs".into(),
+                    base: "a442db5ec26f5be0".into(),
+                    theirs: "5d1e3a0c9b7f2e48".into(),
+                    ours: None,
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Merge a suggested rewrite of a file with changes made since it was \
+                              suggested",
+                item: Self {
+                    file_path: "notes.txt".into(),
+                    base: "Milk\nEggs\n".into(),
+                    theirs: "Milk\nEggs\nBread\n".into(),
+                    ours: Some("Oat milk\nEggs\n".into()),
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for Merge {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        encoding::check_size(&file_path, self.allow_large)?;
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+
+        let (source, _) = Encoding::read(&file_path)?;
+        let side = |text: &str| -> Result<String> {
+            if is_version(text) {
+                Ok(state.versions().get(text)?.to_string())
+            } else {
+                Ok(text.to_string())
+            }
+        };
+        let base = side(&self.base)?;
+        let theirs = side(&self.theirs)?;
+        let ours = match &self.ours {
+            Some(ours) => side(ours)?,
+            None => source.clone(),
+        };
+
+        let merged = match diffy::merge(&base, &ours, &theirs) {
+            Ok(merged) => merged,
+            Err(conflicted) => {
+                let count = conflicts::sections(&conflicted)
+                    .lines()
+                    .filter(|line| line.starts_with("<<<<<<<"))
+                    .count();
+                return Ok(format!(
+                    "The changes conflict in {count} place{}, so nothing was staged. Resolve the \
+                     conflicts between the markers, keeping what each side meant to change, then \
+                     stage the result with preview_edit.\n\n{}",
+                    if count == 1 { "" } else { "s" },
+                    ResponseFormat::configured().section("Merged", &conflicted, "")
+                ));
+            }
+        };
+        if merged == source {
+            return Ok(format!(
+                "{} already has the merged changes, so nothing was staged",
+                self.file_path
+            ));
+        }
+
+        let selector = Selector::anchor(
+            Operation::Replace,
+            source.lines().next().unwrap_or_default().trim(),
+        );
+        state.stage_edit_at(
+            merged,
+            selector,
+            language,
+            file_path,
+            EditPosition {
+                start_byte: 0,
+                end_byte: Some(source.len()),
+            },
+        )
+    }
+}
+
+/// Whether `text` is a version like persist_edit reports rather than the text of a file
+fn is_version(text: &str) -> bool {
+    let text = text.trim();
+    text.len() == 16 && text.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
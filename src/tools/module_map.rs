@@ -0,0 +1,117 @@
+use crate::{
+    languages::LanguageName,
+    module_map::{Location, module_map},
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow, bail};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// Show a Rust crate's module tree with the file each module is defined in
+///
+/// Follows `mod` declarations from the crate root, so `crate::state::StagedOperation` can be
+/// found in whichever file `crate::state` maps to.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "module_map")]
+#[group(skip)]
+pub struct ModuleMap {
+    /// A crate directory containing src/lib.rs or src/main.rs, or a crate root file. Defaults to
+    /// the session working directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub path: Option<String>,
+}
+
+impl WithExamples for ModuleMap {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Map the crate in the working directory",
+                item: Self { path: None },
+            },
+            Example {
+                description: "Map one crate of a workspace by its root file",
+                item: Self {
+                    path: Some("crates/server/src/main.rs".into()),
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for ModuleMap {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let working_directory = state.get_context(None)?;
+        let path = match &self.path {
+            Some(path) => state.resolve_path(path, None)?,
+            None => working_directory.clone().ok_or_else(|| {
+                anyhow!("No working directory is set. Use set_working_directory or pass a path.")
+            })?,
+        };
+
+        let display = |path: &Path| -> PathBuf {
+            working_directory
+                .as_deref()
+                .and_then(|base| path.strip_prefix(base).ok())
+                .unwrap_or(path)
+                .to_path_buf()
+        };
+
+        let roots = if path.is_dir() {
+            let roots = ["src/lib.rs", "src/main.rs"]
+                .into_iter()
+                .map(|root| path.join(root))
+                .filter(|root| root.is_file())
+                .collect::<Vec<_>>();
+            if roots.is_empty() {
+                bail!(
+                    "{} has no src/lib.rs or src/main.rs. Pass the crate root file instead.",
+                    display(&path).display()
+                );
+            }
+            roots
+        } else {
+            vec![path]
+        };
+
+        let rust = state.language_registry().get_language(LanguageName::Rust);
+        let mut response = String::new();
+        for root in roots {
+            let modules = module_map(rust, &root)?;
+            let width = modules
+                .iter()
+                .map(|module| module.path.len())
+                .max()
+                .unwrap_or(0);
+
+            if !response.is_empty() {
+                response.push('\n');
+            }
+            writeln!(response, "Module tree of {}:", display(&root).display())?;
+            for module in modules {
+                let location = match module.location {
+                    Location::File(file) => display(&file).display().to_string(),
+                    Location::Inline(file) => format!("{} (inline)", display(&file).display()),
+                    Location::Missing(candidates) => format!(
+                        "not found, expected {}",
+                        candidates
+                            .iter()
+                            .map(|candidate| display(candidate).display().to_string())
+                            .collect::<Vec<_>>()
+                            .join(" or ")
+                    ),
+                };
+                writeln!(response, "{:width$}  {location}", module.path)?;
+            }
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{
+    languages::{LanguageCommon, LanguageName},
+    node_kinds,
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow, bail};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// List the named node kinds of a language's grammar, the vocabulary of tree-sitter queries and
+/// node kinds in selectors, with what each contains and whether it's a good edit target
+///
+/// A good edit target is a symbol, which a selector can find by name, or a statement or entry,
+/// which can be replaced, or have another inserted before or after it, on its own. Kinds that
+/// start with `_` are supertypes, which stand for any of the kinds listed.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "node_kinds")]
+#[group(skip)]
+pub struct NodeKinds {
+    /// The language whose node kinds to list
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(short, long, value_enum)]
+    pub language: Option<LanguageName>,
+
+    /// List the node kinds of this file's language instead of naming it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub file_path: Option<String>,
+
+    /// Only list kinds whose names contain this, like `function` or `import`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// Only list the kinds that are good edit targets
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub edit_targets_only: bool,
+}
+
+impl WithExamples for NodeKinds {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Find the node kinds for functions before writing a query",
+                item: Self {
+                    language: Some(LanguageName::Rust),
+                    file_path: None,
+                    filter: Some("function".into()),
+                    edit_targets_only: false,
+                },
+            },
+            Example {
+                description: "See what can be targeted in a Python file",
+                item: Self {
+                    language: None,
+                    file_path: Some("app/models.py".into()),
+                    filter: None,
+                    edit_targets_only: true,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for NodeKinds {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let language: &LanguageCommon = match (&self.language, &self.file_path) {
+            (Some(language), _) => state.language_registry().get_language(*language),
+            (None, Some(file_path)) => {
+                let file_path = state.resolve_path(file_path, None)?;
+                let dialect = state.dialects(None)?.get(&file_path);
+                state
+                    .language_registry()
+                    .get_language_with_hint(&file_path, dialect)?
+            }
+            (None, None) => bail!("Pass a language or a file_path to list the node kinds of"),
+        };
+        let glossary = node_kinds::glossary(language).ok_or_else(|| {
+            anyhow!("The {language} grammar doesn't describe its node kinds, so there's no list")
+        })?;
+
+        let filter = self.filter.as_deref().map(str::trim).map(str::to_lowercase);
+        let kinds = glossary
+            .iter()
+            .filter(|kind| !self.edit_targets_only || kind.target.is_good())
+            .filter(|kind| filter.as_ref().is_none_or(|filter| kind.name.contains(filter)))
+            .collect::<Vec<_>>();
+        let filter = filter
+            .map(|filter| format!(" containing \"{filter}\""))
+            .unwrap_or_default();
+        if kinds.is_empty() {
+            return Ok(format!("No {language} node kinds{filter}"));
+        }
+
+        let mut response = format!(
+            "{} {language} node kind{}{filter}:\n",
+            kinds.len(),
+            if kinds.len() == 1 { "" } else { "s" },
+        );
+        for kind in kinds {
+            write!(
+                response,
+                "\n{}: {}\n  {}\n",
+                kind.name, kind.target, kind.description
+            )?;
+        }
+        Ok(response)
+    }
+}
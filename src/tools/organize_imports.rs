@@ -0,0 +1,124 @@
+use crate::{
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    imports,
+    languages::LanguageName,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Sort and group the imports at the top of a Python file the way isort does, and stage the
+/// result for preview
+///
+/// Imports are grouped into `from __future__` imports, the standard library, third-party
+/// packages, the project's own modules, and relative imports, in that order. Imports of the same
+/// module are combined, duplicates are dropped, and comments above an import move with it. The
+/// staged edit is validated and applied with persist_edit like any other.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "organize_imports")]
+#[group(skip)]
+pub struct OrganizeImports {
+    /// Path to the Python source file
+    pub file_path: String,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for OrganizeImports {
+    fn examples() -> Vec<Example<Self>> {
+        vec![Example {
+            description: "Tidy a module's imports after several edits",
+            item: Self {
+                file_path: "app/views.py".into(),
+                allow_large: false,
+            },
+        }]
+    }
+}
+
+impl Tool<SemanticEditTools> for OrganizeImports {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        stage(state, &self.file_path, None, self.allow_large)
+    }
+}
+
+/// Stage the organized imports of `file_path`, with the import statements in `added`
+pub(crate) fn stage(
+    state: &mut SemanticEditTools,
+    display_path: &str,
+    added: Option<&str>,
+    allow_large: bool,
+) -> Result<String> {
+    let file_path = state.resolve_path(display_path, None)?;
+    encoding::check_size(&file_path, allow_large)?;
+    let dialect = state.dialects(None)?.get(&file_path);
+    let language = state
+        .language_registry()
+        .get_language_with_hint(&file_path, dialect)?;
+    if language.name() != LanguageName::Python {
+        return Err(anyhow!(
+            "Imports can be organized in Python files, and {display_path} is {}",
+            language.name()
+        ));
+    }
+
+    let (source, _) = Encoding::read(&file_path)?;
+    let mut parser = language.tree_sitter_parser()?;
+    let tree = parser
+        .parse(&source, None)
+        .ok_or_else(|| anyhow!("Unable to parse {display_path}"))?;
+    let added = added
+        .map(|added| {
+            parser
+                .parse(added, None)
+                .map(|tree| (tree, added))
+                .ok_or_else(|| anyhow!("Unable to parse `{added}`"))
+        })
+        .transpose()?;
+    let organized = imports::organize(
+        &tree,
+        &source,
+        added.as_ref().map(|(tree, added)| (tree, *added)),
+        &file_path,
+    )
+    .map_err(|message| anyhow!(message))?;
+    if organized.content == source[organized.range.clone()] {
+        return Ok(match added {
+            Some(_) => format!("{display_path} already has that import"),
+            None => format!("The imports in {display_path} are already organized"),
+        });
+    }
+
+    let (operation, end_byte) = if organized.range.is_empty() {
+        (Operation::InsertBefore, None)
+    } else {
+        (Operation::Replace, Some(organized.range.end))
+    };
+    let selector = Selector::anchor(
+        operation,
+        source[organized.range.start..]
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim(),
+    );
+    state.stage_edit_at(
+        organized.content,
+        selector,
+        language,
+        file_path,
+        EditPosition {
+            start_byte: organized.range.start,
+            end_byte,
+        },
+    )
+}
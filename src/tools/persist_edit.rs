@@ -1,8 +1,25 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
 
-use crate::editor::Editor;
-use crate::state::SemanticEditTools;
+use crate::checks::Checker;
+use crate::conflicts::{self, OnConflict};
+use crate::editor::{
+    EditPosition, Editor, apply_hunks, clusters, format_numbered_patch, format_patch,
+};
+use crate::encoding::Encoding;
+use crate::formatting::Formatter;
+use crate::guardrails::{self, changed_bytes};
+use crate::hooks::Hooks;
+use crate::protected;
+use crate::response_format::ResponseFormat;
+use crate::secrets::{self, SecretPolicy};
+use crate::selector::{Operation, Selector};
+use crate::state::{SemanticEditTools, StagedOperation};
+use crate::versions;
 use anyhow::{anyhow, Result};
+use diffy::DiffOptions;
 use mcplease::traits::{Tool, WithExamples};
 use mcplease::types::Example;
 use schemars::JsonSchema;
@@ -12,7 +29,32 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug, clap::Args)]
 #[serde(rename = "persist_edit")]
 #[group(skip)]
-pub struct PersistEdit {}
+pub struct PersistEdit {
+    /// Write the edit even though it exceeds the configured write limits, changes a protected
+    /// file, or looks like it contains a secret
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub confirm: bool,
+
+    /// Write only these hunks of the staged edit, numbered as in its preview, and keep the rest
+    /// staged. All of it is written when this is empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[arg(long, value_delimiter = ',')]
+    pub hunks: Vec<usize>,
+
+    /// What to do if the file changed after the edit was staged. Without this, persist_edit
+    /// describes the change and writes nothing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub on_conflict: Option<OnConflict>,
+
+    /// Write the edited file to this path instead, creating any directories it needs, and leave
+    /// the file the edit was staged for as it is. An existing file is only replaced with
+    /// `confirm`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub output_path: Option<String>,
+}
 
 impl JsonSchema for PersistEdit {
     fn schema_name() -> Cow<'static, str> {
@@ -23,37 +65,564 @@ impl JsonSchema for PersistEdit {
         schemars::json_schema!({
             "description": "Execute the currently staged operation",
             "type": "object",
-            "properties": {}
+            "properties": {
+                "confirm": {
+                    "description": "Write the edit even though it exceeds the configured write limits, changes a protected file, or looks like it contains a secret",
+                    "type": "boolean"
+                },
+                "hunks": {
+                    "description": "Write only these hunks of the staged edit, numbered as in its preview, and keep the rest staged. All of it is written when this is empty.",
+                    "type": "array",
+                    "items": { "type": "integer", "minimum": 1 }
+                },
+                "on_conflict": {
+                    "description": "What to do if the file changed after the edit was staged: `reresolve` finds the edit's anchor again in the file as it is now, `merge` three-way merges the staged change with the changes made since, and `overwrite` discards the changes made since. Without this, persist_edit describes the change and writes nothing.",
+                    "type": "string",
+                    "enum": ["reresolve", "merge", "overwrite"]
+                },
+                "output_path": {
+                    "description": "Write the edited file to this path instead, creating any directories it needs, and leave the file the edit was staged for as it is. An existing file is only replaced with `confirm`.",
+                    "type": "string"
+                }
+            }
         })
     }
 }
 
 impl WithExamples for PersistEdit {
     fn examples() -> Vec<Example<Self>> {
-        vec![Example {
-            description: "Commit the currently staged operation",
-            item: Self {},
-        }]
+        vec![
+            Example {
+                description: "Commit the currently staged operation",
+                item: Self {
+                    confirm: false,
+                    hunks: vec![],
+                    on_conflict: None,
+                    output_path: None,
+                },
+            },
+            Example {
+                description: "Commit a reviewed edit that changes more than the write limits allow",
+                item: Self {
+                    confirm: true,
+                    hunks: vec![],
+                    on_conflict: None,
+                    output_path: None,
+                },
+            },
+            Example {
+                description: "Commit the first and third hunks of the staged edit's preview, keeping the second staged",
+                item: Self {
+                    confirm: false,
+                    hunks: vec![1, 3],
+                    on_conflict: None,
+                    output_path: None,
+                },
+            },
+            Example {
+                description: "Commit a staged edit to a file that a formatter rewrote after it was \
+                              staged, merging the two changes",
+                item: Self {
+                    confirm: false,
+                    hunks: vec![],
+                    on_conflict: Some(OnConflict::Merge),
+                    output_path: None,
+                },
+            },
+            Example {
+                description: "Write a staged edit to a new file, like a module extracted from \
+                              the staged file, leaving the staged file unchanged",
+                item: Self {
+                    confirm: false,
+                    hunks: vec![],
+                    on_conflict: None,
+                    output_path: Some("src/parser/tokens.rs".into()),
+                },
+            },
+        ]
     }
 }
 
 impl Tool<SemanticEditTools> for PersistEdit {
     fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        if self.output_path.is_some() && !self.hunks.is_empty() {
+            return Err(anyhow!(
+                "`hunks` and `output_path` can't be used together, since the hunks left staged \
+                 would apply to a file that wasn't written. Nothing was written, and the edit is \
+                 still staged."
+            ));
+        }
+        let target = self
+            .output_path
+            .as_deref()
+            .map(|output_path| state.resolve_new_path(output_path, None))
+            .transpose()?;
         let staged_operation = state
             .take_staged_operation(None)?
             .ok_or_else(|| anyhow!("No operation is currently staged"))?;
 
-        let editor = Editor::from_staged_operation(staged_operation, state.language_registry())?;
-        let (message, output, output_path) = editor.commit()?;
+        let policy = SecretPolicy::configured();
+        let secrets = match policy {
+            SecretPolicy::Off => vec![],
+            _ => secrets::scan(&staged_operation.content),
+        };
+        let checker = Checker::configured(staged_operation.language_name)?;
+        let language = state
+            .language_registry()
+            .get_language(staged_operation.language_name);
+        let formatter = Formatter::configured(language).name(language);
+        let editor =
+            Editor::from_staged_operation(staged_operation.clone(), state.language_registry())?;
+        let stale = staged_operation
+            .base_version
+            .as_ref()
+            .is_some_and(|base_version| *base_version != versions::of(editor.source_code()));
+        let (mut message, output, output_path) = if stale {
+            match resolve_conflict(state, &staged_operation, editor, self.on_conflict) {
+                Ok(resolved) => resolved,
+                Err(error) => {
+                    state.preview_edit(None, Some(staged_operation))?;
+                    return Err(error);
+                }
+            }
+        } else {
+            editor.commit()?
+        };
+        let mut rest = None;
+        let output = match output {
+            Some(output) if !self.hunks.is_empty() => {
+                match select_hunks(state, &staged_operation, &output, &self.hunks) {
+                    Ok(Some(selected)) => {
+                        // keep the account of how a conflict was resolved ahead of the hunks
+                        message = if stale {
+                            format!("{message}\n\n{}", selected.message)
+                        } else {
+                            selected.message
+                        };
+                        rest = Some(selected.rest);
+                        Some(selected.output)
+                    }
+                    Ok(None) => Some(output),
+                    Err(error) => {
+                        state.preview_edit(None, Some(staged_operation))?;
+                        return Err(error);
+                    }
+                }
+            }
+            output => output,
+        };
+
+        let output_path = match target {
+            Some(target) if target != output_path => {
+                message.insert_str(
+                    0,
+                    &format!(
+                        "Wrote the edit to {}, leaving {} unchanged.\n\n",
+                        target.display(),
+                        output_path.display()
+                    ),
+                );
+                target
+            }
+            _ => output_path,
+        };
 
         if let Some(output) = output {
-            if let Some(commit) = state.commit_fn_mut().take() {
-                commit(output_path, output);
-            } else {
-                std::fs::write(output_path, output)?;
+            let protected = protected::reason(&output_path)?;
+            let original = std::fs::read(&output_path).unwrap_or_default();
+            if !self.confirm {
+                let mut exceeded = protected.iter().cloned().collect::<Vec<_>>();
+                if output_path != staged_operation.file_path && output_path.exists() {
+                    exceeded.push(format!("{} already exists", output_path.display()));
+                }
+                if staged_operation.review.approved == Some(false) {
+                    exceeded.push("a reviewer marked it as not approved".into());
+                }
+                if policy == SecretPolicy::Block && !secrets.is_empty() {
+                    exceeded.push(secrets::describe(&secrets));
+                }
+                exceeded.extend(
+                    state
+                        .guardrails()
+                        .exceeded(1, changed_bytes(&original, output.as_bytes())),
+                );
+                if !exceeded.is_empty() {
+                    state.preview_edit(None, Some(staged_operation))?;
+                    return Err(anyhow!(
+                        "{} The edit is still staged.",
+                        guardrails::confirmation_required(
+                            &exceeded,
+                            "call persist_edit with confirm"
+                        )
+                    ));
+                }
+            }
+            let before = output_path
+                .exists()
+                .then(|| String::from_utf8_lossy(&original).into_owned());
+            let committed = match state.commit_fn_mut().take() {
+                Some(commit) => {
+                    commit(output_path.clone(), output.clone());
+                    true
+                }
+                None => {
+                    let written = output_path
+                        .parent()
+                        .map_or(Ok(()), std::fs::create_dir_all)
+                        .and_then(|()| std::fs::write(&output_path, &output));
+                    if let Err(error) = written {
+                        state.preview_edit(None, Some(staged_operation))?;
+                        return Err(anyhow!(
+                            "Unable to write {}: {error}. The edit is still staged.",
+                            output_path.display()
+                        ));
+                    }
+                    false
+                }
+            };
+            state.guardrails().record_persist();
+            state.record_write(
+                &output_path,
+                before.as_deref(),
+                &output,
+                formatter.as_deref(),
+                None,
+            )?;
+            if let Some(rest) = rest {
+                state.preview_edit(None, Some(rest))?;
+            }
+            if !secrets.is_empty() {
+                message.insert_str(
+                    0,
+                    &format!("⚠️ POSSIBLE SECRET WRITTEN: {}\n\n", secrets::describe(&secrets)),
+                );
+            }
+            if let Some(protected) = protected {
+                message.insert_str(0, &format!("⚠️ PROTECTED FILE CHANGED: {protected}\n\n"));
+            }
+            if !staged_operation.review.is_empty() {
+                message.insert_str(0, &format!("{}\n\n", staged_operation.review.describe()));
+            }
+            message.push_str(&format!(
+                "\n\nThe file is now at version `{}`, for expected_version",
+                versions::of(&output)
+            ));
+
+            if !committed {
+                if let Some(checker) = checker {
+                    message.push_str(&check_report(checker, &output_path));
+                }
+                if let Some(hooks) = Hooks::configured() {
+                    message.push_str(&hook_report(&hooks, &output_path));
+                }
             }
         }
 
         Ok(message)
     }
 }
+
+/// Persist an edit whose file changed after it was staged as `on_conflict` says to, or describe
+/// the change and the ways to resolve it. The edit is still staged if this is an error.
+fn resolve_conflict(
+    state: &SemanticEditTools,
+    staged: &StagedOperation,
+    editor: Editor,
+    on_conflict: Option<OnConflict>,
+) -> Result<(String, Option<String>, PathBuf)> {
+    let file = staged.file_path.display();
+    let format = ResponseFormat::configured();
+    let current = editor.source_code().to_string();
+    let base_version = staged.base_version.as_deref().unwrap_or_default();
+    let base = state.versions().get(base_version).ok();
+
+    let Some(on_conflict) = on_conflict else {
+        let mut message = format!(
+            "{file} changed after this edit was staged: it was at version `{base_version}` and \
+             is now at version `{}`. Nothing was written, and the edit is still staged. Call \
+             persist_edit again with on_conflict:\n\
+             - `reresolve` to find the edit's anchor again in the file as it is now and apply \
+             the edit there\n",
+            versions::of(&current)
+        );
+        if base.is_some() {
+            message.push_str(
+                "- `merge` to three-way merge the staged change with the changes made since\n",
+            );
+        }
+        message.push_str(
+            "- `overwrite` to write the file as the edit left it, discarding the changes made \
+             since",
+        );
+        if let Some(base) = &base {
+            let patch = DiffOptions::new().create_patch(base, &current);
+            message.push_str(&format!(
+                "\n\nChanges made since the edit was staged:\n\n{}",
+                format.section("Diff", &format_patch(&patch), "diff")
+            ));
+        }
+        return Err(anyhow!(message));
+    };
+
+    if on_conflict == OnConflict::Reresolve {
+        let reresolved = StagedOperation {
+            edit_position: None,
+            ..staged.clone()
+        };
+        let (message, output, output_path) =
+            Editor::from_staged_operation(reresolved, state.language_registry())?.commit()?;
+        if output.is_none() {
+            return Err(anyhow!(
+                "{file} changed after this edit was staged, and its anchor couldn't be found \
+                 again. Nothing was written, and the edit is still staged.\n\n{message}"
+            ));
+        }
+        return Ok((
+            format!(
+                "{file} changed after this edit was staged, so its anchor was found again in \
+                 the file as it is now.\n\n{message}"
+            ),
+            output,
+            output_path,
+        ));
+    }
+
+    let base = base.ok_or_else(|| {
+        anyhow!(
+            "{file} changed after this edit was staged, and the text it was staged against is \
+             no longer cached, so the edit can only be persisted with `reresolve`. Nothing was \
+             written, and the edit is still staged."
+        )
+    })?;
+    let language = editor.language();
+    let encoding = *editor.encoding();
+    let (message, staged_output, output_path) = Editor::from_source(
+        staged.content.clone(),
+        staged.selector.clone(),
+        language,
+        editor.file_path().to_path_buf(),
+        staged.edit_position,
+        base.to_string(),
+        encoding,
+    )?
+    .with_allow_dirty(staged.allow_dirty)
+    .commit()?;
+    let staged_output = staged_output.ok_or_else(|| {
+        anyhow!(
+            "The staged edit no longer applies to the text it was staged against. Nothing was \
+             written, and the edit is still staged.\n\n{message}"
+        )
+    })?;
+    let staged_output = staged_output.trim_start_matches('\u{feff}');
+
+    let (output, message) = match on_conflict {
+        OnConflict::Merge => {
+            let merged = diffy::merge(&base, &current, staged_output).map_err(|conflicted| {
+                anyhow!(
+                    "The staged edit overlaps the changes made to {file} after it was staged, so \
+                     they can't be merged. Nothing was written, and the edit is still staged. \
+                     Use `reresolve` or `overwrite`, or stage the edit again.\n\n{}",
+                    format.section("Conflicts", &conflicts::sections(&conflicted), "")
+                )
+            })?;
+            let has_errors = |text: &str| -> Result<Option<String>> {
+                let tree = language
+                    .tree_sitter_parser()?
+                    .parse(text, None)
+                    .ok_or_else(|| anyhow!("Unable to parse {file} as {language}"))?;
+                Ok(Editor::validate(language, &tree, text))
+            };
+            if let Some(errors) = has_errors(&merged)?
+                && has_errors(&current)?.is_none()
+            {
+                return Err(anyhow!(
+                    "Merging the staged edit with the changes made to {file} after it was staged \
+                     would leave it with syntax errors. Nothing was written, and the edit is still \
+                     staged.\n\n{errors}"
+                ));
+            }
+            let message = format!(
+                "Merged the staged edit with the changes made to {file} after it was staged:\n\n{}",
+                format.section(
+                    "Diff",
+                    &format_patch(&DiffOptions::new().create_patch(&current, &merged)),
+                    "diff"
+                )
+            );
+            (merged, message)
+        }
+        _ => {
+            let message = format!(
+                "Overwrote the changes made to {file} after the edit was staged:\n\n{}",
+                format.section(
+                    "Diff",
+                    &format_patch(&DiffOptions::new().create_patch(&current, staged_output)),
+                    "diff"
+                )
+            );
+            (staged_output.to_string(), message)
+        }
+    };
+    Ok((message, Some(encoding.encode(output)), output_path))
+}
+
+/// Part of a staged edit chosen with `hunks`
+struct SelectedHunks {
+    /// The content to write, with only the chosen hunks applied
+    output: String,
+    message: String,
+    /// An operation that applies the other hunks to `output`, to stay staged
+    rest: StagedOperation,
+}
+
+/// Narrow the `output` of a staged edit to the hunks of its diff numbered in `hunks`, or None if
+/// they are all of its hunks. Writing some hunks without the others mustn't leave the file with
+/// syntax errors it doesn't have already.
+fn select_hunks(
+    state: &SemanticEditTools,
+    staged: &StagedOperation,
+    output: &str,
+    hunks: &[usize],
+) -> Result<Option<SelectedHunks>> {
+    let (source, encoding) = Encoding::read(&staged.file_path)?;
+    let edited = output.trim_start_matches('\u{feff}');
+    let patch = DiffOptions::new().create_patch(&source, edited);
+    let language = state.language_registry().get_language(staged.language_name);
+    let logical_hunks = clusters(language, &patch, edited);
+    let count = logical_hunks.len();
+    if let Some(hunk) = hunks.iter().find(|hunk| !(1..=count).contains(*hunk)) {
+        return Err(anyhow!(
+            "There is no hunk {hunk}: the staged edit has {count} hunk{}, numbered from 1 in its \
+             preview. The edit is still staged.",
+            if count == 1 { "" } else { "s" }
+        ));
+    }
+    let mut selected = hunks.iter().map(|hunk| hunk - 1).collect::<Vec<_>>();
+    selected.sort_unstable();
+    selected.dedup();
+    if selected.len() == count {
+        return Ok(None);
+    }
+
+    let patch_hunks = selected
+        .iter()
+        .flat_map(|&hunk| logical_hunks[hunk].hunks.clone())
+        .collect::<Vec<_>>();
+    let partial = apply_hunks(&source, &patch, &patch_hunks);
+    let has_errors = |text: &str| -> Result<bool> {
+        Ok(language
+            .tree_sitter_parser()?
+            .parse(text, None)
+            .is_none_or(|tree| tree.root_node().has_error()))
+    };
+    let list = |hunks: &[usize]| {
+        hunks
+            .iter()
+            .map(|hunk| (hunk + 1).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if has_errors(&partial)? && !has_errors(&source)? {
+        return Err(anyhow!(
+            "Writing only hunk{} {} would leave {} with syntax errors, so nothing was written. \
+             Include the hunks they depend on. The edit is still staged.",
+            if selected.len() == 1 { "" } else { "s" },
+            list(&selected),
+            staged.file_path.display()
+        ));
+    }
+
+    let format = ResponseFormat::configured();
+    let rest = DiffOptions::new().create_patch(&partial, edited);
+    let message = format!(
+        "Applied hunk{} {} of {count}:\n\n{}\n\nThe rest of the edit is still staged:\n\n{}",
+        if selected.len() == 1 { "" } else { "s" },
+        list(&selected),
+        format.section(
+            "Diff",
+            &format_patch(&DiffOptions::new().create_patch(&source, &partial)),
+            "diff"
+        ),
+        format.section(
+            "Diff",
+            &format_numbered_patch(&rest, &clusters(language, &rest, edited), &partial),
+            "diff"
+        ),
+    );
+    let rest = StagedOperation {
+        selector: Selector::anchor(
+            Operation::Replace,
+            partial.lines().next().unwrap_or_default().trim(),
+        ),
+        content: edited.to_string(),
+        file_path: staged.file_path.clone(),
+        language_name: staged.language_name,
+        edit_position: Some(EditPosition {
+            start_byte: 0,
+            end_byte: Some(partial.len()),
+        }),
+        allow_dirty: staged.allow_dirty,
+        review: staged.review.clone(),
+        base_version: Some(versions::of(&partial)),
+    };
+    Ok(Some(SelectedHunks {
+        output: encoding.encode(partial),
+        message,
+        rest,
+    }))
+}
+
+/// Run the configured checker on the persisted file and describe what it found
+fn check_report(checker: Checker, file: &Path) -> String {
+    let report = match checker.check(file) {
+        Ok(report) => report,
+        Err(error) => return format!("\n\n⚠️ Unable to check the edit with {checker}: {error}"),
+    };
+
+    let elsewhere = match report.elsewhere {
+        0 => String::new(),
+        1 => " (and 1 problem in other files)".to_string(),
+        count => format!(" (and {count} problems in other files)"),
+    };
+    if report.diagnostics.is_empty() {
+        return format!("\n\n✅ {checker} reports no problems in this file{elsewhere}");
+    }
+
+    let mut response = format!(
+        "\n\n⚠️ {checker} reports {} problem{} in this file{elsewhere}:",
+        report.diagnostics.len(),
+        if report.diagnostics.len() == 1 { "" } else { "s" }
+    );
+    for diagnostic in report.diagnostics {
+        response.push_str(&format!("\n  {diagnostic}"));
+    }
+    response
+}
+
+/// Run the configured pre-commit hooks on the persisted file and describe any that fail
+fn hook_report(hooks: &Hooks, file: &Path) -> String {
+    let failures = match hooks.run(file) {
+        Ok(failures) => failures,
+        Err(error) => return format!("\n\n⚠️ Unable to run pre-commit hooks: {error}"),
+    };
+    if failures.is_empty() {
+        return "\n\n✅ Pre-commit hooks pass".to_string();
+    }
+
+    let mut response = format!(
+        "\n\n⚠️ {} pre-commit hook{} would reject this change. The edit has been persisted; \
+         fix the problems before committing:",
+        failures.len(),
+        if failures.len() == 1 { "" } else { "s" }
+    );
+    for failure in failures {
+        let status = failure
+            .status
+            .map_or_else(|| "killed".to_string(), |code| format!("exit {code}"));
+        response.push_str(&format!(
+            "\n\n`{}` ({status}):\n{}",
+            failure.command, failure.output
+        ));
+    }
+    response
+}
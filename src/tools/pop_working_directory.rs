@@ -0,0 +1,31 @@
+use crate::state::SemanticEditTools;
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Return to the working context path saved by the most recent push_working_directory
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "pop_working_directory")]
+#[group(skip)]
+pub struct PopWorkingDirectory {}
+
+impl WithExamples for PopWorkingDirectory {
+    fn examples() -> Vec<Example<Self>> {
+        vec![Example {
+            description: "returning to the original project after editing a sibling repository",
+            item: Self {},
+        }]
+    }
+}
+
+impl Tool<SemanticEditTools> for PopWorkingDirectory {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let path = state
+            .pop_working_directory(None)?
+            .ok_or_else(|| anyhow!("No saved working directories to return to"))?;
+        Ok(format!("Set context to {}", path.display()))
+    }
+}
@@ -1,14 +1,20 @@
-use crate::editor::Editor;
-use crate::languages::LanguageName;
+use crate::editor::{EditPosition, Editor};
+use crate::encoding::{self, Encoding};
+use crate::languages::{Guess, LanguageCommon, LanguageName};
+use crate::placeholders;
+use crate::protected;
+use crate::secrets::{self, SecretPolicy};
 use crate::selector::{Operation, Selector};
 use crate::state::SemanticEditTools;
-use anyhow::Result;
+use crate::versions;
+use anyhow::{Result, anyhow};
 use mcplease::{
     traits::{Tool, WithExamples},
     types::Example,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::{fmt::Write, path::Path};
 
 /// Stage an operation and see a preview of the changes
 ///
@@ -21,6 +27,7 @@ use serde::{Deserialize, Serialize};
 pub struct PreviewEdit {
     /// Path to the source file.
     /// If a session has been configured, this can be a relative path to the session root.
+    /// `~` and environment variables like `$HOME` are expanded.
     pub file_path: String,
 
     /// Optional language hint. If not provided, language will be detected from file extension.
@@ -33,8 +40,64 @@ pub struct PreviewEdit {
     #[clap(flatten)]
     pub selector: Selector,
 
+    /// Reuse an anchor that recently resolved in this file instead of sending it again: `last`
+    /// for the most recent, or `last-1` through `last-4` for those before it. Leave `anchor`
+    /// empty when this is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub anchor_ref: Option<String>,
+
     /// The new content to insert or replace
     /// IMPORTANT TIP: To remove code, omit `content`
+    ///
+    /// Snippet placeholders are expanded before the edit is validated: `${1:name}` becomes
+    /// `name`, `${file_stem}` and `${file_name}` the file's name without and with its
+    /// extension, and `${date}` today's date.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    /// List other places in the file that reference symbols modified or removed by this edit,
+    /// so that call sites needing follow-up edits can be found before persisting
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub report_impact: bool,
+
+    /// Edit a file that already has syntax errors, which is otherwise refused. Only errors the
+    /// edit introduces are checked, so this is how to make the edit that fixes a broken file.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_dirty: bool,
+
+    /// Edit a file larger than the size limit for editing (4 MiB unless
+    /// `SEMANTIC_EDIT_MAX_FILE_SIZE` says otherwise), which is otherwise refused because files
+    /// that large are usually generated bundles or data
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+
+    /// The version of the file this edit was written against, as reported by persist_edit. The
+    /// edit is refused if the file has changed since, rather than applied to text that may have
+    /// moved or changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub expected_version: Option<String>,
+
+    /// More edits to the same file, applied in order after this one, each seeing the result of
+    /// the edits before it. They are previewed as one combined diff and persisted together, and
+    /// if any of them is rejected, none of them are staged.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[arg(skip)]
+    pub edits: Vec<BatchEdit>,
+}
+
+/// One of several edits to a file staged together by preview_edit
+#[derive(Serialize, Deserialize, Debug, JsonSchema, Clone)]
+pub struct BatchEdit {
+    /// How to position the `content`
+    #[serde(flatten)]
+    pub selector: Selector,
+
+    /// The new content to insert or replace. Omit it to remove code.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
 }
@@ -71,48 +134,135 @@ impl WithExamples for PreviewEdit {
                 description: "Insert content after a function declaration",
                 item: Self {
                     file_path: "src/main.rs".into(),
-                    selector: Selector {
-                        anchor: "fn main() {".into(),
-                        operation: Operation::InsertAfter,
-                    },
+                    selector: Selector::anchor(Operation::InsertAfter, "fn main() {"),
                     content: Some("\n    println!(\"Hello, world!\");".to_string()),
                     language: None,
+                    report_impact: false,
+                    allow_dirty: false,
+                    allow_large: false,
+                    anchor_ref: None,
+                    expected_version: None,
+                    edits: vec![],
                 },
             },
             Example {
                 description: "Replace a function with new implementation",
                 item: Self {
                     file_path: "src/main.rs".into(),
-                    selector: Selector {
-                        anchor: "fn hello()".to_string(),
-                        operation: Operation::Replace,
-                    },
+                    selector: Selector::anchor(Operation::Replace, "fn hello()"),
                     content: Some("fn hello() { println!(\"Hello, world!\"); }".to_string()),
                     language: None,
+                    report_impact: false,
+                    allow_dirty: false,
+                    allow_large: false,
+                    anchor_ref: None,
+                    expected_version: None,
+                    edits: vec![],
                 },
             },
             Example {
                 description: "Replace an if statement",
                 item: Self {
                     file_path: "src/main.rs".into(),
-                    selector: Selector {
-                        operation: Operation::Replace,
-                        anchor: "if let Some(user) = user {".to_string(),
-                    },
+                    selector: Selector::anchor(Operation::Replace, "if let Some(user) = user {"),
                     content: Some("user.map(User::name)".into()),
                     language: None,
+                    report_impact: false,
+                    allow_dirty: false,
+                    allow_large: false,
+                    anchor_ref: None,
+                    expected_version: None,
+                    edits: vec![],
                 },
             },
             Example {
-                description: "Removing a function by omitting replacement content",
+                description: "Change a function signature and list call sites that may need updating",
+                item: Self {
+                    file_path: "src/main.rs".into(),
+                    selector: Selector::anchor(Operation::Replace, "fn display_name("),
+                    content: Some(
+                        "fn display_name(&self, title: &str) -> String { format!(\"{title} {}\", self.name) }"
+                            .into(),
+                    ),
+                    language: None,
+                    report_impact: true,
+                    allow_dirty: false,
+                    allow_large: false,
+                    anchor_ref: None,
+                    expected_version: None,
+                    edits: vec![],
+                },
+            },
+            Example {
+                description: "Replace a function when unsure of the exact spacing and punctuation of its signature",
                 item: Self {
                     file_path: "src/main.rs".into(),
                     selector: Selector {
-                        operation: Operation::Replace,
-                        anchor: "fn main() {".to_string(),
+                        relaxed: true,
+                        ..Selector::anchor(Operation::Replace, "fn greet(name:&str) -> String {")
                     },
+                    content: Some("fn greet(name: &str) -> String { format!(\"Hi, {name}\") }".into()),
+                    language: None,
+                    report_impact: false,
+                    allow_dirty: false,
+                    allow_large: false,
+                    anchor_ref: None,
+                    expected_version: None,
+                    edits: vec![],
+                },
+            },
+            Example {
+                description: "Replace a function only if the file hasn't changed since the last edit to it",
+                item: Self {
+                    file_path: "src/main.rs".into(),
+                    selector: Selector::anchor(Operation::Replace, "fn greet("),
+                    content: Some("fn greet(name: &str) -> String { format!(\"Hi, {name}\") }".into()),
+                    language: None,
+                    report_impact: false,
+                    allow_dirty: false,
+                    allow_large: false,
+                    anchor_ref: None,
+                    expected_version: Some("5d1e3a0c9b7f2e48".into()),
+                    edits: vec![],
+                },
+            },
+            Example {
+                description: "Rename a field and update the places that use it, staged as one edit",
+                item: Self {
+                    file_path: "src/user.rs".into(),
+                    selector: Selector::anchor(Operation::Replace, "pub name: String"),
+                    content: Some("pub display_name: String".into()),
+                    language: None,
+                    report_impact: false,
+                    allow_dirty: false,
+                    allow_large: false,
+                    anchor_ref: None,
+                    expected_version: None,
+                    edits: vec![
+                        BatchEdit {
+                            selector: Selector::anchor(Operation::Replace, "name: name.into()"),
+                            content: Some("display_name: name.into()".into()),
+                        },
+                        BatchEdit {
+                            selector: Selector::anchor(Operation::Replace, "self.name"),
+                            content: Some("self.display_name".into()),
+                        },
+                    ],
+                },
+            },
+            Example {
+                description: "Removing a function by omitting replacement content",
+                item: Self {
+                    file_path: "src/main.rs".into(),
+                    selector: Selector::anchor(Operation::Replace, "fn main() {"),
                     content: None,
                     language: None,
+                    report_impact: false,
+                    allow_dirty: false,
+                    allow_large: false,
+                    anchor_ref: None,
+                    expected_version: None,
+                    edits: vec![],
                 },
             },
         ]
@@ -123,27 +273,250 @@ impl Tool<SemanticEditTools> for PreviewEdit {
     fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
         let Self {
             file_path,
-            selector,
+            mut selector,
             content,
             language,
+            report_impact,
+            allow_dirty,
+            allow_large,
+            anchor_ref,
+            expected_version,
+            edits,
         } = self;
 
-        let file_path = state.resolve_path(&file_path, None)?;
+        let display_path = file_path;
+        let file_path = state.resolve_path(&display_path, None)?;
+        if let Some(anchor_ref) = anchor_ref {
+            selector.anchor = resolve_anchor_ref(state, &file_path, &selector, &anchor_ref)?;
+        }
+        encoding::check_size(&file_path, allow_large)?;
+        if let Some(expected_version) = expected_version {
+            let (text, _) = Encoding::read(&file_path)?;
+            versions::check(&display_path, &text, &expected_version)?;
+        }
 
-        let language = state
-            .language_registry()
-            .get_language_with_hint(&file_path, language)?;
+        let language = language.or(state.dialects(None)?.get(&file_path));
+        let registry = state.language_registry();
+        let guess = language
+            .or_else(|| registry.detect_language_from_path(&file_path))
+            .is_none()
+            .then(|| registry.guess_language_from_content(&file_path))
+            .flatten();
+        let language = registry.get_language_with_hint(&file_path, language)?;
 
-        let editor = Editor::new(
-            content.unwrap_or_default(),
+        // `${1}` in the content of a regex selector is a capture group, not a snippet placeholder
+        let expand = |selector: &Selector, content: Option<String>| match selector.regex {
+            true => content,
+            false => Some(placeholders::expand(
+                &content.unwrap_or_default(),
+                &file_path,
+            )),
+        };
+        let mut batch = vec![BatchEdit {
+            content: expand(&selector, content),
             selector,
-            language,
-            file_path,
-            None,
-        )?;
-        let (message, staged_operation) = editor.preview()?;
+        }];
+        batch.extend(edits.into_iter().map(|edit| BatchEdit {
+            content: expand(&edit.selector, edit.content),
+            ..edit
+        }));
+        let policy = SecretPolicy::configured();
+        let secrets = match policy {
+            SecretPolicy::Off => vec![],
+            _ => batch
+                .iter()
+                .flat_map(|edit| secrets::scan(edit.content.as_deref().unwrap_or_default()))
+                .collect(),
+        };
+
+        let (editor, steps) = if batch.len() == 1 {
+            let BatchEdit { selector, content } = batch.remove(0);
+            let editor = Editor::new(
+                content.unwrap_or_default(),
+                selector,
+                language,
+                file_path.clone(),
+                None,
+            )?;
+            (editor, None)
+        } else {
+            match combine(&batch, language, &file_path, allow_dirty)? {
+                Ok(Combined {
+                    source,
+                    encoding,
+                    output,
+                    steps,
+                }) => {
+                    let selector = Selector::anchor(
+                        Operation::Replace,
+                        source.lines().next().unwrap_or_default().trim(),
+                    );
+                    let position = EditPosition {
+                        start_byte: 0,
+                        end_byte: Some(source.len()),
+                    };
+                    let editor = Editor::from_source(
+                        output,
+                        selector,
+                        language,
+                        file_path.clone(),
+                        Some(position),
+                        source,
+                        encoding,
+                    )?;
+                    (editor, Some(steps))
+                }
+                Err(rejection) => {
+                    state.record_rejection(None)?;
+                    state.preview_edit(None, None)?;
+                    return Ok(rejection);
+                }
+            }
+        };
+        let editor = editor
+            .with_report_impact(report_impact)
+            .with_allow_dirty(allow_dirty);
+        let (mut message, staged_operation) = editor.preview()?;
+        let protected = staged_operation
+            .as_ref()
+            .map(|staged| protected::reason(&staged.file_path))
+            .transpose()?
+            .flatten();
+        match &staged_operation {
+            Some(staged) if steps.is_some() => {
+                for edit in batch.iter().filter(|edit| !edit.selector.regex) {
+                    state.record_anchor(&staged.file_path, &edit.selector.anchor, None)?;
+                }
+            }
+            Some(staged) => state.record_anchor(&staged.file_path, &staged.selector.anchor, None)?,
+            None => state.record_rejection(None)?,
+        }
         state.preview_edit(None, staged_operation)?;
 
+        if let Some(steps) = steps {
+            message.insert_str(
+                0,
+                &format!(
+                    "All {} edits apply, and are staged together as one edit:\n\n{steps}\n",
+                    batch.len()
+                ),
+            );
+        }
+
+        if let Some(Guess {
+            language,
+            confidence,
+        }) = guess
+        {
+            message.insert_str(
+                0,
+                &format!(
+                    "Language guessed from the file's content: {language} ({confidence}% \
+                     confidence). Pass `language` if this is wrong.\n\n"
+                ),
+            );
+        }
+
+        if !secrets.is_empty() {
+            message.insert_str(
+                0,
+                &format!(
+                    "⚠️ POSSIBLE SECRET: {}.{}\n\n",
+                    secrets::describe(&secrets),
+                    if policy == SecretPolicy::Block {
+                        " persist_edit will only write this edit with confirm."
+                    } else {
+                        ""
+                    }
+                ),
+            );
+        }
+
+        if let Some(protected) = protected {
+            message.insert_str(
+                0,
+                &format!(
+                    "⚠️ PROTECTED FILE: {protected}. persist_edit will only write this edit with \
+                     confirm.\n\n"
+                ),
+            );
+        }
+
         Ok(message)
     }
 }
+
+/// The file's text with a batch of edits applied, to be staged as one replacement of all of it
+struct Combined {
+    source: String,
+    encoding: Encoding,
+    output: String,
+    /// A line describing each edit
+    steps: String,
+}
+
+/// Apply `batch` to the file one edit after another, or describe the first edit rejected and why
+fn combine(
+    batch: &[BatchEdit],
+    language: &LanguageCommon,
+    file_path: &Path,
+    allow_dirty: bool,
+) -> Result<Result<Combined, String>> {
+    let (source, encoding) = Encoding::read(file_path)?;
+    let mut output = source.clone();
+    let mut steps = String::new();
+    for (index, edit) in batch.iter().enumerate() {
+        let step = index + 1;
+        let description = format!(
+            "{} `{}`",
+            edit.selector.operation_name(),
+            edit.selector.anchor
+        );
+        let editor = Editor::from_source(
+            edit.content.clone().unwrap_or_default(),
+            edit.selector.clone(),
+            language,
+            file_path.to_path_buf(),
+            None,
+            output,
+            encoding,
+        )?
+        .with_allow_dirty(allow_dirty);
+        match editor.simulate()? {
+            (_, Some(edited)) => {
+                writeln!(steps, "✅ {step}. {description}")?;
+                output = edited;
+            }
+            (message, None) => {
+                return Ok(Err(format!(
+                    "Edit {step} of {} was rejected, so none of them were staged.\n\n{steps}❌ \
+                     {step}. {description}\n\n{}",
+                    batch.len(),
+                    message.trim_end()
+                )));
+            }
+        }
+    }
+    Ok(Ok(Combined {
+        source,
+        encoding,
+        output,
+        steps,
+    }))
+}
+
+/// The anchor an `anchor_ref` names in `file_path`, which can't be combined with an anchor
+pub(crate) fn resolve_anchor_ref(
+    state: &SemanticEditTools,
+    file_path: &Path,
+    selector: &Selector,
+    anchor_ref: &str,
+) -> Result<String> {
+    if !selector.anchor.trim().is_empty() {
+        return Err(anyhow!(
+            "Pass either anchor or anchor_ref, not both. anchor_ref `{anchor_ref}` reuses an \
+             anchor from an earlier edit to this file."
+        ));
+    }
+    state.resolve_anchor_ref(file_path, anchor_ref, None)
+}
@@ -0,0 +1,227 @@
+use crate::{
+    editor::Editor,
+    encoding::Encoding,
+    languages::LanguageName,
+    module_map::{Location, module_map},
+    public_api::{ApiItem, public_api},
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow, bail};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// List the public API of a Rust file or module: every reachable `pub` item with its signature
+///
+/// With `staged`, compare the public API before and after the staged edit instead, to check
+/// whether the edit changes public API surface before persisting it.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "public_api")]
+#[group(skip)]
+pub struct PublicApi {
+    /// A Rust file, or a module path like `crate::state` in the crate at the working directory.
+    /// Defaults to the staged edit's file when `staged` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub path: Option<String>,
+
+    /// Show how the staged edit would change the public API instead of listing it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub staged: bool,
+}
+
+impl WithExamples for PublicApi {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "List the public API of a file",
+                item: Self {
+                    path: Some("src/state.rs".into()),
+                    staged: false,
+                },
+            },
+            Example {
+                description: "List the public API of a module",
+                item: Self {
+                    path: Some("crate::state::session_store".into()),
+                    staged: false,
+                },
+            },
+            Example {
+                description: "Check whether the staged edit changes any public signatures",
+                item: Self {
+                    path: None,
+                    staged: true,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for PublicApi {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let working_directory = state.get_context(None)?;
+        let display = |path: &Path| -> PathBuf {
+            working_directory
+                .as_deref()
+                .and_then(|base| path.strip_prefix(base).ok())
+                .unwrap_or(path)
+                .to_path_buf()
+        };
+
+        let staged_operation = if self.staged {
+            Some(state.get_staged_operation(None)?.ok_or_else(|| {
+                anyhow!("No operation is currently staged. Use preview_edit first.")
+            })?)
+        } else {
+            None
+        };
+
+        let file = match (&self.path, &staged_operation) {
+            (Some(path), _) if path == "crate" || path.starts_with("crate::") => {
+                resolve_module(state, path, working_directory.as_deref())?
+            }
+            (Some(path), _) => state.resolve_path(path, None)?,
+            (None, Some(staged_operation)) => staged_operation.file_path.clone(),
+            (None, None) => bail!("Provide the path of a file or module, or set `staged`."),
+        };
+
+        let dialect = state.dialects(None)?.get(&file);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file, dialect)?;
+        let query = language.public_api_query().ok_or_else(|| {
+            anyhow!(
+                "Listing the public API is not supported for {language} files like {}",
+                display(&file).display()
+            )
+        })?;
+        let api_of = |source: &str| -> Result<Vec<ApiItem>> {
+            let tree = language
+                .tree_sitter_parser()?
+                .parse(source, None)
+                .ok_or_else(|| anyhow!("Unable to parse {}", display(&file).display()))?;
+            Ok(public_api(query, &tree, source))
+        };
+
+        let (source, _) = Encoding::read(&file)?;
+        let before = api_of(&source)?;
+
+        let Some(staged_operation) = staged_operation else {
+            if before.is_empty() {
+                return Ok(format!("{} has no public API", display(&file).display()));
+            }
+            let mut response = format!("Public API of {}:\n", display(&file).display());
+            for item in before {
+                writeln!(
+                    response,
+                    "{:>5}  {}{}",
+                    item.line,
+                    "    ".repeat(item.depth),
+                    item.signature
+                )?;
+            }
+            return Ok(response);
+        };
+
+        if staged_operation.file_path != file {
+            bail!(
+                "The staged edit is for {}, not {}",
+                display(&staged_operation.file_path).display(),
+                display(&file).display()
+            );
+        }
+        let editor = Editor::from_staged_operation(staged_operation, state.language_registry())?;
+        let (message, output, _) = editor.commit()?;
+        let output = output.ok_or_else(|| {
+            anyhow!("The staged edit cannot be applied, so its API changes are unknown.\n\n{message}")
+        })?;
+        let after = api_of(output.trim_start_matches('\u{feff}'))?;
+
+        let (before, after) = (qualified(&before), qualified(&after));
+        let removed = before.iter().filter(|item| !after.contains(item));
+        let added = after.iter().filter(|item| !before.contains(item));
+        let changes = removed
+            .map(|item| format!("- {item}"))
+            .chain(added.map(|item| format!("+ {item}")))
+            .collect::<Vec<_>>();
+
+        Ok(if changes.is_empty() {
+            format!(
+                "The staged edit does not change the public API of {}",
+                display(&file).display()
+            )
+        } else {
+            format!(
+                "The staged edit changes the public API of {}:\n{}\n",
+                display(&file).display(),
+                changes.join("\n")
+            )
+        })
+    }
+}
+
+/// Find the file that defines a module in the crate at the working directory
+fn resolve_module(
+    state: &SemanticEditTools,
+    module_path: &str,
+    working_directory: Option<&Path>,
+) -> Result<PathBuf> {
+    let crate_directory = working_directory.ok_or_else(|| {
+        anyhow!("No working directory is set, so {module_path} cannot be found. Use set_working_directory first.")
+    })?;
+    let rust = state.language_registry().get_language(LanguageName::Rust);
+
+    for root in ["src/lib.rs", "src/main.rs"]
+        .into_iter()
+        .map(|root| crate_directory.join(root))
+        .filter(|root| root.is_file())
+    {
+        for module in module_map(rust, &root)? {
+            if module.path != module_path {
+                continue;
+            }
+            return match module.location {
+                Location::File(file) => Ok(file),
+                Location::Inline(file) => Err(anyhow!(
+                    "{module_path} is declared inline in {}. List the public API of that file instead.",
+                    file.strip_prefix(crate_directory).unwrap_or(&file).display()
+                )),
+                Location::Missing(_) => Err(anyhow!(
+                    "{module_path} is declared but its file does not exist"
+                )),
+            };
+        }
+    }
+
+    Err(anyhow!(
+        "{module_path} was not found in the crate at the working directory. Use module_map to see \
+         its modules."
+    ))
+}
+
+/// Each item's signature, prefixed with the signatures of the items it is listed under
+fn qualified(items: &[ApiItem]) -> Vec<String> {
+    let mut parents: Vec<&str> = vec![];
+    items
+        .iter()
+        .map(|item| {
+            parents.truncate(item.depth);
+            let qualified = parents
+                .iter()
+                .chain([&&*item.signature])
+                .copied()
+                .collect::<Vec<_>>()
+                .join(" → ");
+            parents.push(&item.signature);
+            qualified
+        })
+        .collect()
+}
@@ -0,0 +1,42 @@
+use crate::state::SemanticEditTools;
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Switch the working context path for a session, saving the current one so that
+/// pop_working_directory can return to it
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "push_working_directory")]
+#[group(skip)]
+pub struct PushWorkingDirectory {
+    /// New working directory. Relative paths are resolved against the current working directory.
+    /// `~` and environment variables like `$HOME` are expanded.
+    path: String,
+}
+
+impl WithExamples for PushWorkingDirectory {
+    fn examples() -> Vec<Example<Self>> {
+        vec![Example {
+            description: "temporarily switching to a sibling repository",
+            item: Self {
+                path: "../shared-library".into(),
+            },
+        }]
+    }
+}
+
+impl Tool<SemanticEditTools> for PushWorkingDirectory {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let new_context_path = state.resolve_path(&self.path, None)?;
+        let response = format!("Set context to {}", new_context_path.display());
+        state.push_working_directory(new_context_path, None)?;
+        let depth = state.directory_stack(None)?.len();
+        Ok(format!(
+            "{response}\n{depth} saved director{} (use pop_working_directory to return)",
+            if depth == 1 { "y" } else { "ies" }
+        ))
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{editor::Editor, selector::Selector, state::SemanticEditTools};
+use crate::{
+    editor::Editor, selector::Selector, state::SemanticEditTools,
+    tools::preview_edit::resolve_anchor_ref,
+};
 
 use anyhow::{anyhow, Result};
 use mcplease::{
@@ -16,6 +19,13 @@ pub struct RetargetEdit {
     #[serde(flatten)]
     #[clap(flatten)]
     pub selector: Selector,
+
+    /// Reuse an anchor that recently resolved in the staged file instead of sending it again:
+    /// `last` for the most recent, or `last-1` through `last-4` for those before it. Leave
+    /// `anchor` empty when this is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub anchor_ref: Option<String>,
 }
 
 impl WithExamples for RetargetEdit {
@@ -67,7 +77,18 @@ impl WithExamples for RetargetEdit {
 
 impl Tool<SemanticEditTools> for RetargetEdit {
     fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
-        let Self { selector } = self;
+        let Self {
+            mut selector,
+            anchor_ref,
+        } = self;
+
+        if let Some(anchor_ref) = anchor_ref {
+            let staged = state
+                .get_staged_operation(None)?
+                .ok_or_else(|| anyhow!("no operation staged"))?;
+            selector.anchor =
+                resolve_anchor_ref(state, &staged.file_path, &selector, &anchor_ref)?;
+        }
 
         let staged_operation = state
             .modify_staged_operation(None, |op| op.retarget(selector))?
@@ -75,10 +96,15 @@ impl Tool<SemanticEditTools> for RetargetEdit {
 
         let editor =
             Editor::from_staged_operation(staged_operation.clone(), state.language_registry())?;
+        let review = staged_operation.review.clone();
         let (message, staged_operation) = editor.preview()?;
-        if staged_operation.is_some() {
+        if let Some(mut staged) = staged_operation {
+            state.record_anchor(&staged.file_path, &staged.selector.anchor, None)?;
+            staged.review = review;
             // leave failed operations in place
-            state.preview_edit(None, staged_operation)?;
+            state.preview_edit(None, Some(staged))?;
+        } else {
+            state.record_rejection(None)?;
         }
         Ok(message)
     }
@@ -0,0 +1,269 @@
+use crate::{
+    budget::Budget,
+    codemod::{self, CODEMODS, Codemod},
+    editor::{Editor, format_patch},
+    encoding::{self, Encoding},
+    guardrails::{self, changed_bytes},
+    protected,
+    response_format::{FileReport, FileStatus, ResponseFormat},
+    state::SemanticEditTools,
+    workspace::{self, Cursor},
+};
+use anyhow::{Result, anyhow};
+use diffy::DiffOptions;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Run a named codemod from `.semantic-edit/codemods/` across the workspace and show the
+/// report of each file it changed or skipped
+///
+/// A codemod is a TOML or JSON file with a `language` and a list of `steps`, each a tree-sitter
+/// `query`, the `capture` to replace, and a `replace` template in which `{{capture}}` stands
+/// for the text of a capture. Steps run in order on every file of that language. Nothing is
+/// written unless `apply` is true, and files whose result has syntax errors are never written.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "run_codemod")]
+#[group(skip)]
+pub struct RunCodemod {
+    /// Name of the codemod, the file name in `.semantic-edit/codemods/` without its extension
+    pub name: String,
+
+    /// Directory to run the codemod in. Defaults to the session working directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub path: Option<String>,
+
+    /// Write the changes after reviewing the diff
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub apply: bool,
+
+    /// Write the changes even though they exceed the configured write limits or change protected
+    /// files
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub confirm: bool,
+
+    /// Stop after roughly this many milliseconds and report on the files reached so far, along
+    /// with a continuation token for running the codemod on the rest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_ms: Option<u64>,
+
+    /// Continuation token from a previous run that reached its time limit. Pass it with the same
+    /// name and path to run the codemod on the files that run didn't reach.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub continuation: Option<String>,
+}
+
+impl WithExamples for RunCodemod {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Preview what the unwrap_to_expect codemod would change",
+                item: Self {
+                    name: "unwrap_to_expect".into(),
+                    path: None,
+                    apply: false,
+                    confirm: false,
+                    timeout_ms: None,
+                    continuation: None,
+                },
+            },
+            Example {
+                description: "Apply a reviewed codemod to one directory",
+                item: Self {
+                    name: "unwrap_to_expect".into(),
+                    path: Some("src/parser".into()),
+                    apply: true,
+                    confirm: false,
+                    timeout_ms: None,
+                    continuation: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for RunCodemod {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let working_directory = state.get_context(None)?.ok_or_else(|| {
+            anyhow!("No working directory is set, so codemods cannot be found. Use set_working_directory first.")
+        })?;
+        let root = match &self.path {
+            Some(path) => state.resolve_path(path, None)?,
+            None => working_directory.clone(),
+        };
+
+        let codemod = Codemod::load(&working_directory, &self.name)?;
+        let dialects = state.dialects(None)?;
+        let registry = state.language_registry();
+        let language = registry.get_language(codemod.language);
+        let steps = codemod.compile(language)?;
+
+        let mut changed = vec![];
+        let mut files = vec![];
+        let mut total = 0;
+        let mut replacements = 0;
+        let cursor = self
+            .continuation
+            .as_deref()
+            .map(str::parse::<Cursor>)
+            .transpose()?;
+        let max_size = encoding::max_file_size()?;
+        let mut walk = workspace::walk(&root, Budget::from_timeout_ms(self.timeout_ms))
+            .resume(cursor.as_ref());
+        for file in &mut walk {
+            let path = root.join(&file);
+            let detected = dialects
+                .get(&path)
+                .or_else(|| registry.detect_language_from_path(&path));
+            if detected != Some(codemod.language) {
+                continue;
+            }
+            total += 1;
+            let skipped = |summary: &str, body: Option<String>| FileReport {
+                file: file.display().to_string(),
+                status: FileStatus::Skipped,
+                summary: summary.into(),
+                body,
+            };
+            if encoding::check_size_within(&path, max_size).is_err() {
+                files.push(skipped("larger than the size limit for editing", None));
+                continue;
+            }
+            let Ok((source, encoding)) = Encoding::read(&path) else {
+                files.push(skipped("unable to read it", None));
+                continue;
+            };
+            let (output, count) = codemod::apply(language, &steps, &file, &source)?;
+            if output == source {
+                if count > 0 {
+                    files.push(FileReport {
+                        file: file.display().to_string(),
+                        status: FileStatus::Unchanged,
+                        summary: format!(
+                            "{count} match{} replaced with the same text",
+                            if count == 1 { "" } else { "es" }
+                        ),
+                        body: None,
+                    });
+                }
+                continue;
+            }
+
+            if encoding.read_only_reason().is_some() {
+                files.push(skipped("not valid UTF-8", None));
+                continue;
+            }
+            let tree = language
+                .tree_sitter_parser()?
+                .parse(&output, None)
+                .ok_or_else(|| anyhow!("Unable to parse the result for {}", file.display()))?;
+            if let Some(errors) = Editor::validate(language, &tree, &output) {
+                files.push(skipped("the result has syntax errors", Some(errors)));
+                continue;
+            }
+
+            replacements += count;
+            let patch = DiffOptions::new().create_patch(&source, &output);
+            files.push(FileReport {
+                file: file.display().to_string(),
+                status: FileStatus::Changed,
+                summary: format!(
+                    "{count} replacement{}",
+                    if count == 1 { "" } else { "s" }
+                ),
+                body: Some(format_patch(&patch)),
+            });
+            changed.push((path, source, output, encoding));
+        }
+        let stopped = walk
+            .continuation()
+            .map(|cursor| {
+                format!(
+                    "\nReached the time limit before reaching every file. Run the codemod again \
+                     with the same name and continuation: \"{cursor}\" for the rest."
+                )
+            })
+            .unwrap_or_default();
+
+        if files.is_empty() {
+            return Ok(format!(
+                "The {} codemod matched nothing in {}{stopped}",
+                self.name,
+                root.display()
+            ));
+        }
+
+        let mut response = ResponseFormat::configured().file_report(
+            &format!(
+                "The {} codemod {} {replacements} replacement{} in {} file{}.",
+                self.name,
+                if self.apply { "made" } else { "would make" },
+                if replacements == 1 { "" } else { "s" },
+                changed.len(),
+                if changed.len() == 1 { "" } else { "s" },
+            ),
+            &files,
+            total,
+        );
+
+        let protected = changed
+            .iter()
+            .filter_map(|(path, ..)| protected::reason(path).transpose())
+            .collect::<Result<Vec<_>>>()?;
+        for protected in &protected {
+            writeln!(response, "\n⚠️ PROTECTED FILE: {protected}")?;
+        }
+
+        let exceeded = if self.apply && !self.confirm && !changed.is_empty() {
+            let bytes = changed
+                .iter()
+                .map(|(_, source, output, _)| changed_bytes(source.as_bytes(), output.as_bytes()))
+                .sum();
+            let mut exceeded = protected;
+            exceeded.extend(state.guardrails().exceeded(changed.len(), bytes));
+            exceeded
+        } else {
+            vec![]
+        };
+
+        if !exceeded.is_empty() {
+            write!(
+                response,
+                "\n{}",
+                guardrails::confirmation_required(
+                    &exceeded,
+                    "run the codemod again with apply and confirm"
+                )
+            )?;
+        } else if self.apply {
+            if !changed.is_empty() {
+                state.guardrails().record_persist();
+            }
+            let commit = state.commit_fn_mut().take();
+            for (path, source, output, encoding) in changed {
+                match &commit {
+                    Some(commit) => commit(path.clone(), output.clone()),
+                    None => std::fs::write(&path, encoding.encode(output.clone()))?,
+                }
+                state.record_write(&path, Some(&source), &output, None, None)?;
+            }
+        } else if !changed.is_empty() {
+            write!(
+                response,
+                "\nNothing was written. Run the codemod again with apply to write these \
+                 changes, or adjust its steps in {CODEMODS}/ first."
+            )?;
+        }
+        response.push_str(&stopped);
+
+        Ok(response)
+    }
+}
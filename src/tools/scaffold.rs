@@ -0,0 +1,96 @@
+use crate::{editor::Editor, scaffold::Template, state::SemanticEditTools};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Create a new source file from a template: a Rust module with tests, a TypeScript React
+/// component, a Python module with a main guard, or a project template from
+/// `.semantic-edit/templates/`
+///
+/// `{{name}}` in a template is replaced by the new file's name and `{{Name}}` by the same name
+/// in PascalCase. The generated file is checked for syntax errors before it is written, and
+/// existing files are never overwritten.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "scaffold")]
+#[group(skip)]
+pub struct Scaffold {
+    /// Path of the file to create
+    pub file_path: String,
+
+    /// Name of the template to use. Defaults to the built-in template for the file's extension.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub template: Option<String>,
+}
+
+impl WithExamples for Scaffold {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Start a new Rust module",
+                item: Self {
+                    file_path: "src/tokenizer.rs".into(),
+                    template: None,
+                },
+            },
+            Example {
+                description: "Create a file from a project template in .semantic-edit/templates/",
+                item: Self {
+                    file_path: "src/handlers/users.rs".into(),
+                    template: Some("handler".into()),
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for Scaffold {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_new_path(&self.file_path, None)?;
+        if file_path.exists() {
+            return Err(anyhow!(
+                "{} already exists. Use preview_edit to change it.",
+                self.file_path
+            ));
+        }
+
+        let templates = Template::all(state.get_context(None)?.as_deref())?;
+        let template = Template::find(&templates, self.template.as_deref(), &file_path)?;
+        let content = template.render(&file_path);
+
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+        let tree = language
+            .tree_sitter_parser()?
+            .parse(&content, None)
+            .ok_or_else(|| anyhow!("Unable to parse the {} template", template.name))?;
+        if let Some(errors) = Editor::validate(language, &tree, &content) {
+            return Err(anyhow!(
+                "The {} template does not produce valid {language} for {}, so nothing was \
+                 written:\n\n{errors}",
+                template.name,
+                self.file_path
+            ));
+        }
+
+        if let Some(commit) = state.commit_fn_mut().take() {
+            commit(file_path.clone(), content.clone());
+        } else {
+            if let Some(parent) = file_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&file_path, &content)?;
+        }
+        state.record_write(&file_path, None, &content, None, None)?;
+
+        Ok(format!(
+            "Created {} from the {} template:\n\n{content}",
+            self.file_path, template.name
+        ))
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{
+    budget::Budget,
+    encoding::Encoding,
+    response_format::ResponseFormat,
+    state::SemanticEditTools,
+    symbols,
+    workspace::{self, Page, Stop},
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Find functions, types, classes, and other symbols by name in every project file under a
+/// directory, with the file and line each is defined on
+///
+/// Files are parsed with their language's grammar, so only definitions are found, not mentions.
+/// A query matches every name it appears in, ignoring case, and `*` and `?` make it a wildcard
+/// pattern that must match whole names, like `*Config` or `parse_?`. Hidden files and dependency
+/// or build output directories are skipped.
+#[derive(Serialize, Deserialize, Debug, JsonSchema, clap::Args)]
+#[serde(rename = "search_symbols")]
+#[group(skip)]
+pub struct SearchSymbols {
+    /// The name, part of a name, or wildcard pattern to look for
+    pub query: String,
+
+    /// Only find symbols of this kind, as labeled in outlines, like `fn`, `struct`, `class`, or
+    /// `interface`. `fn` also finds Python `def`s, JavaScript `function`s, and methods.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub kind: Option<String>,
+
+    /// Directory to search. Defaults to the session working directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub path: Option<String>,
+
+    /// Maximum number of symbols to return at once. Defaults to 100. When there are more, the
+    /// response ends with a continuation token for the next page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub page_size: Option<usize>,
+
+    /// Stop after roughly this many milliseconds and return the symbols found so far, along
+    /// with a continuation token for picking up where the search stopped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_ms: Option<u64>,
+
+    /// Continuation token from a previous page or partial result. Pass it with the same query,
+    /// kind, and path to resume the search.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub continuation: Option<String>,
+}
+
+impl WithExamples for SearchSymbols {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Find where a function is defined before editing its callers",
+                item: Self {
+                    query: "display_name".into(),
+                    kind: Some("fn".into()),
+                    path: None,
+                    page_size: None,
+                    timeout_ms: None,
+                    continuation: None,
+                },
+            },
+            Example {
+                description: "List every configuration struct in a crate",
+                item: Self {
+                    query: "*Config".into(),
+                    kind: Some("struct".into()),
+                    path: Some("crates/server".into()),
+                    page_size: None,
+                    timeout_ms: None,
+                    continuation: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for SearchSymbols {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let Self {
+            query,
+            kind,
+            path,
+            page_size,
+            timeout_ms,
+            continuation,
+        } = self;
+
+        let budget = Budget::from_timeout_ms(timeout_ms);
+        let root = match &path {
+            Some(path) => state.resolve_path(path, None)?,
+            None => state.get_context(None)?.ok_or_else(|| {
+                anyhow!("No working directory is set. Use set_working_directory or pass a path.")
+            })?,
+        };
+        let matcher = Matcher::new(query.trim())?;
+        let kind = kind.as_deref().map(str::trim);
+
+        let dialects = state.dialects(None)?;
+        let registry = state.language_registry();
+        let mut page = Page::new(page_size, continuation.as_deref())?;
+        let mut files = workspace::walk(&root, budget).resume(page.cursor());
+
+        for file in &mut files {
+            let path = root.join(&file);
+            let language = dialects
+                .get(&path)
+                .or_else(|| registry.detect_language_from_path(&path))
+                .map(|name| registry.get_language(name))
+                .filter(|language| !language.symbol_kinds().is_empty());
+            let tree = language.and_then(|language| {
+                let (source, _) = Encoding::read(&path).ok()?;
+                let tree = language.tree_sitter_parser().ok()?.parse(&source, None)?;
+                Some((language, source, tree))
+            });
+            let Some((language, source, tree)) = tree else {
+                continue;
+            };
+            let outline = symbols::outline(language, &tree, &source);
+            let matching = (0..outline.len())
+                .filter(|index| {
+                    let symbol = &outline[*index];
+                    matcher.matches(&symbol.name)
+                        && kind.is_none_or(|kind| symbols::is_kind(symbol, kind))
+                })
+                .map(|index| {
+                    (
+                        file.display().to_string(),
+                        outline[index].node.start_position().row + 1,
+                        symbols::describe(&outline, index),
+                    )
+                });
+            if !page.extend(&file, matching) {
+                break;
+            }
+        }
+
+        let page_size = page.size();
+        let stop = page.stop(&files);
+        let found = page.items;
+        let kind = kind.map(|kind| format!("{kind} ")).unwrap_or_default();
+        let mut response = if found.is_empty() {
+            format!("No {kind}symbols match \"{query}\"\n")
+        } else {
+            format!(
+                "{} {kind}symbol{} matching \"{query}\":\n{}",
+                found.len(),
+                if found.len() == 1 { "" } else { "s" },
+                ResponseFormat::configured().matches(
+                    found
+                        .iter()
+                        .map(|(file, line, symbol)| (file.as_str(), *line, symbol.as_str()))
+                )
+            )
+        };
+
+        match stop {
+            Some(Stop::PageFull(cursor)) => write!(
+                response,
+                "\nThere are more symbols than fit in one page of {page_size}. Call \
+                 search_symbols again with the same query and continuation: \"{cursor}\" for \
+                 the next page."
+            )?,
+            Some(Stop::TimeLimit(cursor)) => write!(
+                response,
+                "\nReached the time limit before searching every file. Call search_symbols \
+                 again with the same query and continuation: \"{cursor}\" to search the rest."
+            )?,
+            None => {}
+        }
+
+        Ok(response)
+    }
+}
+
+/// How a query is compared with symbol names
+enum Matcher {
+    /// Names containing this, lowercased
+    Contains(String),
+    /// Names matching a wildcard pattern
+    Pattern(Regex),
+}
+
+impl Matcher {
+    fn new(query: &str) -> Result<Self> {
+        if query.is_empty() {
+            return Err(anyhow!("The query is empty. Use `*` to list every symbol."));
+        }
+        if !query.contains(['*', '?']) {
+            return Ok(Self::Contains(query.to_lowercase()));
+        }
+        let pattern = query
+            .chars()
+            .map(|c| match c {
+                '*' => ".*".to_string(),
+                '?' => ".".to_string(),
+                c => regex::escape(&c.to_string()),
+            })
+            .collect::<String>();
+        Ok(Self::Pattern(Regex::new(&format!("(?i)^{pattern}$"))?))
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Contains(query) => name.to_lowercase().contains(query),
+            Self::Pattern(pattern) => pattern.is_match(name),
+        }
+    }
+}
+
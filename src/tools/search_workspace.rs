@@ -0,0 +1,164 @@
+use crate::{
+    budget::Budget, encoding::Encoding, response_format::ResponseFormat, searcher::find_positions,
+    state::SemanticEditTools,
+    workspace::{self, Page, Stop},
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Search every project file under a directory for a snippet of text
+///
+/// Matching works like anchors: whitespace differences are ignored, so results can be used
+/// directly as anchors for preview_edit. Hidden files and dependency or build output
+/// directories are skipped.
+#[derive(Serialize, Deserialize, Debug, JsonSchema, clap::Args)]
+#[serde(rename = "search_workspace")]
+#[group(skip)]
+pub struct SearchWorkspace {
+    /// Text to search for
+    pub query: String,
+
+    /// Directory to search. Defaults to the session working directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub path: Option<String>,
+
+    /// Maximum number of matches to return at once. Defaults to 100. When there are more, the
+    /// response ends with a continuation token for the next page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub page_size: Option<usize>,
+
+    /// Stop after roughly this many milliseconds and return the matches found so far, along
+    /// with a continuation token for picking up where the search stopped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_ms: Option<u64>,
+
+    /// Continuation token from a previous page or partial result. Pass it with the same query
+    /// and path to resume the search.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub continuation: Option<String>,
+}
+
+impl WithExamples for SearchWorkspace {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Find every call site of a function",
+                item: Self {
+                    query: "display_name(".into(),
+                    path: None,
+                    page_size: None,
+                    timeout_ms: None,
+                    continuation: None,
+                },
+            },
+            Example {
+                description: "Search a large monorepo without waiting more than two seconds",
+                item: Self {
+                    query: "fn handle_request".into(),
+                    path: Some("services".into()),
+                    page_size: None,
+                    timeout_ms: Some(2000),
+                    continuation: None,
+                },
+            },
+            Example {
+                description: "Page through a very common pattern twenty matches at a time",
+                item: Self {
+                    query: ".unwrap()".into(),
+                    path: None,
+                    page_size: Some(20),
+                    timeout_ms: None,
+                    continuation: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for SearchWorkspace {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let Self {
+            query,
+            path,
+            page_size,
+            timeout_ms,
+            continuation,
+        } = self;
+
+        let budget = Budget::from_timeout_ms(timeout_ms);
+        let root = match &path {
+            Some(path) => state.resolve_path(path, None)?,
+            None => state.get_context(None)?.ok_or_else(|| {
+                anyhow!("No working directory is set. Use set_working_directory or pass a path.")
+            })?,
+        };
+        let mut page = Page::new(page_size, continuation.as_deref())?;
+        let mut files = workspace::walk(&root, budget).resume(page.cursor());
+
+        for file in &mut files {
+            let Ok((source, _)) = Encoding::read(&root.join(&file)) else {
+                continue;
+            };
+            let positions = find_positions(&source, query.trim()).unwrap_or_default();
+            let matches = positions.into_iter().map(|(start, _)| {
+                let line_number = source[..start].matches('\n').count() + 1;
+                let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+                let line_end = source[start..]
+                    .find('\n')
+                    .map_or(source.len(), |index| start + index);
+                let line = source[line_start..line_end].trim();
+                (file.display().to_string(), line_number, line.to_string())
+            });
+            if !page.extend(&file, matches) {
+                break;
+            }
+        }
+
+        let page_size = page.size();
+        let stop = page.stop(&files);
+        let matches = page.items;
+        let match_count = matches.len();
+
+        let mut response = if match_count == 0 {
+            format!("No matches for \"{query}\"\n")
+        } else {
+            format!(
+                "{match_count} match{} for \"{query}\":\n{}",
+                if match_count == 1 { "" } else { "es" },
+                ResponseFormat::configured().matches(
+                    matches
+                        .iter()
+                        .map(|(file, line, text)| (file.as_str(), *line, text.as_str()))
+                )
+            )
+        };
+
+        match stop {
+            Some(Stop::PageFull(cursor)) => write!(
+                response,
+                "\nThere are more matches than fit in one page of {page_size}. Call \
+                 search_workspace again with the same query and continuation: \"{cursor}\" for \
+                 the next page."
+            )?,
+            Some(Stop::TimeLimit(cursor)) => write!(
+                response,
+                "\nReached the time limit before searching every file. Call search_workspace \
+                 again with the same query and continuation: \"{cursor}\" to search the rest."
+            )?,
+            None => {}
+        }
+
+        Ok(response)
+    }
+}
+
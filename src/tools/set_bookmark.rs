@@ -0,0 +1,111 @@
+use crate::{
+    encoding::{self, Encoding},
+    searcher::find_positions,
+    state::{Bookmark, SemanticEditTools},
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Mark the syntax node that starts at an anchor with a name, so use_bookmark can target it
+/// later in the session
+///
+/// The bookmark follows its text as edits are persisted to the file, so it stays usable after
+/// changes above or inside it, when the anchor itself may no longer be unique.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "set_bookmark")]
+#[group(skip)]
+pub struct SetBookmark {
+    /// Name to refer to the bookmark by. Setting a name again moves the bookmark.
+    pub name: String,
+
+    /// Path to the source file
+    pub file_path: String,
+
+    /// Text at the start of the location to mark, like `fn parse_header`. Whitespace doesn't
+    /// matter, but the anchor must be unique in the file.
+    pub anchor: String,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for SetBookmark {
+    fn examples() -> Vec<Example<Self>> {
+        vec![Example {
+            description: "Mark a function before adding several helpers after it",
+            item: Self {
+                name: "parser".into(),
+                file_path: "src/parser.rs".into(),
+                anchor: "fn parse_header".into(),
+                allow_large: false,
+            },
+        }]
+    }
+}
+
+impl Tool<SemanticEditTools> for SetBookmark {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        encoding::check_size(&file_path, self.allow_large)?;
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+        let (source, _) = Encoding::read(&file_path)?;
+
+        let (start, end) = match find_positions(&source, self.anchor.trim())
+            .map_err(|message| anyhow!(message))?
+            .as_slice()
+        {
+            [position] => *position,
+            matches => {
+                return Err(anyhow!(
+                    "Anchor \"{}\" matches {} places in {}. Use a longer anchor that only \
+                     matches one.",
+                    self.anchor,
+                    matches.len(),
+                    self.file_path
+                ));
+            }
+        };
+
+        // the largest node that starts where the anchor does, like a whole function for the
+        // start of its signature
+        let tree = language.tree_sitter_parser()?.parse(&source, None);
+        let node = tree
+            .as_ref()
+            .and_then(|tree| tree.root_node().descendant_for_byte_range(start, end))
+            .map(|mut node| {
+                while let Some(parent) = node.parent()
+                    && parent.parent().is_some()
+                    && parent.start_byte() == node.start_byte()
+                {
+                    node = parent;
+                }
+                node
+            });
+        let range = node.map_or(start..end, |node| node.byte_range());
+
+        let first_line = source[..range.start].matches('\n').count() + 1;
+        let last_line = first_line + source[range.clone()].matches('\n').count();
+        let response = format!(
+            "Bookmarked `{}`: {} on {} of {}",
+            self.name,
+            node.map_or("text", |node| node.kind()).replace('_', " "),
+            if first_line == last_line {
+                format!("line {first_line}")
+            } else {
+                format!("lines {first_line}-{last_line}")
+            },
+            self.file_path
+        );
+        state.set_bookmark(&self.name, Bookmark::new(file_path, range, &source), None)?;
+        Ok(response)
+    }
+}
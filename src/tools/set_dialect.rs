@@ -0,0 +1,82 @@
+use crate::{languages::LanguageName, state::SemanticEditTools};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Choose which grammar parses files with an extension for the rest of the session, like JSX
+/// for a project that writes JSX in `.js` files
+///
+/// The TypeScript grammars already accept decorators, so they need no dialect. Omit `language`
+/// to return an extension to its default grammar.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "set_dialect")]
+#[group(skip)]
+pub struct SetDialect {
+    /// File extension, like `js` or `.mjs`
+    pub extension: String,
+
+    /// Language whose grammar should parse these files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long, value_enum)]
+    pub language: Option<LanguageName>,
+}
+
+impl WithExamples for SetDialect {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Parse .js files as JSX in a React project",
+                item: Self {
+                    extension: "js".into(),
+                    language: Some(LanguageName::Jsx),
+                },
+            },
+            Example {
+                description: "Edit ES module files with the JavaScript grammar",
+                item: Self {
+                    extension: "mjs".into(),
+                    language: Some(LanguageName::Javascript),
+                },
+            },
+            Example {
+                description: "Go back to detecting .js files as plain JavaScript",
+                item: Self {
+                    extension: "js".into(),
+                    language: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for SetDialect {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let extension = self.extension.trim().trim_start_matches('.');
+        if extension.is_empty() || extension.contains(['/', '\\']) {
+            return Err(anyhow!(
+                "`{}` is not a file extension. Pass one like `js` or `.mjs`.",
+                self.extension
+            ));
+        }
+
+        state.set_dialect(extension, self.language, None)?;
+
+        let mut response = match self.language {
+            Some(language) => format!("Files ending in .{extension} are now parsed as {language}"),
+            None => format!("Files ending in .{extension} are now detected as usual"),
+        };
+
+        let dialects = state.dialects(None)?;
+        if !dialects.is_empty() {
+            response.push_str("\n\nDialects for this session:");
+            for (extension, language) in dialects.iter() {
+                response.push_str(&format!("\n- .{extension} → {language}"));
+            }
+        }
+
+        Ok(response)
+    }
+}
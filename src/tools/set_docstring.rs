@@ -0,0 +1,154 @@
+use crate::{
+    docstrings::{body_start, docstring, render_docstring},
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    languages::LanguageName,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+    symbols,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Stage a change to the docstring of a Python function or class, found by name, and see a
+/// preview of it like preview_edit
+///
+/// An existing docstring is replaced and keeps its quote style, and a new one is quoted like the
+/// other docstrings in the file and indented to fit the body.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "set_docstring")]
+#[group(skip)]
+pub struct SetDocstring {
+    /// Path to the Python source file
+    pub file_path: String,
+
+    /// The function or class, like `parse` or `Parser.parse` for a method
+    pub symbol: String,
+
+    /// The text of the docstring, without quotes or indentation. Leave it empty to remove the
+    /// docstring.
+    #[arg(long, default_value = "")]
+    pub docstring: String,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for SetDocstring {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Document a method",
+                item: Self {
+                    file_path: "app/parser.py".into(),
+                    symbol: "Parser.parse".into(),
+                    docstring: "Parse one record.\n\nRaises ValueError if the record is empty."
+                        .into(),
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Remove a function's docstring",
+                item: Self {
+                    file_path: "app/util.py".into(),
+                    symbol: "helper".into(),
+                    docstring: String::new(),
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for SetDocstring {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        encoding::check_size(&file_path, self.allow_large)?;
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+        if language.name() != LanguageName::Python {
+            return Err(anyhow!(
+                "set_docstring edits Python docstrings, and {} is {}",
+                self.file_path,
+                language.name()
+            ));
+        }
+        let (source, _) = Encoding::read(&file_path)?;
+        let tree = language
+            .tree_sitter_parser()?
+            .parse(&source, None)
+            .ok_or_else(|| anyhow!("Unable to parse {}", self.file_path))?;
+        let outline = symbols::outline(language, &tree, &source);
+        let node = symbols::find(&outline, &self.symbol).map_err(|message| anyhow!(message))?;
+
+        let existing = docstring(node);
+        let style = existing.or_else(|| outline.iter().find_map(|symbol| docstring(symbol.node)));
+        let line_start = |offset: usize| source[..offset].rfind('\n').map_or(0, |index| index + 1);
+        let (operation, position, content) = match existing {
+            Some(existing) if self.docstring.trim().is_empty() => {
+                let end = source[existing.end_byte()..]
+                    .find('\n')
+                    .map_or(source.len(), |index| existing.end_byte() + index + 1);
+                (
+                    Operation::Replace,
+                    (line_start(existing.start_byte()), Some(end)),
+                    String::new(),
+                )
+            }
+            Some(existing) => (
+                Operation::Replace,
+                (line_start(existing.start_byte()), Some(existing.end_byte())),
+                render_docstring(&self.docstring, Some(&source[existing.byte_range()])),
+            ),
+            None if self.docstring.trim().is_empty() => {
+                return Ok(format!("{} has no docstring to remove", self.symbol));
+            }
+            None => {
+                let first = body_start(node).ok_or_else(|| {
+                    anyhow!("{} has no body to add a docstring to", self.symbol)
+                })?;
+                let start = line_start(first.start_byte());
+                if !source[start..first.start_byte()].trim().is_empty() {
+                    return Err(anyhow!(
+                        "The body of {} is on the same line as its definition. Move it to a line \
+                         of its own before adding a docstring.",
+                        self.symbol
+                    ));
+                }
+                let style = style.map(|style| &source[style.byte_range()]);
+                (
+                    Operation::InsertBefore,
+                    (start, None),
+                    format!("{}\n", render_docstring(&self.docstring, style)),
+                )
+            }
+        };
+
+        let selector = Selector::anchor(
+            operation,
+            source[node.byte_range()]
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim(),
+        );
+        state.stage_edit_at(
+            content,
+            selector,
+            language,
+            file_path,
+            EditPosition {
+                start_byte: position.0,
+                end_byte: position.1,
+            },
+        )
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    jsx,
+    languages::LanguageName,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Stage adding, changing, or removing a prop of a JSX element, found by its component name, and
+/// see a preview of it like preview_edit
+///
+/// New props go after the existing ones, on a line of their own if those are. The element can be
+/// self-closing or have children.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "set_jsx_prop")]
+#[group(skip)]
+pub struct SetJsxProp {
+    /// Path to the JSX or TSX source file
+    pub file_path: String,
+
+    /// The element's name as written in its tag, like `Button`, `Form.Field`, or `div`
+    pub component: String,
+
+    /// Which element with that name, counting from 1 in the order they appear in the file. Needed
+    /// when there is more than one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub occurrence: Option<usize>,
+
+    /// The prop's name
+    pub prop: String,
+
+    /// The prop's value: a `{expression}` or quoted string is used as it is, `true` leaves just
+    /// the prop's name, and other text is quoted. Omit it to remove the prop.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub value: Option<String>,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for SetJsxProp {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Give the second button on a page a variant",
+                item: Self {
+                    file_path: "src/pages/Checkout.tsx".into(),
+                    component: "Button".into(),
+                    occurrence: Some(2),
+                    prop: "variant".into(),
+                    value: Some("primary".into()),
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Pass a handler as an expression",
+                item: Self {
+                    file_path: "src/components/Search.jsx".into(),
+                    component: "input".into(),
+                    occurrence: None,
+                    prop: "onChange".into(),
+                    value: Some("{(event) => setQuery(event.target.value)}".into()),
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Remove a prop",
+                item: Self {
+                    file_path: "src/components/Dialog.tsx".into(),
+                    component: "Modal".into(),
+                    occurrence: None,
+                    prop: "closeOnBlur".into(),
+                    value: None,
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for SetJsxProp {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        encoding::check_size(&file_path, self.allow_large)?;
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+        if !matches!(
+            language.name(),
+            LanguageName::Jsx | LanguageName::Tsx | LanguageName::Javascript
+        ) {
+            return Err(anyhow!(
+                "set_jsx_prop edits JSX elements, and {} is {}",
+                self.file_path,
+                language.name()
+            ));
+        }
+        let (source, _) = Encoding::read(&file_path)?;
+        let tree = language
+            .tree_sitter_parser()?
+            .parse(&source, None)
+            .ok_or_else(|| anyhow!("Unable to parse {}", self.file_path))?;
+        let edit = jsx::set_prop(
+            &tree,
+            &source,
+            &self.component,
+            self.occurrence,
+            &self.prop,
+            self.value.as_deref(),
+        )
+        .map_err(|message| anyhow!(message))?;
+        if edit.content == source[edit.range.clone()] {
+            return Ok(format!(
+                "The <{}> element already has {}",
+                self.component, edit.content
+            ));
+        }
+
+        let (operation, end_byte) = if edit.range.is_empty() {
+            (Operation::InsertAfter, None)
+        } else {
+            (Operation::Replace, Some(edit.range.end))
+        };
+        let selector = Selector::anchor(operation, edit.anchor);
+        state.stage_edit_at(
+            edit.content,
+            selector,
+            language,
+            file_path,
+            EditPosition {
+                start_byte: edit.range.start,
+                end_byte,
+            },
+        )
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    front_matter::{self, Format},
+    key_path::{self, Change, Segment},
+    languages::LanguageRegistry,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+    validation::markdown::is_markdown,
+};
+use anyhow::{Result, anyhow, bail};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Stage setting the value at a key path in a JSON, TOML, or YAML file, or in the front matter
+/// of a Markdown file, and see a preview of it like preview_edit
+///
+/// The path names keys separated by dots and positions in sequences in brackets, counting from
+/// 0, like `jobs.build.steps[2].run` or `bin[0].name` for the first of an array of TOML tables.
+/// Quote a key that contains a dot: `dependencies."serde.json"`. A missing key is added to its
+/// map, along with maps for the keys after it, like `env` for `jobs.build.env.CI`. A position
+/// one past the end of a sequence or `[]`, like `tags[]`, appends to it. A Markdown file without
+/// front matter gets a YAML block for a top-level key.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "set_key")]
+#[group(skip)]
+pub struct SetKey {
+    /// Path to the JSON, TOML, YAML, or Markdown file
+    pub file_path: String,
+
+    /// The key path of the value to set
+    pub path: String,
+
+    /// The new value, written as it would be in the file, with quotes around strings:
+    /// `"vite build"`, `3`, `true`, or `["a", "b"]`
+    pub value: String,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for SetKey {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Change a dependency's version in a Cargo manifest",
+                item: Self {
+                    file_path: "Cargo.toml".into(),
+                    path: "dependencies.serde.version".into(),
+                    value: "\"1.0.200\"".into(),
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Add a tag to the front matter of a blog post",
+                item: Self {
+                    file_path: "content/posts/release.md".into(),
+                    path: "tags[]".into(),
+                    value: "rust".into(),
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Change the command of one step of a job",
+                item: Self {
+                    file_path: "pipeline.json".into(),
+                    path: "jobs.build.steps[2].run".into(),
+                    value: "\"cargo test --all-features\"".into(),
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for SetKey {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        encoding::check_size(&file_path, self.allow_large)?;
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+        let path = key_path::parse(&self.path).map_err(|message| anyhow!(message))?;
+        let (source, _) = Encoding::read(&file_path)?;
+        let change = if is_markdown(&file_path) {
+            front_matter_change(state.language_registry(), &source, &path, self.value.trim())?
+        } else {
+            let tree = language
+                .tree_sitter_parser()?
+                .parse(&source, None)
+                .ok_or_else(|| anyhow!("Unable to parse {}", self.file_path))?;
+            key_path::set(language.editor(), &tree, &source, &path, self.value.trim())
+                .map_err(|message| anyhow!(message))?
+        };
+        if change.content == source[change.range.clone()] {
+            return Ok(format!("{} is already {}", self.path, change.content));
+        }
+
+        let (operation, end_byte) = if change.range.is_empty() {
+            (Operation::InsertAfter, None)
+        } else {
+            (Operation::Replace, Some(change.range.end))
+        };
+        let selector = Selector::anchor(operation, change.anchor);
+        state.stage_edit_at(
+            change.content,
+            selector,
+            language,
+            file_path,
+            EditPosition {
+                start_byte: change.range.start,
+                end_byte,
+            },
+        )
+    }
+}
+
+/// The change to the front matter of a Markdown document, checked with the grammar of its
+/// format since the Markdown editor can't tell whether the front matter is still valid
+fn front_matter_change(
+    registry: &LanguageRegistry,
+    source: &str,
+    path: &[Segment],
+    value: &str,
+) -> Result<Change> {
+    let Some(front_matter) = front_matter::find(source) else {
+        let key = match path {
+            [Segment::Key(key)] | [Segment::Key(key), Segment::Append] => key,
+            _ => bail!(
+                "This document has no front matter, and a new block can only be started with a \
+                 top-level key like `title` or `tags[]`"
+            ),
+        };
+        let value = match path {
+            [_, Segment::Append] => format!("[{value}]"),
+            _ => value.to_string(),
+        };
+        return Ok(Change {
+            range: 0..0,
+            content: front_matter::new_block(&format!("{key}: {value}")),
+            anchor: source.lines().next().unwrap_or_default().trim().to_string(),
+        });
+    };
+
+    let language = registry.get_language(front_matter.format.language());
+    let block = &source[front_matter.range.clone()];
+    let mut parser = language.tree_sitter_parser()?;
+    let tree = parser
+        .parse(block, None)
+        .ok_or_else(|| anyhow!("Unable to parse the front matter"))?;
+    let mut change = key_path::set(language.editor(), &tree, block, path, value)
+        .map_err(|message| anyhow!(message))?;
+
+    let updated = format!(
+        "{}{}{}",
+        &block[..change.range.start],
+        change.content,
+        &block[change.range.end..]
+    );
+    let tree = parser
+        .parse(&updated, None)
+        .ok_or_else(|| anyhow!("Unable to parse the front matter"))?;
+    if let Some(error) = language.editor().collect_errors(&tree, &updated).first() {
+        bail!(
+            "This would leave the {} front matter invalid on line {}{}. No change was staged.",
+            match front_matter.format {
+                Format::Yaml => "YAML",
+                Format::Toml => "TOML",
+            },
+            // counting the opening delimiter
+            error.line + 2,
+            error
+                .hint
+                .as_ref()
+                .map(|hint| format!(": {hint}"))
+                .unwrap_or_default()
+        );
+    }
+
+    change.range =
+        change.range.start + front_matter.range.start..change.range.end + front_matter.range.start;
+    Ok(change)
+}
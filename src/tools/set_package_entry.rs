@@ -0,0 +1,137 @@
+use crate::{
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    package_json::{self, FIELDS},
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Stage adding, changing, or removing an entry of `scripts`, `dependencies`, or another map in
+/// package.json, and see a preview of it like preview_edit
+///
+/// Entries keep their place in the map. New scripts are added at the end, and other new entries
+/// in alphabetical order if the map is in that order, as npm keeps dependencies. They are
+/// indented and spaced like the rest of the file.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "set_package_entry")]
+#[group(skip)]
+pub struct SetPackageEntry {
+    /// Path to the package.json file
+    pub file_path: String,
+
+    /// The field holding the map: scripts (the default), dependencies, devDependencies,
+    /// peerDependencies, optionalDependencies, engines, resolutions, or bin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub field: Option<String>,
+
+    /// The name of the script, package, or other entry
+    pub key: String,
+
+    /// The entry's new value, as plain text without JSON quotes. Omit it to remove the entry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub value: Option<String>,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for SetPackageEntry {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Add a lint script",
+                item: Self {
+                    file_path: "package.json".into(),
+                    field: None,
+                    key: "lint".into(),
+                    value: Some("eslint .".into()),
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Bump a dev dependency",
+                item: Self {
+                    file_path: "web/package.json".into(),
+                    field: Some("devDependencies".into()),
+                    key: "vitest".into(),
+                    value: Some("^1.6.0".into()),
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Remove a script",
+                item: Self {
+                    file_path: "package.json".into(),
+                    field: None,
+                    key: "prepublish".into(),
+                    value: None,
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for SetPackageEntry {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        if file_path.file_name().is_none_or(|name| name != "package.json") {
+            return Err(anyhow!(
+                "set_package_entry edits package.json files, and {} isn't one",
+                self.file_path
+            ));
+        }
+        let field = self.field.as_deref().unwrap_or("scripts");
+        if !FIELDS.contains(&field) {
+            return Err(anyhow!(
+                "{field} isn't a map of strings that set_package_entry edits. Use one of: {}",
+                FIELDS.join(", ")
+            ));
+        }
+        encoding::check_size(&file_path, self.allow_large)?;
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, None)?;
+        let (source, _) = Encoding::read(&file_path)?;
+        let tree = language
+            .tree_sitter_parser()?
+            .parse(&source, None)
+            .ok_or_else(|| anyhow!("Unable to parse {}", self.file_path))?;
+        let change =
+            package_json::set_entry(&tree, &source, field, &self.key, self.value.as_deref())
+                .map_err(|message| anyhow!(message))?;
+        if change.content == source[change.range.clone()] {
+            return Ok(format!(
+                "{} in {field} is already {}",
+                self.key, change.content
+            ));
+        }
+
+        let (operation, end_byte) = if change.range.is_empty() {
+            (Operation::InsertAfter, None)
+        } else {
+            (Operation::Replace, Some(change.range.end))
+        };
+        let selector = Selector::anchor(operation, change.anchor);
+        state.stage_edit_at(
+            change.content,
+            selector,
+            language,
+            file_path,
+            EditPosition {
+                start_byte: change.range.start,
+                end_byte,
+            },
+        )
+    }
+}
@@ -11,7 +11,8 @@ use serde::{Deserialize, Serialize};
 #[serde(rename = "set_working_directory")]
 #[group(skip)]
 pub struct SetWorkingDirectory {
-    /// New working directory. All relative paths will be relative to this path
+    /// New working directory. All relative paths will be relative to this path.
+    /// `~` and environment variables like `$HOME` are expanded.
     path: String,
 }
 
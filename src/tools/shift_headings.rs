@@ -0,0 +1,108 @@
+use crate::{
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    sections,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Promote or demote every heading in a section of a Markdown document by some number of
+/// levels, and stage the result for preview
+///
+/// A section is a heading and everything up to the next heading of the same or a higher level,
+/// so its subsections shift with it. This is for fitting content moved from one document into
+/// another, where it sits at a different depth. Headings in fenced code blocks are left alone,
+/// and the shift is rejected if it would take any heading outside levels 1 through 6.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "shift_headings")]
+#[group(skip)]
+pub struct ShiftHeadings {
+    /// Path to the Markdown file
+    pub file_path: String,
+
+    /// How many levels to shift by: positive to demote, like `1` to turn `##` into `###`, or
+    /// negative to promote
+    #[arg(long, allow_hyphen_values = true)]
+    pub by: isize,
+
+    /// The heading of the section to shift, by its text like `Installation` or by its anchor
+    /// like `#installation` when the text is repeated. Omit to shift every heading in the
+    /// document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub section: Option<String>,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for ShiftHeadings {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Demote a section and its subsections by one level",
+                item: Self {
+                    file_path: "docs/guide.md".into(),
+                    by: 1,
+                    section: Some("Installation".into()),
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Promote every heading of a document that was moved up a level",
+                item: Self {
+                    file_path: "docs/installing.md".into(),
+                    by: -1,
+                    section: None,
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for ShiftHeadings {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        encoding::check_size(&file_path, self.allow_large)?;
+        if self.by == 0 {
+            return Err(anyhow!(
+                "`by` is 0, so no heading would change. Use a positive number to demote \
+                 headings or a negative one to promote them."
+            ));
+        }
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+
+        let (source, _) = Encoding::read(&file_path)?;
+        let shift = sections::shift(&source, self.section.as_deref(), self.by)
+            .map_err(|message| anyhow!(message))?;
+
+        let selector = Selector::anchor(Operation::Replace, shift.anchor);
+        let message = state.stage_edit_at(
+            shift.content,
+            selector,
+            language,
+            file_path,
+            EditPosition {
+                start_byte: shift.range.start,
+                end_byte: Some(shift.range.end),
+            },
+        )?;
+        Ok(format!(
+            "Shifting {} heading{}.\n\n{message}",
+            shift.count,
+            if shift.count == 1 { "" } else { "s" }
+        ))
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{
+    editor::{Editor, format_patch},
+    encoding::{self, Encoding},
+    placeholders,
+    response_format::ResponseFormat,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+};
+use anyhow::Result;
+use diffy::DiffOptions;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, path::PathBuf};
+
+/// Apply a sequence of edits to in-memory copies of files and show the result, without staging
+/// or writing anything
+///
+/// Each edit sees the result of the edits before it, so an entire refactor plan spanning several
+/// files can be checked before any of it is executed with preview_edit and persist_edit. The
+/// simulation stops at the first edit that would be rejected.
+#[derive(Serialize, Deserialize, Debug, JsonSchema, clap::Args)]
+#[serde(rename = "simulate")]
+#[group(skip)]
+pub struct Simulate {
+    /// The edits to apply, in order
+    #[arg(skip)]
+    pub edits: Vec<SimulatedEdit>,
+
+    /// Include the full final content of each changed file, not just its diff
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub show_final: bool,
+
+    /// Edit files larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+/// One step of a simulated plan, with the same arguments as preview_edit
+#[derive(Serialize, Deserialize, Debug, JsonSchema, Clone)]
+pub struct SimulatedEdit {
+    /// Path to the source file, which may be relative to the session working directory
+    pub file_path: String,
+
+    /// How to position the `content`
+    #[serde(flatten)]
+    pub selector: Selector,
+
+    /// The new content to insert or replace. Omit it to remove code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+impl WithExamples for Simulate {
+    fn examples() -> Vec<Example<Self>> {
+        vec![Example {
+            description: "Check that renaming a function and updating its caller in another file \
+                          both apply cleanly",
+            item: Self {
+                edits: vec![
+                    SimulatedEdit {
+                        file_path: "src/parser.rs".into(),
+                        selector: Selector::anchor(Operation::Replace, "pub fn parse_line("),
+                        content: Some(
+                            "pub fn parse_entry(line: &str) -> Entry {\n    Entry::from(line)\n}"
+                                .into(),
+                        ),
+                    },
+                    SimulatedEdit {
+                        file_path: "src/main.rs".into(),
+                        selector: Selector::anchor(Operation::Replace, "let entry = parse_line("),
+                        content: Some("let entry = parse_entry(&line);".into()),
+                    },
+                ],
+                show_final: false,
+                allow_large: false,
+            },
+        }]
+    }
+}
+
+/// A file as the simulation has left it
+struct SimulatedFile {
+    display: String,
+    path: PathBuf,
+    original: String,
+    current: String,
+}
+
+impl Tool<SemanticEditTools> for Simulate {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let mut files: Vec<SimulatedFile> = vec![];
+        let mut steps = String::new();
+        let mut failed_at = None;
+
+        for (index, edit) in self.edits.iter().enumerate() {
+            let step = index + 1;
+            let path = state.resolve_path(&edit.file_path, None)?;
+            let position = match files.iter().position(|file| file.path == path) {
+                Some(position) => position,
+                None => {
+                    encoding::check_size(&path, self.allow_large)?;
+                    let (source, encoding) = Encoding::read(&path)?;
+                    if let Some(reason) = encoding.read_only_reason() {
+                        writeln!(steps, "❌ {step}. {}: {reason}", edit.file_path)?;
+                        failed_at = Some(step);
+                        break;
+                    }
+                    files.push(SimulatedFile {
+                        display: edit.file_path.clone(),
+                        path: path.clone(),
+                        original: source.clone(),
+                        current: source,
+                    });
+                    files.len() - 1
+                }
+            };
+
+            let file = &mut files[position];
+            let dialect = state.dialects(None)?.get(&path);
+            let language = state
+                .language_registry()
+                .get_language_with_hint(&path, dialect)?;
+            let editor = Editor::from_source(
+                placeholders::expand(edit.content.as_deref().unwrap_or_default(), &path),
+                edit.selector.clone(),
+                language,
+                path,
+                None,
+                file.current.clone(),
+                Encoding::default(),
+            )?;
+            let description = format!(
+                "{} `{}` in {}",
+                edit.selector.operation_name(),
+                edit.selector.anchor,
+                edit.file_path
+            );
+
+            match editor.simulate()? {
+                (_, Some(output)) => {
+                    writeln!(steps, "✅ {step}. {description}")?;
+                    file.current = output;
+                }
+                (message, None) => {
+                    writeln!(steps, "❌ {step}. {description}\n\n{}", message.trim_end())?;
+                    failed_at = Some(step);
+                    break;
+                }
+            }
+        }
+
+        let mut response = if let Some(step) = failed_at {
+            format!(
+                "The plan fails at step {step} of {}. Nothing was staged or written.\n\n{steps}",
+                self.edits.len()
+            )
+        } else {
+            format!(
+                "All {} edits apply cleanly. Nothing was staged or written.\n\n{steps}",
+                self.edits.len()
+            )
+        };
+
+        let changed = files
+            .iter()
+            .filter(|file| file.current != file.original)
+            .collect::<Vec<_>>();
+        if !changed.is_empty() {
+            response.push_str(if failed_at.is_some() {
+                "\nCumulative changes before the failing step:\n"
+            } else {
+                "\nCumulative changes:\n"
+            });
+        }
+        let format = ResponseFormat::configured();
+        for file in changed {
+            let patch = DiffOptions::new().create_patch(&file.original, &file.current);
+            response.push_str(&format.file_section(&file.display, &format_patch(&patch), "diff"));
+            if self.show_final {
+                response.push_str(&format.file_section(
+                    &format!("{} (final)", file.display),
+                    &file.current,
+                    "",
+                ));
+            }
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    searcher::find_positions,
+    selector::{Operation, Selector},
+    sort_keys::{container, sorted},
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Sort the keys of a JSON object or TOML document into alphabetical order and stage the result
+/// for preview
+///
+/// Objects and tables nested in the sorted one are sorted too unless `shallow` is set. In TOML,
+/// tables are also put in order by their names, except in documents with arrays of tables,
+/// whose order matters. Comment lines directly above a key move with it, and the document's
+/// formatting is kept. The staged edit is validated and applied with persist_edit like any
+/// other.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "sort_keys")]
+#[group(skip)]
+pub struct SortKeys {
+    /// Path to the JSON or TOML file
+    pub file_path: String,
+
+    /// Text inside the object or table to sort, like one of its keys. Omit to sort the whole
+    /// document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub anchor: Option<String>,
+
+    /// Only sort the keys of the targeted object or table, not those of objects and tables
+    /// nested in it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub shallow: bool,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for SortKeys {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Sort every key in a JSON file",
+                item: Self {
+                    file_path: "package.json".into(),
+                    anchor: None,
+                    shallow: false,
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Sort only the dependencies table of a Cargo manifest",
+                item: Self {
+                    file_path: "Cargo.toml".into(),
+                    anchor: Some("[dependencies]".into()),
+                    shallow: true,
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for SortKeys {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        encoding::check_size(&file_path, self.allow_large)?;
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+
+        let (source, _) = Encoding::read(&file_path)?;
+        let offset = match &self.anchor {
+            Some(anchor) => match find_positions(&source, anchor.trim())
+                .map_err(|message| anyhow!(message))?
+                .as_slice()
+            {
+                [(start, _)] => Some(*start),
+                matches => {
+                    return Err(anyhow!(
+                        "Anchor \"{anchor}\" matches {} places in {}. Use a longer anchor that \
+                         only matches one.",
+                        matches.len(),
+                        self.file_path
+                    ));
+                }
+            },
+            None => None,
+        };
+
+        let tree = language
+            .tree_sitter_parser()?
+            .parse(&source, None)
+            .ok_or_else(|| anyhow!("Unable to parse {}", self.file_path))?;
+        let editor = language.editor();
+        let target = container(editor, &tree, &source, offset).ok_or_else(|| {
+            anyhow!(
+                "There is no {language} object or table to sort {}",
+                match &self.anchor {
+                    Some(anchor) => format!("around \"{anchor}\""),
+                    None => format!("in {}", self.file_path),
+                }
+            )
+        })?;
+        let content = sorted(editor, target, &source, self.shallow);
+        if content == source[target.byte_range()] {
+            return Ok(format!("The keys are already sorted in {}", self.file_path));
+        }
+
+        let start = target.start_byte();
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |index| start + index);
+        let selector = Selector::anchor(Operation::Replace, source[start..line_end].trim());
+        state.stage_edit_at(
+            content,
+            selector,
+            language,
+            file_path,
+            EditPosition {
+                start_byte: start,
+                end_byte: Some(target.end_byte()),
+            },
+        )
+    }
+}
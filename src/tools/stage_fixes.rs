@@ -0,0 +1,222 @@
+use crate::{
+    checks::{self, Checker},
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    fixits::{self, FixIt, Resolved},
+    lsp::unchanged_ends,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Write, ops::Range};
+
+/// Stage the fixes a compiler suggests for a file's diagnostics as one edit for preview, instead
+/// of reading the errors and writing each fix by hand
+///
+/// Reads the JSON lines `cargo check --message-format=json` or `cargo clippy
+/// --message-format=json` prints, or the code fixes tsserver returns for `getCodeFixes`. Without
+/// `diagnostics`, clippy is run on Rust files. Only the first suggestion of each diagnostic is
+/// applied, and fixes that overlap one already applied, or whose text has changed since the
+/// check, are skipped. Persist only some of the fixes with persist_edit's `hunks`.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "stage_fixes")]
+#[group(skip)]
+pub struct StageFixes {
+    /// The file to fix. Fixes for other files are counted but not staged.
+    pub file_path: String,
+
+    /// Diagnostics as cargo's JSON messages or tsserver's code fixes. Omit to run clippy on a
+    /// Rust file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub diagnostics: Option<String>,
+
+    /// Also apply suggestions the compiler isn't sure are what was meant, like rustc's
+    /// `MaybeIncorrect` ones. Suggestions with placeholders are never applied.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub include_uncertain: bool,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for StageFixes {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Run clippy and stage the fixes it is sure of",
+                item: Self {
+                    file_path: "src/main.rs".into(),
+                    diagnostics: None,
+                    include_uncertain: false,
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Stage a fix tsserver offered for an unused variable",
+                item: Self {
+                    file_path: "src/app.ts".into(),
+                    diagnostics: Some(
+                        r#"[{"description":"Remove unused declaration for: 'a'","changes":[{"fileName":"src/app.ts","textChanges":[{"start":{"line":2,"offset":3},"end":{"line":2,"offset":13},"newText":""}]}]}]"#
+                            .into(),
+                    ),
+                    include_uncertain: false,
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for StageFixes {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        encoding::check_size(&file_path, self.allow_large)?;
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+        let directory = file_path.parent().unwrap_or(&file_path);
+
+        let (diagnostics, root) = match self.diagnostics {
+            Some(diagnostics) => {
+                let root = checks::nearest(directory, "Cargo.toml")
+                    .unwrap_or_else(|| directory.to_path_buf());
+                (diagnostics, root)
+            }
+            None => match Checker::default_for(language.name()) {
+                Some(Checker::Clippy) => {
+                    let (output, root) = Checker::Clippy.run(&file_path)?;
+                    (String::from_utf8_lossy(&output.stdout).into_owned(), root)
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "Only clippy can be run for its suggestions. Pass {language} diagnostics \
+                         as `diagnostics`, like tsserver's code fixes."
+                    ));
+                }
+            },
+        };
+
+        let fixes = fixits::parse(&diagnostics);
+        let mut elsewhere = BTreeMap::<&str, usize>::new();
+        let mut uncertain = 0;
+        let mut ours = vec![];
+        for fix in &fixes {
+            if !checks::is_same_file(&file_path, &root, &fix.path) {
+                *elsewhere.entry(fix.path.as_str()).or_default() += 1;
+            } else if fix.certain || self.include_uncertain {
+                ours.push(fix);
+            } else {
+                uncertain += 1;
+            }
+        }
+
+        let (source, _) = Encoding::read(&file_path)?;
+        let mut applied: Vec<(&FixIt, Resolved<'_>)> = vec![];
+        let mut skipped = vec![];
+        for fix in ours {
+            match fix.resolve(&source) {
+                Some(ranges)
+                    if !applied.iter().flat_map(|(_, taken)| taken).any(|(taken, _)| {
+                        ranges.iter().any(|(range, _)| overlaps(taken, range))
+                    }) =>
+                {
+                    applied.push((fix, ranges));
+                }
+                Some(_) => skipped.push((fix, "it overlaps another fix")),
+                None => skipped.push((fix, "the file changed there since the check")),
+            }
+        }
+
+        applied.sort_by_key(|(_, ranges)| ranges[0].0.start);
+        let mut response = String::new();
+        if applied.is_empty() {
+            write!(response, "No fixes can be staged for {}", self.file_path)?;
+        } else {
+            writeln!(response, "Fixes for {}:", self.file_path)?;
+            for (fix, _) in &applied {
+                writeln!(
+                    response,
+                    "- line {}: {}",
+                    fix.replacements[0].start.line(),
+                    fix.message
+                )?;
+            }
+        }
+        for (fix, reason) in &skipped {
+            write!(
+                response,
+                "\nSkipped line {}: {}, because {reason}",
+                fix.replacements[0].start.line(),
+                fix.message
+            )?;
+        }
+        if uncertain > 0 {
+            write!(
+                response,
+                "\n{uncertain} suggestion{} the compiler isn't sure of {} left out. Set \
+                 `include_uncertain` to stage {} too.",
+                if uncertain == 1 { "" } else { "s" },
+                if uncertain == 1 { "was" } else { "were" },
+                if uncertain == 1 { "it" } else { "them" },
+            )?;
+        }
+        if !elsewhere.is_empty() {
+            write!(
+                response,
+                "\nOther files have fixes too: {}",
+                elsewhere
+                    .iter()
+                    .map(|(path, count)| format!("{path} ({count})"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+        if applied.is_empty() {
+            return Ok(response);
+        }
+
+        let mut replacements = applied
+            .iter()
+            .flat_map(|(_, ranges)| ranges)
+            .collect::<Vec<_>>();
+        replacements.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        let mut fixed = source.clone();
+        for (range, text) in replacements {
+            fixed.replace_range(range.clone(), text);
+        }
+
+        let (prefix, suffix) = unchanged_ends(&source, &fixed);
+        let line_start = source[..prefix].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = source[prefix..]
+            .find('\n')
+            .map_or(source.len(), |index| prefix + index);
+        let selector = Selector::anchor(Operation::Replace, source[line_start..line_end].trim());
+        let message = state.stage_edit_at(
+            fixed[prefix..fixed.len() - suffix].to_string(),
+            selector,
+            language,
+            file_path,
+            EditPosition {
+                start_byte: prefix,
+                end_byte: Some(source.len() - suffix),
+            },
+        )?;
+        Ok(format!("{}\n\n{message}", response.trim_end()))
+    }
+}
+
+/// Whether two replaced ranges touch the same text. Insertions at the same place overlap, since
+/// their order would be a guess.
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end || a.start == b.start
+}
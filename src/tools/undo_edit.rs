@@ -0,0 +1,111 @@
+use crate::{
+    editor::format_patch,
+    encoding::Encoding,
+    response_format::ResponseFormat,
+    state::{SemanticEditTools, Write},
+    versions,
+};
+use anyhow::{Result, anyhow};
+use diffy::DiffOptions;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Undo the most recent write this session made with persist_edit, run_codemod, format_files, or
+/// scaffold, putting the file back the way it was
+///
+/// The last 10 writes are remembered, and calling this again undoes the one before. A write is
+/// only undone if the file hasn't changed since, so later changes are never lost.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "undo_edit")]
+#[group(skip)]
+pub struct UndoEdit {
+    /// Undo the most recent write to this file instead of the most recent write to any file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub file_path: Option<String>,
+}
+
+impl WithExamples for UndoEdit {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Roll back the edit that was just persisted",
+                item: Self { file_path: None },
+            },
+            Example {
+                description: "Roll back the last change to one file after editing others",
+                item: Self {
+                    file_path: Some("src/main.rs".into()),
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for UndoEdit {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = self
+            .file_path
+            .as_deref()
+            .map(|file_path| state.resolve_path(file_path, None))
+            .transpose()?;
+        let Some(write) = state.take_write(file_path.as_deref(), None)? else {
+            return Ok(match &self.file_path {
+                Some(file_path) => format!("There are no writes to {file_path} to undo"),
+                None => "There are no writes to undo in this session".into(),
+            });
+        };
+        let read = read_unchanged(&write)
+            .and_then(|current| Ok((current, state.undo_text(&write, None)?)));
+        let ((current, encoding), before) = match read {
+            Ok(read) => read,
+            Err(error) => {
+                state.restore_write(write, None)?;
+                return Err(error);
+            }
+        };
+
+        let file = write.file.display().to_string();
+        let restored = before.clone().unwrap_or_default();
+        match (state.commit_fn_mut().take(), &before) {
+            (Some(commit), _) => commit(write.file.clone(), encoding.encode(restored.clone())),
+            (None, Some(_)) => std::fs::write(&write.file, encoding.encode(restored.clone()))?,
+            (None, None) => std::fs::remove_file(&write.file)?,
+        }
+        state.record_undo(&write.file, &current, &restored, None)?;
+
+        if before.is_none() {
+            return Ok(format!("Removed {file}, which the undone write created"));
+        }
+        let patch = DiffOptions::new().create_patch(&current, &restored);
+        Ok(format!(
+            "Undid the last write to {file}, which is back at version `{}`:\n\n{}",
+            versions::of(&restored),
+            ResponseFormat::configured().section("Diff", &format_patch(&patch), "diff")
+        ))
+    }
+}
+
+/// The file `write` wrote, as long as nothing has changed it since
+fn read_unchanged(write: &Write) -> Result<(String, Encoding)> {
+    let file = write.file.display();
+    if !write.file.exists() {
+        return Err(anyhow!(
+            "{file} no longer exists, so the write to it can't be undone"
+        ));
+    }
+    let (current, encoding) = Encoding::read(&write.file)?;
+    let version = versions::of(&current);
+    if version != write.after {
+        return Err(anyhow!(
+            "{file} has changed since the write to undo, which left it at version `{}` (it is \
+             now at `{version}`). Undoing it would discard the later changes, so nothing was \
+             written.",
+            write.after
+        ));
+    }
+    Ok((current, encoding))
+}
@@ -0,0 +1,122 @@
+use crate::{
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+    toc::{self, END_MARKER, START_MARKER},
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+
+/// Regenerate a Markdown document's table of contents from its headings and stage it for
+/// preview
+///
+/// The table of contents goes between a start and an end marker line, `<!-- toc -->` and
+/// `<!-- tocstop -->` unless others are given, replacing whatever list is there. It links to
+/// each heading after the end marker, nested by level, using the anchors GitHub gives headings.
+/// The staged edit is validated and applied with persist_edit like any other.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "update_toc")]
+#[group(skip)]
+pub struct UpdateToc {
+    /// Path to the Markdown file
+    pub file_path: String,
+
+    /// The line the table of contents starts after, `<!-- toc -->` by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub start_marker: Option<String>,
+
+    /// The line the table of contents ends before, `<!-- tocstop -->` by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub end_marker: Option<String>,
+
+    /// The deepest heading level to list, from 1 for `#` to 6. Defaults to 3.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub max_level: Option<usize>,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for UpdateToc {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Update the table of contents of a README after adding a section",
+                item: Self {
+                    file_path: "README.md".into(),
+                    start_marker: None,
+                    end_marker: None,
+                    max_level: None,
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Update a table of contents between custom markers, listing only \
+                              the top two levels",
+                item: Self {
+                    file_path: "docs/guide.md".into(),
+                    start_marker: Some("<!-- START doctoc -->".into()),
+                    end_marker: Some("<!-- END doctoc -->".into()),
+                    max_level: Some(2),
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for UpdateToc {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let file_path = state.resolve_path(&self.file_path, None)?;
+        encoding::check_size(&file_path, self.allow_large)?;
+        let dialect = state.dialects(None)?.get(&file_path);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&file_path, dialect)?;
+        let start_marker = self.start_marker.as_deref().unwrap_or(START_MARKER);
+        let end_marker = self.end_marker.as_deref().unwrap_or(END_MARKER);
+        let max_level = self.max_level.unwrap_or(3);
+        if !(1..=6).contains(&max_level) {
+            return Err(anyhow!(
+                "max_level is {max_level}, but Markdown headings have levels 1 through 6"
+            ));
+        }
+
+        let (source, _) = Encoding::read(&file_path)?;
+        let (range, content) = toc::regenerate(&source, start_marker, end_marker, max_level)
+            .map_err(|message| anyhow!(message))?;
+        if content == source[range.clone()] {
+            return Ok(format!(
+                "The table of contents in {} is already up to date",
+                self.file_path
+            ));
+        }
+
+        let (operation, end_byte) = if range.is_empty() {
+            (Operation::InsertAfter, None)
+        } else {
+            (Operation::Replace, Some(range.end))
+        };
+        let selector = Selector::anchor(operation, start_marker.trim());
+        state.stage_edit_at(
+            content,
+            selector,
+            language,
+            file_path,
+            EditPosition {
+                start_byte: range.start,
+                end_byte,
+            },
+        )
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    editor::EditPosition,
+    encoding::{self, Encoding},
+    indentation::Indentation,
+    placeholders,
+    selector::{Operation, Selector},
+    state::SemanticEditTools,
+};
+use anyhow::Result;
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Stage an edit before, after, or in place of a location marked with set_bookmark, and see a
+/// preview of it like preview_edit
+///
+/// Content inserted before or after a bookmark that has lines of its own goes on new lines at
+/// the bookmark's indentation.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "use_bookmark")]
+#[group(skip)]
+pub struct UseBookmark {
+    /// Name the bookmark was set with
+    pub name: String,
+
+    /// Where to put the content relative to the bookmarked text
+    #[arg(value_enum)]
+    pub operation: Operation,
+
+    /// The content to insert, or to replace the bookmarked text with. Omit to remove it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    /// Edit a file larger than the size limit for editing, as preview_edit's `allow_large` does
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[arg(long)]
+    pub allow_large: bool,
+}
+
+impl WithExamples for UseBookmark {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Add a helper after a bookmarked function",
+                item: Self {
+                    name: "parser".into(),
+                    operation: Operation::InsertAfter,
+                    content: Some("fn parse_footer(input: &str) -> Footer {\n    todo!()\n}".into()),
+                    allow_large: false,
+                },
+            },
+            Example {
+                description: "Add a doc comment above it",
+                item: Self {
+                    name: "parser".into(),
+                    operation: Operation::InsertBefore,
+                    content: Some("/// Parse the header line of a record".into()),
+                    allow_large: false,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for UseBookmark {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let bookmark = state.bookmark(&self.name, None)?;
+        encoding::check_size(&bookmark.file, self.allow_large)?;
+        let dialect = state.dialects(None)?.get(&bookmark.file);
+        let language = state
+            .language_registry()
+            .get_language_with_hint(&bookmark.file, dialect)?;
+        let (source, _) = Encoding::read(&bookmark.file)?;
+        let range = bookmark.range_in(&self.name, &source)?;
+
+        let content = placeholders::expand(&self.content.unwrap_or_default(), &bookmark.file);
+        let line_start = source[..range.start].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = source[range.end..]
+            .find('\n')
+            .map_or(source.len(), |index| range.end + index);
+        let indented = || {
+            let indentation = Indentation::determine(&source).unwrap_or(Indentation::Spaces(4));
+            let mut content = Cow::Borrowed(content.as_str());
+            indentation.reindent(
+                indentation.unit_count(&source[line_start..]),
+                &mut content,
+                true,
+            );
+            content.into_owned()
+        };
+        let (position, content) = match self.operation {
+            Operation::Replace => ((range.start, Some(range.end)), content.clone()),
+            Operation::InsertBefore if source[line_start..range.start].trim().is_empty() => {
+                ((line_start, None), format!("{}\n", indented()))
+            }
+            Operation::InsertBefore => ((range.start, None), content.clone()),
+            Operation::InsertAfter if source[range.end..line_end].trim().is_empty() => {
+                ((line_end, None), format!("\n{}", indented()))
+            }
+            Operation::InsertAfter => ((range.end, None), content.clone()),
+        };
+
+        let selector = Selector::anchor(
+            self.operation,
+            source[range.clone()]
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim(),
+        );
+        state.stage_edit_at(
+            content,
+            selector,
+            language,
+            bookmark.file,
+            EditPosition {
+                start_byte: position.0,
+                end_byte: position.1,
+            },
+        )
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{
+    budget::Budget,
+    encoding::Encoding,
+    languages::LanguageName,
+    state::SemanticEditTools,
+    workspace::{self, Cursor},
+};
+use anyhow::{Result, anyhow};
+use mcplease::{
+    traits::{Tool, WithExamples},
+    types::Example,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Parse every file in a supported language under a directory and report which ones contain
+/// syntax errors, with how many and where the first one is
+///
+/// Use this to triage a broken branch before making targeted fixes. Hidden files and dependency
+/// or build output directories are skipped.
+#[derive(Serialize, Deserialize, Debug, schemars::JsonSchema, clap::Args)]
+#[serde(rename = "workspace_health")]
+#[group(skip)]
+pub struct WorkspaceHealth {
+    /// Directory to check. Defaults to the session working directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub path: Option<String>,
+
+    /// Stop parsing files after roughly this many milliseconds and report the files parsed so
+    /// far, along with a continuation token for checking the rest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub timeout_ms: Option<u64>,
+
+    /// Continuation token from a previous check that reached its time limit. Pass it with the
+    /// same path to check the files that check didn't reach.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub continuation: Option<String>,
+}
+
+impl WithExamples for WorkspaceHealth {
+    fn examples() -> Vec<Example<Self>> {
+        vec![
+            Example {
+                description: "Find every file that doesn't parse after a bad merge",
+                item: Self {
+                    path: None,
+                    timeout_ms: None,
+                    continuation: None,
+                },
+            },
+            Example {
+                description: "Check one package of a large monorepo without waiting long",
+                item: Self {
+                    path: Some("packages/server".into()),
+                    timeout_ms: Some(2000),
+                    continuation: None,
+                },
+            },
+        ]
+    }
+}
+
+impl Tool<SemanticEditTools> for WorkspaceHealth {
+    fn execute(self, state: &mut SemanticEditTools) -> Result<String> {
+        let budget = Budget::from_timeout_ms(self.timeout_ms);
+        let root = match &self.path {
+            Some(path) => state.resolve_path(path, None)?,
+            None => state.get_context(None)?.ok_or_else(|| {
+                anyhow!("No working directory is set. Use set_working_directory or pass a path.")
+            })?,
+        };
+
+        let dialects = state.dialects(None)?;
+        let registry = state.language_registry();
+        let mut parsed = 0;
+        let mut broken = String::new();
+        let mut broken_count = 0;
+        let cursor = self
+            .continuation
+            .as_deref()
+            .map(str::parse::<Cursor>)
+            .transpose()?;
+        let mut files = workspace::walk(&root, budget).resume(cursor.as_ref());
+        for file in &mut files {
+            let path = root.join(&file);
+            let Some(language) = dialects
+                .get(&path)
+                .or_else(|| registry.detect_language_from_path(&path))
+                .filter(|name| *name != LanguageName::Other)
+                .map(|name| registry.get_language(name))
+            else {
+                continue;
+            };
+            let Ok((source, _)) = Encoding::read(&path) else {
+                continue;
+            };
+            let Some(tree) = language.tree_sitter_parser()?.parse(&source, None) else {
+                continue;
+            };
+            parsed += 1;
+
+            let mut errors = language.editor().collect_errors(&tree, &source);
+            errors.sort_by_key(|error| error.range.start);
+            errors.dedup_by_key(|error| error.range.start);
+            let Some(first) = errors.first() else {
+                continue;
+            };
+            broken_count += 1;
+            write!(
+                broken,
+                "{}: {} syntax error{}, first at {first}",
+                file.display(),
+                errors.len(),
+                if errors.len() == 1 { "" } else { "s" },
+            )?;
+            if let Some(hint) = &first.hint {
+                write!(broken, " ({hint})")?;
+            }
+            broken.push('\n');
+        }
+
+        let mut response = if broken_count == 0 {
+            format!(
+                "No syntax errors in the {parsed} file{} parsed\n",
+                if parsed == 1 { "" } else { "s" }
+            )
+        } else {
+            format!(
+                "{broken_count} of the {parsed} file{} parsed {} syntax errors:\n{broken}",
+                if parsed == 1 { "" } else { "s" },
+                if broken_count == 1 { "has" } else { "have" }
+            )
+        };
+
+        if let Some(cursor) = files.continuation() {
+            write!(
+                response,
+                "\nReached the time limit before parsing every file, so errors in the rest are \
+                 not reported. Call workspace_health again with the same path and continuation: \
+                 \"{cursor}\" to check them."
+            )?;
+        }
+
+        Ok(response)
+    }
+}
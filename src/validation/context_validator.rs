@@ -1,8 +1,54 @@
-use tree_sitter::{Node, Query, QueryCursor, StreamingIterator, Tree};
+use super::{Severity, SyntaxError, ValidationRules};
+use crate::selector::Operation;
+use std::ops::Range;
+use tree_sitter::{
+    Node, Query, QueryCursor, QueryMatch, QueryPredicateArg, StreamingIterator, Tree,
+};
+
+/// The predicate that limits a validation rule to some kinds of edit, like
+/// `(#operation? replace delete)`
+const OPERATION_PREDICATE: &str = "operation?";
 
 /// Tree-sitter based context validator for semantic code editing
 pub struct ContextValidator;
 
+/// The kind of change an edit makes, for rules that are only broken by some kinds of edit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationType {
+    Insert,
+    Replace,
+    Delete,
+}
+
+impl OperationType {
+    /// The kind of change that `operation` makes with `content`. Replacing code with nothing
+    /// deletes it.
+    pub fn new(operation: Operation, content: &str) -> Self {
+        match operation {
+            Operation::InsertAfter | Operation::InsertBefore => Self::Insert,
+            Operation::Replace if content.trim().is_empty() => Self::Delete,
+            Operation::Replace => Self::Replace,
+        }
+    }
+
+    /// The name validation queries use for this kind of change
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Insert => "insert",
+            Self::Replace => "replace",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+/// The edit being validated: what kind of change it made, and the bytes of the edited source
+/// that it wrote
+#[derive(Debug, Clone)]
+pub struct EditContext {
+    pub operation: OperationType,
+    pub region: Range<usize>,
+}
+
 #[derive(Debug)]
 pub struct ValidationResult<'tree, 'source> {
     pub is_valid: bool,
@@ -13,33 +59,49 @@ pub struct ValidationResult<'tree, 'source> {
 #[derive(Debug)]
 pub struct ContextViolation<'tree> {
     pub node: Node<'tree>,
+    /// The rule that was broken, named as in the query without its `invalid.` prefix
+    pub rule: String,
     pub message: String, // Human-readable error
     pub suggestion: &'static str,
 }
 
 impl ContextValidator {
     /// Validate if content can be safely inserted at the target location
+    ///
+    /// With a `range`, only the nodes in that part of the source are checked. Rules limited to
+    /// some kinds of edit with `#operation?` are only checked against the code written by an
+    /// `edit` of one of those kinds.
     pub fn validate_tree<'tree, 'source>(
         tree: &'tree Tree,
         query: &Query,
         source_code: &'source str,
+        range: Option<Range<usize>>,
+        edit: Option<&EditContext>,
     ) -> ValidationResult<'tree, 'source> {
         // Run validation queries against the temporary tree
         let mut cursor = QueryCursor::new();
+        if let Some(range) = range {
+            cursor.set_byte_range(range);
+        }
         let mut matches = cursor.matches(query, tree.root_node(), source_code.as_bytes());
 
         let mut violations = Vec::new();
 
         while let Some(m) = matches.next() {
+            if !Self::applies_to_edit(query, m, edit) {
+                continue;
+            }
+
             for capture in m.captures {
                 let node = capture.node;
 
                 // Extract violation type from capture name
                 if let Some(violation_type) = Self::extract_violation_type(capture.index, query) {
                     // Only process "invalid" captures
-                    if violation_type.starts_with("invalid.") {
+                    if let Some(rule) = violation_type.strip_prefix("invalid.") {
                         violations.push(ContextViolation {
                             node,
+                            rule: rule.to_string(),
                             message: Self::get_violation_message(&violation_type),
                             suggestion: Self::get_violation_suggestion(&violation_type),
                         });
@@ -55,6 +117,26 @@ impl ContextValidator {
         }
     }
 
+    /// Whether the rule that produced `m` applies to `edit`: rules without an `#operation?`
+    /// predicate always do, and the others only when the edit is one of the listed kinds and
+    /// wrote some of the matched code
+    fn applies_to_edit(query: &Query, m: &QueryMatch, edit: Option<&EditContext>) -> bool {
+        query
+            .general_predicates(m.pattern_index)
+            .iter()
+            .filter(|predicate| &*predicate.operator == OPERATION_PREDICATE)
+            .all(|predicate| {
+                edit.is_some_and(|edit| {
+                    predicate.args.iter().any(|arg| {
+                        matches!(arg, QueryPredicateArg::String(name) if **name == *edit.operation.as_str())
+                    }) && m.captures.iter().any(|capture| {
+                        let range = capture.node.byte_range();
+                        range.start <= edit.region.end && edit.region.start <= range.end
+                    })
+                })
+            })
+    }
+
     fn extract_violation_type(capture_index: u32, query: &Query) -> Option<String> {
         query
             .capture_names()
@@ -94,6 +176,24 @@ impl ContextValidator {
                 "Items cannot be nested inside other items".to_string()
             }
             "invalid.expression.as.type" => "Expressions cannot be used as types".to_string(),
+            "invalid.item.replacing.match.arm.body" => {
+                "Items cannot replace the code of a match arm".to_string()
+            }
+            "invalid.self.outside.impl" | "invalid.manual.self.outside.impl" => {
+                "Methods taking `self` must be defined inside an impl block".to_string()
+            }
+            "invalid.visibility.in.function.body" => {
+                "Items inside function bodies cannot be `pub`".to_string()
+            }
+            "invalid.statement.at.module.level" => {
+                "Statements cannot be placed at module level".to_string()
+            }
+            "invalid.duplicate.function.in.impl" => {
+                "A function with this name is already defined in this impl block".to_string()
+            }
+            "invalid.attribute.without.item" => {
+                "This attribute is not followed by an item to apply to".to_string()
+            }
             _ => format!(
                 "Invalid placement: {}",
                 violation_type
@@ -113,22 +213,190 @@ impl ContextValidator {
             | "invalid.trait.in.function.body" => "Move this to module level",
 
             "invalid.use.in.item.body" => "Move use declarations to the top of the file",
+            "invalid.item.replacing.match.arm.body" => {
+                "Replace only the arm's code, and insert the item before or after it instead"
+            }
+            "invalid.self.outside.impl" | "invalid.manual.self.outside.impl" => {
+                "Move the method into the impl block for its type"
+            }
+            "invalid.visibility.in.function.body" => {
+                "Remove the visibility modifier, or move the item to module level"
+            }
+            "invalid.statement.at.module.level" => {
+                "Move this into a function body, or make it a `const` or `static` item"
+            }
+            "invalid.duplicate.function.in.impl" => {
+                "Rename one of them, or replace the existing definition instead of adding another"
+            }
+            "invalid.attribute.without.item" => {
+                "Insert the attribute together with the item it belongs to, or remove it"
+            }
             _ => "Consider placing this construct in an appropriate context",
         }
     }
+
+    /// Code that follows the rule, for the rules that have an example
+    fn get_violation_example(violation_type: &str) -> Option<&'static str> {
+        Some(match violation_type {
+            "invalid.type.in.function.body"
+            | "invalid.impl.in.function.body"
+            | "invalid.trait.in.function.body"
+            | "invalid.mod.in.function.body" => {
+                "struct Point {\n    x: i32,\n}\n\nfn origin() -> Point {\n    Point { x: 0 }\n}"
+            }
+            "invalid.self.outside.impl" | "invalid.manual.self.outside.impl" => {
+                "impl Counter {\n    fn increment(&mut self) {\n        self.count += 1;\n    }\n}"
+            }
+            "invalid.function.in.function" => {
+                "fn double(value: u32) -> u32 {\n    value * 2\n}\n\nfn main() {\n    double(1);\n}"
+            }
+            "invalid.impl.in.impl" | "invalid.impl.nested" => {
+                "impl Parser {\n    fn parse(&self) {}\n}\n\nimpl Display for Parser {\n    // ...\n}"
+            }
+            "invalid.visibility.in.function.body" => "fn main() {\n    struct Local;\n}",
+            "invalid.statement.at.module.level" => {
+                "const LIMIT: u32 = 10;\n\nfn main() {\n    check(LIMIT);\n}"
+            }
+            "invalid.duplicate.function.in.impl" => {
+                "impl Counter {\n    fn increment(&mut self) {}\n\n    fn increment_by(&mut self, n: u32) {}\n}"
+            }
+            "invalid.attribute.without.item" => "#[derive(Debug)]\nstruct Config;",
+            "invalid.item.replacing.match.arm.body" => {
+                "match n {\n    0 => {\n        fn log_zero() {}\n        log_zero();\n    }\n    _ => {}\n}"
+            }
+            "invalid.await.outside.async" => "async fn load() {\n    fetch().await;\n}",
+            "invalid.class.in.function.body" => {
+                "class Helper:\n    pass\n\n\ndef build():\n    return Helper()"
+            }
+            "invalid.return.at.module.level" => "def main():\n    return 42",
+            "invalid.self.method.at.module.level" => {
+                "class Greeter:\n    def greet(self):\n        return \"hi\""
+            }
+            "invalid.yield.at.module.level" => "def numbers():\n    yield 42",
+            _ => return None,
+        })
+    }
+
+    /// The rules checked by a validation query, named without their `invalid.` prefix
+    pub fn rules(query: &Query) -> Vec<&str> {
+        query
+            .capture_names()
+            .iter()
+            .filter_map(|name| name.strip_prefix("invalid."))
+            .collect()
+    }
+
+    /// What `rule` checks for, for explaining why it rejected an edit
+    pub fn explain(rule: &str) -> RuleExplanation {
+        let violation_type = format!("invalid.{rule}");
+        RuleExplanation {
+            message: Self::get_violation_message(&violation_type),
+            suggestion: Self::get_violation_suggestion(&violation_type),
+            example: Self::get_violation_example(&violation_type),
+        }
+    }
+
+    /// The patterns of a validation query that check `rule`, each with the comments above it,
+    /// given the `source` the query was built from
+    pub fn patterns<'source>(query: &Query, source: &'source str, rule: &str) -> Vec<&'source str> {
+        let capture = format!("@invalid.{rule}");
+        (0..query.pattern_count())
+            .map(|index| {
+                let start = comments_above(source, query.start_byte_for_pattern(index));
+                // the pattern's source runs until the next one, through the comments above it
+                let mut pattern = source[start..query.end_byte_for_pattern(index)].trim_end();
+                while let Some((rest, last_line)) = pattern.rsplit_once('\n')
+                    && last_line.trim_start().starts_with(';')
+                {
+                    pattern = rest.trim_end();
+                }
+                pattern.trim_start()
+            })
+            .filter(|pattern| {
+                pattern.match_indices(&capture).any(|(at, _)| {
+                    !pattern[at + capture.len()..]
+                        .starts_with(|c: char| c.is_alphanumeric() || c == '.' || c == '_')
+                })
+            })
+            .collect()
+    }
+}
+
+/// Where the comment lines directly above `start` in a query's source begin
+fn comments_above(source: &str, start: usize) -> usize {
+    let mut comments_start = start;
+    let mut before = source[..start].trim_end();
+    loop {
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        if !before[line_start..].trim_start().starts_with(';') {
+            return comments_start;
+        }
+        comments_start = line_start;
+        if line_start == 0 {
+            return comments_start;
+        }
+        before = &before[..line_start - 1];
+    }
+}
+
+/// What a validation rule checks for and how to follow it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleExplanation {
+    pub message: String,
+    pub suggestion: &'static str,
+    pub example: Option<&'static str>,
 }
 
 impl ValidationResult<'_, '_> {
+    /// Apply the severities a project has given the rules: violations of rules that are off are
+    /// dropped, violations of errors are listed as `Err`, and if there are none, violations of
+    /// warnings are listed as `Ok`
+    pub fn check(mut self, rules: &ValidationRules) -> Result<Option<String>, String> {
+        self.violations
+            .retain(|violation| rules.severity(&violation.rule) != Severity::Off);
+        let (warnings, errors) = self
+            .violations
+            .into_iter()
+            .partition::<Vec<_>, _>(|violation| rules.severity(&violation.rule) == Severity::Warn);
+
+        if !errors.is_empty() {
+            self.violations = errors;
+            self.is_valid = false;
+            return Err(self.format_errors());
+        }
+
+        self.is_valid = true;
+        self.violations = warnings;
+        Ok((!self.violations.is_empty()).then(|| self.format_warnings()))
+    }
+
     pub fn format_errors(&self) -> String {
         if self.is_valid {
             return "✅ All validations passed".to_string();
         }
 
-        let mut response = String::new();
-        response.push_str("❌ Invalid placement detected:\n\n");
+        let mut response = self.format_violations("❌ Invalid placement detected:\n\n");
+        response.push_str(
+            "\nexplain_validation_rule shows what a rule matches and an example of code that \
+             follows it.\n",
+        );
+        response
+    }
+
+    /// List the violations of rules a project has relaxed to warnings
+    pub fn format_warnings(&self) -> String {
+        self.format_violations("⚠️ Validation warnings:\n\n")
+    }
+
+    fn format_violations(&self, heading: &str) -> String {
+        let mut response = heading.to_string();
 
         for violation in &self.violations {
-            response.push_str(&format!("• {}:\n", violation.message));
+            let location = SyntaxError::new(self.source_code, violation.node.byte_range());
+            response.push_str(&format!(
+                "• {} ({location}, rule `{}`):\n",
+                violation.message, violation.rule
+            ));
             let parent = violation.node.parent().unwrap_or(violation.node);
             response.push_str(&self.source_code[parent.byte_range()]);
             response.push_str("\n\n");
@@ -138,3 +406,54 @@ impl ValidationResult<'_, '_> {
         response
     }
 }
+
+#[cfg(all(test, feature = "rust"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operation_rules_only_apply_to_matching_edits() {
+        let source = "fn f(x: u8) {\n    match x {\n        _ => {\n            fn g() {}\n        }\n    }\n}\n";
+        let language = tree_sitter_rust::LANGUAGE.into();
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let query = Query::new(
+            &language,
+            "(match_arm value: (block (function_item) @invalid.item.replacing.match.arm.body) \
+             (#operation? replace delete))",
+        )
+        .unwrap();
+
+        let start = source.find("fn g").unwrap();
+        let region = start..start + "fn g() {}".len();
+        let violations = |operation| {
+            let edit = EditContext {
+                operation,
+                region: region.clone(),
+            };
+            ContextValidator::validate_tree(&tree, &query, source, None, Some(&edit))
+                .violations
+                .len()
+        };
+        assert_eq!(violations(OperationType::Replace), 1);
+        assert_eq!(violations(OperationType::Insert), 0);
+
+        // elsewhere in the file, or outside an edit, the rule doesn't apply
+        let edit = EditContext {
+            operation: OperationType::Replace,
+            region: 0..2,
+        };
+        assert!(ContextValidator::validate_tree(&tree, &query, source, None, Some(&edit)).is_valid);
+        assert!(ContextValidator::validate_tree(&tree, &query, source, None, None).is_valid);
+
+        assert_eq!(
+            OperationType::new(Operation::Replace, "\n"),
+            OperationType::Delete
+        );
+        assert_eq!(
+            OperationType::new(Operation::InsertBefore, "fn g() {}"),
+            OperationType::Insert
+        );
+    }
+}
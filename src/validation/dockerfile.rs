@@ -0,0 +1,249 @@
+//! Best-practice warnings for Dockerfiles, which don't reject an edit but are noted in its
+//! preview
+
+use super::Problem;
+use std::path::Path;
+
+/// Extensions of the archives `ADD` unpacks, which are a reason to use it over `COPY`
+const ARCHIVES: &[&str] = &[
+    ".tar", ".tar.gz", ".tgz", ".tar.bz2", ".tbz2", ".tar.xz", ".txz",
+];
+
+/// Whether `path` is a Dockerfile, like `Dockerfile`, `Dockerfile.dev`, `api.Dockerfile`, or a
+/// `Containerfile`
+pub fn is_dockerfile(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    ["Dockerfile", "Containerfile"]
+        .iter()
+        .any(|base| name == *base || name.starts_with(&format!("{base}.")))
+        || name.ends_with(".Dockerfile")
+        || name.ends_with(".dockerfile")
+}
+
+/// An instruction, with the lines it continues onto joined
+struct Instruction {
+    line: usize,
+    keyword: String,
+    arguments: String,
+}
+
+/// Base images that aren't pinned to a version, `ADD` used to copy local files, and stages that
+/// run or copy files without setting a `WORKDIR`
+pub fn warnings(source: &str) -> Vec<Problem> {
+    let instructions = instructions(source);
+    let mut warnings = vec![];
+    let mut stages: Vec<String> = vec![];
+
+    let starts = instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, instruction)| instruction.keyword == "FROM")
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    for (position, start) in starts.iter().enumerate() {
+        let from = &instructions[*start];
+        let end = starts
+            .get(position + 1)
+            .copied()
+            .unwrap_or(instructions.len());
+        let stage = &instructions[start + 1..end];
+
+        let words = from
+            .arguments
+            .split_whitespace()
+            .filter(|word| !word.starts_with("--"))
+            .collect::<Vec<_>>();
+        let Some(image) = words.first().copied() else {
+            continue;
+        };
+        let builds_on_stage = stages.iter().any(|name| name.eq_ignore_ascii_case(image));
+        if let [_, r#as, name] = words.as_slice()
+            && r#as.eq_ignore_ascii_case("as")
+        {
+            stages.push(name.to_string());
+        }
+        if builds_on_stage || image == "scratch" || image.contains('$') {
+            continue;
+        }
+
+        let name = image.rsplit('/').next().unwrap_or(image);
+        let tag = name.split_once(':').map(|(_, tag)| tag);
+        if !image.contains('@') && tag.is_none_or(|tag| tag == "latest") {
+            warnings.push(Problem {
+                line: from.line,
+                message: format!(
+                    "The base image `{image}` isn't pinned to a version, so a rebuild can pick \
+                     up a different image. Give it a specific tag or a digest."
+                ),
+            });
+        }
+
+        let works_in_directory = stage
+            .iter()
+            .any(|instruction| ["RUN", "COPY", "ADD"].contains(&instruction.keyword.as_str()));
+        if works_in_directory
+            && !stage
+                .iter()
+                .any(|instruction| instruction.keyword == "WORKDIR")
+        {
+            warnings.push(Problem {
+                line: from.line,
+                message: format!(
+                    "The stage from `{image}` has no `WORKDIR`, so `RUN`, `COPY`, and `ADD` use \
+                     the base image's directory, often `/`. Set one, like `WORKDIR /app`."
+                ),
+            });
+        }
+    }
+
+    for instruction in instructions
+        .iter()
+        .filter(|instruction| instruction.keyword == "ADD")
+    {
+        let sources = sources(&instruction.arguments);
+        if sources.iter().any(|source| !fetches_or_unpacks(source)) {
+            warnings.push(Problem {
+                line: instruction.line,
+                message: format!(
+                    "`ADD {}` copies local files, which `COPY` does without also unpacking \
+                     archives and fetching URLs. Use `COPY` for them.",
+                    instruction.arguments
+                ),
+            });
+        }
+    }
+
+    warnings.sort_by_key(|warning| warning.line);
+    warnings
+}
+
+/// The instructions of `source`, skipping comments, blank lines, and the bodies of heredocs
+fn instructions(source: &str) -> Vec<Instruction> {
+    let mut instructions = vec![];
+    let mut lines = source.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut text = line.to_string();
+        while text.ends_with('\\') {
+            text.pop();
+            text.truncate(text.trim_end().len());
+            let Some((_, next)) = lines.find(|(_, next)| !next.trim_start().starts_with('#'))
+            else {
+                break;
+            };
+            text.push(' ');
+            text.push_str(next.trim());
+        }
+        if let Some(delimiter) = heredoc_delimiter(&text) {
+            lines.find(|(_, body)| body.trim() == delimiter);
+        }
+        let (keyword, arguments) = text.split_once(char::is_whitespace).unwrap_or((&text, ""));
+        instructions.push(Instruction {
+            line: index + 1,
+            keyword: keyword.to_ascii_uppercase(),
+            arguments: arguments.trim().to_string(),
+        });
+    }
+    instructions
+}
+
+/// The word that ends a heredoc like `RUN <<EOF` or `COPY <<-"END" /app/config`
+fn heredoc_delimiter(text: &str) -> Option<String> {
+    let (_, after) = text.split_once("<<")?;
+    let word = after
+        .trim_start_matches('-')
+        .split_whitespace()
+        .next()?
+        .trim_matches(['"', '\'']);
+    word.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        .then(|| word.to_string())
+        .filter(|word| !word.is_empty())
+}
+
+/// The sources of an `ADD`, in either its shell or its JSON form, without its flags and
+/// destination
+fn sources(arguments: &str) -> Vec<String> {
+    let mut paths = serde_json::from_str::<Vec<String>>(arguments).unwrap_or_else(|_| {
+        arguments
+            .split_whitespace()
+            .filter(|word| !word.starts_with("--"))
+            .map(str::to_string)
+            .collect()
+    });
+    paths.pop();
+    paths
+}
+
+/// Whether `source` is a URL or archive, which `ADD` fetches or unpacks
+fn fetches_or_unpacks(source: &str) -> bool {
+    source.starts_with("http://")
+        || source.starts_with("https://")
+        || source.starts_with("git@")
+        || source.ends_with(".git")
+        || ARCHIVES.iter().any(|extension| source.ends_with(extension))
+        || source.starts_with("<<")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn best_practices() {
+        let source = r#"# syntax=docker/dockerfile:1
+FROM rust:1.80 AS builder
+WORKDIR /src
+COPY . .
+RUN cargo build --release
+
+FROM debian
+ADD target/release/app \
+    /usr/local/bin/app
+ADD https://example.com/config.tar.gz /etc/app/
+RUN <<EOF
+FROM ubuntu
+EOF
+
+FROM builder AS test
+RUN cargo test
+"#;
+        let warnings = warnings(source)
+            .into_iter()
+            .map(|Problem { line, message }| format!("{line}: {message}"))
+            .collect::<Vec<_>>();
+        assert_eq!(warnings.len(), 3, "{warnings:#?}");
+        assert!(warnings[0].starts_with("7: The base image `debian` isn't pinned"));
+        assert!(warnings[1].starts_with("7: The stage from `debian` has no `WORKDIR`"));
+        assert!(warnings[2].starts_with("8: `ADD target/release/app /usr/local/bin/app` copies"));
+    }
+
+    #[test]
+    fn pinned_images() {
+        for image in [
+            "node:20-alpine",
+            "ghcr.io/org/app@sha256:abc",
+            "localhost:5000/app:1.2",
+        ] {
+            assert_eq!(warnings(&format!("FROM {image}\n")), vec![], "{image}");
+        }
+        for image in ["node", "node:latest", "localhost:5000/app"] {
+            assert_eq!(warnings(&format!("FROM {image}\n")).len(), 1, "{image}");
+        }
+    }
+
+    #[test]
+    fn dockerfile_paths() {
+        assert!(is_dockerfile(Path::new("/repo/Dockerfile")));
+        assert!(is_dockerfile(Path::new("Dockerfile.dev")));
+        assert!(is_dockerfile(Path::new("docker/api.Dockerfile")));
+        assert!(is_dockerfile(Path::new("Containerfile")));
+        assert!(!is_dockerfile(Path::new("Dockerfiles/README.md")));
+        assert!(!is_dockerfile(Path::new(".dockerignore")));
+    }
+}
@@ -0,0 +1,254 @@
+//! Link checks for Markdown, noting references that no longer resolve after an edit
+
+use super::Problem;
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    sync::LazyLock,
+};
+
+static DEFINITION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}\[([^\]]+)\]:\s*\S").unwrap());
+static REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([^\]]*)\]\[([^\]]*)\]").unwrap());
+static ANCHOR_LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\]\(#([^)\s]+)\)").unwrap());
+static HTML_ANCHOR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\b(?:name|id)\s*=\s*"([^"]+)""#).unwrap());
+static INLINE_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap());
+static CODE_SPAN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`+[^`]*`+").unwrap());
+
+/// Whether `path` is a Markdown file
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "md" || extension == "markdown")
+}
+
+/// A heading of a Markdown document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// The line it's on, counting from 1
+    pub line: usize,
+    /// 1 for `#` or a `===` underline, through 6 for `######`
+    pub level: usize,
+    /// Its text, with links replaced by their text
+    pub text: String,
+    /// The anchor GitHub gives it, with a `-1`, `-2`… suffix for repeated headings
+    pub anchor: String,
+    /// Whether it's a setext heading, underlined with `===` or `---` on the next line
+    pub underlined: bool,
+}
+
+/// The ATX and setext headings of `source`, outside fenced code blocks
+pub fn headings(source: &str) -> Vec<Heading> {
+    let lines = prose(source);
+    let mut headings = vec![];
+    let mut slugs = BTreeMap::<String, usize>::new();
+    for (index, (number, line)) in lines.iter().enumerate() {
+        let underline = lines.get(index + 1).and_then(|(next_number, next)| {
+            let next = next.trim();
+            (*next_number == number + 1
+                && !next.is_empty()
+                && (next.chars().all(|c| c == '=') || next.chars().all(|c| c == '-'))
+                && !line.trim().is_empty()
+                && !line.trim_start().starts_with(['-', '*', '>', '#']))
+            .then(|| if next.starts_with('=') { 1 } else { 2 })
+        });
+        let Some((level, heading)) =
+            atx_heading(line).or(underline.map(|level| (level, line.trim())))
+        else {
+            continue;
+        };
+        let slug = slug(heading);
+        let count = slugs.entry(slug.clone()).or_default();
+        let anchor = match *count {
+            0 => slug,
+            count => format!("{slug}-{count}"),
+        };
+        *count += 1;
+        headings.push(Heading {
+            line: *number,
+            level,
+            text: INLINE_LINK.replace_all(heading, "$1").into_owned(),
+            anchor,
+            underlined: underline.is_some() && atx_heading(line).is_none(),
+        });
+    }
+    headings
+}
+
+/// Reference-style links whose label has no definition, and links to `#anchors` that no heading
+/// or HTML anchor in the document has
+pub fn warnings(source: &str) -> Vec<Problem> {
+    let lines = prose(source);
+    let mut definitions = BTreeSet::new();
+    let mut anchors = headings(source)
+        .into_iter()
+        .map(|heading| heading.anchor)
+        .collect::<BTreeSet<_>>();
+
+    for (_, line) in &lines {
+        if let Some(captures) = DEFINITION.captures(line) {
+            definitions.insert(label(&captures[1]));
+        }
+        for captures in HTML_ANCHOR.captures_iter(line) {
+            anchors.insert(captures[1].to_string());
+        }
+    }
+
+    let mut warnings = vec![];
+    for (number, line) in &lines {
+        if DEFINITION.is_match(line) {
+            continue;
+        }
+        for captures in REFERENCE.captures_iter(line) {
+            let reference = if captures[2].is_empty() {
+                &captures[1]
+            } else {
+                &captures[2]
+            };
+            if !definitions.contains(&label(reference)) {
+                warnings.push(Problem {
+                    line: *number,
+                    message: format!(
+                        "`{}` refers to `{reference}`, which has no definition like \
+                         `[{reference}]: https://…`",
+                        &captures[0]
+                    ),
+                });
+            }
+        }
+        for captures in ANCHOR_LINK.captures_iter(line) {
+            let anchor = &captures[1];
+            if !anchors.contains(anchor) {
+                warnings.push(Problem {
+                    line: *number,
+                    message: format!(
+                        "The link to `#{anchor}` doesn't match a heading or anchor in this document"
+                    ),
+                });
+            }
+        }
+    }
+    warnings
+}
+
+/// The lines outside fenced code blocks, each with its number counting from 1 and with its code
+/// spans blanked out
+pub fn prose(source: &str) -> Vec<(usize, String)> {
+    let mut lines = vec![];
+    let mut fence: Option<String> = None;
+    for (index, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let marker = trimmed
+            .chars()
+            .take_while(|c| *c == '`' || *c == '~')
+            .collect::<String>();
+        match &fence {
+            Some(open) => {
+                if marker.starts_with(open.as_str()) && trimmed[marker.len()..].trim().is_empty() {
+                    fence = None;
+                }
+                continue;
+            }
+            None if marker.len() >= 3 && marker.chars().all(|c| marker.starts_with(c)) => {
+                fence = Some(marker);
+                continue;
+            }
+            None => {}
+        }
+        let line = CODE_SPAN.replace_all(line, |captures: &regex::Captures<'_>| {
+            " ".repeat(captures[0].len())
+        });
+        lines.push((index + 1, line.into_owned()));
+    }
+    lines
+}
+
+/// The level and text of an ATX heading like `## Usage ##`
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) || line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let text = &trimmed[level..];
+    (text.is_empty() || text.starts_with([' ', '\t']))
+        .then(|| (level, text.trim().trim_end_matches('#').trim_end()))
+}
+
+/// The anchor GitHub gives a heading: lowercase, without punctuation or link targets, and with
+/// hyphens for spaces
+fn slug(heading: &str) -> String {
+    INLINE_LINK
+        .replace_all(heading, "$1")
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+/// A reference label as it's matched: case-insensitively, with runs of whitespace as one space
+fn label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const README: &str = r#"# My Project
+
+See [the guide][guide], [Setup][], and [usage](#usage-notes).
+Jump to [the API](#the-api-v2) or [the second FAQ](#faq-1).
+
+## Usage notes
+
+## The [API](https://example.com) v2
+
+FAQ
+---
+
+## FAQ
+
+```markdown
+[not a link][nowhere] and [skip](#missing)
+```
+
+Inline `[code][span]` isn't a link either.
+
+[guide]: https://example.com/guide
+[setup]: ./SETUP.md
+"#;
+
+    #[test]
+    fn links_resolve() {
+        assert_eq!(warnings(README), vec![]);
+    }
+
+    #[test]
+    fn orphaned_references() {
+        let edited = README
+            .replace("[setup]: ./SETUP.md\n", "")
+            .replace("## Usage notes", "## Usage");
+        let messages = warnings(&edited)
+            .into_iter()
+            .map(|Problem { line, message }| format!("{line}: {message}"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "3: `[Setup][]` refers to `Setup`, which has no definition like \
+                 `[Setup]: https://…`",
+                "3: The link to `#usage-notes` doesn't match a heading or anchor in this \
+                 document",
+            ]
+        );
+    }
+}
@@ -1,2 +1,84 @@
 mod context_validator;
-pub use context_validator::ContextValidator;
+mod dockerfile;
+pub mod markdown;
+mod rules;
+pub mod scope;
+#[cfg(feature = "spelling")]
+mod spelling;
+mod style;
+mod syntax_error;
+pub mod workflow;
+pub use context_validator::{ContextValidator, EditContext, OperationType};
+pub use rules::{Severity, ValidationRules};
+pub use syntax_error::SyntaxError;
+
+use std::{collections::BTreeSet, path::Path};
+
+/// A check for a kind of file: the name its warnings use, whether a path is that kind of file,
+/// and what the check finds in a file's source
+pub type FileCheck = (&'static str, fn(&Path) -> bool, fn(&str) -> Vec<Problem>);
+
+/// Checks whose findings are noted in previews without rejecting the edit
+pub const FILE_WARNINGS: &[FileCheck] = &[
+    (
+        "Dockerfile",
+        dockerfile::is_dockerfile,
+        dockerfile::warnings,
+    ),
+    ("Markdown", markdown::is_markdown, markdown::warnings),
+];
+
+/// Misspellings in the comments, docstrings, or Markdown of a file, when the dictionary is
+/// built in
+#[cfg(feature = "spelling")]
+pub fn misspellings(
+    language: &crate::languages::LanguageCommon,
+    path: &Path,
+    source: &str,
+) -> Vec<Problem> {
+    spelling::problems(language, path, source)
+}
+
+/// Long lines and trailing whitespace in a file whose formatter leaves them be, checked after
+/// formatting since formatters that do enforce them would have fixed them
+pub fn style(language: crate::languages::LanguageName, path: &Path, source: &str) -> Vec<Problem> {
+    if style::applies(language) {
+        style::problems(
+            source,
+            markdown::is_markdown(path),
+            style::max_line_length(),
+        )
+    } else {
+        vec![]
+    }
+}
+
+/// Something wrong with a file that a check for its kind of file found, and the line it's on,
+/// counting from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub line: usize,
+    pub message: String,
+}
+
+/// The problems in `after` that `before` didn't already have, so that an edit is only held
+/// responsible for what it changes
+pub fn introduced(before: Vec<Problem>, after: Vec<Problem>) -> Vec<Problem> {
+    let existing = before
+        .into_iter()
+        .map(|problem| problem.message)
+        .collect::<BTreeSet<_>>();
+    after
+        .into_iter()
+        .filter(|problem| !existing.contains(&problem.message))
+        .collect()
+}
+
+/// A note listing the warnings a check for `kind` files found
+pub fn describe_warnings(kind: &str, warnings: &[Problem]) -> String {
+    let mut note = format!("⚠️ {kind} warnings:\n");
+    for Problem { line, message } in warnings {
+        note.push_str(&format!("\n• {message} (line {line})"));
+    }
+    note
+}
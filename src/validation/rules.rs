@@ -0,0 +1,104 @@
+use crate::languages::toml;
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// Where a project sets how strictly each validation rule is enforced, relative to a directory
+/// containing the edited file
+pub const RULES: &str = ".semantic-edit/validation.toml";
+
+/// How a broken validation rule is treated
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Reject the edit
+    #[default]
+    Error,
+    /// Apply the edit, listing the violation alongside it
+    Warn,
+    /// Don't check the rule
+    Off,
+}
+
+/// The severities a project has given validation rules for one language, like
+///
+/// ```toml
+/// # .semantic-edit/validation.toml
+/// [rust]
+/// "function.in.function" = "off"
+/// "self.outside.impl" = "warn"
+/// ```
+///
+/// Rules are named as in validation errors. Rules that aren't listed are errors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationRules(BTreeMap<String, Severity>);
+
+impl ValidationRules {
+    /// The rules for `language` set in the nearest validation configuration above `file`
+    pub fn for_file(file: &Path, language: &str) -> Result<Self> {
+        let Some(path) = file
+            .ancestors()
+            .skip(1)
+            .map(|directory| directory.join(RULES))
+            .find(|path| path.is_file())
+        else {
+            return Ok(Self::default());
+        };
+
+        let source = fs::read_to_string(&path)?;
+        let mut languages: BTreeMap<String, BTreeMap<String, Severity>> =
+            serde_json::from_value(toml::to_json(&source)?).map_err(|error| {
+                anyhow!(
+                    "{} is not a valid rule configuration: {error}",
+                    path.display()
+                )
+            })?;
+        Ok(Self(languages.remove(language).unwrap_or_default()))
+    }
+
+    /// How violations of `rule` are treated
+    pub fn severity(&self, rule: &str) -> Severity {
+        self.0.get(rule).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_configuration_applies() {
+        let dir = std::env::temp_dir().join(format!("semantic-edit-rules-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src/.semantic-edit")).unwrap();
+        fs::create_dir_all(dir.join(".semantic-edit")).unwrap();
+        fs::write(
+            dir.join(RULES),
+            "[rust]\n\"function.in.function\" = \"off\"\n\"self.outside.impl\" = \"warn\"\n",
+        )
+        .unwrap();
+
+        let rules = ValidationRules::for_file(&dir.join("lib.rs"), "rust").unwrap();
+        assert_eq!(rules.severity("function.in.function"), Severity::Off);
+        assert_eq!(rules.severity("self.outside.impl"), Severity::Warn);
+        assert_eq!(rules.severity("impl.nested"), Severity::Error);
+
+        let rules = ValidationRules::for_file(&dir.join("main.py"), "python").unwrap();
+        assert_eq!(rules, ValidationRules::default());
+
+        fs::write(
+            dir.join("src").join(RULES),
+            "[rust]\n\"function.in.function\" = \"sometimes\"\n",
+        )
+        .unwrap();
+        let error = ValidationRules::for_file(&dir.join("src/lib.rs"), "rust")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("is not a valid rule configuration"),
+            "{error}"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+use std::{env, ops::Range};
+use tree_sitter::Tree;
+
+/// Files at least this large are only validated around an edit while it is previewed
+pub const PARTIAL_VALIDATION_BYTES: usize = 256 * 1024;
+
+/// How many top-level items on either side of an edit are validated with it when
+/// `SEMANTIC_EDIT_VALIDATION_WINDOW` is not set
+const DEFAULT_WINDOW: usize = 2;
+
+/// Whether a file of `len` bytes is validated only around an edit while it is previewed
+pub fn is_partial(len: usize) -> bool {
+    len >= PARTIAL_VALIDATION_BYTES
+}
+
+/// The part of a large file to validate after `region` of it was edited, or None to validate all
+/// of it. Validation is checked again over the whole file when the edit is persisted.
+pub fn partial(tree: &Tree, region: Range<usize>) -> Option<Range<usize>> {
+    let window = env::var("SEMANTIC_EDIT_VALIDATION_WINDOW")
+        .ok()
+        .and_then(|window| window.parse().ok())
+        .unwrap_or(DEFAULT_WINDOW);
+    is_partial(tree.root_node().end_byte()).then(|| around(tree, region, window))?
+}
+
+/// The top-level items that `region` touches, and `window` more items on either side
+fn around(tree: &Tree, region: Range<usize>, window: usize) -> Option<Range<usize>> {
+    let root = tree.root_node();
+    let items = root.children(&mut root.walk()).collect::<Vec<_>>();
+    let first = items
+        .iter()
+        .position(|item| item.end_byte() >= region.start)?;
+    let last = items
+        .iter()
+        .rposition(|item| item.start_byte() <= region.end)?
+        .max(first);
+    let first = first.saturating_sub(window);
+    let last = (last + window).min(items.len() - 1);
+    Some(items[first].start_byte()..items[last].end_byte())
+}
+
+#[cfg(all(test, feature = "rust"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scope_covers_the_edited_item_and_its_neighbors() {
+        let source = "fn a() {}\nfn b() {}\nfn c() { let x = 1; }\nfn d() {}\nfn e() {}\n";
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_rust::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let edit = source.find("let x").unwrap();
+
+        let scope = around(&tree, edit..edit + 5, 0).unwrap();
+        assert_eq!(&source[scope], "fn c() { let x = 1; }");
+
+        let scope = around(&tree, edit..edit + 5, 1).unwrap();
+        assert_eq!(
+            &source[scope],
+            "fn b() {}\nfn c() { let x = 1; }\nfn d() {}"
+        );
+
+        let scope = around(&tree, 0..source.len(), 10).unwrap();
+        assert_eq!(&source[scope], source.trim_end());
+    }
+}
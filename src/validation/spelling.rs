@@ -0,0 +1,151 @@
+//! Spelling checks for prose: comments, Python docstrings, and Markdown outside code, which note
+//! well-known misspellings an edit introduces
+
+use super::{Problem, markdown};
+use crate::languages::LanguageCommon;
+use regex::Regex;
+use std::{collections::HashMap, path::Path, sync::LazyLock};
+use tree_sitter::Node;
+
+/// Misspellings and their corrections, from `dictionaries/misspellings.txt`
+static MISSPELLINGS: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
+    include_str!("../../dictionaries/misspellings.txt")
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once("->"))
+        .collect()
+});
+
+/// Words, along with what's attached to them, so that identifiers like `snake_case`, paths, and
+/// URLs can be told apart from words
+static WORD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[\w'./:-]+").unwrap());
+
+/// Misspelled words in the prose of `source`: all of a Markdown file outside code, or the
+/// comments and docstrings of a file in a language with a grammar
+pub fn problems(language: &LanguageCommon, path: &Path, source: &str) -> Vec<Problem> {
+    if markdown::is_markdown(path) {
+        return markdown::prose(source)
+            .iter()
+            .flat_map(|(line, text)| misspellings(text, *line))
+            .collect();
+    }
+
+    let Some(tree) = language
+        .tree_sitter_parser()
+        .ok()
+        .and_then(|mut parser| parser.parse(source, None))
+    else {
+        return vec![];
+    };
+    let mut problems = vec![];
+    collect(tree.root_node(), source, &mut problems);
+    problems
+}
+
+fn collect(node: Node<'_>, source: &str, problems: &mut Vec<Problem>) {
+    let is_docstring = node.kind() == "string"
+        && node
+            .parent()
+            .is_some_and(|parent| parent.kind() == "expression_statement");
+    if node.kind().contains("comment") || is_docstring {
+        let first_line = node.start_position().row + 1;
+        for (offset, text) in source[node.byte_range()].lines().enumerate() {
+            problems.extend(misspellings(text, first_line + offset));
+        }
+        return;
+    }
+    for child in node.named_children(&mut node.walk()) {
+        collect(child, source, problems);
+    }
+}
+
+/// The misspelled words in a line of prose, skipping anything that looks like code, such as
+/// words with underscores or dots in them
+fn misspellings(text: &str, line: usize) -> Vec<Problem> {
+    WORD.find_iter(text)
+        .filter_map(|token| {
+            let word = token
+                .as_str()
+                .trim_start_matches(['\'', '.', ':', '-', '/'])
+                .trim_end_matches(['\'', '.', ':', '-', '/']);
+            if !word.chars().all(|c| c.is_ascii_alphabetic()) {
+                return None;
+            }
+            let mut chars = word.chars();
+            let capitalized = chars.next()?.is_ascii_uppercase();
+            if chars.any(|c| c.is_ascii_uppercase()) {
+                return None;
+            }
+            let correction = MISSPELLINGS.get(word.to_ascii_lowercase().as_str())?;
+            let correction = if capitalized {
+                let mut chars = correction.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            } else {
+                correction.to_string()
+            };
+            Some(Problem {
+                line,
+                message: format!(
+                    "`{word}` looks misspelled, and was probably meant as `{correction}`"
+                ),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::{LanguageName, LanguageRegistry};
+
+    fn messages(language: LanguageName, path: &str, source: &str) -> Vec<String> {
+        let registry = LanguageRegistry::new().unwrap();
+        problems(registry.get_language(language), Path::new(path), source)
+            .into_iter()
+            .map(|problem| format!("{}: {}", problem.line, problem.message))
+            .collect()
+    }
+
+    #[test]
+    #[cfg(all(feature = "rust", feature = "python"))]
+    fn comments_and_docstrings_are_checked() {
+        assert_eq!(
+            messages(
+                LanguageName::Rust,
+                "lib.rs",
+                "/// Recieve a message\nfn recieve() {\n    let seperate = 1; // wich one?\n}\n",
+            ),
+            [
+                "1: `Recieve` looks misspelled, and was probably meant as `Receive`",
+                "3: `wich` looks misspelled, and was probably meant as `which`",
+            ]
+        );
+        assert_eq!(
+            messages(
+                LanguageName::Python,
+                "app.py",
+                "def f():\n    \"\"\"Return the\n    lenght.\"\"\"\n    return \"teh lenght\"\n",
+            ),
+            ["3: `lenght` looks misspelled, and was probably meant as `length`"]
+        );
+    }
+
+    #[test]
+    fn markdown_prose_is_checked_outside_code() {
+        assert_eq!(
+            messages(
+                LanguageName::Other,
+                "README.md",
+                "# Setup\n\nRun it `untill` done, untill it's definately\nready.\n\n\
+                 ```sh\nseperate\n```\n\nSee https://example.com/seperate and recieve_all.\n",
+            ),
+            [
+                "3: `untill` looks misspelled, and was probably meant as `until`",
+                "3: `definately` looks misspelled, and was probably meant as `definitely`",
+            ]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Line length and trailing whitespace checks for Markdown, plain text, and TOML, whose
+//! formatters leave long lines and trailing whitespace as they are
+
+use super::Problem;
+use crate::languages::LanguageName;
+use std::env;
+
+/// Lines longer than this many characters are noted, unless `SEMANTIC_EDIT_MAX_LINE_LENGTH`
+/// sets another limit, or turns the check off with 0
+const DEFAULT_MAX_LINE_LENGTH: usize = 100;
+
+/// How much of a line its warnings quote to say which line they're about
+const QUOTED_CHARS: usize = 30;
+
+/// Whether files in `language` get style warnings
+pub fn applies(language: LanguageName) -> bool {
+    matches!(language, LanguageName::Toml | LanguageName::Other)
+}
+
+pub fn max_line_length() -> usize {
+    env::var("SEMANTIC_EDIT_MAX_LINE_LENGTH")
+        .ok()
+        .and_then(|max| max.parse().ok())
+        .unwrap_or(DEFAULT_MAX_LINE_LENGTH)
+}
+
+/// Lines of `source` longer than `max` characters, other than those that can't be wrapped like
+/// long URLs and Markdown tables, and lines that end in whitespace, except Markdown's two-space
+/// line breaks
+pub fn problems(source: &str, markdown: bool, max: usize) -> Vec<Problem> {
+    let mut problems = vec![];
+    for (index, line) in source.lines().enumerate() {
+        let trimmed = line.trim_end();
+        let quoted = || {
+            let quoted = trimmed
+                .trim_start()
+                .chars()
+                .take(QUOTED_CHARS)
+                .collect::<String>();
+            if quoted.len() < trimmed.trim_start().len() {
+                format!("{}…", quoted.trim_end())
+            } else {
+                quoted
+            }
+        };
+
+        let length = line.chars().count();
+        let unbreakable = !trimmed.trim_start().contains(char::is_whitespace)
+            || (markdown && trimmed.trim_start().starts_with('|'));
+        if max > 0 && length > max && !unbreakable {
+            problems.push(Problem {
+                line: index + 1,
+                message: format!(
+                    "`{}` is {length} characters long, more than the {max} allowed",
+                    quoted()
+                ),
+            });
+        }
+
+        let trailing = &line[trimmed.len()..];
+        let line_break = markdown && trailing == "  " && !trimmed.is_empty();
+        if !trailing.is_empty() && !line_break {
+            problems.push(Problem {
+                line: index + 1,
+                message: if trimmed.is_empty() {
+                    "A blank line has whitespace on it".into()
+                } else {
+                    format!("`{}` ends with whitespace", quoted())
+                },
+            });
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(source: &str, markdown: bool) -> Vec<String> {
+        problems(source, markdown, 40)
+            .into_iter()
+            .map(|problem| format!("{}: {}", problem.line, problem.message))
+            .collect()
+    }
+
+    #[test]
+    fn long_lines_and_trailing_whitespace() {
+        let source = "# Title\n\nA line that goes on for rather longer than it should.\n\
+                      https://example.com/a/very/long/url/that/cannot/be/wrapped\n\
+                      | a table | row | that | is | long | but | fine |\n\
+                      Ends with a break  \nEnds with a tab\t\n   \n";
+        assert_eq!(
+            messages(source, true),
+            [
+                "3: `A line that goes on for rather…` is 53 characters long, more than the 40 \
+                 allowed",
+                "7: `Ends with a tab` ends with whitespace",
+                "8: A blank line has whitespace on it",
+            ]
+        );
+        assert_eq!(
+            messages(source, false)[1..],
+            [
+                "5: `| a table | row | that | is |…` is 49 characters long, more than the 40 \
+                 allowed",
+                "6: `Ends with a break` ends with whitespace",
+                "7: `Ends with a tab` ends with whitespace",
+                "8: A blank line has whitespace on it",
+            ]
+        );
+        assert_eq!(problems("x = 1\n", false, 0), vec![]);
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Range,
+};
+
+/// A syntax error in some source, located the way editors show positions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// Bytes of the source that the error covers
+    pub range: Range<usize>,
+    /// Line of the start of the error, from 0
+    pub line: usize,
+    /// Column of the start of the error in characters, from 0
+    pub column: usize,
+    /// Column of the start of the error in UTF-16 code units, from 0, as the language server
+    /// protocol counts them
+    pub utf16_column: usize,
+    /// What the grammar expected here, like "missing `}`", where the parser can tell
+    pub hint: Option<String>,
+}
+
+impl SyntaxError {
+    /// Locate an error covering `range` of `source`. Tree-sitter and taplo report byte offsets,
+    /// which only match the column an editor shows on ASCII lines.
+    pub fn new(source: &str, range: Range<usize>) -> Self {
+        let start = floor_char_boundary(source, range.start);
+        let line_start = source[..start].rfind('\n').map_or(0, |newline| newline + 1);
+        let before = &source[line_start..start];
+        Self {
+            line: source[..line_start].matches('\n').count(),
+            column: before.chars().count(),
+            utf16_column: before.encode_utf16().count(),
+            range,
+            hint: None,
+        }
+    }
+
+    pub fn with_hint(mut self, hint: Option<String>) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    /// A line of `^` under the part of the error's first line that it covers, indented with the
+    /// same tabs as that line so the carets line up beneath it
+    pub fn underline(&self, source: &str) -> String {
+        let start = floor_char_boundary(source, self.range.start);
+        let end = floor_char_boundary(source, self.range.end.max(start));
+        let line_start = source[..start].rfind('\n').map_or(0, |newline| newline + 1);
+        let indent = source[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let covered = source[start..end]
+            .lines()
+            .next()
+            .map_or(0, |line| line.chars().count());
+        format!("{indent}{}", "^".repeat(covered.max(1)))
+    }
+}
+
+impl Display for SyntaxError {
+    /// `line 3, column 7`, counting from 1 like editors do, with the UTF-16 column when it
+    /// differs
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line + 1, self.column + 1)?;
+        if self.utf16_column != self.column {
+            write!(f, " (UTF-16 column {})", self.utf16_column + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// The largest character boundary at or before `index`
+fn floor_char_boundary(source: &str, index: usize) -> usize {
+    let mut index = index.min(source.len());
+    while !source.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_count_characters_not_bytes() {
+        let source = "let a = 1;\nlet café = \"naïve\" +;\nlet 🦀 = ;";
+
+        let plus = source.find('+').unwrap();
+        let error = SyntaxError::new(source, plus..plus + 1);
+        assert_eq!((error.line, error.column, error.utf16_column), (1, 19, 19));
+        assert_eq!(error.to_string(), "line 2, column 20");
+
+        let semicolon = source.rfind(';').unwrap();
+        let error = SyntaxError::new(source, semicolon..semicolon);
+        assert_eq!((error.line, error.column, error.utf16_column), (2, 8, 9));
+        assert_eq!(error.to_string(), "line 3, column 9 (UTF-16 column 10)");
+
+        let error = SyntaxError::new(source, 0..0);
+        assert_eq!(error.to_string(), "line 1, column 1");
+    }
+}
@@ -0,0 +1,338 @@
+//! Checks for GitHub Actions workflows beyond their syntax, so that an edit that would only fail
+//! once it's pushed is rejected in preview instead
+
+use super::Problem;
+use std::path::{Component, Path};
+
+/// The keys a workflow can have at its top level
+const TOP_LEVEL_KEYS: &[&str] = &[
+    "name",
+    "run-name",
+    "on",
+    "permissions",
+    "env",
+    "defaults",
+    "concurrency",
+    "jobs",
+];
+
+/// Whether `path` is a workflow: a YAML file in `.github/workflows/`
+pub fn is_workflow(path: &Path) -> bool {
+    let in_workflows = path
+        .parent()
+        .map(|parent| {
+            parent
+                .components()
+                .rev()
+                .take(2)
+                .map(|component| match component {
+                    Component::Normal(name) => name.to_str().unwrap_or_default(),
+                    _ => "",
+                })
+                .eq(["workflows", ".github"])
+        })
+        .unwrap_or_default();
+    in_workflows
+        && path
+            .extension()
+            .is_some_and(|extension| extension == "yml" || extension == "yaml")
+}
+
+/// A message listing `problems`, for rejecting an edit
+pub fn describe(problems: &[Problem]) -> String {
+    let mut message = "This edit would break the GitHub Actions workflow:".to_string();
+    for Problem {
+        line,
+        message: problem,
+    } in problems
+    {
+        message.push_str(&format!("\n- line {line}: {problem}"));
+    }
+    message
+}
+
+/// A line of the workflow that holds something other than a comment or part of a block scalar
+struct Line<'source> {
+    number: usize,
+    indent: usize,
+    text: &'source str,
+}
+
+impl<'source> Line<'source> {
+    /// The key and value of a `key: value` line, without a leading `- ` or quotes around the key
+    fn pair(&self) -> Option<(&'source str, &'source str)> {
+        let text = self.text.strip_prefix("- ").unwrap_or(self.text);
+        let (key, value) = text.split_once(':')?;
+        (value.is_empty() || value.starts_with(' '))
+            .then(|| (key.trim().trim_matches(['"', '\'']), value.trim()))
+    }
+}
+
+/// The unknown top-level keys, action references that aren't in a form GitHub accepts, and
+/// `needs` that name jobs that don't exist in the workflow `source`
+pub fn problems(source: &str) -> Vec<Problem> {
+    let lines = lines(source);
+    let mut problems = vec![];
+
+    for line in lines.iter().filter(|line| line.indent == 0) {
+        if let Some((key, _)) = line.pair()
+            && !TOP_LEVEL_KEYS.contains(&key)
+        {
+            problems.push(Problem {
+                line: line.number,
+                message: format!(
+                    "`{key}` isn't a workflow key. The top level of a workflow has: {}",
+                    TOP_LEVEL_KEYS.join(", ")
+                ),
+            });
+        }
+    }
+
+    for line in &lines {
+        if let Some(("uses", reference)) = line.pair() {
+            let reference = unquoted(reference);
+            if !is_action_reference(reference) {
+                problems.push(Problem {
+                    line: line.number,
+                    message: format!(
+                        "`uses: {reference}` isn't an action GitHub can find. Use \
+                         `owner/repo@ref`, `owner/repo/path@ref`, `./path/to/action`, or \
+                         `docker://image`"
+                    ),
+                });
+            }
+        }
+    }
+
+    let jobs = jobs(&lines);
+    let ids = jobs.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    for (id, body) in &jobs {
+        for (line, needed) in needs(body) {
+            if !ids.contains(&needed) {
+                problems.push(Problem {
+                    line,
+                    message: format!(
+                        "Job `{id}` needs `{needed}`, but there's no job with that id"
+                    ),
+                });
+            }
+        }
+    }
+
+    problems.sort_by_key(|problem| problem.line);
+    problems
+}
+
+/// The lines of `source` without blank lines, comments, or the contents of block scalars like
+/// `run: |`
+fn lines(source: &str) -> Vec<Line<'_>> {
+    let mut lines = vec![];
+    let mut block_scalar_indent = None;
+    for (index, line) in source.lines().enumerate() {
+        let text = without_comment(line).trim();
+        let indent = line.len() - line.trim_start().len();
+        if let Some(scalar_indent) = block_scalar_indent {
+            if text.is_empty() || indent > scalar_indent {
+                continue;
+            }
+            block_scalar_indent = None;
+        }
+        if text.is_empty() || text == "---" || text == "..." {
+            continue;
+        }
+        let line = Line {
+            number: index + 1,
+            indent,
+            text,
+        };
+        if let Some((_, value)) = line.pair()
+            && value.starts_with(['|', '>'])
+        {
+            block_scalar_indent = Some(indent);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// `line` up to a `#` that starts a comment, which is one outside quotes and after a space
+fn without_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '#') if previous.is_whitespace() => return &line[..index],
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+fn unquoted(value: &str) -> &str {
+    value.trim_matches(['"', '\''])
+}
+
+/// Whether `reference` is a local action, a Docker image, or an action or reusable workflow in a
+/// repository at a ref
+fn is_action_reference(reference: &str) -> bool {
+    if reference.contains("${{") {
+        return true;
+    }
+    if let Some(path) = reference.strip_prefix("./") {
+        return !path.is_empty();
+    }
+    if let Some(image) = reference.strip_prefix("docker://") {
+        return !image.is_empty();
+    }
+    let Some((path, git_ref)) = reference.split_once('@') else {
+        return false;
+    };
+    let parts = path.split('/').collect::<Vec<_>>();
+    !git_ref.is_empty()
+        && !git_ref.contains('@')
+        && parts.len() >= 2
+        && parts.iter().all(|part| !part.is_empty())
+}
+
+/// Each job's id and the lines of its body
+fn jobs<'lines, 'source>(
+    lines: &'lines [Line<'source>],
+) -> Vec<(&'source str, &'lines [Line<'source>])> {
+    let Some(start) = lines
+        .iter()
+        .position(|line| line.indent == 0 && line.pair().is_some_and(|(key, _)| key == "jobs"))
+    else {
+        return vec![];
+    };
+    let section = &lines[start + 1..];
+    let section = &section[..section
+        .iter()
+        .position(|line| line.indent == 0)
+        .unwrap_or(section.len())];
+    let Some(job_indent) = section.first().map(|line| line.indent) else {
+        return vec![];
+    };
+
+    let starts = section
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.indent == job_indent)
+        .filter_map(|(index, line)| Some((index, line.pair()?.0)))
+        .collect::<Vec<_>>();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(position, (index, id))| {
+            let end = starts
+                .get(position + 1)
+                .map_or(section.len(), |(next, _)| *next);
+            (*id, &section[index + 1..end])
+        })
+        .collect()
+}
+
+/// The job ids in a job's `needs`, written as one id, a `[a, b]` list, or a `- a` list on the
+/// lines below it, each with its line
+fn needs<'source>(body: &[Line<'source>]) -> Vec<(usize, &'source str)> {
+    let Some(body_indent) = body.first().map(|line| line.indent) else {
+        return vec![];
+    };
+    let Some(index) = body.iter().position(|line| {
+        line.indent == body_indent && line.pair().is_some_and(|(key, _)| key == "needs")
+    }) else {
+        return vec![];
+    };
+    let line = &body[index];
+    let value = line.pair().map(|(_, value)| value).unwrap_or_default();
+    if value.is_empty() {
+        return body[index + 1..]
+            .iter()
+            .take_while(|item| item.indent > body_indent || item.text.starts_with("- "))
+            .filter_map(|item| Some((item.number, unquoted(item.text.strip_prefix("- ")?.trim()))))
+            .collect();
+    }
+    match value
+        .strip_prefix('[')
+        .and_then(|list| list.strip_suffix(']'))
+    {
+        Some(list) => list
+            .split(',')
+            .map(|id| unquoted(id.trim()))
+            .filter(|id| !id.is_empty())
+            .map(|id| (line.number, id))
+            .collect(),
+        None => vec![(line.number, unquoted(value))],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKFLOW: &str = r#"name: CI
+on:
+  push:
+    branches: [main]
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - name: Build
+        run: |
+          cargo build
+          echo "uses: not-an-action"
+  test:
+    needs: build
+    runs-on: ubuntu-latest
+    steps:
+      - uses: ./.github/actions/setup # local action
+      - uses: "docker://rust:latest"
+  release:
+    needs: [build, test]
+    uses: octo-org/workflows/.github/workflows/release.yml@main
+"#;
+
+    #[test]
+    fn valid_workflow() {
+        assert_eq!(problems(WORKFLOW), vec![]);
+    }
+
+    #[test]
+    fn broken_workflow() {
+        let broken = WORKFLOW
+            .replace("on:\n", "trigger:\n")
+            .replace("actions/checkout@v4", "actions/checkout")
+            .replace("needs: build", "needs:\n      - build\n      - lint");
+        let messages = problems(&broken)
+            .into_iter()
+            .map(|Problem { line, message }| format!("{line}: {message}"))
+            .collect::<Vec<_>>();
+        assert_eq!(messages.len(), 3, "{messages:#?}");
+        assert!(messages[0].starts_with("2: `trigger` isn't a workflow key"));
+        assert!(messages[1].starts_with("10: `uses: actions/checkout` isn't an action"));
+        assert!(messages[2].starts_with("18: Job `test` needs `lint`"));
+    }
+
+    #[test]
+    fn only_new_problems_are_introduced() {
+        let before = WORKFLOW.replace("actions/checkout@v4", "actions/checkout");
+        let after = before.replace("needs: build", "needs: lint");
+        let introduced = crate::validation::introduced(problems(&before), problems(&after));
+        assert_eq!(introduced.len(), 1);
+        assert_eq!(introduced[0].line, 16);
+    }
+
+    #[test]
+    fn workflow_paths() {
+        assert!(is_workflow(Path::new("/repo/.github/workflows/ci.yml")));
+        assert!(is_workflow(Path::new(".github/workflows/release.yaml")));
+        assert!(!is_workflow(Path::new("/repo/.github/dependabot.yml")));
+        assert!(!is_workflow(Path::new("/repo/workflows/ci.yml")));
+        assert!(!is_workflow(Path::new("/repo/.github/workflows/README.md")));
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::{Result, anyhow};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// How many versions [`VersionCache`] keeps
+pub const CACHED_VERSIONS: usize = 32;
+
+/// A content-addressed identifier for a version of a file's text, which stays the same across
+/// sessions and restarts for as long as the text does
+///
+/// This is a 64-bit FNV-1a hash of the text without any byte-order mark, so that the version of
+/// a file read through [`Encoding`](crate::encoding::Encoding) matches the version of the
+/// output written for it.
+pub fn of(text: &str) -> String {
+    let hash = text
+        .trim_start_matches('\u{feff}')
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+    format!("{hash:016x}")
+}
+
+/// Refuse to go on if `text`, the current content of `file_path`, isn't at the version a tool
+/// call expects, because the file changed after that version was seen
+pub fn check(file_path: &str, text: &str, expected: &str) -> Result<()> {
+    let current = of(text);
+    if current == expected.trim() {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "{file_path} has changed since version `{expected}` and is now at version `{current}`. \
+         Read it again before editing it, then pass the new version as expected_version."
+    ))
+}
+
+/// The text of versions written or replaced by this process, most recent last, so that tools can
+/// compare a file with an earlier version of it. Versions are content-addressed, so one cache
+/// serves every session.
+#[derive(Debug, Default)]
+pub struct VersionCache(Mutex<VecDeque<(String, Arc<str>)>>);
+
+impl VersionCache {
+    /// Remember `text`, forgetting the least recently cached version once there are too many
+    pub fn insert(&self, text: &str) {
+        let text = text.trim_start_matches('\u{feff}');
+        let version = of(text);
+        let mut versions = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        versions.retain(|(cached, _)| *cached != version);
+        versions.push_back((version, text.into()));
+        if versions.len() > CACHED_VERSIONS {
+            versions.pop_front();
+        }
+    }
+
+    /// The text of `version`, if it is cached
+    pub fn get(&self, version: &str) -> Result<Arc<str>> {
+        let versions = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        versions
+            .iter()
+            .find(|(cached, _)| cached == version.trim())
+            .map(|(_, text)| text.clone())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Version `{version}` isn't cached. The last {CACHED_VERSIONS} versions of \
+                     files written by these tools, from before and after each write, can be \
+                     compared."
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_addressed_by_content() {
+        assert_eq!(of(""), "cbf29ce484222325");
+        assert_eq!(of("fn main() {}\n"), of("\u{feff}fn main() {}\n"));
+        assert_ne!(of("fn main() {}\n"), of("fn main() { }\n"));
+
+        let version = of("a");
+        assert!(check("a.rs", "a", &version).is_ok());
+        let error = check("a.rs", "b", &version).unwrap_err().to_string();
+        assert!(
+            error.contains(&format!("now at version `{}`", of("b"))),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn the_cache_keeps_the_most_recent_versions() {
+        let cache = VersionCache::default();
+        for index in 0..=CACHED_VERSIONS {
+            cache.insert(&index.to_string());
+        }
+        assert!(cache.get(&of("0")).is_err());
+        assert_eq!(&*cache.get(&of("1")).unwrap(), "1");
+
+        // caching a version again makes it the most recent
+        cache.insert("1");
+        cache.insert("new");
+        assert!(cache.get(&of("1")).is_ok());
+        assert!(cache.get(&of("2")).is_err());
+    }
+}
@@ -0,0 +1,277 @@
+use crate::budget::Budget;
+use anyhow::{Result, anyhow};
+use std::{
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use walkdir::{DirEntry, WalkDir};
+
+/// Directories that hold dependencies or build output rather than project source
+const SKIPPED_DIRECTORIES: [&str; 3] = ["target", "node_modules", "__pycache__"];
+
+fn is_skipped(entry: &DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+    entry.depth() > 0
+        && (name.starts_with('.')
+            || (entry.file_type().is_dir() && SKIPPED_DIRECTORIES.contains(&&*name)))
+}
+
+/// Every project file under `root`, in a stable order, skipping hidden files and dependency or
+/// build output directories, until `budget` is exhausted. Paths are relative to `root`.
+///
+/// Paths are yielded in sorted order, so a workspace-wide tool can resume after the last path it
+/// processed by skipping everything that sorts at or before it, which [`Walk::resume`] does for a
+/// [`Cursor`].
+pub fn walk(root: &Path, budget: Budget) -> Walk {
+    let root = root.to_path_buf();
+    let files = WalkDir::new(&root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| !is_skipped(entry))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(move |entry| entry.path().strip_prefix(&root).ok().map(Path::to_path_buf));
+    Walk {
+        files: Box::new(files),
+        budget,
+        yielded: false,
+        stopped_at: None,
+    }
+}
+
+/// The files of a workspace, as listed by [`walk`]. The walk ends early once its time budget is
+/// exhausted, but always yields at least one file so that resuming it makes progress.
+pub struct Walk {
+    files: Box<dyn Iterator<Item = PathBuf>>,
+    budget: Budget,
+    yielded: bool,
+    stopped_at: Option<PathBuf>,
+}
+
+impl Walk {
+    /// Skip the files before `cursor`, to pick up where an earlier call stopped
+    pub fn resume(mut self, cursor: Option<&Cursor>) -> Self {
+        if let Some(cursor) = cursor.cloned() {
+            self.files = Box::new(self.files.skip_while(move |file| !cursor.includes(file)));
+        }
+        self
+    }
+
+    /// Where to resume the walk if the time budget ran out before it reached every file: the
+    /// first file it didn't reach
+    pub fn continuation(&self) -> Option<Cursor> {
+        self.stopped_at.as_ref().map(|file| Cursor {
+            file: file.clone(),
+            skip: 0,
+        })
+    }
+}
+
+impl Iterator for Walk {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<PathBuf> {
+        if self.stopped_at.is_some() {
+            return None;
+        }
+        let file = self.files.next()?;
+        if self.yielded && self.budget.is_exhausted() {
+            self.stopped_at = Some(file);
+            return None;
+        }
+        self.yielded = true;
+        Some(file)
+    }
+}
+
+/// A position in a workspace-wide listing of results, used as an opaque continuation token so
+/// that tools can return large result sets a page at a time.
+///
+/// The cursor points at the result numbered `skip` (counting from zero) within `file`, and
+/// every file that sorts after it. Its string form is `{skip}:{file}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub file: PathBuf,
+    pub skip: usize,
+}
+
+impl Cursor {
+    /// Whether `file` is at or after this cursor
+    pub fn includes(&self, file: &Path) -> bool {
+        file >= self.file.as_path()
+    }
+
+    /// How many of `file`'s results were already returned before this cursor
+    pub fn skip_in(&self, file: &Path) -> usize {
+        if file == self.file { self.skip } else { 0 }
+    }
+}
+
+impl Display for Cursor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.skip, self.file.display())
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = anyhow::Error;
+
+    fn from_str(token: &str) -> Result<Self> {
+        token
+            .split_once(':')
+            .and_then(|(skip, file)| {
+                Some(Self {
+                    file: file.into(),
+                    skip: skip.parse().ok()?,
+                })
+            })
+            .ok_or_else(|| {
+                anyhow!("\"{token}\" is not a continuation token returned by a previous call")
+            })
+    }
+}
+
+/// Results returned per call when no page size is given
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// One page of a listing that is too long to return at once. Results before the continuation
+/// cursor were returned by earlier pages and are skipped, and once the page is full the position
+/// of the next result becomes the cursor for the page after it.
+#[derive(Debug)]
+pub struct Page<T> {
+    size: usize,
+    cursor: Option<Cursor>,
+    next: Option<Cursor>,
+    pub items: Vec<T>,
+}
+
+impl<T> Page<T> {
+    /// The page that `continuation`, a token returned with an earlier page, asks for, or the
+    /// first page
+    pub fn new(size: Option<usize>, continuation: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            size: size.unwrap_or(DEFAULT_PAGE_SIZE).max(1),
+            cursor: continuation.map(str::parse).transpose()?,
+            next: None,
+            items: vec![],
+        })
+    }
+
+    /// Where this page starts, for resuming a [`Walk`]
+    pub fn cursor(&self) -> Option<&Cursor> {
+        self.cursor.as_ref()
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The cursor for the next page, once this one is full
+    pub fn next(&self) -> Option<&Cursor> {
+        self.next.as_ref()
+    }
+
+    /// Add the results listed for `file`, skipping those earlier pages returned. Returns false
+    /// once the page is full, when the listing should stop.
+    pub fn extend(&mut self, file: &Path, results: impl IntoIterator<Item = T>) -> bool {
+        let skip = self
+            .cursor
+            .as_ref()
+            .map_or(0, |cursor| cursor.skip_in(file));
+        for (index, result) in results.into_iter().enumerate().skip(skip) {
+            if self.items.len() == self.size {
+                self.next = Some(Cursor {
+                    file: file.to_path_buf(),
+                    skip: index,
+                });
+                return false;
+            }
+            self.items.push(result);
+        }
+        true
+    }
+
+    /// Why the listing that filled this page from `walk` returned before reaching the end of
+    /// the workspace, if it did
+    pub fn stop(&self, walk: &Walk) -> Option<Stop> {
+        match (&self.next, walk.continuation()) {
+            (Some(cursor), _) => Some(Stop::PageFull(cursor.clone())),
+            (None, Some(cursor)) => Some(Stop::TimeLimit(cursor)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Why a workspace-wide listing returned early, with the cursor to continue from
+pub enum Stop {
+    PageFull(Cursor),
+    TimeLimit(Cursor),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips_paths_containing_colons() {
+        let cursor = Cursor {
+            file: "src/a:b.rs".into(),
+            skip: 12,
+        };
+        assert_eq!(cursor.to_string(), "12:src/a:b.rs");
+        assert_eq!(cursor.to_string().parse::<Cursor>().unwrap(), cursor);
+    }
+
+    #[test]
+    fn walk_stops_when_the_budget_is_exhausted() {
+        let root = std::env::temp_dir().join(format!("semantic-edit-walk-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        for file in ["a.rs", "b.rs", "c.rs", "target/d.rs", ".hidden.rs"] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+
+        let all = walk(&root, Budget::from_timeout_ms(None)).collect::<Vec<_>>();
+        assert_eq!(
+            all,
+            [Path::new("a.rs"), Path::new("b.rs"), Path::new("c.rs")]
+        );
+
+        let mut walk = walk(&root, Budget::from_timeout_ms(Some(0)));
+        assert_eq!(walk.next().as_deref(), Some(Path::new("a.rs")));
+        assert_eq!(walk.next(), None);
+        let cursor = walk.continuation().unwrap();
+        assert_eq!(cursor.to_string(), "0:b.rs");
+
+        let resumed = super::walk(&root, Budget::from_timeout_ms(None)).resume(Some(&cursor));
+        assert_eq!(
+            resumed.collect::<Vec<_>>(),
+            [Path::new("b.rs"), Path::new("c.rs")]
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn pages_resume_where_the_last_one_stopped() {
+        let mut first = Page::new(Some(3), None).unwrap();
+        assert!(first.extend(Path::new("a.rs"), [1, 2]));
+        assert!(!first.extend(Path::new("b.rs"), [3, 4, 5]));
+        assert_eq!(first.items, [1, 2, 3]);
+        let token = first.next().unwrap().to_string();
+        assert_eq!(token, "1:b.rs");
+
+        let mut second = Page::new(Some(3), Some(&token)).unwrap();
+        assert!(second.extend(Path::new("b.rs"), [3, 4, 5]));
+        assert!(second.extend(Path::new("c.rs"), [6]));
+        assert_eq!(second.items, [4, 5, 6]);
+        assert!(second.next().is_none());
+    }
+
+    #[test]
+    fn cursor_rejects_foreign_tokens() {
+        assert!("lib.rs".parse::<Cursor>().is_err());
+        assert!("x:lib.rs".parse::<Cursor>().is_err());
+    }
+}
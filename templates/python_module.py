@@ -0,0 +1,9 @@
+"""TODO: describe the {{name}} module."""
+
+
+def main() -> None:
+    pass
+
+
+if __name__ == "__main__":
+    main()
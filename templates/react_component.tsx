@@ -0,0 +1,11 @@
+import React from "react";
+
+export interface {{Name}}Props {
+  children?: React.ReactNode;
+}
+
+export function {{Name}}({ children }: {{Name}}Props) {
+  return <div className="{{name}}">{children}</div>;
+}
+
+export default {{Name}};
@@ -0,0 +1,13 @@
+//! TODO: describe the `{{name}}` module
+
+pub fn {{name}}() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn {{name}}_works() {
+        {{name}}();
+    }
+}
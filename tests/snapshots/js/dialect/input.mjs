@@ -0,0 +1,10 @@
+export function total(items) {
+  return items.reduce((sum, item) => sum + price(item), 0);
+}
+
+function price(item) {
+  if (item.discount) {
+    return item.price * (1 - item.discount);
+  }
+  return item.price;
+}
@@ -0,0 +1,8 @@
+fn first() -> u32 {
+    let x = (1 + 2);
+    x
+}
+
+fn second() -> u32 {
+    2
+}
@@ -0,0 +1,9 @@
+pub struct Cart {
+    items: Vec<u32>,
+}
+
+impl Cart {
+    pub fn total(&self) -> u32 {
+        self.items.iter().sum()
+    }
+}
@@ -0,0 +1,10 @@
+pub struct Cart {
+    items: Vec<u32>,
+}
+
+impl Cart {
+    /// The sum of the items
+    pub fn total(&self) -> u32 {
+        self.items.iter().sum()
+    }
+}
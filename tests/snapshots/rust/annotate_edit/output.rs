@@ -0,0 +1,5 @@
+pub const RETRIES: u32 = 5;
+
+pub fn backoff(attempt: u32) -> u64 {
+    100 * u64::from(attempt)
+}
@@ -0,0 +1,17 @@
+pub struct User {
+    pub display_name: String,
+    pub email: String,
+}
+
+impl User {
+    pub fn new(name: &str, email: &str) -> Self {
+        Self {
+            display_name: name.into(),
+            email: email.into(),
+        }
+    }
+
+    pub fn greeting(&self) -> String {
+        format!("Hello, {}", &self.display_name)
+    }
+}
@@ -0,0 +1,41 @@
+pub struct Report {
+    pub title: String,
+    pub rows: Vec<(String, u32)>,
+}
+
+pub fn render(report: &Report) -> String {
+    let mut output = String::new();
+    output.push_str(&report.title);
+    output.push('\n');
+
+    let width = report
+        .rows
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0);
+
+    for (label, count) in &report.rows {
+        output.push_str(&format!("{label:width$} {count}\n"));
+    }
+
+    let total: u32 = report.rows.iter().map(|(_, count)| count).sum();
+    output.push_str(&format!("total {total}\n"));
+    output
+}
+
+pub fn is_empty(report: &Report) -> bool {
+    report.rows.is_empty()
+}
+
+pub fn count(report: &Report, label: &str) -> Option<u32> {
+    report
+        .rows
+        .iter()
+        .find(|(row, _)| row == label)
+        .map(|(_, count)| *count)
+}
+
+pub fn summary(report: &Report) -> String {
+    format!("{}: {} rows", report.title, report.rows.len())
+}
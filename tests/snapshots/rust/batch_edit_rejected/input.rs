@@ -0,0 +1,17 @@
+pub struct User {
+    pub name: String,
+    pub email: String,
+}
+
+impl User {
+    pub fn new(name: &str, email: &str) -> Self {
+        Self {
+            name: name.into(),
+            email: email.into(),
+        }
+    }
+
+    pub fn greeting(&self) -> String {
+        format!("Hello, {}", &self.name)
+    }
+}
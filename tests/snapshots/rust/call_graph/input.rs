@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+pub struct Inventory {
+    items: HashMap<String, u32>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self {
+            items: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, name: &str, count: u32) {
+        let name = normalize(name);
+        *self.items.entry(name).or_default() += count;
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<u32> {
+        self.items.remove(&normalize(name))
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+fn restock(inventory: &mut Inventory) {
+    inventory.add("Widget", 10);
+    inventory.add("gadget ", 5);
+}
+
+fn main() {
+    let mut inventory = Inventory::new();
+    restock(&mut inventory);
+    inventory.remove("widget");
+}
@@ -0,0 +1,16 @@
+struct Counter {
+    count: u32,
+}
+
+impl Counter {
+    fn increment(&mut self) {
+        self.count += 1;
+    }
+
+    fn reset(&mut self) {
+        self.count = 0;
+    }
+    fn decrement(&mut self) {
+        self.count -= 1;
+    }
+}
@@ -0,0 +1,3 @@
+fn greet(name: &str) -> String {
+    format!("Hi, {name}")
+}
@@ -0,0 +1,4 @@
+fn save_user(user: &User) -> Result<()> {
+    log::info!("calling store(user)");
+    store(user).context("saving the user")
+}
@@ -0,0 +1,21 @@
+pub struct User {
+    pub first: String,
+    pub last: String,
+}
+
+impl User {
+    pub fn display_name(&self) -> String {
+        format!("{} {}", self.first, self.last)
+    }
+}
+
+pub struct Team {
+    pub name: String,
+    pub members: Vec<User>,
+}
+
+impl Team {
+    pub fn display_name(&self) -> String {
+        self.name.clone()
+    }
+}
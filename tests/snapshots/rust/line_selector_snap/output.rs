@@ -0,0 +1,20 @@
+pub struct User {
+    pub first: String,
+    pub last: String,
+}
+
+impl User {
+    pub fn display_name(&self) -> String {
+        format!("{} {}", self.first, self.last)
+    }
+}
+
+pub struct Team {
+    pub name: String,
+}
+
+impl Team {
+    pub fn display_name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,3 @@
+fn parse_number(text: &str) -> Option<u32> {
+    text.trim().parse().ok()
+}
@@ -0,0 +1,3 @@
+fn total(values: &[u32]) -> u32 {
+    values.iter().sum()
+}
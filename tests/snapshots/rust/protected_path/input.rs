@@ -0,0 +1,3 @@
+fn version() -> u32 {
+    1
+}
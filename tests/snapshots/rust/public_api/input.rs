@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+/// Settings loaded from disk
+pub struct Config {
+    pub name: String,
+    path: PathBuf,
+}
+
+impl Config {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            path: PathBuf::new(),
+        }
+    }
+
+    fn path(&self) -> &PathBuf {
+        &self.path
+    }
+}
+
+pub(crate) fn internal() {}
@@ -0,0 +1,13 @@
+pub fn parse_number(input: &str) -> Option<u32> {
+    input.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_digits() {
+        assert_eq!(parse_number("42"), Some(42));
+    }
+}
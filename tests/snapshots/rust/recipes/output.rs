@@ -0,0 +1,19 @@
+pub fn parse_number(input: &str) -> Option<u32> {
+    input.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_digits() {
+        assert_eq!(parse_number("42"), Some(42));
+    }
+
+    #[test]
+    fn ignores_whitespace() {
+        assert_eq!(parse_number(" 7 "), Some(7));
+        assert_eq!(parse_number("\t"), None);
+    }
+}
@@ -0,0 +1,13 @@
+pub fn parse(input: &str) -> Option<u32> {
+    input.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_integers() {
+        assert_eq!(parse(" 42 "), Some(42));
+    }
+}
@@ -0,0 +1,17 @@
+pub fn parse(input: &str) -> Option<u32> {
+    input.trim().parse().ok()
+}
+/// Like [`parse`], with zero in place of `None`
+pub fn parse_or_zero(input: &str) -> u32 {
+    parse(input).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(parse(" 42 "), Some(42));
+    }
+}
@@ -0,0 +1,9 @@
+fn describe(n: u8) -> &'static str {
+    match n {
+        0 => {
+            log_zero();
+            "zero"
+        }
+        _ => "some",
+    }
+}
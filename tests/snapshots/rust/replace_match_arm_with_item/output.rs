@@ -0,0 +1,10 @@
+fn describe(n: u8) -> &'static str {
+    match n {
+        0 => {
+            log_zero();
+            fn log_zero() {}
+            "zero"
+        }
+        _ => "some",
+    }
+}
@@ -0,0 +1,17 @@
+pub struct User {
+    pub name: String,
+}
+
+impl User {
+    pub fn display_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+pub fn greet(user: &User) {
+    println!("Hello, {}", user.display_name());
+}
+
+pub fn farewell(user: &User) {
+    println!("Goodbye, {}", user.display_name());
+}
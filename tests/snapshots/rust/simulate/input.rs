@@ -0,0 +1,8 @@
+pub fn parse_line(line: &str) -> usize {
+    line.len()
+}
+
+pub fn main() {
+    let total = parse_line("a") + parse_line("bc");
+    println!("{total}");
+}
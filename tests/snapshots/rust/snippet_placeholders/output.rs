@@ -0,0 +1,9 @@
+/// Settings for input
+#[derive(Debug, Default)]
+pub struct Options {
+    pub retries: u32,
+}
+
+pub struct Config {
+    pub verbose: bool,
+}
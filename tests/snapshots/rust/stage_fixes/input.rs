@@ -0,0 +1,16 @@
+use std::collections::HashMap;
+use std::fmt;
+
+fn total(values: &[u32]) -> u32 {
+    let mut sum = 0;
+    for value in values {
+        sum = sum + value;
+    }
+    return sum;
+}
+
+fn main() {
+    let values = vec![1, 2, 3];
+    println!("{}", total(&values));
+    let _ = fmt::Error;
+}
@@ -0,0 +1,10 @@
+pub fn subtract(a: i32, b: i32) -> i32 {
+    a - b
+}
+pub fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+pub fn main() {
+    println!("{}", add(1, 2));
+}
@@ -0,0 +1,27 @@
+pub struct User {
+    pub first: String,
+    pub last: String,
+}
+
+impl User {
+    pub fn display_name(&self) -> String {
+        format!("{} {}", self.first, self.last)
+    }
+}
+
+pub struct Team {
+    pub name: String,
+}
+impl Default for Team {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+        }
+    }
+}
+
+impl Team {
+    pub fn display_name(&self) -> String {
+        self.name.clone()
+    }
+}
@@ -0,0 +1,7 @@
+fn total(values: &[u32]) -> u32 {
+    values.iter().sum()
+}
+
+fn describe(self) -> String {
+    String::new()
+}
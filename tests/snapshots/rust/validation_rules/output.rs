@@ -0,0 +1,10 @@
+fn total(values: &[u32]) -> u32 {
+    fn double(value: &u32) -> u32 {
+        value * 2
+    }
+    values.iter().sum()
+}
+
+fn describe(self) -> String {
+    String::new()
+}
@@ -0,0 +1,14 @@
+def summarize_sales(rows):
+    totals = {}
+    for row in rows:
+        if row["region"] and row["amount"] > 0:
+            totals[row["region"]] = totals.get(row["region"], 0) + row["amount"]
+    return sorted(totals.items(), key=lambda item: item[1], reverse=True)
+
+
+def summarize_returns(records):
+    totals = {}
+    for record in records:
+        if record["store"] and record["refund"] > 0:
+            totals[record["store"]] = totals.get(record["store"], 0) + record["refund"]
+    return sorted(totals.items(), key=lambda item: item[0])
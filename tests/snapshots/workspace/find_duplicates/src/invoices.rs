@@ -0,0 +1,11 @@
+// Computes the amount due for an invoice
+pub fn invoice_amount(invoice: &Invoice) -> u32 {
+    let mut amount = 0;
+    for entry in &invoice.entries {
+        if entry.count > 0 && !entry.voided {
+            amount += entry.count * entry.price;
+        }
+    }
+    log::debug!("invoice amount: {amount}");
+    amount
+}
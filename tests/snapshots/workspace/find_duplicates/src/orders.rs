@@ -0,0 +1,14 @@
+pub fn order_total(order: &Order) -> u32 {
+    let mut total = 0;
+    for line in &order.lines {
+        if line.quantity > 0 && !line.cancelled {
+            total += line.quantity * line.unit_price;
+        }
+    }
+    log::debug!("order total: {total}");
+    total
+}
+
+pub fn describe(order: &Order) -> String {
+    format!("{} lines", order.lines.len())
+}
@@ -0,0 +1,15 @@
+pub mod state;
+mod util;
+
+#[path = "generated/bindings.rs"]
+mod bindings;
+
+mod missing;
+
+#[cfg(test)]
+mod tests {
+    mod fixtures;
+
+    #[test]
+    fn it_works() {}
+}
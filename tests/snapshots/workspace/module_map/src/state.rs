@@ -0,0 +1,3 @@
+mod migration;
+
+pub struct StagedOperation;
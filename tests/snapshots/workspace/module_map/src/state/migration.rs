@@ -0,0 +1 @@
+pub const CURRENT_VERSION: u32 = 1;
@@ -0,0 +1 @@
+pub const FIXTURE: &str = "";
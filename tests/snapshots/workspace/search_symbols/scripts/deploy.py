@@ -0,0 +1,7 @@
+class DeployConfig:
+    def parse_args(self, argv):
+        return argv[1:]
+
+
+def parse_env(environ):
+    return dict(environ)
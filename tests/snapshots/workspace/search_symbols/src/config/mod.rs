@@ -0,0 +1,13 @@
+pub struct ServerConfig {
+    pub port: u16,
+}
+
+pub struct LogConfig {
+    pub level: String,
+}
+
+pub fn parse_config(source: &str) -> ServerConfig {
+    ServerConfig {
+        port: source.trim().parse().unwrap_or(8080),
+    }
+}
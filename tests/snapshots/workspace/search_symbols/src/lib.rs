@@ -0,0 +1,19 @@
+pub mod config;
+
+pub struct Server {
+    config: config::ServerConfig,
+}
+
+impl Server {
+    pub fn new(config: config::ServerConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn parse_request(&self, raw: &str) -> Option<&str> {
+        raw.lines().next()
+    }
+}
+
+fn parse_header(line: &str) -> Option<(&str, &str)> {
+    line.split_once(':')
+}
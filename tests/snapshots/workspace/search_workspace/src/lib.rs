@@ -0,0 +1,9 @@
+pub mod nested;
+
+pub fn greet(name: &str) -> String {
+    format!("Hello, {}", display_name(name))
+}
+
+fn display_name(name:&str) -> &str {
+    name.trim()
+}
@@ -0,0 +1,3 @@
+pub fn display_name(name: &str) -> String {
+    super::display_name(name).to_uppercase()
+}
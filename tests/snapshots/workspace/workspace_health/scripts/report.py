@@ -0,0 +1,3 @@
+def report(rows):
+    for row in rows:
+        print(row)
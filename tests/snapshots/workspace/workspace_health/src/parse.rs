@@ -0,0 +1,4 @@
+pub fn parse(input: &str) -> Option<u32> {
+    let value = input.trim().parse().ok()
+    value.map(|v| v * 2
+}